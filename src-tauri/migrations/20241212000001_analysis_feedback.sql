@@ -0,0 +1,12 @@
+-- Athlete feedback on tomorrow-prescriptions
+-- One rating per analyzed workout; fed back into the context package
+
+CREATE TABLE IF NOT EXISTS analysis_feedback (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  workout_id INTEGER NOT NULL UNIQUE REFERENCES workouts(id),
+  rating TEXT NOT NULL CHECK (rating IN ('good', 'too_hard', 'too_easy')),
+  note TEXT,
+  created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_analysis_feedback_created ON analysis_feedback(created_at);
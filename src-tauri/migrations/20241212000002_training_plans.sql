@@ -0,0 +1,20 @@
+-- Coach-generated multi-week plans
+-- Stored day-by-day so they can be compared against actual workouts
+
+CREATE TABLE IF NOT EXISTS training_plans (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  start_date DATE NOT NULL,
+  end_date DATE NOT NULL,
+  weeks INTEGER NOT NULL,
+  plan_json TEXT NOT NULL,          -- serialized TrainingPlan
+  model_version TEXT,
+  input_tokens INTEGER,
+  output_tokens INTEGER,
+  created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_training_plans_start ON training_plans(start_date);
+
+-- Goal event for plan countdowns
+ALTER TABLE user_settings ADD COLUMN goal_name TEXT;
+ALTER TABLE user_settings ADD COLUMN goal_date TEXT;  -- ISO date (YYYY-MM-DD)
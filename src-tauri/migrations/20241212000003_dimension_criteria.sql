@@ -0,0 +1,6 @@
+-- Per-dimension progression criteria
+-- Lets athlete-defined dimensions carry their own gating rules instead of
+-- relying on the name-based defaults baked into the engine.
+-- NULL = use the built-in defaults for the dimension name.
+
+ALTER TABLE progression_dimensions ADD COLUMN criteria_json TEXT;
@@ -0,0 +1,8 @@
+-- Configurable LTHR fallback
+-- When lthr is not set, LTHR is estimated as a percentage of max_hr.
+-- The sport-specific values override the general one (cycling threshold HR
+-- usually sits lower than running). NULL = use lthr_pct_of_max.
+
+ALTER TABLE user_settings ADD COLUMN lthr_pct_of_max REAL NOT NULL DEFAULT 0.93;
+ALTER TABLE user_settings ADD COLUMN run_lthr_pct_of_max REAL;
+ALTER TABLE user_settings ADD COLUMN ride_lthr_pct_of_max REAL;
@@ -0,0 +1,6 @@
+-- Configurable volume flag thresholds
+-- Defaults match the values previously hard-coded in TrainingFlags::compute.
+
+ALTER TABLE user_settings ADD COLUMN volume_spike_ratio REAL NOT NULL DEFAULT 1.2;
+ALTER TABLE user_settings ADD COLUMN volume_drop_ratio REAL NOT NULL DEFAULT 0.7;
+ALTER TABLE user_settings ADD COLUMN volume_drop_min_chronic REAL NOT NULL DEFAULT 50.0;
@@ -0,0 +1,37 @@
+-- Allow 'undo' entries in progression_history and remember the prior
+-- last_change_at so an undo can restore it exactly.
+-- SQLite can't alter a CHECK constraint, so rebuild the table.
+
+CREATE TABLE progression_history_new (
+    id INTEGER PRIMARY KEY,
+
+    -- What changed
+    dimension_name TEXT NOT NULL,
+    previous_value TEXT NOT NULL,
+    new_value TEXT NOT NULL,
+
+    -- Why it changed
+    change_type TEXT NOT NULL CHECK (change_type IN ('progress', 'regress', 'ceiling_touch', 'manual', 'ceiling_update', 'undo')),
+    trigger_workout_id INTEGER REFERENCES workouts(id),
+
+    -- Context at time of change (JSON)
+    context_snapshot_json TEXT,
+
+    -- Dimension's last_change_at before this change (restored on undo)
+    previous_last_change_at DATETIME,
+
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+INSERT INTO progression_history_new
+    (id, dimension_name, previous_value, new_value, change_type,
+     trigger_workout_id, context_snapshot_json, created_at)
+SELECT id, dimension_name, previous_value, new_value, change_type,
+       trigger_workout_id, context_snapshot_json, created_at
+FROM progression_history;
+
+DROP TABLE progression_history;
+ALTER TABLE progression_history_new RENAME TO progression_history;
+
+CREATE INDEX IF NOT EXISTS idx_progression_history_dimension ON progression_history(dimension_name);
+CREATE INDEX IF NOT EXISTS idx_progression_history_date ON progression_history(created_at);
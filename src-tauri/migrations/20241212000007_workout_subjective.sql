@@ -0,0 +1,5 @@
+-- Subjective session data entered by the athlete
+-- rpe: session RPE on the CR-10 scale (1-10), notes: free text ("legs felt flat")
+
+ALTER TABLE workouts ADD COLUMN rpe INTEGER CHECK (rpe IS NULL OR (rpe BETWEEN 1 AND 10));
+ALTER TABLE workouts ADD COLUMN notes TEXT;
@@ -0,0 +1,7 @@
+-- Configurable recent-workout trend context
+-- Defaults match the counts previously hard-coded in analyze_workout.
+-- recent_window_days NULL = count mode.
+
+ALTER TABLE user_settings ADD COLUMN recent_same_type_count INTEGER NOT NULL DEFAULT 5;
+ALTER TABLE user_settings ADD COLUMN recent_all_type_count INTEGER NOT NULL DEFAULT 7;
+ALTER TABLE user_settings ADD COLUMN recent_window_days INTEGER;
@@ -0,0 +1,13 @@
+-- Explicit links between a workout and the dimension whose key session it completed
+-- Replaces the "run > 45 min" guess in adherence once the athlete starts linking.
+-- dimension_name is not a foreign key, matching progression_history.
+
+CREATE TABLE IF NOT EXISTS key_sessions (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  workout_id INTEGER NOT NULL REFERENCES workouts(id),
+  dimension_name TEXT NOT NULL,
+  created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+  UNIQUE (workout_id, dimension_name)
+);
+
+CREATE INDEX IF NOT EXISTS idx_key_sessions_workout ON key_sessions(workout_id);
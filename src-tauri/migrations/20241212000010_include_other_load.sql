@@ -0,0 +1,4 @@
+-- Whether cross-training ("other" activities like hiking, rowing) counts toward ATL/CTL
+-- Defaults on, matching existing behavior; volume always counts.
+
+ALTER TABLE user_settings ADD COLUMN include_other_load INTEGER NOT NULL DEFAULT 1;
@@ -0,0 +1,28 @@
+-- Cached 6-week fitness trend (singleton row)
+-- Triggers mark it stale whenever workouts or their metrics change, so reads
+-- can stay instant and refresh_fitness_trend recomputes on demand.
+
+CREATE TABLE IF NOT EXISTS fitness_trend_cache (
+  id INTEGER PRIMARY KEY CHECK (id = 1),
+  trend_json TEXT NOT NULL,
+  computed_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  stale INTEGER NOT NULL DEFAULT 0
+);
+
+CREATE TRIGGER IF NOT EXISTS fitness_trend_stale_on_insert
+AFTER INSERT ON workouts
+BEGIN
+  UPDATE fitness_trend_cache SET stale = 1;
+END;
+
+CREATE TRIGGER IF NOT EXISTS fitness_trend_stale_on_delete
+AFTER DELETE ON workouts
+BEGIN
+  UPDATE fitness_trend_cache SET stale = 1;
+END;
+
+CREATE TRIGGER IF NOT EXISTS fitness_trend_stale_on_metrics
+AFTER UPDATE OF started_at, duration_seconds, rtss, efficiency, pace_min_per_km, average_watts ON workouts
+BEGIN
+  UPDATE fitness_trend_cache SET stale = 1;
+END;
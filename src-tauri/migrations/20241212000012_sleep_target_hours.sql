@@ -0,0 +1,3 @@
+-- Nightly sleep target used for Oura sleep debt (previously hard-coded at 8h)
+
+ALTER TABLE user_settings ADD COLUMN sleep_target_hours REAL NOT NULL DEFAULT 8.0;
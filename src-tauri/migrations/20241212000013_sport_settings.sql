@@ -0,0 +1,10 @@
+-- Per-sport threshold overrides (e.g. cycling max HR ~10 bpm below running)
+-- NULL columns fall back to the global values in user_settings.
+
+CREATE TABLE IF NOT EXISTS sport_settings (
+  sport TEXT PRIMARY KEY CHECK (sport IN ('run', 'ride', 'swim')),
+  max_hr INTEGER,
+  lthr INTEGER,
+  ftp INTEGER,
+  updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,13 @@
+-- Planned periodization blocks (base / build / peak / recovery)
+-- end_date NULL = open-ended. Without a covering block the phase is derived
+-- from goal proximity.
+
+CREATE TABLE IF NOT EXISTS training_phases (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  phase TEXT NOT NULL CHECK (phase IN ('base', 'build', 'peak', 'recovery')),
+  start_date TEXT NOT NULL,
+  end_date TEXT,
+  created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_training_phases_start ON training_phases(start_date);
@@ -0,0 +1,4 @@
+-- Athlete's fixed offset from UTC in minutes for local day/week bucketing.
+-- NULL follows the system timezone (DST-aware).
+
+ALTER TABLE user_settings ADD COLUMN utc_offset_minutes INTEGER;
@@ -0,0 +1,9 @@
+-- Indoor trainer / virtual sessions (Strava `trainer` or a Virtual* sport type).
+-- Their speed is meaningless and their power isn't comparable with the road.
+
+ALTER TABLE workouts ADD COLUMN is_indoor INTEGER NOT NULL DEFAULT 0;
+
+UPDATE workouts SET is_indoor = 1
+WHERE activity_type IN ('VirtualRide', 'VirtualRun')
+   OR (CASE WHEN json_valid(raw_json) THEN json_extract(raw_json, '$.trainer') END) = 1
+   OR (CASE WHEN json_valid(raw_json) THEN json_extract(raw_json, '$.sport_type') END) IN ('VirtualRide', 'VirtualRun');
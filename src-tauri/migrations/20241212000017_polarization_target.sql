@@ -0,0 +1,6 @@
+-- Target intensity split (low = Z1-Z2, moderate = Z3, high = Z4-Z5), in percent.
+-- Defaults to the usual 80/15/5 polarized split.
+
+ALTER TABLE user_settings ADD COLUMN polarization_low_pct REAL NOT NULL DEFAULT 80;
+ALTER TABLE user_settings ADD COLUMN polarization_moderate_pct REAL NOT NULL DEFAULT 15;
+ALTER TABLE user_settings ADD COLUMN polarization_high_pct REAL NOT NULL DEFAULT 5;
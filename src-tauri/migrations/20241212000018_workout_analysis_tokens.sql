@@ -0,0 +1,36 @@
+-- workout_analysis was created by the initial schema (UNIQUE(workout_id, prompt_hash),
+-- no token columns), so the later CREATE TABLE IF NOT EXISTS never applied and
+-- storing an analysis (ON CONFLICT(workout_id)) failed. Rebuild it in the intended
+-- shape, keeping the newest analysis per workout.
+
+CREATE TABLE workout_analysis_new (
+    id INTEGER PRIMARY KEY,
+    workout_id INTEGER NOT NULL REFERENCES workouts(id) ON DELETE CASCADE,
+    summary TEXT NOT NULL,
+    tomorrow_recommendation TEXT NOT NULL,
+    risk_flags_json TEXT,
+    goal_notes TEXT,
+    model_version TEXT NOT NULL DEFAULT 'unknown',
+    prompt_hash TEXT,
+    input_tokens INTEGER,
+    output_tokens INTEGER,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE(workout_id)
+);
+
+INSERT INTO workout_analysis_new (
+    id, workout_id, summary, tomorrow_recommendation, risk_flags_json,
+    goal_notes, model_version, prompt_hash, created_at
+)
+SELECT
+    id, workout_id, COALESCE(summary, ''), COALESCE(tomorrow_recommendation, ''), risk_flags_json,
+    kilimanjaro_notes, COALESCE(model_version, 'unknown'), prompt_hash, created_at
+FROM workout_analysis
+WHERE workout_id IN (SELECT id FROM workouts)
+  AND id IN (SELECT MAX(id) FROM workout_analysis GROUP BY workout_id);
+
+DROP TABLE workout_analysis;
+ALTER TABLE workout_analysis_new RENAME TO workout_analysis;
+
+CREATE INDEX IF NOT EXISTS idx_workout_analysis_workout ON workout_analysis(workout_id);
+CREATE INDEX IF NOT EXISTS idx_workout_analysis_created ON workout_analysis(created_at);
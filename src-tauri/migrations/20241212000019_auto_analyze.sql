@@ -0,0 +1,5 @@
+-- Background analysis of newly synced workouts.
+-- Off by default; the daily cap counts input + output tokens across all analyses.
+
+ALTER TABLE user_settings ADD COLUMN auto_analyze_enabled INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE user_settings ADD COLUMN auto_analyze_daily_token_cap INTEGER NOT NULL DEFAULT 50000;
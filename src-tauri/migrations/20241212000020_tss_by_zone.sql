@@ -0,0 +1,2 @@
+-- rTSS split by HR zone (JSON ZoneTss), computed from the HR stream
+ALTER TABLE workouts ADD COLUMN tss_by_zone_json TEXT;
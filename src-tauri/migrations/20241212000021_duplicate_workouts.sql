@@ -0,0 +1,26 @@
+-- The same session recorded on two devices (watch + head unit) syncs twice.
+-- The later copy points at the one kept and is left out of load and trends.
+
+ALTER TABLE workouts ADD COLUMN duplicate_of INTEGER REFERENCES workouts(id) ON DELETE SET NULL;
+
+-- Mark duplicates already stored: overlapping time ranges with duration and
+-- distance within 10%, keeping the first one synced
+UPDATE workouts
+SET duplicate_of = (
+  SELECT o.id
+  FROM workouts o
+  WHERE o.id < workouts.id
+    AND o.duration_seconds > 0
+    AND workouts.duration_seconds > 0
+    AND julianday(o.started_at) < julianday(workouts.started_at) + workouts.duration_seconds / 86400.0
+    AND julianday(workouts.started_at) < julianday(o.started_at) + o.duration_seconds / 86400.0
+    AND ABS(o.duration_seconds - workouts.duration_seconds) <= 0.1 * MAX(o.duration_seconds, workouts.duration_seconds)
+    AND (
+      (COALESCE(o.distance_meters, 0) = 0 AND COALESCE(workouts.distance_meters, 0) = 0)
+      OR ABS(o.distance_meters - workouts.distance_meters) <= 0.1 * MAX(o.distance_meters, workouts.distance_meters)
+    )
+  ORDER BY o.id
+  LIMIT 1
+);
+
+CREATE INDEX IF NOT EXISTS idx_workouts_duplicate_of ON workouts(duplicate_of);
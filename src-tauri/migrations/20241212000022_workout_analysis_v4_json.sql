@@ -0,0 +1,3 @@
+-- Full V4 analysis (all cards) as JSON, alongside the legacy text columns.
+-- NULL for analyses stored before this column existed.
+ALTER TABLE workout_analysis ADD COLUMN analysis_json TEXT;
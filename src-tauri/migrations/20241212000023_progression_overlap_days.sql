@@ -0,0 +1,2 @@
+-- Overlap rule window: days after one dimension progresses before another may
+ALTER TABLE user_settings ADD COLUMN progression_overlap_days INTEGER NOT NULL DEFAULT 7;
@@ -0,0 +1,15 @@
+-- Coach memory: deterministic observations recorded after each analysis
+-- (elevated HR for the activity type, active flags). Recurring ones are fed
+-- back into later context packages.
+
+CREATE TABLE IF NOT EXISTS observations (
+    id INTEGER PRIMARY KEY,
+    workout_id INTEGER NOT NULL REFERENCES workouts(id) ON DELETE CASCADE,
+    kind TEXT NOT NULL,       -- 'elevated_hr' | 'flag'
+    subject TEXT NOT NULL,    -- activity kind or flag name
+    detail TEXT,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE(workout_id, kind, subject)
+);
+
+CREATE INDEX IF NOT EXISTS idx_observations_workout ON observations(workout_id);
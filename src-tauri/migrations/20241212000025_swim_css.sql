@@ -0,0 +1,10 @@
+-- Critical swim speed (pace per 100m) for CSS-based swim zones
+ALTER TABLE user_settings ADD COLUMN css_pace_sec_per_100m REAL;
+
+-- Swim pace and zone, computed for swims alongside the other metrics
+ALTER TABLE workouts ADD COLUMN swim_pace_sec_per_100m REAL;
+ALTER TABLE workouts ADD COLUMN swim_zone TEXT;
+
+-- Recompute existing swims so they pick up a pace
+UPDATE workouts SET metrics_computed_at = NULL
+WHERE activity_type IN ('Swim', 'Swimming', 'OpenWaterSwim', 'PoolSwim');
@@ -0,0 +1,2 @@
+-- HR stream dropped out for much of the session: no zone, HR metrics shaky
+ALTER TABLE workouts ADD COLUMN hr_low_confidence INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,5 @@
+-- Per-sport multipliers on rTSS when summed into ATL/CTL
+ALTER TABLE user_settings ADD COLUMN run_load_weight REAL NOT NULL DEFAULT 1.0;
+ALTER TABLE user_settings ADD COLUMN ride_load_weight REAL NOT NULL DEFAULT 1.0;
+ALTER TABLE user_settings ADD COLUMN swim_load_weight REAL NOT NULL DEFAULT 1.0;
+ALTER TABLE user_settings ADD COLUMN other_load_weight REAL NOT NULL DEFAULT 1.0;
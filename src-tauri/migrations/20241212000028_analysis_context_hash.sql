@@ -0,0 +1,3 @@
+-- Fingerprint of the workout/settings inputs an analysis was built from,
+-- compared against a fresh build to flag stale analyses
+ALTER TABLE workout_analysis ADD COLUMN context_hash TEXT;
@@ -0,0 +1,14 @@
+-- Strava gear (shoes/bikes) each activity was logged with
+ALTER TABLE workouts ADD COLUMN gear_id TEXT;
+
+-- Gear names looked up from Strava, keyed by gear id
+CREATE TABLE IF NOT EXISTS gear (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    -- "shoe" or "bike"
+    kind TEXT NOT NULL,
+    fetched_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Distance after which shoes are flagged for replacement
+ALTER TABLE user_settings ADD COLUMN shoe_replacement_km REAL NOT NULL DEFAULT 800.0;
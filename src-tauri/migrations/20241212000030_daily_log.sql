@@ -0,0 +1,8 @@
+-- Daily journal: life stress, soreness and a free-text note per local date
+CREATE TABLE IF NOT EXISTS daily_log (
+    date TEXT PRIMARY KEY,             -- YYYY-MM-DD (athlete's local date)
+    stress_1_10 INTEGER,
+    soreness_1_10 INTEGER,
+    note TEXT,
+    updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
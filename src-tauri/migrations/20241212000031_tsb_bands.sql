@@ -0,0 +1,4 @@
+-- TSB edges between the fresh/fatigued bands (Coggan defaults)
+ALTER TABLE user_settings ADD COLUMN tsb_fresh_above REAL NOT NULL DEFAULT 5.0;
+ALTER TABLE user_settings ADD COLUMN tsb_moderate_below REAL NOT NULL DEFAULT -10.0;
+ALTER TABLE user_settings ADD COLUMN tsb_high_below REAL NOT NULL DEFAULT -20.0;
@@ -0,0 +1,13 @@
+-- Tomorrow-prescriptions from each analysis, keyed by the date they target,
+-- so the next day's analysis can check whether the advice was followed
+CREATE TABLE IF NOT EXISTS prescriptions (
+    id INTEGER PRIMARY KEY,
+    workout_id INTEGER NOT NULL UNIQUE REFERENCES workouts(id) ON DELETE CASCADE,
+    target_date TEXT NOT NULL,          -- YYYY-MM-DD (athlete's local date)
+    activity_type TEXT NOT NULL,
+    duration_min INTEGER NOT NULL,
+    intensity TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_prescriptions_target_date ON prescriptions(target_date);
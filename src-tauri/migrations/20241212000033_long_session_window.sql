@@ -0,0 +1,2 @@
+-- Lookback (days) shared by longest_session and the long run/ride gap flags
+ALTER TABLE user_settings ADD COLUMN long_session_window_days INTEGER NOT NULL DEFAULT 21;
@@ -0,0 +1,2 @@
+-- Last date whose Oura data is fully stored (later syncs resume after it)
+ALTER TABLE sync_state ADD COLUMN last_synced_date TEXT;
@@ -0,0 +1,3 @@
+-- HR zone model ('pct_max' or 'hr_reserve') and resting HR for reserve zones
+ALTER TABLE user_settings ADD COLUMN zone_model TEXT NOT NULL DEFAULT 'pct_max';
+ALTER TABLE user_settings ADD COLUMN resting_hr INTEGER;
@@ -0,0 +1,11 @@
+-- Week-level reviews, one per reviewed week (regenerating replaces it)
+CREATE TABLE IF NOT EXISTS weekly_reviews (
+    id INTEGER PRIMARY KEY,
+    week_start TEXT NOT NULL UNIQUE,    -- YYYY-MM-DD (athlete's local date)
+    week_end TEXT NOT NULL,
+    review_json TEXT NOT NULL,          -- serialized WeeklyReview
+    model_version TEXT,
+    input_tokens INTEGER,
+    output_tokens INTEGER,
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,7 @@
+-- Whether average_watts came from a power meter: 1 = meter, 0 = Strava's
+-- estimate, NULL = unknown (older rows, sources that don't say)
+ALTER TABLE workouts ADD COLUMN device_watts INTEGER;
+
+-- A zero average without a known meter is "no power", not a zero-power
+-- ride: recompute those metrics so the power path (kJ, efficiency) drops
+UPDATE workouts SET metrics_computed_at = NULL WHERE average_watts = 0;
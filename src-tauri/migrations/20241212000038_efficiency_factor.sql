@@ -0,0 +1,7 @@
+-- Efficiency Factor (speed or power per beat, higher = fitter), alongside
+-- the existing pace/HR efficiency
+ALTER TABLE workouts ADD COLUMN efficiency_factor REAL;
+
+-- Recompute workouts with HR so they pick up an EF
+UPDATE workouts SET metrics_computed_at = NULL
+WHERE average_heartrate IS NOT NULL;
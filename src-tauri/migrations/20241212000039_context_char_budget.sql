@@ -0,0 +1,3 @@
+-- Ceiling (characters of JSON) on the context package sent for analysis;
+-- optional blocks are trimmed to fit
+ALTER TABLE user_settings ADD COLUMN context_char_budget INTEGER NOT NULL DEFAULT 60000;
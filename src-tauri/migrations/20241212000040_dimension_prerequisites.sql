@@ -0,0 +1,7 @@
+-- Cross-dimension prerequisites
+-- A dimension with a prerequisite holds until the named dimension has reached
+-- prerequisite_min (NULL = that dimension's ceiling), e.g. long_run waits on
+-- run_interval reaching continuous_20.
+
+ALTER TABLE progression_dimensions ADD COLUMN prerequisite TEXT;
+ALTER TABLE progression_dimensions ADD COLUMN prerequisite_min TEXT;
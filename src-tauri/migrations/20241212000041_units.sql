@@ -0,0 +1,3 @@
+-- Units for reported distance, pace and speed ('metric' or 'imperial');
+-- stored values stay metric
+ALTER TABLE user_settings ADD COLUMN units TEXT NOT NULL DEFAULT 'metric';
@@ -0,0 +1,3 @@
+-- A metric fell outside the plausibility bounds (GPS glitch, HR spike) and
+-- was nulled instead of stored
+ALTER TABLE workouts ADD COLUMN data_suspect INTEGER NOT NULL DEFAULT 0;
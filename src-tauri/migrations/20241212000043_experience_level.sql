@@ -0,0 +1,3 @@
+-- Athlete experience ('beginner', 'intermediate', 'advanced'); scales how
+-- cautiously progression criteria are applied
+ALTER TABLE user_settings ADD COLUMN experience_level TEXT NOT NULL DEFAULT 'intermediate';
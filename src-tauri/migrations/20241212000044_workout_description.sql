@@ -0,0 +1,4 @@
+-- Strava activity title and the athlete's description ("felt awful,
+-- headwind, didn't sleep"), passed to the coach as qualitative context
+ALTER TABLE workouts ADD COLUMN name TEXT;
+ALTER TABLE workouts ADD COLUMN description TEXT;
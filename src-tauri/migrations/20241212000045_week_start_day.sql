@@ -0,0 +1,3 @@
+-- First day of the training week ('rolling' or 'monday'..'sunday'); anchors
+-- this/last week volume to calendar weeks instead of rolling 7-day windows
+ALTER TABLE user_settings ADD COLUMN week_start_day TEXT NOT NULL DEFAULT 'rolling';
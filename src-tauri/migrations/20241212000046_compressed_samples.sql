@@ -0,0 +1,4 @@
+-- Workout samples stored gzip-compressed. samples_json is kept for rows
+-- written before this (compact_workout_samples moves them over).
+ALTER TABLE workouts ADD COLUMN samples_blob BLOB;
+ALTER TABLE workouts ADD COLUMN samples_compressed BOOLEAN NOT NULL DEFAULT FALSE;
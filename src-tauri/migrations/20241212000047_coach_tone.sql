@@ -0,0 +1,3 @@
+-- Voice of the coach's feedback ('conversational', 'direct', 'supportive' or
+-- 'analytical'), templated into the analysis system prompt
+ALTER TABLE user_settings ADD COLUMN coach_tone TEXT NOT NULL DEFAULT 'conversational';
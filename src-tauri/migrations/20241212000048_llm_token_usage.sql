@@ -0,0 +1,29 @@
+-- Append-only ledger of LLM token spend, one row per call. Analyses and
+-- reviews are upserted (regenerating overwrites their token counts), so the
+-- daily auto-analyze cap sums this instead.
+CREATE TABLE IF NOT EXISTS llm_token_usage (
+    id INTEGER PRIMARY KEY,
+    source TEXT NOT NULL,           -- 'analysis' | 'weekly_review' | 'plan'
+    model_version TEXT,
+    input_tokens INTEGER NOT NULL,
+    output_tokens INTEGER NOT NULL,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_llm_token_usage_created ON llm_token_usage(created_at);
+
+-- Seed with the spend already on record
+INSERT INTO llm_token_usage (source, model_version, input_tokens, output_tokens, created_at)
+SELECT 'analysis', model_version, COALESCE(input_tokens, 0), COALESCE(output_tokens, 0), COALESCE(created_at, CURRENT_TIMESTAMP)
+FROM workout_analysis
+WHERE input_tokens IS NOT NULL OR output_tokens IS NOT NULL;
+
+INSERT INTO llm_token_usage (source, model_version, input_tokens, output_tokens, created_at)
+SELECT 'weekly_review', model_version, COALESCE(input_tokens, 0), COALESCE(output_tokens, 0), COALESCE(created_at, CURRENT_TIMESTAMP)
+FROM weekly_reviews
+WHERE input_tokens IS NOT NULL OR output_tokens IS NOT NULL;
+
+INSERT INTO llm_token_usage (source, model_version, input_tokens, output_tokens, created_at)
+SELECT 'plan', model_version, COALESCE(input_tokens, 0), COALESCE(output_tokens, 0), COALESCE(created_at, CURRENT_TIMESTAMP)
+FROM training_plans
+WHERE input_tokens IS NOT NULL OR output_tokens IS NOT NULL;
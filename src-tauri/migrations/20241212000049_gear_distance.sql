@@ -0,0 +1,3 @@
+-- Strava's own running total for each piece of gear, in meters. Workouts
+-- synced before gear_id existed never carry it, so this is the mileage floor.
+ALTER TABLE gear ADD COLUMN distance_meters REAL;
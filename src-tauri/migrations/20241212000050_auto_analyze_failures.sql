@@ -0,0 +1,8 @@
+-- Failed auto-analysis attempts per workout. The scheduler skips a workout
+-- once it has failed too often, so it can't hold up the ones after it.
+CREATE TABLE IF NOT EXISTS auto_analyze_failures (
+    workout_id INTEGER PRIMARY KEY REFERENCES workouts(id) ON DELETE CASCADE,
+    attempts INTEGER NOT NULL DEFAULT 1,
+    last_error TEXT,
+    last_attempt_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
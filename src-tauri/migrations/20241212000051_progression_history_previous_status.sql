@@ -0,0 +1,3 @@
+-- Dimension's lifecycle status before each change, so an undo restores it
+-- (a regressing dimension stays regressing). NULL on older rows.
+ALTER TABLE progression_history ADD COLUMN previous_status TEXT;
@@ -0,0 +1,3 @@
+-- Athlete's IANA timezone name (e.g. 'America/Denver'), so local dates
+-- follow DST. Takes precedence over the fixed utc_offset_minutes.
+ALTER TABLE user_settings ADD COLUMN timezone TEXT;
@@ -0,0 +1,13 @@
+-- The fitness trend weights load per sport and may leave out "other" load,
+-- so changing those settings also marks the cached trend stale.
+
+CREATE TRIGGER IF NOT EXISTS fitness_trend_stale_on_load_settings
+AFTER UPDATE OF include_other_load, run_load_weight, ride_load_weight, swim_load_weight, other_load_weight ON user_settings
+WHEN OLD.include_other_load IS NOT NEW.include_other_load
+  OR OLD.run_load_weight IS NOT NEW.run_load_weight
+  OR OLD.ride_load_weight IS NOT NEW.ride_load_weight
+  OR OLD.swim_load_weight IS NOT NEW.swim_load_weight
+  OR OLD.other_load_weight IS NOT NEW.other_load_weight
+BEGIN
+  UPDATE fitness_trend_cache SET stale = 1;
+END;
@@ -0,0 +1,10 @@
+-- The fitness trend buckets workouts by the athlete's local date, so a new
+-- timezone or offset also marks the cached trend stale.
+
+CREATE TRIGGER IF NOT EXISTS fitness_trend_stale_on_timezone
+AFTER UPDATE OF timezone, utc_offset_minutes ON user_settings
+WHEN OLD.timezone IS NOT NEW.timezone
+  OR OLD.utc_offset_minutes IS NOT NEW.utc_offset_minutes
+BEGIN
+  UPDATE fitness_trend_cache SET stale = 1;
+END;
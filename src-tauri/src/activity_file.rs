@@ -0,0 +1,343 @@
+//! Activity file import (GPX)
+//!
+//! Turns a GPX track into the same shapes a Strava sync produces: a
+//! `StravaActivity` summary plus raw `StravaStream`s, so storage,
+//! downsampling and metrics run unchanged.
+
+use crate::strava::{StravaActivity, StravaStream};
+use chrono::{DateTime, Utc};
+
+/// Mean Earth radius for haversine distances
+const EARTH_RADIUS_M: f64 = 6_371_000.0;
+
+/// An activity parsed from a file, ready to store
+#[derive(Debug, Clone)]
+pub struct ParsedActivity {
+  /// Summary in Strava's shape (`id` is unused; files are stored under
+  /// `source_id`)
+  pub activity: StravaActivity,
+  /// time / heartrate / watts / velocity_smooth, one value per trackpoint
+  pub streams: Vec<StravaStream>,
+  /// Stable id for the workouts table: "file_" + start time, so importing
+  /// the same file twice is a no-op
+  pub source_id: String,
+}
+
+/// One GPX trackpoint
+#[derive(Debug, Clone, PartialEq)]
+struct TrackPoint {
+  time: DateTime<Utc>,
+  lat: Option<f64>,
+  lon: Option<f64>,
+  ele: Option<f64>,
+  hr: Option<i64>,
+  watts: Option<i64>,
+}
+
+/// Parse a file by extension. FIT is a binary format not handled yet.
+pub fn parse_activity_file(path: &std::path::Path, contents: &[u8]) -> Result<ParsedActivity, String> {
+  let extension = path
+    .extension()
+    .and_then(|e| e.to_str())
+    .map(|e| e.to_ascii_lowercase());
+  match extension.as_deref() {
+    Some("gpx") => {
+      let xml = std::str::from_utf8(contents).map_err(|e| format!("GPX file is not valid UTF-8: {}", e))?;
+      parse_gpx(xml)
+    }
+    Some("fit") => Err("FIT files aren't supported yet: export the activity as GPX".to_string()),
+    _ => Err(format!("Unsupported activity file: {} (expected .gpx)", path.display())),
+  }
+}
+
+/// Parse a GPX track. Trackpoints need a <time>; heart rate and power come
+/// from Garmin-style extensions (<gpxtpx:hr>, <power>), speed from the
+/// distance between consecutive points.
+pub fn parse_gpx(xml: &str) -> Result<ParsedActivity, String> {
+  let points = parse_trackpoints(xml);
+  if points.len() < 2 {
+    return Err("GPX file has fewer than two timed trackpoints".to_string());
+  }
+
+  // Track name and type sit in <trk> before the first segment
+  let header = xml
+    .find("<trk")
+    .map(|start| &xml[start..xml[start..].find("<trkseg").map_or(xml.len(), |end| start + end)])
+    .unwrap_or("");
+  let header_fields = leaf_elements(header);
+  let field = |name: &str| {
+    header_fields
+      .iter()
+      .find(|(tag, _)| *tag == name)
+      .map(|(_, text)| unescape(text))
+  };
+  let gpx_type = field("type");
+
+  let start = points[0].time;
+  let times: Vec<i64> = points.iter().map(|p| (p.time - start).num_seconds()).collect();
+  let elapsed_time = *times.last().unwrap_or(&0);
+  if elapsed_time <= 0 {
+    return Err("GPX trackpoints don't span any time".to_string());
+  }
+
+  // Distance and speed from consecutive positions
+  let mut distance = 0.0;
+  let mut elevation_gain = 0.0;
+  let mut velocity = vec![0.0; points.len()];
+  for i in 1..points.len() {
+    let (prev, point) = (&points[i - 1], &points[i]);
+    let step = match (prev.lat, prev.lon, point.lat, point.lon) {
+      (Some(lat1), Some(lon1), Some(lat2), Some(lon2)) => haversine_m(lat1, lon1, lat2, lon2),
+      _ => 0.0,
+    };
+    distance += step;
+    let dt = (times[i] - times[i - 1]) as f64;
+    velocity[i] = if dt > 0.0 { step / dt } else { velocity[i - 1] };
+    if let (Some(e1), Some(e2)) = (prev.ele, point.ele) {
+      if e2 > e1 {
+        elevation_gain += e2 - e1;
+      }
+    }
+  }
+  velocity[0] = velocity[1];
+  let has_position = points.iter().any(|p| p.lat.is_some());
+
+  let hr: Vec<i64> = points.iter().filter_map(|p| p.hr).collect();
+  let watts: Vec<i64> = points.iter().filter_map(|p| p.watts).collect();
+  let mean = |values: &[i64]| (!values.is_empty()).then(|| values.iter().sum::<i64>() as f64 / values.len() as f64);
+
+  let stream = |stream_type: &str, data: Vec<serde_json::Value>| StravaStream {
+    stream_type: stream_type.to_string(),
+    data,
+    series_type: Some("time".to_string()),
+    original_size: Some(points.len() as i64),
+    resolution: None,
+  };
+  let mut streams = vec![stream("time", times.iter().map(|t| serde_json::json!(t)).collect())];
+  // Streams must line up with time. HR gaps become 0, which stream rTSS
+  // skips and HR coverage counts as a strap dropout.
+  if !hr.is_empty() {
+    streams.push(stream("heartrate", points.iter().map(|p| serde_json::json!(p.hr.unwrap_or(0))).collect()));
+  }
+  // Power only counts when every point has it: a gap isn't a 0 W coast
+  if watts.len() == points.len() {
+    streams.push(stream("watts", watts.iter().map(|v| serde_json::json!(v)).collect()));
+  }
+  if has_position {
+    streams.push(stream("velocity_smooth", velocity.iter().map(|v| serde_json::json!(v)).collect()));
+  }
+
+  let activity = StravaActivity {
+    id: 0,
+    name: field("name").unwrap_or_else(|| "Imported activity".to_string()),
+    activity_type: activity_type_from_gpx(gpx_type.as_deref()).to_string(),
+    start_date: start,
+    elapsed_time,
+    moving_time: 0,
+    distance: has_position.then_some(distance),
+    total_elevation_gain: points.iter().any(|p| p.ele.is_some()).then_some(elevation_gain),
+    average_heartrate: mean(&hr),
+    max_heartrate: hr.iter().max().map(|&v| v as f64),
+    average_watts: mean(&watts),
+    device_watts: (!watts.is_empty()).then_some(true),
+    suffer_score: None,
+    sport_type: None,
+    trainer: !has_position,
+    gear_id: None,
+    description: field("desc"),
+  };
+
+  Ok(ParsedActivity {
+    activity,
+    streams,
+    source_id: format!("file_{}", start.timestamp()),
+  })
+}
+
+/// GPX <type> values vary by device ("running", "cycling", "1", "Run");
+/// map the common ones onto Strava activity types
+fn activity_type_from_gpx(gpx_type: Option<&str>) -> &'static str {
+  let Some(kind) = gpx_type.map(|t| t.to_ascii_lowercase()) else {
+    return "Workout";
+  };
+  if kind.contains("run") {
+    "Run"
+  } else if kind.contains("cycl") || kind.contains("bik") || kind.contains("ride") {
+    "Ride"
+  } else if kind.contains("swim") {
+    "Swim"
+  } else if kind.contains("hik") {
+    "Hike"
+  } else if kind.contains("walk") {
+    "Walk"
+  } else {
+    "Workout"
+  }
+}
+
+/// Every <trkpt> with a parseable <time>, in file order
+fn parse_trackpoints(xml: &str) -> Vec<TrackPoint> {
+  let mut points = Vec::new();
+  let mut rest = xml;
+  while let Some(start) = rest.find("<trkpt") {
+    rest = &rest[start..];
+    let Some(tag_end) = rest.find('>') else { break };
+    let open_tag = &rest[..tag_end];
+    let (body, next) = if open_tag.ends_with('/') {
+      ("", &rest[tag_end + 1..])
+    } else {
+      match rest.find("</trkpt>") {
+        Some(end) => (&rest[tag_end + 1..end], &rest[end + "</trkpt>".len()..]),
+        None => break,
+      }
+    };
+    rest = next;
+
+    let fields = leaf_elements(body);
+    let number = |names: &[&str]| {
+      fields
+        .iter()
+        .find(|(tag, _)| names.iter().any(|n| tag.eq_ignore_ascii_case(n)))
+        .and_then(|(_, text)| text.parse::<f64>().ok())
+        .filter(|v| v.is_finite())
+    };
+    let Some(time) = fields
+      .iter()
+      .find(|(tag, _)| *tag == "time")
+      .and_then(|(_, text)| DateTime::parse_from_rfc3339(text).ok())
+    else {
+      continue;
+    };
+
+    points.push(TrackPoint {
+      time: time.with_timezone(&Utc),
+      lat: attribute(open_tag, "lat").and_then(|v| v.parse().ok()),
+      lon: attribute(open_tag, "lon").and_then(|v| v.parse().ok()),
+      ele: number(&["ele"]),
+      hr: number(&["hr", "heartrate"]).map(|v| v.round() as i64),
+      watts: number(&["power", "powerinwatts", "watts"]).map(|v| v.round() as i64),
+    });
+  }
+  points
+}
+
+/// (local tag name, text) for each element holding only text, e.g.
+/// `<gpxtpx:hr>150</gpxtpx:hr>` gives ("hr", "150")
+fn leaf_elements(xml: &str) -> Vec<(&str, &str)> {
+  let mut elements = Vec::new();
+  let mut rest = xml;
+  while let Some(start) = rest.find('<') {
+    rest = &rest[start + 1..];
+    let Some(tag_end) = rest.find('>') else { break };
+    let tag = &rest[..tag_end];
+    rest = &rest[tag_end + 1..];
+    if tag.starts_with(['/', '?', '!']) || tag.ends_with('/') {
+      continue;
+    }
+    let name = tag.split_whitespace().next().unwrap_or("");
+    if let Some(text_end) = rest.find('<') {
+      let closing = &rest[text_end..];
+      if closing.strip_prefix("</").is_some_and(|c| c.starts_with(name)) {
+        let local = name.rsplit(':').next().unwrap_or(name);
+        elements.push((local, rest[..text_end].trim()));
+      }
+    }
+  }
+  elements
+}
+
+/// Value of `name="..."` (or single-quoted) in an opening tag
+fn attribute<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+  tag.split_whitespace().find_map(|part| {
+    let value = part.strip_prefix(name)?.strip_prefix('=')?;
+    let quote = value.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+    let value = &value[1..];
+    value.find(quote).map(|end| &value[..end])
+  })
+}
+
+/// The five predefined XML entities
+fn unescape(text: &str) -> String {
+  text
+    .replace("&lt;", "<")
+    .replace("&gt;", ">")
+    .replace("&quot;", "\"")
+    .replace("&apos;", "'")
+    .replace("&amp;", "&")
+}
+
+/// Great-circle distance in meters
+fn haversine_m(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
+  let (phi1, phi2) = (lat1.to_radians(), lat2.to_radians());
+  let d_phi = (lat2 - lat1).to_radians();
+  let d_lambda = (lon2 - lon1).to_radians();
+  let a = (d_phi / 2.0).sin().powi(2) + phi1.cos() * phi2.cos() * (d_lambda / 2.0).sin().powi(2);
+  2.0 * EARTH_RADIUS_M * a.sqrt().asin()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_gpx_trackpoints_with_extensions() {
+    let gpx = r#"<?xml version="1.0" encoding="UTF-8"?>
+<gpx version="1.1" creator="Watch" xmlns:gpxtpx="http://www.garmin.com/xmlschemas/TrackPointExtension/v1">
+  <trk>
+    <name>Morning Run &amp; Drills</name>
+    <type>running</type>
+    <trkseg>
+      <trkpt lat="47.0000" lon="8.0000"><ele>400.0</ele><time>2024-12-10T07:00:00Z</time>
+        <extensions><gpxtpx:TrackPointExtension><gpxtpx:hr>120</gpxtpx:hr></gpxtpx:TrackPointExtension></extensions>
+      </trkpt>
+      <trkpt lat="47.0009" lon="8.0000"><ele>402.5</ele><time>2024-12-10T07:00:30Z</time>
+        <extensions><gpxtpx:TrackPointExtension><gpxtpx:hr>140</gpxtpx:hr></gpxtpx:TrackPointExtension></extensions>
+      </trkpt>
+      <trkpt lat='47.0018' lon='8.0000'><ele>401.0</ele><time>2024-12-10T07:01:00Z</time>
+        <extensions><gpxtpx:TrackPointExtension><gpxtpx:hr>160</gpxtpx:hr></gpxtpx:TrackPointExtension></extensions>
+      </trkpt>
+    </trkseg>
+  </trk>
+</gpx>"#;
+
+    let parsed = parse_gpx(gpx).unwrap();
+    let activity = &parsed.activity;
+    assert_eq!(activity.name, "Morning Run & Drills");
+    assert_eq!(activity.activity_type, "Run");
+    assert_eq!(activity.elapsed_time, 60);
+    assert_eq!(activity.average_heartrate, Some(140.0));
+    assert_eq!(activity.max_heartrate, Some(160.0));
+    assert!((activity.distance.unwrap() - 200.0).abs() < 1.0, "{:?}", activity.distance);
+    assert_eq!(activity.total_elevation_gain, Some(2.5));
+    assert!(!activity.trainer);
+    assert_eq!(parsed.source_id, format!("file_{}", activity.start_date.timestamp()));
+
+    let types: Vec<&str> = parsed.streams.iter().map(|s| s.stream_type.as_str()).collect();
+    assert_eq!(types, vec!["time", "heartrate", "velocity_smooth"]);
+
+    assert!(parse_gpx("<gpx><trk><trkseg></trkseg></trk></gpx>").is_err());
+    assert!(parse_activity_file(std::path::Path::new("ride.fit"), b"").is_err());
+  }
+
+  #[test]
+  fn test_gpx_hr_gaps_keep_the_stream_aligned() {
+    let point = |second: u32, hr: Option<i64>| {
+      let ext = hr.map_or(String::new(), |hr| format!("<extensions><hr>{}</hr></extensions>", hr));
+      format!(r#"<trkpt lat="47.0" lon="8.0"><time>2024-12-10T07:00:{:02}Z</time>{}</trkpt>"#, second, ext)
+    };
+    let gpx = format!(
+      "<gpx><trk><trkseg>{}{}{}{}</trkseg></trk></gpx>",
+      point(0, Some(130)),
+      point(10, None),
+      point(20, Some(150)),
+      point(30, None)
+    );
+
+    let parsed = parse_gpx(&gpx).unwrap();
+    let hr = parsed.streams.iter().find(|s| s.stream_type == "heartrate").unwrap();
+    let expected: Vec<serde_json::Value> = [130, 0, 150, 0].iter().map(|v| serde_json::json!(v)).collect();
+    assert_eq!(hr.data, expected);
+    // Averages only count the points that had HR
+    assert_eq!(parsed.activity.average_heartrate, Some(140.0));
+  }
+}
@@ -15,8 +15,185 @@ pub struct UserSettings {
   pub lthr: Option<i64>,
   pub ftp: Option<i64>,
   pub training_days_per_week: i64,
+  /// Target event name (e.g. "Kilimanjaro")
+  pub goal_name: Option<String>,
+  /// Target event date (YYYY-MM-DD)
+  pub goal_date: Option<String>,
+  /// LTHR fallback as a fraction of max_hr when lthr is not set
+  pub lthr_pct_of_max: f64,
+  /// Running-specific fallback fraction (None = lthr_pct_of_max)
+  pub run_lthr_pct_of_max: Option<f64>,
+  /// Cycling-specific fallback fraction (None = lthr_pct_of_max)
+  pub ride_lthr_pct_of_max: Option<f64>,
+  /// Multipliers and guards used by TrainingFlags
+  #[serde(default)]
+  pub flag_thresholds: FlagThresholds,
+  /// How many recent workouts feed the LLM's trend context
+  #[serde(default)]
+  pub recent_window: RecentWorkoutWindow,
+  /// Count rTSS from "other" activities (hiking, rowing, elliptical) toward
+  /// ATL/CTL. Their hours always count toward volume.
+  #[serde(default = "default_include_other_load")]
+  pub include_other_load: bool,
+  /// Nightly sleep target in hours (drives Oura sleep debt)
+  #[serde(default = "default_sleep_target_hours")]
+  pub sleep_target_hours: f64,
+  /// Per-sport threshold overrides (unset fields fall back to the globals)
+  #[serde(default)]
+  pub sport_settings: Vec<SportSettings>,
+  /// Athlete's IANA timezone, e.g. "America/Denver" (follows DST). Wins
+  /// over `utc_offset_minutes`. Day-of-week and weekly buckets use local dates.
+  #[serde(default)]
+  pub timezone: Option<String>,
+  /// Fixed local offset from UTC in minutes, used when no `timezone` is set.
+  /// A fixed offset doesn't follow DST. None of either = the system timezone.
+  #[serde(default)]
+  pub utc_offset_minutes: Option<i32>,
+  /// Target low/moderate/high intensity split, e.g. 80/15/5
+  #[serde(default)]
+  pub polarization_target: ZoneSplit,
+  /// Analyze newly synced workouts in the background
+  #[serde(default)]
+  pub auto_analyze: AutoAnalyzeSettings,
+  /// Days after one dimension progresses before another may (overlap rule)
+  #[serde(default = "default_progression_overlap_days")]
+  pub progression_overlap_days: i64,
+  /// Critical swim speed as a pace in seconds per 100m (drives swim zones)
+  #[serde(default)]
+  pub css_pace_sec_per_100m: Option<f64>,
+  /// Per-sport multipliers on rTSS when it's summed into ATL/CTL
+  #[serde(default)]
+  pub load_weights: LoadWeights,
+  /// Shoe distance (km) after which it's flagged for replacement
+  #[serde(default = "default_shoe_replacement_km")]
+  pub shoe_replacement_km: f64,
+  /// TSB edges between the fresh/fatigued bands
+  #[serde(default)]
+  pub tsb_bands: TsbBands,
+  /// Lookback for long sessions, shared by `longest_session` and the
+  /// long_run_gap/long_ride_gap flags (see LongestSession)
+  #[serde(default = "default_long_session_window_days")]
+  pub long_session_window_days: i64,
+  /// % of max HR or heart rate reserve zones
+  #[serde(default)]
+  pub zone_model: ZoneModel,
+  /// Resting HR for reserve zones (None = Oura's recent resting HR, if any)
+  #[serde(default)]
+  pub resting_hr: Option<i64>,
+  /// Ceiling on the analysis context package, in characters of JSON
+  #[serde(default = "default_context_char_budget")]
+  pub context_char_budget: i64,
+  /// Units for reported distance, pace and speed (storage stays metric)
+  #[serde(default)]
+  pub units: Units,
+  /// Scales progression criteria (beginners progress more cautiously)
+  #[serde(default)]
+  pub experience_level: crate::progression::ExperienceLevel,
+  /// First day of the training week for this/last week volume
+  #[serde(default)]
+  pub week_start_day: WeekStart,
+  /// Voice the coach writes in (the analysis itself doesn't change)
+  #[serde(default)]
+  pub coach_tone: CoachTone,
 }
 
+/// Threshold overrides for one sport, e.g. a cycling max HR that runs
+/// ~10 bpm below running
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SportSettings {
+  pub sport: ActivityKind,
+  pub max_hr: Option<i64>,
+  pub lthr: Option<i64>,
+  /// Threshold power in watts: FTP for rides, critical power for runs
+  pub ftp: Option<i64>,
+}
+
+impl SportSettings {
+  /// Sports that can carry overrides
+  pub const SPORTS: [ActivityKind; 3] = [ActivityKind::Run, ActivityKind::Ride, ActivityKind::Swim];
+
+  pub fn validate(&self) -> Result<(), String> {
+    if !Self::SPORTS.contains(&self.sport) {
+      return Err(format!("Invalid sport '{}': expected run, ride or swim", self.sport.as_str()));
+    }
+    for (label, value) in [("max_hr", self.max_hr), ("lthr", self.lthr)] {
+      if let Some(bpm) = value {
+        if !(100..=230).contains(&bpm) {
+          return Err(format!("Invalid {} '{}': expected 100 to 230 bpm", label, bpm));
+        }
+      }
+    }
+    if let (Some(lthr), Some(max_hr)) = (self.lthr, self.max_hr) {
+      if lthr > max_hr {
+        return Err(format!("Invalid lthr '{}': above max_hr {}", lthr, max_hr));
+      }
+    }
+    if let Some(ftp) = self.ftp {
+      if !(50..=600).contains(&ftp) {
+        return Err(format!("Invalid ftp '{}': expected 50 to 600 W", ftp));
+      }
+    }
+    Ok(())
+  }
+}
+
+fn default_include_other_load() -> bool {
+  true
+}
+
+fn default_sleep_target_hours() -> f64 {
+  DEFAULT_SLEEP_TARGET_HOURS
+}
+
+fn default_progression_overlap_days() -> i64 {
+  DEFAULT_PROGRESSION_OVERLAP_DAYS
+}
+
+fn default_shoe_replacement_km() -> f64 {
+  DEFAULT_SHOE_REPLACEMENT_KM
+}
+
+fn default_long_session_window_days() -> i64 {
+  DEFAULT_LONG_SESSION_WINDOW_DAYS
+}
+
+fn default_context_char_budget() -> i64 {
+  DEFAULT_CONTEXT_CHAR_BUDGET
+}
+
+/// Default LTHR fallback: 93% of max HR
+pub const DEFAULT_LTHR_PCT_OF_MAX: f64 = 0.93;
+
+/// Default nightly sleep target
+pub const DEFAULT_SLEEP_TARGET_HOURS: f64 = 8.0;
+
+/// Default overlap window between progressions of different dimensions
+pub const DEFAULT_PROGRESSION_OVERLAP_DAYS: i64 = 7;
+
+/// Valid overlap windows: none up to six weeks
+pub const PROGRESSION_OVERLAP_DAYS_RANGE: std::ops::RangeInclusive<i64> = 0..=42;
+
+/// Valid CSS paces: 1:00 to 4:00 per 100m
+pub const CSS_PACE_RANGE: std::ops::RangeInclusive<f64> = 60.0..=240.0;
+
+/// Default long-session lookback: three weeks
+pub const DEFAULT_LONG_SESSION_WINDOW_DAYS: i64 = 21;
+
+/// Valid long-session lookbacks: one to eight weeks
+pub const LONG_SESSION_WINDOW_DAYS_RANGE: std::ops::RangeInclusive<i64> = 7..=56;
+
+/// Typical running shoe lifespan
+pub const DEFAULT_SHOE_REPLACEMENT_KM: f64 = 800.0;
+
+/// Valid shoe replacement thresholds
+pub const SHOE_REPLACEMENT_KM_RANGE: std::ops::RangeInclusive<f64> = 100.0..=3000.0;
+
+/// Default context budget: ~15k tokens of JSON
+pub const DEFAULT_CONTEXT_CHAR_BUDGET: i64 = 60_000;
+
+/// Valid context budgets (the untrimmable core alone is a few thousand)
+pub const CONTEXT_CHAR_BUDGET_RANGE: std::ops::RangeInclusive<i64> = 8_000..=400_000;
+
 impl Default for UserSettings {
   fn default() -> Self {
     Self {
@@ -24,14 +201,173 @@ impl Default for UserSettings {
       lthr: None,
       ftp: None,
       training_days_per_week: 6,
+      goal_name: None,
+      goal_date: None,
+      lthr_pct_of_max: DEFAULT_LTHR_PCT_OF_MAX,
+      run_lthr_pct_of_max: None,
+      ride_lthr_pct_of_max: None,
+      flag_thresholds: FlagThresholds::default(),
+      recent_window: RecentWorkoutWindow::default(),
+      include_other_load: true,
+      sleep_target_hours: DEFAULT_SLEEP_TARGET_HOURS,
+      sport_settings: Vec::new(),
+      timezone: None,
+      utc_offset_minutes: None,
+      polarization_target: ZoneSplit::default(),
+      auto_analyze: AutoAnalyzeSettings::default(),
+      progression_overlap_days: DEFAULT_PROGRESSION_OVERLAP_DAYS,
+      css_pace_sec_per_100m: None,
+      load_weights: LoadWeights::default(),
+      shoe_replacement_km: DEFAULT_SHOE_REPLACEMENT_KM,
+      tsb_bands: TsbBands::default(),
+      long_session_window_days: DEFAULT_LONG_SESSION_WINDOW_DAYS,
+      zone_model: ZoneModel::default(),
+      resting_hr: None,
+      context_char_budget: DEFAULT_CONTEXT_CHAR_BUDGET,
+      units: Units::default(),
+      experience_level: crate::progression::ExperienceLevel::default(),
+      week_start_day: WeekStart::default(),
+      coach_tone: CoachTone::default(),
     }
   }
 }
 
+/// Valid fixed offsets: UTC-12:00 to UTC+14:00
+pub const UTC_OFFSET_MINUTES_RANGE: std::ops::RangeInclusive<i32> = -720..=840;
+
+/// Parse an IANA timezone name ("Europe/Berlin")
+pub fn parse_timezone(name: &str) -> Option<chrono_tz::Tz> {
+  name.parse().ok()
+}
+
 impl UserSettings {
-  /// Get LTHR, falling back to 93% of max_hr if not set
+  /// Convert a UTC timestamp to the athlete's local time: the named
+  /// timezone (with its DST offset at `at`), else the fixed offset, else the
+  /// system timezone
+  pub fn to_local(&self, at: &chrono::DateTime<chrono::Utc>) -> chrono::DateTime<chrono::FixedOffset> {
+    if let Some(tz) = self.timezone.as_deref().and_then(parse_timezone) {
+      return at.with_timezone(&tz).fixed_offset();
+    }
+    match self.utc_offset_minutes.and_then(|m| chrono::FixedOffset::east_opt(m * 60)) {
+      Some(offset) => at.with_timezone(&offset),
+      None => at.with_timezone(&chrono::Local).fixed_offset(),
+    }
+  }
+
+  /// Calendar date of a UTC timestamp in the athlete's timezone
+  pub fn local_date(&self, at: &chrono::DateTime<chrono::Utc>) -> chrono::NaiveDate {
+    self.to_local(at).date_naive()
+  }
+
+  /// The UTC instant the athlete's local `date` begins, for selecting stored
+  /// rows by local day
+  pub fn local_day_start_utc(&self, date: chrono::NaiveDate) -> chrono::DateTime<chrono::Utc> {
+    use chrono::TimeZone;
+
+    let to_utc = |local: chrono::NaiveDateTime| {
+      if let Some(tz) = self.timezone.as_deref().and_then(parse_timezone) {
+        tz.from_local_datetime(&local).earliest().map(|dt| dt.with_timezone(&chrono::Utc))
+      } else if let Some(offset) = self.utc_offset_minutes.and_then(|m| chrono::FixedOffset::east_opt(m * 60)) {
+        offset.from_local_datetime(&local).earliest().map(|dt| dt.with_timezone(&chrono::Utc))
+      } else {
+        chrono::Local.from_local_datetime(&local).earliest().map(|dt| dt.with_timezone(&chrono::Utc))
+      }
+    };
+    let midnight = date.and_time(chrono::NaiveTime::MIN);
+    // Where DST skips midnight, the day starts when the clocks resume
+    to_utc(midnight)
+      .or_else(|| to_utc(midnight + chrono::Duration::hours(1)))
+      .unwrap_or_else(|| midnight.and_utc())
+  }
+
+
+  /// Whole local calendar days between `started_at` and `now` (0 = same day)
+  pub fn local_days_between(
+    &self,
+    started_at: &chrono::DateTime<chrono::Utc>,
+    now: &chrono::DateTime<chrono::Utc>,
+  ) -> i64 {
+    (self.local_date(now) - self.local_date(started_at)).num_days()
+  }
+
+  /// Local noon on `date`, in UTC - an instant that falls on that local day
+  pub fn local_noon(&self, date: chrono::NaiveDate) -> chrono::DateTime<chrono::Utc> {
+    let noon = date.and_hms_opt(12, 0, 0).unwrap_or_default().and_utc();
+    let offset = self.to_local(&noon).offset().local_minus_utc();
+    noon - chrono::Duration::seconds(offset as i64)
+  }
+
+  /// Get LTHR, falling back to lthr_pct_of_max x max_hr if not set
   pub fn effective_lthr(&self) -> Option<i64> {
-    self.lthr.or_else(|| self.max_hr.map(|m| (m as f64 * 0.93) as i64))
+    self.lthr_with_fallback(self.lthr_pct_of_max)
+  }
+
+  /// Get LTHR for an activity. A sport override wins; a sport max HR is
+  /// scaled by the sport-specific fallback before the global LTHR is used.
+  pub fn effective_lthr_for(&self, activity_type: &str) -> Option<i64> {
+    let kind = canonical_activity(activity_type);
+    let pct = match kind {
+      ActivityKind::Run => self.run_lthr_pct_of_max,
+      ActivityKind::Ride => self.ride_lthr_pct_of_max,
+      _ => None,
+    }
+    .unwrap_or(self.lthr_pct_of_max);
+
+    match self.sport_override(kind) {
+      Some(SportSettings { lthr: Some(lthr), .. }) => Some(*lthr),
+      Some(SportSettings { max_hr: Some(max_hr), .. }) => Some((*max_hr as f64 * pct) as i64),
+      _ => self.lthr_with_fallback(pct),
+    }
+  }
+
+  /// Explicitly set LTHR for an activity (sport override, then global)
+  pub fn lthr_for(&self, activity_type: &str) -> Option<i64> {
+    self
+      .sport_override(canonical_activity(activity_type))
+      .and_then(|s| s.lthr)
+      .or(self.lthr)
+  }
+
+  /// Max HR for an activity (sport override, then global)
+  pub fn max_hr_for(&self, activity_type: &str) -> Option<i64> {
+    self
+      .sport_override(canonical_activity(activity_type))
+      .and_then(|s| s.max_hr)
+      .or(self.max_hr)
+  }
+
+  /// HR zone for an activity's heart rate under the configured zone model
+  /// (% of max when reserve zones have no resting HR). None without a max HR.
+  pub fn hr_zone_for(&self, activity_type: &str, hr: i64) -> Option<HrZone> {
+    self
+      .max_hr_for(activity_type)
+      .map(|max| HrZone::classify(hr, max, self.zone_model, self.resting_hr))
+  }
+
+  /// FTP for an activity (sport override, then global)
+  pub fn ftp_for(&self, activity_type: &str) -> Option<i64> {
+    self
+      .sport_override(canonical_activity(activity_type))
+      .and_then(|s| s.ftp)
+      .or(self.ftp)
+  }
+
+  /// Threshold power for power-based TSS. Runs use only the run override
+  /// (running critical power): a cycling FTP would misstate run load.
+  pub fn power_threshold_for(&self, activity_type: &str) -> Option<i64> {
+    match canonical_activity(activity_type) {
+      ActivityKind::Run => self.sport_override(ActivityKind::Run).and_then(|s| s.ftp),
+      ActivityKind::Ride => self.ftp_for(activity_type),
+      _ => None,
+    }
+  }
+
+  fn sport_override(&self, kind: ActivityKind) -> Option<&SportSettings> {
+    self.sport_settings.iter().find(|s| s.sport == kind)
+  }
+
+  fn lthr_with_fallback(&self, pct_of_max: f64) -> Option<i64> {
+    self.lthr.or_else(|| self.max_hr.map(|m| (m as f64 * pct_of_max) as i64))
   }
 }
 
@@ -50,7 +386,27 @@ pub enum HrZone {
 
 impl HrZone {
   pub fn from_hr(hr: i64, max_hr: i64) -> Self {
-    let pct = (hr as f64 / max_hr as f64) * 100.0;
+    Self::from_pct((hr as f64 / max_hr as f64) * 100.0)
+  }
+
+  /// Karvonen: the same bands over heart rate reserve, (hr - rest) / (max - rest).
+  /// Falls back to % of max when the resting HR isn't below max.
+  pub fn from_hr_reserve(hr: i64, resting_hr: i64, max_hr: i64) -> Self {
+    if resting_hr <= 0 || resting_hr >= max_hr {
+      return Self::from_hr(hr, max_hr);
+    }
+    Self::from_pct((hr - resting_hr) as f64 / (max_hr - resting_hr) as f64 * 100.0)
+  }
+
+  /// Zone under a model: HR reserve when a resting HR is known, else % of max
+  pub fn classify(hr: i64, max_hr: i64, model: ZoneModel, resting_hr: Option<i64>) -> Self {
+    match (model, resting_hr) {
+      (ZoneModel::HrReserve, Some(rest)) => Self::from_hr_reserve(hr, rest, max_hr),
+      _ => Self::from_hr(hr, max_hr),
+    }
+  }
+
+  fn from_pct(pct: f64) -> Self {
     match pct {
       p if p < 60.0 => HrZone::Z1,
       p if p < 70.0 => HrZone::Z2,
@@ -60,6 +416,18 @@ impl HrZone {
     }
   }
 
+  /// Parse a stored zone label ("Z1".."Z5")
+  pub fn parse(s: &str) -> Option<Self> {
+    match s {
+      "Z1" => Some(HrZone::Z1),
+      "Z2" => Some(HrZone::Z2),
+      "Z3" => Some(HrZone::Z3),
+      "Z4" => Some(HrZone::Z4),
+      "Z5" => Some(HrZone::Z5),
+      _ => None,
+    }
+  }
+
   pub fn as_str(&self) -> &'static str {
     match self {
       HrZone::Z1 => "Z1",
@@ -71,1144 +439,7150 @@ impl HrZone {
   }
 }
 
-/// ---------------------------------------------------------------------------
-/// Tier 1: Per-Workout Computed Metrics
-/// ---------------------------------------------------------------------------
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct WorkoutMetrics {
-  /// Running pace in min/km (None for non-run activities)
-  pub pace_min_per_km: Option<f64>,
-
-  /// Cycling speed in km/h (fallback if no power)
-  pub speed_kmh: Option<f64>,
+/// How HR zones are derived from max HR
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ZoneModel {
+  /// Percent of max HR
+  #[default]
+  PctMax,
+  /// Percent of heart rate reserve (Karvonen); needs a resting HR
+  HrReserve,
+}
 
-  /// Cycling work in kilojoules
-  pub kj: Option<f64>,
+impl ZoneModel {
+  pub fn parse(s: &str) -> Option<Self> {
+    match s {
+      "pct_max" => Some(ZoneModel::PctMax),
+      "hr_reserve" => Some(ZoneModel::HrReserve),
+      _ => None,
+    }
+  }
 
-  /// Relative Training Stress Score (HR-based)
-  pub rtss: Option<f64>,
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      ZoneModel::PctMax => "pct_max",
+      ZoneModel::HrReserve => "hr_reserve",
+    }
+  }
+}
 
-  /// Efficiency: pace/hr (run) or watts/hr (ride)
-  pub efficiency: Option<f64>,
+/// Where "this week" begins for weekly volume. Rolling compares the last
+/// 7 days with the 7 before; a weekday anchors calendar weeks to that day.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WeekStart {
+  #[default]
+  Rolling,
+  Monday,
+  Tuesday,
+  Wednesday,
+  Thursday,
+  Friday,
+  Saturday,
+  Sunday,
+}
 
-  /// Cardiac cost: avg_hr * duration_min
-  pub cardiac_cost: Option<f64>,
+impl WeekStart {
+  pub fn parse(s: &str) -> Option<Self> {
+    match s {
+      "rolling" => Some(WeekStart::Rolling),
+      "monday" => Some(WeekStart::Monday),
+      "tuesday" => Some(WeekStart::Tuesday),
+      "wednesday" => Some(WeekStart::Wednesday),
+      "thursday" => Some(WeekStart::Thursday),
+      "friday" => Some(WeekStart::Friday),
+      "saturday" => Some(WeekStart::Saturday),
+      "sunday" => Some(WeekStart::Sunday),
+      _ => None,
+    }
+  }
 
-  /// HR zone based on average HR
-  pub hr_zone: Option<HrZone>,
-}
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      WeekStart::Rolling => "rolling",
+      WeekStart::Monday => "monday",
+      WeekStart::Tuesday => "tuesday",
+      WeekStart::Wednesday => "wednesday",
+      WeekStart::Thursday => "thursday",
+      WeekStart::Friday => "friday",
+      WeekStart::Saturday => "saturday",
+      WeekStart::Sunday => "sunday",
+    }
+  }
 
-impl WorkoutMetrics {
-  /// Compute all Tier 1 metrics from raw workout data
-  pub fn compute(
-    activity_type: &str,
-    duration_seconds: Option<i64>,
-    distance_meters: Option<f64>,
-    average_hr: Option<i64>,
-    average_watts: Option<f64>,
-    settings: &UserSettings,
-  ) -> Self {
-    let duration_min = duration_seconds.map(|s| s as f64 / 60.0);
-    let duration_hr = duration_seconds.map(|s| s as f64 / 3600.0);
-    let distance_km = distance_meters.map(|m| m / 1000.0);
+  pub fn weekday(&self) -> Option<chrono::Weekday> {
+    match self {
+      WeekStart::Rolling => None,
+      WeekStart::Monday => Some(chrono::Weekday::Mon),
+      WeekStart::Tuesday => Some(chrono::Weekday::Tue),
+      WeekStart::Wednesday => Some(chrono::Weekday::Wed),
+      WeekStart::Thursday => Some(chrono::Weekday::Thu),
+      WeekStart::Friday => Some(chrono::Weekday::Fri),
+      WeekStart::Saturday => Some(chrono::Weekday::Sat),
+      WeekStart::Sunday => Some(chrono::Weekday::Sun),
+    }
+  }
 
-    // Pace (running only)
-    let pace_min_per_km = if activity_type.to_lowercase() == "run" {
-      match (duration_min, distance_km) {
-        (Some(dur), Some(dist)) if dist > 0.0 => Some(dur / dist),
-        _ => None,
-      }
-    } else {
-      None
-    };
+  /// Local days from the first day of the current week to `today` (0 on
+  /// the start day). A rolling week always began 6 days ago.
+  pub fn days_into_week(&self, today: chrono::NaiveDate) -> i64 {
+    use chrono::Datelike;
 
-    // Speed (cycling, fallback metric)
-    let speed_kmh = if activity_type.to_lowercase() == "ride" {
-      match (distance_km, duration_hr) {
-        (Some(dist), Some(dur)) if dur > 0.0 => Some(dist / dur),
-        _ => None,
+    match self.weekday() {
+      Some(start) => {
+        ((today.weekday().num_days_from_monday() + 7 - start.num_days_from_monday()) % 7) as i64
       }
-    } else {
-      None
-    };
+      None => 6,
+    }
+  }
 
-    // kJ (cycling with power)
-    let kj = if activity_type.to_lowercase() == "ride" {
-      match (average_watts, duration_seconds) {
-        (Some(watts), Some(secs)) => Some(watts * secs as f64 / 1000.0),
-        _ => None,
-      }
-    } else {
-      None
-    };
+  /// First day of the calendar week containing `today`. A rolling week has
+  /// no calendar anchor, so calendar views (weekly review, normalized load)
+  /// fall back to Monday weeks.
+  pub fn calendar_week_start(&self, today: chrono::NaiveDate) -> chrono::NaiveDate {
+    use chrono::Datelike;
 
-    // rTSS (HR-based training stress)
-    // Formula: (duration_min * (avg_hr / lthr)^2) / 60 * 100
-    let rtss = match (duration_min, average_hr, settings.effective_lthr()) {
-      (Some(dur), Some(hr), Some(lthr)) if lthr > 0 => {
-        let intensity = hr as f64 / lthr as f64;
-        Some((dur * intensity.powi(2)) / 60.0 * 100.0)
-      }
-      _ => None,
+    let days_into_week = match self.weekday() {
+      Some(_) => self.days_into_week(today),
+      None => today.weekday().num_days_from_monday() as i64,
     };
+    today - chrono::Duration::days(days_into_week)
+  }
+}
 
-    // Efficiency
-    let efficiency = match (activity_type.to_lowercase().as_str(), average_hr) {
-      ("run", Some(hr)) if hr > 0 => {
-        // For running: lower pace/hr is better (faster at lower HR)
-        pace_min_per_km.map(|pace| pace / hr as f64)
-      }
-      ("ride", Some(hr)) if hr > 0 => {
-        // For cycling: higher watts/hr is better
-        average_watts.map(|watts| watts / hr as f64)
-      }
-      _ => None,
-    };
+/// Voice of the coach's feedback, templated into the system prompt.
+/// Conversational is the original Strava-like voice.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CoachTone {
+  #[default]
+  Conversational,
+  Direct,
+  Supportive,
+  Analytical,
+}
 
-    // Cardiac cost
-    let cardiac_cost = match (average_hr, duration_min) {
-      (Some(hr), Some(dur)) => Some(hr as f64 * dur),
+impl CoachTone {
+  pub fn parse(s: &str) -> Option<Self> {
+    match s {
+      "conversational" => Some(CoachTone::Conversational),
+      "direct" => Some(CoachTone::Direct),
+      "supportive" => Some(CoachTone::Supportive),
+      "analytical" => Some(CoachTone::Analytical),
       _ => None,
-    };
+    }
+  }
 
-    // HR Zone
-    let hr_zone = match (average_hr, settings.max_hr) {
-      (Some(hr), Some(max)) => Some(HrZone::from_hr(hr, max)),
-      _ => None,
-    };
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      CoachTone::Conversational => "conversational",
+      CoachTone::Direct => "direct",
+      CoachTone::Supportive => "supportive",
+      CoachTone::Analytical => "analytical",
+    }
+  }
 
-    Self {
-      pace_min_per_km,
-      speed_kmh,
-      kj,
-      rtss,
-      efficiency,
-      cardiac_cost,
-      hr_zone,
+  /// The voice line for the system prompt's design principles
+  pub fn voice(&self) -> &'static str {
+    match self {
+      CoachTone::Conversational => "Strava voice: conversational, confident, occasionally playful",
+      CoachTone::Direct => "Direct voice: blunt and to the point, state the call and the reason, no cushioning",
+      CoachTone::Supportive => {
+        "Supportive voice: warm and encouraging, credit what went well before what to change"
+      }
+      CoachTone::Analytical => {
+        "Analytical voice: numbers first, lead with the metric and its delta - precise but never clinical"
+      }
     }
   }
 }
 
 /// ---------------------------------------------------------------------------
-/// Tier 2: Rolling Context Metrics
+/// Display Units
 /// ---------------------------------------------------------------------------
 
-/// A workout summary used for rolling calculations
-#[derive(Debug, Clone)]
-pub struct WorkoutSummary {
-  pub started_at: chrono::DateTime<chrono::Utc>,
-  pub activity_type: String,
-  pub duration_seconds: Option<i64>,
-  pub rtss: Option<f64>,
-  pub hr_zone: Option<HrZone>,
+/// Kilometres in a statute mile
+pub const KM_PER_MILE: f64 = 1.609344;
+
+/// Units distance, pace and speed are reported in. Everything is stored
+/// metric; conversion happens only when outputs are built.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Units {
+  /// km, min/km, km/h
+  #[default]
+  Metric,
+  /// mi, min/mi, mph
+  Imperial,
 }
 
-/// Training context computed from rolling windows
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct TrainingContext {
-  /// Acute Training Load: 7-day rTSS sum
-  pub atl: Option<f64>,
+impl Units {
+  pub fn parse(s: &str) -> Option<Self> {
+    match s {
+      "metric" => Some(Units::Metric),
+      "imperial" => Some(Units::Imperial),
+      _ => None,
+    }
+  }
 
-  /// Chronic Training Load: 42-day rTSS average
-  pub ctl: Option<f64>,
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      Units::Metric => "metric",
+      Units::Imperial => "imperial",
+    }
+  }
 
-  /// Training Stress Balance: CTL - ATL (form indicator)
-  pub tsb: Option<f64>,
+  pub fn is_metric(&self) -> bool {
+    *self == Units::Metric
+  }
 
-  /// Weekly volume in hours by modality
-  pub weekly_volume: WeeklyVolume,
+  /// Distance from kilometres
+  pub fn distance(&self, km: f64) -> f64 {
+    match self {
+      Units::Metric => km,
+      Units::Imperial => km / KM_PER_MILE,
+    }
+  }
 
-  /// Week-over-week volume change percentage
-  pub week_over_week_delta_pct: Option<f64>,
+  /// Pace from minutes per kilometre (a mile takes longer)
+  pub fn pace(&self, min_per_km: f64) -> f64 {
+    match self {
+      Units::Metric => min_per_km,
+      Units::Imperial => min_per_km * KM_PER_MILE,
+    }
+  }
 
-  /// Intensity distribution (zone percentages) over 7 days
-  pub intensity_distribution: IntensityDistribution,
+  /// Speed from km/h
+  pub fn speed(&self, kmh: f64) -> f64 {
+    self.distance(kmh)
+  }
 
-  /// Longest session by modality in last 28 days (in minutes)
-  pub longest_session: LongestSession,
+  pub fn distance_label(&self) -> &'static str {
+    match self {
+      Units::Metric => "km",
+      Units::Imperial => "mi",
+    }
+  }
 
-  /// Consistency: workout count vs expected over 28 days (percentage)
-  pub consistency_pct: Option<f64>,
-
-  /// Number of workouts this week
-  pub workouts_this_week: i32,
-}
+  pub fn pace_label(&self) -> &'static str {
+    match self {
+      Units::Metric => "min/km",
+      Units::Imperial => "min/mi",
+    }
+  }
 
-/// Weekly volume breakdown by modality
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
-pub struct WeeklyVolume {
-  pub total_hrs: f64,
-  pub run_hrs: f64,
-  pub ride_hrs: f64,
-  pub other_hrs: f64,
+  pub fn speed_label(&self) -> &'static str {
+    match self {
+      Units::Metric => "km/h",
+      Units::Imperial => "mph",
+    }
+  }
 }
 
-/// Intensity distribution by HR zone
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
-pub struct IntensityDistribution {
-  pub z1_pct: f64,
-  pub z2_pct: f64,
-  pub z3_pct: f64,
-  pub z4_pct: f64,
-  pub z5_pct: f64,
+/// Distance, pace and speed converted to the athlete's units, each with
+/// its label
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UnitMetrics {
+  pub units: Units,
+  pub distance: Option<f64>,
+  pub distance_unit: String,
+  pub pace: Option<f64>,
+  pub pace_unit: String,
+  pub speed: Option<f64>,
+  pub speed_unit: String,
 }
 
-/// Longest session by modality
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
-pub struct LongestSession {
-  pub run_min: Option<f64>,
-  pub ride_min: Option<f64>,
+impl UnitMetrics {
+  /// Convert metric values (km, min/km, km/h)
+  pub fn new(units: Units, distance_km: Option<f64>, pace_min_km: Option<f64>, speed_kmh: Option<f64>) -> Self {
+    Self {
+      units,
+      distance: distance_km.map(|km| units.distance(km)),
+      distance_unit: units.distance_label().to_string(),
+      pace: pace_min_km.map(|pace| units.pace(pace)),
+      pace_unit: units.pace_label().to_string(),
+      speed: speed_kmh.map(|kmh| units.speed(kmh)),
+      speed_unit: units.speed_label().to_string(),
+    }
+  }
 }
 
-impl TrainingContext {
-  /// Compute training context from a list of recent workouts
-  pub fn compute(workouts: &[WorkoutSummary], settings: &UserSettings) -> Self {
-    let now = chrono::Utc::now();
-
-    // Filter workouts by time windows
-    let days_7: Vec<_> = workouts
-      .iter()
-      .filter(|w| (now - w.started_at).num_days() < 7)
-      .collect();
-
-    let days_14: Vec<_> = workouts
-      .iter()
-      .filter(|w| (now - w.started_at).num_days() < 14)
-      .collect();
-
-    let days_28: Vec<_> = workouts
-      .iter()
-      .filter(|w| (now - w.started_at).num_days() < 28)
-      .collect();
-
-    let days_42: Vec<_> = workouts
-      .iter()
-      .filter(|w| (now - w.started_at).num_days() < 42)
-      .collect();
-
-    // ATL: 7-day rTSS sum
-    let atl = Self::compute_rtss_sum(&days_7);
-
-    // CTL: 42-day rTSS average (daily average)
-    let ctl = Self::compute_rtss_avg(&days_42, 42);
-
-    // TSB: CTL - ATL
-    let tsb = match (ctl, atl) {
-      (Some(c), Some(a)) => Some(c - a / 7.0), // Normalize ATL to daily
-      _ => None,
-    };
-
-    // Weekly volume
-    let weekly_volume = Self::compute_weekly_volume(&days_7);
-
-    // Week-over-week delta
-    let this_week_volume = weekly_volume.total_hrs;
-    let last_week_volume = Self::compute_weekly_volume(
-      &days_14
-        .iter()
-        .filter(|w| (now - w.started_at).num_days() >= 7)
-        .cloned()
-        .collect::<Vec<_>>(),
-    )
-    .total_hrs;
-
-    let week_over_week_delta_pct = if last_week_volume > 0.0 {
-      Some(((this_week_volume - last_week_volume) / last_week_volume) * 100.0)
-    } else if this_week_volume > 0.0 {
-      Some(100.0) // First week with data
-    } else {
-      None
-    };
-
-    // Intensity distribution
-    let intensity_distribution = Self::compute_intensity_distribution(&days_7);
-
-    // Longest session (28 days)
-    let longest_session = Self::compute_longest_session(&days_28);
-
-    // Consistency: actual workouts vs expected
-    let expected_workouts_28d = settings.training_days_per_week as f64 * 4.0;
-    let actual_workouts_28d = days_28.len() as f64;
-    let consistency_pct = if expected_workouts_28d > 0.0 {
-      Some((actual_workouts_28d / expected_workouts_28d) * 100.0)
-    } else {
-      None
-    };
+/// ---------------------------------------------------------------------------
+/// Swim Zones
+/// ---------------------------------------------------------------------------
 
-    let workouts_this_week = days_7.len() as i32;
+/// Swim intensity from pace relative to critical swim speed (CSS). Swim HR
+/// from a wrist sensor is unreliable, so pace sets the zone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SwimZone {
+  Recovery,  // > 110% of CSS pace
+  Aerobic,   // 103-110% of CSS pace
+  Threshold, // 97-103% of CSS pace
+  Vo2,       // < 97% of CSS pace
+}
 
-    Self {
-      atl,
-      ctl,
-      tsb,
-      weekly_volume,
-      week_over_week_delta_pct,
-      intensity_distribution,
-      longest_session,
-      consistency_pct,
-      workouts_this_week,
+impl SwimZone {
+  /// Zone for a pace in sec/100m (higher = slower)
+  pub fn from_pace(pace_sec_per_100m: f64, css_sec_per_100m: f64) -> Self {
+    // Compare raw paces so a boundary pace (e.g. 110% of CSS) isn't pushed
+    // across by float error in the ratio
+    match pace_sec_per_100m {
+      p if p > css_sec_per_100m * 1.10 => SwimZone::Recovery,
+      p if p > css_sec_per_100m * 1.03 => SwimZone::Aerobic,
+      p if p >= css_sec_per_100m * 0.97 => SwimZone::Threshold,
+      _ => SwimZone::Vo2,
     }
   }
 
-  fn compute_rtss_sum(workouts: &[&WorkoutSummary]) -> Option<f64> {
-    let sum: f64 = workouts.iter().filter_map(|w| w.rtss).sum();
-    if sum > 0.0 {
-      Some(sum)
-    } else {
-      None
+  /// Parse a stored zone label ("recovery".."vo2")
+  pub fn parse(s: &str) -> Option<Self> {
+    match s {
+      "recovery" => Some(SwimZone::Recovery),
+      "aerobic" => Some(SwimZone::Aerobic),
+      "threshold" => Some(SwimZone::Threshold),
+      "vo2" => Some(SwimZone::Vo2),
+      _ => None,
     }
   }
 
-  fn compute_rtss_avg(workouts: &[&WorkoutSummary], days: i64) -> Option<f64> {
-    let sum: f64 = workouts.iter().filter_map(|w| w.rtss).sum();
-    if sum > 0.0 {
-      Some(sum / days as f64)
-    } else {
-      None
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      SwimZone::Recovery => "recovery",
+      SwimZone::Aerobic => "aerobic",
+      SwimZone::Threshold => "threshold",
+      SwimZone::Vo2 => "vo2",
     }
   }
 
-  fn compute_weekly_volume(workouts: &[&WorkoutSummary]) -> WeeklyVolume {
-    let mut volume = WeeklyVolume::default();
-
-    for w in workouts {
-      let hrs = w.duration_seconds.map(|s| s as f64 / 3600.0).unwrap_or(0.0);
-      volume.total_hrs += hrs;
-
-      match w.activity_type.to_lowercase().as_str() {
-        "run" => volume.run_hrs += hrs,
-        "ride" => volume.ride_hrs += hrs,
-        _ => volume.other_hrs += hrs,
-      }
+  /// The HR zone with the same stimulus, so swims share the intensity
+  /// distribution with runs and rides
+  pub fn hr_equivalent(&self) -> HrZone {
+    match self {
+      SwimZone::Recovery => HrZone::Z1,
+      SwimZone::Aerobic => HrZone::Z2,
+      SwimZone::Threshold => HrZone::Z4,
+      SwimZone::Vo2 => HrZone::Z5,
     }
-
-    volume
   }
+}
 
-  fn compute_intensity_distribution(workouts: &[&WorkoutSummary]) -> IntensityDistribution {
-    let mut dist = IntensityDistribution::default();
-    let mut total_duration = 0.0;
+/// ---------------------------------------------------------------------------
+/// Activity Classification
+/// ---------------------------------------------------------------------------
 
-    // Sum duration by zone
-    let mut z1_duration = 0.0;
-    let mut z2_duration = 0.0;
-    let mut z3_duration = 0.0;
-    let mut z4_duration = 0.0;
-    let mut z5_duration = 0.0;
+/// Strava activity types treated as supplemental (strength/mobility) work.
+/// These often carry bogus distance or HR and shouldn't count as endurance load.
+const SUPPLEMENTAL_ACTIVITY_TYPES: &[&str] = &["weighttraining", "yoga", "workout", "crossfit", "pilates"];
 
-    for w in workouts {
-      if let (Some(zone), Some(dur)) = (&w.hr_zone, w.duration_seconds) {
-        let dur_min = dur as f64 / 60.0;
-        total_duration += dur_min;
-        match zone {
-          HrZone::Z1 => z1_duration += dur_min,
-          HrZone::Z2 => z2_duration += dur_min,
-          HrZone::Z3 => z3_duration += dur_min,
-          HrZone::Z4 => z4_duration += dur_min,
-          HrZone::Z5 => z5_duration += dur_min,
-        }
-      }
-    }
+/// Check if an activity is supplemental cross-training rather than endurance work
+pub fn is_supplemental_activity(activity_type: &str) -> bool {
+  let normalized = normalize_activity_type(activity_type);
+  SUPPLEMENTAL_ACTIVITY_TYPES.contains(&normalized.as_str())
+}
 
-    if total_duration > 0.0 {
-      dist.z1_pct = (z1_duration / total_duration) * 100.0;
-      dist.z2_pct = (z2_duration / total_duration) * 100.0;
-      dist.z3_pct = (z3_duration / total_duration) * 100.0;
-      dist.z4_pct = (z4_duration / total_duration) * 100.0;
-      dist.z5_pct = (z5_duration / total_duration) * 100.0;
-    }
+/// Canonical endurance modality, independent of how the source spelled it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ActivityKind {
+  Run,
+  Ride,
+  Swim,
+  Other,
+}
 
-    dist
+impl ActivityKind {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      ActivityKind::Run => "run",
+      ActivityKind::Ride => "ride",
+      ActivityKind::Swim => "swim",
+      ActivityKind::Other => "other",
+    }
   }
+}
 
-  fn compute_longest_session(workouts: &[&WorkoutSummary]) -> LongestSession {
-    let mut longest = LongestSession::default();
-
-    for w in workouts {
-      let dur_min = w.duration_seconds.map(|s| s as f64 / 60.0);
+/// Known spellings per modality (after normalization: lowercase, no spaces/underscores/hyphens)
+const RUN_ALIASES: &[&str] = &["run", "running", "trailrun", "virtualrun", "treadmill", "treadmillrun"];
+const RIDE_ALIASES: &[&str] = &[
+  "ride", "cycling", "bike", "biking", "virtualride", "ebikeride", "mountainbikeride",
+  "emountainbikeride", "gravelride", "indoorcycling",
+];
+const SWIM_ALIASES: &[&str] = &["swim", "swimming", "openwaterswim", "poolswim"];
+/// Cross-training kinds broken out in weekly volume
+const HIKE_ALIASES: &[&str] = &["hike", "hiking"];
+const ROW_ALIASES: &[&str] = &["row", "rowing", "indoorrowing", "virtualrow"];
+/// Types that are always indoors (trainer or treadmill)
+const INDOOR_ALIASES: &[&str] = &["virtualrun", "treadmill", "treadmillrun", "virtualride", "indoorcycling"];
+
+/// Lowercase and strip separators so "Trail Run", "trail_run" and "TrailRun" compare equal
+fn normalize_activity_type(activity_type: &str) -> String {
+  activity_type
+    .chars()
+    .filter(|c| !matches!(c, ' ' | '_' | '-'))
+    .flat_map(|c| c.to_lowercase())
+    .collect()
+}
 
-      match w.activity_type.to_lowercase().as_str() {
-        "run" => {
-          if let Some(d) = dur_min {
-            longest.run_min = Some(longest.run_min.map_or(d, |curr| curr.max(d)));
-          }
-        }
-        "ride" => {
-          if let Some(d) = dur_min {
-            longest.ride_min = Some(longest.ride_min.map_or(d, |curr| curr.max(d)));
-          }
-        }
-        _ => {}
-      }
-    }
+/// Is this type recorded indoors by definition ("VirtualRide", "Treadmill")?
+/// Outdoor types can still be indoor via Strava's `trainer` flag.
+pub fn is_indoor_activity(activity_type: &str) -> bool {
+  INDOOR_ALIASES.contains(&normalize_activity_type(activity_type).as_str())
+}
 
-    longest
+/// Map a raw activity type ("Run", "Running", "TrailRun", "VirtualRide"...) to its modality
+pub fn canonical_activity(activity_type: &str) -> ActivityKind {
+  let normalized = normalize_activity_type(activity_type);
+  let normalized = normalized.as_str();
+
+  if RUN_ALIASES.contains(&normalized) {
+    ActivityKind::Run
+  } else if RIDE_ALIASES.contains(&normalized) {
+    ActivityKind::Ride
+  } else if SWIM_ALIASES.contains(&normalized) {
+    ActivityKind::Swim
+  } else {
+    ActivityKind::Other
   }
 }
 
+/// Normalized spellings that count as the same type as `activity_type`:
+/// every alias of its modality, or just its own name when unrecognized
+pub fn activity_type_aliases(activity_type: &str) -> Vec<String> {
+  let aliases = match canonical_activity(activity_type) {
+    ActivityKind::Run => RUN_ALIASES,
+    ActivityKind::Ride => RIDE_ALIASES,
+    ActivityKind::Swim => SWIM_ALIASES,
+    ActivityKind::Other => return vec![normalize_activity_type(activity_type)],
+  };
+  aliases.iter().map(|alias| alias.to_string()).collect()
+}
+
 /// ---------------------------------------------------------------------------
-/// Tier 3: Training Flags (Boolean Alerts)
+/// Tier 1: Per-Workout Computed Metrics
 /// ---------------------------------------------------------------------------
 
-/// Training flags that indicate potential issues or achievements
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
-pub struct TrainingFlags {
-  /// Volume > 1.2x chronic average
-  pub volume_spike: bool,
+pub struct WorkoutMetrics {
+  /// Running pace in min/km (None for non-run activities)
+  pub pace_min_per_km: Option<f64>,
 
-  /// Volume < 0.7x chronic average
-  pub volume_drop: bool,
+  /// Cycling speed in km/h (fallback if no power; None indoors)
+  pub speed_kmh: Option<f64>,
 
-  /// TSB < -20 (accumulated fatigue)
-  pub high_fatigue: bool,
+  /// Cycling work in kilojoules
+  pub kj: Option<f64>,
 
-  /// TSB between +5 and +15 (good racing form)
-  pub peak_form: bool,
+  /// Relative Training Stress Score (HR-based; power-based for runs with
+  /// a running critical power)
+  pub rtss: Option<f64>,
 
-  /// No run > 10km in 3 weeks
-  pub long_run_gap: bool,
+  /// Efficiency: pace/hr (run) or watts/hr (ride)
+  pub efficiency: Option<f64>,
 
-  /// No ride > 60min in 3 weeks
-  pub long_ride_gap: bool,
+  /// Efficiency Factor: speed in m/min (run) or watts (ride) per beat of
+  /// average HR, so higher is always better
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub efficiency_factor: Option<f64>,
 
-  /// Intensity predominantly Z3+ (> 40%)
-  pub intensity_heavy: bool,
+  /// Cardiac cost: avg_hr * duration_min
+  pub cardiac_cost: Option<f64>,
 
-  /// Predominantly Z1-Z2 (> 80%) - good aerobic base
-  pub polarized_training: bool,
-}
+  /// HR zone based on average HR
+  pub hr_zone: Option<HrZone>,
 
-impl TrainingFlags {
-  /// Compute training flags from workout history, context, and progression dimensions
-  pub fn compute(
-    workouts: &[WorkoutSummary],
-    context: &TrainingContext,
-    _settings: &UserSettings,
-    dimensions: &[crate::progression::ProgressionDimension],
-  ) -> Self {
-    let now = chrono::Utc::now();
-    let mut flags = TrainingFlags::default();
+  /// rTSS split by the HR zone it was accrued in (HR stream only)
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub tss_by_zone: Option<ZoneTss>,
 
-    // Volume spike: current week > 1.2x chronic (use CTL as proxy for chronic load)
-    // We approximate chronic volume from CTL and compare to current week
-    if let (Some(atl), Some(ctl)) = (context.atl, context.ctl) {
-      // If weekly load (ATL) is much higher than chronic daily average * 7
-      let chronic_weekly = ctl * 7.0;
-      if atl > chronic_weekly * 1.2 {
-        flags.volume_spike = true;
-      }
-      if atl < chronic_weekly * 0.7 && chronic_weekly > 50.0 {
-        // Only flag if there's meaningful chronic load
-        flags.volume_drop = true;
-      }
-    }
+  /// Swim pace in seconds per 100m (None for non-swim activities)
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub swim_pace_sec_per_100m: Option<f64>,
 
-    // High fatigue: TSB < -20
-    if let Some(tsb) = context.tsb {
-      if tsb < -20.0 {
-        flags.high_fatigue = true;
-      }
-      if tsb > 5.0 && tsb < 15.0 {
-        flags.peak_form = true;
-      }
-    }
+  /// Swim zone from pace vs CSS (None without a CSS setting)
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub swim_zone: Option<SwimZone>,
 
-    // Long run gap: no run >= ceiling in 21 days
-    // Get the long_run ceiling from dimensions, default to 90 min if not set
-    let long_run_ceiling_min = dimensions
-      .iter()
-      .find(|d| d.name == "long_run")
-      .and_then(|d| d.ceiling_value.parse::<f64>().ok())
-      .unwrap_or(90.0);
-    let long_run_threshold_secs = (long_run_ceiling_min * 60.0) as i64;
+  /// HR stream had too many dropouts: HR-derived values are low-confidence
+  /// and no zone is assigned
+  #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+  pub hr_low_confidence: bool,
 
-    let days_21: Vec<_> = workouts
-      .iter()
-      .filter(|w| (now - w.started_at).num_days() < 21)
-      .collect();
+  /// A value fell outside the plausibility bounds (GPS glitch, HR spike)
+  /// and was nulled rather than stored as a real data point
+  #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+  pub data_suspect: bool,
+}
 
-    let has_long_run = days_21.iter().any(|w| {
-      w.activity_type.to_lowercase() == "run"
-        && w.duration_seconds.map_or(false, |d| d >= long_run_threshold_secs)
-    });
-    if !has_long_run && days_21.iter().any(|w| w.activity_type.to_lowercase() == "run") {
-      flags.long_run_gap = true;
-    }
+/// Plausible running pace (min/km); faster or slower is a GPS glitch
+pub const PLAUSIBLE_RUN_PACE: std::ops::RangeInclusive<f64> = 2.0..=12.0;
+/// Plausible outdoor ride speed (km/h)
+pub const PLAUSIBLE_RIDE_SPEED: std::ops::RangeInclusive<f64> = 5.0..=80.0;
+/// Plausible session average HR (bpm)
+pub const PLAUSIBLE_AVERAGE_HR: std::ops::RangeInclusive<i64> = 30..=230;
+/// Plausible run efficiency (pace/HR): the pace bounds over the HR bounds
+pub const PLAUSIBLE_RUN_EFFICIENCY: std::ops::RangeInclusive<f64> = 0.008..=0.4;
+/// Plausible ride efficiency (watts/HR)
+pub const PLAUSIBLE_RIDE_EFFICIENCY: std::ops::RangeInclusive<f64> = 0.2..=5.0;
+
+/// Training stress accrued in each HR zone. A 100-rTSS threshold session
+/// and a 100-rTSS long Z2 run carry the same total but not the same stimulus.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct ZoneTss {
+  pub z1: f64,
+  pub z2: f64,
+  pub z3: f64,
+  pub z4: f64,
+  pub z5: f64,
+}
 
-    // Long ride gap: no ride >= ceiling in 21 days
-    // Get the z2_ride ceiling from dimensions, default to 60 min if not set
-    let z2_ride_ceiling_min = dimensions
-      .iter()
-      .find(|d| d.name == "z2_ride")
-      .and_then(|d| d.ceiling_value.parse::<f64>().ok())
-      .unwrap_or(60.0);
-    let long_ride_threshold_secs = (z2_ride_ceiling_min * 60.0) as i64;
-
-    let has_long_ride = days_21.iter().any(|w| {
-      w.activity_type.to_lowercase() == "ride"
-        && w.duration_seconds.map_or(false, |d| d >= long_ride_threshold_secs)
-    });
-    if !has_long_ride && days_21.iter().any(|w| w.activity_type.to_lowercase() == "ride") {
-      flags.long_ride_gap = true;
+impl ZoneTss {
+  fn add(&mut self, zone: HrZone, tss: f64) {
+    match zone {
+      HrZone::Z1 => self.z1 += tss,
+      HrZone::Z2 => self.z2 += tss,
+      HrZone::Z3 => self.z3 += tss,
+      HrZone::Z4 => self.z4 += tss,
+      HrZone::Z5 => self.z5 += tss,
     }
+  }
 
-    // Intensity flags from distribution
-    let high_intensity_pct =
-      context.intensity_distribution.z3_pct
-        + context.intensity_distribution.z4_pct
-        + context.intensity_distribution.z5_pct;
-    let low_intensity_pct = context.intensity_distribution.z1_pct + context.intensity_distribution.z2_pct;
+  pub fn total(&self) -> f64 {
+    self.z1 + self.z2 + self.z3 + self.z4 + self.z5
+  }
 
-    if high_intensity_pct > 40.0 {
-      flags.intensity_heavy = true;
-    }
-    if low_intensity_pct > 80.0 {
-      flags.polarized_training = true;
+  fn is_finite(&self) -> bool {
+    self.total().is_finite()
+  }
+}
+
+impl WorkoutMetrics {
+  /// Compute all Tier 1 metrics from raw workout data
+  pub fn compute(
+    activity_type: &str,
+    duration_seconds: Option<i64>,
+    distance_meters: Option<f64>,
+    average_hr: Option<i64>,
+    average_watts: Option<f64>,
+    hr_samples: &[i64],
+    settings: &UserSettings,
+  ) -> Self {
+    // Supplemental sessions (strength, yoga) go in the "other" bucket with no
+    // pace/load metrics - their distance and HR aren't meaningful
+    if is_supplemental_activity(activity_type) {
+      return Self {
+        pace_min_per_km: None,
+        speed_kmh: None,
+        kj: None,
+        rtss: None,
+        efficiency: None,
+        efficiency_factor: None,
+        cardiac_cost: None,
+        hr_zone: None,
+        tss_by_zone: None,
+        swim_pace_sec_per_100m: None,
+        swim_zone: None,
+        hr_low_confidence: false,
+        data_suspect: false,
+      };
     }
 
-    flags
-  }
+    let kind = canonical_activity(activity_type);
+    let duration_min = duration_seconds.map(|s| s as f64 / 60.0);
+    let duration_hr = duration_seconds.map(|s| s as f64 / 3600.0);
+    let distance_km = distance_meters.map(|m| m / 1000.0);
 
-  /// Convert flags to a prioritized list with (flag_name, priority, description)
-  /// Priority: 1 = highest, 5 = lowest
-  pub fn to_prioritized_list(&self) -> Vec<(String, u8, String)> {
-    let mut flags = Vec::new();
+    // Pace (running only)
+    let pace_min_per_km = if kind == ActivityKind::Run {
+      match (duration_min, distance_km) {
+        (Some(dur), Some(dist)) if dist > 0.0 => Some(dur / dist),
+        _ => None,
+      }
+    } else {
+      None
+    };
 
-    if self.high_fatigue {
-      flags.push((
-        "high_fatigue".to_string(),
-        1,
-        "TSB indicates accumulated fatigue (< -20)".to_string(),
-      ));
-    }
-    if self.volume_spike {
-      flags.push((
-        "volume_spike".to_string(),
-        2,
-        "Training volume significantly above chronic average".to_string(),
-      ));
-    }
-    if self.intensity_heavy {
-      flags.push((
-        "intensity_heavy".to_string(),
-        3,
-        ">40% of training in Z3+".to_string(),
-      ));
-    }
-    if self.long_run_gap {
-      flags.push((
-        "long_run_gap".to_string(),
-        4,
-        "No run at ceiling duration in 3 weeks".to_string(),
-      ));
+    // Pace per 100m (swimming only)
+    let swim_pace_sec_per_100m = if kind == ActivityKind::Swim {
+      match (duration_seconds, distance_meters) {
+        (Some(secs), Some(dist)) if dist > 0.0 => Some(secs as f64 / (dist / 100.0)),
+        _ => None,
+      }
+    } else {
+      None
+    };
+
+    // Swim zone (pace relative to CSS)
+    let swim_zone = match (swim_pace_sec_per_100m, settings.css_pace_sec_per_100m) {
+      (Some(pace), Some(css)) if css > 0.0 && pace.is_finite() => Some(SwimZone::from_pace(pace, css)),
+      _ => None,
+    };
+
+    // Speed (cycling, fallback metric)
+    let speed_kmh = if kind == ActivityKind::Ride {
+      match (distance_km, duration_hr) {
+        (Some(dist), Some(dur)) if dur > 0.0 => Some(dist / dur),
+        _ => None,
+      }
+    } else {
+      None
+    };
+
+    // kJ (cycling with power)
+    let kj = if kind == ActivityKind::Ride {
+      match (average_watts, duration_seconds) {
+        (Some(watts), Some(secs)) => Some(watts * secs as f64 / 1000.0),
+        _ => None,
+      }
+    } else {
+      None
+    };
+
+    // Run power (Stryd-style) reacts to intervals faster than HR, so it wins
+    // when the athlete has a running critical power
+    let run_power_tss = if kind == ActivityKind::Run {
+      compute_power_tss(average_watts, settings.power_threshold_for(activity_type), duration_min)
+    } else {
+      None
+    };
+
+    // rTSS (HR-based training stress)
+    // Prefer the HR stream so surges count at their real intensity; fall back
+    // to the whole-workout average: (duration_min * (avg_hr / lthr)^2) / 60 * 100
+    let rtss = run_power_tss.or_else(|| match (duration_min, settings.effective_lthr_for(activity_type)) {
+      (Some(dur), Some(lthr)) if lthr > 0 => compute_rtss_from_stream(hr_samples, lthr, dur)
+        .or_else(|| {
+          average_hr.map(|hr| {
+            let intensity = hr as f64 / lthr as f64;
+            (dur * intensity.powi(2)) / 60.0 * 100.0
+          })
+        }),
+      _ => None,
+    });
+
+    // Where the HR-based load came from; skipped when power set the rTSS so
+    // the zones always add up to it
+    let tss_by_zone = if run_power_tss.is_none() {
+      match (duration_min, settings.effective_lthr_for(activity_type), settings.max_hr_for(activity_type)) {
+        (Some(dur), Some(lthr), Some(max_hr)) => {
+          compute_tss_by_zone(hr_samples, lthr, max_hr, dur, settings.zone_model, settings.resting_hr)
+        }
+        _ => None,
+      }
+    } else {
+      None
+    };
+
+    // Efficiency
+    let efficiency = match (kind, average_hr) {
+      (ActivityKind::Run, Some(hr)) if hr > 0 => {
+        // For running: lower pace/hr is better (faster at lower HR)
+        pace_min_per_km.filter(|pace| *pace > 0.0).map(|pace| pace / hr as f64)
+      }
+      (ActivityKind::Ride, Some(hr)) if hr > 0 => {
+        // For cycling: higher watts/hr is better
+        average_watts.map(|watts| watts / hr as f64)
+      }
+      _ => None,
+    };
+
+    let efficiency_factor = Self::compute_efficiency_factor(kind, pace_min_per_km, average_watts, average_hr);
+
+    // Cardiac cost
+    let cardiac_cost = match (average_hr, duration_min) {
+      (Some(hr), Some(dur)) => Some(hr as f64 * dur),
+      _ => None,
+    };
+
+    // HR Zone
+    let hr_zone = average_hr.and_then(|hr| settings.hr_zone_for(activity_type, hr));
+
+    Self {
+      pace_min_per_km,
+      speed_kmh,
+      kj,
+      rtss,
+      efficiency,
+      efficiency_factor,
+      cardiac_cost,
+      hr_zone,
+      tss_by_zone,
+      swim_pace_sec_per_100m,
+      swim_zone,
+      hr_low_confidence: false,
+      data_suspect: false,
     }
-    if self.long_ride_gap {
-      flags.push((
-        "long_ride_gap".to_string(),
-        4,
-        "No ride at ceiling duration in 3 weeks".to_string(),
-      ));
+    .with_indoor(is_indoor_activity(activity_type))
+    .sanitized()
+    .with_plausibility_bounds(kind, average_hr)
+  }
+
+  /// Efficiency Factor: run speed (m/min) or ride power over average HR.
+  /// Unlike `efficiency` (pace/HR for runs), higher is better for both.
+  pub fn compute_efficiency_factor(
+    kind: ActivityKind,
+    pace_min_per_km: Option<f64>,
+    average_watts: Option<f64>,
+    average_hr: Option<i64>,
+  ) -> Option<f64> {
+    let hr = average_hr.filter(|hr| *hr > 0)? as f64;
+    let output = match kind {
+      ActivityKind::Run => pace_min_per_km.filter(|pace| *pace > 0.0).map(|pace| 1000.0 / pace),
+      ActivityKind::Ride => average_watts.filter(|watts| *watts > 0.0),
+      _ => None,
+    }?;
+    finite(Some(output / hr))
+  }
+
+  /// Drop speed for indoor sessions: trainer "distance" is simulated
+  pub fn with_indoor(mut self, is_indoor: bool) -> Self {
+    if is_indoor {
+      self.speed_kmh = None;
     }
-    if self.volume_drop {
-      flags.push((
-        "volume_drop".to_string(),
-        5,
-        "Training volume significantly below chronic average".to_string(),
-      ));
+    self
+  }
+
+  /// Drop zone classification when the HR strap dropped out. Load then
+  /// comes from the duration estimate (see WorkoutSamples::into_load_hr).
+  pub fn with_hr_coverage(mut self, hr_reliable: bool) -> Self {
+    if !hr_reliable {
+      self.hr_low_confidence = true;
+      self.hr_zone = None;
+      self.tss_by_zone = None;
     }
-    if self.peak_form {
-      flags.push((
-        "peak_form".to_string(),
-        5,
-        "TSB indicates good racing form (+5 to +15)".to_string(),
-      ));
+    self
+  }
+
+  /// Null values outside the PLAUSIBLE_* bounds and mark the workout
+  /// data_suspect, so trends and the coach never compare against them.
+  /// Values derived from a nulled one (efficiency from pace, HR costs from
+  /// an impossible average HR) go with it.
+  pub fn with_plausibility_bounds(mut self, kind: ActivityKind, average_hr: Option<i64>) -> Self {
+    let mut suspect = false;
+    let mut bound = |value: &mut Option<f64>, range: &std::ops::RangeInclusive<f64>| {
+      if value.is_some_and(|v| !range.contains(&v)) {
+        *value = None;
+        suspect = true;
+      }
+    };
+
+    match kind {
+      ActivityKind::Run => {
+        bound(&mut self.pace_min_per_km, &PLAUSIBLE_RUN_PACE);
+        bound(&mut self.efficiency, &PLAUSIBLE_RUN_EFFICIENCY);
+        if self.pace_min_per_km.is_none() {
+          self.efficiency = None;
+          self.efficiency_factor = None;
+        }
+      }
+      ActivityKind::Ride => {
+        bound(&mut self.speed_kmh, &PLAUSIBLE_RIDE_SPEED);
+        bound(&mut self.efficiency, &PLAUSIBLE_RIDE_EFFICIENCY);
+        if self.efficiency.is_none() {
+          self.efficiency_factor = None;
+        }
+      }
+      _ => {}
     }
-    if self.polarized_training {
-      flags.push((
-        "polarized_training".to_string(),
-        5,
-        "Good - >80% in Z1-Z2 aerobic zones".to_string(),
-      ));
+
+    // 0 is a missing reading, not an implausible one
+    if average_hr.is_some_and(|hr| hr > 0 && !PLAUSIBLE_AVERAGE_HR.contains(&hr)) {
+      suspect = true;
+      self.efficiency = None;
+      self.efficiency_factor = None;
+      self.cardiac_cost = None;
+      self.hr_zone = None;
     }
 
-    // Sort by priority (lowest number = highest priority)
-    flags.sort_by_key(|(_, priority, _)| *priority);
-    flags
+    self.data_suspect |= suspect;
+    self
   }
 
-  /// Convert flags to a list of string descriptions for the LLM (legacy format)
-  pub fn to_string_list(&self) -> Vec<String> {
-    self.to_prioritized_list()
-      .into_iter()
-      .map(|(name, _, desc)| format!("{}: {}", name, desc))
-      .collect()
+  /// Drop any NaN/Inf left by degenerate inputs so it never reaches the LLM
+  fn sanitized(self) -> Self {
+    Self {
+      pace_min_per_km: finite(self.pace_min_per_km),
+      speed_kmh: finite(self.speed_kmh),
+      kj: finite(self.kj),
+      rtss: finite(self.rtss),
+      efficiency: finite(self.efficiency),
+      efficiency_factor: finite(self.efficiency_factor),
+      cardiac_cost: finite(self.cardiac_cost),
+      hr_zone: self.hr_zone,
+      tss_by_zone: self.tss_by_zone.filter(ZoneTss::is_finite),
+      swim_pace_sec_per_100m: finite(self.swim_pace_sec_per_100m),
+      swim_zone: self.swim_zone,
+      hr_low_confidence: self.hr_low_confidence,
+      data_suspect: self.data_suspect,
+    }
   }
 }
 
-/// ---------------------------------------------------------------------------
-/// Context Package for LLM
-/// ---------------------------------------------------------------------------
+/// None for NaN/Inf (serde_json writes them as null, silently)
+pub(crate) fn finite(value: Option<f64>) -> Option<f64> {
+  value.filter(|v| v.is_finite())
+}
 
-use crate::progression::ProgressionSummary;
+/// 0.0 for NaN/Inf in fields that are always present
+fn finite_or_zero(value: f64) -> f64 {
+  if value.is_finite() {
+    value
+  } else {
+    0.0
+  }
+}
 
-/// The complete context package sent to Claude for analysis
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ContextPackage {
-  /// The specific workout being analyzed
-  pub workout: WorkoutContext,
+/// rTSS from an HR stream: the per-sample intensity^2 integrated over the
+/// workout. Samples are evenly spaced buckets, so the mean of intensity^2
+/// scaled by duration is the integral. Dropouts (HR <= 0) are ignored.
+/// Returns None when the stream has no usable samples.
+pub fn compute_rtss_from_stream(hr_samples: &[i64], lthr: i64, duration_min: f64) -> Option<f64> {
+  if lthr <= 0 || duration_min <= 0.0 {
+    return None;
+  }
 
-  /// Recent workouts of the same type for trend comparison
-  pub recent_same_type: Vec<RecentWorkoutSummary>,
+  let intensities_sq: Vec<f64> = hr_samples
+    .iter()
+    .filter(|hr| **hr > 0)
+    .map(|hr| (*hr as f64 / lthr as f64).powi(2))
+    .collect();
+  if intensities_sq.is_empty() {
+    return None;
+  }
 
-  /// Recent workouts of any type for weekly context
-  pub recent_all: Vec<RecentWorkoutSummary>,
+  let mean_sq = intensities_sq.iter().sum::<f64>() / intensities_sq.len() as f64;
+  Some(duration_min * mean_sq / 60.0 * 100.0)
+}
 
-  /// Fatigue metrics with TSB band
-  pub fatigue: FatigueContext,
+/// compute_rtss_from_stream split by the zone of each sample (seconds in
+/// zone x intensity^2), so the zones sum to the stream rTSS.
+/// Returns None when the stream has no usable samples.
+pub fn compute_tss_by_zone(
+  hr_samples: &[i64],
+  lthr: i64,
+  max_hr: i64,
+  duration_min: f64,
+  model: ZoneModel,
+  resting_hr: Option<i64>,
+) -> Option<ZoneTss> {
+  if lthr <= 0 || max_hr <= 0 || duration_min <= 0.0 {
+    return None;
+  }
 
-  /// Schedule and day awareness
-  pub schedule: ScheduleContext,
+  let valid: Vec<i64> = hr_samples.iter().copied().filter(|hr| *hr > 0).collect();
+  if valid.is_empty() {
+    return None;
+  }
 
-  /// Allowed durations based on TSB (for regulated dimensions)
-  pub allowed_durations: AllowedDurations,
+  let minutes_per_sample = duration_min / valid.len() as f64;
+  let mut by_zone = ZoneTss::default();
+  for hr in valid {
+    let intensity = hr as f64 / lthr as f64;
+    by_zone.add(HrZone::classify(hr, max_hr, model, resting_hr), minutes_per_sample * intensity.powi(2) / 60.0 * 100.0);
+  }
+  Some(by_zone)
+}
 
-  /// Active training flags
-  pub flags: Vec<String>,
+/// Average power to use for power-based metrics. Strava's estimate
+/// (`device_watts` false) never counts; a zero average counts only when a
+/// meter is confirmed or a power stream was recorded - otherwise it means
+/// "no power", not a zero-power ride.
+pub fn measured_power(average_watts: Option<f64>, device_watts: Option<bool>, has_power_stream: bool) -> Option<f64> {
+  match (average_watts, device_watts) {
+    (_, Some(false)) => None,
+    (Some(watts), Some(true)) => Some(watts),
+    (Some(watts), None) if watts > 0.0 || has_power_stream => Some(watts),
+    _ => None,
+  }
+}
 
-  /// User settings relevant to analysis
-  pub user: UserContext,
+/// Power-based TSS: (duration_min * (avg_watts / threshold)^2) / 60 * 100.
+/// Average rather than normalized power, so intervals read slightly low.
+pub fn compute_power_tss(
+  average_watts: Option<f64>,
+  threshold_watts: Option<i64>,
+  duration_min: Option<f64>,
+) -> Option<f64> {
+  match (average_watts, threshold_watts, duration_min) {
+    (Some(watts), Some(threshold), Some(dur)) if watts > 0.0 && threshold > 0 => {
+      let intensity = watts / threshold as f64;
+      Some((dur * intensity.powi(2)) / 60.0 * 100.0)
+    }
+    _ => None,
+  }
+}
 
-  /// Significance thresholds for detecting meaningful changes
-  pub thresholds: SignificanceThresholds,
+/// Session-RPE (RPE x minutes, in AU) to rTSS-equivalent: an hour at RPE 7
+/// (roughly threshold on the CR-10 scale) = 420 AU = 100 rTSS
+pub const SRPE_TO_RTSS: f64 = 100.0 / 420.0;
+
+/// Estimated rTSS from session RPE when there's no HR to measure load.
+/// Supplemental sessions (strength, yoga) stay out of endurance load.
+pub fn estimate_rtss_from_rpe(
+  activity_type: &str,
+  rpe: Option<i64>,
+  duration_seconds: Option<i64>,
+) -> Option<f64> {
+  if is_supplemental_activity(activity_type) {
+    return None;
+  }
+  match (rpe, duration_seconds) {
+    (Some(rpe), Some(secs)) if (1..=10).contains(&rpe) && secs > 0 => {
+      let session_rpe = rpe as f64 * secs as f64 / 60.0;
+      Some(session_rpe * SRPE_TO_RTSS)
+    }
+    _ => None,
+  }
+}
 
-  /// Oura sleep and recovery data (optional)
-  #[serde(skip_serializing_if = "Option::is_none")]
-  pub oura: Option<crate::oura::OuraContext>,
+/// RPE points between felt and measured effort before they count as diverging
+pub const RPE_MISMATCH_POINTS: i64 = 4;
+
+/// RPE (CR-10) a session usually feels like when spent mostly in this zone
+fn expected_rpe_for_zone(zone: HrZone) -> f64 {
+  match zone {
+    HrZone::Z1 => 2.0,
+    HrZone::Z2 => 3.0,
+    HrZone::Z3 => 5.0,
+    HrZone::Z4 => 7.0,
+    HrZone::Z5 => 9.0,
+  }
+}
 
-  /// Progression summary (computed by Rust, explains engine decisions to LLM)
-  #[serde(skip_serializing_if = "Option::is_none")]
-  pub progression_summary: Option<ProgressionSummary>,
+/// Signed gap between the athlete's RPE and the effort the device measured
+/// (positive = felt harder than HR/load says). Uses the HR zone, falling back
+/// to rTSS per hour on the session-RPE scale. Sessions without device data
+/// have RPE-estimated load, so there's nothing objective to compare against.
+pub fn rpe_load_gap(workout: &WorkoutSummary) -> Option<f64> {
+  let rpe = workout.rpe.filter(|r| (1..=10).contains(r))?;
+  if !workout.has_device_data {
+    return None;
+  }
+  let expected = match workout.hr_zone {
+    Some(zone) => expected_rpe_for_zone(zone),
+    None => {
+      let hours = workout.duration_seconds.filter(|s| *s > 0)? as f64 / 3600.0;
+      let rtss_per_hour = workout.rtss? / hours;
+      (rtss_per_hour / (60.0 * SRPE_TO_RTSS)).clamp(1.0, 10.0)
+    }
+  };
+  Some(rpe as f64 - expected)
 }
 
-/// Workout structure metadata (for structured workouts like TrainerRoad)
+/// ---------------------------------------------------------------------------
+/// Tier 2: Rolling Context Metrics
+/// ---------------------------------------------------------------------------
+
+/// A workout summary used for rolling calculations
+#[derive(Debug, Clone, Default)]
+pub struct WorkoutSummary {
+  pub started_at: chrono::DateTime<chrono::Utc>,
+  pub activity_type: String,
+  pub duration_seconds: Option<i64>,
+  pub rtss: Option<f64>,
+  pub hr_zone: Option<HrZone>,
+  /// Pace-based zone for swims (takes precedence over hr_zone)
+  pub swim_zone: Option<SwimZone>,
+  /// Recorded with HR or power (load is measured rather than estimated)
+  pub has_device_data: bool,
+  /// Session RPE (1-10) entered by the athlete
+  pub rpe: Option<i64>,
+}
+
+/// Training context computed from rolling windows
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct WorkoutStructure {
-  pub is_structured: bool,
-  #[serde(skip_serializing_if = "Option::is_none")]
-  pub block_type: Option<String>,
-  #[serde(skip_serializing_if = "Option::is_none")]
-  pub prescribed_target_watts: Option<f64>,
+pub struct TrainingContext {
+  /// Acute Training Load: 7-day rTSS sum
+  pub atl: Option<f64>,
+
+  /// Chronic Training Load: 42-day rTSS average
+  pub ctl: Option<f64>,
+
+  /// Training Stress Balance: CTL - ATL (form indicator)
+  pub tsb: Option<f64>,
+
+  /// Weekly volume in hours by modality
+  pub weekly_volume: WeeklyVolume,
+
+  /// Week-over-week volume change percentage
+  pub week_over_week_delta_pct: Option<f64>,
+
+  /// Week-over-week change with this week's volume projected over the
+  /// pattern's training days still to come (None before any have passed)
+  pub projected_week_over_week_delta_pct: Option<f64>,
+
+  /// Share of this week's expected training days already done (1.0 = complete)
+  pub week_elapsed_fraction: Option<f64>,
+
+  /// The same share over the rolling 7 days ATL covers (differs from
+  /// week_elapsed_fraction when a week start day is set)
+  #[serde(default)]
+  pub rolling_week_elapsed_fraction: Option<f64>,
+
+  /// Intensity distribution (zone percentages) over 7 days
+  pub intensity_distribution: IntensityDistribution,
+
+  /// 7-day low/moderate/high split against the polarization target
+  pub polarization_gap: Option<PolarizationGap>,
+
+  /// Longest session by modality in the long-session window (in minutes)
+  pub longest_session: LongestSession,
+
+  /// Consistency: workout count vs expected over 28 days (percentage)
+  pub consistency_pct: Option<f64>,
+
+  /// Number of workouts this week
+  pub workouts_this_week: i32,
+
+  /// Supplemental strength/mobility sessions this week (not counted as endurance load)
+  pub strength_sessions: i32,
+
+  /// Foster monotony: mean / SD of daily rTSS over 7 days (zero days included)
+  pub monotony: Option<f64>,
+
+  /// Foster strain: 7-day rTSS x monotony
+  pub strain: Option<f64>,
+
+  /// How much of ATL/CTL is device-measured vs estimated
+  pub load_confidence: LoadConfidence,
+
+  /// How much history CTL and TSB rest on
+  #[serde(default)]
+  pub data_maturity: DataMaturity,
+}
+
+/// History below which CTL is still filling up and TSB reads falsely fresh
+pub const MIN_HISTORY_DAYS_FOR_TSB: i64 = 28;
+
+/// Days of history behind the rolling loads. Only the 42-day CTL window is
+/// visible here, so a long layoff reads the same as a new account.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct DataMaturity {
+  /// Local days from the oldest workout in the CTL window through today
+  /// (0 with no workouts, at most 42)
+  pub history_days: i64,
+  /// Under MIN_HISTORY_DAYS_FOR_TSB: CTL is understated, so TSB is too high
+  pub tsb_low_confidence: bool,
 }
 
-impl Default for WorkoutStructure {
-  fn default() -> Self {
-    Self {
-      is_structured: false,
-      block_type: None,
-      prescribed_target_watts: None,
-    }
+impl DataMaturity {
+  pub fn from_history_days(history_days: i64) -> Self {
+    Self {
+      history_days,
+      tsb_low_confidence: history_days < MIN_HISTORY_DAYS_FOR_TSB,
+    }
+  }
+}
+
+/// Split of training load into device-measured (HR/power) and estimated portions
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct LoadConfidence {
+  pub atl_measured: f64,
+  pub atl_estimated: f64,
+  pub ctl_measured: f64,
+  pub ctl_estimated: f64,
+  /// Share of 42-day load that was measured (None with no load)
+  pub measured_pct: Option<f64>,
+}
+
+/// Weekly volume breakdown by modality
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct WeeklyVolume {
+  pub total_hrs: f64,
+  pub run_hrs: f64,
+  pub ride_hrs: f64,
+  pub swim_hrs: f64,
+  /// Supplemental strength/mobility work (weights, yoga, pilates...)
+  pub strength_hrs: f64,
+  pub hike_hrs: f64,
+  pub row_hrs: f64,
+  /// Everything not broken out above (walks, skiing...)
+  pub other_hrs: f64,
+}
+
+/// Intensity distribution by HR zone
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct IntensityDistribution {
+  pub z1_pct: f64,
+  pub z2_pct: f64,
+  pub z3_pct: f64,
+  pub z4_pct: f64,
+  pub z5_pct: f64,
+}
+
+/// Longest session by modality over `long_session_window_days`: local
+/// calendar days back from today, today being day 0. The long_run_gap and
+/// long_ride_gap flags look back over the same window, so a gap is flagged
+/// exactly when the longest session reported here falls short of the ceiling.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct LongestSession {
+  pub run_min: Option<f64>,
+  pub ride_min: Option<f64>,
+}
+
+impl TrainingContext {
+  /// Compute training context from a list of recent workouts
+  pub fn compute(workouts: &[WorkoutSummary], settings: &UserSettings) -> Self {
+    Self::compute_at(workouts, settings, chrono::Utc::now())
+  }
+
+  /// Compute training context as of `now`. Windows count local calendar
+  /// days, so a late-evening workout stays in the week it happened.
+  pub fn compute_at(
+    workouts: &[WorkoutSummary],
+    settings: &UserSettings,
+    now: chrono::DateTime<chrono::Utc>,
+  ) -> Self {
+    let days_ago = |w: &WorkoutSummary| settings.local_days_between(&w.started_at, &now);
+
+    // Cross-training load only counts when the athlete opts in
+    let workouts: Vec<WorkoutSummary> = workouts
+      .iter()
+      .cloned()
+      .map(|mut w| {
+        if !settings.include_other_load && canonical_activity(&w.activity_type) == ActivityKind::Other {
+          w.rtss = None;
+        }
+        w
+      })
+      .collect();
+    let workouts = workouts.as_slice();
+
+    // Filter workouts by time windows
+    let days_7: Vec<_> = workouts
+      .iter()
+      .filter(|w| days_ago(w) < 7)
+      .collect();
+
+    let days_28: Vec<_> = workouts
+      .iter()
+      .filter(|w| days_ago(w) < 28)
+      .collect();
+
+    // Whole local days (today = 0), so the windows only move at midnight
+    // and CTL is the same whenever it's computed during a day
+    let days_42: Vec<_> = workouts
+      .iter()
+      .filter(|w| days_ago(w) < CTL_DAYS)
+      .collect();
+
+    // ATL: 7-day rTSS sum
+    let atl = Self::compute_rtss_sum(&days_7, &settings.load_weights);
+
+    // CTL: 42-day rTSS average (daily average)
+    let ctl = Self::compute_rtss_avg(&days_42, CTL_DAYS, &settings.load_weights);
+
+    // TSB: CTL - ATL
+    let tsb = match (ctl, atl) {
+      (Some(c), Some(a)) => Some(c - a / 7.0), // Normalize ATL to daily
+      _ => None,
+    };
+
+    // This week: the calendar week so far when a week start day is set,
+    // else the last 7 days. Last week is the 7 days before it.
+    let today = settings.local_date(&now);
+    let days_into_week = settings.week_start_day.days_into_week(today);
+    let this_week: Vec<_> = workouts
+      .iter()
+      .filter(|w| days_ago(w) <= days_into_week)
+      .collect();
+
+    // Weekly volume
+    let weekly_volume = Self::compute_weekly_volume(&this_week);
+
+    // Week-over-week delta
+    let this_week_volume = weekly_volume.total_hrs;
+    let last_week_volume = Self::compute_weekly_volume(
+      &workouts
+        .iter()
+        .filter(|w| (days_into_week + 1..=days_into_week + 7).contains(&days_ago(w)))
+        .collect::<Vec<_>>(),
+    )
+    .total_hrs;
+
+    let delta_vs_last_week = |this_week: f64| {
+      if last_week_volume > 0.0 {
+        Some(((this_week - last_week_volume) / last_week_volume) * 100.0)
+      } else if this_week > 0.0 {
+        Some(100.0) // First week with data
+      } else {
+        None
+      }
+    };
+    let week_over_week_delta_pct = delta_vs_last_week(this_week_volume);
+
+    // Today's session may still be ahead; scale up so a morning snapshot
+    // doesn't read as a drop
+    let trained_today = this_week.iter().any(|w| days_ago(w) == 0);
+    let week_first_day = today - chrono::Duration::days(days_into_week);
+    let week_elapsed_fraction = Self::week_elapsed_fraction(week_first_day, today, trained_today);
+    let projected_week_over_week_delta_pct =
+      week_elapsed_fraction.and_then(|fraction| delta_vs_last_week(this_week_volume / fraction));
+    let rolling_week_elapsed_fraction =
+      Self::week_elapsed_fraction(today - chrono::Duration::days(6), today, trained_today);
+
+    // Intensity distribution
+    let intensity_distribution = Self::compute_intensity_distribution(&days_7);
+    let polarization_gap = PolarizationGap::compute(&intensity_distribution, &settings.polarization_target);
+
+    // Longest session over the long-session window (shared with the gap flags)
+    let long_window: Vec<_> = workouts
+      .iter()
+      .filter(|w| days_ago(w) < settings.long_session_window_days)
+      .collect();
+    let longest_session = Self::compute_longest_session(&long_window);
+
+    // Consistency: actual workouts vs expected
+    let expected_workouts_28d = settings.training_days_per_week as f64 * 4.0;
+    let actual_workouts_28d = days_28.len() as f64;
+    let consistency_pct = if expected_workouts_28d > 0.0 {
+      Some((actual_workouts_28d / expected_workouts_28d) * 100.0)
+    } else {
+      None
+    };
+
+    let workouts_this_week = this_week.len() as i32;
+    let strength_sessions = this_week
+      .iter()
+      .filter(|w| is_supplemental_activity(&w.activity_type))
+      .count() as i32;
+
+    let (monotony, strain) = compute_monotony_and_strain(workouts, settings, now);
+    let load_confidence = Self::compute_load_confidence(&days_7, &days_42, &settings.load_weights);
+    let history_days = days_42.iter().map(|w| days_ago(w) + 1).max().unwrap_or(0);
+    let data_maturity = DataMaturity::from_history_days(history_days);
+
+    Self {
+      atl,
+      ctl,
+      tsb,
+      weekly_volume,
+      week_over_week_delta_pct,
+      projected_week_over_week_delta_pct,
+      week_elapsed_fraction,
+      rolling_week_elapsed_fraction,
+      intensity_distribution,
+      polarization_gap,
+      longest_session,
+      consistency_pct,
+      workouts_this_week,
+      strength_sessions,
+      monotony,
+      strain,
+      load_confidence,
+      data_maturity,
+    }
+    .sanitized()
+  }
+
+  /// Fraction of the weekly pattern's training days in the week starting
+  /// `first_day` that are behind us. Today counts once something is logged.
+  fn week_elapsed_fraction(
+    first_day: chrono::NaiveDate,
+    today: chrono::NaiveDate,
+    trained_today: bool,
+  ) -> Option<f64> {
+    use chrono::Datelike;
+
+    let training_days: Vec<chrono::NaiveDate> = (0..7)
+      .map(|i| first_day + chrono::Duration::days(i))
+      .filter(|d| expected_session_type(d.weekday()) != "rest")
+      .collect();
+    if training_days.is_empty() {
+      return Some(1.0);
+    }
+    let elapsed = training_days
+      .iter()
+      .filter(|d| **d < today || (**d == today && trained_today))
+      .count();
+    if elapsed == 0 {
+      return None;
+    }
+    Some(elapsed as f64 / training_days.len() as f64)
+  }
+
+  /// Final pass: NaN/Inf become None (or 0.0 for always-present totals)
+  fn sanitized(self) -> Self {
+    let volume = self.weekly_volume;
+    let dist = self.intensity_distribution;
+    let confidence = self.load_confidence;
+
+    Self {
+      atl: finite(self.atl),
+      ctl: finite(self.ctl),
+      tsb: finite(self.tsb),
+      weekly_volume: WeeklyVolume {
+        total_hrs: finite_or_zero(volume.total_hrs),
+        run_hrs: finite_or_zero(volume.run_hrs),
+        ride_hrs: finite_or_zero(volume.ride_hrs),
+        swim_hrs: finite_or_zero(volume.swim_hrs),
+        strength_hrs: finite_or_zero(volume.strength_hrs),
+        hike_hrs: finite_or_zero(volume.hike_hrs),
+        row_hrs: finite_or_zero(volume.row_hrs),
+        other_hrs: finite_or_zero(volume.other_hrs),
+      },
+      week_over_week_delta_pct: finite(self.week_over_week_delta_pct),
+      projected_week_over_week_delta_pct: finite(self.projected_week_over_week_delta_pct),
+      week_elapsed_fraction: finite(self.week_elapsed_fraction),
+      rolling_week_elapsed_fraction: finite(self.rolling_week_elapsed_fraction),
+      intensity_distribution: IntensityDistribution {
+        z1_pct: finite_or_zero(dist.z1_pct),
+        z2_pct: finite_or_zero(dist.z2_pct),
+        z3_pct: finite_or_zero(dist.z3_pct),
+        z4_pct: finite_or_zero(dist.z4_pct),
+        z5_pct: finite_or_zero(dist.z5_pct),
+      },
+      polarization_gap: self.polarization_gap,
+      longest_session: LongestSession {
+        run_min: finite(self.longest_session.run_min),
+        ride_min: finite(self.longest_session.ride_min),
+      },
+      consistency_pct: finite(self.consistency_pct),
+      monotony: finite(self.monotony),
+      strain: finite(self.strain),
+      load_confidence: LoadConfidence {
+        atl_measured: finite_or_zero(confidence.atl_measured),
+        atl_estimated: finite_or_zero(confidence.atl_estimated),
+        ctl_measured: finite_or_zero(confidence.ctl_measured),
+        ctl_estimated: finite_or_zero(confidence.ctl_estimated),
+        measured_pct: finite(confidence.measured_pct),
+      },
+      workouts_this_week: self.workouts_this_week,
+      strength_sessions: self.strength_sessions,
+      data_maturity: self.data_maturity,
+    }
+  }
+
+  fn compute_load_confidence(
+    days_7: &[&WorkoutSummary],
+    days_42: &[&WorkoutSummary],
+    weights: &LoadWeights,
+  ) -> LoadConfidence {
+    let split = |workouts: &[&WorkoutSummary]| -> (f64, f64) {
+      workouts.iter().fold((0.0, 0.0), |(measured, estimated), w| {
+        let load = weights.weighted_rtss(w).unwrap_or(0.0);
+        if w.has_device_data {
+          (measured + load, estimated)
+        } else {
+          (measured, estimated + load)
+        }
+      })
+    };
+
+    let (atl_measured, atl_estimated) = split(days_7);
+    let (measured_42, estimated_42) = split(days_42);
+    let total_42 = measured_42 + estimated_42;
+
+    LoadConfidence {
+      atl_measured,
+      atl_estimated,
+      ctl_measured: measured_42 / 42.0,
+      ctl_estimated: estimated_42 / 42.0,
+      measured_pct: if total_42 > 0.0 {
+        Some(measured_42 / total_42 * 100.0)
+      } else {
+        None
+      },
+    }
+  }
+
+  fn compute_rtss_sum(workouts: &[&WorkoutSummary], weights: &LoadWeights) -> Option<f64> {
+    let sum: f64 = workouts.iter().filter_map(|w| weights.weighted_rtss(w)).sum();
+    if sum > 0.0 {
+      Some(sum)
+    } else {
+      None
+    }
+  }
+
+  fn compute_rtss_avg(workouts: &[&WorkoutSummary], days: i64, weights: &LoadWeights) -> Option<f64> {
+    let sum: f64 = workouts.iter().filter_map(|w| weights.weighted_rtss(w)).sum();
+    if sum > 0.0 {
+      Some(sum / days as f64)
+    } else {
+      None
+    }
+  }
+
+  fn compute_weekly_volume(workouts: &[&WorkoutSummary]) -> WeeklyVolume {
+    let mut volume = WeeklyVolume::default();
+
+    for w in workouts {
+      let hrs = w.duration_seconds.map(|s| s as f64 / 3600.0).unwrap_or(0.0);
+      volume.total_hrs += hrs;
+
+      let bucket = match canonical_activity(&w.activity_type) {
+        ActivityKind::Run => &mut volume.run_hrs,
+        ActivityKind::Ride => &mut volume.ride_hrs,
+        ActivityKind::Swim => &mut volume.swim_hrs,
+        ActivityKind::Other => {
+          let normalized = normalize_activity_type(&w.activity_type);
+          if is_supplemental_activity(&w.activity_type) {
+            &mut volume.strength_hrs
+          } else if HIKE_ALIASES.contains(&normalized.as_str()) {
+            &mut volume.hike_hrs
+          } else if ROW_ALIASES.contains(&normalized.as_str()) {
+            &mut volume.row_hrs
+          } else {
+            &mut volume.other_hrs
+          }
+        }
+      };
+      *bucket += hrs;
+    }
+
+    volume
+  }
+
+  fn compute_intensity_distribution(workouts: &[&WorkoutSummary]) -> IntensityDistribution {
+    let mut dist = IntensityDistribution::default();
+    let mut total_duration = 0.0;
+
+    // Sum duration by zone
+    let mut z1_duration = 0.0;
+    let mut z2_duration = 0.0;
+    let mut z3_duration = 0.0;
+    let mut z4_duration = 0.0;
+    let mut z5_duration = 0.0;
+
+    for w in workouts {
+      // Swims count by pace zone: wrist HR in the water is unreliable
+      let zone = w.swim_zone.map(|z| z.hr_equivalent()).or(w.hr_zone);
+      if let (Some(zone), Some(dur)) = (zone, w.duration_seconds) {
+        let dur_min = dur as f64 / 60.0;
+        total_duration += dur_min;
+        match zone {
+          HrZone::Z1 => z1_duration += dur_min,
+          HrZone::Z2 => z2_duration += dur_min,
+          HrZone::Z3 => z3_duration += dur_min,
+          HrZone::Z4 => z4_duration += dur_min,
+          HrZone::Z5 => z5_duration += dur_min,
+        }
+      }
+    }
+
+    if total_duration > 0.0 {
+      dist.z1_pct = (z1_duration / total_duration) * 100.0;
+      dist.z2_pct = (z2_duration / total_duration) * 100.0;
+      dist.z3_pct = (z3_duration / total_duration) * 100.0;
+      dist.z4_pct = (z4_duration / total_duration) * 100.0;
+      dist.z5_pct = (z5_duration / total_duration) * 100.0;
+    }
+
+    dist
+  }
+
+  fn compute_longest_session(workouts: &[&WorkoutSummary]) -> LongestSession {
+    let mut longest = LongestSession::default();
+
+    for w in workouts {
+      let dur_min = w.duration_seconds.map(|s| s as f64 / 60.0);
+
+      match canonical_activity(&w.activity_type) {
+        ActivityKind::Run => {
+          if let Some(d) = dur_min {
+            longest.run_min = Some(longest.run_min.map_or(d, |curr| curr.max(d)));
+          }
+        }
+        ActivityKind::Ride => {
+          if let Some(d) = dur_min {
+            longest.ride_min = Some(longest.ride_min.map_or(d, |curr| curr.max(d)));
+          }
+        }
+        _ => {}
+      }
+    }
+
+    longest
+  }
+}
+
+/// Cap for monotony when daily load is perfectly uniform (SD = 0)
+const MAX_MONOTONY: f64 = 10.0;
+
+/// Foster's monotony (mean daily load / SD of daily load) and strain
+/// (weekly load x monotony) over the last 7 local calendar days.
+/// Rest days count as zero-load days. Returns (None, None) with no load.
+pub fn compute_monotony_and_strain(
+  workouts: &[WorkoutSummary],
+  settings: &UserSettings,
+  now: chrono::DateTime<chrono::Utc>,
+) -> (Option<f64>, Option<f64>) {
+  let mut daily_load = [0.0_f64; 7];
+
+  for w in workouts {
+    let days_ago = settings.local_days_between(&w.started_at, &now);
+    if (0..7).contains(&days_ago) {
+      daily_load[days_ago as usize] += settings.load_weights.weighted_rtss(w).unwrap_or(0.0);
+    }
+  }
+
+  let weekly_load: f64 = daily_load.iter().sum();
+  if weekly_load <= 0.0 {
+    return (None, None);
+  }
+
+  let mean = weekly_load / 7.0;
+  let variance = daily_load.iter().map(|d| (d - mean).powi(2)).sum::<f64>() / 7.0;
+  let sd = variance.sqrt();
+
+  let monotony = if sd > f64::EPSILON {
+    (mean / sd).min(MAX_MONOTONY)
+  } else {
+    MAX_MONOTONY
+  };
+
+  (Some(monotony), Some(weekly_load * monotony))
+}
+
+/// ---------------------------------------------------------------------------
+/// Tier 3: Training Flags (Boolean Alerts)
+/// ---------------------------------------------------------------------------
+
+/// Tunable thresholds for the volume flags. Defaults match the original
+/// hard-coded values; return-to-training athletes may want tighter ones.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FlagThresholds {
+  /// Flag volume_spike when weekly load exceeds chronic weekly load x this
+  pub volume_spike_ratio: f64,
+  /// Flag volume_drop when weekly load falls below chronic weekly load x this
+  pub volume_drop_ratio: f64,
+  /// Chronic weekly load needed before a drop is meaningful
+  pub volume_drop_min_chronic: f64,
+}
+
+impl Default for FlagThresholds {
+  fn default() -> Self {
+    Self {
+      volume_spike_ratio: 1.2,
+      volume_drop_ratio: 0.7,
+      volume_drop_min_chronic: 50.0,
+    }
+  }
+}
+
+impl FlagThresholds {
+  /// Reject thresholds that would make the flags meaningless
+  pub fn validate(&self) -> Result<(), String> {
+    if self.volume_spike_ratio <= 1.0 {
+      return Err(format!(
+        "volume_spike_ratio must be above 1.0, got {}",
+        self.volume_spike_ratio
+      ));
+    }
+    if self.volume_drop_ratio <= 0.0 || self.volume_drop_ratio >= 1.0 {
+      return Err(format!(
+        "volume_drop_ratio must be between 0 and 1, got {}",
+        self.volume_drop_ratio
+      ));
+    }
+    if self.volume_drop_min_chronic < 0.0 {
+      return Err(format!(
+        "volume_drop_min_chronic must not be negative, got {}",
+        self.volume_drop_min_chronic
+      ));
+    }
+    Ok(())
+  }
+}
+
+/// Background analysis of newly synced workouts. Off by default; the cap
+/// counts input + output tokens across every analysis that day.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AutoAnalyzeSettings {
+  pub enabled: bool,
+  pub daily_token_cap: i64,
+}
+
+impl Default for AutoAnalyzeSettings {
+  fn default() -> Self {
+    Self {
+      enabled: false,
+      daily_token_cap: 50_000,
+    }
+  }
+}
+
+/// Upper bound on the daily auto-analysis budget
+pub const MAX_DAILY_TOKEN_CAP: i64 = 2_000_000;
+
+impl AutoAnalyzeSettings {
+  pub fn validate(&self) -> Result<(), String> {
+    if !(0..=MAX_DAILY_TOKEN_CAP).contains(&self.daily_token_cap) {
+      return Err(format!(
+        "daily_token_cap must be between 0 and {}, got {}",
+        MAX_DAILY_TOKEN_CAP, self.daily_token_cap
+      ));
+    }
+    Ok(())
+  }
+}
+
+/// How much one rTSS point of each sport counts toward ATL/CTL. A point of
+/// cycling stress carries less impact loading than running, so coaches
+/// often down-weight it (e.g. ride 0.7). Defaults to 1.0 everywhere.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LoadWeights {
+  pub run: f64,
+  pub ride: f64,
+  pub swim: f64,
+  pub other: f64,
+}
+
+impl Default for LoadWeights {
+  fn default() -> Self {
+    Self {
+      run: 1.0,
+      ride: 1.0,
+      swim: 1.0,
+      other: 1.0,
+    }
+  }
+}
+
+/// Valid load weights: a tenth to double
+pub const LOAD_WEIGHT_RANGE: std::ops::RangeInclusive<f64> = 0.1..=2.0;
+
+impl LoadWeights {
+  pub fn for_activity(&self, activity_type: &str) -> f64 {
+    match canonical_activity(activity_type) {
+      ActivityKind::Run => self.run,
+      ActivityKind::Ride => self.ride,
+      ActivityKind::Swim => self.swim,
+      ActivityKind::Other => self.other,
+    }
+  }
+
+  /// A workout's rTSS as it counts toward load
+  pub fn weighted_rtss(&self, workout: &WorkoutSummary) -> Option<f64> {
+    workout.rtss.map(|rtss| rtss * self.for_activity(&workout.activity_type))
+  }
+
+  pub fn validate(&self) -> Result<(), String> {
+    for (label, weight) in [("run", self.run), ("ride", self.ride), ("swim", self.swim), ("other", self.other)] {
+      if !LOAD_WEIGHT_RANGE.contains(&weight) {
+        return Err(format!("{} load weight must be between 0.1 and 2.0, got {}", label, weight));
+      }
+    }
+    Ok(())
+  }
+}
+
+/// Time split across three intensity bands, in percent: low = Z1-Z2,
+/// moderate = Z3, high = Z4-Z5. As a target, defaults to 80/15/5.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ZoneSplit {
+  pub low_pct: f64,
+  pub moderate_pct: f64,
+  pub high_pct: f64,
+}
+
+impl Default for ZoneSplit {
+  fn default() -> Self {
+    Self {
+      low_pct: 80.0,
+      moderate_pct: 15.0,
+      high_pct: 5.0,
+    }
+  }
+}
+
+impl ZoneSplit {
+  /// Collapse a five-zone distribution into low/moderate/high
+  pub fn from_distribution(dist: &IntensityDistribution) -> Self {
+    Self {
+      low_pct: dist.z1_pct + dist.z2_pct,
+      moderate_pct: dist.z3_pct,
+      high_pct: dist.z4_pct + dist.z5_pct,
+    }
+  }
+
+  /// Reject targets that aren't a split of 100%
+  pub fn validate(&self) -> Result<(), String> {
+    for (label, pct) in [
+      ("low_pct", self.low_pct),
+      ("moderate_pct", self.moderate_pct),
+      ("high_pct", self.high_pct),
+    ] {
+      if !(0.0..=100.0).contains(&pct) {
+        return Err(format!("{} must be between 0 and 100, got {}", label, pct));
+      }
+    }
+    let total = self.low_pct + self.moderate_pct + self.high_pct;
+    if (total - 100.0).abs() > 0.5 {
+      return Err(format!("Polarization target must add up to 100%, got {}", total));
+    }
+    Ok(())
+  }
+
+  /// "62/25/13"
+  pub fn label(&self) -> String {
+    format!("{:.0}/{:.0}/{:.0}", self.low_pct, self.moderate_pct, self.high_pct)
+  }
+}
+
+/// Band gaps within this many points count as on target
+const POLARIZATION_TOLERANCE_PCT: f64 = 5.0;
+
+/// Actual intensity split against the athlete's target
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PolarizationGap {
+  pub actual: ZoneSplit,
+  pub target: ZoneSplit,
+  /// actual - target per band (positive = more than planned)
+  pub gap: ZoneSplit,
+  /// e.g. "62/25/13 vs 80/15/5 target: too much Z3"
+  pub summary: String,
+}
+
+impl PolarizationGap {
+  /// None when no time in the window has an HR zone
+  pub fn compute(dist: &IntensityDistribution, target: &ZoneSplit) -> Option<Self> {
+    let actual = ZoneSplit::from_distribution(dist);
+    if actual.low_pct + actual.moderate_pct + actual.high_pct <= 0.0 {
+      return None;
+    }
+    let gap = ZoneSplit {
+      low_pct: actual.low_pct - target.low_pct,
+      moderate_pct: actual.moderate_pct - target.moderate_pct,
+      high_pct: actual.high_pct - target.high_pct,
+    };
+
+    // The band most over target is what to pull back on
+    let (excess, band) = [
+      (gap.low_pct, "too much easy volume, not enough intensity"),
+      (gap.moderate_pct, "too much Z3"),
+      (gap.high_pct, "too much Z4-Z5"),
+    ]
+    .into_iter()
+    .fold((f64::MIN, ""), |best, (g, band)| if g > best.0 { (g, band) } else { best });
+    let verdict = if excess < POLARIZATION_TOLERANCE_PCT { "on target" } else { band };
+
+    Some(Self {
+      actual,
+      target: *target,
+      gap,
+      summary: format!("{} vs {} target: {}", actual.label(), target.label(), verdict),
+    })
+  }
+}
+
+/// Selection of recent workouts used as trend context for analysis.
+/// Count mode takes the newest N; setting `window_days` switches to every
+/// workout in the days before the analyzed one, so context tracks time
+/// rather than training frequency.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RecentWorkoutWindow {
+  /// Same-type workouts to include in count mode
+  pub same_type_count: i64,
+  /// Workouts of any type to include in count mode
+  pub all_type_count: i64,
+  /// Time-bounded mode: include all workouts from this many days back
+  pub window_days: Option<i64>,
+}
+
+impl Default for RecentWorkoutWindow {
+  fn default() -> Self {
+    Self {
+      same_type_count: 5,
+      all_type_count: 7,
+      window_days: None,
+    }
+  }
+}
+
+/// Upper bound on recent-workout counts (keeps prompt size sane)
+pub const MAX_RECENT_WORKOUT_COUNT: i64 = 30;
+
+/// Upper bound on the time-bounded window
+pub const MAX_RECENT_WINDOW_DAYS: i64 = 90;
+
+impl RecentWorkoutWindow {
+  /// Reject counts and windows outside the supported range
+  pub fn validate(&self) -> Result<(), String> {
+    for (label, count) in [
+      ("same_type_count", self.same_type_count),
+      ("all_type_count", self.all_type_count),
+    ] {
+      if !(0..=MAX_RECENT_WORKOUT_COUNT).contains(&count) {
+        return Err(format!(
+          "{} must be between 0 and {}, got {}",
+          label, MAX_RECENT_WORKOUT_COUNT, count
+        ));
+      }
+    }
+    if let Some(days) = self.window_days {
+      if !(1..=MAX_RECENT_WINDOW_DAYS).contains(&days) {
+        return Err(format!(
+          "window_days must be between 1 and {}, got {}",
+          MAX_RECENT_WINDOW_DAYS, days
+        ));
+      }
+    }
+    Ok(())
+  }
+}
+
+/// Training flags that indicate potential issues or achievements
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct TrainingFlags {
+  /// Volume > 1.2x chronic average
+  pub volume_spike: bool,
+
+  /// Volume < 0.7x chronic average
+  pub volume_drop: bool,
+
+  /// TSB at or below the high-fatigue band edge (default -20)
+  pub high_fatigue: bool,
+
+  /// TSB in the PEAK_FORM_SPAN above the fresh band edge (default +5 to +15)
+  pub peak_form: bool,
+
+  /// Runs in the long-session window, none reaching the long_run ceiling
+  pub long_run_gap: bool,
+
+  /// Rides in the long-session window, none reaching the z2_ride ceiling
+  pub long_ride_gap: bool,
+
+  /// Intensity predominantly Z3+ (> 40%)
+  pub intensity_heavy: bool,
+
+  /// Predominantly Z1-Z2 (> 80%) - good aerobic base
+  pub polarized_training: bool,
+
+  /// Foster monotony > 2.0 (same load every day, overtraining risk)
+  pub high_monotony: bool,
+
+  /// A session in the last 7 days where RPE and HR/load diverged by
+  /// RPE_MISMATCH_POINTS or more (high RPE at low HR: fatigue or illness)
+  #[serde(default)]
+  pub rpe_hr_mismatch: bool,
+
+  /// Current training phase; shifts flag priorities (see with_phase)
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub phase: Option<TrainingPhase>,
+
+  /// Bands the fatigue and form flags were judged against (for their descriptions)
+  #[serde(skip)]
+  pub tsb_bands: TsbBands,
+}
+
+/// Peak form runs from the fresh band edge up this many TSB points; fresher
+/// than that is detraining
+const PEAK_FORM_SPAN: f64 = 10.0;
+
+impl TrainingFlags {
+  /// Compute training flags from workout history, context, and progression dimensions
+  pub fn compute(
+    workouts: &[WorkoutSummary],
+    context: &TrainingContext,
+    settings: &UserSettings,
+    dimensions: &[crate::progression::ProgressionDimension],
+  ) -> Self {
+    Self::compute_at(workouts, context, settings, dimensions, chrono::Utc::now())
+  }
+
+  /// Compute training flags as of `now` (pair with `TrainingContext::compute_at`)
+  pub fn compute_at(
+    workouts: &[WorkoutSummary],
+    context: &TrainingContext,
+    settings: &UserSettings,
+    dimensions: &[crate::progression::ProgressionDimension],
+    now: chrono::DateTime<chrono::Utc>,
+  ) -> Self {
+    let mut flags = TrainingFlags { tsb_bands: settings.tsb_bands.clone(), ..Default::default() };
+    let thresholds = &settings.flag_thresholds;
+
+    // Volume spike: current week > spike ratio x chronic (use CTL as proxy for chronic load)
+    // We approximate chronic volume from CTL and compare to current week
+    if let (Some(atl), Some(ctl)) = (context.atl, context.ctl) {
+      // If weekly load (ATL) is much higher than chronic daily average * 7
+      let chronic_weekly = ctl * 7.0;
+      if atl > chronic_weekly * thresholds.volume_spike_ratio {
+        flags.volume_spike = true;
+      }
+      // A drop is judged on the projected week: sessions still to come aren't missing.
+      // ATL is a rolling 7-day sum, so project it over that window, not the calendar week.
+      let projected_atl = atl / context.rolling_week_elapsed_fraction.unwrap_or(1.0);
+      if projected_atl < chronic_weekly * thresholds.volume_drop_ratio
+        && chronic_weekly > thresholds.volume_drop_min_chronic
+      {
+        // Only flag if there's meaningful chronic load
+        flags.volume_drop = true;
+      }
+    }
+
+    // High fatigue and peak form follow the athlete's TSB bands
+    if let Some(tsb) = context.tsb {
+      let bands = &settings.tsb_bands;
+      if bands.band(Some(tsb)) == "high_fatigue" {
+        flags.high_fatigue = true;
+      }
+      if tsb > bands.tsb_fresh_above && tsb < bands.tsb_fresh_above + PEAK_FORM_SPAN {
+        flags.peak_form = true;
+      }
+    }
+
+    // Long run gap: no run >= ceiling in the long-session window
+    // Get the long_run ceiling from dimensions, default to 90 min if not set
+    let long_run_ceiling_min = dimensions
+      .iter()
+      .find(|d| d.name == "long_run")
+      .and_then(|d| d.ceiling_value.parse::<f64>().ok())
+      .unwrap_or(90.0);
+    let long_run_threshold_secs = (long_run_ceiling_min * 60.0) as i64;
+
+    // Same window (and day counting) as TrainingContext::longest_session
+    let long_window: Vec<_> = workouts
+      .iter()
+      .filter(|w| settings.local_days_between(&w.started_at, &now) < settings.long_session_window_days)
+      .collect();
+
+    let has_long_run = long_window.iter().any(|w| {
+      canonical_activity(&w.activity_type) == ActivityKind::Run
+        && w.duration_seconds.map_or(false, |d| d >= long_run_threshold_secs)
+    });
+    if !has_long_run
+      && long_window.iter().any(|w| canonical_activity(&w.activity_type) == ActivityKind::Run)
+    {
+      flags.long_run_gap = true;
+    }
+
+    // Long ride gap: no ride >= ceiling in the long-session window
+    // Get the z2_ride ceiling from dimensions, default to 60 min if not set
+    let z2_ride_ceiling_min = dimensions
+      .iter()
+      .find(|d| d.name == "z2_ride")
+      .and_then(|d| d.ceiling_value.parse::<f64>().ok())
+      .unwrap_or(60.0);
+    let long_ride_threshold_secs = (z2_ride_ceiling_min * 60.0) as i64;
+
+    let has_long_ride = long_window.iter().any(|w| {
+      canonical_activity(&w.activity_type) == ActivityKind::Ride
+        && w.duration_seconds.map_or(false, |d| d >= long_ride_threshold_secs)
+    });
+    if !has_long_ride
+      && long_window.iter().any(|w| canonical_activity(&w.activity_type) == ActivityKind::Ride)
+    {
+      flags.long_ride_gap = true;
+    }
+
+    // Intensity flags from distribution
+    let high_intensity_pct =
+      context.intensity_distribution.z3_pct
+        + context.intensity_distribution.z4_pct
+        + context.intensity_distribution.z5_pct;
+    let low_intensity_pct = context.intensity_distribution.z1_pct + context.intensity_distribution.z2_pct;
+
+    if high_intensity_pct > 40.0 {
+      flags.intensity_heavy = true;
+    }
+    if low_intensity_pct > 80.0 {
+      flags.polarized_training = true;
+    }
+
+    // High monotony: too little day-to-day variation in load
+    if context.monotony.map_or(false, |m| m > 2.0) {
+      flags.high_monotony = true;
+    }
+
+    // Subjective vs objective effort on a recent session
+    flags.rpe_hr_mismatch = workouts
+      .iter()
+      .filter(|w| settings.local_days_between(&w.started_at, &now) < 7)
+      .filter_map(rpe_load_gap)
+      .any(|gap| gap.abs() >= RPE_MISMATCH_POINTS as f64);
+
+    flags
+  }
+
+  /// Convert flags to a prioritized list with (flag_name, priority, description)
+  /// Priority: 1 = highest, 5 = lowest
+  pub fn to_prioritized_list(&self) -> Vec<(String, u8, String)> {
+    let mut flags = Vec::new();
+
+    if self.high_fatigue {
+      flags.push((
+        "high_fatigue".to_string(),
+        1,
+        format!("TSB indicates accumulated fatigue (<= {})", self.tsb_bands.tsb_high_below),
+      ));
+    }
+    if self.volume_spike {
+      flags.push((
+        "volume_spike".to_string(),
+        2,
+        "Training volume significantly above chronic average".to_string(),
+      ));
+    }
+    if self.high_monotony {
+      flags.push((
+        "high_monotony".to_string(),
+        2,
+        "Daily load too uniform (Foster monotony > 2.0)".to_string(),
+      ));
+    }
+    if self.rpe_hr_mismatch {
+      flags.push((
+        "rpe_hr_mismatch".to_string(),
+        2,
+        "Perceived effort and HR/load diverged on a recent session - ask how the athlete is feeling".to_string(),
+      ));
+    }
+    if self.intensity_heavy {
+      flags.push((
+        "intensity_heavy".to_string(),
+        3,
+        ">40% of training in Z3+".to_string(),
+      ));
+    }
+    if self.long_run_gap {
+      flags.push((
+        "long_run_gap".to_string(),
+        4,
+        "No run at ceiling duration in 3 weeks".to_string(),
+      ));
+    }
+    if self.long_ride_gap {
+      flags.push((
+        "long_ride_gap".to_string(),
+        4,
+        "No ride at ceiling duration in 3 weeks".to_string(),
+      ));
+    }
+    if self.volume_drop {
+      flags.push((
+        "volume_drop".to_string(),
+        5,
+        "Training volume significantly below chronic average".to_string(),
+      ));
+    }
+    if self.peak_form {
+      flags.push((
+        "peak_form".to_string(),
+        5,
+        format!(
+          "TSB indicates good racing form ({:+} to {:+})",
+          self.tsb_bands.tsb_fresh_above,
+          self.tsb_bands.tsb_fresh_above + PEAK_FORM_SPAN
+        ),
+      ));
+    }
+    if self.polarized_training {
+      flags.push((
+        "polarized_training".to_string(),
+        5,
+        "Good - >80% in Z1-Z2 aerobic zones".to_string(),
+      ));
+    }
+
+    // Phase expectations move flags up or down before sorting
+    if let Some(phase) = self.phase {
+      for (name, priority, desc) in flags.iter_mut() {
+        if let Some((adjusted, note)) = phase.adjust_flag(name) {
+          *priority = adjusted;
+          desc.push_str(&format!(" ({} during {})", note, phase.as_str()));
+        }
+      }
+    }
+
+    // Sort by priority (lowest number = highest priority)
+    flags.sort_by_key(|(_, priority, _)| *priority);
+    flags
+  }
+
+  /// Set the training phase used to weigh flag priorities
+  pub fn with_phase(mut self, phase: Option<TrainingPhase>) -> Self {
+    self.phase = phase;
+    self
+  }
+
+  /// Convert flags to a list of string descriptions for the LLM (legacy format)
+  pub fn to_string_list(&self) -> Vec<String> {
+    self.to_prioritized_list()
+      .into_iter()
+      .map(|(name, _, desc)| format!("{}: {}", name, desc))
+      .collect()
+  }
+}
+
+/// ---------------------------------------------------------------------------
+/// Training Phase (periodization)
+/// ---------------------------------------------------------------------------
+
+/// Final weeks before the goal are treated as Peak (sharpen + taper)
+const PEAK_WEEKS_BEFORE_GOAL: i64 = 2;
+/// Weeks before the goal (beyond Peak) treated as Build
+const BUILD_WEEKS_BEFORE_GOAL: i64 = 10;
+/// Days after the goal event treated as Recovery
+const RECOVERY_DAYS_AFTER_GOAL: i64 = 14;
+
+/// Periodization block the athlete is in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TrainingPhase {
+  Base,
+  Build,
+  Peak,
+  Recovery,
+}
+
+impl TrainingPhase {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      TrainingPhase::Base => "base",
+      TrainingPhase::Build => "build",
+      TrainingPhase::Peak => "peak",
+      TrainingPhase::Recovery => "recovery",
+    }
+  }
+
+  pub fn parse(value: &str) -> Option<Self> {
+    match value.trim().to_lowercase().as_str() {
+      "base" => Some(TrainingPhase::Base),
+      "build" => Some(TrainingPhase::Build),
+      "peak" => Some(TrainingPhase::Peak),
+      "recovery" => Some(TrainingPhase::Recovery),
+      _ => None,
+    }
+  }
+
+  /// Phase implied by distance to the goal date (None without a goal, or
+  /// once recovery from it is over)
+  pub fn from_goal_proximity(settings: &UserSettings, today: chrono::NaiveDate) -> Option<Self> {
+    let countdown = GoalCountdown::from_settings(settings, today)?;
+    match countdown.days_remaining {
+      d if d < -RECOVERY_DAYS_AFTER_GOAL => None,
+      d if d < 0 => Some(TrainingPhase::Recovery),
+      d if d <= PEAK_WEEKS_BEFORE_GOAL * 7 => Some(TrainingPhase::Peak),
+      d if d <= BUILD_WEEKS_BEFORE_GOAL * 7 => Some(TrainingPhase::Build),
+      _ => Some(TrainingPhase::Base),
+    }
+  }
+
+  /// Phase-specific priority for a flag, with a short reason.
+  /// None = the flag keeps its default priority.
+  fn adjust_flag(&self, flag: &str) -> Option<(u8, &'static str)> {
+    match (self, flag) {
+      // Planned overload is the point of a build block
+      (TrainingPhase::Build, "volume_spike") => Some((4, "expected")),
+      // Load should be coming down, not up
+      (TrainingPhase::Peak, "volume_spike") => Some((1, "alarming")),
+      (TrainingPhase::Recovery, "volume_spike") => Some((1, "alarming")),
+      (TrainingPhase::Recovery, "intensity_heavy") => Some((2, "unexpected")),
+      // Base is aerobic; intensity creep matters more
+      (TrainingPhase::Base, "intensity_heavy") => Some((2, "unexpected")),
+      (TrainingPhase::Recovery, "volume_drop") => Some((5, "expected")),
+      (TrainingPhase::Peak, "volume_drop") => Some((5, "expected")),
+      (TrainingPhase::Recovery, "long_run_gap") | (TrainingPhase::Recovery, "long_ride_gap") => {
+        Some((5, "expected"))
+      }
+      _ => None,
+    }
+  }
+}
+
+/// Where the current phase came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PhaseSource {
+  /// A phase block the athlete set explicitly
+  Planned,
+  /// Inferred from weeks to the goal date
+  GoalProximity,
+}
+
+/// The phase in effect on a date, and its date range when planned
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrainingPhaseContext {
+  pub phase: TrainingPhase,
+  pub source: PhaseSource,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub start_date: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub end_date: Option<String>,
+}
+
+/// ---------------------------------------------------------------------------
+/// Context Package for LLM
+/// ---------------------------------------------------------------------------
+
+use crate::progression::ProgressionSummary;
+
+/// The complete context package sent to Claude for analysis
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContextPackage {
+  /// The specific workout being analyzed
+  pub workout: WorkoutContext,
+
+  /// Recent workouts of the same type for trend comparison
+  pub recent_same_type: Vec<RecentWorkoutSummary>,
+
+  /// Recent workouts of any type for weekly context
+  pub recent_all: Vec<RecentWorkoutSummary>,
+
+  /// Fatigue metrics with TSB band
+  pub fatigue: FatigueContext,
+
+  /// Schedule and day awareness
+  pub schedule: ScheduleContext,
+
+  /// Allowed durations based on TSB (for regulated dimensions)
+  pub allowed_durations: AllowedDurations,
+
+  /// Active training flags
+  pub flags: Vec<String>,
+
+  /// User settings relevant to analysis
+  pub user: UserContext,
+
+  /// Significance thresholds for detecting meaningful changes
+  pub thresholds: SignificanceThresholds,
+
+  /// Oura sleep and recovery data (optional)
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub oura: Option<crate::oura::OuraContext>,
+
+  /// Progression summary (computed by Rust, explains engine decisions to LLM)
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub progression_summary: Option<ProgressionSummary>,
+
+  /// Rust-computed confidence for tomorrow's prescription (ground truth for the LLM)
+  pub prescription_confidence: PrescriptionConfidence,
+
+  /// Athlete ratings of recent prescriptions (most recent first)
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub recent_feedback: Vec<PrescriptionFeedback>,
+
+  /// Periodization phase (flags above are already weighted for it)
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub training_phase: Option<TrainingPhase>,
+
+  /// Rust-computed performance card; the LLM only adds the insight
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub performance: Option<crate::llm::PerformanceCard>,
+
+  /// 7-day intensity split against the athlete's polarization target
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub polarization: Option<PolarizationGap>,
+
+  /// Patterns seen in several recent analyses (coach memory)
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub recurring_observations: Vec<RecurringObservation>,
+
+  /// The athlete's journal for the workout's date (stress, soreness, life)
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub daily_log: Option<DailyLog>,
+
+  /// Whether this session followed the coach's prescription for its date
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub adherence_to_prescription: Option<PrescriptionAdherence>,
+
+  /// Intended vs executed intensity for the training-plan session on this date
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub plan_execution: Option<PlanExecution>,
+}
+
+/// How the athlete rated a tomorrow-prescription
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PrescriptionRating {
+  Good,
+  TooHard,
+  TooEasy,
+}
+
+impl PrescriptionRating {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      PrescriptionRating::Good => "good",
+      PrescriptionRating::TooHard => "too_hard",
+      PrescriptionRating::TooEasy => "too_easy",
+    }
+  }
+}
+
+impl std::str::FromStr for PrescriptionRating {
+  type Err = String;
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "good" => Ok(PrescriptionRating::Good),
+      "too_hard" => Ok(PrescriptionRating::TooHard),
+      "too_easy" => Ok(PrescriptionRating::TooEasy),
+      _ => Err(format!("Unknown prescription rating: {}", s)),
+    }
+  }
+}
+
+/// Feedback on a past prescription, included so the LLM can self-correct
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrescriptionFeedback {
+  pub workout_id: i64,
+  pub workout_date: String,
+  /// The tomorrow recommendation that was rated (if the analysis still exists)
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub prescription: Option<String>,
+  pub rating: PrescriptionRating,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub note: Option<String>,
+}
+
+/// Life stress at or above this (1-10) calls for a conservative prescription
+pub const HIGH_LIFE_STRESS: i64 = 8;
+
+/// The athlete's journal entry for one day (life outside training)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DailyLog {
+  /// Local date (YYYY-MM-DD)
+  pub date: String,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub stress_1_10: Option<i64>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub soreness_1_10: Option<i64>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub note: Option<String>,
+  /// Stress at or above HIGH_LIFE_STRESS
+  #[serde(default)]
+  pub high_stress: bool,
+}
+
+impl DailyLog {
+  pub fn new(date: String, stress_1_10: Option<i64>, soreness_1_10: Option<i64>, note: Option<String>) -> Self {
+    let high_stress = stress_1_10.is_some_and(|stress| stress >= HIGH_LIFE_STRESS);
+    Self { date, stress_1_10, soreness_1_10, note, high_stress }
+  }
+}
+
+/// Actual duration further than this fraction from the prescribed one is a deviation
+pub const PRESCRIPTION_DURATION_TOLERANCE: f64 = 0.25;
+
+/// A tomorrow-prescription as stored for its target date
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StoredPrescription {
+  /// Local date the session was prescribed for (YYYY-MM-DD)
+  pub target_date: String,
+  /// Expected session type, e.g. "ride", "run_long" or "rest"
+  pub activity_type: String,
+  pub duration_min: i32,
+  pub intensity: String,
+}
+
+impl StoredPrescription {
+  /// Compact text, e.g. "45 min Z2 ride"
+  pub fn describe(&self) -> String {
+    if self.activity_type == "rest" {
+      return "rest day".to_string();
+    }
+    format!("{} min {} {}", self.duration_min, self.intensity, self.activity_type.replace('_', " "))
+  }
+}
+
+/// How the analyzed workout compares with what the coach prescribed for its day
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PrescriptionAdherence {
+  pub prescribed: String,
+  pub followed: bool,
+  /// One line per mismatch (type, duration, intensity)
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub deviations: Vec<String>,
+}
+
+impl PrescriptionAdherence {
+  pub fn compare(prescription: &StoredPrescription, workout: &WorkoutContext) -> Self {
+    let mut deviations = Vec::new();
+    let done = canonical_activity(&workout.activity_type);
+
+    // "run_long" is still a run
+    let expected = prescription.activity_type.split('_').next().unwrap_or_default();
+    if expected == "rest" {
+      deviations.push(format!("type: rest day prescribed, {} done", done.as_str()));
+    } else {
+      if canonical_activity(expected) != done {
+        deviations.push(format!("type: {} prescribed, {} done", expected, done.as_str()));
+      }
+
+      if let Some(actual) = workout.duration_min {
+        let prescribed = prescription.duration_min as f64;
+        let delta = actual - prescribed;
+        if prescribed > 0.0 && delta.abs() > prescribed * PRESCRIPTION_DURATION_TOLERANCE {
+          deviations.push(format!(
+            "duration: {:.0} min vs {} prescribed ({:+.0} min)",
+            actual, prescription.duration_min, delta
+          ));
+        }
+      }
+
+      let executed = executed_intensity(workout);
+      if let (Some(target), Some(executed_zone)) = (intensity_level(&prescription.intensity), executed.as_deref()) {
+        if let Some(actual) = intensity_level(executed_zone) {
+          if actual != target {
+            let direction = if actual > target { "harder" } else { "easier" };
+            deviations.push(format!(
+              "intensity: {} vs {} prescribed ({})",
+              executed_zone, prescription.intensity, direction
+            ));
+          }
+        }
+      }
+    }
+
+    Self { prescribed: prescription.describe(), followed: deviations.is_empty(), deviations }
+  }
+}
+
+/// Zone number for a prescribed or measured intensity ("Z1".."Z5",
+/// "recovery", "tempo")
+fn intensity_level(intensity: &str) -> Option<u8> {
+  match intensity.to_lowercase().as_str() {
+    "z1" | "recovery" => Some(1),
+    "z2" => Some(2),
+    "z3" | "tempo" => Some(3),
+    "z4" => Some(4),
+    "z5" => Some(5),
+    _ => None,
+  }
+}
+
+/// Upper intensity-factor bounds of power zones Z1-Z4; anything above is Z5
+pub const POWER_ZONE_IF_BOUNDS: [f64; 4] = [0.55, 0.75, 0.90, 1.05];
+
+/// Power zone for an intensity factor (average power / threshold)
+fn power_zone(intensity_factor: f64) -> HrZone {
+  match POWER_ZONE_IF_BOUNDS.iter().position(|&bound| intensity_factor < bound) {
+    Some(0) => HrZone::Z1,
+    Some(1) => HrZone::Z2,
+    Some(2) => HrZone::Z3,
+    Some(3) => HrZone::Z4,
+    _ => HrZone::Z5,
+  }
+}
+
+/// Zone a session was executed in: swims by their CSS zone's HR equivalent,
+/// otherwise the HR zone, falling back to the power zone without HR
+fn executed_intensity(workout: &WorkoutContext) -> Option<String> {
+  workout
+    .swim_zone
+    .as_deref()
+    .and_then(SwimZone::parse)
+    .map(|zone| zone.hr_equivalent())
+    .or_else(|| workout.zone.as_deref().and_then(HrZone::parse))
+    .or_else(|| workout.intensity_factor.map(power_zone))
+    .map(|zone| zone.as_str().to_string())
+}
+
+/// Executed intensity against the intended one
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IntensityExecution {
+  AsPlanned,
+  HarderThanPlanned,
+  EasierThanPlanned,
+}
+
+impl IntensityExecution {
+  /// None when either intensity isn't a recognizable zone
+  pub fn classify(intended: &str, executed: &str) -> Option<Self> {
+    let (intended, executed) = (intensity_level(intended)?, intensity_level(executed)?);
+    Some(match executed.cmp(&intended) {
+      std::cmp::Ordering::Equal => IntensityExecution::AsPlanned,
+      std::cmp::Ordering::Greater => IntensityExecution::HarderThanPlanned,
+      std::cmp::Ordering::Less => IntensityExecution::EasierThanPlanned,
+    })
+  }
+}
+
+/// How the analyzed workout's intensity compares with the training-plan
+/// session intended for its date
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlanExecution {
+  /// Compact text, e.g. "45 min Z2 ride"
+  pub planned: String,
+  pub intended: String,
+  /// Zone the session was actually executed in
+  pub executed: String,
+  pub execution: IntensityExecution,
+}
+
+impl PlanExecution {
+  /// Compare against the plan day on the workout's date with the same
+  /// modality (a planned run is matched by a run). None without a match or
+  /// when either intensity can't be placed in a zone.
+  pub fn compare(plan_days: &[PlannedDay], workout: &WorkoutContext) -> Option<Self> {
+    let done = canonical_activity(&workout.activity_type);
+    let day = plan_days.iter().find(|day| {
+      day.date == workout.date
+        && !day.activity_type.eq_ignore_ascii_case("rest")
+        && planned_kind(&day.activity_type) == done
+    })?;
+    let executed = executed_intensity(workout)?;
+    let execution = IntensityExecution::classify(&day.intensity, &executed)?;
+
+    Some(Self {
+      planned: format!("{} min {} {}", day.duration_min, day.intensity, day.activity_type.replace('_', " ")),
+      intended: day.intensity.clone(),
+      executed,
+      execution,
+    })
+  }
+}
+
+/// Workout structure metadata (for structured workouts like TrainerRoad)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkoutStructure {
+  pub is_structured: bool,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub block_type: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub prescribed_target_watts: Option<f64>,
+}
+
+impl Default for WorkoutStructure {
+  fn default() -> Self {
+    Self {
+      is_structured: false,
+      block_type: None,
+      prescribed_target_watts: None,
+    }
+  }
+}
+
+/// Workout-specific context for the LLM
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkoutContext {
+  pub activity_type: String,
+  pub duration_min: Option<f64>,
+  pub distance_km: Option<f64>,
+  pub pace_min_km: Option<f64>,
+  pub avg_hr: Option<i64>,
+  pub avg_watts: Option<f64>,
+  pub rtss: Option<f64>,
+  pub zone: Option<String>,
+  pub date: String,
+  pub day_of_week: String,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub efficiency: Option<f64>,
+  /// Cycling speed in km/h (None indoors)
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub speed_kmh: Option<f64>,
+  /// Cycling work in kilojoules
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub kj: Option<f64>,
+  /// avg_hr x duration_min
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub cardiac_cost: Option<f64>,
+  /// avg_watts / threshold power for the sport (run critical power or FTP)
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub intensity_factor: Option<f64>,
+  /// rTSS accrued per HR zone (from the HR stream)
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub tss_by_zone: Option<ZoneTss>,
+  /// Swim pace in seconds per 100m
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub swim_pace_sec_per_100m: Option<f64>,
+  /// Swim zone from pace vs CSS ("recovery", "aerobic", "threshold", "vo2")
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub swim_zone: Option<String>,
+  pub structure: WorkoutStructure,
+  /// Athlete's session RPE (1-10)
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub rpe: Option<i64>,
+  /// Athlete's note on how the session felt
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub notes: Option<String>,
+  /// Activity title as named on Strava
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub name: Option<String>,
+  /// The athlete's Strava description of the session
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub description: Option<String>,
+  /// Pa:HR decoupling (%), only when the stream was reliable
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub decoupling_pct: Option<f64>,
+  /// Trainer/treadmill session (no real speed; power not road-comparable)
+  #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+  pub is_indoor: bool,
+  /// HR strap dropped out for much of the session (no zone, HR values shaky)
+  #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+  pub hr_low_confidence: bool,
+  /// Implausible pace/speed/HR values were dropped (see WorkoutMetrics)
+  #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+  pub data_suspect: bool,
+  /// Distance, pace and speed in the athlete's units; None when metric
+  /// (the *_km fields already are)
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub display_units: Option<UnitMetrics>,
+}
+
+/// Summary of a recent workout for comparison context
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecentWorkoutSummary {
+  pub date: String,
+  pub activity_type: String,
+  pub duration_min: f64,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub avg_power: Option<f64>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub avg_hr: Option<i64>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub pace_min_km: Option<f64>,
+  /// `pace_min_km` in min/mi; set only for imperial athletes
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub pace_min_mi: Option<f64>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub rtss: Option<f64>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub efficiency: Option<f64>,
+  /// Trainer/treadmill session: power and pace aren't comparable with outdoors
+  #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+  pub is_indoor: bool,
+}
+
+/// Recency penalty: a session this many days away from the target costs
+/// as much as being half a log-unit (~1.65x) off in duration
+pub const SIMILARITY_RECENCY_DAYS: f64 = 28.0;
+
+/// The session recent same-type workouts are ranked against
+#[derive(Debug, Clone)]
+pub struct ComparisonTarget {
+  pub date: chrono::NaiveDate,
+  pub duration_min: f64,
+  pub rtss: Option<f64>,
+}
+
+impl ComparisonTarget {
+  /// rTSS per hour: intensity independent of duration
+  fn intensity(duration_min: f64, rtss: Option<f64>) -> Option<f64> {
+    rtss.filter(|r| *r > 0.0 && duration_min > 0.0).map(|r| r / (duration_min / 60.0))
+  }
+
+  /// Lower is more comparable: log-ratio distance in duration and intensity
+  /// plus a recency penalty. Missing intensity on either side costs a flat 0.5.
+  pub fn distance(&self, candidate: &RecentWorkoutSummary) -> f64 {
+    let log_ratio = |a: f64, b: f64| (a / b).ln().abs();
+
+    let duration = if self.duration_min > 0.0 && candidate.duration_min > 0.0 {
+      log_ratio(candidate.duration_min, self.duration_min)
+    } else {
+      1.0
+    };
+    let intensity = match (
+      Self::intensity(self.duration_min, self.rtss),
+      Self::intensity(candidate.duration_min, candidate.rtss),
+    ) {
+      (Some(target), Some(other)) => log_ratio(other, target),
+      _ => 0.5,
+    };
+    let days_apart = chrono::NaiveDate::parse_from_str(&candidate.date, "%Y-%m-%d")
+      .map(|d| (self.date - d).num_days().abs() as f64)
+      .unwrap_or(SIMILARITY_RECENCY_DAYS);
+
+    duration + intensity + 0.5 * days_apart / SIMILARITY_RECENCY_DAYS
+  }
+
+  /// The `n` most comparable candidates, newest first
+  pub fn select(&self, mut candidates: Vec<RecentWorkoutSummary>, n: usize) -> Vec<RecentWorkoutSummary> {
+    candidates.sort_by(|a, b| self.distance(a).total_cmp(&self.distance(b)));
+    candidates.truncate(n);
+    candidates.sort_by(|a, b| b.date.cmp(&a.date));
+    candidates
+  }
+}
+
+/// Schedule context for day awareness
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduleContext {
+  pub today_is: String,
+  pub tomorrow_is: String,
+  pub tomorrow_expected_type: String,
+  pub weekly_pattern: WeeklyPattern,
+}
+
+/// Weekly training pattern
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WeeklyPattern {
+  pub monday: String,
+  pub tuesday: String,
+  pub wednesday: String,
+  pub thursday: String,
+  pub friday: String,
+  pub saturday: String,
+  pub sunday: String,
+}
+
+impl Default for WeeklyPattern {
+  fn default() -> Self {
+    Self {
+      monday: "ride".to_string(),
+      tuesday: "run".to_string(),
+      wednesday: "ride".to_string(),
+      thursday: "run".to_string(),
+      friday: "ride".to_string(),
+      saturday: "run_long".to_string(),
+      sunday: "rest".to_string(),
+    }
+  }
+}
+
+/// TSB edges between the fatigue bands. Defaults are Coggan's +5/-10/-20;
+/// some athletes race best at +10 or routinely train through -30.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TsbBands {
+  /// Fresh above this TSB
+  pub tsb_fresh_above: f64,
+  /// Moderate fatigue at or below this TSB
+  pub tsb_moderate_below: f64,
+  /// High fatigue at or below this TSB
+  pub tsb_high_below: f64,
+}
+
+impl Default for TsbBands {
+  fn default() -> Self {
+    Self {
+      tsb_fresh_above: 5.0,
+      tsb_moderate_below: -10.0,
+      tsb_high_below: -20.0,
+    }
+  }
+}
+
+/// Valid band edges
+pub const TSB_BAND_RANGE: std::ops::RangeInclusive<f64> = -60.0..=30.0;
+
+impl TsbBands {
+  /// Band name for a TSB ("unknown" without one)
+  pub fn band(&self, tsb: Option<f64>) -> &'static str {
+    match tsb {
+      Some(tsb) if tsb > self.tsb_fresh_above => "fresh",
+      Some(tsb) if tsb > self.tsb_moderate_below => "slightly_fatigued",
+      Some(tsb) if tsb > self.tsb_high_below => "moderate_fatigue",
+      Some(_) => "high_fatigue",
+      None => "unknown",
+    }
+  }
+
+  /// Edges must lie in TSB_BAND_RANGE and descend fresh > moderate > high
+  pub fn validate(&self) -> Result<(), String> {
+    for (label, edge) in [
+      ("tsb_fresh_above", self.tsb_fresh_above),
+      ("tsb_moderate_below", self.tsb_moderate_below),
+      ("tsb_high_below", self.tsb_high_below),
+    ] {
+      if !TSB_BAND_RANGE.contains(&edge) {
+        return Err(format!("Invalid {} '{}': expected -60 to 30", label, edge));
+      }
+    }
+    if self.tsb_fresh_above <= self.tsb_moderate_below || self.tsb_moderate_below <= self.tsb_high_below {
+      return Err(format!(
+        "TSB bands must descend: fresh above {} > moderate below {} > high below {}",
+        self.tsb_fresh_above, self.tsb_moderate_below, self.tsb_high_below
+      ));
+    }
+    Ok(())
+  }
+}
+
+/// Fatigue context with TSB band and trend
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FatigueContext {
+  pub atl: Option<f64>,
+  pub ctl: Option<f64>,
+  pub tsb: Option<f64>,
+  pub tsb_band: String,
+  pub tsb_trend: String,
+  /// Under four weeks of history: TSB overstates freshness, hedge on form
+  #[serde(default)]
+  pub tsb_low_confidence: bool,
+  /// Percent pace/power decrement current fatigue alone explains
+  /// (see expected_performance_adjustment)
+  #[serde(default)]
+  pub expected_performance_decrement_pct: Option<f64>,
+}
+
+impl FatigueContext {
+  /// Build fatigue context from training context and workout history
+  #[allow(dead_code)]
+  pub fn from_training_context_and_workouts(
+    ctx: &TrainingContext,
+    workouts: &[WorkoutSummary],
+    bands: &TsbBands,
+  ) -> Self {
+    let tsb_band = bands.band(ctx.tsb);
+
+    // Compute TSB trend over last 7 days
+    let tsb_trend = Self::compute_tsb_trend(workouts, ctx.tsb);
+
+    Self {
+      atl: ctx.atl,
+      ctl: ctx.ctl,
+      tsb: ctx.tsb,
+      tsb_band: tsb_band.to_string(),
+      tsb_trend,
+      tsb_low_confidence: ctx.data_maturity.tsb_low_confidence,
+      expected_performance_decrement_pct: ctx.tsb.map(expected_performance_adjustment),
+    }
+  }
+
+  /// Legacy method for backward compatibility
+  pub fn from_training_context(ctx: &TrainingContext, bands: &TsbBands) -> Self {
+    let tsb_band = bands.band(ctx.tsb);
+
+    Self {
+      atl: ctx.atl,
+      ctl: ctx.ctl,
+      tsb: ctx.tsb,
+      tsb_band: tsb_band.to_string(),
+      tsb_trend: "unknown".to_string(),
+      tsb_low_confidence: ctx.data_maturity.tsb_low_confidence,
+      expected_performance_decrement_pct: ctx.tsb.map(expected_performance_adjustment),
+    }
+  }
+
+  /// Compute TSB trend direction over last 7 days
+  #[allow(dead_code)]
+  fn compute_tsb_trend(workouts: &[WorkoutSummary], current_tsb: Option<f64>) -> String {
+    let current_tsb = match current_tsb {
+      Some(tsb) => tsb,
+      None => return "unknown".to_string(),
+    };
+
+    // Get TSB from 7 days ago by recomputing from workouts
+    // This is a simplified approach - ideally we'd store TSB history
+    let now = chrono::Utc::now();
+
+    // Filter workouts to 7-14 days ago (the "previous week")
+    let prev_week: Vec<_> = workouts
+      .iter()
+      .filter(|w| {
+        let days_ago = (now - w.started_at).num_days();
+        days_ago >= 7 && days_ago < 14
+      })
+      .collect();
+
+    if prev_week.is_empty() {
+      return "unknown".to_string();
+    }
+
+    // Rough approximation: compare current TSB to average rTSS from prev week
+    // This isn't perfect but gives directional sense
+    let prev_week_avg_rtss: f64 = prev_week
+      .iter()
+      .filter_map(|w| w.rtss)
+      .sum::<f64>()
+      / prev_week.len() as f64;
+
+    // If current TSB is improving (less negative), trend is up
+    // This is a simplified heuristic - proper implementation would track TSB history
+    if current_tsb > -10.0 && prev_week_avg_rtss < 40.0 {
+      "improving".to_string()
+    } else if current_tsb < -15.0 && prev_week_avg_rtss > 50.0 {
+      "declining".to_string()
+    } else {
+      "stable".to_string()
+    }
+  }
+}
+
+/// Prescription confidence based on signal quality
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrescriptionConfidence {
+  pub level: String,  // "high" | "medium" | "low"
+  pub reason: String, // Brief explanation
+}
+
+impl PrescriptionConfidence {
+  pub fn compute(
+    tsb: Option<f64>,
+    flags_count: usize,
+    adherence_pct: f64,
+    recent_workouts_count: usize,
+  ) -> Self {
+    // High confidence: clear signals, good data
+    if tsb.is_some() && flags_count <= 1 && adherence_pct > 0.8 && recent_workouts_count >= 5 {
+      return Self {
+        level: "high".to_string(),
+        reason: "Clear signals, good data".to_string(),
+      };
+    }
+
+    // Low confidence: mixed signals or sparse data
+    if tsb.is_none() || flags_count >= 3 || recent_workouts_count < 3 {
+      return Self {
+        level: "low".to_string(),
+        reason: "Mixed signals or limited data".to_string(),
+      };
+    }
+
+    // Medium: everything else
+    Self {
+      level: "medium".to_string(),
+      reason: "Some mixed indicators".to_string(),
+    }
+  }
+
+  /// Drop to low while CTL is still filling up: form advice from a TSB on
+  /// a few weeks of data is confidently wrong
+  pub fn with_data_maturity(self, maturity: &DataMaturity) -> Self {
+    if !maturity.tsb_low_confidence {
+      return self;
+    }
+    Self {
+      level: "low".to_string(),
+      reason: format!(
+        "Only {} days of history; TSB not reliable before {}",
+        maturity.history_days, MIN_HISTORY_DAYS_FOR_TSB
+      ),
+    }
+  }
+
+  /// Reconcile the LLM's self-reported confidence with this one.
+  /// The LLM may be more cautious than the data supports, never less;
+  /// unrecognized levels fall back to the computed level.
+  pub fn cap(&self, llm_level: &str) -> String {
+    let rank = |level: &str| match level.to_lowercase().as_str() {
+      "low" => Some(0),
+      "medium" => Some(1),
+      "high" => Some(2),
+      _ => None,
+    };
+
+    match (rank(llm_level), rank(&self.level)) {
+      (Some(llm), Some(computed)) if llm < computed => llm_level.to_lowercase(),
+      _ => self.level.clone(),
+    }
+  }
+}
+
+/// Allowed durations for TSB-regulated dimensions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AllowedDurations {
+  pub z2_ride: DurationOptions,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DurationOptions {
+  pub short: i32,
+  pub standard: i32,
+  pub long: i32,
+  pub recommended: String,
+}
+
+impl AllowedDurations {
+  pub fn from_tsb_band(tsb_band: &str) -> Self {
+    let (recommended, short, standard, long) = match tsb_band {
+      "fresh" => ("long", 45, 60, 60),
+      "slightly_fatigued" => ("standard", 40, 45, 60),
+      "moderate_fatigue" => ("short", 40, 45, 45),
+      "high_fatigue" => ("short", 30, 40, 40),
+      _ => ("standard", 40, 45, 60),
+    };
+
+    Self {
+      z2_ride: DurationOptions {
+        short,
+        standard,
+        long,
+        recommended: recommended.to_string(),
+      },
+    }
+  }
+}
+
+/// User context for the LLM
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserContext {
+  pub max_hr: Option<i64>,
+  pub lthr: Option<i64>,
+  pub training_days_per_week: i64,
+}
+
+/// Significance thresholds for detecting meaningful changes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignificanceThresholds {
+  pub hr_delta_significant: i64,        // >5 beats
+  pub efficiency_delta_significant: f64, // >3%
+  pub pace_delta_significant: f64,      // >10 sec/km
+  pub power_delta_significant: f64,     // >10W
+  pub temperature_delta_significant: f64, // >5°C
+}
+
+impl Default for SignificanceThresholds {
+  fn default() -> Self {
+    Self {
+      hr_delta_significant: 5,
+      efficiency_delta_significant: 0.03,
+      pace_delta_significant: 10.0,
+      power_delta_significant: 10.0,
+      temperature_delta_significant: 5.0,
+    }
+  }
+}
+
+impl ContextPackage {
+  /// Build a context package from workout data and computed metrics.
+  /// `adherence_pct` is the week's completed fraction of planned (or
+  /// expected) sessions, as in `AdherenceSummary`.
+  #[allow(clippy::too_many_arguments)]
+  pub fn build(
+    workout_type: &str,
+    started_at: &chrono::DateTime<chrono::Utc>,
+    duration_seconds: Option<i64>,
+    distance_meters: Option<f64>,
+    average_hr: Option<i64>,
+    average_watts: Option<f64>,
+    metrics: &WorkoutMetrics,
+    training_context: TrainingContext,
+    flags: TrainingFlags,
+    adherence_pct: f64,
+    settings: &UserSettings,
+    mut recent_same_type: Vec<RecentWorkoutSummary>,
+    mut recent_all: Vec<RecentWorkoutSummary>,
+  ) -> Self {
+    // Compute fatigue context from training context
+    // TODO: Pass workouts to compute TSB trend
+    let fatigue = FatigueContext::from_training_context(&training_context, &settings.tsb_bands);
+    let allowed_durations = AllowedDurations::from_tsb_band(&fatigue.tsb_band);
+
+    // Build schedule context (local time: a late run is still "today")
+    let local_start = settings.to_local(started_at);
+    let schedule = Self::build_schedule(&local_start);
+
+    // Determine workout structure
+    // For now: assume all rides are structured (TrainerRoad), runs are unstructured
+    let structure = if canonical_activity(workout_type) == ActivityKind::Ride {
+      WorkoutStructure {
+        is_structured: true,
+        block_type: Some("z2_steady".to_string()),
+        prescribed_target_watts: average_watts, // Use avg as proxy for target
+      }
+    } else {
+      WorkoutStructure::default()
+    };
+
+    let distance_km = distance_meters.map(|m| m / 1000.0);
+    let display_units = (!settings.units.is_metric())
+      .then(|| UnitMetrics::new(settings.units, distance_km, metrics.pace_min_per_km, metrics.speed_kmh));
+    if !settings.units.is_metric() {
+      for recent in recent_same_type.iter_mut().chain(recent_all.iter_mut()) {
+        recent.pace_min_mi = recent.pace_min_km.map(|pace| settings.units.pace(pace));
+      }
+    }
+    let workout = WorkoutContext {
+      activity_type: workout_type.to_string(),
+      duration_min: duration_seconds.map(|s| s as f64 / 60.0),
+      distance_km,
+      pace_min_km: metrics.pace_min_per_km,
+      avg_hr: average_hr,
+      avg_watts: average_watts,
+      rtss: metrics.rtss,
+      zone: metrics.hr_zone.map(|z| z.as_str().to_string()),
+      date: local_start.format("%Y-%m-%d").to_string(),
+      day_of_week: local_start.format("%A").to_string(),
+      efficiency: metrics.efficiency,
+      speed_kmh: metrics.speed_kmh,
+      kj: metrics.kj,
+      cardiac_cost: metrics.cardiac_cost,
+      intensity_factor: average_watts
+        .filter(|w| *w > 0.0)
+        .zip(settings.power_threshold_for(workout_type).filter(|t| *t > 0))
+        .map(|(watts, threshold)| watts / threshold as f64),
+      tss_by_zone: metrics.tss_by_zone,
+      swim_pace_sec_per_100m: metrics.swim_pace_sec_per_100m,
+      swim_zone: metrics.swim_zone.map(|z| z.as_str().to_string()),
+      structure,
+      rpe: None,
+      notes: None,
+      name: None,
+      description: None,
+      decoupling_pct: None,
+      is_indoor: is_indoor_activity(workout_type),
+      hr_low_confidence: metrics.hr_low_confidence,
+      data_suspect: metrics.data_suspect,
+      display_units,
+    };
+
+    let user = UserContext {
+      max_hr: settings.max_hr_for(workout_type),
+      lthr: settings.effective_lthr_for(workout_type),
+      training_days_per_week: settings.training_days_per_week,
+    };
+
+    let flags_list = flags.to_string_list();
+    let prescription_confidence =
+      PrescriptionConfidence::compute(training_context.tsb, flags_list.len(), adherence_pct, recent_all.len())
+    .with_data_maturity(&training_context.data_maturity);
+
+    let thresholds = SignificanceThresholds::default();
+    let performance =
+      compute_performance_card(&workout, &recent_same_type, &thresholds, fatigue.tsb, settings.units);
+
+    Self {
+      workout,
+      recent_same_type,
+      recent_all,
+      fatigue,
+      schedule,
+      allowed_durations,
+      flags: flags_list,
+      user,
+      thresholds,
+      oura: None,  // Attached via with_oura (loaded for the workout's date)
+      progression_summary: None,
+      prescription_confidence,
+      recent_feedback: Vec::new(),
+      training_phase: flags.phase,
+      performance,
+      polarization: training_context.polarization_gap.clone(),
+      recurring_observations: Vec::new(),
+      daily_log: None,
+      adherence_to_prescription: None,
+      plan_execution: None,
+    }
+  }
+
+  /// Build schedule context from the workout's local date
+  fn build_schedule(workout_date: &chrono::DateTime<chrono::FixedOffset>) -> ScheduleContext {
+    use chrono::{Datelike, Weekday};
+
+    let today = workout_date.weekday();
+    let tomorrow = today.succ();
+
+    let day_name = |w: Weekday| -> String {
+      match w {
+        Weekday::Mon => "Monday",
+        Weekday::Tue => "Tuesday",
+        Weekday::Wed => "Wednesday",
+        Weekday::Thu => "Thursday",
+        Weekday::Fri => "Friday",
+        Weekday::Sat => "Saturday",
+        Weekday::Sun => "Sunday",
+      }.to_string()
+    };
+
+    ScheduleContext {
+      today_is: day_name(today),
+      tomorrow_is: day_name(tomorrow),
+      tomorrow_expected_type: expected_session_type(tomorrow).to_string(),
+      weekly_pattern: WeeklyPattern::default(),
+    }
+  }
+
+  /// Add progression summary (from Rust progression engine)
+  pub fn with_progression_summary(mut self, summary: ProgressionSummary) -> Self {
+    self.progression_summary = Some(summary);
+    self
+  }
+
+  /// Add the athlete's subjective RPE and note for this workout
+  pub fn with_subjective(mut self, rpe: Option<i64>, notes: Option<String>) -> Self {
+    self.workout.rpe = rpe;
+    self.workout.notes = notes;
+    self
+  }
+
+  /// Add the activity's title and description (blank ones are left out)
+  pub fn with_activity_text(mut self, name: Option<String>, description: Option<String>) -> Self {
+    let non_blank = |text: Option<String>| text.map(|t| t.trim().to_string()).filter(|t| !t.is_empty());
+    self.workout.name = non_blank(name);
+    self.workout.description = non_blank(description);
+    self
+  }
+
+  /// Add aerobic decoupling computed from the workout's streams
+  pub fn with_decoupling(mut self, decoupling_pct: Option<f64>) -> Self {
+    self.workout.decoupling_pct = decoupling_pct;
+    self
+  }
+
+  /// Mark a trainer session; the performance card then compares only
+  /// against other indoor sessions
+  pub fn with_indoor(mut self, is_indoor: bool) -> Self {
+    self.workout.is_indoor = self.workout.is_indoor || is_indoor;
+    if self.workout.is_indoor {
+      self.workout.speed_kmh = None;
+    }
+    let units = self.workout.display_units.as_ref().map_or(Units::Metric, |display| display.units);
+    self.performance =
+      compute_performance_card(&self.workout, &self.recent_same_type, &self.thresholds, self.fatigue.tsb, units);
+    self
+  }
+
+  /// Add Oura recovery context for the workout's date
+  pub fn with_oura(mut self, oura: Option<crate::oura::OuraContext>) -> Self {
+    self.oura = oura;
+    self
+  }
+
+  /// Add the athlete's journal entry for the workout's date
+  pub fn with_daily_log(mut self, daily_log: Option<DailyLog>) -> Self {
+    self.daily_log = daily_log;
+    self
+  }
+
+  /// Compare the session against the prescription stored for its date
+  pub fn with_prescription(mut self, prescription: Option<StoredPrescription>) -> Self {
+    self.adherence_to_prescription = prescription.map(|p| PrescriptionAdherence::compare(&p, &self.workout));
+    self
+  }
+
+  /// Compare the session's intensity against the training plan's days
+  pub fn with_plan_days(mut self, plan_days: &[PlannedDay]) -> Self {
+    self.plan_execution = PlanExecution::compare(plan_days, &self.workout);
+    self
+  }
+
+  /// Add recent prescription feedback from the athlete
+  pub fn with_feedback(mut self, feedback: Vec<PrescriptionFeedback>) -> Self {
+    self.recent_feedback = feedback;
+    self
+  }
+
+  pub fn with_observations(mut self, observations: Vec<RecurringObservation>) -> Self {
+    self.recurring_observations = observations;
+    self
+  }
+
+  /// Serialize to JSON for the LLM prompt
+  pub fn to_json(&self) -> String {
+    serde_json::to_string_pretty(self).unwrap_or_default()
+  }
+
+  /// Apply trim steps, least essential first, until the JSON fits in
+  /// `budget_chars`. The workout, flags, fatigue, performance card,
+  /// progression summary and prescription confidence are never trimmed, so
+  /// a tiny budget can still be exceeded.
+  pub fn trim_to_budget(&mut self, budget_chars: usize) -> ContextTrim {
+    let original_chars = self.to_json().len();
+    let mut chars = original_chars;
+    let mut steps = Vec::new();
+
+    let mut step = 0;
+    while chars > budget_chars {
+      let Some(name) = self.apply_trim_step(step) else {
+        break;
+      };
+      let trimmed = self.to_json().len();
+      if trimmed < chars {
+        steps.push(name.to_string());
+        chars = trimmed;
+      }
+      step += 1;
+    }
+
+    ContextTrim {
+      budget_chars,
+      original_chars,
+      final_chars: chars,
+      steps,
+    }
+  }
+
+  /// One trim step (None once they're used up): cap the recent-workout
+  /// lists, then drop optional blocks, then empty the lists
+  fn apply_trim_step(&mut self, step: usize) -> Option<&'static str> {
+    let name = match step {
+      0 => {
+        self.recent_all.truncate(TRIMMED_RECENT_ALL);
+        "recent_all capped"
+      }
+      1 => {
+        self.recent_same_type.truncate(TRIMMED_RECENT_SAME_TYPE);
+        "recent_same_type capped"
+      }
+      2 => {
+        self.recurring_observations.clear();
+        "recurring_observations dropped"
+      }
+      3 => {
+        self.recent_feedback.clear();
+        "recent_feedback dropped"
+      }
+      4 => {
+        self.oura = None;
+        "oura dropped"
+      }
+      5 => {
+        self.daily_log = None;
+        "daily_log dropped"
+      }
+      6 => {
+        self.recent_all.clear();
+        "recent_all dropped"
+      }
+      7 => {
+        self.adherence_to_prescription = None;
+        "adherence_to_prescription dropped"
+      }
+      8 => {
+        self.plan_execution = None;
+        "plan_execution dropped"
+      }
+      9 => {
+        self.polarization = None;
+        "polarization dropped"
+      }
+      10 => {
+        self.recent_same_type.clear();
+        "recent_same_type dropped"
+      }
+      _ => return None,
+    };
+    Some(name)
+  }
+
+  /// Fingerprint of the inputs specific to this workout: the session itself,
+  /// the athlete's thresholds and the coach tone it was written in. Rolling
+  /// context (recent workouts, fatigue, feedback) moves every day, so it's
+  /// left out; otherwise every analysis would go stale overnight.
+  pub fn input_hash(&self, tone: CoachTone) -> String {
+    let mut inputs = serde_json::json!({ "workout": self.workout, "user": self.user });
+    // The default tone stays out so analyses from before tones keep their hash
+    if tone != CoachTone::default() {
+      inputs["coach_tone"] = serde_json::json!(tone.as_str());
+    }
+    // FNV-1a: stable across builds, unlike std's DefaultHasher
+    let hash = inputs
+      .to_string()
+      .bytes()
+      .fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x0000_0100_0000_01b3));
+    format!("{:016x}", hash)
+  }
+}
+
+/// Recent-workout list lengths after the first trim steps
+const TRIMMED_RECENT_ALL: usize = 7;
+const TRIMMED_RECENT_SAME_TYPE: usize = 5;
+
+/// What trimming did to fit a context package in its budget
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ContextTrim {
+  pub budget_chars: usize,
+  pub original_chars: usize,
+  pub final_chars: usize,
+  /// Steps that shrank the package, in the order applied
+  pub steps: Vec<String>,
+}
+
+/// Default weekly schedule: MWF ride, T/Th run, Sat long run, Sun rest
+pub fn expected_session_type(weekday: chrono::Weekday) -> &'static str {
+  use chrono::Weekday;
+
+  match weekday {
+    Weekday::Mon => "ride",
+    Weekday::Tue => "run",
+    Weekday::Wed => "ride",
+    Weekday::Thu => "run",
+    Weekday::Fri => "ride",
+    Weekday::Sat => "run_long",
+    Weekday::Sun => "rest",
+  }
+}
+
+/// ---------------------------------------------------------------------------
+/// Observations (coach memory across analyses)
+/// ---------------------------------------------------------------------------
+
+/// Average HR this far above the recent same-type average counts as elevated
+pub const ELEVATED_HR_BPM: f64 = 5.0;
+
+/// Sessions an observation must appear in to be sent as a pattern
+pub const RECURRING_MIN_SESSIONS: usize = 3;
+
+/// How far back observations are searched for patterns
+pub const OBSERVATION_WINDOW_DAYS: i64 = 28;
+
+/// What a stored observation is about
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ObservationKind {
+  /// Avg HR above the recent average for the same activity type
+  ElevatedHr,
+  /// A training flag was active
+  Flag,
+}
+
+impl ObservationKind {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      ObservationKind::ElevatedHr => "elevated_hr",
+      ObservationKind::Flag => "flag",
+    }
+  }
+
+  pub fn parse(value: &str) -> Option<Self> {
+    match value {
+      "elevated_hr" => Some(ObservationKind::ElevatedHr),
+      "flag" => Some(ObservationKind::Flag),
+      _ => None,
+    }
+  }
+}
+
+/// One thing noticed about an analyzed session
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Observation {
+  pub kind: ObservationKind,
+  /// Activity kind for elevated_hr ("run"), flag name for flag
+  pub subject: String,
+  pub detail: String,
+}
+
+impl Observation {
+  /// Observations for one session: elevated HR against the recent
+  /// same-type sessions, plus every active flag
+  pub fn extract(
+    activity_type: &str,
+    average_hr: Option<i64>,
+    recent_same_type: &[RecentWorkoutSummary],
+    flags: &TrainingFlags,
+  ) -> Vec<Self> {
+    let mut observations = Vec::new();
+
+    let recent_hr: Vec<f64> = recent_same_type.iter().filter_map(|w| w.avg_hr).map(|hr| hr as f64).collect();
+    if let (Some(hr), true) = (average_hr, recent_hr.len() >= 2) {
+      let baseline = recent_hr.iter().sum::<f64>() / recent_hr.len() as f64;
+      if hr as f64 - baseline >= ELEVATED_HR_BPM {
+        observations.push(Self {
+          kind: ObservationKind::ElevatedHr,
+          subject: canonical_activity(activity_type).as_str().to_string(),
+          detail: format!("avg HR {} vs {:.0} recent", hr, baseline),
+        });
+      }
+    }
+
+    for (flag, _, description) in flags.to_prioritized_list() {
+      observations.push(Self { kind: ObservationKind::Flag, subject: flag, detail: description });
+    }
+
+    observations
+  }
+}
+
+/// An observation repeated across recent sessions
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecurringObservation {
+  pub kind: ObservationKind,
+  pub subject: String,
+  pub sessions: usize,
+  pub first_seen: String,
+  pub last_seen: String,
+  /// e.g. "elevated HR on run in 3 sessions since 2024-12-03"
+  pub summary: String,
+}
+
+impl RecurringObservation {
+  /// Group stored observations (kind, subject, session date) and keep those
+  /// seen in at least RECURRING_MIN_SESSIONS sessions, most frequent first
+  pub fn from_history(history: &[(ObservationKind, String, String)]) -> Vec<Self> {
+    let mut groups: Vec<((ObservationKind, &str), Vec<&str>)> = Vec::new();
+    for (kind, subject, date) in history {
+      match groups.iter_mut().find(|((k, s), _)| k == kind && s == subject) {
+        Some((_, dates)) => dates.push(date),
+        None => groups.push(((*kind, subject), vec![date])),
+      }
+    }
+
+    let mut recurring: Vec<Self> = groups
+      .into_iter()
+      .filter(|(_, dates)| dates.len() >= RECURRING_MIN_SESSIONS)
+      .map(|((kind, subject), dates)| {
+        let first_seen = dates.iter().min().copied().unwrap_or_default().to_string();
+        let last_seen = dates.iter().max().copied().unwrap_or_default().to_string();
+        let what = match kind {
+          ObservationKind::ElevatedHr => format!("elevated HR on {}", subject),
+          ObservationKind::Flag => subject.to_string(),
+        };
+        Self {
+          kind,
+          subject: subject.to_string(),
+          sessions: dates.len(),
+          summary: format!("{} in {} sessions since {}", what, dates.len(), first_seen),
+          first_seen,
+          last_seen,
+        }
+      })
+      .collect();
+    recurring.sort_by(|a, b| b.sessions.cmp(&a.sessions).then(b.last_seen.cmp(&a.last_seen)));
+    recurring
+  }
+}
+
+/// ---------------------------------------------------------------------------
+/// Performance Comparison (deterministic card numbers)
+/// ---------------------------------------------------------------------------
+
+/// TSB at or above which fatigue isn't expected to cost performance
+const FATIGUE_FREE_TSB: f64 = -10.0;
+/// Expected decrement per TSB point below FATIGUE_FREE_TSB
+const FATIGUE_DECREMENT_PCT_PER_TSB: f64 = 0.3;
+/// Ceiling on the expected decrement however deep the fatigue
+const MAX_FATIGUE_DECREMENT_PCT: f64 = 8.0;
+
+/// Percent pace/power decrement attributable to current fatigue: none while
+/// TSB is at or above -10, then 0.3% per point below it (TSB -25 expects
+/// ~4.5%), capped at 8%
+pub fn expected_performance_adjustment(tsb: f64) -> f64 {
+  ((FATIGUE_FREE_TSB - tsb) * FATIGUE_DECREMENT_PCT_PER_TSB).clamp(0.0, MAX_FATIGUE_DECREMENT_PCT)
+}
+
+/// Fill the performance card's numbers against the most recent same-type
+/// workout (same indoor/outdoor setting) sharing a metric (power, then pace,
+/// then efficiency). The LLM
+/// only writes `insight`; the numbers are overwritten with these afterwards.
+/// A decline is checked against the decrement the workout's TSB explains.
+/// Pace is shown in the athlete's units; its significance is judged in sec/km.
+pub fn compute_performance_card(
+  workout: &WorkoutContext,
+  recent_same_type: &[RecentWorkoutSummary],
+  thresholds: &SignificanceThresholds,
+  tsb: Option<f64>,
+  units: Units,
+) -> Option<crate::llm::PerformanceCard> {
+  let positive = |v: Option<f64>| v.filter(|v| v.is_finite() && *v > 0.0);
+  // Trainer and road numbers aren't comparable: stay on the same side
+  let most_recent = |metric: fn(&RecentWorkoutSummary) -> Option<f64>| {
+    recent_same_type
+      .iter()
+      .filter(|w| w.is_indoor == workout.is_indoor)
+      .find_map(|w| positive(metric(w)).map(|value| (w.date.clone(), value)))
+  };
+  let direction = |gain: f64, significant: f64| {
+    if gain.abs() < significant {
+      "stable"
+    } else if gain > 0.0 {
+      "improved"
+    } else {
+      "declined"
+    }
+  };
+  // `gain_pct` is the relative change, positive when better
+  let card = |metric_name: &str,
+              date: String,
+              comparison: String,
+              today: String,
+              delta: String,
+              dir: &str,
+              gain_pct: f64| crate::llm::PerformanceCard {
+    metric_name: metric_name.to_string(),
+    comparison_date: date,
+    comparison_value: comparison,
+    today_value: today,
+    delta,
+    insight: String::new(),
+    direction: Some(dir.to_string()),
+    within_fatigue_expectation: tsb
+      .filter(|_| dir == "declined")
+      .map(|tsb| -gain_pct <= expected_performance_adjustment(tsb)),
+  };
+
+  // Power: higher is better
+  if let (Some(today), Some((date, prior))) = (positive(workout.avg_watts), most_recent(|w| w.avg_power)) {
+    let delta = today - prior;
+    return Some(card(
+      "power",
+      date,
+      format!("{:.0}W", prior),
+      format!("{:.0}W", today),
+      format!("{:+.0}W", delta),
+      direction(delta, thresholds.power_delta_significant),
+      delta / prior * 100.0,
+    ));
+  }
+
+  // Pace: lower is better, compared in sec/km
+  if let (Some(today), Some((date, prior))) = (positive(workout.pace_min_km), most_recent(|w| w.pace_min_km)) {
+    let delta_sec = ((today - prior) * 60.0).round();
+    let display_delta_sec = (units.pace(today - prior) * 60.0).round();
+    return Some(card(
+      "pace",
+      date,
+      format_pace(prior, units),
+      format_pace(today, units),
+      format!("{:+.0} sec/{}", display_delta_sec, units.distance_label()),
+      direction(-delta_sec, thresholds.pace_delta_significant),
+      (prior - today) / prior * 100.0,
+    ));
+  }
+
+  // Efficiency: relative change; runs (pace/HR) improve downward
+  if let (Some(today), Some((date, prior))) = (positive(workout.efficiency), most_recent(|w| w.efficiency)) {
+    let change = (today - prior) / prior;
+    let gain = match canonical_activity(&workout.activity_type) {
+      ActivityKind::Run => -change,
+      _ => change,
+    };
+    return Some(card(
+      "efficiency",
+      date,
+      format!("{:.3}", prior),
+      format!("{:.3}", today),
+      format!("{:+.1}%", change * 100.0),
+      direction(gain, thresholds.efficiency_delta_significant),
+      gain * 100.0,
+    ));
+  }
+
+  None
+}
+
+/// Format a min/km pace as "M:SS/km" (or "M:SS/mi" for imperial)
+fn format_pace(pace_min_km: f64, units: Units) -> String {
+  let total_sec = (units.pace(pace_min_km) * 60.0).round() as i64;
+  format!("{}:{:02}/{}", total_sec / 60, total_sec % 60, units.distance_label())
+}
+
+/// ---------------------------------------------------------------------------
+/// Model Selection (cost control)
+/// ---------------------------------------------------------------------------
+
+/// Athlete RPE at or above this marks a hard effort or race
+const HARD_RPE: i64 = 8;
+
+/// Pick the model for a workout analysis: the cheap model for routine
+/// sessions, the premium one when something needs careful reading (high fatigue,
+/// a volume spike, an RPE/HR mismatch, a hard or race effort, a PR, a threshold
+/// test found in the streams, a session linked as a key session).
+pub fn select_model(
+  context: &ContextPackage,
+  flags: &TrainingFlags,
+  threshold_test: Option<&TestResult>,
+  key_session: bool,
+) -> &'static str {
+  use crate::llm::{CLAUDE_MODEL, CLAUDE_MODEL_FAST};
+
+  let workout = &context.workout;
+  let hard_rpe = workout.rpe.is_some_and(|rpe| rpe >= HARD_RPE);
+
+  // PR against recent same-type sessions: fastest pace or highest power
+  let pace_pr = workout.pace_min_km.filter(|p| *p > 0.0).is_some_and(|pace| {
+    let recent: Vec<f64> = context.recent_same_type.iter().filter_map(|w| w.pace_min_km).collect();
+    !recent.is_empty() && recent.iter().all(|r| pace < *r)
+  });
+  let power_pr = workout.avg_watts.filter(|w| *w > 0.0).is_some_and(|watts| {
+    let recent: Vec<f64> = context.recent_same_type.iter().filter_map(|w| w.avg_power).collect();
+    !recent.is_empty() && recent.iter().all(|r| watts > *r)
+  });
+
+  let significant = flags.high_fatigue
+    || flags.volume_spike
+    || flags.rpe_hr_mismatch
+    || threshold_test.is_some()
+    || hard_rpe
+    || key_session
+    || pace_pr
+    || power_pr;
+
+  if significant {
+    CLAUDE_MODEL
+  } else {
+    CLAUDE_MODEL_FAST
+  }
+}
+
+/// ---------------------------------------------------------------------------
+/// Plan Context (multi-week plan generation)
+/// ---------------------------------------------------------------------------
+
+/// Countdown to the athlete's goal event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GoalCountdown {
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub name: Option<String>,
+  pub date: String,
+  pub days_remaining: i64,
+  pub weeks_remaining: f64,
+}
+
+impl GoalCountdown {
+  /// Build from settings; None if no goal date is set or it can't be parsed
+  pub fn from_settings(settings: &UserSettings, today: chrono::NaiveDate) -> Option<Self> {
+    let date_str = settings.goal_date.as_ref()?;
+    let date = chrono::NaiveDate::parse_from_str(date_str, "%Y-%m-%d").ok()?;
+    let days_remaining = (date - today).num_days();
+
+    Some(Self {
+      name: settings.goal_name.clone(),
+      date: date_str.clone(),
+      days_remaining,
+      weeks_remaining: (days_remaining as f64 / 7.0 * 10.0).round() / 10.0,
+    })
+  }
+}
+
+/// One day of the plan window with its default session type
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlanCalendarDay {
+  pub date: String,
+  pub day_of_week: String,
+  pub expected_type: String,
+}
+
+/// Extended context for generating a multi-week plan
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlanContext {
+  pub start_date: String,
+  pub end_date: String,
+  pub weeks: i64,
+
+  /// Every day in the plan window with the default weekly pattern applied
+  pub calendar: Vec<PlanCalendarDay>,
+
+  /// Current fatigue state
+  pub fatigue: FatigueContext,
+
+  /// Current rolling load and volume
+  pub training: TrainingContext,
+
+  /// Allowed durations for regulated dimensions at the current TSB band
+  pub allowed_durations: AllowedDurations,
+
+  /// Active training flags
+  pub flags: Vec<String>,
+
+  /// User settings relevant to planning
+  pub user: UserContext,
+
+  /// Goal event countdown (if a goal date is set)
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub goal: Option<GoalCountdown>,
+
+  /// Progression readiness and ceilings
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub progression_summary: Option<ProgressionSummary>,
+}
+
+impl PlanContext {
+  /// Build plan context for `weeks` weeks starting at `start_date`
+  pub fn build(
+    start_date: chrono::NaiveDate,
+    weeks: i64,
+    training_context: TrainingContext,
+    flags: TrainingFlags,
+    settings: &UserSettings,
+  ) -> Self {
+    use chrono::Datelike;
+
+    let days = weeks * 7;
+    let end_date = start_date + chrono::Duration::days(days - 1);
+
+    let calendar = (0..days)
+      .map(|offset| {
+        let date = start_date + chrono::Duration::days(offset);
+        PlanCalendarDay {
+          date: date.format("%Y-%m-%d").to_string(),
+          day_of_week: date.format("%A").to_string(),
+          expected_type: expected_session_type(date.weekday()).to_string(),
+        }
+      })
+      .collect();
+
+    let fatigue = FatigueContext::from_training_context(&training_context, &settings.tsb_bands);
+    let allowed_durations = AllowedDurations::from_tsb_band(&fatigue.tsb_band);
+
+    Self {
+      start_date: start_date.format("%Y-%m-%d").to_string(),
+      end_date: end_date.format("%Y-%m-%d").to_string(),
+      weeks,
+      calendar,
+      fatigue,
+      training: training_context,
+      allowed_durations,
+      flags: flags.to_string_list(),
+      user: UserContext {
+        max_hr: settings.max_hr,
+        lthr: settings.effective_lthr(),
+        training_days_per_week: settings.training_days_per_week,
+      },
+      goal: GoalCountdown::from_settings(settings, start_date),
+      progression_summary: None,
+    }
+  }
+
+  /// Add progression summary (ceilings and readiness)
+  pub fn with_progression_summary(mut self, summary: ProgressionSummary) -> Self {
+    self.progression_summary = Some(summary);
+    self
+  }
+
+  /// Serialize to JSON for the LLM prompt
+  pub fn to_json(&self) -> String {
+    serde_json::to_string_pretty(self).unwrap_or_default()
+  }
+}
+
+/// ---------------------------------------------------------------------------
+/// Plan Adherence (planned vs actual)
+/// ---------------------------------------------------------------------------
+
+use crate::llm::PlannedDay;
+use crate::strava::{WorkoutSamples, SAMPLE_INTERVAL_SECONDS};
+
+/// How a planned day played out
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PlanDayStatus {
+  /// Planned session done with the same modality
+  Completed,
+  /// Planned session, nothing recorded
+  Missed,
+  /// Trained that day, but a different modality than planned
+  Substituted,
+  /// Planned rest day
+  Rest,
+  /// Today (not yet done) or later
+  Upcoming,
+}
+
+/// Per-day comparison of plan and actual workouts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlanDayAdherence {
+  pub date: String,
+  pub planned_type: String,
+  pub actual_types: Vec<String>,
+  pub status: PlanDayStatus,
+}
+
+/// Plan vs actual summary
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct PlanAdherence {
+  pub days: Vec<PlanDayAdherence>,
+  /// Non-rest planned sessions that are due (past, or today if done)
+  pub planned_sessions: u32,
+  pub completed: u32,
+  pub missed: u32,
+  pub substituted: u32,
+  /// Planned long sessions due / completed
+  pub key_planned: u32,
+  pub key_completed: u32,
+  /// completed / planned_sessions (None until a session is due)
+  pub adherence_pct: Option<f64>,
+}
+
+/// Modality of a planned session type ("run_long" -> Run, "ride" -> Ride)
+fn planned_kind(planned_type: &str) -> ActivityKind {
+  canonical_activity(planned_type.split('_').next().unwrap_or(planned_type))
+}
+
+/// Compare planned days against actual workouts (date, activity_type)
+pub fn compute_plan_adherence(
+  plan_days: &[PlannedDay],
+  actual: &[(chrono::NaiveDate, String)],
+  today: chrono::NaiveDate,
+) -> PlanAdherence {
+  let mut result = PlanAdherence::default();
+
+  for day in plan_days {
+    let date = match chrono::NaiveDate::parse_from_str(&day.date, "%Y-%m-%d") {
+      Ok(d) => d,
+      Err(_) => continue,
+    };
+
+    let actual_types: Vec<String> = actual
+      .iter()
+      .filter(|(d, _)| *d == date)
+      .map(|(_, t)| t.clone())
+      .collect();
+
+    let is_rest = day.activity_type.eq_ignore_ascii_case("rest");
+    let kind = planned_kind(&day.activity_type);
+    let matched = actual_types.iter().any(|t| canonical_activity(t) == kind);
+
+    let status = if is_rest {
+      PlanDayStatus::Rest
+    } else if matched {
+      PlanDayStatus::Completed
+    } else if date >= today {
+      PlanDayStatus::Upcoming
+    } else if actual_types.is_empty() {
+      PlanDayStatus::Missed
+    } else {
+      PlanDayStatus::Substituted
+    };
+
+    let is_key = day.activity_type.to_lowercase().ends_with("_long");
+    match status {
+      PlanDayStatus::Completed => {
+        result.completed += 1;
+        if is_key {
+          result.key_completed += 1;
+        }
+      }
+      PlanDayStatus::Missed => result.missed += 1,
+      PlanDayStatus::Substituted => result.substituted += 1,
+      PlanDayStatus::Rest | PlanDayStatus::Upcoming => {}
+    }
+    if matches!(
+      status,
+      PlanDayStatus::Completed | PlanDayStatus::Missed | PlanDayStatus::Substituted
+    ) {
+      result.planned_sessions += 1;
+      if is_key {
+        result.key_planned += 1;
+      }
+    }
+
+    result.days.push(PlanDayAdherence {
+      date: day.date.clone(),
+      planned_type: day.activity_type.clone(),
+      actual_types,
+      status,
+    });
+  }
+
+  result.adherence_pct = if result.planned_sessions > 0 {
+    Some(result.completed as f64 / result.planned_sessions as f64 * 100.0)
+  } else {
+    None
+  };
+
+  result
+}
+
+/// ---------------------------------------------------------------------------
+/// Weekly Review Context
+/// ---------------------------------------------------------------------------
+
+use crate::progression::ProgressionHistoryEntry;
+
+/// Session counts for the reviewed week
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WeekSummary {
+  pub sessions: i32,
+  /// Distinct local days with an endurance session
+  pub training_days: i32,
+  pub expected_training_days: i64,
+  pub strength_sessions: i32,
+}
+
+/// ATL/CTL/TSB at the start of the week (end of the day before) and at its end
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct PmcDelta {
+  pub atl_start: Option<f64>,
+  pub atl_end: Option<f64>,
+  pub atl_delta: Option<f64>,
+  pub ctl_start: Option<f64>,
+  pub ctl_end: Option<f64>,
+  pub ctl_delta: Option<f64>,
+  pub tsb_start: Option<f64>,
+  pub tsb_end: Option<f64>,
+  pub tsb_delta: Option<f64>,
+}
+
+impl PmcDelta {
+  pub fn between(start: &TrainingContext, end: &TrainingContext) -> Self {
+    let delta = |a: Option<f64>, b: Option<f64>| match (a, b) {
+      (Some(a), Some(b)) => Some(b - a),
+      _ => None,
+    };
+    Self {
+      atl_start: start.atl,
+      atl_end: end.atl,
+      atl_delta: delta(start.atl, end.atl),
+      ctl_start: start.ctl,
+      ctl_end: end.ctl,
+      ctl_delta: delta(start.ctl, end.ctl),
+      tsb_start: start.tsb,
+      tsb_end: end.tsb,
+      tsb_delta: delta(start.tsb, end.tsb),
+    }
+  }
+}
+
+/// One session in the reviewed week
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReviewSession {
+  /// Local date (YYYY-MM-DD)
+  pub date: String,
+  pub activity_type: String,
+  pub duration_min: Option<f64>,
+  pub rtss: Option<f64>,
+  /// Swim zone for swims, HR zone otherwise
+  pub zone: Option<String>,
+  pub rpe: Option<i64>,
+}
+
+/// Week-level context for the weekly review (distinct from the per-workout
+/// ContextPackage)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WeeklyReviewContext {
+  pub week_start: String,
+  pub week_end: String,
+  pub summary: WeekSummary,
+  pub pmc: PmcDelta,
+
+  /// Rolling context as of the last day of the week
+  pub training: TrainingContext,
+
+  /// Training flags as of the last day of the week
+  pub flags: Vec<String>,
+
+  pub sessions: Vec<ReviewSession>,
+
+  /// Progression engine changes recorded during the week
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  pub progression_changes: Vec<ProgressionHistoryEntry>,
+
+  /// Planned vs actual (if a plan covered the week)
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub plan_adherence: Option<PlanAdherence>,
+}
+
+impl WeeklyReviewContext {
+  /// Build the context for the 7 local days starting at `week_start`.
+  /// Workouts after the week are ignored, so the week reads as it stood
+  /// when it ended.
+  pub fn build(
+    week_start: chrono::NaiveDate,
+    workouts: &[WorkoutSummary],
+    settings: &UserSettings,
+    dimensions: &[crate::progression::ProgressionDimension],
+  ) -> Self {
+    let week_end = week_start + chrono::Duration::days(6);
+    let day_before = week_start - chrono::Duration::days(1);
+    let through = |last: chrono::NaiveDate| -> Vec<WorkoutSummary> {
+      workouts
+        .iter()
+        .filter(|w| settings.local_date(&w.started_at) <= last)
+        .cloned()
+        .collect()
+    };
+
+    let through_end = through(week_end);
+    let end_at = settings.local_noon(week_end);
+    let training = TrainingContext::compute_at(&through_end, settings, end_at);
+    let before = TrainingContext::compute_at(&through(day_before), settings, settings.local_noon(day_before));
+    let flags = TrainingFlags::compute_at(&through_end, &training, settings, dimensions, end_at);
+
+    let mut in_week: Vec<&WorkoutSummary> = through_end
+      .iter()
+      .filter(|w| settings.local_date(&w.started_at) >= week_start)
+      .collect();
+    in_week.sort_by_key(|w| w.started_at);
+
+    let mut training_dates: Vec<chrono::NaiveDate> = in_week
+      .iter()
+      .filter(|w| !is_supplemental_activity(&w.activity_type))
+      .map(|w| settings.local_date(&w.started_at))
+      .collect();
+    training_dates.dedup();
+
+    let sessions = in_week
+      .iter()
+      .map(|w| ReviewSession {
+        date: settings.local_date(&w.started_at).format("%Y-%m-%d").to_string(),
+        activity_type: w.activity_type.clone(),
+        duration_min: w.duration_seconds.map(|s| (s as f64 / 60.0).round()),
+        rtss: w.rtss,
+        zone: w
+          .swim_zone
+          .map(|z| z.as_str().to_string())
+          .or_else(|| w.hr_zone.map(|z| z.as_str().to_string())),
+        rpe: w.rpe,
+      })
+      .collect();
+
+    Self {
+      week_start: week_start.format("%Y-%m-%d").to_string(),
+      week_end: week_end.format("%Y-%m-%d").to_string(),
+      summary: WeekSummary {
+        sessions: in_week.len() as i32,
+        training_days: training_dates.len() as i32,
+        expected_training_days: settings.training_days_per_week,
+        strength_sessions: training.strength_sessions,
+      },
+      pmc: PmcDelta::between(&before, &training),
+      training,
+      flags: flags.to_string_list(),
+      sessions,
+      progression_changes: Vec::new(),
+      plan_adherence: None,
+    }
+  }
+
+  /// Add progression changes recorded during the week
+  pub fn with_progression_changes(mut self, changes: Vec<ProgressionHistoryEntry>) -> Self {
+    self.progression_changes = changes;
+    self
+  }
+
+  /// Add plan vs actual for the week
+  pub fn with_plan_adherence(mut self, adherence: PlanAdherence) -> Self {
+    self.plan_adherence = Some(adherence);
+    self
+  }
+
+  /// Serialize to JSON for the LLM prompt
+  pub fn to_json(&self) -> String {
+    serde_json::to_string_pretty(self).unwrap_or_default()
+  }
+}
+
+/// ---------------------------------------------------------------------------
+/// Seasonal Comparison (same calendar window, prior years)
+/// ---------------------------------------------------------------------------
+
+/// Days either side of the workout's date that count as "the same time of year"
+pub const SEASONAL_WINDOW_DAYS: i64 = 14;
+
+/// Averages for one prior year's window and today's delta against them
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SeasonalYear {
+  pub year: i32,
+  pub workouts: usize,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub avg_pace_min_km: Option<f64>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub avg_power: Option<f64>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub avg_efficiency: Option<f64>,
+  /// Today's pace minus prior pace in sec/km (negative = faster today)
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub pace_delta_sec_km: Option<f64>,
+  /// Today's power minus prior power in watts
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub power_delta_w: Option<f64>,
+  /// Efficiency change vs prior in percent (run: lower is better, ride: higher is better)
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub efficiency_delta_pct: Option<f64>,
+}
+
+/// Year-over-year comparison for one workout. `prior_years` is empty when
+/// there's no same-type data in any earlier year's window.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SeasonalComparison {
+  pub workout_date: String,
+  pub activity_type: String,
+  pub window_days: i64,
+  pub prior_years: Vec<SeasonalYear>,
+}
+
+/// Same date `years` earlier; Feb 29 falls back to Feb 28
+fn shift_years_back(date: chrono::NaiveDate, years: i32) -> Option<chrono::NaiveDate> {
+  use chrono::Datelike;
+  let year = date.year() - years;
+  date
+    .with_year(year)
+    .or_else(|| chrono::NaiveDate::from_ymd_opt(year, date.month(), 28))
+}
+
+/// Which prior year's window (if any) a candidate date falls into.
+/// Windows can straddle New Year, so neighbouring offsets are checked too.
+fn seasonal_year_for(
+  current: chrono::NaiveDate,
+  candidate: chrono::NaiveDate,
+  window_days: i64,
+) -> Option<i32> {
+  use chrono::Datelike;
+  let diff = current.year() - candidate.year();
+  ((diff - 1).max(1)..=diff + 1)
+    .filter_map(|years| shift_years_back(current, years))
+    .find(|anchor| (candidate - *anchor).num_days().abs() <= window_days)
+    .map(|anchor| anchor.year())
+}
+
+fn mean(values: impl Iterator<Item = f64>) -> Option<f64> {
+  let values: Vec<f64> = values.collect();
+  if values.is_empty() {
+    None
+  } else {
+    Some(values.iter().sum::<f64>() / values.len() as f64)
+  }
+}
+
+/// Compare a workout against same-type workouts from prior years' windows.
+/// `candidates` should already be filtered to the same activity kind.
+pub fn compute_seasonal_comparison(
+  current: &RecentWorkoutSummary,
+  candidates: &[RecentWorkoutSummary],
+  window_days: i64,
+) -> SeasonalComparison {
+  let mut by_year: std::collections::BTreeMap<i32, Vec<&RecentWorkoutSummary>> =
+    std::collections::BTreeMap::new();
+
+  if let Ok(current_date) = chrono::NaiveDate::parse_from_str(&current.date, "%Y-%m-%d") {
+    for candidate in candidates {
+      let date = match chrono::NaiveDate::parse_from_str(&candidate.date, "%Y-%m-%d") {
+        Ok(d) => d,
+        Err(_) => continue,
+      };
+      if let Some(year) = seasonal_year_for(current_date, date, window_days) {
+        by_year.entry(year).or_default().push(candidate);
+      }
+    }
+  }
+
+  // Most recent year first
+  let prior_years = by_year
+    .into_iter()
+    .rev()
+    .map(|(year, workouts)| {
+      let avg_pace_min_km = mean(workouts.iter().filter_map(|w| w.pace_min_km));
+      let avg_power = mean(workouts.iter().filter_map(|w| w.avg_power));
+      let avg_efficiency = mean(workouts.iter().filter_map(|w| w.efficiency));
+
+      SeasonalYear {
+        year,
+        workouts: workouts.len(),
+        avg_pace_min_km,
+        avg_power,
+        avg_efficiency,
+        pace_delta_sec_km: current
+          .pace_min_km
+          .zip(avg_pace_min_km)
+          .map(|(today, prior)| (today - prior) * 60.0),
+        power_delta_w: current.avg_power.zip(avg_power).map(|(today, prior)| today - prior),
+        efficiency_delta_pct: current
+          .efficiency
+          .zip(avg_efficiency)
+          .filter(|(_, prior)| *prior > 0.0)
+          .map(|(today, prior)| (today - prior) / prior * 100.0),
+      }
+    })
+    .collect();
+
+  SeasonalComparison {
+    workout_date: current.date.clone(),
+    activity_type: current.activity_type.clone(),
+    window_days,
+    prior_years,
+  }
+}
+
+/// ---------------------------------------------------------------------------
+/// Threshold Test Detection (FTP / LTHR)
+/// ---------------------------------------------------------------------------
+
+/// Classic 20-min FTP test: FTP = 95% of the block's average power
+pub const FTP_TEST_MINUTES: i64 = 20;
+pub const FTP_FROM_20MIN_FACTOR: f64 = 0.95;
+/// Running 30-min time trial: LTHR = average HR of the final 20 minutes
+pub const LTHR_TEST_MINUTES: i64 = 30;
+pub const LTHR_TAIL_MINUTES: i64 = 20;
+/// Test block must stand this far above the rest of the session (warm-up/cool-down)
+const TEST_BLOCK_MIN_POWER_RATIO: f64 = 1.2;
+const TEST_BLOCK_MIN_HR_RATIO: f64 = 1.08;
+/// Max coefficient of variation inside the block (a test is a steady, sustained effort)
+const TEST_BLOCK_MAX_CV: f64 = 0.15;
+
+/// Which threshold a test measures
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ThresholdTestKind {
+  Ftp,
+  Lthr,
+}
+
+/// A detected threshold test and the value it implies
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TestResult {
+  pub kind: ThresholdTestKind,
+  /// Offset of the test block from the start of the workout
+  pub block_start_min: f64,
+  pub block_minutes: i64,
+  /// Average power (FTP) or HR (LTHR) over the block
+  pub block_avg: f64,
+  /// Implied FTP (watts) or LTHR (bpm)
+  pub suggested_value: i64,
+}
+
+/// Best rolling-window average: (start index, average)
+fn best_window(values: &[i64], window: usize) -> Option<(usize, f64)> {
+  if window == 0 || values.len() < window {
+    return None;
+  }
+  let mut sum: i64 = values[..window].iter().sum();
+  let mut best = (0, sum);
+  for i in window..values.len() {
+    sum += values[i] - values[i - window];
+    if sum > best.1 {
+      best = (i + 1 - window, sum);
+    }
+  }
+  Some((best.0, best.1 as f64 / window as f64))
+}
+
+/// Is the block steady and clearly harder than the rest of the session?
+fn is_sustained_max_block(values: &[i64], start: usize, window: usize, min_ratio: f64) -> bool {
+  let block = &values[start..start + window];
+  let block_mean = block.iter().sum::<i64>() as f64 / window as f64;
+  if block_mean <= 0.0 {
+    return false;
+  }
+
+  let variance = block
+    .iter()
+    .map(|v| (*v as f64 - block_mean).powi(2))
+    .sum::<f64>()
+    / window as f64;
+  if variance.sqrt() / block_mean > TEST_BLOCK_MAX_CV {
+    return false;
+  }
+
+  // A test needs a warm-up or cool-down to stand out from
+  let outside: Vec<i64> = values[..start]
+    .iter()
+    .chain(values[start + window..].iter())
+    .copied()
+    .filter(|v| *v > 0)
+    .collect();
+  if outside.is_empty() {
+    return false;
+  }
+  let outside_mean = outside.iter().sum::<i64>() as f64 / outside.len() as f64;
+  block_mean >= outside_mean * min_ratio
+}
+
+/// Detect a threshold test in a workout's 10-second samples.
+/// Rides look for a sustained 20-min maximal power block (FTP = 95%);
+/// runs look for a 30-min time trial by HR (LTHR = last 20 min average).
+pub fn detect_threshold_test(samples: &WorkoutSamples, activity_type: &str) -> Option<TestResult> {
+  // Gappy or low-resolution streams can fake a steady block
+  if !samples.is_reliable() {
+    return None;
+  }
+  let per_min = (60 / SAMPLE_INTERVAL_SECONDS) as usize;
+
+  match canonical_activity(activity_type) {
+    ActivityKind::Ride => {
+      let window = FTP_TEST_MINUTES as usize * per_min;
+      let (start, avg) = best_window(&samples.watts, window)?;
+      if !is_sustained_max_block(&samples.watts, start, window, TEST_BLOCK_MIN_POWER_RATIO) {
+        return None;
+      }
+      Some(TestResult {
+        kind: ThresholdTestKind::Ftp,
+        block_start_min: start as f64 / per_min as f64,
+        block_minutes: FTP_TEST_MINUTES,
+        block_avg: avg,
+        suggested_value: (avg * FTP_FROM_20MIN_FACTOR).round() as i64,
+      })
+    }
+    ActivityKind::Run => {
+      if !samples.has_reliable_hr() {
+        return None;
+      }
+      let window = LTHR_TEST_MINUTES as usize * per_min;
+      let (start, avg) = best_window(&samples.hr, window)?;
+      if !is_sustained_max_block(&samples.hr, start, window, TEST_BLOCK_MIN_HR_RATIO) {
+        return None;
+      }
+      let tail_len = LTHR_TAIL_MINUTES as usize * per_min;
+      let tail = &samples.hr[start + window - tail_len..start + window];
+      let tail_avg = tail.iter().sum::<i64>() as f64 / tail_len as f64;
+      Some(TestResult {
+        kind: ThresholdTestKind::Lthr,
+        block_start_min: start as f64 / per_min as f64,
+        block_minutes: LTHR_TEST_MINUTES,
+        block_avg: avg,
+        suggested_value: tail_avg.round() as i64,
+      })
+    }
+    _ => None,
+  }
+}
+
+/// ---------------------------------------------------------------------------
+/// Aerobic Decoupling (Pa:HR)
+/// ---------------------------------------------------------------------------
+
+/// Sessions shorter than this are too short for halves to drift apart
+const MIN_DECOUPLING_MINUTES: i64 = 20;
+
+/// Percent drop in output-per-heartbeat from the first half of the session
+/// to the second (positive = HR drifted up relative to output). Runs use
+/// speed, rides use power. None when the stream is unreliable, too short,
+/// or output and HR buckets don't line up.
+pub fn compute_decoupling(samples: &WorkoutSamples, activity_type: &str) -> Option<f64> {
+  if !samples.is_reliable() || !samples.has_reliable_hr() {
+    return None;
+  }
+
+  let output: Vec<f64> = match canonical_activity(activity_type) {
+    ActivityKind::Ride => samples.watts.iter().map(|&w| w as f64).collect(),
+    // Pace (min/km) inverted to speed so higher is better, like power
+    ActivityKind::Run => samples.pace.iter().map(|&p| 60.0 / p).collect(),
+    _ => return None,
+  };
+
+  let min_buckets = (MIN_DECOUPLING_MINUTES * 60 / SAMPLE_INTERVAL_SECONDS) as usize;
+  if output.len() != samples.hr.len() || output.len() < min_buckets {
+    return None;
+  }
+
+  let half = output.len() / 2;
+  let ratio = |range: std::ops::Range<usize>| -> Option<f64> {
+    let out: f64 = output[range.clone()].iter().sum();
+    let hr: i64 = samples.hr[range].iter().sum();
+    (hr > 0 && out > 0.0).then(|| out / hr as f64)
+  };
+  let first = ratio(0..half)?;
+  let second = ratio(half..output.len())?;
+
+  Some(((first - second) / first * 1000.0).round() / 10.0)
+}
+
+/// ---------------------------------------------------------------------------
+/// Fitness Trend (rolling 6-week summary)
+/// ---------------------------------------------------------------------------
+
+/// Weeks covered by the fitness trend
+pub const FITNESS_TREND_WEEKS: i64 = 6;
+/// Days of load averaged into CTL (matches TrainingContext)
+const CTL_DAYS: i64 = 42;
+/// Sessions shorter than this don't count toward PRs (strides, warm-ups)
+const PR_MIN_DURATION_MIN: f64 = 20.0;
+
+/// Totals for one 7-day block of the trend window
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WeekTrend {
+  pub week_start: chrono::NaiveDate,
+  pub workouts: usize,
+  pub hours: f64,
+  pub rtss: f64,
+}
+
+/// Pre-digested 6-week summary for the dashboard and weekly review
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FitnessTrend {
+  pub window_start: chrono::NaiveDate,
+  pub window_end: chrono::NaiveDate,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub ctl_start: Option<f64>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub ctl_end: Option<f64>,
+  /// Oldest first
+  pub weeks: Vec<WeekTrend>,
+  /// Runs faster or rides stronger than every earlier one (20+ min sessions)
+  pub pr_count: usize,
+  /// Change in run efficiency per week (pace/HR: negative = improving)
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub run_efficiency_slope: Option<f64>,
+  /// Change in outdoor ride efficiency per week (watts/HR: positive = improving)
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub ride_efficiency_slope: Option<f64>,
+  /// Same for trainer rides, kept apart since trainer power isn't road power
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub indoor_ride_efficiency_slope: Option<f64>,
+}
+
+/// Least-squares slope of y over x; None with fewer than 3 points or no spread in x
+fn linear_slope(points: &[(f64, f64)]) -> Option<f64> {
+  if points.len() < 3 {
+    return None;
+  }
+  let n = points.len() as f64;
+  let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / n;
+  let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / n;
+  let covariance: f64 = points.iter().map(|(x, y)| (x - mean_x) * (y - mean_y)).sum();
+  let variance: f64 = points.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+  if variance > 0.0 {
+    Some(covariance / variance)
+  } else {
+    None
+  }
+}
+
+/// Summarize the 6 weeks ending `today` from full workout history (history
+/// before the window feeds CTL at its start and the PR baselines). Load
+/// counts as in TrainingContext (per-sport weights, "other" load only when
+/// opted in).
+pub fn compute_fitness_trend(
+  workouts: &[RecentWorkoutSummary],
+  settings: &UserSettings,
+  today: chrono::NaiveDate,
+) -> FitnessTrend {
+  let window_start = today - chrono::Duration::days(FITNESS_TREND_WEEKS * 7 - 1);
+
+  let mut dated: Vec<(chrono::NaiveDate, &RecentWorkoutSummary)> = workouts
+    .iter()
+    .filter_map(|w| {
+      chrono::NaiveDate::parse_from_str(&w.date, "%Y-%m-%d")
+        .ok()
+        .filter(|date| *date <= today)
+        .map(|date| (date, w))
+    })
+    .collect();
+  dated.sort_by_key(|(date, _)| *date);
+
+  let load = |w: &RecentWorkoutSummary| {
+    if !settings.include_other_load && canonical_activity(&w.activity_type) == ActivityKind::Other {
+      return None;
+    }
+    w.rtss.map(|rtss| rtss * settings.load_weights.for_activity(&w.activity_type))
+  };
+
+  // CTL on a given day: daily average load over the preceding CTL_DAYS
+  let ctl_on = |day: chrono::NaiveDate| {
+    let sum: f64 = dated
+      .iter()
+      .filter(|(date, _)| *date <= day && (day - *date).num_days() < CTL_DAYS)
+      .filter_map(|(_, w)| load(w))
+      .sum();
+    if sum > 0.0 {
+      Some(sum / CTL_DAYS as f64)
+    } else {
+      None
+    }
+  };
+
+  let weeks = (0..FITNESS_TREND_WEEKS)
+    .map(|i| {
+      let week_start = window_start + chrono::Duration::days(i * 7);
+      let week_end = week_start + chrono::Duration::days(7);
+      let in_week: Vec<&RecentWorkoutSummary> = dated
+        .iter()
+        .filter(|(date, _)| *date >= week_start && *date < week_end)
+        .map(|(_, w)| *w)
+        .collect();
+      WeekTrend {
+        week_start,
+        workouts: in_week.len(),
+        hours: in_week.iter().map(|w| w.duration_min / 60.0).sum(),
+        rtss: in_week.iter().filter_map(|w| load(w)).sum(),
+      }
+    })
+    .collect();
+
+  // PRs: a new best pace (runs) or average power (rides) against everything
+  // before it, counted only when it falls inside the window
+  let mut best_pace: Option<f64> = None;
+  let mut best_power: Option<f64> = None;
+  let mut pr_count = 0;
+  for (date, w) in &dated {
+    if w.duration_min < PR_MIN_DURATION_MIN {
+      continue;
+    }
+    let is_pr = match canonical_activity(&w.activity_type) {
+      ActivityKind::Run => w.pace_min_km.filter(|p| *p > 0.0).is_some_and(|pace| {
+        let pr = best_pace.is_some_and(|best| pace < best);
+        best_pace = Some(best_pace.map_or(pace, |best| best.min(pace)));
+        pr
+      }),
+      ActivityKind::Ride => w.avg_power.filter(|p| *p > 0.0).is_some_and(|power| {
+        let pr = best_power.is_some_and(|best| power > best);
+        best_power = Some(best_power.map_or(power, |best| best.max(power)));
+        pr
+      }),
+      _ => false,
+    };
+    if is_pr && *date >= window_start {
+      pr_count += 1;
+    }
+  }
+
+  let efficiency_slope = |kind: ActivityKind, indoor: bool| {
+    let points: Vec<(f64, f64)> = dated
+      .iter()
+      .filter(|(date, w)| *date >= window_start && canonical_activity(&w.activity_type) == kind)
+      .filter(|(_, w)| kind != ActivityKind::Ride || w.is_indoor == indoor)
+      .filter_map(|(date, w)| w.efficiency.map(|e| ((*date - window_start).num_days() as f64 / 7.0, e)))
+      .collect();
+    linear_slope(&points)
+  };
+
+  FitnessTrend {
+    window_start,
+    window_end: today,
+    ctl_start: ctl_on(window_start),
+    ctl_end: ctl_on(today),
+    weeks,
+    pr_count,
+    run_efficiency_slope: efficiency_slope(ActivityKind::Run, false),
+    ride_efficiency_slope: efficiency_slope(ActivityKind::Ride, false),
+    indoor_ride_efficiency_slope: efficiency_slope(ActivityKind::Ride, true),
+  }
+}
+
+/// ---------------------------------------------------------------------------
+/// Efficiency Factor Trend
+/// ---------------------------------------------------------------------------
+
+/// Default lookback for the EF trend
+pub const EF_TREND_DEFAULT_DAYS: i64 = 90;
+
+/// One steady aerobic session on the EF trend
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EfPoint {
+  pub date: chrono::NaiveDate,
+  pub efficiency_factor: f64,
+  pub average_hr: Option<i64>,
+}
+
+/// EF over time for Z2 sessions of one modality. Rising EF at a stable HR
+/// is the plainest sign of aerobic fitness improving.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EfTrend {
+  pub activity_type: ActivityKind,
+  pub days: i64,
+  /// Oldest first
+  pub points: Vec<EfPoint>,
+  /// Least-squares change in EF per week (positive = fitter)
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub slope_per_week: Option<f64>,
+  /// The weekly slope as a percent of mean EF
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub slope_pct_per_week: Option<f64>,
+  /// Change in mean HR per week, to tell fitness from drifting effort
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub hr_slope_per_week: Option<f64>,
+}
+
+/// Fit EF over time (None slopes with fewer than 3 sessions)
+pub fn compute_ef_trend(activity_type: ActivityKind, days: i64, mut points: Vec<EfPoint>) -> EfTrend {
+  points.sort_by_key(|p| p.date);
+
+  let (slope_per_week, slope_pct_per_week, hr_slope_per_week) = match points.first() {
+    Some(first) => {
+      let weeks = |date: chrono::NaiveDate| (date - first.date).num_days() as f64 / 7.0;
+      let ef: Vec<(f64, f64)> = points.iter().map(|p| (weeks(p.date), p.efficiency_factor)).collect();
+      let hr: Vec<(f64, f64)> = points
+        .iter()
+        .filter_map(|p| p.average_hr.map(|hr| (weeks(p.date), hr as f64)))
+        .collect();
+      let slope = finite(linear_slope(&ef));
+      let mean_ef = ef.iter().map(|(_, y)| y).sum::<f64>() / ef.len() as f64;
+      let slope_pct = slope.filter(|_| mean_ef > 0.0).map(|s| s / mean_ef * 100.0);
+      (slope, finite(slope_pct), finite(linear_slope(&hr)))
+    }
+    None => (None, None, None),
+  };
+
+  EfTrend {
+    activity_type,
+    days,
+    points,
+    slope_per_week,
+    slope_pct_per_week,
+    hr_slope_per_week,
+  }
+}
+
+/// ---------------------------------------------------------------------------
+/// Normalized Weekly Load (periodization view)
+/// ---------------------------------------------------------------------------
+
+/// Default and valid number of weeks in the normalized load view
+pub const DEFAULT_NORMALIZED_LOAD_WEEKS: i64 = 12;
+pub const NORMALIZED_LOAD_WEEKS_RANGE: std::ops::RangeInclusive<i64> = 2..=52;
+
+/// Which week 100% refers to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LoadReference {
+  /// The biggest completed week in the window
+  Max,
+  /// The mean of the completed weeks in the window
+  Average,
+}
+
+impl LoadReference {
+  pub fn parse(value: &str) -> Option<Self> {
+    match value {
+      "max" => Some(LoadReference::Max),
+      "average" => Some(LoadReference::Average),
+      _ => None,
+    }
+  }
+}
+
+/// One local week of load, starting on the week start day
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NormalizedWeek {
+  pub week_start: chrono::NaiveDate,
+  pub rtss: f64,
+  /// Share of the reference week (None without a reference)
+  pub pct_of_reference: Option<f64>,
+  /// The current week, still in progress
+  pub partial: bool,
+}
+
+/// Weekly load as a percentage of a reference week, so build and recovery
+/// weeks read at a glance
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NormalizedWeeklyLoad {
+  pub reference: LoadReference,
+  /// rTSS of the reference (None with no load in the completed weeks)
+  pub reference_rtss: Option<f64>,
+  /// Oldest first, ending with the current week
+  pub weeks: Vec<NormalizedWeek>,
+}
+
+/// Sum load per local week (see WeekStart::calendar_week_start) for the
+/// `weeks` weeks ending with the current one. The reference comes from completed weeks only, so a half-done week
+/// doesn't drag the average down. Load counts as in TrainingContext
+/// (per-sport weights, "other" load only when opted in).
+pub fn compute_normalized_weekly_load(
+  workouts: &[WorkoutSummary],
+  settings: &UserSettings,
+  today: chrono::NaiveDate,
+  weeks: i64,
+  reference: LoadReference,
+) -> NormalizedWeeklyLoad {
+  let current_week = settings.week_start_day.calendar_week_start(today);
+  let first_week = current_week - chrono::Duration::days((weeks - 1) * 7);
+
+  let mut totals = vec![0.0; weeks.max(0) as usize];
+  for w in workouts {
+    if !settings.include_other_load && canonical_activity(&w.activity_type) == ActivityKind::Other {
+      continue;
+    }
+    let days_in = (settings.local_date(&w.started_at) - first_week).num_days();
+    if days_in < 0 {
+      continue;
+    }
+    if let Some(total) = totals.get_mut((days_in / 7) as usize) {
+      *total += settings.load_weights.weighted_rtss(w).unwrap_or(0.0);
+    }
+  }
+
+  let completed = &totals[..totals.len().saturating_sub(1)];
+  let reference_rtss = match reference {
+    LoadReference::Max => completed.iter().cloned().reduce(f64::max),
+    LoadReference::Average if !completed.is_empty() => Some(completed.iter().sum::<f64>() / completed.len() as f64),
+    LoadReference::Average => None,
+  }
+  .filter(|rtss| *rtss > 0.0);
+
+  let last = totals.len().saturating_sub(1);
+  NormalizedWeeklyLoad {
+    reference,
+    reference_rtss,
+    weeks: totals
+      .iter()
+      .enumerate()
+      .map(|(i, rtss)| NormalizedWeek {
+        week_start: first_week + chrono::Duration::days(i as i64 * 7),
+        rtss: *rtss,
+        pct_of_reference: reference_rtss.map(|reference| rtss / reference * 100.0),
+        partial: i == last,
+      })
+      .collect(),
+  }
+}
+
+/// ---------------------------------------------------------------------------
+/// Activity Calendar (heatmap + streaks)
+/// ---------------------------------------------------------------------------
+
+/// Longest calendar window the heatmap covers
+pub const MAX_CALENDAR_DAYS: i64 = 366;
+
+/// One day of the heatmap
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CalendarDay {
+  pub date: chrono::NaiveDate,
+  pub workouts: usize,
+  pub rtss: f64,
+}
+
+/// Per-day workout counts plus training streaks
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ActivityCalendar {
+  /// Oldest first, one entry per day (including rest days), ending today
+  pub days: Vec<CalendarDay>,
+  /// Consecutive training days up to today. Today without a workout yet
+  /// doesn't break it; the streak then runs through yesterday.
+  pub current_streak: i64,
+  /// Longest run of consecutive training days in the full history
+  pub longest_streak: i64,
+}
+
+/// Aggregate workouts by the athlete's local date for the last `days` days
+/// (today included)
+pub fn build_activity_calendar(
+  workouts: &[WorkoutSummary],
+  settings: &UserSettings,
+  today: chrono::NaiveDate,
+  days: i64,
+) -> ActivityCalendar {
+  use std::collections::BTreeMap;
+
+  let mut by_date: BTreeMap<chrono::NaiveDate, (usize, f64)> = BTreeMap::new();
+  for w in workouts {
+    let entry = by_date.entry(settings.local_date(&w.started_at)).or_default();
+    entry.0 += 1;
+    entry.1 += w.rtss.filter(|r| r.is_finite()).unwrap_or(0.0);
+  }
+
+  let start = today - chrono::Duration::days(days - 1);
+  let calendar_days = (0..days)
+    .map(|offset| {
+      let date = start + chrono::Duration::days(offset);
+      let (workouts, rtss) = by_date.get(&date).copied().unwrap_or_default();
+      CalendarDay { date, workouts, rtss: (rtss * 10.0).round() / 10.0 }
+    })
+    .collect();
+
+  // Longest: walk training dates in order, resetting on any gap
+  let mut longest_streak = 0;
+  let mut run = 0;
+  let mut previous: Option<chrono::NaiveDate> = None;
+  for &date in by_date.keys().filter(|d| **d <= today) {
+    run = match previous {
+      Some(prev) if date - prev == chrono::Duration::days(1) => run + 1,
+      _ => 1,
+    };
+    longest_streak = longest_streak.max(run);
+    previous = Some(date);
+  }
+
+  // Current: count back from today (or yesterday if today is still open)
+  let mut day = if by_date.contains_key(&today) { today } else { today - chrono::Duration::days(1) };
+  let mut current_streak = 0;
+  while by_date.contains_key(&day) {
+    current_streak += 1;
+    day -= chrono::Duration::days(1);
+  }
+
+  ActivityCalendar { days: calendar_days, current_streak, longest_streak }
+}
+
+/// ---------------------------------------------------------------------------
+/// Tests
+/// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_hr_reserve_zone_boundaries() {
+    // Rest 50, max 190: reserve 140, so 60/70/80/90% land on 134/148/162/176
+    let (rest, max_hr) = (50, 190);
+    assert_eq!(HrZone::from_hr_reserve(133, rest, max_hr), HrZone::Z1);
+    assert_eq!(HrZone::from_hr_reserve(134, rest, max_hr), HrZone::Z2);
+    assert_eq!(HrZone::from_hr_reserve(147, rest, max_hr), HrZone::Z2);
+    assert_eq!(HrZone::from_hr_reserve(148, rest, max_hr), HrZone::Z3);
+    assert_eq!(HrZone::from_hr_reserve(161, rest, max_hr), HrZone::Z3);
+    assert_eq!(HrZone::from_hr_reserve(162, rest, max_hr), HrZone::Z4);
+    assert_eq!(HrZone::from_hr_reserve(175, rest, max_hr), HrZone::Z4);
+    assert_eq!(HrZone::from_hr_reserve(176, rest, max_hr), HrZone::Z5);
+
+    // Low resting HR: 134 bpm is 71% of max (Z3) but only 60% of reserve (Z2)
+    assert_eq!(HrZone::from_hr(134, max_hr), HrZone::Z3);
+
+    // Reserve zones fall back to % of max without a resting HR
+    let reserve = UserSettings { max_hr: Some(max_hr), zone_model: ZoneModel::HrReserve, ..Default::default() };
+    assert_eq!(reserve.hr_zone_for("Run", 134), Some(HrZone::Z3));
+    let with_rest = UserSettings { resting_hr: Some(rest), ..reserve };
+    assert_eq!(with_rest.hr_zone_for("Run", 134), Some(HrZone::Z2));
+    // A resting HR alone doesn't switch models
+    let pct_max = UserSettings { zone_model: ZoneModel::PctMax, ..with_rest };
+    assert_eq!(pct_max.hr_zone_for("Run", 134), Some(HrZone::Z3));
+  }
+
+  #[test]
+  fn test_hr_zones() {
+    let max_hr = 190;
+    assert_eq!(HrZone::from_hr(100, max_hr), HrZone::Z1); // 53%
+    assert_eq!(HrZone::from_hr(120, max_hr), HrZone::Z2); // 63%
+    assert_eq!(HrZone::from_hr(140, max_hr), HrZone::Z3); // 74%
+    assert_eq!(HrZone::from_hr(165, max_hr), HrZone::Z4); // 87%
+    assert_eq!(HrZone::from_hr(180, max_hr), HrZone::Z5); // 95%
+  }
+
+  #[test]
+  fn test_running_metrics() {
+    let settings = UserSettings {
+      max_hr: Some(190),
+      lthr: Some(170),
+      ftp: None,
+      training_days_per_week: 6,
+      ..Default::default()
+    };
+
+    let metrics = WorkoutMetrics::compute(
+      "Run",
+      Some(2640),     // 44 minutes
+      Some(6000.0),   // 6 km
+      Some(139),      // avg HR
+      None,           // no watts
+      &[],            // no HR stream
+      &settings,
+    );
+
+    // Pace should be ~7.33 min/km
+    assert!(metrics.pace_min_per_km.is_some());
+    let pace = metrics.pace_min_per_km.unwrap();
+    assert!((pace - 7.33).abs() < 0.1);
+
+    // Should have rTSS
+    assert!(metrics.rtss.is_some());
+
+    // Should be Z3 (139/190 = 73%)
+    assert_eq!(metrics.hr_zone, Some(HrZone::Z3));
+
+    // No cycling metrics
+    assert!(metrics.kj.is_none());
+    assert!(metrics.speed_kmh.is_none());
+  }
+
+  #[test]
+  fn test_stream_rtss_scores_surges_higher_than_flat_average() {
+    // 40 min of 3min @ 180 / 2min @ 120 (avg 156) vs a flat 156 stream
+    let surgy: Vec<i64> = (0..240)
+      .map(|i| if i % 30 < 18 { 180 } else { 120 })
+      .collect();
+    let avg = surgy.iter().sum::<i64>() / surgy.len() as i64;
+    assert_eq!(avg, 156);
+    let flat = vec![avg; surgy.len()];
+
+    let surgy_rtss = compute_rtss_from_stream(&surgy, 170, 40.0).unwrap();
+    let flat_rtss = compute_rtss_from_stream(&flat, 170, 40.0).unwrap();
+    assert!(surgy_rtss > flat_rtss, "{} vs {}", surgy_rtss, flat_rtss);
+
+    // A flat stream matches the average-based formula
+    let avg_formula = 40.0 * (156.0_f64 / 170.0).powi(2) / 60.0 * 100.0;
+    assert!((flat_rtss - avg_formula).abs() < 1e-9);
+  }
+
+  #[test]
+  fn test_stream_rtss_ignores_dropouts_and_empty_streams() {
+    assert!(compute_rtss_from_stream(&[], 170, 40.0).is_none());
+    assert!(compute_rtss_from_stream(&[0, 0], 170, 40.0).is_none());
+    assert_eq!(
+      compute_rtss_from_stream(&[0, 170, 170], 170, 60.0),
+      Some(100.0)
+    );
+  }
+
+  #[test]
+  fn test_metrics_prefer_hr_stream_for_rtss() {
+    let settings = UserSettings {
+      max_hr: Some(190),
+      lthr: Some(170),
+      ..Default::default()
+    };
+    let surgy: Vec<i64> = (0..240).map(|i| if i % 30 < 18 { 180 } else { 120 }).collect();
+
+    let from_avg =
+      WorkoutMetrics::compute("Run", Some(2400), Some(8000.0), Some(156), None, &[], &settings);
+    let from_stream =
+      WorkoutMetrics::compute("Run", Some(2400), Some(8000.0), Some(156), None, &surgy, &settings);
+    assert!(from_stream.rtss.unwrap() > from_avg.rtss.unwrap());
+  }
+
+  #[test]
+  fn test_tss_by_zone_separates_intervals_from_easy_running() {
+    let settings = UserSettings {
+      max_hr: Some(190),
+      lthr: Some(170),
+      ..Default::default()
+    };
+    // 10 min warm-up at 125 (Z2), then 3min @ 175 (Z5) / 2min @ 160 (Z4) repeats
+    let intervals: Vec<i64> = (0..360)
+      .map(|i| if i < 60 { 125 } else if (i - 60) % 30 < 18 { 175 } else { 160 })
+      .collect();
+    let easy = vec![125; 360];
+
+    let hard = WorkoutMetrics::compute("Run", Some(3600), Some(12000.0), Some(160), None, &intervals, &settings);
+    let zones = hard.tss_by_zone.unwrap();
+    assert!((zones.total() - hard.rtss.unwrap()).abs() < 1e-9);
+    assert!(zones.z4 + zones.z5 > 0.8 * zones.total(), "{:?}", zones);
+
+    let steady = WorkoutMetrics::compute("Run", Some(3600), Some(10000.0), Some(125), None, &easy, &settings);
+    let zones = steady.tss_by_zone.unwrap();
+    assert!((zones.z2 - zones.total()).abs() < 1e-9, "{:?}", zones);
+
+    // No stream: no breakdown
+    let no_stream = WorkoutMetrics::compute("Run", Some(3600), Some(10000.0), Some(125), None, &[], &settings);
+    assert!(no_stream.tss_by_zone.is_none());
+  }
+
+  #[test]
+  fn test_swim_zones_from_pace_relative_to_css() {
+    // CSS of 1:40/100m
+    let css = 100.0;
+    assert_eq!(SwimZone::from_pace(120.0, css), SwimZone::Recovery);
+    assert_eq!(SwimZone::from_pace(110.0, css), SwimZone::Aerobic);
+    assert_eq!(SwimZone::from_pace(106.0, css), SwimZone::Aerobic);
+    assert_eq!(SwimZone::from_pace(103.0, css), SwimZone::Threshold);
+    assert_eq!(SwimZone::from_pace(100.0, css), SwimZone::Threshold);
+    assert_eq!(SwimZone::from_pace(97.0, css), SwimZone::Threshold);
+    assert_eq!(SwimZone::from_pace(94.0, css), SwimZone::Vo2);
+    for zone in [SwimZone::Recovery, SwimZone::Aerobic, SwimZone::Threshold, SwimZone::Vo2] {
+      assert_eq!(SwimZone::parse(zone.as_str()), Some(zone));
+    }
+
+    // 2000m in 40 min = 2:00/100m: recovery against a 1:40 CSS
+    let settings = UserSettings { css_pace_sec_per_100m: Some(css), ..Default::default() };
+    let swim = WorkoutMetrics::compute("Swim", Some(2400), Some(2000.0), None, None, &[], &settings);
+    assert!((swim.swim_pace_sec_per_100m.unwrap() - 120.0).abs() < 1e-9);
+    assert_eq!(swim.swim_zone, Some(SwimZone::Recovery));
+
+    // Pace without a CSS, and no swim metrics on runs
+    let no_css = WorkoutMetrics::compute("Swim", Some(2400), Some(2000.0), None, None, &[], &UserSettings::default());
+    assert!(no_css.swim_pace_sec_per_100m.is_some());
+    assert!(no_css.swim_zone.is_none());
+    let run = WorkoutMetrics::compute("Run", Some(2400), Some(8000.0), None, None, &[], &settings);
+    assert!(run.swim_pace_sec_per_100m.is_none());
+
+    // Swim time counts toward the intensity distribution by pace zone
+    let swim_summary = WorkoutSummary {
+      started_at: chrono::Utc::now() - chrono::Duration::hours(2),
+      activity_type: "Swim".to_string(),
+      duration_seconds: Some(3600),
+      hr_zone: Some(HrZone::Z3),
+      swim_zone: Some(SwimZone::Threshold),
+      ..Default::default()
+    };
+    let ctx = TrainingContext::compute(&[swim_summary], &UserSettings::default());
+    assert_eq!(ctx.intensity_distribution.z4_pct, 100.0);
+  }
+
+  #[test]
+  fn test_hr_dropout_suppresses_zone_metrics() {
+    let settings = UserSettings { max_hr: Some(190), lthr: Some(170), ..Default::default() };
+    // 50% of the run with no HR: strap dropped
+    let hr: Vec<i64> = (0..360).map(|i| if i % 2 == 0 { 150 } else { 0 }).collect();
+    let samples = WorkoutSamples {
+      hr: hr.clone(),
+      pace: vec![5.0; 360],
+      hr_coverage_pct: Some(50.0),
+      ..Default::default()
+    };
+    assert!(!samples.has_reliable_hr());
+
+    let hr_reliable = samples.has_reliable_hr();
+    let load_hr = samples.clone().into_load_hr();
+    assert!(load_hr.is_empty());
+    // The watch's session average (160) saw more than the surviving samples
+    let metrics = WorkoutMetrics::compute("Run", Some(3600), Some(12000.0), Some(160), None, &load_hr, &settings)
+      .with_hr_coverage(hr_reliable);
+    assert!(metrics.hr_low_confidence);
+    assert!(metrics.hr_zone.is_none());
+    assert!(metrics.tss_by_zone.is_none());
+    // Load is the average-HR estimate over the full hour, not the half of
+    // the stream that survived
+    let expected = 60.0 * (160.0f64 / 170.0).powi(2) / 60.0 * 100.0;
+    assert!((metrics.rtss.unwrap() - expected).abs() < 1e-9);
+    let sparse = WorkoutMetrics::compute("Run", Some(3600), Some(12000.0), Some(160), None, &hr, &settings);
+    assert!(sparse.rtss.unwrap() < expected);
+    assert!(compute_decoupling(&samples, "Run").is_none());
+
+    let covered = WorkoutMetrics::compute("Run", Some(3600), Some(12000.0), Some(150), None, &hr, &settings)
+      .with_hr_coverage(true);
+    assert!(!covered.hr_low_confidence);
+    assert!(covered.hr_zone.is_some());
+  }
+
+  #[test]
+  fn test_rpe_estimate_scales_with_effort() {
+    let easy = estimate_rtss_from_rpe("Run", Some(3), Some(3600)).unwrap();
+    let hard = estimate_rtss_from_rpe("Run", Some(7), Some(3600)).unwrap();
+    assert!((hard - 100.0).abs() < 1e-9);
+    assert!(hard > easy * 2.0);
+
+    assert!(estimate_rtss_from_rpe("Run", None, Some(3600)).is_none());
+    assert!(estimate_rtss_from_rpe("Run", Some(0), Some(3600)).is_none());
+    assert!(estimate_rtss_from_rpe("WeightTraining", Some(8), Some(3600)).is_none());
+  }
+
+  #[test]
+  fn test_hard_and_easy_hrless_sessions_of_equal_length_differ() {
+    // 50 min intervals at RPE 8 vs a 50 min jog at RPE 4: 400 vs 200 AU
+    let intervals = estimate_rtss_from_rpe("Run", Some(8), Some(3000)).unwrap();
+    let jog = estimate_rtss_from_rpe("Run", Some(4), Some(3000)).unwrap();
+    assert!((intervals - 400.0 * SRPE_TO_RTSS).abs() < 1e-9);
+    assert!((jog - 200.0 * SRPE_TO_RTSS).abs() < 1e-9);
+    assert!(intervals > jog * 1.9, "{} vs {}", intervals, jog);
+  }
+
+  #[test]
+  fn test_long_run_compares_against_other_long_runs() {
+    let run = |date: &str, duration_min: f64, rtss: f64| RecentWorkoutSummary {
+      date: date.to_string(),
+      activity_type: "Run".to_string(),
+      duration_min,
+      avg_power: None,
+      avg_hr: Some(140),
+      pace_min_km: Some(6.0),
+      pace_min_mi: None,
+      rtss: Some(rtss),
+      efficiency: None,
+      is_indoor: false,
+    };
+    let target = ComparisonTarget {
+      date: chrono::NaiveDate::from_ymd_opt(2025, 3, 30).unwrap(),
+      duration_min: 90.0,
+      rtss: Some(105.0),
+    };
+
+    // Recent shakeouts vs older long runs
+    let candidates = vec![
+      run("2025-03-29", 20.0, 15.0),
+      run("2025-03-27", 25.0, 20.0),
+      run("2025-03-25", 20.0, 15.0),
+      run("2025-03-23", 85.0, 95.0),
+      run("2025-03-16", 90.0, 100.0),
+      run("2025-03-09", 80.0, 90.0),
+    ];
+    let selected = target.select(candidates, 3);
+    let dates: Vec<&str> = selected.iter().map(|w| w.date.as_str()).collect();
+    assert_eq!(dates, vec!["2025-03-23", "2025-03-16", "2025-03-09"]);
+
+    // Among equally comparable sessions, recency wins
+    let ties = vec![run("2025-03-01", 90.0, 105.0), run("2025-03-28", 90.0, 105.0)];
+    assert_eq!(target.select(ties, 1)[0].date, "2025-03-28");
+  }
+
+  #[test]
+  fn test_cycling_metrics() {
+    let settings = UserSettings {
+      max_hr: Some(190),
+      lthr: Some(170),
+      ftp: Some(250),
+      training_days_per_week: 6,
+      ..Default::default()
+    };
+
+    let metrics = WorkoutMetrics::compute(
+      "Ride",
+      Some(2700),     // 45 minutes
+      Some(20600.0),  // 20.6 km
+      Some(126),      // avg HR
+      Some(180.0),    // 180 watts
+      &[],            // no HR stream
+      &settings,
+    );
+
+    // kJ should be 180 * 2700 / 1000 = 486
+    assert!(metrics.kj.is_some());
+    let kj = metrics.kj.unwrap();
+    assert!((kj - 486.0).abs() < 1.0);
+
+    // Speed should be ~27.5 km/h
+    assert!(metrics.speed_kmh.is_some());
+
+    // Efficiency: watts/hr = 180/126 ≈ 1.43
+    assert!(metrics.efficiency.is_some());
+    let eff = metrics.efficiency.unwrap();
+    assert!((eff - 1.43).abs() < 0.1);
+
+    // No running metrics
+    assert!(metrics.pace_min_per_km.is_none());
+  }
+
+  #[test]
+  fn test_virtual_ride_has_no_speed() {
+    let settings = UserSettings { max_hr: Some(190), ..Default::default() };
+
+    let metrics = WorkoutMetrics::compute("VirtualRide", Some(3600), Some(32000.0), Some(135), Some(200.0), &[], &settings);
+    assert!(metrics.speed_kmh.is_none());
+    assert_eq!(metrics.kj, Some(720.0));
+    assert!(metrics.efficiency.is_some());
+
+    // A "Ride" on the trainer (Strava's `trainer` flag) loses speed too
+    let trainer = WorkoutMetrics::compute("Ride", Some(3600), Some(32000.0), Some(135), Some(200.0), &[], &settings);
+    assert!(trainer.speed_kmh.is_some());
+    assert!(trainer.with_indoor(true).speed_kmh.is_none());
+
+    assert!(is_indoor_activity("Virtual Ride"));
+    assert!(is_indoor_activity("Treadmill"));
+    assert!(!is_indoor_activity("GravelRide"));
+  }
+
+  #[test]
+  fn test_indoor_rides_compared_separately() {
+    let ride = |days_ago: i64, watts: f64, is_indoor: bool| RecentWorkoutSummary {
+      activity_type: "Ride".to_string(),
+      avg_power: Some(watts),
+      pace_min_km: None,
+      is_indoor,
+      ..recent_workout(days_ago)
+    };
+    let recent = vec![ride(1, 210.0, true), ride(3, 160.0, false)];
+    let settings = UserSettings::default();
+    let metrics = WorkoutMetrics::compute("Ride", Some(3600), Some(30000.0), Some(135), Some(165.0), &[], &settings);
+    let package = ContextPackage::build(
+      "Ride",
+      &chrono::Utc::now(),
+      Some(3600),
+      Some(30000.0),
+      Some(135),
+      Some(165.0),
+      &metrics,
+      TrainingContext::compute(&[], &settings),
+      TrainingFlags::default(),
+      1.0,
+      &settings,
+      recent,
+      vec![],
+    );
+
+    // Outdoor ride skips yesterday's trainer session
+    let card = package.performance.clone().unwrap();
+    assert_eq!((card.comparison_value.as_str(), card.delta.as_str()), ("160W", "+5W"));
+
+    let card = package.with_indoor(true).performance.unwrap();
+    assert_eq!((card.comparison_value.as_str(), card.delta.as_str()), ("210W", "-45W"));
+    assert_eq!(card.direction.as_deref(), Some("declined"));
+
+    // Trainer efficiency trends apart from road efficiency
+    let today = chrono::Utc::now().date_naive();
+    let trend_ride = |days_ago: i64, efficiency: f64, is_indoor: bool| RecentWorkoutSummary {
+      efficiency: Some(efficiency),
+      ..ride(days_ago, 180.0, is_indoor)
+    };
+    let workouts = vec![
+      trend_ride(21, 1.20, false),
+      trend_ride(20, 1.60, true),
+      trend_ride(14, 1.25, false),
+      trend_ride(13, 1.55, true),
+      trend_ride(7, 1.30, false),
+      trend_ride(6, 1.50, true),
+    ];
+    let trend = compute_fitness_trend(&workouts, &settings, today);
+    assert!(trend.ride_efficiency_slope.unwrap() > 0.0);
+    assert!(trend.indoor_ride_efficiency_slope.unwrap() < 0.0);
+  }
+
+  #[test]
+  fn test_lthr_fallback() {
+    let settings = UserSettings {
+      max_hr: Some(190),
+      lthr: None, // Not set
+      ftp: None,
+      training_days_per_week: 6,
+      ..Default::default()
+    };
+
+    // Should fall back to 93% of max = 177
+    assert_eq!(settings.effective_lthr(), Some(176)); // 190 * 0.93 = 176.7 -> 176
+  }
+
+  #[test]
+  fn test_custom_lthr_fallback_pct() {
+    let settings = UserSettings {
+      max_hr: Some(190),
+      lthr_pct_of_max: 0.88,
+      ..Default::default()
+    };
+    assert_eq!(settings.effective_lthr(), Some(167)); // 190 * 0.88 = 167.2
+
+    // An explicit LTHR still wins over any fallback
+    let explicit = UserSettings {
+      lthr: Some(172),
+      ..settings.clone()
+    };
+    assert_eq!(explicit.effective_lthr(), Some(172));
+  }
+
+  #[test]
+  fn test_sport_specific_lthr_fallback() {
+    let settings = UserSettings {
+      max_hr: Some(190),
+      ride_lthr_pct_of_max: Some(0.88),
+      ..Default::default()
+    };
+    assert_eq!(settings.effective_lthr_for("VirtualRide"), Some(167));
+    assert_eq!(settings.effective_lthr_for("Run"), Some(176));
+    assert_eq!(settings.effective_lthr_for("Swim"), Some(176));
+
+    // Lower ride threshold -> higher rTSS for the same ride HR
+    let default_settings = UserSettings {
+      max_hr: Some(190),
+      ..Default::default()
+    };
+    let custom = WorkoutMetrics::compute("Ride", Some(3600), None, Some(150), None, &[], &settings);
+    let default = WorkoutMetrics::compute("Ride", Some(3600), None, Some(150), None, &[], &default_settings);
+    assert!(custom.rtss.unwrap() > default.rtss.unwrap());
+  }
+
+  #[test]
+  fn test_run_power_drives_tss_with_run_critical_power() {
+    let settings = UserSettings {
+      max_hr: Some(190),
+      ftp: Some(300),
+      sport_settings: vec![SportSettings { sport: ActivityKind::Run, max_hr: None, lthr: None, ftp: Some(250) }],
+      ..Default::default()
+    };
+    // 1 hour at 225 W against a 250 W critical power: IF 0.9 = 81 TSS
+    let run = WorkoutMetrics::compute("Run", Some(3600), Some(12000.0), Some(150), Some(225.0), &[], &settings);
+    assert!((run.rtss.unwrap() - 81.0).abs() < 1e-9);
+    assert_eq!(settings.power_threshold_for("TrailRun"), Some(250));
+
+    // The cycling FTP never stands in for run power: HR-based instead
+    let ftp_only = UserSettings { sport_settings: vec![], ..settings.clone() };
+    assert_eq!(ftp_only.power_threshold_for("Run"), None);
+    let hr_based = WorkoutMetrics::compute("Run", Some(3600), Some(12000.0), Some(150), Some(225.0), &[], &ftp_only);
+    let no_power = WorkoutMetrics::compute("Run", Some(3600), Some(12000.0), Some(150), None, &[], &ftp_only);
+    assert_eq!(hr_based.rtss, no_power.rtss);
+
+    // Run power reaches the coach with its intensity factor
+    let package = ContextPackage::build(
+      "Run",
+      &chrono::Utc::now(),
+      Some(3600),
+      Some(12000.0),
+      Some(150),
+      Some(225.0),
+      &run,
+      TrainingContext::compute(&[], &settings),
+      TrainingFlags::default(),
+      1.0,
+      &settings,
+      vec![],
+      vec![],
+    );
+    assert_eq!(package.workout.avg_watts, Some(225.0));
+    assert!((package.workout.intensity_factor.unwrap() - 0.9).abs() < 1e-9);
+  }
+
+  #[test]
+  fn test_sport_overrides_pick_thresholds_by_activity() {
+    let settings = UserSettings {
+      max_hr: Some(190),
+      lthr: Some(172),
+      ftp: Some(250),
+      sport_settings: vec![
+        SportSettings { sport: ActivityKind::Run, max_hr: None, lthr: Some(174), ftp: None },
+        SportSettings { sport: ActivityKind::Ride, max_hr: Some(180), lthr: Some(162), ftp: Some(240) },
+      ],
+      ..Default::default()
+    };
+    assert_eq!(settings.effective_lthr_for("VirtualRide"), Some(162));
+    assert_eq!(settings.effective_lthr_for("TrailRun"), Some(174));
+    assert_eq!(settings.max_hr_for("Ride"), Some(180));
+    assert_eq!(settings.max_hr_for("Run"), Some(190)); // no run max_hr override
+    assert_eq!(settings.ftp_for("Ride"), Some(240));
+    // No swim override: globals apply
+    assert_eq!(settings.effective_lthr_for("Swim"), Some(172));
+
+    // Same 130 bpm: Z3 against the cycling max, Z2 against the running max
+    let ride = WorkoutMetrics::compute("Ride", Some(3600), None, Some(130), None, &[], &settings);
+    let run = WorkoutMetrics::compute("Run", Some(3600), Some(10000.0), Some(130), None, &[], &settings);
+    assert_eq!(ride.hr_zone, Some(HrZone::Z3)); // 130 / 180 = 72%
+    assert_eq!(run.hr_zone, Some(HrZone::Z2)); // 130 / 190 = 68%
+    assert!(ride.rtss.unwrap() > run.rtss.unwrap());
+
+    // A sport max HR without a sport LTHR scales by the fallback fraction
+    let max_only = UserSettings {
+      lthr: Some(172),
+      sport_settings: vec![SportSettings { sport: ActivityKind::Ride, max_hr: Some(180), lthr: None, ftp: None }],
+      ..Default::default()
+    };
+    assert_eq!(max_only.effective_lthr_for("Ride"), Some(167)); // 180 * 0.93
+  }
+
+  #[test]
+  fn test_sport_settings_validate() {
+    let ride = SportSettings { sport: ActivityKind::Ride, max_hr: Some(180), lthr: Some(165), ftp: Some(240) };
+    assert!(ride.validate().is_ok());
+    assert!(SportSettings { lthr: Some(185), ..ride.clone() }.validate().is_err());
+    assert!(SportSettings { ftp: Some(2000), ..ride.clone() }.validate().is_err());
+    assert!(SportSettings { sport: ActivityKind::Other, ..ride }.validate().is_err());
+  }
+
+  #[test]
+  fn test_yoga_gets_no_endurance_metrics() {
+    let settings = UserSettings {
+      max_hr: Some(190),
+      lthr: Some(170),
+      ftp: None,
+      training_days_per_week: 6,
+      ..Default::default()
+    };
+
+    // Yoga with a bogus distance and HR from the watch
+    let metrics = WorkoutMetrics::compute(
+      "Yoga",
+      Some(3600),
+      Some(1200.0),
+      Some(95),
+      None,
+      &[],
+      &settings,
+    );
+
+    assert!(is_supplemental_activity("Yoga"));
+    assert!(metrics.pace_min_per_km.is_none());
+    assert!(metrics.rtss.is_none());
+    assert!(metrics.hr_zone.is_none());
+  }
+
+  /// Every value must be finite or absent
+  fn assert_finite(values: &[Option<f64>]) {
+    for (i, value) in values.iter().enumerate() {
+      assert!(value.is_none_or(f64::is_finite), "field {} is {:?}", i, value);
+    }
+  }
+
+  fn assert_metrics_finite(m: &WorkoutMetrics) {
+    assert_finite(&[m.pace_min_per_km, m.speed_kmh, m.kj, m.rtss, m.efficiency, m.cardiac_cost]);
+  }
+
+  fn assert_context_finite(ctx: &TrainingContext) {
+    let v = &ctx.weekly_volume;
+    let d = &ctx.intensity_distribution;
+    let c = &ctx.load_confidence;
+    assert_finite(&[ctx.atl, ctx.ctl, ctx.tsb, ctx.week_over_week_delta_pct, ctx.consistency_pct]);
+    assert_finite(&[ctx.monotony, ctx.strain, ctx.longest_session.run_min, ctx.longest_session.ride_min]);
+    assert_finite(&[Some(v.total_hrs), Some(v.run_hrs), Some(v.ride_hrs), Some(v.swim_hrs)]);
+    assert_finite(&[Some(v.strength_hrs), Some(v.hike_hrs), Some(v.row_hrs), Some(v.other_hrs)]);
+    assert_finite(&[Some(d.z1_pct), Some(d.z2_pct), Some(d.z3_pct), Some(d.z4_pct), Some(d.z5_pct)]);
+    assert_finite(&[Some(c.atl_measured), Some(c.atl_estimated), Some(c.ctl_measured), Some(c.ctl_estimated)]);
+    assert_finite(&[c.measured_pct]);
+  }
+
+  #[test]
+  fn test_implausible_values_are_nulled_and_flagged() {
+    let settings = UserSettings { max_hr: Some(190), lthr: Some(170), ..Default::default() };
+
+    // 10 km in 15 minutes is 1:30/km: a GPS glitch
+    let glitch = WorkoutMetrics::compute("Run", Some(900), Some(10_000.0), Some(150), None, &[], &settings);
+    assert!(glitch.data_suspect);
+    assert!(glitch.pace_min_per_km.is_none());
+    assert!(glitch.efficiency.is_none() && glitch.efficiency_factor.is_none());
+    // HR-based values are still real
+    assert!(glitch.rtss.is_some() && glitch.cardiac_cost.is_some());
+
+    let real = WorkoutMetrics::compute("Run", Some(3000), Some(10_000.0), Some(150), None, &[], &settings);
+    assert!(!real.data_suspect);
+    assert_eq!(real.pace_min_per_km, Some(5.0));
+
+    // 150 km/h on a bike
+    let ride = WorkoutMetrics::compute("Ride", Some(3600), Some(150_000.0), Some(140), Some(200.0), &[], &settings);
+    assert!(ride.data_suspect);
+    assert!(ride.speed_kmh.is_none());
+    assert!(ride.efficiency.is_some());
+
+    // A strap spike to 400 bpm takes the HR-derived values with it
+    let spike = WorkoutMetrics::compute("Run", Some(3000), Some(10_000.0), Some(400), None, &[], &settings);
+    assert!(spike.data_suspect);
+    assert!(spike.cardiac_cost.is_none() && spike.efficiency.is_none() && spike.hr_zone.is_none());
+    assert_eq!(spike.pace_min_per_km, Some(5.0));
+
+    let serialized = serde_json::to_value(&glitch).unwrap();
+    assert_eq!(serialized["data_suspect"], true);
+    assert!(serde_json::to_value(&real).unwrap().get("data_suspect").is_none());
+  }
+
+  #[test]
+  fn test_degenerate_inputs_yield_finite_metrics() {
+    let settings = UserSettings { max_hr: Some(190), lthr: Some(170), ..Default::default() };
+
+    // Zero distance, zero HR, zero duration
+    for activity in ["Run", "Ride", "Swim", "Hike"] {
+      let m = WorkoutMetrics::compute(activity, Some(0), Some(0.0), Some(0), Some(0.0), &[0, 0], &settings);
+      assert_metrics_finite(&m);
+      assert!(m.efficiency.is_none());
+    }
+
+    // Zero HR with real distance: pace but no efficiency
+    let run = WorkoutMetrics::compute("Run", Some(1800), Some(5000.0), Some(0), None, &[], &settings);
+    assert_eq!(run.pace_min_per_km, Some(6.0));
+    assert!(run.efficiency.is_none());
+
+    // Corrupt upstream values are dropped, not passed through
+    let ride = WorkoutMetrics::compute("Ride", Some(3600), Some(30000.0), Some(140), Some(f64::NAN), &[], &settings);
+    assert_metrics_finite(&ride);
+    assert!(ride.kj.is_none() && ride.efficiency.is_none());
+    assert!(ride.rtss.is_some());
+
+    let zero_lthr = UserSettings { lthr: Some(0), max_hr: Some(0), ..Default::default() };
+    assert_metrics_finite(&WorkoutMetrics::compute("Run", Some(1800), Some(5000.0), Some(150), None, &[150], &zero_lthr));
+  }
+
+  #[test]
+  fn test_degenerate_workouts_yield_finite_context() {
+    let now = chrono::Utc::now();
+    let workout = |days_ago: i64, duration: Option<i64>, rtss: Option<f64>| WorkoutSummary {
+      started_at: now - chrono::Duration::days(days_ago),
+      activity_type: "Run".to_string(),
+      duration_seconds: duration,
+      rtss,
+      hr_zone: Some(HrZone::Z2),
+      swim_zone: None,
+      has_device_data: true,
+      rpe: None,
+    };
+
+    let settings = UserSettings { training_days_per_week: 0, ..Default::default() };
+    assert_context_finite(&TrainingContext::compute(&[], &settings));
+
+    let zero = vec![workout(1, Some(0), Some(0.0)), workout(9, Some(0), None)];
+    assert_context_finite(&TrainingContext::compute(&zero, &settings));
+
+    let corrupt = vec![workout(1, Some(3600), Some(f64::INFINITY)), workout(2, Some(3600), Some(f64::NAN))];
+    let ctx = TrainingContext::compute(&corrupt, &UserSettings::default());
+    assert_context_finite(&ctx);
+    assert!(ctx.atl.is_none());
+  }
+
+  #[test]
+  fn test_supplemental_counted_as_strength_session() {
+    let settings = UserSettings::default();
+    let now = chrono::Utc::now();
+
+    let workouts = vec![
+      WorkoutSummary {
+        started_at: now - chrono::Duration::days(1),
+        activity_type: "Yoga".to_string(),
+        duration_seconds: Some(3600),
+        rtss: None,
+        hr_zone: None,
+        swim_zone: None,
+        has_device_data: false,
+        rpe: None,
+      },
+      WorkoutSummary {
+        started_at: now - chrono::Duration::days(2),
+        activity_type: "Run".to_string(),
+        duration_seconds: Some(2400),
+        rtss: Some(40.0),
+        hr_zone: Some(HrZone::Z2),
+        swim_zone: None,
+        has_device_data: true,
+        rpe: None,
+      },
+    ];
+
+    let ctx = TrainingContext::compute(&workouts, &settings);
+    assert_eq!(ctx.strength_sessions, 1);
+    assert_eq!(ctx.workouts_this_week, 2);
+    // Yoga contributes no load
+    assert_eq!(ctx.atl, Some(40.0));
+  }
+
+  fn daily_block(loads: &[f64]) -> Vec<WorkoutSummary> {
+    let now = chrono::Utc::now();
+    loads
+      .iter()
+      .enumerate()
+      .filter(|(_, load)| **load > 0.0)
+      .map(|(day, load)| WorkoutSummary {
+        started_at: now - chrono::Duration::days(day as i64),
+        activity_type: "Run".to_string(),
+        duration_seconds: Some(3600),
+        rtss: Some(*load),
+        hr_zone: Some(HrZone::Z2),
+        swim_zone: None,
+        has_device_data: true,
+        rpe: None,
+      })
+      .collect()
+  }
+
+  #[test]
+  fn test_monotony_high_for_even_block() {
+    let workouts = daily_block(&[50.0, 52.0, 48.0, 50.0, 51.0, 49.0, 50.0]);
+    let (monotony, strain) = compute_monotony_and_strain(&workouts, &UserSettings::default(), chrono::Utc::now());
+
+    let monotony = monotony.unwrap();
+    assert!(monotony > 2.0, "monotony was {}", monotony);
+    assert!((strain.unwrap() - 350.0 * monotony).abs() < 1e-6);
+
+    let ctx = TrainingContext::compute(&workouts, &UserSettings::default());
+    let flags = TrainingFlags::compute(&workouts, &ctx, &UserSettings::default(), &[]);
+    assert!(flags.high_monotony);
+  }
+
+  #[test]
+  fn test_monotony_low_for_varied_block() {
+    // Hard, rest, easy, long, rest, moderate, rest
+    let workouts = daily_block(&[90.0, 0.0, 30.0, 120.0, 0.0, 60.0, 0.0]);
+    let (monotony, _) = compute_monotony_and_strain(&workouts, &UserSettings::default(), chrono::Utc::now());
+
+    let monotony = monotony.unwrap();
+    assert!(monotony < 1.5, "monotony was {}", monotony);
+
+    let ctx = TrainingContext::compute(&workouts, &UserSettings::default());
+    let flags = TrainingFlags::compute(&workouts, &ctx, &UserSettings::default(), &[]);
+    assert!(!flags.high_monotony);
+  }
+
+  fn context_with_load(atl: f64, ctl: f64) -> TrainingContext {
+    let mut ctx = TrainingContext::compute(&[], &UserSettings::default());
+    ctx.atl = Some(atl);
+    ctx.ctl = Some(ctl);
+    ctx
+  }
+
+  #[test]
+  fn test_volume_spike_threshold_is_configurable() {
+    // Chronic weekly load 70, this week 80.5 = 1.15x
+    let ctx = context_with_load(80.5, 10.0);
+
+    let default_flags = TrainingFlags::compute(&[], &ctx, &UserSettings::default(), &[]);
+    assert!(!default_flags.volume_spike);
+
+    let conservative = UserSettings {
+      flag_thresholds: FlagThresholds {
+        volume_spike_ratio: 1.1,
+        ..Default::default()
+      },
+      ..Default::default()
+    };
+    let flags = TrainingFlags::compute(&[], &ctx, &conservative, &[]);
+    assert!(flags.volume_spike);
+  }
+
+  #[test]
+  fn test_fatigue_and_form_flags_follow_tsb_bands() {
+    let mut ctx = TrainingContext::compute(&[], &UserSettings::default());
+    ctx.tsb = Some(-25.0);
+    let default_flags = TrainingFlags::compute(&[], &ctx, &UserSettings::default(), &[]);
+    assert!(default_flags.high_fatigue);
+    assert!(default_flags
+      .to_prioritized_list()
+      .iter()
+      .any(|(_, _, desc)| desc == "TSB indicates accumulated fatigue (<= -20)"));
+
+    // Trains through -30 and races best at +10
+    let tolerant = UserSettings {
+      tsb_bands: TsbBands { tsb_fresh_above: 10.0, tsb_moderate_below: -20.0, tsb_high_below: -35.0 },
+      ..Default::default()
+    };
+    assert!(!TrainingFlags::compute(&[], &ctx, &tolerant, &[]).high_fatigue);
+    ctx.tsb = Some(-40.0);
+    let deep = TrainingFlags::compute(&[], &ctx, &tolerant, &[]);
+    assert!(deep.high_fatigue);
+    assert!(deep
+      .to_prioritized_list()
+      .iter()
+      .any(|(_, _, desc)| desc == "TSB indicates accumulated fatigue (<= -35)"));
+
+    // +8 is peak form by default but still slightly fatigued for this athlete
+    ctx.tsb = Some(8.0);
+    assert!(TrainingFlags::compute(&[], &ctx, &UserSettings::default(), &[]).peak_form);
+    assert!(!TrainingFlags::compute(&[], &ctx, &tolerant, &[]).peak_form);
+    ctx.tsb = Some(18.0);
+    let racing = TrainingFlags::compute(&[], &ctx, &tolerant, &[]);
+    assert!(racing.peak_form);
+    assert!(racing
+      .to_prioritized_list()
+      .iter()
+      .any(|(_, _, desc)| desc == "TSB indicates good racing form (+10 to +20)"));
+  }
+
+  #[test]
+  fn test_volume_spike_priority_depends_on_phase() {
+    // Chronic weekly load 70, this week 91 = 1.3x, with Z3+ heavy intensity
+    let mut ctx = context_with_load(91.0, 10.0);
+    ctx.intensity_distribution.z3_pct = 50.0;
+    let flags = TrainingFlags::compute(&[], &ctx, &UserSettings::default(), &[]);
+    assert!(flags.volume_spike && flags.intensity_heavy);
+
+    let priority_of = |phase: Option<TrainingPhase>| {
+      let list = flags.clone().with_phase(phase).to_prioritized_list();
+      let spike = list.iter().find(|(name, _, _)| name == "volume_spike").unwrap();
+      (spike.1, list[0].0.clone())
+    };
+
+    assert_eq!(priority_of(None), (2, "volume_spike".to_string()));
+    // Planned overload: still listed, but behind intensity
+    assert_eq!(priority_of(Some(TrainingPhase::Build)), (4, "intensity_heavy".to_string()));
+    assert_eq!(priority_of(Some(TrainingPhase::Peak)), (1, "volume_spike".to_string()));
+
+    let build = flags.clone().with_phase(Some(TrainingPhase::Build)).to_string_list();
+    assert!(build.iter().any(|f| f.starts_with("volume_spike") && f.ends_with("(expected during build)")));
+  }
+
+  #[test]
+  fn test_high_rpe_on_z2_session_trips_mismatch() {
+    let z2_run = |rpe: i64| WorkoutSummary {
+      started_at: chrono::Utc::now() - chrono::Duration::days(1),
+      activity_type: "Run".to_string(),
+      duration_seconds: Some(3600),
+      rtss: Some(50.0),
+      hr_zone: Some(HrZone::Z2),
+      swim_zone: None,
+      has_device_data: true,
+      rpe: Some(rpe),
+    };
+    let ctx = TrainingContext::compute(&[], &UserSettings::default());
+
+    let flags = TrainingFlags::compute(&[z2_run(9)], &ctx, &UserSettings::default(), &[]);
+    assert!(flags.rpe_hr_mismatch);
+    assert!(flags.to_string_list().iter().any(|f| f.starts_with("rpe_hr_mismatch")));
+
+    // Matching effort, or RPE-estimated load with nothing to compare against
+    let flags = TrainingFlags::compute(&[z2_run(3)], &ctx, &UserSettings::default(), &[]);
+    assert!(!flags.rpe_hr_mismatch);
+    let no_device = WorkoutSummary { hr_zone: None, has_device_data: false, ..z2_run(9) };
+    assert!(rpe_load_gap(&no_device).is_none());
+
+    // Easy RPE on threshold-level load (100 rTSS/h = RPE 7) diverges the other way
+    let hard_load = WorkoutSummary { hr_zone: None, rtss: Some(100.0), ..z2_run(2) };
+    assert!((rpe_load_gap(&hard_load).unwrap() + 5.0).abs() < 0.01);
+  }
+
+  #[test]
+  fn test_phase_from_goal_proximity() {
+    let today = chrono::NaiveDate::from_ymd_opt(2025, 3, 1).unwrap();
+    let with_goal = |goal: &str| UserSettings { goal_date: Some(goal.to_string()), ..Default::default() };
+
+    assert_eq!(TrainingPhase::from_goal_proximity(&UserSettings::default(), today), None);
+    assert_eq!(TrainingPhase::from_goal_proximity(&with_goal("2025-09-01"), today), Some(TrainingPhase::Base));
+    assert_eq!(TrainingPhase::from_goal_proximity(&with_goal("2025-04-12"), today), Some(TrainingPhase::Build));
+    assert_eq!(TrainingPhase::from_goal_proximity(&with_goal("2025-03-10"), today), Some(TrainingPhase::Peak));
+    assert_eq!(TrainingPhase::from_goal_proximity(&with_goal("2025-02-22"), today), Some(TrainingPhase::Recovery));
+    assert_eq!(TrainingPhase::from_goal_proximity(&with_goal("2025-01-15"), today), None);
+  }
+
+  #[test]
+  fn test_volume_drop_threshold_is_configurable() {
+    // Chronic weekly load 42 (below the default 50 guard), this week 21
+    let ctx = context_with_load(21.0, 6.0);
+    assert!(!TrainingFlags::compute(&[], &ctx, &UserSettings::default(), &[]).volume_drop);
+
+    let settings = UserSettings {
+      flag_thresholds: FlagThresholds {
+        volume_drop_min_chronic: 30.0,
+        ..Default::default()
+      },
+      ..Default::default()
+    };
+    assert!(TrainingFlags::compute(&[], &ctx, &settings, &[]).volume_drop);
+  }
+
+  #[test]
+  fn test_tuesday_snapshot_before_session_is_not_a_drop() {
+    use chrono::Datelike;
+
+    // Tuesday morning, today's run not done yet; six weeks of the usual pattern before it
+    let now = utc("2024-06-11T07:00:00Z");
+    let settings = UserSettings {
+      utc_offset_minutes: Some(0),
+      flag_thresholds: FlagThresholds { volume_drop_ratio: 0.9, ..Default::default() },
+      ..Default::default()
+    };
+    let workouts: Vec<WorkoutSummary> = (1..=42)
+      .map(|d| now - chrono::Duration::days(d))
+      .filter(|at| expected_session_type(at.weekday()) != "rest")
+      .map(|started_at| WorkoutSummary {
+        started_at,
+        activity_type: "Run".to_string(),
+        duration_seconds: Some(3600),
+        rtss: Some(60.0),
+        has_device_data: true,
+        ..Default::default()
+      })
+      .collect();
+
+    let ctx = TrainingContext::compute_at(&workouts, &settings, now);
+    // Raw: 5 sessions so far vs 6 last week
+    assert!((ctx.week_over_week_delta_pct.unwrap() + 16.67).abs() < 0.1);
+    assert!((ctx.week_elapsed_fraction.unwrap() - 5.0 / 6.0).abs() < 1e-9);
+    assert!(ctx.projected_week_over_week_delta_pct.unwrap().abs() < 0.01);
+
+    let flags = TrainingFlags::compute(&workouts, &ctx, &settings, &[]);
+    assert!(!flags.volume_drop);
+
+    // Once today's run is logged the week is complete and nothing is projected
+    let mut with_today = workouts.clone();
+    with_today.push(WorkoutSummary { started_at: now, ..workouts[0].clone() });
+    let ctx = TrainingContext::compute_at(&with_today, &settings, now);
+    assert_eq!(ctx.week_elapsed_fraction, Some(1.0));
+    assert_eq!(ctx.projected_week_over_week_delta_pct, ctx.week_over_week_delta_pct);
+  }
+
+  #[test]
+  fn test_tuesday_drop_flagged_with_monday_week_start() {
+    use chrono::Datelike;
+
+    // Tuesday morning after five normal weeks, then a week with only Monday's ride
+    let now = utc("2024-06-11T07:00:00Z");
+    let settings = UserSettings {
+      utc_offset_minutes: Some(0),
+      week_start_day: WeekStart::Monday,
+      ..Default::default()
+    };
+    let workouts: Vec<WorkoutSummary> = (1..=42)
+      .filter(|d| *d == 1 || *d >= 7)
+      .map(|d| now - chrono::Duration::days(d))
+      .filter(|at| expected_session_type(at.weekday()) != "rest")
+      .map(|started_at| WorkoutSummary {
+        started_at,
+        activity_type: "Ride".to_string(),
+        duration_seconds: Some(3600),
+        rtss: Some(60.0),
+        has_device_data: true,
+        ..Default::default()
+      })
+      .collect();
+
+    let ctx = TrainingContext::compute_at(&workouts, &settings, now);
+    // One of the calendar week's six training days is behind us, five of the rolling window's six
+    assert!((ctx.week_elapsed_fraction.unwrap() - 1.0 / 6.0).abs() < 1e-9);
+    assert!((ctx.rolling_week_elapsed_fraction.unwrap() - 5.0 / 6.0).abs() < 1e-9);
+
+    let flags = TrainingFlags::compute_at(&workouts, &ctx, &settings, &[], now);
+    assert!(flags.volume_drop);
+  }
+
+  #[test]
+  fn test_polarization_gap_against_80_15_5() {
+    let skewed = IntensityDistribution { z1_pct: 20.0, z2_pct: 42.0, z3_pct: 25.0, z4_pct: 10.0, z5_pct: 3.0 };
+    let gap = PolarizationGap::compute(&skewed, &ZoneSplit::default()).unwrap();
+    assert_eq!(gap.actual, ZoneSplit { low_pct: 62.0, moderate_pct: 25.0, high_pct: 13.0 });
+    assert!((gap.gap.low_pct + 18.0).abs() < 1e-9);
+    assert!((gap.gap.moderate_pct - 10.0).abs() < 1e-9);
+    assert!((gap.gap.high_pct - 8.0).abs() < 1e-9);
+    assert_eq!(gap.summary, "62/25/13 vs 80/15/5 target: too much Z3");
+
+    // Within tolerance of the target
+    let close = IntensityDistribution { z2_pct: 78.0, z3_pct: 16.0, z4_pct: 6.0, ..Default::default() };
+    assert!(PolarizationGap::compute(&close, &ZoneSplit::default()).unwrap().summary.ends_with("on target"));
+
+    // Wired into the context from zoned workouts; no zones, no gap
+    let zoned = |zone: HrZone, minutes: i64| WorkoutSummary {
+      started_at: chrono::Utc::now() - chrono::Duration::hours(2),
+      activity_type: "Run".to_string(),
+      duration_seconds: Some(minutes * 60),
+      hr_zone: Some(zone),
+      ..Default::default()
+    };
+    let workouts = vec![zoned(HrZone::Z2, 50), zoned(HrZone::Z3, 50)];
+    let ctx = TrainingContext::compute(&workouts, &UserSettings::default());
+    let gap = ctx.polarization_gap.unwrap();
+    assert_eq!((gap.gap.low_pct, gap.gap.moderate_pct), (-30.0, 35.0));
+    assert!(TrainingContext::compute(&[], &UserSettings::default()).polarization_gap.is_none());
+
+    assert!(ZoneSplit { low_pct: 80.0, moderate_pct: 20.0, high_pct: 10.0 }.validate().is_err());
+    assert!(ZoneSplit::default().validate().is_ok());
+  }
+
+  #[test]
+  fn test_flag_thresholds_validate() {
+    assert!(FlagThresholds::default().validate().is_ok());
+    let bad_spike = FlagThresholds { volume_spike_ratio: 0.9, ..Default::default() };
+    assert!(bad_spike.validate().is_err());
+    let bad_drop = FlagThresholds { volume_drop_ratio: 1.5, ..Default::default() };
+    assert!(bad_drop.validate().is_err());
+  }
+
+  #[test]
+  fn test_recent_workout_window_validate() {
+    assert!(RecentWorkoutWindow::default().validate().is_ok());
+    let too_many = RecentWorkoutWindow { all_type_count: MAX_RECENT_WORKOUT_COUNT + 1, ..Default::default() };
+    assert!(too_many.validate().is_err());
+    let empty_window = RecentWorkoutWindow { window_days: Some(0), ..Default::default() };
+    assert!(empty_window.validate().is_err());
+  }
+
+  #[test]
+  fn test_monotony_none_without_load() {
+    assert_eq!(
+      compute_monotony_and_strain(&[], &UserSettings::default(), chrono::Utc::now()),
+      (None, None)
+    );
+  }
+
+  #[test]
+  fn test_canonical_activity_aliases() {
+    for alias in ["Run", "run", "RUN", "Running", "TrailRun", "Trail Run", "trail_run", "VirtualRun", "Treadmill"] {
+      assert_eq!(canonical_activity(alias), ActivityKind::Run, "{}", alias);
+    }
+    for alias in ["Ride", "ride", "VirtualRide", "EBikeRide", "MountainBikeRide", "GravelRide", "Cycling", "Bike"] {
+      assert_eq!(canonical_activity(alias), ActivityKind::Ride, "{}", alias);
+    }
+    for alias in ["Swim", "Swimming", "OpenWaterSwim"] {
+      assert_eq!(canonical_activity(alias), ActivityKind::Swim, "{}", alias);
+    }
+    for other in ["Walk", "Hike", "WeightTraining", "Yoga", ""] {
+      assert_eq!(canonical_activity(other), ActivityKind::Other, "{}", other);
+    }
+  }
+
+  #[test]
+  fn test_alias_variants_get_run_metrics() {
+    let settings = UserSettings {
+      max_hr: Some(190),
+      lthr: Some(170),
+      ..Default::default()
+    };
+    let metrics = WorkoutMetrics::compute("TrailRun", Some(3000), Some(10000.0), Some(150), None, &[], &settings);
+    assert!((metrics.pace_min_per_km.unwrap() - 5.0).abs() < 0.01);
+    assert!(metrics.speed_kmh.is_none());
+  }
+
+  #[test]
+  fn test_weekly_volume_uses_canonical_kind() {
+    let now = chrono::Utc::now();
+    let workouts: Vec<WorkoutSummary> = ["Running", "VirtualRide", "Walk"]
+      .iter()
+      .map(|t| WorkoutSummary {
+        started_at: now - chrono::Duration::hours(2),
+        activity_type: t.to_string(),
+        duration_seconds: Some(3600),
+        rtss: None,
+        hr_zone: None,
+        swim_zone: None,
+        has_device_data: false,
+        rpe: None,
+      })
+      .collect();
+
+    let ctx = TrainingContext::compute(&workouts, &UserSettings::default());
+    assert_eq!(ctx.weekly_volume.run_hrs, 1.0);
+    assert_eq!(ctx.weekly_volume.ride_hrs, 1.0);
+    assert_eq!(ctx.weekly_volume.other_hrs, 1.0);
+  }
+
+  #[test]
+  fn test_weekly_volume_separates_cross_training() {
+    let now = chrono::Utc::now();
+    let workouts: Vec<WorkoutSummary> = [
+      ("Run", 1.0),
+      ("Swim", 0.75),
+      ("WeightTraining", 0.5),
+      ("Yoga", 1.0),
+      ("Hike", 3.0),
+      ("Rowing", 0.5),
+      ("AlpineSki", 2.0),
+    ]
+    .iter()
+    .map(|(t, hrs)| WorkoutSummary {
+      started_at: now - chrono::Duration::hours(2),
+      activity_type: t.to_string(),
+      duration_seconds: Some((hrs * 3600.0) as i64),
+      ..Default::default()
+    })
+    .collect();
+
+    let v = TrainingContext::compute(&workouts, &UserSettings::default()).weekly_volume;
+    assert_eq!(v.run_hrs, 1.0);
+    assert_eq!(v.ride_hrs, 0.0);
+    assert_eq!(v.swim_hrs, 0.75);
+    assert_eq!(v.strength_hrs, 1.5);
+    assert_eq!(v.hike_hrs, 3.0);
+    assert_eq!(v.row_hrs, 0.5);
+    assert_eq!(v.other_hrs, 2.0);
+    assert_eq!(v.total_hrs, 8.75);
+  }
+
+  #[test]
+  fn test_load_confidence_splits_measured_and_estimated() {
+    let now = chrono::Utc::now();
+    let workouts = vec![
+      // Measured this week
+      WorkoutSummary {
+        started_at: now - chrono::Duration::days(1),
+        activity_type: "Run".to_string(),
+        rtss: Some(60.0),
+        has_device_data: true,
+        ..Default::default()
+      },
+      // Estimated this week (no HR/power)
+      WorkoutSummary {
+        started_at: now - chrono::Duration::days(2),
+        activity_type: "Run".to_string(),
+        rtss: Some(40.0),
+        has_device_data: false,
+        ..Default::default()
+      },
+      // Measured three weeks ago (CTL only)
+      WorkoutSummary {
+        started_at: now - chrono::Duration::days(20),
+        activity_type: "Ride".to_string(),
+        rtss: Some(100.0),
+        has_device_data: true,
+        ..Default::default()
+      },
+    ];
+
+    let ctx = TrainingContext::compute(&workouts, &UserSettings::default());
+    let lc = &ctx.load_confidence;
+
+    assert_eq!(lc.atl_measured, 60.0);
+    assert_eq!(lc.atl_estimated, 40.0);
+    assert!((lc.ctl_measured - 160.0 / 42.0).abs() < 1e-9);
+    assert!((lc.ctl_estimated - 40.0 / 42.0).abs() < 1e-9);
+    assert!((lc.measured_pct.unwrap() - 80.0).abs() < 1e-9);
+    // Split sums back to the totals
+    assert!((lc.atl_measured + lc.atl_estimated - ctx.atl.unwrap()).abs() < 1e-9);
+  }
+
+  #[test]
+  fn test_hike_with_hr_gets_rtss() {
+    let settings = UserSettings { max_hr: Some(190), ..Default::default() };
+    let metrics = WorkoutMetrics::compute("Hike", Some(3 * 3600), Some(12_000.0), Some(130), None, &[], &settings);
+    assert!(metrics.rtss.unwrap() > 0.0);
+    assert!(metrics.hr_zone.is_some());
   }
-}
 
-/// Workout-specific context for the LLM
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct WorkoutContext {
-  pub activity_type: String,
-  pub duration_min: Option<f64>,
-  pub distance_km: Option<f64>,
-  pub pace_min_km: Option<f64>,
-  pub avg_hr: Option<i64>,
-  pub avg_watts: Option<f64>,
-  pub rtss: Option<f64>,
-  pub zone: Option<String>,
-  pub date: String,
-  pub day_of_week: String,
-  #[serde(skip_serializing_if = "Option::is_none")]
-  pub efficiency: Option<f64>,
-  pub structure: WorkoutStructure,
-}
+  #[test]
+  fn test_other_activity_load_follows_setting() {
+    let now = chrono::Utc::now();
+    let workouts = vec![
+      WorkoutSummary {
+        started_at: now - chrono::Duration::days(1),
+        activity_type: "Run".to_string(),
+        duration_seconds: Some(3600),
+        rtss: Some(60.0),
+        has_device_data: true,
+        ..Default::default()
+      },
+      WorkoutSummary {
+        started_at: now - chrono::Duration::days(2),
+        activity_type: "Hike".to_string(),
+        duration_seconds: Some(3 * 3600),
+        rtss: Some(90.0),
+        has_device_data: true,
+        ..Default::default()
+      },
+    ];
 
-/// Summary of a recent workout for comparison context
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct RecentWorkoutSummary {
-  pub date: String,
-  pub activity_type: String,
-  pub duration_min: f64,
-  #[serde(skip_serializing_if = "Option::is_none")]
-  pub avg_power: Option<f64>,
-  #[serde(skip_serializing_if = "Option::is_none")]
-  pub avg_hr: Option<i64>,
-  #[serde(skip_serializing_if = "Option::is_none")]
-  pub pace_min_km: Option<f64>,
-  #[serde(skip_serializing_if = "Option::is_none")]
-  pub rtss: Option<f64>,
-  #[serde(skip_serializing_if = "Option::is_none")]
-  pub efficiency: Option<f64>,
-}
+    let included = TrainingContext::compute(&workouts, &UserSettings::default());
+    assert_eq!(included.atl, Some(150.0));
 
-/// Schedule context for day awareness
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ScheduleContext {
-  pub today_is: String,
-  pub tomorrow_is: String,
-  pub tomorrow_expected_type: String,
-  pub weekly_pattern: WeeklyPattern,
-}
+    let excluded_settings = UserSettings { include_other_load: false, ..Default::default() };
+    let excluded = TrainingContext::compute(&workouts, &excluded_settings);
+    assert_eq!(excluded.atl, Some(60.0));
+    assert!((excluded.ctl.unwrap() - 60.0 / 42.0).abs() < 1e-9);
+    // Hours still count as volume either way
+    assert_eq!(excluded.weekly_volume.hike_hrs, 3.0);
+  }
 
-/// Weekly training pattern
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct WeeklyPattern {
-  pub monday: String,
-  pub tuesday: String,
-  pub wednesday: String,
-  pub thursday: String,
-  pub friday: String,
-  pub saturday: String,
-  pub sunday: String,
-}
+  #[test]
+  fn test_longest_session_and_long_run_gap_share_window() {
+    let now = chrono::Utc::now();
+    let run = |days: i64, minutes: i64| WorkoutSummary {
+      started_at: now - chrono::Duration::days(days),
+      activity_type: "Run".to_string(),
+      duration_seconds: Some(minutes * 60),
+      has_device_data: true,
+      ..Default::default()
+    };
+    // A 100-min long run 25 days ago (past the 90-min ceiling), an easy run since
+    let workouts = vec![run(25, 100), run(3, 40)];
+
+    let three_weeks = UserSettings { utc_offset_minutes: Some(0), ..Default::default() };
+    assert_eq!(three_weeks.long_session_window_days, 21);
+    let ctx = TrainingContext::compute(&workouts, &three_weeks);
+    let flags = TrainingFlags::compute(&workouts, &ctx, &three_weeks, &[]);
+    assert_eq!(ctx.longest_session.run_min, Some(40.0));
+    assert!(flags.long_run_gap);
+
+    // Widening the window reaches the long run in both places
+    let four_weeks = UserSettings { long_session_window_days: 28, ..three_weeks };
+    let ctx = TrainingContext::compute(&workouts, &four_weeks);
+    let flags = TrainingFlags::compute(&workouts, &ctx, &four_weeks, &[]);
+    assert_eq!(ctx.longest_session.run_min, Some(100.0));
+    assert!(!flags.long_run_gap);
+  }
 
-impl Default for WeeklyPattern {
-  fn default() -> Self {
-    Self {
-      monday: "ride".to_string(),
-      tuesday: "run".to_string(),
-      wednesday: "ride".to_string(),
-      thursday: "run".to_string(),
-      friday: "ride".to_string(),
-      saturday: "run_long".to_string(),
-      sunday: "rest".to_string(),
-    }
+  #[test]
+  fn test_shifted_tsb_bands_reclassify_same_tsb() {
+    let mut ctx = TrainingContext::compute(&[], &UserSettings::default());
+    ctx.tsb = Some(-25.0);
+    let coggan = TsbBands::default();
+    // Trains through -30 and races best at +10
+    let tolerant = TsbBands { tsb_fresh_above: 10.0, tsb_moderate_below: -20.0, tsb_high_below: -35.0 };
+
+    let default_read = FatigueContext::from_training_context(&ctx, &coggan);
+    let tolerant_read = FatigueContext::from_training_context(&ctx, &tolerant);
+    assert_eq!(default_read.tsb_band, "high_fatigue");
+    assert_eq!(tolerant_read.tsb_band, "moderate_fatigue");
+    // The band drives the allowed durations too
+    assert_eq!(AllowedDurations::from_tsb_band(&default_read.tsb_band).z2_ride.standard, 40);
+    assert_eq!(AllowedDurations::from_tsb_band(&tolerant_read.tsb_band).z2_ride.standard, 45);
+
+    assert_eq!(coggan.band(Some(8.0)), "fresh");
+    assert_eq!(tolerant.band(Some(8.0)), "slightly_fatigued");
+    assert_eq!(tolerant.band(None), "unknown");
+
+    assert!(tolerant.validate().is_ok());
+    assert!(TsbBands { tsb_moderate_below: 10.0, ..tolerant.clone() }.validate().is_err());
+    assert!(TsbBands { tsb_high_below: -80.0, ..tolerant }.validate().is_err());
   }
-}
 
-/// Fatigue context with TSB band and trend
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct FatigueContext {
-  pub atl: Option<f64>,
-  pub ctl: Option<f64>,
-  pub tsb: Option<f64>,
-  pub tsb_band: String,
-  pub tsb_trend: String,
-}
+  #[test]
+  fn test_cycling_load_weight_reduces_ctl() {
+    let now = chrono::Utc::now();
+    // Ride-heavy block: five 80-rTSS rides and one 60-rTSS run a week
+    let workouts: Vec<WorkoutSummary> = (1..=42)
+      .filter(|day| day % 7 != 0)
+      .map(|day| WorkoutSummary {
+        started_at: now - chrono::Duration::days(day),
+        activity_type: if day % 7 == 1 { "Run" } else { "Ride" }.to_string(),
+        duration_seconds: Some(3600),
+        rtss: Some(if day % 7 == 1 { 60.0 } else { 80.0 }),
+        has_device_data: true,
+        ..Default::default()
+      })
+      .collect();
 
-impl FatigueContext {
-  /// Build fatigue context from training context and workout history
-  #[allow(dead_code)]
-  pub fn from_training_context_and_workouts(
-    ctx: &TrainingContext,
-    workouts: &[WorkoutSummary],
-  ) -> Self {
-    let tsb_band = match ctx.tsb {
-      Some(tsb) if tsb > 5.0 => "fresh",
-      Some(tsb) if tsb > -10.0 => "slightly_fatigued",
-      Some(tsb) if tsb > -20.0 => "moderate_fatigue",
-      Some(_) => "high_fatigue",
-      None => "unknown",
+    let even = TrainingContext::compute(&workouts, &UserSettings::default());
+    let weighted_settings = UserSettings {
+      load_weights: LoadWeights { ride: 0.7, ..Default::default() },
+      ..Default::default()
     };
+    let weighted = TrainingContext::compute(&workouts, &weighted_settings);
+
+    // 6 weeks x (5 rides x 80 + 1 run x 60)
+    assert!((even.ctl.unwrap() - 6.0 * 460.0 / 42.0).abs() < 1e-9);
+    assert!((weighted.ctl.unwrap() - 6.0 * (5.0 * 80.0 * 0.7 + 60.0) / 42.0).abs() < 1e-9);
+    assert!(weighted.atl.unwrap() < even.atl.unwrap());
+    // Load confidence splits the weighted load
+    let lc = &weighted.load_confidence;
+    assert!((lc.atl_measured + lc.atl_estimated - weighted.atl.unwrap()).abs() < 1e-9);
+
+    assert!(LoadWeights { ride: 0.0, ..Default::default() }.validate().is_err());
+    assert!(weighted_settings.load_weights.validate().is_ok());
+  }
 
-    // Compute TSB trend over last 7 days
-    let tsb_trend = Self::compute_tsb_trend(workouts, ctx.tsb);
+  #[test]
+  fn test_load_confidence_none_without_load() {
+    let ctx = TrainingContext::compute(&[], &UserSettings::default());
+    assert!(ctx.load_confidence.measured_pct.is_none());
+  }
 
-    Self {
-      atl: ctx.atl,
-      ctl: ctx.ctl,
-      tsb: ctx.tsb,
-      tsb_band: tsb_band.to_string(),
-      tsb_trend,
-    }
+  fn utc(s: &str) -> chrono::DateTime<chrono::Utc> {
+    chrono::DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&chrono::Utc)
   }
 
-  /// Legacy method for backward compatibility
-  pub fn from_training_context(ctx: &TrainingContext) -> Self {
-    let tsb_band = match ctx.tsb {
-      Some(tsb) if tsb > 5.0 => "fresh",
-      Some(tsb) if tsb > -10.0 => "slightly_fatigued",
-      Some(tsb) if tsb > -20.0 => "moderate_fatigue",
-      Some(_) => "high_fatigue",
-      None => "unknown",
+  #[test]
+  fn test_named_timezone_follows_dst() {
+    let settings = UserSettings {
+      timezone: Some("America/New_York".to_string()),
+      utc_offset_minutes: Some(0), // ignored while a timezone is set
+      ..Default::default()
     };
+    // 23:30 local both times: EST (UTC-5) in January, EDT (UTC-4) in July
+    let winter = utc("2024-01-16T04:30:00Z");
+    let summer = utc("2024-07-16T03:30:00Z");
+    assert_eq!(settings.local_date(&winter), chrono::NaiveDate::from_ymd_opt(2024, 1, 15).unwrap());
+    assert_eq!(settings.local_date(&summer), chrono::NaiveDate::from_ymd_opt(2024, 7, 15).unwrap());
+    assert_eq!(settings.to_local(&summer).offset().local_minus_utc(), -4 * 3600);
+
+    assert!(parse_timezone("Mars/Olympus_Mons").is_none());
+  }
 
-    Self {
-      atl: ctx.atl,
-      ctl: ctx.ctl,
-      tsb: ctx.tsb,
-      tsb_band: tsb_band.to_string(),
-      tsb_trend: "unknown".to_string(),
-    }
+  #[test]
+  fn test_late_evening_workout_bucketed_in_local_week() {
+    // UTC-5: Sunday 2024-12-08 22:30 local is Monday 03:30 UTC
+    let settings = UserSettings { utc_offset_minutes: Some(-300), ..Default::default() };
+    let now = utc("2024-12-15T17:00:00Z"); // Sunday noon local, one week later
+    let workouts = vec![WorkoutSummary {
+      started_at: utc("2024-12-09T03:30:00Z"),
+      activity_type: "Run".to_string(),
+      duration_seconds: Some(3600),
+      rtss: Some(60.0),
+      hr_zone: Some(HrZone::Z2),
+      swim_zone: None,
+      has_device_data: true,
+      rpe: None,
+    }];
+
+    // Only 6.5 days ago in UTC, but seven local days: last week
+    assert_eq!(settings.local_days_between(&workouts[0].started_at, &now), 7);
+    let ctx = TrainingContext::compute_at(&workouts, &settings, now);
+    assert_eq!(ctx.workouts_this_week, 0);
+    assert_eq!(ctx.weekly_volume.total_hrs, 0.0);
+    assert_eq!(ctx.week_over_week_delta_pct, Some(-100.0));
+    assert!(ctx.atl.is_none());
+
+    // The same instant in UTC lands in the current week
+    let utc_settings = UserSettings { utc_offset_minutes: Some(0), ..Default::default() };
+    let ctx = TrainingContext::compute_at(&workouts, &utc_settings, now);
+    assert_eq!(ctx.workouts_this_week, 1);
   }
 
-  /// Compute TSB trend direction over last 7 days
-  #[allow(dead_code)]
-  fn compute_tsb_trend(workouts: &[WorkoutSummary], current_tsb: Option<f64>) -> String {
-    let current_tsb = match current_tsb {
-      Some(tsb) => tsb,
-      None => return "unknown".to_string(),
+  #[test]
+  fn test_monday_week_start_splits_sunday_from_monday() {
+    let run = |started_at: &str| WorkoutSummary {
+      started_at: utc(started_at),
+      activity_type: "Run".to_string(),
+      duration_seconds: Some(3600),
+      rtss: Some(60.0),
+      hr_zone: Some(HrZone::Z2),
+      swim_zone: None,
+      has_device_data: true,
+      rpe: None,
     };
+    // Sunday and Monday mornings, seen on Tuesday
+    let workouts = vec![run("2024-12-08T07:00:00Z"), run("2024-12-09T07:00:00Z")];
+    let now = utc("2024-12-10T12:00:00Z");
+    let settings = |week_start_day| UserSettings { utc_offset_minutes: Some(0), week_start_day, ..Default::default() };
+
+    // Rolling: both are within the last 7 days
+    let rolling = TrainingContext::compute_at(&workouts, &settings(WeekStart::Rolling), now);
+    assert_eq!(rolling.workouts_this_week, 2);
+    assert_eq!(rolling.weekly_volume.total_hrs, 2.0);
+
+    // Monday start: Sunday's run belongs to last week
+    let monday = TrainingContext::compute_at(&workouts, &settings(WeekStart::Monday), now);
+    assert_eq!(monday.workouts_this_week, 1);
+    assert_eq!(monday.weekly_volume.total_hrs, 1.0);
+    assert_eq!(monday.week_over_week_delta_pct, Some(0.0));
+    // ATL stays a rolling 7-day load
+    assert_eq!(monday.atl, rolling.atl);
+
+    // Sunday start: both in the same week again
+    let sunday = TrainingContext::compute_at(&workouts, &settings(WeekStart::Sunday), now);
+    assert_eq!(sunday.workouts_this_week, 2);
+
+    assert_eq!(WeekStart::Monday.days_into_week(chrono::NaiveDate::from_ymd_opt(2024, 12, 9).unwrap()), 0);
+    assert_eq!(WeekStart::Monday.days_into_week(chrono::NaiveDate::from_ymd_opt(2024, 12, 8).unwrap()), 6);
+  }
 
-    // Get TSB from 7 days ago by recomputing from workouts
-    // This is a simplified approach - ideally we'd store TSB history
-    let now = chrono::Utc::now();
+  #[test]
+  fn test_ctl_stable_through_the_day() {
+    // UTC+2: sessions at the start and end of the 42-day window
+    let settings = UserSettings { utc_offset_minutes: Some(120), ..Default::default() };
+    let session = |started_at: &str, rtss: f64| WorkoutSummary {
+      started_at: utc(started_at),
+      activity_type: "Run".to_string(),
+      duration_seconds: Some(3600),
+      rtss: Some(rtss),
+      has_device_data: true,
+      ..Default::default()
+    };
+    let workouts = vec![
+      // 2024-10-25 00:30 local: day 41 on 2024-12-05, inside the window
+      session("2024-10-24T22:30:00Z", 84.0),
+      // 2024-10-24 23:30 local: day 42, outside
+      session("2024-10-24T21:30:00Z", 500.0),
+      session("2024-12-01T06:00:00Z", 42.0),
+    ];
+
+    // Just after local midnight and just before the next one on 2024-12-05
+    let early = TrainingContext::compute_at(&workouts, &settings, utc("2024-12-04T22:05:00Z"));
+    let late = TrainingContext::compute_at(&workouts, &settings, utc("2024-12-05T21:55:00Z"));
+    assert_eq!(early.ctl, Some(3.0));
+    assert_eq!(early.ctl, late.ctl);
+    assert_eq!(early.atl, late.atl);
+    assert_eq!(early.tsb, late.tsb);
+  }
 
-    // Filter workouts to 7-14 days ago (the "previous week")
-    let prev_week: Vec<_> = workouts
-      .iter()
-      .filter(|w| {
-        let days_ago = (now - w.started_at).num_days();
-        days_ago >= 7 && days_ago < 14
-      })
-      .collect();
+  #[test]
+  fn test_schedule_uses_local_day() {
+    let settings = UserSettings { utc_offset_minutes: Some(-300), ..Default::default() };
+    let started_at = utc("2024-12-09T03:30:00Z");
+    let metrics = WorkoutMetrics::compute("Run", Some(2400), Some(7000.0), None, None, &[], &settings);
+    let package = ContextPackage::build(
+      "Run",
+      &started_at,
+      Some(2400),
+      Some(7000.0),
+      None,
+      None,
+      &metrics,
+      TrainingContext::compute_at(&[], &settings, started_at),
+      TrainingFlags::default(),
+      1.0,
+      &settings,
+      vec![],
+      vec![],
+    );
 
-    if prev_week.is_empty() {
-      return "unknown".to_string();
-    }
+    assert_eq!(package.workout.date, "2024-12-08");
+    assert_eq!(package.workout.day_of_week, "Sunday");
+    assert_eq!(package.schedule.today_is, "Sunday");
+    assert_eq!(package.schedule.tomorrow_is, "Monday");
+    assert_eq!(package.schedule.tomorrow_expected_type, "ride");
+  }
 
-    // Rough approximation: compare current TSB to average rTSS from prev week
-    // This isn't perfect but gives directional sense
-    let prev_week_avg_rtss: f64 = prev_week
-      .iter()
-      .filter_map(|w| w.rtss)
-      .sum::<f64>()
-      / prev_week.len() as f64;
+  fn build_package(training_context: TrainingContext, recent_all: Vec<RecentWorkoutSummary>) -> ContextPackage {
+    build_package_with_adherence(training_context, recent_all, 1.0)
+  }
 
-    // If current TSB is improving (less negative), trend is up
-    // This is a simplified heuristic - proper implementation would track TSB history
-    if current_tsb > -10.0 && prev_week_avg_rtss < 40.0 {
-      "improving".to_string()
-    } else if current_tsb < -15.0 && prev_week_avg_rtss > 50.0 {
-      "declining".to_string()
-    } else {
-      "stable".to_string()
-    }
+  fn build_package_with_adherence(
+    training_context: TrainingContext,
+    recent_all: Vec<RecentWorkoutSummary>,
+    adherence_pct: f64,
+  ) -> ContextPackage {
+    let settings = UserSettings::default();
+    let started_at = chrono::Utc::now();
+    let metrics = WorkoutMetrics::compute("Run", Some(2400), Some(7000.0), None, None, &[], &settings);
+    ContextPackage::build(
+      "Run",
+      &started_at,
+      Some(2400),
+      Some(7000.0),
+      None,
+      None,
+      &metrics,
+      training_context,
+      TrainingFlags::default(),
+      adherence_pct,
+      &settings,
+      vec![],
+      recent_all,
+    )
   }
-}
 
-/// Prescription confidence based on signal quality
-#[allow(dead_code)]
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct PrescriptionConfidence {
-  pub level: String,  // "high" | "medium" | "low"
-  pub reason: String, // Brief explanation
-}
+  #[test]
+  fn test_imperial_units_in_workout_context() {
+    // 7 km in 40 minutes: 5:43/km is 9:12/mi
+    let package = build_package(TrainingContext::compute(&[], &UserSettings::default()), vec![]);
+    assert!(package.workout.display_units.is_none());
+    assert!(!package.to_json().contains("display_units"));
+
+    let settings = UserSettings { units: Units::Imperial, ..Default::default() };
+    let metrics = WorkoutMetrics::compute("Run", Some(2400), Some(7000.0), None, None, &[], &settings);
+    let package = ContextPackage::build(
+      "Run",
+      &chrono::Utc::now(),
+      Some(2400),
+      Some(7000.0),
+      None,
+      None,
+      &metrics,
+      TrainingContext::compute(&[], &settings),
+      TrainingFlags::default(),
+      1.0,
+      &settings,
+      vec![],
+      vec![],
+    );
+    let display = package.workout.display_units.clone().unwrap();
+    assert_eq!(display.units, Units::Imperial);
+    assert!((display.pace.unwrap() - 40.0 / 7.0 * KM_PER_MILE).abs() < 1e-9);
+    assert!((display.pace.unwrap() - 9.196).abs() < 0.001);
+    assert_eq!(display.pace_unit, "min/mi");
+    assert!((display.distance.unwrap() - 4.3496).abs() < 0.001);
+    assert_eq!(display.distance_unit, "mi");
+    // Internal metric fields are untouched
+    assert_eq!(package.workout.distance_km, Some(7.0));
+    assert_eq!(Units::parse("imperial"), Some(Units::Imperial));
+    assert_eq!(Units::parse("furlongs"), None);
+  }
 
-impl PrescriptionConfidence {
-  #[allow(dead_code)]
-  pub fn compute(
-    tsb: Option<f64>,
-    flags_count: usize,
-    adherence_pct: f64,
-    recent_workouts_count: usize,
-  ) -> Self {
-    // High confidence: clear signals, good data
-    if tsb.is_some() && flags_count <= 1 && adherence_pct > 0.8 && recent_workouts_count >= 5 {
-      return Self {
-        level: "high".to_string(),
-        reason: "Clear signals, good data".to_string(),
-      };
-    }
+  #[test]
+  fn test_select_model_escalates_only_significant_sessions() {
+    use crate::llm::{CLAUDE_MODEL, CLAUDE_MODEL_FAST};
+
+    let settings = UserSettings { max_hr: Some(190), ..Default::default() };
+    let started_at = chrono::Utc::now();
+    let recent_ride = RecentWorkoutSummary {
+      activity_type: "Ride".to_string(),
+      avg_power: Some(180.0),
+      pace_min_km: None,
+      ..recent_workout(2)
+    };
+    // 45-min Z2 recovery ride, well under recent power
+    let easy_ride = |flags: TrainingFlags| {
+      let metrics = WorkoutMetrics::compute("Ride", Some(2700), Some(20000.0), Some(120), Some(140.0), &[], &settings);
+      let context = TrainingContext::compute(&[], &settings);
+      ContextPackage::build(
+        "Ride",
+        &started_at,
+        Some(2700),
+        Some(20000.0),
+        Some(120),
+        Some(140.0),
+        &metrics,
+        context,
+        flags,
+        1.0,
+        &settings,
+        vec![recent_ride.clone()],
+        vec![],
+      )
+    };
 
-    // Low confidence: mixed signals or sparse data
-    if tsb.is_none() || flags_count >= 3 || recent_workouts_count < 3 {
-      return Self {
-        level: "low".to_string(),
-        reason: "Mixed signals or limited data".to_string(),
-      };
-    }
+    let boring = easy_ride(TrainingFlags::default());
+    assert_eq!(boring.workout.zone.as_deref(), Some("Z2"));
+    assert_eq!(select_model(&boring, &TrainingFlags::default(), None, false), CLAUDE_MODEL_FAST);
+
+    let fatigued = TrainingFlags { high_fatigue: true, ..Default::default() };
+    assert_eq!(select_model(&easy_ride(fatigued.clone()), &fatigued, None, false), CLAUDE_MODEL);
+
+    // Same ride rated a 9/10 is a hard effort
+    let hard = easy_ride(TrainingFlags::default()).with_subjective(Some(9), None);
+    assert_eq!(select_model(&hard, &TrainingFlags::default(), None, false), CLAUDE_MODEL);
+
+    // A power PR over recent rides escalates too
+    let mut pr = easy_ride(TrainingFlags::default());
+    pr.workout.avg_watts = Some(200.0);
+    assert_eq!(select_model(&pr, &TrainingFlags::default(), None, false), CLAUDE_MODEL);
+
+    // Length alone doesn't make a key session; the athlete's link does
+    let mut long = easy_ride(TrainingFlags::default());
+    long.workout.duration_min = Some(150.0);
+    assert_eq!(select_model(&long, &TrainingFlags::default(), None, false), CLAUDE_MODEL_FAST);
+    assert_eq!(select_model(&long, &TrainingFlags::default(), None, true), CLAUDE_MODEL);
+
+    // A threshold test detected in the streams
+    let test = TestResult {
+      kind: ThresholdTestKind::Ftp,
+      block_start_min: 15.0,
+      block_minutes: 20,
+      block_avg: 250.0,
+      suggested_value: 238,
+    };
+    assert_eq!(select_model(&boring, &TrainingFlags::default(), Some(&test), false), CLAUDE_MODEL);
+  }
 
-    // Medium: everything else
-    Self {
-      level: "medium".to_string(),
-      reason: "Some mixed indicators".to_string(),
+  fn recent_workout(days_ago: i64) -> RecentWorkoutSummary {
+    RecentWorkoutSummary {
+      date: (chrono::Utc::now() - chrono::Duration::days(days_ago)).format("%Y-%m-%d").to_string(),
+      activity_type: "Run".to_string(),
+      duration_min: 45.0,
+      avg_power: None,
+      avg_hr: Some(140),
+      pace_min_km: Some(5.5),
+      pace_min_mi: None,
+      rtss: Some(50.0),
+      efficiency: None,
+      is_indoor: false,
     }
   }
-}
 
-/// Allowed durations for TSB-regulated dimensions
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct AllowedDurations {
-  pub z2_ride: DurationOptions,
-}
+  #[test]
+  fn test_sparse_data_yields_low_confidence() {
+    // No workouts: no TSB, no recent history
+    let context = TrainingContext::compute(&[], &UserSettings::default());
+    let package = build_package(context, vec![recent_workout(1)]);
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct DurationOptions {
-  pub short: i32,
-  pub standard: i32,
-  pub long: i32,
-  pub recommended: String,
-}
+    assert_eq!(package.prescription_confidence.level, "low");
+    assert!(package.to_json().contains("\"prescription_confidence\""));
+  }
 
-impl AllowedDurations {
-  pub fn from_tsb_band(tsb_band: &str) -> Self {
-    let (recommended, short, standard, long) = match tsb_band {
-      "fresh" => ("long", 45, 60, 60),
-      "slightly_fatigued" => ("standard", 40, 45, 60),
-      "moderate_fatigue" => ("short", 40, 45, 45),
-      "high_fatigue" => ("short", 30, 40, 40),
-      _ => ("standard", 40, 45, 60),
+  #[test]
+  fn test_rich_data_yields_high_confidence() {
+    let now = chrono::Utc::now();
+    let workouts: Vec<WorkoutSummary> = (0..28)
+      .map(|day| WorkoutSummary {
+        started_at: now - chrono::Duration::days(day) - chrono::Duration::hours(1),
+        activity_type: "Run".to_string(),
+        duration_seconds: Some(2700),
+        rtss: Some(if day % 2 == 0 { 70.0 } else { 30.0 }),
+        hr_zone: Some(HrZone::Z2),
+        swim_zone: None,
+        has_device_data: true,
+        rpe: None,
+      })
+      .collect();
+    let context = TrainingContext::compute(&workouts, &UserSettings::default());
+    let recent: Vec<_> = (1..=7).map(recent_workout).collect();
+    let package = build_package(context.clone(), recent.clone());
+
+    assert_eq!(package.prescription_confidence.level, "high");
+
+    // Consistent training isn't enough when this week's plan is being missed
+    let package = build_package_with_adherence(context, recent, 0.5);
+    assert_eq!(package.prescription_confidence.level, "medium");
+  }
+
+  #[test]
+  fn test_short_history_flags_tsb_low_confidence() {
+    let now = chrono::Utc::now();
+    // Three weeks of steady training: ATL is real but CTL is still filling up
+    let workouts = |days: i64| -> Vec<WorkoutSummary> {
+      (0..days)
+        .map(|day| WorkoutSummary {
+          started_at: now - chrono::Duration::days(day) - chrono::Duration::hours(1),
+          activity_type: "Run".to_string(),
+          duration_seconds: Some(2700),
+          rtss: Some(50.0),
+          hr_zone: Some(HrZone::Z2),
+          has_device_data: true,
+          ..Default::default()
+        })
+        .collect()
     };
 
-    Self {
-      z2_ride: DurationOptions {
-        short,
-        standard,
-        long,
-        recommended: recommended.to_string(),
-      },
-    }
+    let young = TrainingContext::compute(&workouts(21), &UserSettings::default());
+    assert_eq!(young.data_maturity.history_days, 21);
+    assert!(young.data_maturity.tsb_low_confidence);
+    let fatigue = FatigueContext::from_training_context(&young, &TsbBands::default());
+    assert!(fatigue.tsb_low_confidence);
+    let recent: Vec<_> = (1..=7).map(recent_workout).collect();
+    let package = build_package(young, recent.clone());
+    assert!(package.fatigue.tsb_low_confidence);
+    assert_eq!(package.prescription_confidence.level, "low");
+    assert!(package.prescription_confidence.reason.contains("21 days"));
+
+    let mature = TrainingContext::compute(&workouts(35), &UserSettings::default());
+    assert_eq!(mature.data_maturity.history_days, 35);
+    assert!(!FatigueContext::from_training_context(&mature, &TsbBands::default()).tsb_low_confidence);
+    assert_eq!(build_package(mature, recent).prescription_confidence.level, "high");
   }
-}
 
-/// User context for the LLM
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct UserContext {
-  pub max_hr: Option<i64>,
-  pub lthr: Option<i64>,
-  pub training_days_per_week: i64,
-}
+  #[test]
+  fn test_oversized_context_trimmed_to_budget() {
+    let mut package = build_package(
+      TrainingContext::compute(&[], &UserSettings::default()),
+      (1..=400).map(|day| recent_workout(day % 40)).collect(),
+    );
+    package.recent_same_type = (1..=200).map(recent_workout).collect();
+    package.flags = vec!["volume_spike".to_string(), "long_run_gap".to_string()];
+    let workout_json = serde_json::to_string(&package.workout).unwrap();
+
+    let mut core = package.clone();
+    core.recent_all.clear();
+    core.recent_same_type.clear();
+    let budget = core.to_json().len() + 3_000;
+
+    let trim = package.trim_to_budget(budget);
+    assert!(trim.original_chars > budget);
+    assert!(trim.final_chars <= budget);
+    assert_eq!(trim.final_chars, package.to_json().len());
+    assert_eq!(trim.steps[0], "recent_all capped");
+    assert!(package.recent_all.len() <= 7);
+
+    // The essentials survive
+    assert_eq!(serde_json::to_string(&package.workout).unwrap(), workout_json);
+    assert_eq!(package.flags, vec!["volume_spike", "long_run_gap"]);
+
+    // A package already under budget is left alone
+    let untouched = package.trim_to_budget(budget);
+    assert!(untouched.steps.is_empty());
+    assert_eq!(untouched.original_chars, untouched.final_chars);
+  }
 
-/// Significance thresholds for detecting meaningful changes
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct SignificanceThresholds {
-  pub hr_delta_significant: i64,        // >5 beats
-  pub efficiency_delta_significant: f64, // >3%
-  pub pace_delta_significant: f64,      // >10 sec/km
-  pub power_delta_significant: f64,     // >10W
-  pub temperature_delta_significant: f64, // >5°C
-}
+  #[test]
+  fn test_confidence_cap() {
+    let low = PrescriptionConfidence::compute(None, 0, 1.0, 10);
+    assert_eq!(low.level, "low");
+    // LLM can't claim more confidence than the data supports
+    assert_eq!(low.cap("high"), "low");
+    assert_eq!(low.cap("nonsense"), "low");
+
+    let high = PrescriptionConfidence::compute(Some(0.0), 0, 0.9, 6);
+    assert_eq!(high.level, "high");
+    // LLM may be more cautious
+    assert_eq!(high.cap("Medium"), "medium");
+    assert_eq!(high.cap("high"), "high");
+  }
 
-impl Default for SignificanceThresholds {
-  fn default() -> Self {
-    Self {
-      hr_delta_significant: 5,
-      efficiency_delta_significant: 0.03,
-      pace_delta_significant: 10.0,
-      power_delta_significant: 10.0,
-      temperature_delta_significant: 5.0,
+  #[test]
+  fn test_plan_context_calendar_and_goal() {
+    let settings = UserSettings {
+      goal_name: Some("Kilimanjaro".to_string()),
+      goal_date: Some("2025-02-15".to_string()),
+      ..Default::default()
+    };
+    // 2024-12-16 is a Monday
+    let start = chrono::NaiveDate::from_ymd_opt(2024, 12, 16).unwrap();
+    let context = TrainingContext::compute(&[], &settings);
+
+    let plan = PlanContext::build(start, 2, context, TrainingFlags::default(), &settings);
+
+    assert_eq!(plan.calendar.len(), 14);
+    assert_eq!(plan.end_date, "2024-12-29");
+    assert_eq!(plan.calendar[0].day_of_week, "Monday");
+    assert_eq!(plan.calendar[0].expected_type, "ride");
+    assert_eq!(plan.calendar[6].expected_type, "rest");
+
+    let goal = plan.goal.unwrap();
+    assert_eq!(goal.days_remaining, 61);
+    assert_eq!(goal.name.as_deref(), Some("Kilimanjaro"));
+  }
+
+  fn planned(date: &str, activity_type: &str) -> PlannedDay {
+    PlannedDay {
+      date: date.to_string(),
+      activity_type: activity_type.to_string(),
+      duration_min: if activity_type == "rest" { 0 } else { 45 },
+      intensity: "Z2".to_string(),
+      purpose: None,
+      intervals: None,
     }
   }
-}
 
-impl ContextPackage {
-  /// Build a context package from workout data and computed metrics
-  pub fn build(
-    workout_type: &str,
-    started_at: &chrono::DateTime<chrono::Utc>,
-    duration_seconds: Option<i64>,
-    distance_meters: Option<f64>,
-    average_hr: Option<i64>,
-    average_watts: Option<f64>,
-    metrics: &WorkoutMetrics,
-    training_context: TrainingContext,
-    flags: TrainingFlags,
-    settings: &UserSettings,
-    recent_same_type: Vec<RecentWorkoutSummary>,
-    recent_all: Vec<RecentWorkoutSummary>,
-  ) -> Self {
-    // Compute fatigue context from training context
-    // TODO: Pass workouts to compute TSB trend
-    let fatigue = FatigueContext::from_training_context(&training_context);
-    let allowed_durations = AllowedDurations::from_tsb_band(&fatigue.tsb_band);
+  #[test]
+  fn test_plan_adherence_missed_and_swapped() {
+    let d = |day: u32| chrono::NaiveDate::from_ymd_opt(2024, 12, day).unwrap();
+    let plan = vec![
+      planned("2024-12-16", "ride"),
+      planned("2024-12-17", "run"),
+      planned("2024-12-18", "ride"),
+      planned("2024-12-19", "run"),
+      planned("2024-12-20", "ride"),
+      planned("2024-12-21", "run_long"),
+    ];
+    let actual = vec![
+      (d(16), "VirtualRide".to_string()),
+      (d(17), "Run".to_string()),
+      // 18th missed
+      (d(19), "Ride".to_string()), // swapped run for ride
+      (d(20), "Ride".to_string()),
+      (d(21), "TrailRun".to_string()),
+    ];
+
+    let result = compute_plan_adherence(&plan, &actual, d(22));
+
+    let statuses: Vec<_> = result.days.iter().map(|day| day.status).collect();
+    assert_eq!(
+      statuses,
+      vec![
+        PlanDayStatus::Completed,
+        PlanDayStatus::Completed,
+        PlanDayStatus::Missed,
+        PlanDayStatus::Substituted,
+        PlanDayStatus::Completed,
+        PlanDayStatus::Completed,
+      ]
+    );
+    assert_eq!(result.planned_sessions, 6);
+    assert_eq!(result.completed, 4);
+    assert_eq!(result.missed, 1);
+    assert_eq!(result.substituted, 1);
+    assert_eq!((result.key_planned, result.key_completed), (1, 1));
+    assert!((result.adherence_pct.unwrap() - 66.666).abs() < 0.01);
+  }
 
-    // Build schedule context
-    let schedule = Self::build_schedule(started_at);
+  #[test]
+  fn test_plan_adherence_future_and_rest_not_counted() {
+    let d = |day: u32| chrono::NaiveDate::from_ymd_opt(2024, 12, day).unwrap();
+    let plan = vec![
+      planned("2024-12-16", "rest"),
+      planned("2024-12-17", "run"),
+      planned("2024-12-18", "ride"),
+    ];
+
+    // Today is the 17th and the run isn't done yet
+    let result = compute_plan_adherence(&plan, &[], d(17));
+    assert_eq!(result.days[0].status, PlanDayStatus::Rest);
+    assert_eq!(result.days[1].status, PlanDayStatus::Upcoming);
+    assert_eq!(result.planned_sessions, 0);
+    assert!(result.adherence_pct.is_none());
+  }
 
-    // Determine workout structure
-    // For now: assume all rides are structured (TrainerRoad), runs are unstructured
-    let structure = if workout_type.to_lowercase() == "ride" {
-      WorkoutStructure {
-        is_structured: true,
-        block_type: Some("z2_steady".to_string()),
-        prescribed_target_watts: average_watts, // Use avg as proxy for target
-      }
-    } else {
-      WorkoutStructure::default()
+  #[test]
+  fn test_plan_execution_classifies_intended_vs_executed() {
+    let package = build_package(TrainingContext::compute(&[], &UserSettings::default()), vec![]);
+    let plan = vec![planned("2024-12-17", "ride"), planned("2024-12-17", "run")];
+    let run = |zone: Option<&str>, intensity_factor: Option<f64>| WorkoutContext {
+      date: "2024-12-17".to_string(),
+      zone: zone.map(str::to_string),
+      intensity_factor,
+      ..package.workout.clone()
     };
 
-    let workout = WorkoutContext {
-      activity_type: workout_type.to_string(),
-      duration_min: duration_seconds.map(|s| s as f64 / 60.0),
-      distance_km: distance_meters.map(|m| m / 1000.0),
-      pace_min_km: metrics.pace_min_per_km,
-      avg_hr: average_hr,
-      avg_watts: average_watts,
-      rtss: metrics.rtss,
-      zone: metrics.hr_zone.map(|z| z.as_str().to_string()),
-      date: started_at.format("%Y-%m-%d").to_string(),
-      day_of_week: started_at.format("%A").to_string(),
-      efficiency: metrics.efficiency,
-      structure,
-    };
+    // Intended Z2, executed Z4: matched to the run, not the ride
+    let execution = PlanExecution::compare(&plan, &run(Some("Z4"), None)).unwrap();
+    assert_eq!(execution.planned, "45 min Z2 run");
+    assert_eq!((execution.intended.as_str(), execution.executed.as_str()), ("Z2", "Z4"));
+    assert_eq!(execution.execution, IntensityExecution::HarderThanPlanned);
 
-    let user = UserContext {
-      max_hr: settings.max_hr,
-      lthr: settings.effective_lthr(),
-      training_days_per_week: settings.training_days_per_week,
-    };
+    let as_planned = PlanExecution::compare(&plan, &run(Some("Z2"), None)).unwrap();
+    assert_eq!(as_planned.execution, IntensityExecution::AsPlanned);
 
-    Self {
-      workout,
-      recent_same_type,
-      recent_all,
-      fatigue,
-      schedule,
-      allowed_durations,
-      flags: flags.to_string_list(),
-      user,
-      thresholds: SignificanceThresholds::default(),
-      oura: None,  // TODO: Fetch from database when Oura is connected
-      progression_summary: None,
+    // Without HR the power zone stands in: IF 0.6 is Z2, 0.5 is Z1
+    assert_eq!(
+      PlanExecution::compare(&plan, &run(None, Some(0.6))).unwrap().execution,
+      IntensityExecution::AsPlanned
+    );
+    assert_eq!(
+      PlanExecution::compare(&plan, &run(None, Some(0.5))).unwrap().execution,
+      IntensityExecution::EasierThanPlanned
+    );
+
+    // No zone at all, another date, or no matching modality: nothing to compare
+    assert!(PlanExecution::compare(&plan, &run(None, None)).is_none());
+    assert!(PlanExecution::compare(&plan[..1], &run(Some("Z4"), None)).is_none());
+    let next_day = WorkoutContext { date: "2024-12-18".to_string(), ..run(Some("Z4"), None) };
+    assert!(PlanExecution::compare(&plan, &next_day).is_none());
+
+    assert_eq!(IntensityExecution::classify("tempo", "Z3"), Some(IntensityExecution::AsPlanned));
+    assert_eq!(IntensityExecution::classify("long", "Z3"), None);
+  }
+
+  fn seasonal_run(date: &str, pace: f64, efficiency: f64) -> RecentWorkoutSummary {
+    RecentWorkoutSummary {
+      date: date.to_string(),
+      activity_type: "Run".to_string(),
+      duration_min: 60.0,
+      avg_power: None,
+      avg_hr: Some(145),
+      pace_min_km: Some(pace),
+      pace_min_mi: None,
+      rtss: None,
+      efficiency: Some(efficiency),
+      is_indoor: false,
     }
   }
 
-  /// Build schedule context from the workout date
-  fn build_schedule(workout_date: &chrono::DateTime<chrono::Utc>) -> ScheduleContext {
-    use chrono::{Datelike, Duration, Weekday};
+  #[test]
+  fn test_seasonal_year_window_straddles_new_year() {
+    let current = chrono::NaiveDate::from_ymd_opt(2025, 1, 5).unwrap();
+    let dec = chrono::NaiveDate::from_ymd_opt(2023, 12, 28).unwrap();
+    assert_eq!(seasonal_year_for(current, dec, 14), Some(2024));
 
-    let today = workout_date.weekday();
-    let tomorrow = (workout_date.clone() + Duration::days(1)).weekday();
+    let far = chrono::NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+    assert_eq!(seasonal_year_for(current, far, 14), None);
 
-    let day_name = |w: Weekday| -> String {
-      match w {
-        Weekday::Mon => "Monday",
-        Weekday::Tue => "Tuesday",
-        Weekday::Wed => "Wednesday",
-        Weekday::Thu => "Thursday",
-        Weekday::Fri => "Friday",
-        Weekday::Sat => "Saturday",
-        Weekday::Sun => "Sunday",
-      }.to_string()
-    };
+    // Same year never counts as a prior season
+    let recent = chrono::NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+    assert_eq!(seasonal_year_for(current, recent, 14), None);
+  }
 
-    // Default schedule: MWF ride, T/Th run, Sat long run, Sun rest
-    let expected_type = |w: Weekday| -> String {
-      match w {
-        Weekday::Mon => "ride",
-        Weekday::Tue => "run",
-        Weekday::Wed => "ride",
-        Weekday::Thu => "run",
-        Weekday::Fri => "ride",
-        Weekday::Sat => "run_long",
-        Weekday::Sun => "rest",
-      }.to_string()
-    };
+  #[test]
+  fn test_seasonal_comparison_deltas() {
+    let current = seasonal_run("2025-03-15", 5.0, 0.034);
+    let candidates = vec![
+      seasonal_run("2024-03-10", 5.25, 0.036),
+      seasonal_run("2024-03-20", 5.25, 0.036),
+      seasonal_run("2024-06-01", 4.5, 0.030), // outside window
+      seasonal_run("2023-03-16", 5.5, 0.040),
+    ];
+
+    let comparison = compute_seasonal_comparison(&current, &candidates, SEASONAL_WINDOW_DAYS);
+    assert_eq!(comparison.prior_years.len(), 2);
+
+    let last_year = &comparison.prior_years[0];
+    assert_eq!(last_year.year, 2024);
+    assert_eq!(last_year.workouts, 2);
+    assert!((last_year.pace_delta_sec_km.unwrap() - -15.0).abs() < 1e-9);
+    assert!(last_year.power_delta_w.is_none());
+
+    let two_years = &comparison.prior_years[1];
+    assert_eq!(two_years.year, 2023);
+    assert!((two_years.pace_delta_sec_km.unwrap() - -30.0).abs() < 1e-9);
+    assert!((two_years.efficiency_delta_pct.unwrap() - -15.0).abs() < 1e-9);
+  }
 
-    ScheduleContext {
-      today_is: day_name(today),
-      tomorrow_is: day_name(tomorrow),
-      tomorrow_expected_type: expected_type(tomorrow),
-      weekly_pattern: WeeklyPattern::default(),
-    }
+  #[test]
+  fn test_seasonal_comparison_without_prior_data() {
+    let current = seasonal_run("2025-03-15", 5.0, 0.034);
+    let comparison = compute_seasonal_comparison(&current, &[], SEASONAL_WINDOW_DAYS);
+    assert!(comparison.prior_years.is_empty());
+    assert_eq!(comparison.workout_date, "2025-03-15");
   }
 
-  /// Add progression summary (from Rust progression engine)
-  pub fn with_progression_summary(mut self, summary: ProgressionSummary) -> Self {
-    self.progression_summary = Some(summary);
-    self
+  /// Minutes of constant value in 10-second samples
+  fn steady(minutes: usize, value: i64) -> Vec<i64> {
+    vec![value; minutes * 6]
   }
 
-  /// Serialize to JSON for the LLM prompt
-  pub fn to_json(&self) -> String {
-    serde_json::to_string_pretty(self).unwrap_or_default()
+  #[test]
+  fn test_detects_20min_ftp_test() {
+    let mut watts = steady(15, 150); // warm-up
+    watts.extend((0..120).map(|i| if i % 2 == 0 { 275 } else { 285 })); // 20 min @ ~280W
+    watts.extend(steady(10, 120)); // cool-down
+    let samples = WorkoutSamples { hr: vec![], watts, ..Default::default() };
+
+    let result = detect_threshold_test(&samples, "VirtualRide").unwrap();
+    assert_eq!(result.kind, ThresholdTestKind::Ftp);
+    assert_eq!(result.block_minutes, 20);
+    assert!((result.block_start_min - 15.0).abs() < 0.01);
+    assert!((result.block_avg - 280.0).abs() < 0.01);
+    assert_eq!(result.suggested_value, 266); // 280 * 0.95
   }
-}
 
-/// ---------------------------------------------------------------------------
-/// Tests
-/// ---------------------------------------------------------------------------
+  #[test]
+  fn test_steady_endurance_ride_is_not_a_test() {
+    let samples = WorkoutSamples { hr: vec![], watts: steady(60, 180), ..Default::default() };
+    assert!(detect_threshold_test(&samples, "Ride").is_none());
 
-#[cfg(test)]
-mod tests {
-  use super::*;
+    // Too short for a 20-min block
+    let samples = WorkoutSamples { hr: vec![], watts: steady(15, 300), ..Default::default() };
+    assert!(detect_threshold_test(&samples, "Ride").is_none());
+  }
 
   #[test]
-  fn test_hr_zones() {
-    let max_hr = 190;
-    assert_eq!(HrZone::from_hr(100, max_hr), HrZone::Z1); // 53%
-    assert_eq!(HrZone::from_hr(120, max_hr), HrZone::Z2); // 63%
-    assert_eq!(HrZone::from_hr(140, max_hr), HrZone::Z3); // 74%
-    assert_eq!(HrZone::from_hr(165, max_hr), HrZone::Z4); // 87%
-    assert_eq!(HrZone::from_hr(180, max_hr), HrZone::Z5); // 95%
+  fn test_detects_30min_run_time_trial() {
+    let mut hr = steady(10, 135);
+    hr.extend(steady(10, 165));
+    hr.extend(steady(20, 172));
+    hr.extend(steady(5, 130));
+    let samples = WorkoutSamples { hr, watts: vec![], ..Default::default() };
+
+    let result = detect_threshold_test(&samples, "Run").unwrap();
+    assert_eq!(result.kind, ThresholdTestKind::Lthr);
+    assert_eq!(result.suggested_value, 172);
+    assert!(detect_threshold_test(&samples, "Yoga").is_none());
   }
 
   #[test]
-  fn test_running_metrics() {
+  fn test_sparse_stream_suppresses_decoupling_and_test_detection() {
+    use crate::strava::StreamQuality;
+
+    // 60-min ride, same power throughout, HR drifts 140 -> 147 in the second half
+    let mut hr = steady(30, 140);
+    hr.extend(steady(30, 147));
+    let mut samples = WorkoutSamples { hr, watts: steady(60, 200), ..Default::default() };
+    samples.stream_quality = Some(StreamQuality { resolution: None, coverage_pct: 98.0, sample_count: 3600 });
+
+    let decoupling = compute_decoupling(&samples, "Ride").unwrap();
+    assert!((decoupling - 4.8).abs() < 0.05);
+
+    // Same buckets from a gappy recording: refuse rather than guess
+    samples.stream_quality = Some(StreamQuality { resolution: None, coverage_pct: 35.0, sample_count: 400 });
+    assert!(compute_decoupling(&samples, "Ride").is_none());
+
+    let mut watts = steady(15, 150);
+    watts.extend(steady(20, 280));
+    watts.extend(steady(10, 120));
+    let sparse_test = WorkoutSamples { watts, stream_quality: samples.stream_quality.clone(), ..Default::default() };
+    assert!(detect_threshold_test(&sparse_test, "Ride").is_none());
+  }
+
+  fn trend_run(date: &str, pace: f64, efficiency: f64, rtss: f64) -> RecentWorkoutSummary {
+    RecentWorkoutSummary {
+      date: date.to_string(),
+      activity_type: "Run".to_string(),
+      duration_min: 45.0,
+      avg_power: None,
+      avg_hr: Some(150),
+      pace_min_km: Some(pace),
+      pace_min_mi: None,
+      rtss: Some(rtss),
+      efficiency: Some(efficiency),
+      is_indoor: false,
+    }
+  }
+
+  #[test]
+  fn test_fitness_trend_weeks_ctl_prs_and_slope() {
+    let today = chrono::NaiveDate::from_ymd_opt(2025, 3, 31).unwrap();
+    let workouts = vec![
+      // Before the window: sets the pace baseline and CTL at the start
+      trend_run("2025-02-10", 5.5, 0.040, 84.0),
+      // Inside the window (starts 2025-02-18) - steadily more efficient
+      trend_run("2025-02-24", 5.4, 0.039, 84.0),
+      trend_run("2025-03-03", 5.6, 0.038, 60.0),
+      trend_run("2025-03-10", 5.3, 0.037, 60.0),
+      trend_run("2025-03-17", 5.35, 0.036, 60.0),
+      trend_run("2025-03-30", 5.2, 0.035, 60.0),
+      // After today: ignored
+      trend_run("2025-04-02", 4.0, 0.030, 60.0),
+    ];
+
+    let trend = compute_fitness_trend(&workouts, &UserSettings::default(), today);
+    assert_eq!(trend.window_start, chrono::NaiveDate::from_ymd_opt(2025, 2, 18).unwrap());
+    assert_eq!(trend.weeks.len(), FITNESS_TREND_WEEKS as usize);
+    assert_eq!(trend.weeks.iter().map(|w| w.workouts).sum::<usize>(), 5);
+    assert!((trend.weeks.last().unwrap().hours - 0.75).abs() < 1e-9);
+
+    // 2025-02-18 looks back 42 days: only the 2025-02-10 run
+    assert!((trend.ctl_start.unwrap() - 2.0).abs() < 1e-9);
+    assert!(trend.ctl_end.is_some());
+
+    // 5.4 (in window), 5.3 and 5.2 beat every earlier run; 5.6 and 5.35 don't
+    assert_eq!(trend.pr_count, 3);
+    assert!(trend.run_efficiency_slope.unwrap() < 0.0);
+    assert_eq!(trend.ride_efficiency_slope, None);
+  }
+
+  #[test]
+  fn test_fitness_trend_load_follows_settings() {
+    let today = chrono::NaiveDate::from_ymd_opt(2025, 3, 31).unwrap();
+    let workouts = vec![
+      trend_run("2025-03-30", 5.2, 0.035, 84.0),
+      RecentWorkoutSummary {
+        activity_type: "Yoga".to_string(),
+        pace_min_km: None,
+        efficiency: None,
+        ..trend_run("2025-03-30", 0.0, 0.0, 42.0)
+      },
+    ];
+
+    let trend = compute_fitness_trend(&workouts, &UserSettings::default(), today);
+    assert!((trend.ctl_end.unwrap() - 3.0).abs() < 1e-9);
+    assert!((trend.weeks.last().unwrap().rtss - 126.0).abs() < 1e-9);
+
+    // Run weighted by half, "other" load left out
     let settings = UserSettings {
-      max_hr: Some(190),
-      lthr: Some(170),
-      ftp: None,
-      training_days_per_week: 6,
+      include_other_load: false,
+      load_weights: LoadWeights { run: 0.5, ..Default::default() },
+      ..Default::default()
     };
+    let trend = compute_fitness_trend(&workouts, &settings, today);
+    assert!((trend.ctl_end.unwrap() - 1.0).abs() < 1e-9);
+    assert!((trend.weeks.last().unwrap().rtss - 42.0).abs() < 1e-9);
+    assert_eq!(trend.weeks.last().unwrap().workouts, 2);
+  }
 
-    let metrics = WorkoutMetrics::compute(
-      "Run",
-      Some(2640),     // 44 minutes
-      Some(6000.0),   // 6 km
-      Some(139),      // avg HR
-      None,           // no watts
-      &settings,
-    );
+  #[test]
+  fn test_efficiency_factor_higher_is_better() {
+    let settings = UserSettings::default();
+    // 10 km in 50 min at 150 bpm: 200 m/min / 150
+    let run = WorkoutMetrics::compute("Run", Some(3000), Some(10000.0), Some(150), None, &[], &settings);
+    assert!((run.efficiency_factor.unwrap() - 200.0 / 150.0).abs() < 1e-9);
+    let faster = WorkoutMetrics::compute("Run", Some(2700), Some(10000.0), Some(150), None, &[], &settings);
+    assert!(faster.efficiency_factor > run.efficiency_factor);
+
+    let ride = WorkoutMetrics::compute("Ride", Some(3600), None, Some(140), Some(210.0), &[], &settings);
+    assert!((ride.efficiency_factor.unwrap() - 1.5).abs() < 1e-9);
+    assert!(WorkoutMetrics::compute("Ride", Some(3600), Some(30000.0), None, Some(210.0), &[], &settings)
+      .efficiency_factor
+      .is_none());
+  }
 
-    // Pace should be ~7.33 min/km
-    assert!(metrics.pace_min_per_km.is_some());
-    let pace = metrics.pace_min_per_km.unwrap();
-    assert!((pace - 7.33).abs() < 0.1);
+  #[test]
+  fn test_ef_trend_positive_slope_when_improving() {
+    let date = |d: &str| chrono::NaiveDate::parse_from_str(d, "%Y-%m-%d").unwrap();
+    let points = vec![
+      EfPoint { date: date("2025-03-15"), efficiency_factor: 1.40, average_hr: Some(141) },
+      EfPoint { date: date("2025-03-01"), efficiency_factor: 1.32, average_hr: Some(140) },
+      EfPoint { date: date("2025-03-08"), efficiency_factor: 1.37, average_hr: Some(142) },
+      EfPoint { date: date("2025-03-22"), efficiency_factor: 1.44, average_hr: Some(140) },
+    ];
+
+    let trend = compute_ef_trend(ActivityKind::Run, 90, points);
+    assert_eq!(trend.points.first().unwrap().date, date("2025-03-01"));
+    let slope = trend.slope_per_week.unwrap();
+    assert!((slope - 0.039).abs() < 1e-3);
+    assert!(trend.slope_pct_per_week.unwrap() > 2.0);
+    assert!(trend.hr_slope_per_week.unwrap().abs() < 0.5);
+
+    let sparse = compute_ef_trend(ActivityKind::Ride, 90, vec![]);
+    assert_eq!(sparse.slope_per_week, None);
+  }
 
-    // Should have rTSS
-    assert!(metrics.rtss.is_some());
+  #[test]
+  fn test_recovery_week_is_half_of_peak() {
+    let settings = UserSettings { utc_offset_minutes: Some(0), ..Default::default() };
+    // Wednesday; the current week started Monday 2025-03-17
+    let today = chrono::NaiveDate::from_ymd_opt(2025, 3, 19).unwrap();
+    let session = |date: &str, rtss: f64| WorkoutSummary {
+      started_at: chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .unwrap()
+        .and_hms_opt(7, 0, 0)
+        .unwrap()
+        .and_utc(),
+      activity_type: "Run".to_string(),
+      rtss: Some(rtss),
+      ..Default::default()
+    };
+    let workouts = vec![
+      // Build: 300, 350, 400 (peak), then a 200 recovery week
+      session("2025-02-17", 150.0),
+      session("2025-02-20", 150.0),
+      session("2025-02-24", 350.0),
+      session("2025-03-03", 200.0),
+      session("2025-03-08", 200.0),
+      session("2025-03-10", 200.0),
+      // This week so far, and a session before the window
+      session("2025-03-17", 100.0),
+      session("2025-02-10", 999.0),
+    ];
+
+    let load = compute_normalized_weekly_load(&workouts, &settings, today, 5, LoadReference::Max);
+    assert_eq!(load.reference_rtss, Some(400.0));
+    let pct: Vec<f64> = load.weeks.iter().map(|w| w.pct_of_reference.unwrap()).collect();
+    assert_eq!(pct, vec![75.0, 87.5, 100.0, 50.0, 25.0]);
+    assert_eq!(load.weeks[0].week_start, chrono::NaiveDate::from_ymd_opt(2025, 2, 17).unwrap());
+    assert!(load.weeks[4].partial && !load.weeks[3].partial);
+
+    // Average of the completed weeks: (300 + 350 + 400 + 200) / 4 = 312.5
+    let average = compute_normalized_weekly_load(&workouts, &settings, today, 5, LoadReference::Average);
+    assert_eq!(average.reference_rtss, Some(312.5));
+    assert!((average.weeks[3].pct_of_reference.unwrap() - 64.0).abs() < 1e-9);
+
+    let empty = compute_normalized_weekly_load(&[], &settings, today, 5, LoadReference::Max);
+    assert_eq!(empty.reference_rtss, None);
+    assert!(empty.weeks.iter().all(|w| w.pct_of_reference.is_none()));
+
+    // Sunday weeks start a day earlier (the sessions fall in the same weeks)
+    let sunday = UserSettings { week_start_day: WeekStart::Sunday, ..settings.clone() };
+    let load = compute_normalized_weekly_load(&workouts, &sunday, today, 5, LoadReference::Max);
+    assert_eq!(load.weeks[0].week_start, chrono::NaiveDate::from_ymd_opt(2025, 2, 16).unwrap());
+    assert_eq!(load.weeks[4].week_start, chrono::NaiveDate::from_ymd_opt(2025, 3, 16).unwrap());
+    let pct: Vec<f64> = load.weeks.iter().map(|w| w.pct_of_reference.unwrap()).collect();
+    assert_eq!(pct, vec![75.0, 87.5, 100.0, 50.0, 25.0]);
+  }
 
-    // Should be Z3 (139/190 = 73%)
-    assert_eq!(metrics.hr_zone, Some(HrZone::Z3));
+  #[test]
+  fn test_activity_calendar_streaks() {
+    let settings = UserSettings { utc_offset_minutes: Some(0), ..Default::default() };
+    let today = chrono::NaiveDate::from_ymd_opt(2025, 3, 20).unwrap();
+    let on = |days_ago: i64, rtss: Option<f64>| WorkoutSummary {
+      started_at: (today - chrono::Duration::days(days_ago)).and_hms_opt(7, 0, 0).unwrap().and_utc(),
+      activity_type: "Run".to_string(),
+      duration_seconds: Some(2700),
+      rtss,
+      hr_zone: None,
+      swim_zone: None,
+      has_device_data: true,
+      rpe: None,
+    };
 
-    // No cycling metrics
-    assert!(metrics.kj.is_none());
-    assert!(metrics.speed_kmh.is_none());
+    // 4-day block, rest day, 3-day block ending yesterday (two sessions on one day)
+    let workouts = vec![
+      on(9, Some(40.0)),
+      on(8, Some(40.0)),
+      on(7, Some(40.0)),
+      on(6, Some(40.0)),
+      on(4, Some(50.0)),
+      on(3, Some(30.0)),
+      on(3, Some(25.5)),
+      on(1, None),
+    ];
+    let calendar = build_activity_calendar(&workouts, &settings, today, 14);
+
+    assert_eq!(calendar.days.len(), 14);
+    assert_eq!(calendar.days.last().unwrap().date, today);
+    let day3 = &calendar.days[13 - 3];
+    assert_eq!((day3.workouts, day3.rtss), (2, 55.5));
+    assert_eq!(calendar.days[13 - 5].workouts, 0);
+
+    // The rest 5 days ago split the blocks; day 2 was also rest
+    assert_eq!(calendar.longest_streak, 4);
+    // Today is open, so the streak is yesterday's single day
+    assert_eq!(calendar.current_streak, 1);
+
+    let mut trained_today = workouts.clone();
+    trained_today.push(on(0, Some(20.0)));
+    trained_today.push(on(2, Some(20.0)));
+    let calendar = build_activity_calendar(&trained_today, &settings, today, 14);
+    assert_eq!(calendar.current_streak, 5); // days 4..=0
+    assert_eq!(calendar.longest_streak, 5);
+
+    // A rest day yesterday and nothing today ends the streak
+    let calendar = build_activity_calendar(&workouts[..7], &settings, today, 14);
+    assert_eq!(calendar.current_streak, 0);
   }
 
   #[test]
-  fn test_cycling_metrics() {
-    let settings = UserSettings {
-      max_hr: Some(190),
-      lthr: Some(170),
-      ftp: Some(250),
-      training_days_per_week: 6,
+  fn test_performance_card_computed_from_most_recent_same_type() {
+    let settings = UserSettings::default();
+    let started_at = chrono::Utc::now();
+    let ride = |days_ago: i64, watts: f64| RecentWorkoutSummary {
+      activity_type: "Ride".to_string(),
+      avg_power: Some(watts),
+      pace_min_km: None,
+      ..recent_workout(days_ago)
     };
-
-    let metrics = WorkoutMetrics::compute(
+    let previous = ride(2, 138.0);
+    let metrics = WorkoutMetrics::compute("Ride", Some(3600), Some(30000.0), Some(135), Some(150.0), &[], &settings);
+    let package = ContextPackage::build(
       "Ride",
-      Some(2700),     // 45 minutes
-      Some(20600.0),  // 20.6 km
-      Some(126),      // avg HR
-      Some(180.0),    // 180 watts
+      &started_at,
+      Some(3600),
+      Some(30000.0),
+      Some(135),
+      Some(150.0),
+      &metrics,
+      TrainingContext::compute(&[], &settings),
+      TrainingFlags::default(),
+      1.0,
       &settings,
+      vec![previous.clone(), ride(5, 142.0)],
+      vec![],
     );
 
-    // kJ should be 180 * 2700 / 1000 = 486
-    assert!(metrics.kj.is_some());
-    let kj = metrics.kj.unwrap();
-    assert!((kj - 486.0).abs() < 1.0);
-
-    // Speed should be ~27.5 km/h
-    assert!(metrics.speed_kmh.is_some());
+    let card = package.performance.clone().unwrap();
+    assert_eq!(card.metric_name, "power");
+    assert_eq!(card.comparison_date, previous.date);
+    assert_eq!(card.comparison_value, "138W");
+    assert_eq!(card.today_value, "150W");
+    assert_eq!(card.delta, "+12W");
+    assert_eq!(card.direction.as_deref(), Some("improved"));
+    assert!(card.insight.is_empty());
+    assert!(package.to_json().contains("\"performance\""));
+
+    // Pace: 5:30/km vs 5:45/km is 15 sec/km faster
+    let thresholds = SignificanceThresholds::default();
+    let run = |pace: f64| WorkoutContext { pace_min_km: Some(pace), ..package.workout.clone() };
+    let prior_run = RecentWorkoutSummary { pace_min_km: Some(5.75), ..recent_workout(1) };
+    let card = compute_performance_card(&run(5.5), &[prior_run.clone()], &thresholds, None, Units::Metric).unwrap();
+    let card_fields = (card.metric_name.as_str(), card.comparison_value.as_str(), card.today_value.as_str());
+    assert_eq!(card_fields, ("pace", "5:45/km", "5:30/km"));
+    assert_eq!(card.delta, "-15 sec/km");
+    assert_eq!(card.direction.as_deref(), Some("improved"));
+
+    // Under the 10 sec/km threshold is stable, not a trend
+    let card = compute_performance_card(&run(5.8), &[prior_run], &thresholds, None, Units::Metric).unwrap();
+    assert_eq!(card.delta, "+3 sec/km");
+    assert_eq!(card.direction.as_deref(), Some("stable"));
+
+    // Nothing comparable: the LLM fills the card as before
+    assert!(compute_performance_card(&run(5.5), &[], &thresholds, None, Units::Metric).is_none());
+  }
 
-    // Efficiency: watts/hr = 180/126 ≈ 1.43
-    assert!(metrics.efficiency.is_some());
-    let eff = metrics.efficiency.unwrap();
-    assert!((eff - 1.43).abs() < 0.1);
+  #[test]
+  fn test_performance_card_in_imperial_units() {
+    let settings = UserSettings { units: Units::Imperial, ..Default::default() };
+    // 10 km in 55 min is 5:30/km; the prior run was 5:45/km
+    let metrics = WorkoutMetrics::compute("Run", Some(3300), Some(10000.0), Some(150), None, &[], &settings);
+    let prior_run = RecentWorkoutSummary { pace_min_km: Some(5.75), ..recent_workout(1) };
+    let package = ContextPackage::build(
+      "Run",
+      &chrono::Utc::now(),
+      Some(3300),
+      Some(10000.0),
+      Some(150),
+      None,
+      &metrics,
+      TrainingContext::compute(&[], &settings),
+      TrainingFlags::default(),
+      1.0,
+      &settings,
+      vec![prior_run.clone()],
+      vec![prior_run],
+    );
 
-    // No running metrics
-    assert!(metrics.pace_min_per_km.is_none());
+    let card = package.performance.clone().unwrap();
+    let card_fields = (card.metric_name.as_str(), card.comparison_value.as_str(), card.today_value.as_str());
+    assert_eq!(card_fields, ("pace", "9:15/mi", "8:51/mi"));
+    assert_eq!(card.delta, "-24 sec/mi");
+    assert_eq!(card.direction.as_deref(), Some("improved"));
+
+    // Recent paces carry the min/mi value alongside the stored min/km
+    let recent_mi = package.recent_same_type[0].pace_min_mi.unwrap();
+    assert!((recent_mi - 5.75 * KM_PER_MILE).abs() < 1e-9);
+    assert_eq!(package.recent_all[0].pace_min_mi, Some(recent_mi));
+
+    // Re-deriving the card for an indoor session keeps the units
+    let package = package.with_indoor(false);
+    assert_eq!(package.performance.unwrap().today_value, "8:51/mi");
+
+    // Metric athletes see neither
+    let metric = build_package(TrainingContext::compute(&[], &UserSettings::default()), vec![recent_workout(1)]);
+    assert!(metric.recent_all[0].pace_min_mi.is_none());
+    assert!(!metric.to_json().contains("pace_min_mi"));
   }
 
   #[test]
-  fn test_lthr_fallback() {
-    let settings = UserSettings {
-      max_hr: Some(190),
-      lthr: None, // Not set
-      ftp: None,
-      training_days_per_week: 6,
-    };
-
-    // Should fall back to 93% of max = 177
-    assert_eq!(settings.effective_lthr(), Some(176)); // 190 * 0.93 = 176.7 -> 176
+  fn test_fatigue_explains_modest_slowdown() {
+    assert_eq!(expected_performance_adjustment(0.0), 0.0);
+    assert!((expected_performance_adjustment(-25.0) - 4.5).abs() < 1e-9);
+    assert_eq!(expected_performance_adjustment(-80.0), MAX_FATIGUE_DECREMENT_PCT);
+
+    // 5:43/km against 5:30/km is ~4% slower: a decline, but TSB -25 explains it
+    let fatigued = TrainingContext { tsb: Some(-25.0), ..TrainingContext::compute(&[], &UserSettings::default()) };
+    let package = build_package(fatigued, vec![]);
+    assert_eq!(package.fatigue.expected_performance_decrement_pct, Some(4.5));
+    assert!(package.to_json().contains("\"expected_performance_decrement_pct\""));
+
+    let prior = [recent_workout(1)];
+    let card = compute_performance_card(
+      &package.workout,
+      &prior,
+      &package.thresholds,
+      package.fatigue.tsb,
+      Units::Metric,
+    )
+    .unwrap();
+    assert_eq!(card.delta, "+13 sec/km");
+    assert_eq!(card.direction.as_deref(), Some("declined"));
+    assert_eq!(card.within_fatigue_expectation, Some(true));
+
+    // Fresh, the same slowdown is unexplained
+    let card = compute_performance_card(
+      &package.workout,
+      &prior,
+      &package.thresholds,
+      Some(0.0),
+      Units::Metric,
+    )
+    .unwrap();
+    assert_eq!(card.within_fatigue_expectation, Some(false));
+
+    // Fatigue doesn't cover a much bigger drop, and there's nothing to explain when improving
+    let faster_prior = [RecentWorkoutSummary { pace_min_km: Some(5.0), ..recent_workout(1) }];
+    let card = compute_performance_card(
+      &package.workout,
+      &faster_prior,
+      &package.thresholds,
+      Some(-25.0),
+      Units::Metric,
+    )
+    .unwrap();
+    assert_eq!(card.within_fatigue_expectation, Some(false));
+    let slower_prior = [RecentWorkoutSummary { pace_min_km: Some(6.0), ..recent_workout(1) }];
+    let card = compute_performance_card(
+      &package.workout,
+      &slower_prior,
+      &package.thresholds,
+      Some(-25.0),
+      Units::Metric,
+    )
+    .unwrap();
+    assert_eq!(card.within_fatigue_expectation, None);
   }
 }
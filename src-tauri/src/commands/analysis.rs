@@ -1,12 +1,22 @@
 use crate::analysis::{
-  ContextPackage, HrZone, RecentWorkoutSummary, TrainingContext, TrainingFlags, UserSettings,
-  WorkoutMetrics, WorkoutSummary,
+  activity_type_aliases, build_activity_calendar, canonical_activity, compute_decoupling, compute_ef_trend, compute_fitness_trend, compute_normalized_weekly_load, compute_seasonal_comparison, detect_threshold_test, estimate_rtss_from_rpe, is_supplemental_activity, measured_power, select_model, ActivityKind,
+  ActivityCalendar, AutoAnalyzeSettings, CoachTone, ComparisonTarget, ContextPackage, ContextTrim, DailyLog, EfPoint, EfTrend, FitnessTrend, FlagThresholds, HrZone, LoadReference, LoadWeights, NormalizedWeeklyLoad, Observation, ObservationKind, PrescriptionFeedback, PrescriptionRating, RecentWorkoutSummary, RecentWorkoutWindow, RecurringObservation,
+  SeasonalComparison, SportSettings, StoredPrescription, SwimZone, TestResult, ThresholdTestKind, TrainingContext, TrainingFlags, TsbBands, UnitMetrics, Units, UserSettings, WeekStart, WorkoutMetrics, ZoneModel,
+  WorkoutSummary, ZoneSplit, DEFAULT_NORMALIZED_LOAD_WEEKS, EF_TREND_DEFAULT_DAYS, NORMALIZED_LOAD_WEEKS_RANGE, MAX_CALENDAR_DAYS, CONTEXT_CHAR_BUDGET_RANGE, CSS_PACE_RANGE, LONG_SESSION_WINDOW_DAYS_RANGE, OBSERVATION_WINDOW_DAYS, PROGRESSION_OVERLAP_DAYS_RANGE, SEASONAL_WINDOW_DAYS, SHOE_REPLACEMENT_KM_RANGE, UTC_OFFSET_MINUTES_RANGE,
+  parse_timezone,
 };
-use crate::llm::{ClaudeClient, LlmError, WorkoutAnalysisV4};
+use crate::commands::oura::{load_oura_context, load_resting_hr_baseline};
+use crate::commands::plan::{load_active_training_plan, load_training_phase, plan_adherence_for};
+use crate::llm::{AnalysisDiff, ClaudeClient, LlmError, PerformanceCard, WorkoutAnalysisV4};
 use crate::db::AppState;
-use crate::progression::{load_all_dimensions, AdherenceSummary, ProgressionSummary};
+use crate::models::Workout;
+use crate::progression::{count_recent_key_sessions, is_key_session, load_all_dimensions, AdherenceSummary, ExperienceLevel, ProgressionSummary};
+use crate::scheduler::record_token_usage;
+use crate::strava::WorkoutSamples;
 use chrono::{DateTime, Utc};
 use serde::Serialize;
+use sqlx::Row;
+use std::path::Path;
 use std::sync::Arc;
 use tauri::State;
 
@@ -18,32 +28,448 @@ use tauri::State;
 pub async fn get_user_settings(
   state: State<'_, Arc<AppState>>,
 ) -> Result<UserSettings, String> {
-  let row: Option<(Option<i64>, Option<i64>, Option<i64>, i64)> = sqlx::query_as(
-    "SELECT max_hr, lthr, ftp, training_days_per_week FROM user_settings WHERE id = 1",
+  load_user_settings(&state.db).await
+}
+
+/// Helper: Load the singleton settings row (defaults if missing)
+pub(crate) async fn load_user_settings(db: &crate::db::DbPool) -> Result<UserSettings, String> {
+  let row = sqlx::query(
+    "SELECT max_hr, lthr, ftp, training_days_per_week, goal_name, goal_date,
+            lthr_pct_of_max, run_lthr_pct_of_max, ride_lthr_pct_of_max,
+            volume_spike_ratio, volume_drop_ratio, volume_drop_min_chronic,
+            recent_same_type_count, recent_all_type_count, recent_window_days,
+            include_other_load, sleep_target_hours, timezone, utc_offset_minutes,
+            polarization_low_pct, polarization_moderate_pct, polarization_high_pct,
+            auto_analyze_enabled, auto_analyze_daily_token_cap,
+            progression_overlap_days, css_pace_sec_per_100m,
+            run_load_weight, ride_load_weight, swim_load_weight, other_load_weight,
+            shoe_replacement_km, tsb_fresh_above, tsb_moderate_below, tsb_high_below,
+            long_session_window_days, zone_model, resting_hr, context_char_budget, units,
+            experience_level, week_start_day, coach_tone
+     FROM user_settings WHERE id = 1",
   )
-  .fetch_optional(&state.db)
+  .fetch_optional(db)
   .await
   .map_err(|e| format!("Failed to get settings: {}", e))?;
 
+  let sport_settings = load_sport_settings(db).await?;
+
   match row {
-    Some((max_hr, lthr, ftp, days)) => Ok(UserSettings {
-      max_hr,
-      lthr,
-      ftp,
-      training_days_per_week: days,
+    Some(row) => Ok(UserSettings {
+      max_hr: row.get("max_hr"),
+      lthr: row.get("lthr"),
+      ftp: row.get("ftp"),
+      training_days_per_week: row.get("training_days_per_week"),
+      goal_name: row.get("goal_name"),
+      goal_date: row.get("goal_date"),
+      lthr_pct_of_max: row.get("lthr_pct_of_max"),
+      run_lthr_pct_of_max: row.get("run_lthr_pct_of_max"),
+      ride_lthr_pct_of_max: row.get("ride_lthr_pct_of_max"),
+      flag_thresholds: FlagThresholds {
+        volume_spike_ratio: row.get("volume_spike_ratio"),
+        volume_drop_ratio: row.get("volume_drop_ratio"),
+        volume_drop_min_chronic: row.get("volume_drop_min_chronic"),
+      },
+      recent_window: RecentWorkoutWindow {
+        same_type_count: row.get("recent_same_type_count"),
+        all_type_count: row.get("recent_all_type_count"),
+        window_days: row.get("recent_window_days"),
+      },
+      include_other_load: row.get("include_other_load"),
+      sleep_target_hours: row.get("sleep_target_hours"),
+      sport_settings,
+      timezone: row.get("timezone"),
+      utc_offset_minutes: row.get("utc_offset_minutes"),
+      polarization_target: ZoneSplit {
+        low_pct: row.get("polarization_low_pct"),
+        moderate_pct: row.get("polarization_moderate_pct"),
+        high_pct: row.get("polarization_high_pct"),
+      },
+      auto_analyze: AutoAnalyzeSettings {
+        enabled: row.get("auto_analyze_enabled"),
+        daily_token_cap: row.get("auto_analyze_daily_token_cap"),
+      },
+      progression_overlap_days: row.get("progression_overlap_days"),
+      css_pace_sec_per_100m: row.get("css_pace_sec_per_100m"),
+      load_weights: LoadWeights {
+        run: row.get("run_load_weight"),
+        ride: row.get("ride_load_weight"),
+        swim: row.get("swim_load_weight"),
+        other: row.get("other_load_weight"),
+      },
+      shoe_replacement_km: row.get("shoe_replacement_km"),
+      tsb_bands: TsbBands {
+        tsb_fresh_above: row.get("tsb_fresh_above"),
+        tsb_moderate_below: row.get("tsb_moderate_below"),
+        tsb_high_below: row.get("tsb_high_below"),
+      },
+      long_session_window_days: row.get("long_session_window_days"),
+      zone_model: ZoneModel::parse(row.get::<String, _>("zone_model").as_str()).unwrap_or_default(),
+      resting_hr: row.get("resting_hr"),
+      context_char_budget: row.get("context_char_budget"),
+      units: Units::parse(row.get::<String, _>("units").as_str()).unwrap_or_default(),
+      experience_level: ExperienceLevel::parse(row.get::<String, _>("experience_level").as_str()).unwrap_or_default(),
+      week_start_day: WeekStart::parse(row.get::<String, _>("week_start_day").as_str()).unwrap_or_default(),
+      coach_tone: CoachTone::parse(row.get::<String, _>("coach_tone").as_str()).unwrap_or_default(),
     }),
-    None => Ok(UserSettings::default()),
+    None => Ok(UserSettings { sport_settings, ..Default::default() }),
+  }
+}
+
+/// Helper: Load per-sport threshold overrides
+async fn load_sport_settings(db: &crate::db::DbPool) -> Result<Vec<SportSettings>, String> {
+  let rows: Vec<(String, Option<i64>, Option<i64>, Option<i64>)> =
+    sqlx::query_as("SELECT sport, max_hr, lthr, ftp FROM sport_settings ORDER BY sport")
+      .fetch_all(db)
+      .await
+      .map_err(|e| format!("Failed to get sport settings: {}", e))?;
+
+  Ok(
+    rows
+      .into_iter()
+      .map(|(sport, max_hr, lthr, ftp)| SportSettings {
+        sport: canonical_activity(&sport),
+        max_hr,
+        lthr,
+        ftp,
+      })
+      .collect(),
+  )
+}
+
+/// Get per-sport threshold overrides
+#[tauri::command]
+pub async fn get_sport_settings(state: State<'_, Arc<AppState>>) -> Result<Vec<SportSettings>, String> {
+  load_sport_settings(&state.db).await
+}
+
+/// Set the threshold overrides for one sport ("run", "ride", "swim").
+/// Values are replaced as given: None clears that override back to the
+/// global setting, and clearing all three removes the sport's row.
+#[tauri::command]
+pub async fn update_sport_settings(
+  state: State<'_, Arc<AppState>>,
+  sport: String,
+  max_hr: Option<i64>,
+  lthr: Option<i64>,
+  ftp: Option<i64>,
+) -> Result<Vec<SportSettings>, String> {
+  let kind = canonical_activity(&sport);
+  if !SportSettings::SPORTS.contains(&kind) {
+    return Err(format!("Invalid sport '{}': expected run, ride or swim", sport));
+  }
+  let settings = SportSettings { sport: kind, max_hr, lthr, ftp };
+  save_sport_settings(&state.db, &settings).await?;
+  load_sport_settings(&state.db).await
+}
+
+/// Helper: Validate and store one sport's overrides
+async fn save_sport_settings(db: &crate::db::DbPool, settings: &SportSettings) -> Result<(), String> {
+  settings.validate()?;
+
+  if settings.max_hr.is_none() && settings.lthr.is_none() && settings.ftp.is_none() {
+    sqlx::query("DELETE FROM sport_settings WHERE sport = ?1")
+      .bind(settings.sport.as_str())
+      .execute(db)
+      .await
+      .map_err(|e| format!("Failed to clear sport settings: {}", e))?;
+    return Ok(());
+  }
+
+  sqlx::query(
+    r#"
+    INSERT INTO sport_settings (sport, max_hr, lthr, ftp)
+    VALUES (?1, ?2, ?3, ?4)
+    ON CONFLICT(sport) DO UPDATE SET
+      max_hr = excluded.max_hr,
+      lthr = excluded.lthr,
+      ftp = excluded.ftp,
+      updated_at = CURRENT_TIMESTAMP
+    "#,
+  )
+  .bind(settings.sport.as_str())
+  .bind(settings.max_hr)
+  .bind(settings.lthr)
+  .bind(settings.ftp)
+  .execute(db)
+  .await
+  .map_err(|e| format!("Failed to update sport settings: {}", e))?;
+
+  Ok(())
+}
+
+/// Valid range for LTHR-as-fraction-of-max-HR fallbacks
+fn validate_lthr_pct(label: &str, pct: Option<f64>) -> Result<(), String> {
+  match pct {
+    Some(p) if !(0.5..=1.0).contains(&p) => Err(format!(
+      "Invalid {} '{}': expected a fraction between 0.5 and 1.0",
+      label, p
+    )),
+    _ => Ok(()),
   }
 }
 
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn update_user_settings(
   state: State<'_, Arc<AppState>>,
   max_hr: Option<i64>,
   lthr: Option<i64>,
   ftp: Option<i64>,
   training_days_per_week: Option<i64>,
+  goal_name: Option<String>,
+  goal_date: Option<String>,
+  lthr_pct_of_max: Option<f64>,
+  run_lthr_pct_of_max: Option<f64>,
+  ride_lthr_pct_of_max: Option<f64>,
+  include_other_load: Option<bool>,
+  sleep_target_hours: Option<f64>,
+  utc_offset_minutes: Option<i32>,
+  progression_overlap_days: Option<i64>,
+  css_pace_sec_per_100m: Option<f64>,
+  shoe_replacement_km: Option<f64>,
+  long_session_window_days: Option<i64>,
+  resting_hr: Option<i64>,
+  zone_model: Option<String>,
+  context_char_budget: Option<i64>,
+  units: Option<String>,
+  experience_level: Option<String>,
+  week_start_day: Option<String>,
+  coach_tone: Option<String>,
+  clear_goal: Option<bool>,
+  clear_run_lthr_pct_of_max: Option<bool>,
+  clear_ride_lthr_pct_of_max: Option<bool>,
+  clear_utc_offset_minutes: Option<bool>,
+  timezone: Option<String>,
+  clear_timezone: Option<bool>,
+  clear_resting_hr: Option<bool>,
+  clear_css_pace_sec_per_100m: Option<bool>,
 ) -> Result<(), String> {
+  let update = UserSettingsUpdate {
+    max_hr,
+    lthr,
+    ftp,
+    training_days_per_week,
+    goal_name,
+    goal_date,
+    lthr_pct_of_max,
+    run_lthr_pct_of_max,
+    ride_lthr_pct_of_max,
+    include_other_load,
+    sleep_target_hours,
+    utc_offset_minutes,
+    progression_overlap_days,
+    css_pace_sec_per_100m,
+    shoe_replacement_km,
+    long_session_window_days,
+    resting_hr,
+    zone_model,
+    context_char_budget,
+    units,
+    experience_level,
+    week_start_day,
+    coach_tone,
+    clear_goal: clear_goal.unwrap_or(false),
+    clear_run_lthr_pct_of_max: clear_run_lthr_pct_of_max.unwrap_or(false),
+    clear_ride_lthr_pct_of_max: clear_ride_lthr_pct_of_max.unwrap_or(false),
+    clear_utc_offset_minutes: clear_utc_offset_minutes.unwrap_or(false),
+    timezone,
+    clear_timezone: clear_timezone.unwrap_or(false),
+    clear_resting_hr: clear_resting_hr.unwrap_or(false),
+    clear_css_pace_sec_per_100m: clear_css_pace_sec_per_100m.unwrap_or(false),
+  };
+  save_user_settings(&state.db, update).await
+}
+
+/// Changes to the general settings: `None` keeps the stored value, and the
+/// `clear_*` flags reset nullable ones (COALESCE can't tell "unset" from
+/// "keep")
+#[derive(Debug, Clone, Default)]
+pub(crate) struct UserSettingsUpdate {
+  pub max_hr: Option<i64>,
+  pub lthr: Option<i64>,
+  pub ftp: Option<i64>,
+  pub training_days_per_week: Option<i64>,
+  pub goal_name: Option<String>,
+  pub goal_date: Option<String>,
+  pub lthr_pct_of_max: Option<f64>,
+  pub run_lthr_pct_of_max: Option<f64>,
+  pub ride_lthr_pct_of_max: Option<f64>,
+  pub include_other_load: Option<bool>,
+  pub sleep_target_hours: Option<f64>,
+  pub utc_offset_minutes: Option<i32>,
+  pub progression_overlap_days: Option<i64>,
+  pub css_pace_sec_per_100m: Option<f64>,
+  pub shoe_replacement_km: Option<f64>,
+  pub long_session_window_days: Option<i64>,
+  pub resting_hr: Option<i64>,
+  pub zone_model: Option<String>,
+  pub context_char_budget: Option<i64>,
+  pub units: Option<String>,
+  pub experience_level: Option<String>,
+  pub week_start_day: Option<String>,
+  pub coach_tone: Option<String>,
+  pub clear_goal: bool,
+  /// Back to the general `lthr_pct_of_max` fallback for runs
+  pub clear_run_lthr_pct_of_max: bool,
+  /// Back to the general `lthr_pct_of_max` fallback for rides
+  pub clear_ride_lthr_pct_of_max: bool,
+  /// Back to the system's local offset
+  pub clear_utc_offset_minutes: bool,
+  /// IANA timezone name; takes precedence over `utc_offset_minutes`
+  pub timezone: Option<String>,
+  /// Back to `utc_offset_minutes` (or the system timezone)
+  pub clear_timezone: bool,
+  /// Back to zones without a resting HR (hr_reserve falls back to % of max)
+  pub clear_resting_hr: bool,
+  /// Back to no CSS (swims go unzoned)
+  pub clear_css_pace_sec_per_100m: bool,
+}
+
+/// Helper: Validate and store a settings update
+pub(crate) async fn save_user_settings(db: &crate::db::DbPool, update: UserSettingsUpdate) -> Result<(), String> {
+  let UserSettingsUpdate {
+    max_hr,
+    lthr,
+    ftp,
+    training_days_per_week,
+    goal_name,
+    goal_date,
+    lthr_pct_of_max,
+    run_lthr_pct_of_max,
+    ride_lthr_pct_of_max,
+    include_other_load,
+    sleep_target_hours,
+    utc_offset_minutes,
+    progression_overlap_days,
+    css_pace_sec_per_100m,
+    shoe_replacement_km,
+    long_session_window_days,
+    resting_hr,
+    zone_model,
+    context_char_budget,
+    units,
+    experience_level,
+    week_start_day,
+    coach_tone,
+    clear_goal,
+    clear_run_lthr_pct_of_max,
+    clear_ride_lthr_pct_of_max,
+    clear_utc_offset_minutes,
+    timezone,
+    clear_timezone,
+    clear_resting_hr,
+    clear_css_pace_sec_per_100m,
+  } = update;
+
+  // COALESCE keeps a missing value, so clearing needs its own flags
+  if clear_goal && (goal_name.is_some() || goal_date.is_some()) {
+    return Err("Set a goal or clear it, not both".to_string());
+  }
+  if clear_run_lthr_pct_of_max && run_lthr_pct_of_max.is_some() {
+    return Err("Set run_lthr_pct_of_max or clear it, not both".to_string());
+  }
+  if clear_ride_lthr_pct_of_max && ride_lthr_pct_of_max.is_some() {
+    return Err("Set ride_lthr_pct_of_max or clear it, not both".to_string());
+  }
+  if clear_utc_offset_minutes && utc_offset_minutes.is_some() {
+    return Err("Set utc_offset_minutes or clear it, not both".to_string());
+  }
+  if clear_timezone && timezone.is_some() {
+    return Err("Set timezone or clear it, not both".to_string());
+  }
+  if clear_resting_hr && resting_hr.is_some() {
+    return Err("Set resting_hr or clear it, not both".to_string());
+  }
+  if clear_css_pace_sec_per_100m && css_pace_sec_per_100m.is_some() {
+    return Err("Set css_pace_sec_per_100m or clear it, not both".to_string());
+  }
+  if let Some(date) = &goal_date {
+    chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d")
+      .map_err(|_| format!("Invalid goal date '{}': expected YYYY-MM-DD", date))?;
+  }
+  validate_lthr_pct("lthr_pct_of_max", lthr_pct_of_max)?;
+  validate_lthr_pct("run_lthr_pct_of_max", run_lthr_pct_of_max)?;
+  validate_lthr_pct("ride_lthr_pct_of_max", ride_lthr_pct_of_max)?;
+  if let Some(hours) = sleep_target_hours {
+    if !(4.0..=12.0).contains(&hours) {
+      return Err(format!("Invalid sleep_target_hours '{}': expected 4 to 12 hours", hours));
+    }
+  }
+  let timezone = timezone
+    .map(|name| {
+      parse_timezone(name.trim())
+        .ok_or_else(|| format!("Invalid timezone '{}': expected an IANA name like America/Denver", name))
+    })
+    .transpose()?;
+  if let Some(minutes) = utc_offset_minutes {
+    if !UTC_OFFSET_MINUTES_RANGE.contains(&minutes) {
+      return Err(format!("Invalid utc_offset_minutes '{}': expected -720 to 840", minutes));
+    }
+  }
+  if let Some(days) = progression_overlap_days {
+    if !PROGRESSION_OVERLAP_DAYS_RANGE.contains(&days) {
+      return Err(format!("Invalid progression_overlap_days '{}': expected 0 to 42", days));
+    }
+  }
+  if let Some(css) = css_pace_sec_per_100m {
+    if !CSS_PACE_RANGE.contains(&css) {
+      return Err(format!("Invalid css_pace_sec_per_100m '{}': expected 60 to 240 seconds", css));
+    }
+  }
+  if let Some(km) = shoe_replacement_km {
+    if !SHOE_REPLACEMENT_KM_RANGE.contains(&km) {
+      return Err(format!("Invalid shoe_replacement_km '{}': expected 100 to 3000 km", km));
+    }
+  }
+  if let Some(days) = long_session_window_days {
+    if !LONG_SESSION_WINDOW_DAYS_RANGE.contains(&days) {
+      return Err(format!("Invalid long_session_window_days '{}': expected 7 to 56", days));
+    }
+  }
+  if let Some(bpm) = resting_hr {
+    if !(25..=100).contains(&bpm) {
+      return Err(format!("Invalid resting_hr '{}': expected 25 to 100 bpm", bpm));
+    }
+  }
+  // Parse once so the canonical label is what gets stored
+  let zone_model = zone_model
+    .map(|model| {
+      ZoneModel::parse(&model)
+        .ok_or_else(|| format!("Invalid zone_model '{}': expected pct_max or hr_reserve", model))
+    })
+    .transpose()?;
+  if let Some(chars) = context_char_budget {
+    if !CONTEXT_CHAR_BUDGET_RANGE.contains(&chars) {
+      return Err(format!("Invalid context_char_budget '{}': expected 8000 to 400000", chars));
+    }
+  }
+  let units = units
+    .map(|units| {
+      Units::parse(&units)
+        .ok_or_else(|| format!("Invalid units '{}': expected metric or imperial", units))
+    })
+    .transpose()?;
+  let experience_level = experience_level
+    .map(|level| {
+      ExperienceLevel::parse(&level)
+        .ok_or_else(|| format!("Invalid experience_level '{}': expected beginner, intermediate or advanced", level))
+    })
+    .transpose()?;
+  let week_start_day = week_start_day
+    .map(|day| {
+      WeekStart::parse(&day)
+        .ok_or_else(|| format!("Invalid week_start_day '{}': expected rolling or a weekday (monday..sunday)", day))
+    })
+    .transpose()?;
+  let coach_tone = coach_tone
+    .map(|tone| {
+      CoachTone::parse(&tone).ok_or_else(|| {
+        format!("Invalid coach_tone '{}': expected conversational, direct, supportive or analytical", tone)
+      })
+    })
+    .transpose()?;
+
   sqlx::query(
     r#"
     UPDATE user_settings SET
@@ -51,6 +477,26 @@ pub async fn update_user_settings(
       lthr = COALESCE(?2, lthr),
       ftp = COALESCE(?3, ftp),
       training_days_per_week = COALESCE(?4, training_days_per_week),
+      goal_name = CASE WHEN ?24 THEN NULL ELSE COALESCE(?5, goal_name) END,
+      goal_date = CASE WHEN ?24 THEN NULL ELSE COALESCE(?6, goal_date) END,
+      lthr_pct_of_max = COALESCE(?7, lthr_pct_of_max),
+      run_lthr_pct_of_max = CASE WHEN ?25 THEN NULL ELSE COALESCE(?8, run_lthr_pct_of_max) END,
+      ride_lthr_pct_of_max = CASE WHEN ?26 THEN NULL ELSE COALESCE(?9, ride_lthr_pct_of_max) END,
+      include_other_load = COALESCE(?10, include_other_load),
+      sleep_target_hours = COALESCE(?11, sleep_target_hours),
+      utc_offset_minutes = CASE WHEN ?27 THEN NULL ELSE COALESCE(?12, utc_offset_minutes) END,
+      timezone = CASE WHEN ?29 THEN NULL ELSE COALESCE(?28, timezone) END,
+      progression_overlap_days = COALESCE(?13, progression_overlap_days),
+      css_pace_sec_per_100m = CASE WHEN ?31 THEN NULL ELSE COALESCE(?14, css_pace_sec_per_100m) END,
+      shoe_replacement_km = COALESCE(?15, shoe_replacement_km),
+      long_session_window_days = COALESCE(?16, long_session_window_days),
+      resting_hr = CASE WHEN ?30 THEN NULL ELSE COALESCE(?17, resting_hr) END,
+      zone_model = COALESCE(?18, zone_model),
+      context_char_budget = COALESCE(?19, context_char_budget),
+      units = COALESCE(?20, units),
+      experience_level = COALESCE(?21, experience_level),
+      week_start_day = COALESCE(?22, week_start_day),
+      coach_tone = COALESCE(?23, coach_tone),
       updated_at = CURRENT_TIMESTAMP
     WHERE id = 1
     "#,
@@ -59,427 +505,947 @@ pub async fn update_user_settings(
   .bind(lthr)
   .bind(ftp)
   .bind(training_days_per_week)
-  .execute(&state.db)
+  .bind(goal_name)
+  .bind(goal_date)
+  .bind(lthr_pct_of_max)
+  .bind(run_lthr_pct_of_max)
+  .bind(ride_lthr_pct_of_max)
+  .bind(include_other_load)
+  .bind(sleep_target_hours)
+  .bind(utc_offset_minutes)
+  .bind(progression_overlap_days)
+  .bind(css_pace_sec_per_100m)
+  .bind(shoe_replacement_km)
+  .bind(long_session_window_days)
+  .bind(resting_hr)
+  .bind(zone_model.map(|model| model.as_str()))
+  .bind(context_char_budget)
+  .bind(units.map(|units| units.as_str()))
+  .bind(experience_level.map(|level| level.as_str()))
+  .bind(week_start_day.map(|day| day.as_str()))
+  .bind(coach_tone.map(|tone| tone.as_str()))
+  .bind(clear_goal)
+  .bind(clear_run_lthr_pct_of_max)
+  .bind(clear_ride_lthr_pct_of_max)
+  .bind(clear_utc_offset_minutes)
+  .bind(timezone.map(|tz| tz.name()))
+  .bind(clear_timezone)
+  .bind(clear_resting_hr)
+  .bind(clear_css_pace_sec_per_100m)
+  .execute(db)
   .await
   .map_err(|e| format!("Failed to update settings: {}", e))?;
 
+  // Swim zones depend on CSS: re-zone stored swims on the next compute
+  if css_pace_sec_per_100m.is_some() || clear_css_pace_sec_per_100m {
+    sqlx::query("UPDATE workouts SET metrics_computed_at = NULL WHERE swim_pace_sec_per_100m IS NOT NULL")
+      .execute(db)
+      .await
+      .map_err(|e| format!("Failed to reset swim metrics: {}", e))?;
+  }
+
+  // HR zones depend on the zone model: re-zone everything on the next compute
+  if resting_hr.is_some() || clear_resting_hr || zone_model.is_some() {
+    sqlx::query("UPDATE workouts SET metrics_computed_at = NULL WHERE average_heartrate IS NOT NULL")
+      .execute(db)
+      .await
+      .map_err(|e| format!("Failed to reset HR zone metrics: {}", e))?;
+  }
+
   Ok(())
 }
 
-/// ---------------------------------------------------------------------------
-/// Compute Metrics for Workouts
-/// ---------------------------------------------------------------------------
-
-/// Compute and store metrics for all workouts that don't have them yet
+/// Update the volume flag thresholds (unset values keep their current setting)
 #[tauri::command]
-pub async fn compute_workout_metrics(
+pub async fn update_flag_thresholds(
   state: State<'_, Arc<AppState>>,
-) -> Result<ComputeResult, String> {
-  // Get user settings
-  let settings = get_user_settings(state.clone()).await?;
-
-  // Find workouts without computed metrics
-  let workouts: Vec<(i64, String, Option<i64>, Option<f64>, Option<i64>, Option<f64>)> =
-    sqlx::query_as(
-      r#"
-      SELECT id, activity_type, duration_seconds, distance_meters,
-             average_heartrate, average_watts
-      FROM workouts
-      WHERE metrics_computed_at IS NULL
-      "#,
-    )
-    .fetch_all(&state.db)
-    .await
-    .map_err(|e| format!("Failed to fetch workouts: {}", e))?;
-
-  let total = workouts.len();
-  let mut computed = 0;
-
-  for (id, activity_type, duration, distance, hr, watts) in workouts {
-    let metrics = WorkoutMetrics::compute(
-      &activity_type,
-      duration,
-      distance,
-      hr,
-      watts,
-      &settings,
-    );
+  volume_spike_ratio: Option<f64>,
+  volume_drop_ratio: Option<f64>,
+  volume_drop_min_chronic: Option<f64>,
+) -> Result<FlagThresholds, String> {
+  let current = load_user_settings(&state.db).await?.flag_thresholds;
+  let thresholds = FlagThresholds {
+    volume_spike_ratio: volume_spike_ratio.unwrap_or(current.volume_spike_ratio),
+    volume_drop_ratio: volume_drop_ratio.unwrap_or(current.volume_drop_ratio),
+    volume_drop_min_chronic: volume_drop_min_chronic.unwrap_or(current.volume_drop_min_chronic),
+  };
+  save_flag_thresholds(&state.db, &thresholds).await?;
+  Ok(thresholds)
+}
 
-    // Store computed metrics
-    sqlx::query(
-      r#"
-      UPDATE workouts SET
-        pace_min_per_km = ?1,
-        speed_kmh = ?2,
-        kj = ?3,
-        rtss = ?4,
-        efficiency = ?5,
-        cardiac_cost = ?6,
-        hr_zone = ?7,
-        metrics_computed_at = ?8
-      WHERE id = ?9
-      "#,
-    )
-    .bind(metrics.pace_min_per_km)
-    .bind(metrics.speed_kmh)
-    .bind(metrics.kj)
-    .bind(metrics.rtss)
-    .bind(metrics.efficiency)
-    .bind(metrics.cardiac_cost)
-    .bind(metrics.hr_zone.map(|z| z.as_str()))
-    .bind(Utc::now())
-    .bind(id)
-    .execute(&state.db)
-    .await
-    .map_err(|e| format!("Failed to update workout {}: {}", id, e))?;
+/// Helper: Validate and store flag thresholds
+async fn save_flag_thresholds(
+  db: &crate::db::DbPool,
+  thresholds: &FlagThresholds,
+) -> Result<(), String> {
+  thresholds.validate()?;
 
-    computed += 1;
-  }
+  sqlx::query(
+    r#"
+    UPDATE user_settings SET
+      volume_spike_ratio = ?1,
+      volume_drop_ratio = ?2,
+      volume_drop_min_chronic = ?3,
+      updated_at = CURRENT_TIMESTAMP
+    WHERE id = 1
+    "#,
+  )
+  .bind(thresholds.volume_spike_ratio)
+  .bind(thresholds.volume_drop_ratio)
+  .bind(thresholds.volume_drop_min_chronic)
+  .execute(db)
+  .await
+  .map_err(|e| format!("Failed to update flag thresholds: {}", e))?;
 
-  Ok(ComputeResult { total, computed })
+  Ok(())
 }
 
-#[derive(Serialize)]
-pub struct ComputeResult {
-  pub total: usize,
-  pub computed: usize,
+/// Set the target low/moderate/high intensity split (must add up to 100)
+#[tauri::command]
+pub async fn update_polarization_target(
+  state: State<'_, Arc<AppState>>,
+  low_pct: f64,
+  moderate_pct: f64,
+  high_pct: f64,
+) -> Result<ZoneSplit, String> {
+  let target = ZoneSplit { low_pct, moderate_pct, high_pct };
+  save_polarization_target(&state.db, &target).await?;
+  Ok(target)
 }
 
-/// ---------------------------------------------------------------------------
-/// Get Workout with Computed Metrics
-/// ---------------------------------------------------------------------------
+/// Helper: Validate and store the polarization target
+async fn save_polarization_target(db: &crate::db::DbPool, target: &ZoneSplit) -> Result<(), String> {
+  target.validate()?;
 
-#[derive(Serialize)]
-pub struct WorkoutWithMetrics {
-  pub id: i64,
-  pub strava_id: String,
-  pub activity_type: String,
-  pub started_at: String,
-  pub duration_seconds: Option<i64>,
-  pub distance_meters: Option<f64>,
-  pub average_heartrate: Option<i64>,
-  pub average_watts: Option<f64>,
-  pub suffer_score: Option<f64>,
-  // Computed metrics
-  pub pace_min_per_km: Option<f64>,
-  pub speed_kmh: Option<f64>,
-  pub kj: Option<f64>,
-  pub rtss: Option<f64>,
-  pub efficiency: Option<f64>,
-  pub cardiac_cost: Option<f64>,
-  pub hr_zone: Option<String>,
+  sqlx::query(
+    r#"
+    UPDATE user_settings SET
+      polarization_low_pct = ?1,
+      polarization_moderate_pct = ?2,
+      polarization_high_pct = ?3,
+      updated_at = CURRENT_TIMESTAMP
+    WHERE id = 1
+    "#,
+  )
+  .bind(target.low_pct)
+  .bind(target.moderate_pct)
+  .bind(target.high_pct)
+  .execute(db)
+  .await
+  .map_err(|e| format!("Failed to update polarization target: {}", e))?;
+
+  Ok(())
 }
 
+/// Set per-sport load weights (unset values keep their current setting)
 #[tauri::command]
-pub async fn get_workouts_with_metrics(
+pub async fn update_load_weights(
   state: State<'_, Arc<AppState>>,
-  limit: Option<i64>,
-) -> Result<Vec<WorkoutWithMetrics>, String> {
-  let limit = limit.unwrap_or(50);
+  run: Option<f64>,
+  ride: Option<f64>,
+  swim: Option<f64>,
+  other: Option<f64>,
+) -> Result<LoadWeights, String> {
+  let current = load_user_settings(&state.db).await?.load_weights;
+  let weights = LoadWeights {
+    run: run.unwrap_or(current.run),
+    ride: ride.unwrap_or(current.ride),
+    swim: swim.unwrap_or(current.swim),
+    other: other.unwrap_or(current.other),
+  };
+  save_load_weights(&state.db, &weights).await?;
+  Ok(weights)
+}
 
-  println!("Fetching workouts with limit: {}", limit);
+/// Helper: Validate and store the load weights
+async fn save_load_weights(db: &crate::db::DbPool, weights: &LoadWeights) -> Result<(), String> {
+  weights.validate()?;
 
-  let rows: Vec<(
-    i64, String, String, String, Option<i64>, Option<f64>,
-    Option<i64>, Option<f64>, Option<f64>,
-    Option<f64>, Option<f64>, Option<f64>, Option<f64>,
-    Option<f64>, Option<f64>, Option<String>,
-  )> = sqlx::query_as(
+  sqlx::query(
     r#"
-    SELECT
-      id, strava_id, activity_type, started_at,
-      duration_seconds, CAST(distance_meters AS REAL), average_heartrate,
-      CAST(average_watts AS REAL), CAST(suffer_score AS REAL),
-      CAST(pace_min_per_km AS REAL), CAST(speed_kmh AS REAL), CAST(kj AS REAL),
-      CAST(rtss AS REAL), CAST(efficiency AS REAL), CAST(cardiac_cost AS REAL), hr_zone
-    FROM workouts
-    ORDER BY started_at DESC
-    LIMIT ?1
+    UPDATE user_settings SET
+      run_load_weight = ?1,
+      ride_load_weight = ?2,
+      swim_load_weight = ?3,
+      other_load_weight = ?4,
+      updated_at = CURRENT_TIMESTAMP
+    WHERE id = 1
     "#,
   )
-  .bind(limit)
-  .fetch_all(&state.db)
+  .bind(weights.run)
+  .bind(weights.ride)
+  .bind(weights.swim)
+  .bind(weights.other)
+  .execute(db)
   .await
-  .map_err(|e| {
-    println!("Query error: {}", e);
-    format!("Failed to fetch workouts: {}", e)
-  })?;
-
-  println!("Fetched {} rows", rows.len());
-
-  let workouts = rows
-    .into_iter()
-    .map(|(
-      id, strava_id, activity_type, started_at,
-      duration_seconds, distance_meters, average_heartrate, average_watts, suffer_score,
-      pace_min_per_km, speed_kmh, kj, rtss, efficiency, cardiac_cost, hr_zone,
-    )| WorkoutWithMetrics {
-      id,
-      strava_id,
-      activity_type,
-      started_at,
-      duration_seconds,
-      distance_meters,
-      average_heartrate,
-      average_watts,
-      suffer_score,
-      pace_min_per_km,
-      speed_kmh,
-      kj,
-      rtss,
-      efficiency,
-      cardiac_cost,
-      hr_zone,
-    })
-    .collect();
+  .map_err(|e| format!("Failed to update load weights: {}", e))?;
 
-  Ok(workouts)
+  Ok(())
 }
 
-/// ---------------------------------------------------------------------------
-/// Get Training Context (Tier 2 Rolling Metrics)
-/// ---------------------------------------------------------------------------
-
+/// Move the TSB band edges (unset values keep their current setting)
 #[tauri::command]
-pub async fn get_training_context(
+pub async fn update_tsb_bands(
   state: State<'_, Arc<AppState>>,
-) -> Result<TrainingContext, String> {
-  // Get user settings
-  let settings = get_user_settings(state.clone()).await?;
+  tsb_fresh_above: Option<f64>,
+  tsb_moderate_below: Option<f64>,
+  tsb_high_below: Option<f64>,
+) -> Result<TsbBands, String> {
+  let current = load_user_settings(&state.db).await?.tsb_bands;
+  let bands = TsbBands {
+    tsb_fresh_above: tsb_fresh_above.unwrap_or(current.tsb_fresh_above),
+    tsb_moderate_below: tsb_moderate_below.unwrap_or(current.tsb_moderate_below),
+    tsb_high_below: tsb_high_below.unwrap_or(current.tsb_high_below),
+  };
+  save_tsb_bands(&state.db, &bands).await?;
+  Ok(bands)
+}
 
-  // Fetch workouts from last 42 days (needed for CTL calculation)
-  let rows: Vec<(String, String, Option<i64>, Option<f64>, Option<String>)> = sqlx::query_as(
+/// Helper: Validate and store the TSB band edges
+async fn save_tsb_bands(db: &crate::db::DbPool, bands: &TsbBands) -> Result<(), String> {
+  bands.validate()?;
+
+  sqlx::query(
     r#"
-    SELECT
-      started_at,
-      activity_type,
-      duration_seconds,
-      CAST(rtss AS REAL),
-      hr_zone
-    FROM workouts
-    WHERE started_at >= datetime('now', '-42 days')
-    ORDER BY started_at DESC
+    UPDATE user_settings SET
+      tsb_fresh_above = ?1,
+      tsb_moderate_below = ?2,
+      tsb_high_below = ?3,
+      updated_at = CURRENT_TIMESTAMP
+    WHERE id = 1
     "#,
   )
-  .fetch_all(&state.db)
+  .bind(bands.tsb_fresh_above)
+  .bind(bands.tsb_moderate_below)
+  .bind(bands.tsb_high_below)
+  .execute(db)
   .await
-  .map_err(|e| format!("Failed to fetch workouts for context: {}", e))?;
+  .map_err(|e| format!("Failed to update TSB bands: {}", e))?;
 
-  // Convert to WorkoutSummary
-  let workouts: Vec<WorkoutSummary> = rows
-    .into_iter()
-    .filter_map(|(started_at, activity_type, duration_seconds, rtss, hr_zone)| {
-      // Parse the started_at timestamp
-      let dt = DateTime::parse_from_rfc3339(&started_at)
-        .or_else(|_| DateTime::parse_from_str(&started_at, "%Y-%m-%dT%H:%M:%SZ"))
-        .or_else(|_| DateTime::parse_from_str(&format!("{}+00:00", started_at), "%Y-%m-%d %H:%M:%S%:z"))
-        .ok()?;
+  Ok(())
+}
 
-      let hr_zone_enum = hr_zone.as_ref().and_then(|z| match z.as_str() {
-        "Z1" => Some(HrZone::Z1),
-        "Z2" => Some(HrZone::Z2),
-        "Z3" => Some(HrZone::Z3),
-        "Z4" => Some(HrZone::Z4),
-        "Z5" => Some(HrZone::Z5),
-        _ => None,
-      });
+/// Turn background analysis of new workouts on or off, with a daily token budget
+#[tauri::command]
+pub async fn set_auto_analyze(
+  state: State<'_, Arc<AppState>>,
+  enabled: bool,
+  daily_token_cap: Option<i64>,
+) -> Result<AutoAnalyzeSettings, String> {
+  let current = load_user_settings(&state.db).await?.auto_analyze;
+  let auto_analyze = AutoAnalyzeSettings {
+    enabled,
+    daily_token_cap: daily_token_cap.unwrap_or(current.daily_token_cap),
+  };
+  save_auto_analyze(&state.db, &auto_analyze).await?;
+  Ok(auto_analyze)
+}
 
-      Some(WorkoutSummary {
-        started_at: dt.with_timezone(&Utc),
-        activity_type,
-        duration_seconds,
-        rtss,
-        hr_zone: hr_zone_enum,
-      })
-    })
-    .collect();
+/// Helper: Validate and store the auto-analyze settings
+pub(crate) async fn save_auto_analyze(db: &crate::db::DbPool, auto_analyze: &AutoAnalyzeSettings) -> Result<(), String> {
+  auto_analyze.validate()?;
 
-  Ok(TrainingContext::compute(&workouts, &settings))
+  sqlx::query(
+    r#"
+    UPDATE user_settings SET
+      auto_analyze_enabled = ?1,
+      auto_analyze_daily_token_cap = ?2,
+      updated_at = CURRENT_TIMESTAMP
+    WHERE id = 1
+    "#,
+  )
+  .bind(auto_analyze.enabled)
+  .bind(auto_analyze.daily_token_cap)
+  .execute(db)
+  .await
+  .map_err(|e| format!("Failed to update auto-analyze settings: {}", e))?;
+
+  Ok(())
+}
+
+/// Update how many recent workouts feed trend context. Unset counts keep
+/// their current value; `window_days` is always applied (None = count mode).
+#[tauri::command]
+pub async fn update_recent_workout_window(
+  state: State<'_, Arc<AppState>>,
+  same_type_count: Option<i64>,
+  all_type_count: Option<i64>,
+  window_days: Option<i64>,
+) -> Result<RecentWorkoutWindow, String> {
+  let current = load_user_settings(&state.db).await?.recent_window;
+  let window = RecentWorkoutWindow {
+    same_type_count: same_type_count.unwrap_or(current.same_type_count),
+    all_type_count: all_type_count.unwrap_or(current.all_type_count),
+    window_days,
+  };
+  save_recent_workout_window(&state.db, &window).await?;
+  Ok(window)
+}
+
+/// Helper: Validate and store the recent-workout window
+async fn save_recent_workout_window(
+  db: &crate::db::DbPool,
+  window: &RecentWorkoutWindow,
+) -> Result<(), String> {
+  window.validate()?;
+
+  sqlx::query(
+    r#"
+    UPDATE user_settings SET
+      recent_same_type_count = ?1,
+      recent_all_type_count = ?2,
+      recent_window_days = ?3,
+      updated_at = CURRENT_TIMESTAMP
+    WHERE id = 1
+    "#,
+  )
+  .bind(window.same_type_count)
+  .bind(window.all_type_count)
+  .bind(window.window_days)
+  .execute(db)
+  .await
+  .map_err(|e| format!("Failed to update recent workout window: {}", e))?;
+
+  Ok(())
 }
 
 /// ---------------------------------------------------------------------------
-/// LLM Workout Analysis Commands
+/// Subjective Workout Data (RPE + notes)
 /// ---------------------------------------------------------------------------
 
-/// Error type that can be serialized for Tauri
-#[derive(Debug, Serialize)]
-pub struct AnalysisError {
-  pub message: String,
+/// Record how a session felt: RPE 1-10 and a free-text note.
+/// Passing None clears the stored value.
+#[tauri::command]
+pub async fn set_workout_subjective(
+  state: State<'_, Arc<AppState>>,
+  workout_id: i64,
+  rpe: Option<i64>,
+  notes: Option<String>,
+) -> Result<(), String> {
+  save_workout_subjective(&state.db, workout_id, rpe, notes.as_deref()).await
 }
 
-impl From<LlmError> for AnalysisError {
-  fn from(e: LlmError) -> Self {
-    Self {
-      message: e.to_string(),
+/// Helper: Validate and store RPE/notes for a workout
+async fn save_workout_subjective(
+  db: &crate::db::DbPool,
+  workout_id: i64,
+  rpe: Option<i64>,
+  notes: Option<&str>,
+) -> Result<(), String> {
+  if let Some(rpe) = rpe {
+    if !(1..=10).contains(&rpe) {
+      return Err(format!("Invalid RPE {}: expected 1-10", rpe));
     }
   }
-}
+  let notes = notes.map(str::trim).filter(|n| !n.is_empty());
 
-impl From<String> for AnalysisError {
-  fn from(s: String) -> Self {
-    Self { message: s }
+  let result = sqlx::query("UPDATE workouts SET rpe = ?1, notes = ?2 WHERE id = ?3")
+    .bind(rpe)
+    .bind(notes)
+    .bind(workout_id)
+    .execute(db)
+    .await
+    .map_err(|e| format!("Failed to save workout notes: {}", e))?;
+
+  if result.rows_affected() == 0 {
+    return Err(format!("Workout not found: {}", workout_id));
   }
-}
 
-/// Result of analyzing a workout with Claude (V4 format)
-#[derive(Serialize)]
-pub struct WorkoutAnalysisResult {
-  pub workout_id: i64,
-  pub analysis: WorkoutAnalysisV4,  // V4 multi-card format
-  pub input_tokens: u32,
-  pub output_tokens: u32,
+  Ok(())
 }
 
-/// Stored analysis with ID for frontend
-#[derive(Debug, Clone, Serialize)]
-pub struct StoredWorkoutAnalysis {
-  pub id: Option<i64>,
-  pub workout_id: i64,
-  pub summary: String,
-  pub tomorrow_recommendation: String,
-  pub risk_flags: Vec<String>,
-  pub goal_notes: Option<String>,
-  pub created_at: Option<String>,
+/// ---------------------------------------------------------------------------
+/// Daily Log (journal: stress, soreness, life notes)
+/// ---------------------------------------------------------------------------
+
+/// Record the day's life stress and soreness (1-10) and a note. Replaces
+/// any entry for that date; passing None clears a value.
+#[tauri::command]
+pub async fn set_daily_log(
+  state: State<'_, Arc<AppState>>,
+  date: String,
+  stress_1_10: Option<i64>,
+  soreness_1_10: Option<i64>,
+  note: Option<String>,
+) -> Result<DailyLog, String> {
+  save_daily_log(&state.db, &date, stress_1_10, soreness_1_10, note.as_deref()).await
 }
 
-/// Analyze a specific workout with Claude
+/// Get the journal entry for a date (YYYY-MM-DD), if any
 #[tauri::command]
-pub async fn analyze_workout(
+pub async fn get_daily_log(
   state: State<'_, Arc<AppState>>,
-  workout_id: i64,
-) -> Result<WorkoutAnalysisResult, AnalysisError> {
-  // Get the workout data
-  let workout: Option<(
-    i64,
-    String,
-    String,
-    Option<i64>,
-    Option<f64>,
-    Option<i64>,
-    Option<f64>,
-    Option<f64>,
-    Option<f64>,
-    Option<String>,
-  )> = sqlx::query_as(
+  date: String,
+) -> Result<Option<DailyLog>, String> {
+  let date = chrono::NaiveDate::parse_from_str(&date, "%Y-%m-%d")
+    .map_err(|_| format!("Invalid date '{}': expected YYYY-MM-DD", date))?;
+  load_daily_log(&state.db, date).await
+}
+
+/// Helper: Validate and upsert a day's journal entry
+pub(crate) async fn save_daily_log(
+  db: &crate::db::DbPool,
+  date: &str,
+  stress_1_10: Option<i64>,
+  soreness_1_10: Option<i64>,
+  note: Option<&str>,
+) -> Result<DailyLog, String> {
+  let date = chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d")
+    .map_err(|_| format!("Invalid date '{}': expected YYYY-MM-DD", date))?;
+  for (label, value) in [("stress", stress_1_10), ("soreness", soreness_1_10)] {
+    if let Some(score) = value {
+      if !(1..=10).contains(&score) {
+        return Err(format!("Invalid {} {}: expected 1-10", label, score));
+      }
+    }
+  }
+  let note = note.map(str::trim).filter(|n| !n.is_empty());
+
+  sqlx::query(
     r#"
-    SELECT
-      id, activity_type, started_at, duration_seconds,
-      CAST(distance_meters AS REAL), average_heartrate,
-      CAST(average_watts AS REAL), CAST(rtss AS REAL),
-      CAST(pace_min_per_km AS REAL), hr_zone
-    FROM workouts
-    WHERE id = ?1
+    INSERT INTO daily_log (date, stress_1_10, soreness_1_10, note)
+    VALUES (?1, ?2, ?3, ?4)
+    ON CONFLICT(date) DO UPDATE SET
+      stress_1_10 = excluded.stress_1_10,
+      soreness_1_10 = excluded.soreness_1_10,
+      note = excluded.note,
+      updated_at = CURRENT_TIMESTAMP
     "#,
   )
-  .bind(workout_id)
-  .fetch_optional(&state.db)
+  .bind(date.format("%Y-%m-%d").to_string())
+  .bind(stress_1_10)
+  .bind(soreness_1_10)
+  .bind(note)
+  .execute(db)
   .await
-  .map_err(|e| AnalysisError::from(format!("Failed to fetch workout: {}", e)))?;
+  .map_err(|e| format!("Failed to save daily log: {}", e))?;
 
-  let (
-    _id,
-    activity_type,
-    started_at_str,
-    duration_seconds,
-    distance_meters,
-    average_hr,
-    average_watts,
-    rtss,
-    pace_min_per_km,
-    hr_zone,
-  ) = workout.ok_or_else(|| AnalysisError::from("Workout not found".to_string()))?;
+  Ok(DailyLog::new(date.format("%Y-%m-%d").to_string(), stress_1_10, soreness_1_10, note.map(str::to_string)))
+}
 
-  // Parse the started_at timestamp
-  let started_at = DateTime::parse_from_rfc3339(&started_at_str)
-    .or_else(|_| DateTime::parse_from_str(&started_at_str, "%Y-%m-%dT%H:%M:%SZ"))
-    .map(|dt| dt.with_timezone(&Utc))
-    .map_err(|e| AnalysisError::from(format!("Failed to parse date: {}", e)))?;
+/// Helper: Load the journal entry for one local date
+pub(crate) async fn load_daily_log(
+  db: &crate::db::DbPool,
+  date: chrono::NaiveDate,
+) -> Result<Option<DailyLog>, String> {
+  let row: Option<(String, Option<i64>, Option<i64>, Option<String>)> =
+    sqlx::query_as("SELECT date, stress_1_10, soreness_1_10, note FROM daily_log WHERE date = ?1")
+      .bind(date.format("%Y-%m-%d").to_string())
+      .fetch_optional(db)
+      .await
+      .map_err(|e| format!("Failed to load daily log: {}", e))?;
+
+  Ok(row.map(|(date, stress, soreness, note)| DailyLog::new(date, stress, soreness, note)))
+}
+
+/// ---------------------------------------------------------------------------
+/// Compute Metrics for Workouts
+/// ---------------------------------------------------------------------------
+
+/// Compute and store metrics for all workouts that don't have them yet
+#[tauri::command]
+pub async fn compute_workout_metrics(
+  state: State<'_, Arc<AppState>>,
+) -> Result<ComputeResult, String> {
+  compute_pending_metrics(&state.db).await
+}
 
+/// Helper: Compute metrics for every workout without them (shared with sync_all)
+pub(crate) async fn compute_pending_metrics(db: &crate::db::DbPool) -> Result<ComputeResult, String> {
   // Get user settings
-  let settings = get_user_settings(state.clone())
-    .await
-    .map_err(AnalysisError::from)?;
+  let mut settings = load_user_settings(db).await?;
 
-  // Reconstruct metrics (we stored them, but need WorkoutMetrics for the package)
-  let metrics = WorkoutMetrics {
-    pace_min_per_km,
-    speed_kmh: None,
-    kj: None,
-    rtss,
-    efficiency: None,
-    cardiac_cost: None,
-    hr_zone: hr_zone.as_ref().and_then(|z| match z.as_str() {
-      "Z1" => Some(HrZone::Z1),
-      "Z2" => Some(HrZone::Z2),
-      "Z3" => Some(HrZone::Z3),
-      "Z4" => Some(HrZone::Z4),
-      "Z5" => Some(HrZone::Z5),
-      _ => None,
-    }),
-  };
+  // Reserve zones without a set resting HR use Oura's recent baseline
+  if settings.zone_model == ZoneModel::HrReserve && settings.resting_hr.is_none() {
+    settings.resting_hr = load_resting_hr_baseline(db).await.unwrap_or_default();
+  }
 
-  // Get training context (includes all workouts for rolling calculations)
-  let training_context = get_training_context(state.clone())
-    .await
-    .map_err(AnalysisError::from)?;
+  // Find workouts without computed metrics
+  let workouts: Vec<(
+    i64, String, Option<i64>, Option<f64>, Option<i64>, Option<f64>, Option<bool>, Option<String>, Option<Vec<u8>>,
+    bool, bool,
+  )> = sqlx::query_as(
+    r#"
+    SELECT id, activity_type, duration_seconds, distance_meters,
+           average_heartrate, average_watts, device_watts, samples_json, samples_blob,
+           samples_compressed, is_indoor
+    FROM workouts
+    WHERE metrics_computed_at IS NULL
+    "#,
+  )
+  .fetch_all(db)
+  .await
+  .map_err(|e| format!("Failed to fetch workouts: {}", e))?;
 
-  // Load progression dimensions FIRST (needed for flag computation)
-  let dimensions = load_all_dimensions(&state.db)
-    .await
-    .map_err(|e| AnalysisError::from(format!("Failed to load progression dimensions: {}", e)))?;
+  let total = workouts.len();
+  let mut computed = 0;
 
-  // Get all workouts for flag computation
-  let workouts_for_flags = get_workout_summaries(&state.db)
-    .await
-    .map_err(|e| AnalysisError::from(format!("Failed to get workout summaries: {}", e)))?;
+  for (
+    id,
+    activity_type,
+    duration,
+    distance,
+    hr,
+    watts,
+    device_watts,
+    samples_json,
+    samples_blob,
+    samples_compressed,
+    is_indoor,
+  ) in workouts
+  {
+    // HR stream (if fetched and reliable) lets rTSS integrate intensity per
+    // sample; sparse streams and strap dropouts fall back to the average-HR
+    // estimate over the full duration
+    let samples = WorkoutSamples::from_stored(samples_json.as_deref(), samples_blob.as_deref(), samples_compressed);
+    let watts = measured_power(
+      watts,
+      device_watts,
+      samples.as_ref().is_some_and(|samples| !samples.watts.is_empty()),
+    );
+    let hr_reliable = samples.as_ref().is_none_or(WorkoutSamples::has_reliable_hr);
+    let hr_samples = samples.map(WorkoutSamples::into_load_hr).unwrap_or_default();
 
-  // Compute flags (now dimension-aware for gap thresholds)
-  let flags = TrainingFlags::compute(&workouts_for_flags, &training_context, &settings, &dimensions);
+    let metrics = WorkoutMetrics::compute(
+      &activity_type,
+      duration,
+      distance,
+      hr,
+      watts,
+      &hr_samples,
+      &settings,
+    )
+    .with_indoor(is_indoor)
+    .with_hr_coverage(hr_reliable);
 
-  // Fetch recent workouts for trend context
-  let recent_same_type = get_recent_same_type_workouts(&state.db, &activity_type, workout_id, 5)
-    .await
-    .unwrap_or_default();
-  let recent_all = get_recent_all_workouts(&state.db, workout_id, 7)
+    // Store computed metrics
+    sqlx::query(
+      r#"
+      UPDATE workouts SET
+        pace_min_per_km = ?1,
+        speed_kmh = ?2,
+        kj = ?3,
+        rtss = ?4,
+        efficiency = ?5,
+        cardiac_cost = ?6,
+        hr_zone = ?7,
+        tss_by_zone_json = ?8,
+        swim_pace_sec_per_100m = ?9,
+        swim_zone = ?10,
+        hr_low_confidence = ?11,
+        efficiency_factor = ?12,
+        data_suspect = ?13,
+        metrics_computed_at = ?14
+      WHERE id = ?15
+      "#,
+    )
+    .bind(metrics.pace_min_per_km)
+    .bind(metrics.speed_kmh)
+    .bind(metrics.kj)
+    .bind(metrics.rtss)
+    .bind(metrics.efficiency)
+    .bind(metrics.cardiac_cost)
+    .bind(metrics.hr_zone.map(|z| z.as_str()))
+    .bind(metrics.tss_by_zone.and_then(|zones| serde_json::to_string(&zones).ok()))
+    .bind(metrics.swim_pace_sec_per_100m)
+    .bind(metrics.swim_zone.map(|z| z.as_str()))
+    .bind(metrics.hr_low_confidence)
+    .bind(metrics.efficiency_factor)
+    .bind(metrics.data_suspect)
+    .bind(Utc::now())
+    .bind(id)
+    .execute(db)
     .await
-    .unwrap_or_default();
+    .map_err(|e| format!("Failed to update workout {}: {}", id, e))?;
 
-  // Build context package
-  let mut context_package = ContextPackage::build(
-    &activity_type,
-    &started_at,
-    duration_seconds,
-    distance_meters,
-    average_hr,
-    average_watts,
-    &metrics,
-    training_context.clone(),
-    flags.clone(),
-    &settings,
-    recent_same_type,
-    recent_all,
-  );
+    computed += 1;
+  }
 
-  // Compute adherence from recent workout data
-  let adherence = compute_adherence(&state.db, &settings).await
-    .unwrap_or_default();
+  Ok(ComputeResult { total, computed })
+}
 
-  // Compute progression summary
-  let progression_summary = ProgressionSummary::compute(
-    &dimensions,
-    &training_context,
-    &flags,
-    adherence,
-  );
+#[derive(Serialize)]
+pub struct ComputeResult {
+  pub total: usize,
+  pub computed: usize,
+}
 
-  // Attach progression summary to context package
-  context_package = context_package.with_progression_summary(progression_summary);
+/// Re-zone every workout with HR under the current zone model without a
+/// full metric recompute (useful right after switching zone models)
+#[tauri::command]
+pub async fn reclassify_zones(
+  state: State<'_, Arc<AppState>>,
+) -> Result<ReclassifyResult, String> {
+  reclassify_hr_zones(&state.db).await
+}
 
-  // Call Claude (V4 format)
+/// Helper: Recompute stored `hr_zone` from average HR in one transaction.
+/// Per-zone load (tss_by_zone) needs the HR stream and is left to
+/// compute_pending_metrics.
+pub(crate) async fn reclassify_hr_zones(db: &crate::db::DbPool) -> Result<ReclassifyResult, String> {
+  let mut settings = load_user_settings(db).await?;
+  if settings.zone_model == ZoneModel::HrReserve && settings.resting_hr.is_none() {
+    settings.resting_hr = load_resting_hr_baseline(db).await.unwrap_or_default();
+  }
+
+  let workouts: Vec<(i64, String, i64, Option<String>, bool)> = sqlx::query_as(
+    r#"
+    SELECT id, activity_type, average_heartrate, hr_zone, hr_low_confidence
+    FROM workouts
+    WHERE average_heartrate IS NOT NULL
+      AND metrics_computed_at IS NOT NULL
+    "#,
+  )
+  .fetch_all(db)
+  .await
+  .map_err(|e| format!("Failed to fetch workouts: {}", e))?;
+
+  let mut tx = db.begin().await.map_err(|e| format!("Failed to start transaction: {}", e))?;
+  let total = workouts.len();
+  let mut changed = 0;
+
+  for (id, activity_type, hr, stored, hr_low_confidence) in workouts {
+    // Same rules as WorkoutMetrics::compute: no zone for supplemental work
+    // or an unreliable HR stream
+    let zone = if hr_low_confidence || is_supplemental_activity(&activity_type) {
+      None
+    } else {
+      settings.hr_zone_for(&activity_type, hr)
+    };
+    let zone = zone.map(|z| z.as_str());
+    if zone == stored.as_deref() {
+      continue;
+    }
+
+    sqlx::query("UPDATE workouts SET hr_zone = ?1 WHERE id = ?2")
+      .bind(zone)
+      .bind(id)
+      .execute(&mut *tx)
+      .await
+      .map_err(|e| format!("Failed to update workout {}: {}", id, e))?;
+    changed += 1;
+  }
+
+  tx.commit().await.map_err(|e| format!("Failed to commit zones: {}", e))?;
+
+  Ok(ReclassifyResult { total, changed })
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReclassifyResult {
+  pub total: usize,
+  pub changed: usize,
+}
+
+/// ---------------------------------------------------------------------------
+/// Get Workout with Computed Metrics
+/// ---------------------------------------------------------------------------
+
+#[derive(Serialize)]
+pub struct WorkoutWithMetrics {
+  pub id: i64,
+  pub strava_id: String,
+  pub activity_type: String,
+  pub started_at: String,
+  pub duration_seconds: Option<i64>,
+  pub distance_meters: Option<f64>,
+  pub average_heartrate: Option<i64>,
+  pub average_watts: Option<f64>,
+  pub suffer_score: Option<f64>,
+  // Computed metrics
+  pub pace_min_per_km: Option<f64>,
+  pub speed_kmh: Option<f64>,
+  pub kj: Option<f64>,
+  pub rtss: Option<f64>,
+  pub efficiency: Option<f64>,
+  pub cardiac_cost: Option<f64>,
+  pub hr_zone: Option<String>,
+  /// Distance, pace and speed in the athlete's units, with labels
+  pub display: UnitMetrics,
+}
+
+#[tauri::command]
+pub async fn get_workouts_with_metrics(
+  state: State<'_, Arc<AppState>>,
+  limit: Option<i64>,
+) -> Result<Vec<WorkoutWithMetrics>, String> {
+  load_workouts_with_metrics(&state.db, limit.unwrap_or(50)).await
+}
+
+/// Helper: Most recent workouts with their stored metrics, converted for
+/// display in the configured units
+pub(crate) async fn load_workouts_with_metrics(
+  db: &crate::db::DbPool,
+  limit: i64,
+) -> Result<Vec<WorkoutWithMetrics>, String> {
+  let units = load_user_settings(db).await?.units;
+
+  println!("Fetching workouts with limit: {}", limit);
+
+  let rows: Vec<(
+    i64, String, String, String, Option<i64>, Option<f64>,
+    Option<i64>, Option<f64>, Option<f64>,
+    Option<f64>, Option<f64>, Option<f64>, Option<f64>,
+    Option<f64>, Option<f64>, Option<String>,
+  )> = sqlx::query_as(
+    r#"
+    SELECT
+      id, strava_id, activity_type, started_at,
+      duration_seconds, CAST(distance_meters AS REAL), average_heartrate,
+      CAST(average_watts AS REAL), CAST(suffer_score AS REAL),
+      CAST(pace_min_per_km AS REAL), CAST(speed_kmh AS REAL), CAST(kj AS REAL),
+      CAST(rtss AS REAL), CAST(efficiency AS REAL), CAST(cardiac_cost AS REAL), hr_zone
+    FROM workouts
+    ORDER BY started_at DESC
+    LIMIT ?1
+    "#,
+  )
+  .bind(limit)
+  .fetch_all(db)
+  .await
+  .map_err(|e| {
+    println!("Query error: {}", e);
+    format!("Failed to fetch workouts: {}", e)
+  })?;
+
+  println!("Fetched {} rows", rows.len());
+
+  let workouts = rows
+    .into_iter()
+    .map(|(
+      id, strava_id, activity_type, started_at,
+      duration_seconds, distance_meters, average_heartrate, average_watts, suffer_score,
+      pace_min_per_km, speed_kmh, kj, rtss, efficiency, cardiac_cost, hr_zone,
+    )| WorkoutWithMetrics {
+      id,
+      strava_id,
+      activity_type,
+      started_at,
+      duration_seconds,
+      distance_meters,
+      average_heartrate,
+      average_watts,
+      suffer_score,
+      pace_min_per_km,
+      speed_kmh,
+      kj,
+      rtss,
+      efficiency,
+      cardiac_cost,
+      hr_zone,
+      display: UnitMetrics::new(units, distance_meters.map(|m| m / 1000.0), pace_min_per_km, speed_kmh),
+    })
+    .collect();
+
+  Ok(workouts)
+}
+
+/// The metrics stored for one workout by compute_pending_metrics (None if
+/// the workout doesn't exist)
+pub(crate) async fn load_stored_metrics(
+  db: &crate::db::DbPool,
+  workout_id: i64,
+) -> Result<Option<WorkoutMetrics>, sqlx::Error> {
+  let row: Option<(
+    Option<f64>, Option<f64>, Option<f64>, Option<f64>, Option<f64>, Option<f64>, Option<String>,
+    Option<String>, Option<f64>, Option<String>, bool, Option<f64>, bool,
+  )> = sqlx::query_as(
+    r#"
+    SELECT
+      CAST(pace_min_per_km AS REAL), CAST(speed_kmh AS REAL), CAST(kj AS REAL),
+      CAST(rtss AS REAL), CAST(efficiency AS REAL), CAST(cardiac_cost AS REAL), hr_zone,
+      tss_by_zone_json, CAST(swim_pace_sec_per_100m AS REAL), swim_zone, hr_low_confidence,
+      CAST(efficiency_factor AS REAL), data_suspect
+    FROM workouts
+    WHERE id = ?1
+    "#,
+  )
+  .bind(workout_id)
+  .fetch_optional(db)
+  .await?;
+
+  Ok(row.map(
+    |(
+      pace_min_per_km, speed_kmh, kj, rtss, efficiency, cardiac_cost, hr_zone,
+      tss_by_zone_json, swim_pace_sec_per_100m, swim_zone, hr_low_confidence, efficiency_factor, data_suspect,
+    )| WorkoutMetrics {
+      pace_min_per_km,
+      speed_kmh,
+      kj,
+      rtss,
+      efficiency,
+      efficiency_factor,
+      cardiac_cost,
+      hr_zone: hr_zone.as_deref().and_then(HrZone::parse),
+      tss_by_zone: tss_by_zone_json.and_then(|json| serde_json::from_str(&json).ok()),
+      swim_pace_sec_per_100m,
+      swim_zone: swim_zone.as_deref().and_then(SwimZone::parse),
+      hr_low_confidence,
+      data_suspect,
+    },
+  ))
+}
+
+/// ---------------------------------------------------------------------------
+/// Get Training Context (Tier 2 Rolling Metrics)
+/// ---------------------------------------------------------------------------
+
+#[tauri::command]
+pub async fn get_training_context(
+  state: State<'_, Arc<AppState>>,
+) -> Result<TrainingContext, String> {
+  // Get user settings
+  let settings = get_user_settings(state.clone()).await?;
+
+  // Fetch workouts from last 42 days (needed for CTL calculation)
+  let workouts = get_workout_summaries(&state.db)
+    .await
+    .map_err(|e| format!("Failed to fetch workouts for context: {}", e))?;
+
+  Ok(TrainingContext::compute(&workouts, &settings))
+}
+
+/// ---------------------------------------------------------------------------
+/// LLM Workout Analysis Commands
+/// ---------------------------------------------------------------------------
+
+/// What went wrong, so the UI can offer the right recovery action
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AnalysisErrorKind {
+  /// The workout (or another required row) doesn't exist
+  NotFound,
+  /// Reading or writing the local database failed
+  Database,
+  /// The Claude request failed or was rejected (rate limits land here - retry)
+  Llm,
+  /// A response or stored value couldn't be parsed
+  Parse,
+  /// Missing configuration, e.g. no API key
+  Config,
+}
+
+/// Error type that can be serialized for Tauri as `{kind, message}`
+#[derive(Debug, Serialize)]
+pub struct AnalysisError {
+  pub kind: AnalysisErrorKind,
+  pub message: String,
+}
+
+impl AnalysisError {
+  pub fn new(kind: AnalysisErrorKind, message: impl Into<String>) -> Self {
+    Self {
+      kind,
+      message: message.into(),
+    }
+  }
+}
+
+impl From<LlmError> for AnalysisError {
+  fn from(e: LlmError) -> Self {
+    let kind = match e {
+      LlmError::MissingApiKey => AnalysisErrorKind::Config,
+      LlmError::Request(_) | LlmError::Api(_) => AnalysisErrorKind::Llm,
+      LlmError::Parse(_) => AnalysisErrorKind::Parse,
+    };
+    Self::new(kind, e.to_string())
+  }
+}
+
+impl From<sqlx::Error> for AnalysisError {
+  fn from(e: sqlx::Error) -> Self {
+    let kind = match e {
+      sqlx::Error::RowNotFound => AnalysisErrorKind::NotFound,
+      _ => AnalysisErrorKind::Database,
+    };
+    Self::new(kind, format!("Database error: {}", e))
+  }
+}
+
+/// Result of analyzing a workout with Claude (V4 format)
+#[derive(Serialize)]
+pub struct WorkoutAnalysisResult {
+  pub workout_id: i64,
+  pub analysis: WorkoutAnalysisV4,  // V4 multi-card format
+  /// Model that produced the analysis (cheap for routine sessions)
+  pub model: String,
+  pub input_tokens: u32,
+  pub output_tokens: u32,
+  /// Size of the context package sent, after trimming
+  pub context_chars: usize,
+}
+
+/// Stored analysis with ID for frontend
+#[derive(Debug, Clone, Serialize)]
+pub struct StoredWorkoutAnalysis {
+  pub id: Option<i64>,
+  pub workout_id: i64,
+  pub summary: String,
+  pub tomorrow_recommendation: String,
+  pub risk_flags: Vec<String>,
+  pub goal_notes: Option<String>,
+  pub created_at: Option<String>,
+  /// The workout or settings changed since this analysis ran (re-analyze
+  /// recommended). False for analyses stored before inputs were tracked.
+  pub is_stale: bool,
+}
+
+/// Analyze a specific workout with Claude
+#[tauri::command]
+pub async fn analyze_workout(
+  state: State<'_, Arc<AppState>>,
+  workout_id: i64,
+) -> Result<WorkoutAnalysisResult, AnalysisError> {
+  // One analysis at a time: the background scheduler takes the same lock
+  let _guard = state.analysis_lock.lock().await;
+  run_workout_analysis(&state.db, workout_id).await
+}
+
+/// Helper: Build the context package, call Claude and store the result
+/// (shared by the command and the auto-analyze scheduler)
+pub(crate) async fn run_workout_analysis(
+  db: &crate::db::DbPool,
+  workout_id: i64,
+) -> Result<WorkoutAnalysisResult, AnalysisError> {
+  let PreparedAnalysis {
+    context: context_package,
+    flags,
+    observations,
+    local_date,
+    trim,
+    coach_tone,
+    threshold_test,
+    key_session,
+  } = prepare_analysis(db, workout_id).await?;
+
+  // Call Claude (V4 format); routine sessions go to the cheaper model
+  let model = select_model(&context_package, &flags, threshold_test.as_ref(), key_session);
   let client = ClaudeClient::from_env()?;
   let context_json = context_package.to_json();
   println!("=== CONTEXT PACKAGE ===\n{}\n=== END CONTEXT ===", context_json);
-  let (v4_analysis, usage) = client.analyze_workout_v4_or_fallback(&context_json).await?;
+  let (mut v4_analysis, usage) = client
+    .analyze_workout_v4_or_fallback(model, &context_json, coach_tone)
+    .await?;
+  record_token_usage(db, "analysis", &format!("{}-v4", model), &usage)
+    .await
+    .map_err(|e| AnalysisError::new(AnalysisErrorKind::Database, format!("Failed to record token usage: {}", e)))?;
+
+  // Rust-computed confidence caps the LLM's self-reported one
+  let confidence = context_package
+    .prescription_confidence
+    .cap(&v4_analysis.tomorrow.confidence);
+  if confidence != v4_analysis.tomorrow.confidence {
+    println!(
+      "Overriding LLM confidence '{}' with '{}'",
+      v4_analysis.tomorrow.confidence, confidence
+    );
+    v4_analysis.tomorrow.confidence = confidence;
+  }
+
+  // Rust owns the performance numbers; keep only the LLM's insight
+  if let Some(card) = context_package.performance.clone() {
+    v4_analysis.performance = PerformanceCard { insight: v4_analysis.performance.insight, ..card };
+  }
 
   // Convert V4 to legacy for DB storage (backward compatibility)
   let legacy_analysis: crate::llm::WorkoutAnalysis = v4_analysis.clone().into();
@@ -491,9 +1457,9 @@ pub async fn analyze_workout(
     r#"
     INSERT INTO workout_analysis (
       workout_id, summary, tomorrow_recommendation, risk_flags_json,
-      goal_notes, model_version, input_tokens, output_tokens
+      goal_notes, model_version, input_tokens, output_tokens, analysis_json, context_hash
     )
-    VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+    VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
     ON CONFLICT(workout_id) DO UPDATE SET
       summary = excluded.summary,
       tomorrow_recommendation = excluded.tomorrow_recommendation,
@@ -502,6 +1468,8 @@ pub async fn analyze_workout(
       model_version = excluded.model_version,
       input_tokens = excluded.input_tokens,
       output_tokens = excluded.output_tokens,
+      analysis_json = excluded.analysis_json,
+      context_hash = excluded.context_hash,
       created_at = CURRENT_TIMESTAMP
     "#,
   )
@@ -510,95 +1478,356 @@ pub async fn analyze_workout(
   .bind(&legacy_analysis.tomorrow_recommendation)
   .bind(&risk_flags_json)
   .bind(&legacy_analysis.goal_notes)
-  .bind("claude-sonnet-4-20250514-v4")
+  .bind(format!("{}-v4", model))
   .bind(usage.input_tokens as i64)
   .bind(usage.output_tokens as i64)
-  .execute(&state.db)
+  .bind(serde_json::to_string(&v4_analysis).ok())
+  .bind(context_package.input_hash(coach_tone))
+  .execute(db)
   .await
-  .map_err(|e| AnalysisError::from(format!("Failed to store analysis: {}", e)))?;
+  .map_err(|e| AnalysisError::new(AnalysisErrorKind::Database, format!("Failed to store analysis: {}", e)))?;
+
+  store_observations(db, workout_id, &observations)
+    .await
+    .map_err(|e| AnalysisError::new(AnalysisErrorKind::Database, format!("Failed to store observations: {}", e)))?;
+
+  // Keep tomorrow's prescription so the next session can be checked against it
+  store_prescription(db, workout_id, local_date, &v4_analysis.tomorrow)
+    .await
+    .map_err(|e| AnalysisError::new(AnalysisErrorKind::Database, format!("Failed to store prescription: {}", e)))?;
 
   println!(
-    "Analyzed workout {}: {} tokens in, {} tokens out",
-    workout_id, usage.input_tokens, usage.output_tokens
+    "Analyzed workout {} with {}: {} tokens in, {} tokens out",
+    workout_id, model, usage.input_tokens, usage.output_tokens
   );
 
   // Return V4 format to frontend
   Ok(WorkoutAnalysisResult {
     workout_id,
     analysis: v4_analysis,
+    model: model.to_string(),
     input_tokens: usage.input_tokens,
     output_tokens: usage.output_tokens,
+    context_chars: trim.final_chars,
   })
 }
 
-/// Get stored analysis for a workout
-#[tauri::command]
-pub async fn get_workout_analysis(
-  state: State<'_, Arc<AppState>>,
-  workout_id: i64,
-) -> Result<Option<StoredWorkoutAnalysis>, String> {
-  let row: Option<(i64, i64, String, String, Option<String>, Option<String>, String)> =
-    sqlx::query_as(
-      r#"
-      SELECT id, workout_id, summary, tomorrow_recommendation,
-             risk_flags_json, goal_notes, created_at
-      FROM workout_analysis
-      WHERE workout_id = ?1
-      "#,
-    )
-    .bind(workout_id)
-    .fetch_optional(&state.db)
-    .await
-    .map_err(|e| format!("Failed to fetch analysis: {}", e))?;
-
-  match row {
-    Some((id, wid, summary, rec, flags_json, notes, created)) => {
-      let risk_flags: Vec<String> = flags_json
-        .as_ref()
-        .and_then(|s| serde_json::from_str(s).ok())
-        .unwrap_or_default();
-
-      Ok(Some(StoredWorkoutAnalysis {
-        id: Some(id),
-        workout_id: wid,
-        summary,
-        tomorrow_recommendation: rec,
-        risk_flags,
-        goal_notes: notes,
-        created_at: Some(created),
-      }))
-    }
-    None => Ok(None),
-  }
+/// Everything assembled for one analysis before the LLM call
+pub(crate) struct PreparedAnalysis {
+  pub context: ContextPackage,
+  pub flags: TrainingFlags,
+  /// What this session adds to the coach's memory (stored after the analysis)
+  pub observations: Vec<Observation>,
+  /// The workout's local date (tomorrow's prescription targets the day after)
+  pub local_date: chrono::NaiveDate,
+  /// How the context was cut down to the character budget
+  pub trim: ContextTrim,
+  /// Voice for the coach's system prompt
+  pub coach_tone: CoachTone,
+  /// Threshold test found in the workout's streams
+  pub threshold_test: Option<TestResult>,
+  /// The athlete linked this workout as a key session (mark_key_session)
+  pub key_session: bool,
 }
 
-/// Get the latest workout analysis (most recent workout that has an analysis)
+/// Helper: Build the context package a workout's analysis would send with
+/// the current data (also used to tell whether a stored analysis is stale)
+pub(crate) async fn prepare_analysis(
+  db: &crate::db::DbPool,
+  workout_id: i64,
+) -> Result<PreparedAnalysis, AnalysisError> {
+  // Get the workout data
+  let workout: Option<(
+    i64,
+    String,
+    String,
+    Option<i64>,
+    Option<f64>,
+    Option<i64>,
+    Option<f64>,
+    Option<i64>,
+    Option<String>,
+    Option<String>,
+    Option<Vec<u8>>,
+    bool,
+    bool,
+    Option<String>,
+    Option<String>,
+  )> = sqlx::query_as(
+    r#"
+    SELECT
+      id, activity_type, started_at, duration_seconds,
+      CAST(distance_meters AS REAL), average_heartrate,
+      CASE WHEN device_watts = 1 OR (device_watts IS NULL AND average_watts > 0) THEN CAST(average_watts AS REAL) END,
+      rpe, notes, samples_json, samples_blob, samples_compressed, is_indoor, name, description
+    FROM workouts
+    WHERE id = ?1
+    "#,
+  )
+  .bind(workout_id)
+  .fetch_optional(db)
+  .await
+  .map_err(|e| AnalysisError::new(AnalysisErrorKind::Database, format!("Failed to fetch workout: {}", e)))?;
+
+  let (
+    _id,
+    activity_type,
+    started_at_str,
+    duration_seconds,
+    distance_meters,
+    average_hr,
+    average_watts,
+    rpe,
+    notes,
+    samples_json,
+    samples_blob,
+    samples_compressed,
+    is_indoor,
+    name,
+    description,
+  ) = workout.ok_or_else(|| AnalysisError::new(AnalysisErrorKind::NotFound, "Workout not found"))?;
+
+  // Full stored metric set (efficiency, kj, ...), not just the columns above
+  let mut metrics = load_stored_metrics(db, workout_id)
+    .await
+    .map_err(|e| AnalysisError::new(AnalysisErrorKind::Database, format!("Failed to fetch workout metrics: {}", e)))?
+    .unwrap_or_default();
+
+  // HR-less sessions fall back to the session-RPE load estimate
+  metrics.rtss = metrics.rtss.or_else(|| estimate_rtss_from_rpe(&activity_type, rpe, duration_seconds));
+
+  // Parse the started_at timestamp
+  let started_at = DateTime::parse_from_rfc3339(&started_at_str)
+    .or_else(|_| DateTime::parse_from_str(&started_at_str, "%Y-%m-%dT%H:%M:%SZ"))
+    .map(|dt| dt.with_timezone(&Utc))
+    .map_err(|e| AnalysisError::new(AnalysisErrorKind::Parse, format!("Failed to parse date: {}", e)))?;
+
+  // Get user settings
+  let settings = load_user_settings(db)
+    .await
+    .map_err(|e| AnalysisError::new(AnalysisErrorKind::Database, e))?;
+
+  // All workouts, for rolling context and flag computation
+  let workouts_for_flags = get_workout_summaries(db)
+    .await
+    .map_err(|e| {
+      AnalysisError::new(AnalysisErrorKind::Database, format!("Failed to get workout summaries: {}", e))
+    })?;
+
+  // Get training context (includes all workouts for rolling calculations)
+  let training_context = TrainingContext::compute(&workouts_for_flags, &settings);
+
+  // Load progression dimensions FIRST (needed for flag computation)
+  let dimensions = load_all_dimensions(db)
+    .await
+    .map_err(|e| {
+      AnalysisError::new(AnalysisErrorKind::Database, format!("Failed to load progression dimensions: {}", e))
+    })?;
+
+  // Periodization phase on the workout's date weighs flag priorities
+  let phase = load_training_phase(db, &settings, settings.local_date(&started_at))
+    .await
+    .unwrap_or_default()
+    .map(|p| p.phase);
+
+  // Compute flags (now dimension-aware for gap thresholds)
+  let flags = TrainingFlags::compute(&workouts_for_flags, &training_context, &settings, &dimensions)
+    .with_phase(phase);
+
+  // Fetch recent workouts for trend context (count or time-bounded per settings)
+  let recent_window = &settings.recent_window;
+  let bounds = recent_bounds(&started_at, recent_window.window_days);
+  let comparison_target = ComparisonTarget {
+    date: settings.local_date(&started_at),
+    duration_min: duration_seconds.map(|s| s as f64 / 60.0).unwrap_or(0.0),
+    rtss: metrics.rtss,
+  };
+  let recent_same_type = get_recent_same_type_workouts(
+    db,
+    &settings,
+    &activity_type,
+    workout_id,
+    recent_window.same_type_count,
+    bounds.as_ref(),
+    &comparison_target,
+  )
+  .await
+  .unwrap_or_default();
+  let recent_all = get_recent_all_workouts(db, &settings, workout_id, recent_window.all_type_count, bounds.as_ref())
+    .await
+    .unwrap_or_default();
+
+  // What this session adds to the coach's memory (stored after the analysis)
+  let observations = Observation::extract(&activity_type, average_hr, &recent_same_type, &flags);
+  let recurring_observations = load_recurring_observations(db, workout_id, &started_at)
+    .await
+    .unwrap_or_default();
+
+  // Compute adherence from recent workout data
+  let adherence = compute_adherence(db, &settings).await
+    .unwrap_or_default();
+
+  // Build context package
+  let mut context_package = ContextPackage::build(
+    &activity_type,
+    &started_at,
+    duration_seconds,
+    distance_meters,
+    average_hr,
+    average_watts,
+    &metrics,
+    training_context.clone(),
+    flags.clone(),
+    adherence.adherence_pct as f64,
+    &settings,
+    recent_same_type,
+    recent_all,
+  );
+
+  // Compute progression summary
+  let progression_summary = ProgressionSummary::compute(
+    &dimensions,
+    &training_context,
+    &flags,
+    adherence,
+    settings.progression_overlap_days,
+    settings.experience_level,
+  );
+
+  // Recent athlete ratings of prescriptions so the coach can self-correct
+  let recent_feedback = load_recent_feedback(db, FEEDBACK_CONTEXT_LIMIT)
+    .await
+    .unwrap_or_default();
+
+  // Morning sleep/HRV/RHR for the workout's own date (not just "last night")
+  let oura = load_oura_context(db, settings.local_date(&started_at), settings.sleep_target_hours)
+    .await
+    .unwrap_or_default();
+
+  // The athlete's journal for the same local date
+  let daily_log = load_daily_log(db, settings.local_date(&started_at))
+    .await
+    .unwrap_or_default();
+
+  // What the coach prescribed for this session's date (by an earlier analysis)
+  let local_date = settings.local_date(&started_at);
+  let prescription = load_prescription_for(db, local_date, workout_id)
+    .await
+    .unwrap_or_default();
+
+  // Training-plan sessions, to compare intended with executed intensity
+  let plan_days = load_active_training_plan(db, local_date)
+    .await
+    .unwrap_or_default()
+    .map(|stored| stored.plan.days)
+    .unwrap_or_default();
+
+  // HR drift against output; None for missing or unreliable streams
+  let samples = WorkoutSamples::from_stored(samples_json.as_deref(), samples_blob.as_deref(), samples_compressed);
+  let decoupling = samples.as_ref().and_then(|samples| compute_decoupling(samples, &activity_type));
+  let threshold_test = samples.as_ref().and_then(|samples| detect_threshold_test(samples, &activity_type));
+  let key_session = is_key_session(db, workout_id)
+    .await
+    .map_err(|e| AnalysisError::new(AnalysisErrorKind::Database, e))?;
+
+  // Attach progression summary, feedback and recovery to context package
+  context_package = context_package
+    .with_progression_summary(progression_summary)
+    .with_feedback(recent_feedback)
+    .with_observations(recurring_observations)
+    .with_oura(oura)
+    .with_daily_log(daily_log)
+    .with_prescription(prescription)
+    .with_plan_days(&plan_days)
+    .with_subjective(rpe, notes)
+    .with_activity_text(name, description)
+    .with_decoupling(decoupling)
+    .with_indoor(is_indoor);
+
+  // Keep cost bounded as history grows
+  let trim = context_package.trim_to_budget(settings.context_char_budget.max(0) as usize);
+  if !trim.steps.is_empty() {
+    println!(
+      "Trimmed context for workout {} from {} to {} chars (budget {}): {}",
+      workout_id,
+      trim.original_chars,
+      trim.final_chars,
+      trim.budget_chars,
+      trim.steps.join(", ")
+    );
+  }
+
+  Ok(PreparedAnalysis {
+    context: context_package,
+    flags,
+    observations,
+    local_date,
+    trim,
+    coach_tone: settings.coach_tone,
+    threshold_test,
+    key_session,
+  })
+}
+
+/// Get stored analysis for a workout
+#[tauri::command]
+pub async fn get_workout_analysis(
+  state: State<'_, Arc<AppState>>,
+  workout_id: i64,
+) -> Result<Option<StoredWorkoutAnalysis>, String> {
+  load_stored_analysis(&state.db, Some(workout_id)).await
+}
+
+/// Get the latest workout analysis (most recent workout that has an analysis)
 #[tauri::command]
 pub async fn get_latest_analysis(
   state: State<'_, Arc<AppState>>,
 ) -> Result<Option<StoredWorkoutAnalysis>, String> {
-  let row: Option<(i64, i64, String, String, Option<String>, Option<String>, String)> =
+  load_stored_analysis(&state.db, None).await
+}
+
+/// Helper: Load the analysis of one workout (None = the most recent workout
+/// with an analysis) and check it against the current inputs
+pub(crate) async fn load_stored_analysis(
+  db: &crate::db::DbPool,
+  workout_id: Option<i64>,
+) -> Result<Option<StoredWorkoutAnalysis>, String> {
+  let row: Option<(i64, i64, String, String, Option<String>, Option<String>, String, Option<String>)> =
     sqlx::query_as(
       r#"
       SELECT wa.id, wa.workout_id, wa.summary, wa.tomorrow_recommendation,
-             wa.risk_flags_json, wa.goal_notes, wa.created_at
+             wa.risk_flags_json, wa.goal_notes, wa.created_at, wa.context_hash
       FROM workout_analysis wa
       JOIN workouts w ON w.id = wa.workout_id
+      WHERE ?1 IS NULL OR wa.workout_id = ?1
       ORDER BY w.started_at DESC
       LIMIT 1
       "#,
     )
-    .fetch_optional(&state.db)
+    .bind(workout_id)
+    .fetch_optional(db)
     .await
     .map_err(|e| format!("Failed to fetch analysis: {}", e))?;
 
   match row {
-    Some((id, wid, summary, rec, flags_json, notes, created)) => {
+    Some((id, wid, summary, rec, flags_json, notes, created, context_hash)) => {
       let risk_flags: Vec<String> = flags_json
         .as_ref()
         .and_then(|s| serde_json::from_str(s).ok())
         .unwrap_or_default();
 
+      // Rebuild (without sending) what an analysis would see now
+      let is_stale = match context_hash {
+        Some(stored) => match prepare_analysis(db, wid).await {
+          Ok(prepared) => prepared.context.input_hash(prepared.coach_tone) != stored,
+          Err(e) => {
+            println!("Could not rebuild context for workout {}: {}", wid, e.message);
+            false
+          }
+        },
+        None => false,
+      };
+
       Ok(Some(StoredWorkoutAnalysis {
         id: Some(id),
         workout_id: wid,
@@ -607,230 +1836,2371 @@ pub async fn get_latest_analysis(
         risk_flags,
         goal_notes: notes,
         created_at: Some(created),
+        is_stale,
       }))
     }
     None => Ok(None),
   }
 }
 
-/// Helper: Get workout summaries for flag computation
-async fn get_workout_summaries(
-  db: &crate::db::DbPool,
-) -> Result<Vec<WorkoutSummary>, sqlx::Error> {
-  let rows: Vec<(String, String, Option<i64>, Option<f64>, Option<String>)> = sqlx::query_as(
-    r#"
-    SELECT started_at, activity_type, duration_seconds,
-           CAST(rtss AS REAL), hr_zone
-    FROM workouts
-    WHERE started_at >= datetime('now', '-42 days')
-    ORDER BY started_at DESC
-    "#,
-  )
-  .fetch_all(db)
-  .await?;
-
-  let workouts: Vec<WorkoutSummary> = rows
-    .into_iter()
-    .filter_map(|(started_at, activity_type, duration_seconds, rtss, hr_zone)| {
-      let dt = DateTime::parse_from_rfc3339(&started_at)
-        .or_else(|_| DateTime::parse_from_str(&started_at, "%Y-%m-%dT%H:%M:%SZ"))
-        .or_else(|_| {
-          DateTime::parse_from_str(&format!("{}+00:00", started_at), "%Y-%m-%d %H:%M:%S%:z")
-        })
-        .ok()?;
+/// What changed in the coach's read between the analyses of two workouts
+/// (TSB band, flags, progression state, tomorrow's prescription)
+#[tauri::command]
+pub async fn diff_analyses(
+  state: State<'_, Arc<AppState>>,
+  workout_id_a: i64,
+  workout_id_b: i64,
+) -> Result<AnalysisDiff, String> {
+  diff_stored_analyses(&state.db, workout_id_a, workout_id_b).await
+}
 
-      let hr_zone_enum = hr_zone.as_ref().and_then(|z| match z.as_str() {
-        "Z1" => Some(HrZone::Z1),
-        "Z2" => Some(HrZone::Z2),
-        "Z3" => Some(HrZone::Z3),
-        "Z4" => Some(HrZone::Z4),
-        "Z5" => Some(HrZone::Z5),
-        _ => None,
-      });
+/// Helper: Diff the stored V4 analyses of two workouts
+pub(crate) async fn diff_stored_analyses(
+  db: &crate::db::DbPool,
+  workout_id_a: i64,
+  workout_id_b: i64,
+) -> Result<AnalysisDiff, String> {
+  let a = require_stored_v4(db, workout_id_a).await?;
+  let b = require_stored_v4(db, workout_id_b).await?;
+  Ok(AnalysisDiff::between(&a, &b))
+}
 
-      Some(WorkoutSummary {
-        started_at: dt.with_timezone(&Utc),
-        activity_type,
-        duration_seconds,
-        rtss,
-        hr_zone: hr_zone_enum,
-      })
-    })
-    .collect();
+async fn require_stored_v4(db: &crate::db::DbPool, workout_id: i64) -> Result<WorkoutAnalysisV4, String> {
+  load_stored_v4(db, workout_id)
+    .await
+    .map_err(|e| format!("Failed to fetch analysis: {}", e))?
+    .ok_or_else(|| format!("No full analysis stored for workout {} (re-analyze it)", workout_id))
+}
 
-  Ok(workouts)
+/// The full V4 analysis stored for a workout. None when there is no
+/// analysis, or it predates full storage.
+pub(crate) async fn load_stored_v4(
+  db: &crate::db::DbPool,
+  workout_id: i64,
+) -> Result<Option<WorkoutAnalysisV4>, sqlx::Error> {
+  let json: Option<Option<String>> =
+    sqlx::query_scalar("SELECT analysis_json FROM workout_analysis WHERE workout_id = ?1")
+      .bind(workout_id)
+      .fetch_optional(db)
+      .await?;
+  Ok(json.flatten().and_then(|json| serde_json::from_str(&json).ok()))
 }
 
 /// ---------------------------------------------------------------------------
-/// Recent Workouts for Trend Context
+/// Workout Export
 /// ---------------------------------------------------------------------------
 
-/// Get recent workouts of the same type for trend comparison
-/// Excludes the current workout being analyzed
-async fn get_recent_same_type_workouts(
+/// One workout as a self-contained artifact for a coach or second opinion
+#[derive(Debug, Serialize)]
+pub struct WorkoutBundle {
+  pub exported_at: String,
+  /// The stored workout row as synced
+  pub workout: Workout,
+  pub metrics: WorkoutMetrics,
+  pub samples: Option<WorkoutSamples>,
+  /// The context an analysis would send with the current data
+  pub context: ContextPackage,
+  /// Stored V4 analysis (None if never analyzed, or analyzed before full storage)
+  pub analysis: Option<WorkoutAnalysisV4>,
+}
+
+/// Write one workout's full analysis bundle as JSON to `path`. The
+/// destination must not exist yet.
+#[tauri::command]
+pub async fn export_workout_bundle(
+  state: State<'_, Arc<AppState>>,
+  workout_id: i64,
+  path: String,
+) -> Result<(), String> {
+  export_workout_bundle_to(&state.db, workout_id, Path::new(&path)).await
+}
+
+/// Helper: Assemble a workout's bundle and write it to `dest`
+pub(crate) async fn export_workout_bundle_to(
   db: &crate::db::DbPool,
-  activity_type: &str,
-  exclude_workout_id: i64,
-  limit: i32,
-) -> Result<Vec<RecentWorkoutSummary>, String> {
-  let rows: Vec<(
-    String, String, Option<i64>, Option<f64>, Option<i64>,
-    Option<f64>, Option<f64>, Option<f64>,
-  )> = sqlx::query_as(
-    r#"
-    SELECT
-      started_at,
-      activity_type,
-      duration_seconds,
-      CAST(average_watts AS REAL),
-      average_heartrate,
-      CAST(pace_min_per_km AS REAL),
-      CAST(rtss AS REAL),
-      CAST(efficiency AS REAL)
-    FROM workouts
-    WHERE activity_type = ?1 AND id != ?2
-    ORDER BY started_at DESC
-    LIMIT ?3
-    "#,
-  )
-  .bind(activity_type)
-  .bind(exclude_workout_id)
-  .bind(limit)
-  .fetch_all(db)
-  .await
-  .map_err(|e| format!("Failed to fetch recent same-type workouts: {}", e))?;
+  workout_id: i64,
+  dest: &Path,
+) -> Result<(), String> {
+  if dest.exists() {
+    return Err(format!("Export destination already exists: {}", dest.display()));
+  }
 
-  let workouts = rows
-    .into_iter()
-    .filter_map(|(started_at, activity_type, duration_secs, watts, hr, pace, rtss, efficiency)| {
-      let dt = DateTime::parse_from_rfc3339(&started_at)
-        .or_else(|_| DateTime::parse_from_str(&started_at, "%Y-%m-%dT%H:%M:%SZ"))
-        .ok()?;
+  let bundle = build_workout_bundle(db, workout_id).await?;
+  let json =
+    serde_json::to_string_pretty(&bundle).map_err(|e| format!("Failed to serialize workout bundle: {}", e))?;
+  std::fs::write(dest, json).map_err(|e| format!("Failed to write {}: {}", dest.display(), e))
+}
 
-      let duration_min = duration_secs.map(|s| s as f64 / 60.0).unwrap_or(0.0);
+async fn build_workout_bundle(db: &crate::db::DbPool, workout_id: i64) -> Result<WorkoutBundle, String> {
+  let workout = sqlx::query_as::<_, Workout>("SELECT * FROM workouts WHERE id = ?1")
+    .bind(workout_id)
+    .fetch_optional(db)
+    .await
+    .map_err(|e| format!("Failed to fetch workout: {}", e))?
+    .ok_or_else(|| format!("Workout {} not found", workout_id))?;
 
-      Some(RecentWorkoutSummary {
-        date: dt.format("%Y-%m-%d").to_string(),
-        activity_type,
-        duration_min,
-        avg_power: watts,
-        avg_hr: hr,
-        pace_min_km: pace,
-        rtss,
-        efficiency,
-      })
-    })
-    .collect();
+  let metrics = load_stored_metrics(db, workout_id)
+    .await
+    .map_err(|e| format!("Failed to fetch workout metrics: {}", e))?
+    .unwrap_or_default();
+  let samples = load_workout_samples(db, workout_id)
+    .await
+    .map_err(|e| format!("Failed to fetch workout samples: {}", e))?;
+  let context = prepare_analysis(db, workout_id)
+    .await
+    .map_err(|e| format!("Failed to build context: {}", e.message))?
+    .context;
+  let analysis = load_stored_v4(db, workout_id)
+    .await
+    .map_err(|e| format!("Failed to fetch analysis: {}", e))?;
 
-  Ok(workouts)
+  Ok(WorkoutBundle {
+    exported_at: Utc::now().to_rfc3339(),
+    workout,
+    metrics,
+    samples,
+    context,
+    analysis,
+  })
 }
 
-/// Get recent workouts of any type for weekly context
-/// Excludes the current workout being analyzed
-async fn get_recent_all_workouts(
+/// The HR/power/pace samples stored for a workout (None if never fetched)
+pub(crate) async fn load_workout_samples(
   db: &crate::db::DbPool,
-  exclude_workout_id: i64,
-  limit: i32,
-) -> Result<Vec<RecentWorkoutSummary>, String> {
-  let rows: Vec<(
-    String, String, Option<i64>, Option<f64>, Option<i64>,
-    Option<f64>, Option<f64>, Option<f64>,
-  )> = sqlx::query_as(
-    r#"
-    SELECT
-      started_at,
-      activity_type,
-      duration_seconds,
-      CAST(average_watts AS REAL),
-      average_heartrate,
-      CAST(pace_min_per_km AS REAL),
-      CAST(rtss AS REAL),
-      CAST(efficiency AS REAL)
-    FROM workouts
-    WHERE id != ?1
-    ORDER BY started_at DESC
-    LIMIT ?2
-    "#,
-  )
-  .bind(exclude_workout_id)
-  .bind(limit)
-  .fetch_all(db)
-  .await
-  .map_err(|e| format!("Failed to fetch recent all workouts: {}", e))?;
+  workout_id: i64,
+) -> Result<Option<WorkoutSamples>, sqlx::Error> {
+  let row: Option<(Option<String>, Option<Vec<u8>>, bool)> =
+    sqlx::query_as("SELECT samples_json, samples_blob, samples_compressed FROM workouts WHERE id = ?1")
+      .bind(workout_id)
+      .fetch_optional(db)
+      .await?;
+  Ok(row.and_then(|(json, blob, compressed)| WorkoutSamples::from_stored(json.as_deref(), blob.as_deref(), compressed)))
+}
 
-  let workouts = rows
+/// ---------------------------------------------------------------------------
+/// Observations (coach memory)
+/// ---------------------------------------------------------------------------
+
+/// Helper: Replace a workout's observations (re-analysis starts fresh)
+pub(crate) async fn store_observations(
+  db: &crate::db::DbPool,
+  workout_id: i64,
+  observations: &[Observation],
+) -> Result<(), sqlx::Error> {
+  let mut tx = db.begin().await?;
+  sqlx::query("DELETE FROM observations WHERE workout_id = ?1")
+    .bind(workout_id)
+    .execute(&mut *tx)
+    .await?;
+  for observation in observations {
+    sqlx::query(
+      "INSERT OR IGNORE INTO observations (workout_id, kind, subject, detail) VALUES (?1, ?2, ?3, ?4)",
+    )
+    .bind(workout_id)
+    .bind(observation.kind.as_str())
+    .bind(&observation.subject)
+    .bind(&observation.detail)
+    .execute(&mut *tx)
+    .await?;
+  }
+  tx.commit().await
+}
+
+/// Helper: Store the tomorrow-prescription of a workout's analysis against
+/// the day after the workout's local date (re-analysis replaces it)
+pub(crate) async fn store_prescription(
+  db: &crate::db::DbPool,
+  workout_id: i64,
+  workout_date: chrono::NaiveDate,
+  tomorrow: &crate::llm::TomorrowCard,
+) -> Result<(), sqlx::Error> {
+  let target_date = (workout_date + chrono::Duration::days(1)).format("%Y-%m-%d").to_string();
+
+  sqlx::query(
+    r#"
+    INSERT INTO prescriptions (workout_id, target_date, activity_type, duration_min, intensity)
+    VALUES (?1, ?2, ?3, ?4, ?5)
+    ON CONFLICT(workout_id) DO UPDATE SET
+      target_date = excluded.target_date,
+      activity_type = excluded.activity_type,
+      duration_min = excluded.duration_min,
+      intensity = excluded.intensity,
+      created_at = CURRENT_TIMESTAMP
+    "#,
+  )
+  .bind(workout_id)
+  .bind(target_date)
+  .bind(&tomorrow.activity_type)
+  .bind(tomorrow.duration_min)
+  .bind(&tomorrow.intensity)
+  .execute(db)
+  .await?;
+  Ok(())
+}
+
+/// Helper: The latest prescription targeting `date`, ignoring one made by
+/// the workout itself
+pub(crate) async fn load_prescription_for(
+  db: &crate::db::DbPool,
+  date: chrono::NaiveDate,
+  workout_id: i64,
+) -> Result<Option<StoredPrescription>, sqlx::Error> {
+  let row: Option<(String, String, i32, String)> = sqlx::query_as(
+    r#"
+    SELECT target_date, activity_type, duration_min, intensity
+    FROM prescriptions
+    WHERE target_date = ?1 AND workout_id != ?2
+    ORDER BY created_at DESC, id DESC
+    LIMIT 1
+    "#,
+  )
+  .bind(date.format("%Y-%m-%d").to_string())
+  .bind(workout_id)
+  .fetch_optional(db)
+  .await?;
+
+  Ok(row.map(|(target_date, activity_type, duration_min, intensity)| StoredPrescription {
+    target_date,
+    activity_type,
+    duration_min,
+    intensity,
+  }))
+}
+
+/// Helper: Patterns from sessions in the OBSERVATION_WINDOW_DAYS before
+/// `before` (the workout being analyzed is excluded)
+pub(crate) async fn load_recurring_observations(
+  db: &crate::db::DbPool,
+  workout_id: i64,
+  before: &DateTime<Utc>,
+) -> Result<Vec<RecurringObservation>, sqlx::Error> {
+  let since = *before - chrono::Duration::days(OBSERVATION_WINDOW_DAYS);
+  let rows: Vec<(String, String, String)> = sqlx::query_as(
+    r#"
+    SELECT o.kind, o.subject, date(w.started_at)
+    FROM observations o
+    JOIN workouts w ON w.id = o.workout_id
+    WHERE o.workout_id != ?1
+      AND julianday(w.started_at) >= julianday(?2)
+      AND julianday(w.started_at) < julianday(?3)
+    ORDER BY w.started_at
+    "#,
+  )
+  .bind(workout_id)
+  .bind(since)
+  .bind(*before)
+  .fetch_all(db)
+  .await?;
+
+  let history: Vec<(ObservationKind, String, String)> = rows
+    .into_iter()
+    .filter_map(|(kind, subject, date)| Some((ObservationKind::parse(&kind)?, subject, date)))
+    .collect();
+  Ok(RecurringObservation::from_history(&history))
+}
+
+/// ---------------------------------------------------------------------------
+/// Analysis Feedback Commands
+/// ---------------------------------------------------------------------------
+
+/// Number of recent feedback entries included in the context package
+const FEEDBACK_CONTEXT_LIMIT: i64 = 5;
+
+/// Stored athlete feedback on a workout's tomorrow-prescription
+#[derive(Debug, Clone, Serialize)]
+pub struct AnalysisFeedback {
+  pub id: i64,
+  pub workout_id: i64,
+  pub rating: PrescriptionRating,
+  pub note: Option<String>,
+  pub created_at: String,
+}
+
+/// Rate the prescription from a workout's analysis (good / too_hard / too_easy).
+/// Re-submitting replaces the previous rating.
+#[tauri::command]
+pub async fn submit_analysis_feedback(
+  state: State<'_, Arc<AppState>>,
+  workout_id: i64,
+  rating: PrescriptionRating,
+  note: Option<String>,
+) -> Result<(), String> {
+  save_analysis_feedback(&state.db, workout_id, rating, note.as_deref())
+    .await
+    .map_err(|e| format!("Failed to save feedback: {}", e))
+}
+
+/// Get the feedback recorded for a workout, if any
+#[tauri::command]
+pub async fn get_analysis_feedback(
+  state: State<'_, Arc<AppState>>,
+  workout_id: i64,
+) -> Result<Option<AnalysisFeedback>, String> {
+  load_analysis_feedback(&state.db, workout_id)
+    .await
+    .map_err(|e| format!("Failed to fetch feedback: {}", e))
+}
+
+/// Helper: Upsert feedback for a workout
+async fn save_analysis_feedback(
+  db: &crate::db::DbPool,
+  workout_id: i64,
+  rating: PrescriptionRating,
+  note: Option<&str>,
+) -> Result<(), sqlx::Error> {
+  sqlx::query(
+    r#"
+    INSERT INTO analysis_feedback (workout_id, rating, note)
+    VALUES (?1, ?2, ?3)
+    ON CONFLICT(workout_id) DO UPDATE SET
+      rating = excluded.rating,
+      note = excluded.note,
+      created_at = CURRENT_TIMESTAMP
+    "#,
+  )
+  .bind(workout_id)
+  .bind(rating.as_str())
+  .bind(note)
+  .execute(db)
+  .await?;
+
+  Ok(())
+}
+
+/// Helper: Load feedback for a single workout
+async fn load_analysis_feedback(
+  db: &crate::db::DbPool,
+  workout_id: i64,
+) -> Result<Option<AnalysisFeedback>, sqlx::Error> {
+  let row: Option<(i64, i64, String, Option<String>, String)> = sqlx::query_as(
+    "SELECT id, workout_id, rating, note, created_at FROM analysis_feedback WHERE workout_id = ?1",
+  )
+  .bind(workout_id)
+  .fetch_optional(db)
+  .await?;
+
+  Ok(row.and_then(|(id, workout_id, rating, note, created_at)| {
+    Some(AnalysisFeedback {
+      id,
+      workout_id,
+      rating: rating.parse().ok()?,
+      note,
+      created_at,
+    })
+  }))
+}
+
+/// Helper: Load the most recent feedback with the prescription it rated
+async fn load_recent_feedback(
+  db: &crate::db::DbPool,
+  limit: i64,
+) -> Result<Vec<PrescriptionFeedback>, sqlx::Error> {
+  let rows: Vec<(i64, String, Option<String>, String, Option<String>)> = sqlx::query_as(
+    r#"
+    SELECT f.workout_id, w.started_at,
+           (SELECT wa.tomorrow_recommendation FROM workout_analysis wa
+            WHERE wa.workout_id = f.workout_id
+            ORDER BY wa.created_at DESC LIMIT 1),
+           f.rating, f.note
+    FROM analysis_feedback f
+    JOIN workouts w ON w.id = f.workout_id
+    ORDER BY f.created_at DESC, f.id DESC
+    LIMIT ?1
+    "#,
+  )
+  .bind(limit)
+  .fetch_all(db)
+  .await?;
+
+  Ok(
+    rows
+      .into_iter()
+      .filter_map(|(workout_id, started_at, prescription, rating, note)| {
+        Some(PrescriptionFeedback {
+          workout_id,
+          workout_date: started_at.chars().take(10).collect(),
+          prescription,
+          rating: rating.parse().ok()?,
+          note,
+        })
+      })
+      .collect(),
+  )
+}
+
+type SummaryRow = (String, String, Option<i64>, Option<f64>, Option<String>, Option<String>, bool, Option<i64>);
+
+/// Helper: Get workout summaries for flag computation. The rolling 42-day
+/// cutoff always covers the 42 local calendar days TrainingContext uses
+/// (and a few hours more, which its day windows drop), so the result
+/// doesn't depend on the hour it's loaded.
+pub(crate) async fn get_workout_summaries(
+  db: &crate::db::DbPool,
+) -> Result<Vec<WorkoutSummary>, sqlx::Error> {
+  let rows: Vec<SummaryRow> = sqlx::query_as(
+    r#"
+    SELECT started_at, activity_type, duration_seconds,
+           CAST(rtss AS REAL), hr_zone, swim_zone,
+           (average_heartrate IS NOT NULL OR device_watts = 1 OR (device_watts IS NULL AND average_watts > 0)),
+           rpe
+    FROM workouts
+    WHERE started_at >= datetime('now', '-42 days')
+      AND duplicate_of IS NULL
+    ORDER BY started_at DESC
+    "#,
+  )
+  .fetch_all(db)
+  .await?;
+
+  Ok(summaries_from_rows(rows))
+}
+
+/// Helper: Workout summaries started in [since, before), for contexts
+/// computed as of a past date
+pub(crate) async fn get_workout_summaries_between(
+  db: &crate::db::DbPool,
+  since: DateTime<Utc>,
+  before: DateTime<Utc>,
+) -> Result<Vec<WorkoutSummary>, sqlx::Error> {
+  let rows: Vec<SummaryRow> = sqlx::query_as(
+    r#"
+    SELECT started_at, activity_type, duration_seconds,
+           CAST(rtss AS REAL), hr_zone, swim_zone,
+           (average_heartrate IS NOT NULL OR device_watts = 1 OR (device_watts IS NULL AND average_watts > 0)),
+           rpe
+    FROM workouts
+    WHERE julianday(started_at) >= julianday(?1)
+      AND julianday(started_at) < julianday(?2)
+      AND duplicate_of IS NULL
+    ORDER BY started_at DESC
+    "#,
+  )
+  .bind(since)
+  .bind(before)
+  .fetch_all(db)
+  .await?;
+
+  Ok(summaries_from_rows(rows))
+}
+
+fn summaries_from_rows(rows: Vec<SummaryRow>) -> Vec<WorkoutSummary> {
+  rows
     .into_iter()
-    .filter_map(|(started_at, activity_type, duration_secs, watts, hr, pace, rtss, efficiency)| {
+    .filter_map(|(started_at, activity_type, duration_seconds, rtss, hr_zone, swim_zone, has_device_data, rpe)| {
       let dt = DateTime::parse_from_rfc3339(&started_at)
         .or_else(|_| DateTime::parse_from_str(&started_at, "%Y-%m-%dT%H:%M:%SZ"))
+        .or_else(|_| {
+          DateTime::parse_from_str(&format!("{}+00:00", started_at), "%Y-%m-%d %H:%M:%S%:z")
+        })
         .ok()?;
 
-      let duration_min = duration_secs.map(|s| s as f64 / 60.0).unwrap_or(0.0);
+      let hr_zone_enum = hr_zone.as_deref().and_then(HrZone::parse);
 
-      Some(RecentWorkoutSummary {
-        date: dt.format("%Y-%m-%d").to_string(),
+      // No measured load: fall back to the session-RPE estimate
+      let rtss = rtss.or_else(|| estimate_rtss_from_rpe(&activity_type, rpe, duration_seconds));
+
+      Some(WorkoutSummary {
+        started_at: dt.with_timezone(&Utc),
         activity_type,
-        duration_min,
-        avg_power: watts,
-        avg_hr: hr,
-        pace_min_km: pace,
+        duration_seconds,
         rtss,
-        efficiency,
+        hr_zone: hr_zone_enum,
+        swim_zone: swim_zone.as_deref().and_then(SwimZone::parse),
+        has_device_data,
+        rpe,
       })
     })
-    .collect();
-
-  Ok(workouts)
+    .collect()
 }
 
 /// ---------------------------------------------------------------------------
-/// Adherence Computation
+/// Recent Workouts for Trend Context
 /// ---------------------------------------------------------------------------
 
-/// Compute adherence summary from workout history
-///
-/// This calculates how well the athlete has been hitting their expected workouts
-/// over the current week, which affects progression decisions.
-async fn compute_adherence(
+/// How many recent same-type workouts to rank in count mode (also caps
+/// time-bounded selection)
+const SAME_TYPE_SCAN_LIMIT: i64 = 200;
+
+/// Time-bounded selection: workouts started in [since, before)
+struct RecentBounds {
+  since: String,
+  before: String,
+}
+
+/// Helper: Bounds for the `window_days` before a workout (None = count mode)
+fn recent_bounds(started_at: &DateTime<Utc>, window_days: Option<i64>) -> Option<RecentBounds> {
+  let days = window_days?;
+  let format = "%Y-%m-%dT%H:%M:%SZ";
+  Some(RecentBounds {
+    since: (*started_at - chrono::Duration::days(days)).format(format).to_string(),
+    before: started_at.format(format).to_string(),
+  })
+}
+
+/// (started_at, activity_type, duration_seconds, watts, hr, pace, rtss, efficiency, is_indoor)
+type RecentWorkoutRow = (
+  String, String, Option<i64>, Option<f64>, Option<i64>,
+  Option<f64>, Option<f64>, Option<f64>, bool,
+);
+
+/// Helper: Convert a workout row into a comparison summary, dated on the
+/// athlete's local day (None if the date is unparseable)
+fn recent_summary_from_row(row: RecentWorkoutRow, settings: &UserSettings) -> Option<RecentWorkoutSummary> {
+  let (started_at, activity_type, duration_secs, watts, hr, pace, rtss, efficiency, is_indoor) = row;
+  let dt = DateTime::parse_from_rfc3339(&started_at)
+    .or_else(|_| DateTime::parse_from_str(&started_at, "%Y-%m-%dT%H:%M:%SZ"))
+    .ok()?;
+
+  let duration_min = duration_secs.map(|s| s as f64 / 60.0).unwrap_or(0.0);
+
+  Some(RecentWorkoutSummary {
+    date: settings.local_date(&dt.with_timezone(&Utc)).format("%Y-%m-%d").to_string(),
+    activity_type,
+    duration_min,
+    avg_power: watts,
+    avg_hr: hr,
+    pace_min_km: pace,
+    pace_min_mi: None,
+    rtss,
+    efficiency,
+    is_indoor,
+  })
+}
+
+/// Helper: Does `other` count as the same type as `activity_type`? Aliases
+/// match by canonical kind; unrecognized types match by name.
+fn is_same_activity_type(activity_type: &str, other: &str) -> bool {
+  match canonical_activity(activity_type) {
+    ActivityKind::Other => other.eq_ignore_ascii_case(activity_type),
+    kind => canonical_activity(other) == kind,
+  }
+}
+
+/// Helper: Recent workout rows, newest first, excluding the current workout.
+/// With bounds, only rows inside the window are returned; with aliases, only
+/// rows whose normalized type is one of them (see activity_type_aliases).
+async fn fetch_recent_rows(
   db: &crate::db::DbPool,
-  settings: &UserSettings,
-) -> Result<AdherenceSummary, String> {
-  // Get workouts from current week (last 7 days)
-  let rows: Vec<(String, Option<i64>)> = sqlx::query_as(
+  exclude_workout_id: i64,
+  bounds: Option<&RecentBounds>,
+  type_aliases: Option<&[String]>,
+  limit: i64,
+) -> Result<Vec<RecentWorkoutRow>, sqlx::Error> {
+  sqlx::query_as(
     r#"
-    SELECT activity_type, duration_seconds
+    SELECT
+      started_at,
+      activity_type,
+      duration_seconds,
+      CASE WHEN device_watts = 1 OR (device_watts IS NULL AND average_watts > 0) THEN CAST(average_watts AS REAL) END,
+      average_heartrate,
+      CAST(pace_min_per_km AS REAL),
+      CAST(rtss AS REAL),
+      CAST(efficiency AS REAL),
+      is_indoor
     FROM workouts
-    WHERE started_at >= datetime('now', '-7 days')
+    WHERE id != ?1
+      AND duplicate_of IS NULL
+      AND (?2 IS NULL OR (started_at >= ?2 AND started_at < ?3))
+      AND (?5 IS NULL OR LOWER(REPLACE(REPLACE(REPLACE(activity_type, ' ', ''), '_', ''), '-', ''))
+        IN (SELECT value FROM json_each(?5)))
     ORDER BY started_at DESC
+    LIMIT ?4
     "#,
   )
+  .bind(exclude_workout_id)
+  .bind(bounds.map(|b| b.since.as_str()))
+  .bind(bounds.map(|b| b.before.as_str()))
+  .bind(limit)
+  .bind(type_aliases.map(|aliases| serde_json::to_string(aliases).unwrap_or_default()))
   .fetch_all(db)
   .await
-  .map_err(|e| format!("Failed to fetch workouts for adherence: {}", e))?;
+}
 
-  let total_completed = rows.len() as u8;
-  let total_expected = settings.training_days_per_week as u8;
+/// Get recent workouts of the same type for trend comparison
+/// Excludes the current workout being analyzed. Aliases ("Run", "TrailRun")
+/// match by canonical kind; unrecognized types match by name. Without
+/// bounds, the `limit` most comparable to `target` are returned; with
+/// bounds, every match in the window is returned and `limit` is ignored.
+async fn get_recent_same_type_workouts(
+  db: &crate::db::DbPool,
+  settings: &UserSettings,
+  activity_type: &str,
+  exclude_workout_id: i64,
+  limit: i64,
+  bounds: Option<&RecentBounds>,
+  target: &ComparisonTarget,
+) -> Result<Vec<RecentWorkoutSummary>, String> {
+  // SQL narrows to the type's aliases before the limit, so a rare type isn't
+  // crowded out by hundreds of runs; the exact match is decided here
+  let aliases = activity_type_aliases(activity_type);
+  let rows = fetch_recent_rows(db, exclude_workout_id, bounds, Some(&aliases), SAME_TYPE_SCAN_LIMIT)
+    .await
+    .map_err(|e| format!("Failed to fetch recent same-type workouts: {}", e))?;
 
-  // Key sessions: count long runs (>45 min) as key sessions
-  // For now, we expect 1 long run per week as a key session
-  let key_expected = 1u8;
-  let key_completed = rows
-    .iter()
-    .filter(|(activity_type, duration)| {
-      activity_type.to_lowercase() == "run"
-        && duration.map_or(false, |d| d > 45 * 60) // > 45 min
-    })
-    .count() as u8;
+  let workouts: Vec<RecentWorkoutSummary> = rows
+    .into_iter()
+    .filter(|row| is_same_activity_type(activity_type, &row.1))
+    .filter_map(|row| recent_summary_from_row(row, settings))
+    .collect();
 
-  // Check for consecutive low adherence weeks (simplified - just current week for now)
-  // TODO: Track this properly in the database
-  let consecutive_low_weeks = 0u8;
+  // Count mode keeps the most comparable sessions (duration, intensity,
+  // recency) so a long run isn't measured against yesterday's shakeout
+  if bounds.is_some() {
+    Ok(workouts)
+  } else {
+    Ok(target.select(workouts, limit.max(0) as usize))
+  }
+}
 
-  Ok(AdherenceSummary::compute(
-    total_expected,
-    total_completed,
-    key_expected,
-    key_completed,
-    consecutive_low_weeks,
-  ))
+/// Get recent workouts of any type for weekly context
+/// Excludes the current workout being analyzed. With bounds, every workout
+/// in the window is returned and `limit` is ignored.
+async fn get_recent_all_workouts(
+  db: &crate::db::DbPool,
+  settings: &UserSettings,
+  exclude_workout_id: i64,
+  limit: i64,
+  bounds: Option<&RecentBounds>,
+) -> Result<Vec<RecentWorkoutSummary>, String> {
+  let limit = if bounds.is_some() { SAME_TYPE_SCAN_LIMIT } else { limit.max(0) };
+  let rows = fetch_recent_rows(db, exclude_workout_id, bounds, None, limit)
+    .await
+    .map_err(|e| format!("Failed to fetch recent all workouts: {}", e))?;
+
+  let workouts = rows
+    .into_iter()
+    .filter_map(|row| recent_summary_from_row(row, settings))
+    .collect();
+
+  Ok(workouts)
+}
+
+/// ---------------------------------------------------------------------------
+/// Seasonal Comparison
+/// ---------------------------------------------------------------------------
+
+/// Compare a workout with same-type workouts from the same calendar window
+/// (±2 weeks) in prior years. Returns an empty `prior_years` when there's no
+/// earlier-season data.
+#[tauri::command]
+pub async fn get_seasonal_comparison(
+  state: State<'_, Arc<AppState>>,
+  workout_id: i64,
+) -> Result<SeasonalComparison, String> {
+  seasonal_comparison_for(&state.db, workout_id).await
+}
+
+/// Helper: Build the seasonal comparison for a workout
+async fn seasonal_comparison_for(
+  db: &crate::db::DbPool,
+  workout_id: i64,
+) -> Result<SeasonalComparison, String> {
+  let settings = load_user_settings(db).await?;
+  let row: Option<RecentWorkoutRow> = sqlx::query_as(
+    r#"
+    SELECT
+      started_at,
+      activity_type,
+      duration_seconds,
+      CASE WHEN device_watts = 1 OR (device_watts IS NULL AND average_watts > 0) THEN CAST(average_watts AS REAL) END,
+      average_heartrate,
+      CAST(pace_min_per_km AS REAL),
+      CAST(rtss AS REAL),
+      CAST(efficiency AS REAL),
+      is_indoor
+    FROM workouts
+    WHERE id = ?1
+    "#,
+  )
+  .bind(workout_id)
+  .fetch_optional(db)
+  .await
+  .map_err(|e| format!("Failed to fetch workout: {}", e))?;
+
+  let current = row
+    .and_then(|row| recent_summary_from_row(row, &settings))
+    .ok_or_else(|| format!("Workout not found: {}", workout_id))?;
+
+  // Everything up to the end of last year's window; the exact per-year
+  // window match happens in compute_seasonal_comparison
+  let cutoff = chrono::NaiveDate::parse_from_str(&current.date, "%Y-%m-%d")
+    .map_err(|e| format!("Invalid workout date: {}", e))?
+    - chrono::Duration::days(365 - SEASONAL_WINDOW_DAYS - 1);
+
+  let rows: Vec<RecentWorkoutRow> = sqlx::query_as(
+    r#"
+    SELECT
+      started_at,
+      activity_type,
+      duration_seconds,
+      CASE WHEN device_watts = 1 OR (device_watts IS NULL AND average_watts > 0) THEN CAST(average_watts AS REAL) END,
+      average_heartrate,
+      CAST(pace_min_per_km AS REAL),
+      CAST(rtss AS REAL),
+      CAST(efficiency AS REAL),
+      is_indoor
+    FROM workouts
+    WHERE id != ?1 AND started_at < ?2 AND duplicate_of IS NULL
+    ORDER BY started_at DESC
+    "#,
+  )
+  .bind(workout_id)
+  .bind(cutoff.format("%Y-%m-%d").to_string())
+  .fetch_all(db)
+  .await
+  .map_err(|e| format!("Failed to fetch prior-year workouts: {}", e))?;
+
+  let candidates: Vec<RecentWorkoutSummary> = rows
+    .into_iter()
+    .filter(|row| is_same_activity_type(&current.activity_type, &row.1))
+    .filter_map(|row| recent_summary_from_row(row, &settings))
+    .collect();
+
+  Ok(compute_seasonal_comparison(&current, &candidates, SEASONAL_WINDOW_DAYS))
+}
+
+/// ---------------------------------------------------------------------------
+/// Fitness Trend Cache
+/// ---------------------------------------------------------------------------
+
+/// The cached 6-week trend and whether it's still current
+#[derive(Debug, Serialize)]
+pub struct CachedFitnessTrend {
+  pub trend: FitnessTrend,
+  pub computed_at: String,
+  /// A workout changed, or the local day moved past the window's end, since
+  /// this was computed; call refresh_fitness_trend
+  pub stale: bool,
+}
+
+/// Read the cached fitness trend (computed on first use). Returns stale data
+/// flagged as such rather than blocking on a recompute.
+#[tauri::command]
+pub async fn get_fitness_trend(
+  state: State<'_, Arc<AppState>>,
+) -> Result<CachedFitnessTrend, String> {
+  let settings = load_user_settings(&state.db).await?;
+  let today = settings.local_date(&Utc::now());
+  load_fitness_trend(&state.db, &settings, today).await
+}
+
+/// Recompute and cache the fitness trend
+#[tauri::command]
+pub async fn refresh_fitness_trend(
+  state: State<'_, Arc<AppState>>,
+) -> Result<CachedFitnessTrend, String> {
+  let settings = load_user_settings(&state.db).await?;
+  let today = settings.local_date(&Utc::now());
+  refresh_fitness_trend_cache(&state.db, &settings, today).await
+}
+
+/// Helper: Read the cache, filling it if empty. A window ending before
+/// `today` (the athlete's local date) is stale: CTL has decayed since.
+async fn load_fitness_trend(
+  db: &crate::db::DbPool,
+  settings: &UserSettings,
+  today: chrono::NaiveDate,
+) -> Result<CachedFitnessTrend, String> {
+  match read_fitness_trend_cache(db).await? {
+    Some(mut cached) => {
+      cached.stale = cached.stale || cached.trend.window_end != today;
+      Ok(cached)
+    }
+    None => refresh_fitness_trend_cache(db, settings, today).await,
+  }
+}
+
+/// Helper: The cached row, if any
+async fn read_fitness_trend_cache(db: &crate::db::DbPool) -> Result<Option<CachedFitnessTrend>, String> {
+  let row: Option<(String, String, bool)> =
+    sqlx::query_as("SELECT trend_json, computed_at, stale FROM fitness_trend_cache WHERE id = 1")
+      .fetch_optional(db)
+      .await
+      .map_err(|e| format!("Failed to read fitness trend: {}", e))?;
+
+  row
+    .map(|(trend_json, computed_at, stale)| {
+      Ok(CachedFitnessTrend {
+        trend: serde_json::from_str(&trend_json)
+          .map_err(|e| format!("Failed to parse cached fitness trend: {}", e))?,
+        computed_at,
+        stale,
+      })
+    })
+    .transpose()
+}
+
+/// Helper: Aggregate full workout history into the trend and store it
+async fn refresh_fitness_trend_cache(
+  db: &crate::db::DbPool,
+  settings: &UserSettings,
+  today: chrono::NaiveDate,
+) -> Result<CachedFitnessTrend, String> {
+  let rows: Vec<RecentWorkoutRow> = sqlx::query_as(
+    r#"
+    SELECT
+      started_at,
+      activity_type,
+      duration_seconds,
+      CASE WHEN device_watts = 1 OR (device_watts IS NULL AND average_watts > 0) THEN CAST(average_watts AS REAL) END,
+      average_heartrate,
+      CAST(pace_min_per_km AS REAL),
+      CAST(rtss AS REAL),
+      CAST(efficiency AS REAL),
+      is_indoor
+    FROM workouts
+    WHERE duplicate_of IS NULL
+    ORDER BY started_at
+    "#,
+  )
+  .fetch_all(db)
+  .await
+  .map_err(|e| format!("Failed to fetch workouts for fitness trend: {}", e))?;
+
+  let workouts: Vec<RecentWorkoutSummary> = rows.into_iter().filter_map(|row| recent_summary_from_row(row, settings)).collect();
+  let trend = compute_fitness_trend(&workouts, settings, today);
+  let trend_json =
+    serde_json::to_string(&trend).map_err(|e| format!("Failed to serialize fitness trend: {}", e))?;
+
+  sqlx::query(
+    r#"
+    INSERT INTO fitness_trend_cache (id, trend_json, computed_at, stale)
+    VALUES (1, ?1, CURRENT_TIMESTAMP, 0)
+    ON CONFLICT(id) DO UPDATE SET
+      trend_json = excluded.trend_json,
+      computed_at = excluded.computed_at,
+      stale = 0
+    "#,
+  )
+  .bind(&trend_json)
+  .execute(db)
+  .await
+  .map_err(|e| format!("Failed to store fitness trend: {}", e))?;
+
+  read_fitness_trend_cache(db)
+    .await?
+    .ok_or_else(|| "Fitness trend cache missing after refresh".to_string())
+}
+
+/// ---------------------------------------------------------------------------
+/// Activity Calendar
+/// ---------------------------------------------------------------------------
+
+/// Heatmap window when the caller doesn't pass one
+const DEFAULT_CALENDAR_DAYS: i64 = 90;
+
+/// Per-day workout counts and rTSS for the last `days` days (default 90),
+/// with current and longest training streaks
+#[tauri::command]
+pub async fn get_activity_calendar(
+  state: State<'_, Arc<AppState>>,
+  days: Option<i64>,
+) -> Result<ActivityCalendar, String> {
+  let settings = load_user_settings(&state.db).await?;
+  let today = settings.local_date(&Utc::now());
+  load_activity_calendar(&state.db, &settings, today, days.unwrap_or(DEFAULT_CALENDAR_DAYS)).await
+}
+
+/// Helper: Build the calendar from the workouts on the athlete's local
+/// dates within the window ending on `today`
+async fn load_activity_calendar(
+  db: &crate::db::DbPool,
+  settings: &UserSettings,
+  today: chrono::NaiveDate,
+  days: i64,
+) -> Result<ActivityCalendar, String> {
+  if !(1..=MAX_CALENDAR_DAYS).contains(&days) {
+    return Err(format!("Invalid days '{}': expected 1 to {}", days, MAX_CALENDAR_DAYS));
+  }
+
+  // Local days start and end at different UTC times than UTC days
+  let window_start = settings.local_day_start_utc(today - chrono::Duration::days(days - 1));
+  let window_end = settings.local_day_start_utc(today + chrono::Duration::days(1));
+  let rows: Vec<SummaryRow> = sqlx::query_as(
+    r#"
+    SELECT started_at, activity_type, duration_seconds,
+           CAST(rtss AS REAL), hr_zone, swim_zone,
+           (average_heartrate IS NOT NULL OR device_watts = 1 OR (device_watts IS NULL AND average_watts > 0)),
+           rpe
+    FROM workouts
+    WHERE julianday(started_at) >= julianday(?1)
+      AND julianday(started_at) < julianday(?2)
+      AND duplicate_of IS NULL
+    ORDER BY started_at DESC
+    "#,
+  )
+  .bind(window_start)
+  .bind(window_end)
+  .fetch_all(db)
+  .await
+  .map_err(|e| format!("Failed to get workout summaries: {}", e))?;
+  let workouts = summaries_from_rows(rows);
+
+  Ok(build_activity_calendar(&workouts, settings, today, days))
+}
+
+/// ---------------------------------------------------------------------------
+/// Efficiency Factor Trend
+/// ---------------------------------------------------------------------------
+
+/// EF over the last `days` days (default 90) for outdoor Z2 sessions of one
+/// modality ("run" or "ride", any spelling)
+#[tauri::command]
+pub async fn get_ef_trend(
+  state: State<'_, Arc<AppState>>,
+  activity_type: String,
+  days: Option<i64>,
+) -> Result<EfTrend, String> {
+  load_ef_trend(&state.db, &activity_type, days.unwrap_or(EF_TREND_DEFAULT_DAYS), Utc::now()).await
+}
+
+/// Helper: Z2 sessions only, so the trend compares like with like; indoor
+/// sessions are left out since trainer power and treadmill speed aren't
+/// comparable to outdoor ones
+async fn load_ef_trend(
+  db: &crate::db::DbPool,
+  activity_type: &str,
+  days: i64,
+  now: DateTime<Utc>,
+) -> Result<EfTrend, String> {
+  let kind = canonical_activity(activity_type);
+  if !matches!(kind, ActivityKind::Run | ActivityKind::Ride) {
+    return Err(format!("Invalid activity_type '{}': expected a run or ride", activity_type));
+  }
+  if !(1..=MAX_CALENDAR_DAYS).contains(&days) {
+    return Err(format!("Invalid days '{}': expected 1 to {}", days, MAX_CALENDAR_DAYS));
+  }
+
+  let settings = load_user_settings(db).await?;
+  let rows: Vec<(String, String, f64, Option<i64>)> = sqlx::query_as(
+    r#"
+    SELECT started_at, activity_type, CAST(efficiency_factor AS REAL), average_heartrate
+    FROM workouts
+    WHERE efficiency_factor IS NOT NULL
+      AND hr_zone = 'Z2'
+      AND is_indoor = 0
+      AND duplicate_of IS NULL
+      AND julianday(started_at) >= julianday(?1)
+    "#,
+  )
+  .bind(now - chrono::Duration::days(days))
+  .fetch_all(db)
+  .await
+  .map_err(|e| format!("Failed to fetch efficiency factors: {}", e))?;
+
+  let points = rows
+    .into_iter()
+    .filter(|(_, activity_type, _, _)| canonical_activity(activity_type) == kind)
+    .filter_map(|(started_at, _, efficiency_factor, average_hr)| {
+      let started_at = DateTime::parse_from_rfc3339(&started_at).ok()?.with_timezone(&Utc);
+      Some(EfPoint {
+        date: settings.local_date(&started_at),
+        efficiency_factor,
+        average_hr,
+      })
+    })
+    .collect();
+
+  Ok(compute_ef_trend(kind, days, points))
+}
+
+/// ---------------------------------------------------------------------------
+/// Normalized Weekly Load
+/// ---------------------------------------------------------------------------
+
+/// Each of the last `weeks` weeks (default 12) as a percentage of a
+/// reference week: "max" (the biggest, default) or "average"
+#[tauri::command]
+pub async fn get_normalized_weekly_load(
+  state: State<'_, Arc<AppState>>,
+  weeks: Option<i64>,
+  reference: Option<String>,
+) -> Result<NormalizedWeeklyLoad, String> {
+  load_normalized_weekly_load(
+    &state.db,
+    weeks.unwrap_or(DEFAULT_NORMALIZED_LOAD_WEEKS),
+    reference.as_deref().unwrap_or("max"),
+    Utc::now(),
+  )
+  .await
+}
+
+/// Helper: Validate inputs and aggregate the stored workouts
+async fn load_normalized_weekly_load(
+  db: &crate::db::DbPool,
+  weeks: i64,
+  reference: &str,
+  now: DateTime<Utc>,
+) -> Result<NormalizedWeeklyLoad, String> {
+  if !NORMALIZED_LOAD_WEEKS_RANGE.contains(&weeks) {
+    return Err(format!("Invalid weeks '{}': expected 2 to 52", weeks));
+  }
+  let reference = LoadReference::parse(reference)
+    .ok_or_else(|| format!("Invalid reference '{}': expected max or average", reference))?;
+
+  let settings = load_user_settings(db).await?;
+  // A day of slack either side covers any local offset
+  let since = now - chrono::Duration::days(weeks * 7 + 1);
+  let workouts = get_workout_summaries_between(db, since, now + chrono::Duration::days(1))
+    .await
+    .map_err(|e| format!("Failed to get workout summaries: {}", e))?;
+
+  Ok(compute_normalized_weekly_load(&workouts, &settings, settings.local_date(&now), weeks, reference))
+}
+
+/// ---------------------------------------------------------------------------
+/// Threshold Test Suggestions
+/// ---------------------------------------------------------------------------
+
+/// Default look-back for threshold test detection
+const THRESHOLD_TEST_LOOKBACK_DAYS: i64 = 30;
+
+/// A detected test whose implied value differs from the current setting.
+/// Suggestions only - nothing is written to settings.
+#[derive(Debug, Serialize)]
+pub struct ThresholdTestSuggestion {
+  pub workout_id: i64,
+  pub date: String,
+  pub activity_type: String,
+  pub test: TestResult,
+  /// Current FTP or LTHR setting (None if unset)
+  pub current_value: Option<i64>,
+}
+
+/// Find recent FTP / LTHR tests and suggest settings updates (not applied)
+#[tauri::command]
+pub async fn get_threshold_test_suggestions(
+  state: State<'_, Arc<AppState>>,
+  days: Option<i64>,
+) -> Result<Vec<ThresholdTestSuggestion>, String> {
+  let settings = load_user_settings(&state.db).await?;
+  let since = Utc::now() - chrono::Duration::days(days.unwrap_or(THRESHOLD_TEST_LOOKBACK_DAYS));
+  threshold_test_suggestions(&state.db, &settings, since).await
+}
+
+/// Helper: Scan workouts with samples since `since` for threshold tests
+async fn threshold_test_suggestions(
+  db: &crate::db::DbPool,
+  settings: &UserSettings,
+  since: DateTime<Utc>,
+) -> Result<Vec<ThresholdTestSuggestion>, String> {
+  let rows: Vec<(i64, String, String, Option<String>, Option<Vec<u8>>, bool)> = sqlx::query_as(
+    r#"
+    SELECT id, started_at, activity_type, samples_json, samples_blob, samples_compressed
+    FROM workouts
+    WHERE (samples_json IS NOT NULL OR samples_blob IS NOT NULL) AND started_at >= ?1
+    ORDER BY started_at DESC
+    "#,
+  )
+  .bind(since.format("%Y-%m-%dT%H:%M:%SZ").to_string())
+  .fetch_all(db)
+  .await
+  .map_err(|e| format!("Failed to fetch workouts with samples: {}", e))?;
+
+  let suggestions = rows
+    .into_iter()
+    .filter_map(|(workout_id, started_at, activity_type, samples_json, samples_blob, compressed)| {
+      let samples = WorkoutSamples::from_stored(samples_json.as_deref(), samples_blob.as_deref(), compressed)?;
+      let test = detect_threshold_test(&samples, &activity_type)?;
+      let current_value = match test.kind {
+        ThresholdTestKind::Ftp => settings.ftp_for(&activity_type),
+        ThresholdTestKind::Lthr => settings.lthr_for(&activity_type),
+      };
+      if current_value == Some(test.suggested_value) {
+        return None;
+      }
+      Some(ThresholdTestSuggestion {
+        workout_id,
+        date: started_at.chars().take(10).collect(),
+        activity_type,
+        test,
+        current_value,
+      })
+    })
+    .collect();
+
+  Ok(suggestions)
+}
+
+/// ---------------------------------------------------------------------------
+/// Adherence Computation
+/// ---------------------------------------------------------------------------
+
+/// Compute adherence summary from workout history
+///
+/// This calculates how well the athlete has been hitting their expected workouts
+/// over the current week, which affects progression decisions.
+pub(crate) async fn compute_adherence(
+  db: &crate::db::DbPool,
+  settings: &UserSettings,
+) -> Result<AdherenceSummary, String> {
+  // Prefer the active plan when one covers this week: planned intent beats
+  // counting workouts against training_days_per_week
+  let today = settings.local_date(&Utc::now());
+  if let Some(plan) = load_active_training_plan(db, today).await? {
+    let week = plan_adherence_for(db, &plan, Some(today - chrono::Duration::days(6)), today).await?;
+    if week.planned_sessions > 0 {
+      return Ok(AdherenceSummary::compute(
+        week.planned_sessions as u8,
+        week.completed as u8,
+        week.key_planned as u8,
+        week.key_completed as u8,
+        0,
+      ));
+    }
+  }
+
+  // Get workouts from current week (last 7 days)
+  let rows: Vec<(String, Option<i64>)> = sqlx::query_as(
+    r#"
+    SELECT activity_type, duration_seconds
+    FROM workouts
+    WHERE started_at >= datetime('now', '-7 days') AND duplicate_of IS NULL
+    ORDER BY started_at DESC
+    "#,
+  )
+  .fetch_all(db)
+  .await
+  .map_err(|e| format!("Failed to fetch workouts for adherence: {}", e))?;
+
+  let total_completed = rows.len() as u8;
+  let total_expected = settings.training_days_per_week as u8;
+
+  // Key sessions: explicit links (mark_key_session) win once the athlete
+  // uses them; otherwise count long runs (>45 min) as key sessions
+  // For now, we expect 1 key session per week
+  let key_expected = 1u8;
+  let key_completed = match count_recent_key_sessions(db).await? {
+    Some(linked) => linked as u8,
+    None => rows
+      .iter()
+      .filter(|(activity_type, duration)| {
+        canonical_activity(activity_type) == ActivityKind::Run
+          && duration.map_or(false, |d| d > 45 * 60) // > 45 min
+      })
+      .count() as u8,
+  };
+
+  // Check for consecutive low adherence weeks (simplified - just current week for now)
+  // TODO: Track this properly in the database
+  let consecutive_low_weeks = 0u8;
+
+  Ok(AdherenceSummary::compute(
+    total_expected,
+    total_completed,
+    key_expected,
+    key_completed,
+    consecutive_low_weeks,
+  ))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  async fn insert_workout(db: &crate::db::DbPool, strava_id: &str, started_at: &str) -> i64 {
+    sqlx::query(
+      "INSERT INTO workouts (strava_id, activity_type, started_at, duration_seconds) VALUES (?1, 'Run', ?2, 2700)",
+    )
+    .bind(strava_id)
+    .bind(started_at)
+    .execute(db)
+    .await
+    .unwrap()
+    .last_insert_rowid()
+  }
+
+  #[tokio::test]
+  async fn test_feedback_stored_and_surfaces_in_context() {
+    let db = crate::db::test_pool().await;
+    let workout_id = insert_workout(&db, "1001", "2024-12-10T07:00:00Z").await;
+
+    sqlx::query(
+      "INSERT INTO workout_analysis (workout_id, summary, tomorrow_recommendation) VALUES (?1, 'Solid Z2', 'run for 60 min at Z2 intensity.')",
+    )
+    .bind(workout_id)
+    .execute(&db)
+    .await
+    .unwrap();
+
+    save_analysis_feedback(&db, workout_id, PrescriptionRating::TooHard, Some("legs were dead"))
+      .await
+      .unwrap();
+
+    let stored = load_analysis_feedback(&db, workout_id).await.unwrap().unwrap();
+    assert_eq!(stored.rating, PrescriptionRating::TooHard);
+    assert_eq!(stored.note.as_deref(), Some("legs were dead"));
+
+    // Re-rating replaces the earlier entry
+    save_analysis_feedback(&db, workout_id, PrescriptionRating::TooEasy, None)
+      .await
+      .unwrap();
+    let stored = load_analysis_feedback(&db, workout_id).await.unwrap().unwrap();
+    assert_eq!(stored.rating, PrescriptionRating::TooEasy);
+
+    let feedback = load_recent_feedback(&db, FEEDBACK_CONTEXT_LIMIT).await.unwrap();
+    assert_eq!(feedback.len(), 1);
+    assert_eq!(feedback[0].workout_date, "2024-12-10");
+    assert_eq!(
+      feedback[0].prescription.as_deref(),
+      Some("run for 60 min at Z2 intensity.")
+    );
+
+    let settings = UserSettings::default();
+    let started_at = Utc::now();
+    let metrics = WorkoutMetrics::compute("Run", Some(2700), Some(8000.0), Some(140), None, &[], &settings);
+    let context = TrainingContext::compute(&[], &settings);
+    let package = ContextPackage::build(
+      "Run",
+      &started_at,
+      Some(2700),
+      Some(8000.0),
+      Some(140),
+      None,
+      &metrics,
+      context,
+      TrainingFlags::default(),
+      1.0,
+      &settings,
+      vec![],
+      vec![],
+    )
+    .with_feedback(feedback);
+
+    let json = package.to_json();
+    assert!(json.contains("\"recent_feedback\""));
+    assert!(json.contains("\"too_easy\""));
+    assert!(json.contains("run for 60 min at Z2 intensity."));
+  }
+
+  #[tokio::test]
+  async fn test_recent_feedback_empty_without_ratings() {
+    let db = crate::db::test_pool().await;
+    let feedback = load_recent_feedback(&db, FEEDBACK_CONTEXT_LIMIT).await.unwrap();
+    assert!(feedback.is_empty());
+  }
+
+  async fn insert_run_with_metrics(db: &crate::db::DbPool, strava_id: &str, started_at: &str, pace: f64, efficiency: f64) -> i64 {
+    sqlx::query(
+      "INSERT INTO workouts (strava_id, activity_type, started_at, duration_seconds, pace_min_per_km, efficiency) VALUES (?1, 'Run', ?2, 3600, ?3, ?4)",
+    )
+    .bind(strava_id)
+    .bind(started_at)
+    .bind(pace)
+    .bind(efficiency)
+    .execute(db)
+    .await
+    .unwrap()
+    .last_insert_rowid()
+  }
+
+  #[tokio::test]
+  async fn test_glitched_run_stored_without_pace_and_flagged() {
+    let db = crate::db::test_pool().await;
+    sqlx::query("UPDATE user_settings SET max_hr = 190").execute(&db).await.unwrap();
+    let id = sqlx::query(
+      "INSERT INTO workouts (strava_id, activity_type, started_at, duration_seconds, distance_meters, average_heartrate)
+       VALUES ('g1', 'Run', '2025-03-01T07:00:00Z', 900, 10000, 150)",
+    )
+    .execute(&db)
+    .await
+    .unwrap()
+    .last_insert_rowid();
+
+    compute_pending_metrics(&db).await.unwrap();
+
+    let stored = load_stored_metrics(&db, id).await.unwrap().unwrap();
+    assert!(stored.data_suspect);
+    assert!(stored.pace_min_per_km.is_none());
+    let pace: Option<f64> = sqlx::query_scalar("SELECT pace_min_per_km FROM workouts WHERE id = ?1")
+      .bind(id)
+      .fetch_one(&db)
+      .await
+      .unwrap();
+    assert_eq!(pace, None);
+  }
+
+  #[tokio::test]
+  async fn test_imperial_units_report_min_per_mile() {
+    let db = crate::db::test_pool().await;
+    // 10 km in 50 minutes, stored metric
+    sqlx::query(
+      "INSERT INTO workouts (strava_id, activity_type, started_at, duration_seconds, distance_meters, pace_min_per_km)
+       VALUES ('u1', 'Run', '2025-03-01T07:00:00Z', 3000, 10000, 5.0)",
+    )
+    .execute(&db)
+    .await
+    .unwrap();
+
+    let metric = &load_workouts_with_metrics(&db, 10).await.unwrap()[0];
+    assert_eq!((metric.display.pace, metric.display.pace_unit.as_str()), (Some(5.0), "min/km"));
+    assert_eq!(metric.display.distance, Some(10.0));
+
+    sqlx::query("UPDATE user_settings SET units = 'imperial'").execute(&db).await.unwrap();
+    let imperial = &load_workouts_with_metrics(&db, 10).await.unwrap()[0];
+    // 5:00/km is 8:03/mi; 10 km is 6.21 mi
+    assert!((imperial.display.pace.unwrap() - 8.0467).abs() < 0.001);
+    assert_eq!(imperial.display.pace_unit, "min/mi");
+    assert!((imperial.display.distance.unwrap() - 6.2137).abs() < 0.001);
+    assert_eq!(imperial.display.distance_unit, "mi");
+    // Storage and the raw fields stay metric
+    assert_eq!(imperial.pace_min_per_km, Some(5.0));
+    assert_eq!(imperial.distance_meters, Some(10000.0));
+  }
+
+  #[tokio::test]
+  async fn test_seasonal_comparison_across_two_years() {
+    let db = crate::db::test_pool().await;
+
+    // Two prior seasons in the mid-March window, plus noise outside it
+    insert_run_with_metrics(&db, "2001", "2023-03-12T07:00:00Z", 5.5, 0.040).await;
+    insert_run_with_metrics(&db, "2002", "2024-03-08T07:00:00Z", 5.3, 0.037).await;
+    insert_run_with_metrics(&db, "2003", "2024-03-22T07:00:00Z", 5.2, 0.036).await;
+    insert_run_with_metrics(&db, "2004", "2024-07-01T07:00:00Z", 4.8, 0.033).await;
+    insert_run_with_metrics(&db, "2005", "2025-03-01T07:00:00Z", 5.1, 0.035).await;
+    sqlx::query(
+      "INSERT INTO workouts (strava_id, activity_type, started_at, duration_seconds, average_watts) VALUES ('2006', 'Ride', '2024-03-15T07:00:00Z', 3600, 180)",
+    )
+    .execute(&db)
+    .await
+    .unwrap();
+
+    let workout_id = insert_run_with_metrics(&db, "2010", "2025-03-15T07:00:00Z", 5.0, 0.034).await;
+
+    let comparison = seasonal_comparison_for(&db, workout_id).await.unwrap();
+    let years: Vec<i32> = comparison.prior_years.iter().map(|y| y.year).collect();
+    assert_eq!(years, vec![2024, 2023]);
+
+    let last_year = &comparison.prior_years[0];
+    assert_eq!(last_year.workouts, 2);
+    assert!((last_year.avg_pace_min_km.unwrap() - 5.25).abs() < 1e-9);
+    assert!((last_year.pace_delta_sec_km.unwrap() - -15.0).abs() < 1e-6);
+
+    let two_years = &comparison.prior_years[1];
+    assert_eq!(two_years.workouts, 1);
+    assert!((two_years.pace_delta_sec_km.unwrap() - -30.0).abs() < 1e-6);
+  }
+
+  #[tokio::test]
+  async fn test_seasonal_comparison_without_prior_years() {
+    let db = crate::db::test_pool().await;
+    insert_run_with_metrics(&db, "3001", "2025-03-01T07:00:00Z", 5.1, 0.035).await;
+    let workout_id = insert_run_with_metrics(&db, "3002", "2025-03-15T07:00:00Z", 5.0, 0.034).await;
+
+    let comparison = seasonal_comparison_for(&db, workout_id).await.unwrap();
+    assert!(comparison.prior_years.is_empty());
+
+    assert!(seasonal_comparison_for(&db, 9999).await.is_err());
+  }
+
+  #[tokio::test]
+  async fn test_threshold_test_suggestion_is_not_applied() {
+    let db = crate::db::test_pool().await;
+
+    let mut watts = vec![150; 90];
+    watts.extend(vec![300; 120]);
+    watts.extend(vec![120; 60]);
+    let samples = WorkoutSamples { hr: vec![], watts, ..Default::default() };
+    let started_at = (Utc::now() - chrono::Duration::days(2)).format("%Y-%m-%dT%H:%M:%SZ").to_string();
+    let workout_id = sqlx::query(
+      "INSERT INTO workouts (strava_id, activity_type, started_at, duration_seconds, samples_json) VALUES ('4001', 'Ride', ?1, 2700, ?2)",
+    )
+    .bind(&started_at)
+    .bind(samples.to_json())
+    .execute(&db)
+    .await
+    .unwrap()
+    .last_insert_rowid();
+
+    let settings = UserSettings { ftp: Some(250), ..Default::default() };
+    let since = Utc::now() - chrono::Duration::days(THRESHOLD_TEST_LOOKBACK_DAYS);
+    let suggestions = threshold_test_suggestions(&db, &settings, since).await.unwrap();
+    assert_eq!(suggestions.len(), 1);
+    assert_eq!(suggestions[0].workout_id, workout_id);
+    assert_eq!(suggestions[0].test.suggested_value, 285);
+    assert_eq!(suggestions[0].current_value, Some(250));
+
+    // Settings untouched
+    assert_eq!(load_user_settings(&db).await.unwrap().ftp, None);
+
+    // Already matching FTP -> nothing to suggest
+    let settings = UserSettings { ftp: Some(285), ..Default::default() };
+    assert!(threshold_test_suggestions(&db, &settings, since).await.unwrap().is_empty());
+  }
+
+  #[tokio::test]
+  async fn test_sport_lthr_pct_can_be_cleared() {
+    let db = crate::db::test_pool().await;
+    let update = UserSettingsUpdate {
+      run_lthr_pct_of_max: Some(0.9),
+      ride_lthr_pct_of_max: Some(0.85),
+      ..Default::default()
+    };
+    save_user_settings(&db, update).await.unwrap();
+    let settings = load_user_settings(&db).await.unwrap();
+    assert_eq!(settings.run_lthr_pct_of_max, Some(0.9));
+    assert_eq!(settings.ride_lthr_pct_of_max, Some(0.85));
+
+    // An unrelated update keeps them
+    save_user_settings(&db, UserSettingsUpdate { ftp: Some(250), ..Default::default() }).await.unwrap();
+    assert_eq!(load_user_settings(&db).await.unwrap().run_lthr_pct_of_max, Some(0.9));
+
+    // Clearing runs falls back to the general fraction; rides keep theirs
+    save_user_settings(&db, UserSettingsUpdate { clear_run_lthr_pct_of_max: true, ..Default::default() })
+      .await
+      .unwrap();
+    let settings = load_user_settings(&db).await.unwrap();
+    assert_eq!(settings.run_lthr_pct_of_max, None);
+    assert_eq!(settings.ride_lthr_pct_of_max, Some(0.85));
+
+    let conflicting = UserSettingsUpdate {
+      ride_lthr_pct_of_max: Some(0.9),
+      clear_ride_lthr_pct_of_max: true,
+      ..Default::default()
+    };
+    assert!(save_user_settings(&db, conflicting).await.is_err());
+    save_user_settings(&db, UserSettingsUpdate { clear_ride_lthr_pct_of_max: true, ..Default::default() })
+      .await
+      .unwrap();
+    assert_eq!(load_user_settings(&db).await.unwrap().ride_lthr_pct_of_max, None);
+  }
+
+  #[tokio::test]
+  async fn test_utc_offset_can_be_cleared() {
+    let db = crate::db::test_pool().await;
+    save_user_settings(&db, UserSettingsUpdate { utc_offset_minutes: Some(-300), ..Default::default() })
+      .await
+      .unwrap();
+    assert_eq!(load_user_settings(&db).await.unwrap().utc_offset_minutes, Some(-300));
+
+    let conflicting = UserSettingsUpdate {
+      utc_offset_minutes: Some(60),
+      clear_utc_offset_minutes: true,
+      ..Default::default()
+    };
+    assert!(save_user_settings(&db, conflicting).await.is_err());
+
+    // Cleared: back to the system's local offset
+    save_user_settings(&db, UserSettingsUpdate { clear_utc_offset_minutes: true, ..Default::default() })
+      .await
+      .unwrap();
+    assert_eq!(load_user_settings(&db).await.unwrap().utc_offset_minutes, None);
+  }
+
+  #[tokio::test]
+  async fn test_resting_hr_can_be_cleared() {
+    let db = crate::db::test_pool().await;
+    save_user_settings(&db, UserSettingsUpdate { resting_hr: Some(48), ..Default::default() }).await.unwrap();
+    assert_eq!(load_user_settings(&db).await.unwrap().resting_hr, Some(48));
+    sqlx::query(
+      "INSERT INTO workouts (strava_id, activity_type, started_at, average_heartrate, metrics_computed_at)
+       VALUES ('r1', 'Run', '2025-03-01T07:00:00Z', 150, '2025-03-01T08:00:00Z')",
+    )
+    .execute(&db)
+    .await
+    .unwrap();
+
+    let conflicting = UserSettingsUpdate { resting_hr: Some(50), clear_resting_hr: true, ..Default::default() };
+    assert!(save_user_settings(&db, conflicting).await.is_err());
+    assert_eq!(load_user_settings(&db).await.unwrap().resting_hr, Some(48));
+
+    // Cleared: HR zones no longer use the reserve, so stored runs re-zone
+    save_user_settings(&db, UserSettingsUpdate { clear_resting_hr: true, ..Default::default() }).await.unwrap();
+    assert_eq!(load_user_settings(&db).await.unwrap().resting_hr, None);
+    let reset: bool = sqlx::query_scalar("SELECT metrics_computed_at IS NULL FROM workouts WHERE strava_id = 'r1'")
+      .fetch_one(&db)
+      .await
+      .unwrap();
+    assert!(reset);
+  }
+
+  #[tokio::test]
+  async fn test_css_pace_can_be_cleared() {
+    let db = crate::db::test_pool().await;
+    let update = UserSettingsUpdate { css_pace_sec_per_100m: Some(105.0), ..Default::default() };
+    save_user_settings(&db, update).await.unwrap();
+    assert_eq!(load_user_settings(&db).await.unwrap().css_pace_sec_per_100m, Some(105.0));
+    sqlx::query(
+      "INSERT INTO workouts (strava_id, activity_type, started_at, swim_pace_sec_per_100m, metrics_computed_at)
+       VALUES ('s1', 'Swim', '2025-03-01T07:00:00Z', 110, '2025-03-01T08:00:00Z')",
+    )
+    .execute(&db)
+    .await
+    .unwrap();
+
+    let conflicting = UserSettingsUpdate {
+      css_pace_sec_per_100m: Some(100.0),
+      clear_css_pace_sec_per_100m: true,
+      ..Default::default()
+    };
+    assert!(save_user_settings(&db, conflicting).await.is_err());
+    assert_eq!(load_user_settings(&db).await.unwrap().css_pace_sec_per_100m, Some(105.0));
+
+    // Cleared: stored swims drop their CSS-based zones on the next compute
+    let clear = UserSettingsUpdate { clear_css_pace_sec_per_100m: true, ..Default::default() };
+    save_user_settings(&db, clear).await.unwrap();
+    assert_eq!(load_user_settings(&db).await.unwrap().css_pace_sec_per_100m, None);
+    let reset: bool = sqlx::query_scalar("SELECT metrics_computed_at IS NULL FROM workouts WHERE strava_id = 's1'")
+      .fetch_one(&db)
+      .await
+      .unwrap();
+    assert!(reset);
+  }
+
+  #[tokio::test]
+  async fn test_timezone_stored_and_cleared() {
+    let db = crate::db::test_pool().await;
+    let update = UserSettingsUpdate { timezone: Some("America/Denver".to_string()), ..Default::default() };
+    save_user_settings(&db, update).await.unwrap();
+    assert_eq!(load_user_settings(&db).await.unwrap().timezone.as_deref(), Some("America/Denver"));
+
+    let invalid = UserSettingsUpdate { timezone: Some("Mountain Time".to_string()), ..Default::default() };
+    assert!(save_user_settings(&db, invalid).await.is_err());
+    let conflicting = UserSettingsUpdate {
+      timezone: Some("Europe/Berlin".to_string()),
+      clear_timezone: true,
+      ..Default::default()
+    };
+    assert!(save_user_settings(&db, conflicting).await.is_err());
+
+    save_user_settings(&db, UserSettingsUpdate { clear_timezone: true, ..Default::default() })
+      .await
+      .unwrap();
+    assert_eq!(load_user_settings(&db).await.unwrap().timezone, None);
+  }
+
+  #[tokio::test]
+  async fn test_coach_tone_stored_canonically() {
+    let db = crate::db::test_pool().await;
+    save_user_settings(&db, UserSettingsUpdate { coach_tone: Some("direct".to_string()), ..Default::default() })
+      .await
+      .unwrap();
+    assert_eq!(load_user_settings(&db).await.unwrap().coach_tone, CoachTone::Direct);
+
+    let invalid = UserSettingsUpdate { coach_tone: Some("sarcastic".to_string()), ..Default::default() };
+    assert!(save_user_settings(&db, invalid).await.is_err());
+    let stored: String = sqlx::query_scalar("SELECT coach_tone FROM user_settings WHERE id = 1")
+      .fetch_one(&db)
+      .await
+      .unwrap();
+    assert_eq!(stored, "direct");
+  }
+
+  #[tokio::test]
+  async fn test_flag_thresholds_round_trip() {
+    let db = crate::db::test_pool().await;
+    assert_eq!(load_user_settings(&db).await.unwrap().flag_thresholds, FlagThresholds::default());
+
+    let conservative = FlagThresholds { volume_spike_ratio: 1.1, ..Default::default() };
+    save_flag_thresholds(&db, &conservative).await.unwrap();
+    assert_eq!(load_user_settings(&db).await.unwrap().flag_thresholds, conservative);
+
+    let invalid = FlagThresholds { volume_drop_ratio: 0.0, ..Default::default() };
+    assert!(save_flag_thresholds(&db, &invalid).await.is_err());
+    assert_eq!(load_user_settings(&db).await.unwrap().flag_thresholds, conservative);
+  }
+
+  #[tokio::test]
+  async fn test_polarization_target_round_trip() {
+    let db = crate::db::test_pool().await;
+    assert_eq!(load_user_settings(&db).await.unwrap().polarization_target, ZoneSplit::default());
+
+    let pyramidal = ZoneSplit { low_pct: 75.0, moderate_pct: 20.0, high_pct: 5.0 };
+    save_polarization_target(&db, &pyramidal).await.unwrap();
+    assert_eq!(load_user_settings(&db).await.unwrap().polarization_target, pyramidal);
+
+    let invalid = ZoneSplit { low_pct: 80.0, moderate_pct: 20.0, high_pct: 10.0 };
+    assert!(save_polarization_target(&db, &invalid).await.is_err());
+    assert_eq!(load_user_settings(&db).await.unwrap().polarization_target, pyramidal);
+  }
+
+  #[tokio::test]
+  async fn test_sport_settings_round_trip() {
+    let db = crate::db::test_pool().await;
+    assert!(load_user_settings(&db).await.unwrap().sport_settings.is_empty());
+
+    let ride = SportSettings { sport: ActivityKind::Ride, max_hr: Some(180), lthr: Some(162), ftp: Some(240) };
+    let run = SportSettings { sport: ActivityKind::Run, max_hr: None, lthr: Some(174), ftp: None };
+    save_sport_settings(&db, &ride).await.unwrap();
+    save_sport_settings(&db, &run).await.unwrap();
+
+    let settings = load_user_settings(&db).await.unwrap();
+    assert_eq!(settings.sport_settings, vec![ride.clone(), run]);
+    assert_eq!(settings.effective_lthr_for("Ride"), Some(162));
+    assert_eq!(settings.effective_lthr_for("Run"), Some(174));
+
+    // Invalid overrides are rejected; clearing every value removes the row
+    assert!(save_sport_settings(&db, &SportSettings { lthr: Some(190), ..ride.clone() }).await.is_err());
+    let cleared = SportSettings { max_hr: None, lthr: None, ftp: None, ..ride };
+    save_sport_settings(&db, &cleared).await.unwrap();
+    let sports: Vec<ActivityKind> = load_sport_settings(&db).await.unwrap().iter().map(|s| s.sport).collect();
+    assert_eq!(sports, vec![ActivityKind::Run]);
+  }
+
+  #[tokio::test]
+  async fn test_activity_calendar_from_stored_workouts() {
+    let db = crate::db::test_pool().await;
+    insert_workout(&db, "c1", "2025-03-18T07:00:00Z").await;
+    insert_workout(&db, "c2", "2025-03-19T07:00:00Z").await;
+    let today = chrono::NaiveDate::from_ymd_opt(2025, 3, 20).unwrap();
+    let settings = UserSettings { utc_offset_minutes: Some(0), ..Default::default() };
+
+    let calendar = load_activity_calendar(&db, &settings, today, 90).await.unwrap();
+    assert_eq!(calendar.days.len(), 90);
+    assert_eq!(calendar.current_streak, 2);
+    assert_eq!(calendar.days.iter().map(|d| d.workouts).sum::<usize>(), 2);
+
+    assert!(load_activity_calendar(&db, &settings, today, 0).await.is_err());
+    assert!(load_activity_calendar(&db, &settings, today, 400).await.is_err());
+  }
+
+  #[tokio::test]
+  async fn test_activity_calendar_uses_local_days() {
+    let db = crate::db::test_pool().await;
+    // UTC-5: 23:30 local on the 19th and 22:00 local today are the next UTC day
+    insert_workout(&db, "l1", "2025-03-20T04:30:00Z").await;
+    insert_workout(&db, "l2", "2025-03-21T03:00:00Z").await;
+    // 23:30 local the day before the 7-day window: outside it
+    insert_workout(&db, "l3", "2025-03-14T04:30:00Z").await;
+    let today = chrono::NaiveDate::from_ymd_opt(2025, 3, 20).unwrap();
+    let settings = UserSettings { utc_offset_minutes: Some(-300), ..Default::default() };
+
+    let calendar = load_activity_calendar(&db, &settings, today, 7).await.unwrap();
+    let day = |date: chrono::NaiveDate| calendar.days.iter().find(|d| d.date == date).unwrap().workouts;
+    assert_eq!(day(today), 1);
+    assert_eq!(day(today - chrono::Duration::days(1)), 1);
+    assert_eq!(calendar.days.iter().map(|d| d.workouts).sum::<usize>(), 2);
+    assert_eq!(calendar.current_streak, 2);
+  }
+
+  /// Runs every other day through March, a ride on each run day, plus one
+  /// run after the analyzed workout. Returns the analyzed workout's id.
+  async fn insert_recent_history(db: &crate::db::DbPool) -> i64 {
+    for day in (1..=29).step_by(2) {
+      insert_run_with_metrics(db, &format!("r{}", day), &format!("2025-03-{:02}T07:00:00Z", day), 5.0, 0.035).await;
+      sqlx::query(
+        "INSERT INTO workouts (strava_id, activity_type, started_at, duration_seconds) VALUES (?1, 'Ride', ?2, 3600)",
+      )
+      .bind(format!("b{}", day))
+      .bind(format!("2025-03-{:02}T18:00:00Z", day))
+      .execute(db)
+      .await
+      .unwrap();
+    }
+    insert_run_with_metrics(db, "later", "2025-04-05T07:00:00Z", 5.0, 0.035).await;
+    insert_run_with_metrics(db, "current", "2025-03-30T07:00:00Z", 5.0, 0.035).await
+  }
+
+  #[tokio::test]
+  async fn test_recent_workouts_count_mode() {
+    let db = crate::db::test_pool().await;
+    let workout_id = insert_recent_history(&db).await;
+    let settings = UserSettings::default();
+
+    let target = ComparisonTarget {
+      date: chrono::NaiveDate::from_ymd_opt(2025, 3, 30).unwrap(),
+      duration_min: 60.0,
+      rtss: None,
+    };
+    let same = get_recent_same_type_workouts(&db, &settings, "Run", workout_id, 3, None, &target).await.unwrap();
+    assert_eq!(same.len(), 3);
+    assert!(same.iter().all(|w| w.activity_type == "Run"));
+
+    let all = get_recent_all_workouts(&db, &settings, workout_id, 4, None).await.unwrap();
+    assert_eq!(all.len(), 4);
+    assert!(all.iter().any(|w| w.activity_type == "Ride"));
+  }
+
+  #[tokio::test]
+  async fn test_rare_type_found_behind_many_newer_workouts() {
+    let db = crate::db::test_pool().await;
+    for (strava_id, activity_type, started_at) in [
+      ("s1", "Swim", "2025-01-10T07:00:00Z"),
+      ("s2", "Open Water Swim", "2025-01-12T07:00:00Z"),
+    ] {
+      sqlx::query(
+        "INSERT INTO workouts (strava_id, activity_type, started_at, duration_seconds) VALUES (?1, ?2, ?3, 1800)",
+      )
+      .bind(strava_id)
+      .bind(activity_type)
+      .bind(started_at)
+      .execute(&db)
+      .await
+      .unwrap();
+    }
+    // More newer runs than the scan limit
+    let start = DateTime::parse_from_rfc3339("2025-01-13T07:00:00Z").unwrap().with_timezone(&Utc);
+    for i in 0..SAME_TYPE_SCAN_LIMIT + 10 {
+      let at = (start + chrono::Duration::hours(i * 12)).format("%Y-%m-%dT%H:%M:%SZ").to_string();
+      insert_run_with_metrics(&db, &format!("r{}", i), &at, 5.0, 0.035).await;
+    }
+
+    let target = ComparisonTarget {
+      date: chrono::NaiveDate::from_ymd_opt(2025, 5, 1).unwrap(),
+      duration_min: 30.0,
+      rtss: None,
+    };
+    let swims = get_recent_same_type_workouts(&db, &UserSettings::default(), "Swim", 0, 5, None, &target).await.unwrap();
+    let types: Vec<&str> = swims.iter().map(|w| w.activity_type.as_str()).collect();
+    assert_eq!(types.len(), 2);
+    assert!(types.contains(&"Swim") && types.contains(&"Open Water Swim"));
+  }
+
+  #[tokio::test]
+  async fn test_recent_workouts_time_bounded_mode() {
+    let db = crate::db::test_pool().await;
+    let workout_id = insert_recent_history(&db).await;
+    let started_at = DateTime::parse_from_rfc3339("2025-03-30T07:00:00Z").unwrap().with_timezone(&Utc);
+    let settings = UserSettings { utc_offset_minutes: Some(0), ..Default::default() };
+
+    // 10 days back: runs on the 21st..29th, nothing after the analyzed workout
+    let bounds = recent_bounds(&started_at, Some(10));
+    let target = ComparisonTarget { date: settings.local_date(&started_at), duration_min: 60.0, rtss: None };
+    let same =
+      get_recent_same_type_workouts(&db, &settings, "Run", workout_id, 1, bounds.as_ref(), &target).await.unwrap();
+    let dates: Vec<&str> = same.iter().map(|w| w.date.as_str()).collect();
+    assert_eq!(dates, vec!["2025-03-29", "2025-03-27", "2025-03-25", "2025-03-23", "2025-03-21"]);
+
+    let all = get_recent_all_workouts(&db, &settings, workout_id, 1, bounds.as_ref()).await.unwrap();
+    assert_eq!(all.len(), 10);
+
+    // UTC+10: the 18:00 UTC rides fall on the next local day
+    let ahead = UserSettings { utc_offset_minutes: Some(600), ..Default::default() };
+    let all = get_recent_all_workouts(&db, &ahead, workout_id, 1, bounds.as_ref()).await.unwrap();
+    let ride_dates: Vec<&str> =
+      all.iter().filter(|w| w.activity_type == "Ride").map(|w| w.date.as_str()).collect();
+    assert_eq!(ride_dates, vec!["2025-03-30", "2025-03-28", "2025-03-26", "2025-03-24", "2025-03-22"]);
+  }
+
+  #[tokio::test]
+  async fn test_recent_workout_window_round_trip() {
+    let db = crate::db::test_pool().await;
+    assert_eq!(load_user_settings(&db).await.unwrap().recent_window, RecentWorkoutWindow::default());
+
+    let window = RecentWorkoutWindow { window_days: Some(28), ..Default::default() };
+    save_recent_workout_window(&db, &window).await.unwrap();
+    assert_eq!(load_user_settings(&db).await.unwrap().recent_window, window);
+
+    let invalid = RecentWorkoutWindow { same_type_count: -1, ..Default::default() };
+    assert!(save_recent_workout_window(&db, &invalid).await.is_err());
+  }
+
+  #[tokio::test]
+  async fn test_adherence_prefers_explicit_key_sessions() {
+    let db = crate::db::test_pool().await;
+    let recent = |days: i64| (Utc::now() - chrono::Duration::days(days)).format("%Y-%m-%dT%H:%M:%SZ").to_string();
+    let mut run_ids = Vec::new();
+    for (i, minutes) in [50, 55, 40].iter().enumerate() {
+      let id = insert_workout(&db, &format!("600{}", i), &recent(i as i64 + 1)).await;
+      sqlx::query("UPDATE workouts SET duration_seconds = ?1 WHERE id = ?2")
+        .bind(minutes * 60)
+        .bind(id)
+        .execute(&db)
+        .await
+        .unwrap();
+      run_ids.push(id);
+    }
+    let settings = UserSettings::default();
+
+    // Heuristic: both easy runs over 45 min look like key sessions
+    assert_eq!(compute_adherence(&db, &settings).await.unwrap().key_completed, 2);
+
+    // Once linked, only the deliberate key session counts
+    crate::progression::mark_key_session(&db, run_ids[2], "run_interval").await.unwrap();
+    crate::progression::mark_key_session(&db, run_ids[2], "run_interval").await.unwrap();
+    assert_eq!(compute_adherence(&db, &settings).await.unwrap().key_completed, 1);
+
+    assert!(crate::progression::mark_key_session(&db, run_ids[0], "no_such_dim").await.is_err());
+    assert!(crate::progression::mark_key_session(&db, 9999, "run_interval").await.is_err());
+  }
+
+  #[test]
+  fn test_analysis_error_kinds() {
+    let kind = |e: LlmError| AnalysisError::from(e).kind;
+    assert_eq!(kind(LlmError::MissingApiKey), AnalysisErrorKind::Config);
+    assert_eq!(kind(LlmError::Api("429 rate_limit_error".to_string())), AnalysisErrorKind::Llm);
+    assert_eq!(kind(LlmError::Request("timeout".to_string())), AnalysisErrorKind::Llm);
+    assert_eq!(kind(LlmError::Parse("bad json".to_string())), AnalysisErrorKind::Parse);
+
+    assert_eq!(AnalysisError::from(sqlx::Error::RowNotFound).kind, AnalysisErrorKind::NotFound);
+    assert_eq!(AnalysisError::from(sqlx::Error::PoolTimedOut).kind, AnalysisErrorKind::Database);
+  }
+
+  #[test]
+  fn test_analysis_error_serializes_kind_and_message() {
+    let error = AnalysisError::new(AnalysisErrorKind::NotFound, "Workout not found");
+    assert_eq!(
+      serde_json::to_value(&error).unwrap(),
+      serde_json::json!({"kind": "not_found", "message": "Workout not found"})
+    );
+  }
+
+  #[tokio::test]
+  async fn test_new_workout_marks_fitness_trend_stale_and_refresh_recomputes() {
+    let db = crate::db::test_pool().await;
+    let days_ago = |days: i64| (Utc::now() - chrono::Duration::days(days)).format("%Y-%m-%dT%H:%M:%SZ").to_string();
+    let today = Utc::now().date_naive();
+    let settings = UserSettings::default();
+    insert_run_with_metrics(&db, "7001", &days_ago(20), 5.5, 0.040).await;
+    insert_run_with_metrics(&db, "7002", &days_ago(10), 5.3, 0.038).await;
+
+    // First read fills the cache
+    let cached = load_fitness_trend(&db, &settings, today).await.unwrap();
+    assert!(!cached.stale);
+    let total = |c: &CachedFitnessTrend| c.trend.weeks.iter().map(|w| w.workouts).sum::<usize>();
+    assert_eq!(total(&cached), 2);
+
+    insert_run_with_metrics(&db, "7003", &days_ago(1), 5.1, 0.036).await;
+    let cached = load_fitness_trend(&db, &settings, today).await.unwrap();
+    assert!(cached.stale);
+    assert_eq!(total(&cached), 2);
+
+    let refreshed = refresh_fitness_trend_cache(&db, &settings, today).await.unwrap();
+    assert!(!refreshed.stale);
+    assert_eq!(total(&refreshed), 3);
+    assert_eq!(refreshed.trend.pr_count, 2);
+
+    // Metric recomputation also invalidates
+    sqlx::query("UPDATE workouts SET rtss = 50 WHERE strava_id = '7003'")
+      .execute(&db)
+      .await
+      .unwrap();
+    assert!(load_fitness_trend(&db, &settings, today).await.unwrap().stale);
+
+    // Untouched workouts, but the next day: the window no longer ends today
+    refresh_fitness_trend_cache(&db, &settings, today).await.unwrap();
+    assert!(!load_fitness_trend(&db, &settings, today).await.unwrap().stale);
+    assert!(load_fitness_trend(&db, &settings, today + chrono::Duration::days(1)).await.unwrap().stale);
+
+    // Load settings change what the trend counts
+    save_load_weights(&db, &LoadWeights { ride: 0.7, ..Default::default() }).await.unwrap();
+    assert!(load_fitness_trend(&db, &settings, today).await.unwrap().stale);
+    refresh_fitness_trend_cache(&db, &settings, today).await.unwrap();
+    save_user_settings(&db, UserSettingsUpdate { include_other_load: Some(false), ..Default::default() })
+      .await
+      .unwrap();
+    assert!(load_fitness_trend(&db, &settings, today).await.unwrap().stale);
+
+    // Saving settings that leave load alone keeps the cache
+    refresh_fitness_trend_cache(&db, &settings, today).await.unwrap();
+    save_user_settings(&db, UserSettingsUpdate { max_hr: Some(188), ..Default::default() }).await.unwrap();
+    assert!(!load_fitness_trend(&db, &settings, today).await.unwrap().stale);
+
+    // Local dates move with the timezone
+    save_user_settings(&db, UserSettingsUpdate { utc_offset_minutes: Some(-300), ..Default::default() })
+      .await
+      .unwrap();
+    assert!(load_fitness_trend(&db, &settings, today).await.unwrap().stale);
+  }
+
+  #[tokio::test]
+  async fn test_subjective_rpe_in_context_and_estimated_load() {
+    let db = crate::db::test_pool().await;
+    let started_at = (Utc::now() - chrono::Duration::days(1)).format("%Y-%m-%dT%H:%M:%SZ").to_string();
+    // Treadmill run with no HR strap
+    let workout_id = insert_workout(&db, "5001", &started_at).await;
+
+    save_workout_subjective(&db, workout_id, Some(6), Some("  legs felt flat ")).await.unwrap();
+    assert!(save_workout_subjective(&db, workout_id, Some(11), None).await.is_err());
+    assert!(save_workout_subjective(&db, 9999, Some(5), None).await.is_err());
+
+    // RPE drives the estimated load when HR is absent: 6 x 45 min = 270 AU
+    let summaries = get_workout_summaries(&db).await.unwrap();
+    assert_eq!(summaries.len(), 1);
+    assert_eq!(summaries[0].rpe, Some(6));
+    assert!(!summaries[0].has_device_data);
+    let expected = 270.0 * crate::analysis::SRPE_TO_RTSS;
+    assert!((summaries[0].rtss.unwrap() - expected).abs() < 1e-9);
+
+    let settings = UserSettings::default();
+    let context = TrainingContext::compute(&summaries, &settings);
+    assert!((context.load_confidence.atl_estimated - expected).abs() < 1e-9);
+    assert_eq!(context.load_confidence.atl_measured, 0.0);
+
+    // Stored RPE and note reach the coach
+    let (rpe, notes): (Option<i64>, Option<String>) =
+      sqlx::query_as("SELECT rpe, notes FROM workouts WHERE id = ?1")
+        .bind(workout_id)
+        .fetch_one(&db)
+        .await
+        .unwrap();
+    let metrics = WorkoutMetrics::compute("Run", Some(2700), None, None, None, &[], &settings);
+    let package = ContextPackage::build(
+      "Run",
+      &Utc::now(),
+      Some(2700),
+      None,
+      None,
+      None,
+      &metrics,
+      context,
+      TrainingFlags::default(),
+      1.0,
+      &settings,
+      vec![],
+      vec![],
+    )
+    .with_subjective(rpe, notes);
+    assert_eq!(package.workout.rpe, Some(6));
+    assert!(package.to_json().contains("legs felt flat"));
+  }
+
+  #[tokio::test]
+  async fn test_analyzed_ride_context_carries_stored_metrics() {
+    let db = crate::db::test_pool().await;
+    let workout_id = sqlx::query(
+      r#"
+      INSERT INTO workouts (strava_id, activity_type, started_at, duration_seconds, distance_meters, average_heartrate, average_watts)
+      VALUES ('8001', 'Ride', '2024-12-10T07:00:00Z', 3600, 30000, 140, 200)
+      "#,
+    )
+    .execute(&db)
+    .await
+    .unwrap()
+    .last_insert_rowid();
+    compute_pending_metrics(&db).await.unwrap();
+
+    let metrics = load_stored_metrics(&db, workout_id).await.unwrap().unwrap();
+    assert_eq!(metrics.kj, Some(720.0));
+    let efficiency = metrics.efficiency.expect("ride with power and HR has an efficiency");
+    assert!(load_stored_metrics(&db, 9999).await.unwrap().is_none());
+
+    let settings = UserSettings::default();
+    let package = ContextPackage::build(
+      "Ride",
+      &Utc::now(),
+      Some(3600),
+      Some(30000.0),
+      Some(140),
+      Some(200.0),
+      &metrics,
+      TrainingContext::compute(&[], &settings),
+      TrainingFlags::default(),
+      1.0,
+      &settings,
+      vec![],
+      vec![],
+    );
+    assert_eq!(package.workout.kj, Some(720.0));
+    assert_eq!(package.workout.efficiency, Some(efficiency));
+    assert!(package.workout.speed_kmh.is_some());
+    assert!(package.to_json().contains("\"kj\""));
+  }
+
+  #[tokio::test]
+  async fn test_workout_bundle_round_trip() {
+    let db = crate::db::test_pool().await;
+    let workout_id = sqlx::query(
+      r#"
+      INSERT INTO workouts (strava_id, activity_type, started_at, duration_seconds, distance_meters, average_heartrate, average_watts, samples_json)
+      VALUES ('8201', 'Ride', '2024-12-10T07:00:00Z', 3600, 30000, 140, 200, '{"hr": [138, 140, 142], "watts": [190, 200, 210]}')
+      "#,
+    )
+    .execute(&db)
+    .await
+    .unwrap()
+    .last_insert_rowid();
+    compute_pending_metrics(&db).await.unwrap();
+
+    let analysis = serde_json::json!({
+      "performance": {
+        "metric_name": "power", "comparison_date": "2024-12-03", "comparison_value": "190W",
+        "today_value": "200W", "delta": "+10W", "insight": "Stronger"
+      },
+      "hr_efficiency": { "avg_hr": 140, "hr_zone": "Z2", "hr_pct_max": 74, "hr_assessment": "Z2 throughout" },
+      "training_status": {
+        "tsb_value": -4.0, "tsb_band": "optimal", "tsb_assessment": "...",
+        "top_flags": [], "adherence_note": "5/6", "progression_state": "On track"
+      },
+      "tomorrow": {
+        "activity_type": "Run", "duration_min": 45, "duration_label": "MEDIUM", "intensity": "Z2",
+        "goal": "aerobic_base", "rationale": "...", "confidence": "high"
+      }
+    });
+    sqlx::query("INSERT INTO workout_analysis (workout_id, summary, tomorrow_recommendation, analysis_json) VALUES (?1, 'Strong ride', 'Easy run', ?2)")
+      .bind(workout_id)
+      .bind(analysis.to_string())
+      .execute(&db)
+      .await
+      .unwrap();
+
+    let dest = std::env::temp_dir().join(format!("tempo-bundle-test-{}.json", std::process::id()));
+    let _ = std::fs::remove_file(&dest);
+    export_workout_bundle_to(&db, workout_id, &dest).await.unwrap();
+
+    // Never overwrite an existing file
+    assert!(export_workout_bundle_to(&db, workout_id, &dest).await.is_err());
+
+    let bundle: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&dest).unwrap()).unwrap();
+    std::fs::remove_file(&dest).unwrap();
+
+    for section in ["workout", "metrics", "samples", "context", "analysis"] {
+      assert!(!bundle[section].is_null(), "missing {}", section);
+    }
+    assert_eq!(bundle["workout"]["strava_id"], "8201");
+    assert_eq!(bundle["samples"]["watts"].as_array().unwrap().len(), 3);
+    assert_eq!(bundle["analysis"]["performance"]["metric_name"], "power");
+
+    let (kj, rtss, efficiency): (Option<f64>, Option<f64>, Option<f64>) =
+      sqlx::query_as("SELECT CAST(kj AS REAL), CAST(rtss AS REAL), CAST(efficiency AS REAL) FROM workouts WHERE id = ?1")
+        .bind(workout_id)
+        .fetch_one(&db)
+        .await
+        .unwrap();
+    assert_eq!(bundle["metrics"]["kj"].as_f64(), kj);
+    assert_eq!(bundle["metrics"]["rtss"].as_f64(), rtss);
+    assert_eq!(bundle["metrics"]["efficiency"].as_f64(), efficiency);
+    assert_eq!(kj, Some(720.0));
+  }
+
+  #[tokio::test]
+  async fn test_powerless_ride_has_no_zero_kj() {
+    let db = crate::db::test_pool().await;
+    let insert = |strava_id: &'static str, watts: Option<f64>, device_watts: Option<bool>| {
+      sqlx::query(
+        r#"
+        INSERT INTO workouts (strava_id, activity_type, started_at, duration_seconds, average_heartrate, average_watts, device_watts)
+        VALUES (?1, 'Ride', '2024-12-10T07:00:00Z', 3600, 140, ?2, ?3)
+        "#,
+      )
+      .bind(strava_id)
+      .bind(watts)
+      .bind(device_watts)
+      .execute(&db)
+    };
+    // Stored zero, no meter known; Strava's estimate; a real meter reading zero
+    let no_power = insert("8101", Some(0.0), None).await.unwrap().last_insert_rowid();
+    let estimated = insert("8102", Some(150.0), Some(false)).await.unwrap().last_insert_rowid();
+    let zero_meter = insert("8103", Some(0.0), Some(true)).await.unwrap().last_insert_rowid();
+    compute_pending_metrics(&db).await.unwrap();
+
+    let metrics = load_stored_metrics(&db, no_power).await.unwrap().unwrap();
+    assert_eq!(metrics.kj, None);
+    assert_eq!(metrics.efficiency, None);
+    assert_eq!(load_stored_metrics(&db, estimated).await.unwrap().unwrap().kj, None);
+    assert_eq!(load_stored_metrics(&db, zero_meter).await.unwrap().unwrap().kj, Some(0.0));
+
+    // The powerless ride isn't counted as device data from its watts alone
+    sqlx::query("UPDATE workouts SET average_heartrate = NULL").execute(&db).await.unwrap();
+    sqlx::query("UPDATE workouts SET started_at = ?1").bind(Utc::now().to_rfc3339()).execute(&db).await.unwrap();
+    let summaries = get_workout_summaries(&db).await.unwrap();
+    assert_eq!(summaries.iter().filter(|w| w.has_device_data).count(), 1);
+  }
+
+  #[tokio::test]
+  async fn test_ef_trend_rises_for_faster_z2_runs() {
+    let db = crate::db::test_pool().await;
+    sqlx::query("UPDATE user_settings SET max_hr = 200, utc_offset_minutes = 0")
+      .execute(&db)
+      .await
+      .unwrap();
+    let now = Utc::now();
+    let insert = |strava_id: &'static str, activity_type: &'static str, days_ago: i64, seconds: i64, hr: i64| {
+      sqlx::query(
+        r#"
+        INSERT INTO workouts (strava_id, activity_type, started_at, duration_seconds, distance_meters, average_heartrate)
+        VALUES (?1, ?2, ?3, ?4, 10000, ?5)
+        "#,
+      )
+      .bind(strava_id)
+      .bind(activity_type)
+      .bind((now - chrono::Duration::days(days_ago)).to_rfc3339())
+      .bind(seconds)
+      .bind(hr)
+      .execute(&db)
+    };
+    // The same 10 km at 130 bpm (Z2) gets quicker week by week
+    insert("9101", "Run", 28, 3600, 130).await.unwrap();
+    insert("9102", "Run", 21, 3540, 130).await.unwrap();
+    insert("9103", "Run", 14, 3480, 130).await.unwrap();
+    insert("9104", "Run", 7, 3420, 130).await.unwrap();
+    // Hard run and a ride stay off the run trend; so does anything too old
+    insert("9105", "Run", 10, 2700, 175).await.unwrap();
+    insert("9106", "Ride", 5, 1200, 130).await.unwrap();
+    insert("9107", "Run", 120, 4200, 130).await.unwrap();
+    compute_pending_metrics(&db).await.unwrap();
+
+    let trend = load_ef_trend(&db, "Running", 90, now).await.unwrap();
+    assert_eq!(trend.activity_type, ActivityKind::Run);
+    assert_eq!(trend.points.len(), 4);
+    assert!(trend.slope_per_week.unwrap() > 0.0);
+    assert!(trend.hr_slope_per_week.unwrap().abs() < 1e-9);
+
+    assert!(load_ef_trend(&db, "Swim", 90, now).await.is_err());
+  }
+
+  #[tokio::test]
+  async fn test_reclassify_zones_after_zone_model_change() {
+    let db = crate::db::test_pool().await;
+    sqlx::query("UPDATE user_settings SET max_hr = 190, resting_hr = 50, zone_model = 'pct_max'")
+      .execute(&db)
+      .await
+      .unwrap();
+    let id = sqlx::query(
+      r#"
+      INSERT INTO workouts (strava_id, activity_type, started_at, duration_seconds, distance_meters, average_heartrate)
+      VALUES ('9201', 'Run', '2024-12-10T07:00:00Z', 3600, 10000, 130)
+      "#,
+    )
+    .execute(&db)
+    .await
+    .unwrap()
+    .last_insert_rowid();
+    compute_pending_metrics(&db).await.unwrap();
+    async fn zone(db: &crate::db::DbPool, id: i64) -> Option<HrZone> {
+      load_stored_metrics(db, id).await.unwrap().unwrap().hr_zone
+    }
+    // 130 of 190 max is 68%
+    assert_eq!(zone(&db, id).await, Some(HrZone::Z2));
+
+    // Switch models directly (the settings command would also queue a full recompute)
+    sqlx::query("UPDATE user_settings SET zone_model = 'hr_reserve'").execute(&db).await.unwrap();
+    let result = reclassify_hr_zones(&db).await.unwrap();
+    assert_eq!((result.total, result.changed), (1, 1));
+    // 80 of 140 reserve is 57%
+    assert_eq!(zone(&db, id).await, Some(HrZone::Z1));
+
+    // Nothing left to change on a second pass
+    assert_eq!(reclassify_hr_zones(&db).await.unwrap().changed, 0);
+  }
+
+  #[tokio::test]
+  async fn test_repeated_elevated_hr_surfaces_in_next_context() {
+    let db = crate::db::test_pool().await;
+    let baseline = |date: &str| RecentWorkoutSummary {
+      date: date.to_string(),
+      activity_type: "Run".to_string(),
+      duration_min: 45.0,
+      avg_power: None,
+      avg_hr: Some(140),
+      pace_min_km: Some(6.0),
+      pace_min_mi: None,
+      rtss: Some(50.0),
+      efficiency: None,
+      is_indoor: false,
+    };
+    let recent = vec![baseline("2024-11-26"), baseline("2024-11-28")];
+    let flags = TrainingFlags::default();
+
+    // Three Tuesdays in a row at 150 bpm against a 140 bpm norm
+    for (i, date) in ["2024-12-03", "2024-12-10", "2024-12-17"].iter().enumerate() {
+      let workout_id = insert_workout(&db, &format!("obs{}", i), &format!("{}T07:00:00Z", date)).await;
+      let observations = Observation::extract("Run", Some(150), &recent, &flags);
+      assert_eq!(observations.len(), 1);
+      store_observations(&db, workout_id, &observations).await.unwrap();
+      // Re-storing replaces rather than duplicates
+      store_observations(&db, workout_id, &observations).await.unwrap();
+    }
+    // A normal session adds nothing
+    assert!(Observation::extract("Run", Some(141), &recent, &flags).is_empty());
+
+    let next_id = insert_workout(&db, "obs-next", "2024-12-19T07:00:00Z").await;
+    let started_at: DateTime<Utc> = "2024-12-19T07:00:00Z".parse().unwrap();
+    let recurring = load_recurring_observations(&db, next_id, &started_at).await.unwrap();
+    assert_eq!(recurring.len(), 1);
+    assert_eq!(recurring[0].kind, ObservationKind::ElevatedHr);
+    assert_eq!(recurring[0].sessions, 3);
+    assert_eq!(recurring[0].summary, "elevated HR on run in 3 sessions since 2024-12-03");
+
+    let settings = UserSettings::default();
+    let metrics = WorkoutMetrics::compute("Run", Some(2700), Some(8000.0), Some(148), None, &[], &settings);
+    let package = ContextPackage::build(
+      "Run",
+      &started_at,
+      Some(2700),
+      Some(8000.0),
+      Some(148),
+      None,
+      &metrics,
+      TrainingContext::compute(&[], &settings),
+      flags,
+      1.0,
+      &settings,
+      vec![],
+      vec![],
+    )
+    .with_observations(recurring);
+    let json = package.to_json();
+    assert!(json.contains("\"recurring_observations\""));
+    assert!(json.contains("elevated HR on run in 3 sessions"));
+
+    // Outside the window nothing recurs
+    let much_later: DateTime<Utc> = "2025-02-01T07:00:00Z".parse().unwrap();
+    assert!(load_recurring_observations(&db, next_id, &much_later).await.unwrap().is_empty());
+  }
+
+  #[tokio::test]
+  async fn test_editing_hr_after_analysis_marks_it_stale() {
+    let db = crate::db::test_pool().await;
+    let workout_id: i64 = sqlx::query_scalar(
+      "INSERT INTO workouts (strava_id, activity_type, started_at, duration_seconds, distance_meters, average_heartrate)
+       VALUES ('stale1', 'Run', '2024-12-10T07:00:00Z', 2700, 8000, 145) RETURNING id",
+    )
+    .fetch_one(&db)
+    .await
+    .unwrap();
+
+    // Store an analysis the way run_workout_analysis does, minus the LLM call
+    let prepared = prepare_analysis(&db, workout_id).await.unwrap();
+    sqlx::query(
+      "INSERT INTO workout_analysis (workout_id, summary, tomorrow_recommendation, context_hash) VALUES (?1, 'Easy run', 'Rest', ?2)",
+    )
+    .bind(workout_id)
+    .bind(prepared.context.input_hash(prepared.coach_tone))
+    .execute(&db)
+    .await
+    .unwrap();
+
+    let stored = load_stored_analysis(&db, Some(workout_id)).await.unwrap().unwrap();
+    assert!(!stored.is_stale);
+    assert_eq!(load_stored_analysis(&db, None).await.unwrap().unwrap().workout_id, workout_id);
+
+    // Switching the coach's tone means the stored feedback is in the old voice
+    sqlx::query("UPDATE user_settings SET coach_tone = 'direct'").execute(&db).await.unwrap();
+    assert!(load_stored_analysis(&db, Some(workout_id)).await.unwrap().unwrap().is_stale);
+    sqlx::query("UPDATE user_settings SET coach_tone = 'conversational'").execute(&db).await.unwrap();
+    assert!(!load_stored_analysis(&db, Some(workout_id)).await.unwrap().unwrap().is_stale);
+
+    sqlx::query("UPDATE workouts SET average_heartrate = 158 WHERE id = ?1")
+      .bind(workout_id)
+      .execute(&db)
+      .await
+      .unwrap();
+    assert!(load_stored_analysis(&db, Some(workout_id)).await.unwrap().unwrap().is_stale);
+
+    // Analyses stored before inputs were tracked can't tell
+    sqlx::query("UPDATE workout_analysis SET context_hash = NULL").execute(&db).await.unwrap();
+    assert!(!load_stored_analysis(&db, Some(workout_id)).await.unwrap().unwrap().is_stale);
+  }
+
+  #[tokio::test]
+  async fn test_high_stress_daily_log_in_context_for_that_date() {
+    let db = crate::db::test_pool().await;
+    sqlx::query("UPDATE user_settings SET utc_offset_minutes = 0").execute(&db).await.unwrap();
+    let workout_id: i64 = sqlx::query_scalar(
+      "INSERT INTO workouts (strava_id, activity_type, started_at, duration_seconds, distance_meters, average_heartrate)
+       VALUES ('journal1', 'Run', '2024-12-10T12:00:00Z', 2700, 8000, 145) RETURNING id",
+    )
+    .fetch_one(&db)
+    .await
+    .unwrap();
+
+    save_daily_log(&db, "2024-12-09", Some(3), None, Some("Calm day")).await.unwrap();
+    save_daily_log(&db, "2024-12-10", Some(9), Some(4), Some("  Deadline at work, slept badly ")).await.unwrap();
+    assert!(save_daily_log(&db, "2024-12-10", Some(11), None, None).await.is_err());
+
+    let context = prepare_analysis(&db, workout_id).await.unwrap().context;
+    let log = context.daily_log.unwrap();
+    assert_eq!(log.date, "2024-12-10");
+    assert_eq!(log.stress_1_10, Some(9));
+    assert_eq!(log.note.as_deref(), Some("Deadline at work, slept badly"));
+    assert!(log.high_stress);
+
+    // A calmer rewrite of the same day replaces the entry
+    let calm = save_daily_log(&db, "2024-12-10", Some(4), None, None).await.unwrap();
+    assert!(!calm.high_stress);
+    let date = chrono::NaiveDate::from_ymd_opt(2024, 12, 10).unwrap();
+    assert_eq!(load_daily_log(&db, date).await.unwrap(), Some(calm));
+  }
+
+  #[tokio::test]
+  async fn test_strava_description_flows_into_context() {
+    let db = crate::db::test_pool().await;
+    let activity: crate::strava::StravaActivity = serde_json::from_value(serde_json::json!({
+      "id": 777,
+      "name": "Windy loop",
+      "type": "Ride",
+      "start_date": "2024-12-10T12:00:00Z",
+      "elapsed_time": 3600,
+      "moving_time": 3600,
+      "distance": 25000.0,
+      "description": "  Felt awful, headwind, didn't sleep  ",
+    }))
+    .unwrap();
+    crate::commands::strava::save_activity_as(&db, "777", &activity).await.unwrap();
+    let workout_id: i64 = sqlx::query_scalar("SELECT id FROM workouts WHERE strava_id = '777'")
+      .fetch_one(&db)
+      .await
+      .unwrap();
+
+    let context = prepare_analysis(&db, workout_id).await.unwrap().context;
+    assert_eq!(context.workout.name.as_deref(), Some("Windy loop"));
+    assert_eq!(context.workout.description.as_deref(), Some("Felt awful, headwind, didn't sleep"));
+    assert!(context.to_json().contains("headwind"));
+  }
+
+  #[tokio::test]
+  async fn test_hard_session_after_prescribed_easy_day_surfaces_deviation() {
+    let db = crate::db::test_pool().await;
+    sqlx::query("UPDATE user_settings SET utc_offset_minutes = 0").execute(&db).await.unwrap();
+    let analyzed = insert_workout(&db, "rx1", "2024-12-09T12:00:00Z").await;
+    let tomorrow = crate::llm::TomorrowCard {
+      activity_type: "ride".to_string(),
+      duration_min: 45,
+      duration_label: "STANDARD".to_string(),
+      intensity: "Z2".to_string(),
+      goal: "load_management".to_string(),
+      rationale: "TSB -12: keep it easy".to_string(),
+      confidence: "high".to_string(),
+      intervals: None,
+    };
+    let date = chrono::NaiveDate::from_ymd_opt(2024, 12, 9).unwrap();
+    store_prescription(&db, analyzed, date, &tomorrow).await.unwrap();
+
+    // Next day: 70 min at Z3 instead of 45 min at Z2
+    let workout_id: i64 = sqlx::query_scalar(
+      "INSERT INTO workouts (strava_id, activity_type, started_at, duration_seconds, average_heartrate, hr_zone)
+       VALUES ('rx2', 'Ride', '2024-12-10T12:00:00Z', 4200, 150, 'Z3') RETURNING id",
+    )
+    .fetch_one(&db)
+    .await
+    .unwrap();
+
+    let context = prepare_analysis(&db, workout_id).await.unwrap().context;
+    let adherence = context.adherence_to_prescription.unwrap();
+    assert_eq!(adherence.prescribed, "45 min Z2 ride");
+    assert!(!adherence.followed);
+    assert_eq!(
+      adherence.deviations,
+      vec![
+        "duration: 70 min vs 45 prescribed (+25 min)".to_string(),
+        "intensity: Z3 vs Z2 prescribed (harder)".to_string(),
+      ]
+    );
+
+    // The prescribing workout's own analysis has nothing to compare against
+    assert!(prepare_analysis(&db, analyzed).await.unwrap().context.adherence_to_prescription.is_none());
+  }
+
+  #[tokio::test]
+  async fn test_planned_z2_run_executed_in_z4_is_harder_than_planned() {
+    let db = crate::db::test_pool().await;
+    sqlx::query("UPDATE user_settings SET utc_offset_minutes = 0").execute(&db).await.unwrap();
+    let plan_json = r#"{"days": [
+      {"date": "2024-12-10", "activity_type": "ride", "duration_min": 60, "intensity": "Z4"},
+      {"date": "2024-12-10", "activity_type": "run", "duration_min": 40, "intensity": "Z2"}
+    ]}"#;
+    sqlx::query(
+      "INSERT INTO training_plans (start_date, end_date, weeks, plan_json) VALUES ('2024-12-09', '2024-12-22', 2, ?1)",
+    )
+    .bind(plan_json)
+    .execute(&db)
+    .await
+    .unwrap();
+
+    let workout_id: i64 = sqlx::query_scalar(
+      "INSERT INTO workouts (strava_id, activity_type, started_at, duration_seconds, average_heartrate, hr_zone)
+       VALUES ('pe1', 'Run', '2024-12-10T07:00:00Z', 2400, 172, 'Z4') RETURNING id",
+    )
+    .fetch_one(&db)
+    .await
+    .unwrap();
+
+    let context = prepare_analysis(&db, workout_id).await.unwrap().context;
+    let execution = context.plan_execution.unwrap();
+    assert_eq!(execution.planned, "40 min Z2 run");
+    assert_eq!(execution.executed, "Z4");
+    assert_eq!(execution.execution, crate::analysis::IntensityExecution::HarderThanPlanned);
+  }
 }
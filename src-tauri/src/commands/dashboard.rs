@@ -0,0 +1,249 @@
+use crate::analysis::{DailyLog, TrainingContext, TrainingFlags, UserSettings};
+use crate::commands::analysis::{
+  compute_adherence, get_workout_summaries, load_daily_log, load_stored_analysis, load_user_settings,
+  StoredWorkoutAnalysis,
+};
+use crate::commands::oura::{load_auth_status as load_oura_auth_status, load_oura_context, OuraAuthStatus};
+use crate::commands::plan::load_training_phase;
+use crate::commands::strava::{load_auth_status as load_strava_auth_status, StravaAuthStatus};
+use crate::db::AppState;
+use crate::oura::OuraContext;
+use crate::progression::{load_all_dimensions, EngineDecision, ProgressionSummary};
+use chrono::Utc;
+use serde::Serialize;
+use std::sync::Arc;
+use tauri::State;
+
+/// Flags shown on the home screen (highest priority first)
+pub const DASHBOARD_TOP_FLAGS: usize = 3;
+
+/// ---------------------------------------------------------------------------
+/// Home Screen Summary
+/// ---------------------------------------------------------------------------
+
+/// Everything the home screen shows, in one call. A part that failed to
+/// load is empty (None / 0) with an entry in `errors`; the rest still fill in.
+#[derive(Debug, Serialize)]
+pub struct Dashboard {
+  /// Analysis of the most recent analyzed workout (None before the first)
+  pub latest_analysis: Option<StoredWorkoutAnalysis>,
+  pub context: Option<TrainingContext>,
+  pub top_flags: Vec<DashboardFlag>,
+  /// Dimensions the engine would let progress today
+  pub progression_ready: usize,
+  pub readiness: DailyReadiness,
+  pub strava: Option<StravaAuthStatus>,
+  pub oura: Option<OuraAuthStatus>,
+  /// One "part: message" entry per part that failed to load
+  pub errors: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DashboardFlag {
+  pub name: String,
+  pub description: String,
+}
+
+/// How the athlete is coming into today: last night's Oura data against
+/// its baseline, and today's journal entry
+#[derive(Debug, Serialize)]
+pub struct DailyReadiness {
+  /// Local date (YYYY-MM-DD)
+  pub date: String,
+  /// None when Oura isn't connected or has no recent data
+  pub oura: Option<OuraContext>,
+  pub daily_log: Option<DailyLog>,
+}
+
+/// Get the composed home-screen summary
+#[tauri::command]
+pub async fn get_dashboard(state: State<'_, Arc<AppState>>) -> Result<Dashboard, String> {
+  Ok(load_dashboard(&state.db).await)
+}
+
+/// Helper: Load each part independently, collecting failures instead of
+/// stopping
+pub(crate) async fn load_dashboard(db: &crate::db::DbPool) -> Dashboard {
+  let mut errors = Vec::new();
+
+  let settings = load_user_settings(db).await.unwrap_or_else(|e| {
+    errors.push(format!("settings: {}", e));
+    UserSettings::default()
+  });
+  let today = settings.local_date(&Utc::now());
+
+  let latest_analysis = load_stored_analysis(db, None)
+    .await
+    .map_err(|e| errors.push(format!("analysis: {}", e)))
+    .ok()
+    .flatten();
+
+  let workouts = get_workout_summaries(db)
+    .await
+    .map_err(|e| errors.push(format!("context: {}", e)))
+    .ok();
+  let context = workouts.as_ref().map(|w| TrainingContext::compute(w, &settings));
+
+  let mut top_flags = Vec::new();
+  let mut progression_ready = 0;
+  if let (Some(workouts), Some(context)) = (&workouts, &context) {
+    let dimensions = load_all_dimensions(db)
+      .await
+      .map_err(|e| errors.push(format!("progression: {}", e)))
+      .ok();
+    let phase = load_training_phase(db, &settings, today)
+      .await
+      .map_err(|e| errors.push(format!("phase: {}", e)))
+      .ok()
+      .flatten();
+    let flags = TrainingFlags::compute(workouts, context, &settings, dimensions.as_deref().unwrap_or_default())
+      .with_phase(phase.map(|p| p.phase));
+
+    top_flags = flags
+      .to_prioritized_list()
+      .into_iter()
+      .take(DASHBOARD_TOP_FLAGS)
+      .map(|(name, _, description)| DashboardFlag { name, description })
+      .collect();
+
+    if let Some(dimensions) = dimensions {
+      let adherence = compute_adherence(db, &settings).await.unwrap_or_default();
+      progression_ready = ProgressionSummary::compute(
+        &dimensions,
+        context,
+        &flags,
+        adherence,
+        settings.progression_overlap_days,
+        settings.experience_level,
+      )
+      .dimensions
+      .iter()
+      .filter(|d| d.engine_decision == EngineDecision::ProgressAllowed)
+      .count();
+    }
+  }
+
+  let readiness = DailyReadiness {
+    date: today.format("%Y-%m-%d").to_string(),
+    oura: load_oura_context(db, today, settings.sleep_target_hours)
+      .await
+      .map_err(|e| errors.push(format!("oura data: {}", e)))
+      .ok()
+      .flatten(),
+    daily_log: load_daily_log(db, today)
+      .await
+      .map_err(|e| errors.push(format!("daily log: {}", e)))
+      .ok()
+      .flatten(),
+  };
+
+  let strava = load_strava_auth_status(db)
+    .await
+    .map_err(|e| errors.push(format!("strava: {}", e)))
+    .ok();
+  let oura = load_oura_auth_status(db)
+    .await
+    .map_err(|e| errors.push(format!("oura: {}", e)))
+    .ok();
+
+  Dashboard {
+    latest_analysis,
+    context,
+    top_flags,
+    progression_ready,
+    readiness,
+    strava,
+    oura,
+    errors,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use chrono::Duration;
+
+  #[tokio::test]
+  async fn test_dashboard_degrades_on_empty_database() {
+    let db = crate::db::test_pool().await;
+
+    let dashboard = load_dashboard(&db).await;
+    assert!(dashboard.errors.is_empty(), "{:?}", dashboard.errors);
+    assert!(dashboard.latest_analysis.is_none());
+    assert!(dashboard.context.is_some());
+    assert!(dashboard.readiness.oura.is_none());
+    assert!(dashboard.readiness.daily_log.is_none());
+    assert!(!dashboard.strava.unwrap().is_authenticated);
+    assert!(!dashboard.oura.unwrap().is_authenticated);
+  }
+
+  #[tokio::test]
+  async fn test_dashboard_populates_from_seeded_data() {
+    let db = crate::db::test_pool().await;
+    sqlx::query("UPDATE user_settings SET utc_offset_minutes = 0")
+      .execute(&db)
+      .await
+      .unwrap();
+
+    // A hard fortnight: one 90-minute session a day
+    let now = Utc::now();
+    let mut latest_id = 0;
+    for days_ago in (1..=14).rev() {
+      latest_id = sqlx::query(
+        "INSERT INTO workouts (strava_id, activity_type, started_at, duration_seconds, rtss, hr_zone)
+         VALUES (?1, 'Run', ?2, 5400, 120, 'Z4')",
+      )
+      .bind(format!("d{}", days_ago))
+      .bind((now - Duration::days(days_ago)).to_rfc3339())
+      .execute(&db)
+      .await
+      .unwrap()
+      .last_insert_rowid();
+    }
+    sqlx::query(
+      "INSERT INTO workout_analysis (workout_id, summary, tomorrow_recommendation) VALUES (?1, 'Tough tempo', 'Rest')",
+    )
+    .bind(latest_id)
+    .execute(&db)
+    .await
+    .unwrap();
+
+    let today = now.format("%Y-%m-%d").to_string();
+    sqlx::query("INSERT INTO daily_log (date, stress_1_10, soreness_1_10) VALUES (?1, 6, 7)")
+      .bind(&today)
+      .execute(&db)
+      .await
+      .unwrap();
+    sqlx::query("INSERT INTO oura_resting_hr (date, resting_hr) VALUES (?1, 54)")
+      .bind(&today)
+      .execute(&db)
+      .await
+      .unwrap();
+    sqlx::query(
+      "INSERT INTO sync_state (source, access_token, refresh_token, token_expires_at)
+       VALUES ('strava', 'access', 'refresh', ?1)",
+    )
+    .bind(now + Duration::hours(6))
+    .execute(&db)
+    .await
+    .unwrap();
+
+    let dashboard = load_dashboard(&db).await;
+    assert!(dashboard.errors.is_empty(), "{:?}", dashboard.errors);
+
+    let analysis = dashboard.latest_analysis.unwrap();
+    assert_eq!((analysis.workout_id, analysis.summary.as_str()), (latest_id, "Tough tempo"));
+    assert!(dashboard.context.unwrap().tsb.unwrap() < -20.0);
+
+    assert!(dashboard.top_flags.len() <= DASHBOARD_TOP_FLAGS);
+    assert_eq!(dashboard.top_flags[0].name, "high_fatigue");
+    assert!(dashboard.progression_ready <= 2);
+
+    assert_eq!(dashboard.readiness.date, today);
+    assert_eq!(dashboard.readiness.oura.unwrap().resting_hr, Some(54));
+    assert_eq!(dashboard.readiness.daily_log.unwrap().soreness_1_10, Some(7));
+
+    assert!(dashboard.strava.unwrap().is_authenticated);
+    assert!(!dashboard.oura.unwrap().is_authenticated);
+  }
+}
@@ -0,0 +1,181 @@
+use crate::db::{AppState, StartupStatus};
+use serde::Serialize;
+use sqlx::sqlite::SqliteConnectOptions;
+use sqlx::{ConnectOptions, Connection};
+use std::path::Path;
+use std::sync::Arc;
+use tauri::State;
+
+/// ---------------------------------------------------------------------------
+/// Database Info and Backup
+/// ---------------------------------------------------------------------------
+
+/// Where the training database lives and how big it is
+#[derive(Debug, Serialize)]
+pub struct DatabaseInfo {
+  /// File path of the main database (None for an in-memory database)
+  pub path: Option<String>,
+  /// page_count x page_size
+  pub size_bytes: i64,
+  pub workout_count: i64,
+}
+
+/// Whether the database opened at startup, and why not if it didn't
+#[tauri::command]
+pub async fn get_startup_status(state: State<'_, Arc<AppState>>) -> Result<StartupStatus, String> {
+  Ok(state.startup_status())
+}
+
+/// Current database path and size
+#[tauri::command]
+pub async fn get_database_info(state: State<'_, Arc<AppState>>) -> Result<DatabaseInfo, String> {
+  load_database_info(&state.db)
+    .await
+    .map_err(|e| format!("Failed to read database info: {}", e))
+}
+
+/// Helper: Path, size and workout count via PRAGMAs, so it works for any pool
+pub(crate) async fn load_database_info(db: &crate::db::DbPool) -> Result<DatabaseInfo, sqlx::Error> {
+  // PRAGMA database_list: (seq, name, file); file is empty for in-memory
+  let databases: Vec<(i64, String, String)> = sqlx::query_as("PRAGMA database_list")
+    .fetch_all(db)
+    .await?;
+  let path = databases
+    .into_iter()
+    .find(|(_, name, _)| name == "main")
+    .map(|(_, _, file)| file)
+    .filter(|file| !file.is_empty());
+
+  let (page_count,): (i64,) = sqlx::query_as("PRAGMA page_count").fetch_one(db).await?;
+  let (page_size,): (i64,) = sqlx::query_as("PRAGMA page_size").fetch_one(db).await?;
+  let (workout_count,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM workouts").fetch_one(db).await?;
+
+  Ok(DatabaseInfo {
+    path,
+    size_bytes: page_count * page_size,
+    workout_count,
+  })
+}
+
+/// Write a consistent snapshot of the database to `dest_path`.
+/// VACUUM INTO runs inside a read transaction, so a sync in progress can't
+/// leave the copy half-written. The destination must not exist yet.
+#[tauri::command]
+pub async fn backup_database(
+  state: State<'_, Arc<AppState>>,
+  dest_path: String,
+) -> Result<DatabaseInfo, String> {
+  backup_database_to(&state.db, Path::new(&dest_path)).await
+}
+
+/// Helper: VACUUM INTO `dest`, then report on the backup file
+pub(crate) async fn backup_database_to(db: &crate::db::DbPool, dest: &Path) -> Result<DatabaseInfo, String> {
+  if dest.exists() {
+    return Err(format!("Backup destination already exists: {}", dest.display()));
+  }
+  let dest_str = dest
+    .to_str()
+    .ok_or_else(|| format!("Backup path is not valid UTF-8: {}", dest.display()))?;
+
+  sqlx::query("VACUUM INTO ?1")
+    .bind(dest_str)
+    .execute(db)
+    .await
+    .map_err(|e| format!("Failed to back up database: {}", e))?;
+
+  let size_bytes = std::fs::metadata(dest)
+    .map_err(|e| format!("Failed to read backup file: {}", e))?
+    .len() as i64;
+
+  // Count from the snapshot itself: the live database may have moved on
+  let mut backup = SqliteConnectOptions::new()
+    .filename(dest)
+    .read_only(true)
+    .connect()
+    .await
+    .map_err(|e| format!("Failed to open backup file: {}", e))?;
+  let (workout_count,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM workouts")
+    .fetch_one(&mut backup)
+    .await
+    .map_err(|e| format!("Failed to count workouts: {}", e))?;
+  backup
+    .close()
+    .await
+    .map_err(|e| format!("Failed to close backup file: {}", e))?;
+
+  Ok(DatabaseInfo {
+    path: Some(dest_str.to_string()),
+    size_bytes,
+    workout_count,
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use sqlx::sqlite::SqlitePoolOptions;
+
+  async fn count(db: &crate::db::DbPool, table: &str) -> i64 {
+    let (n,): (i64,) = sqlx::query_as(&format!("SELECT COUNT(*) FROM {}", table))
+      .fetch_one(db)
+      .await
+      .unwrap();
+    n
+  }
+
+  #[tokio::test]
+  async fn test_backup_is_openable_with_same_rows() {
+    // VACUUM INTO needs a real on-disk source, so don't use test_pool here
+    let dir = std::env::temp_dir();
+    let source = dir.join(format!("tempo-backup-source-{}.db", std::process::id()));
+    let dest = dir.join(format!("tempo-backup-test-{}.db", std::process::id()));
+    let _ = std::fs::remove_file(&source);
+    let _ = std::fs::remove_file(&dest);
+
+    let db = SqlitePoolOptions::new()
+      .max_connections(1)
+      .connect(&format!("sqlite://{}?mode=rwc", source.display()))
+      .await
+      .unwrap();
+    sqlx::migrate!("./migrations").run(&db).await.unwrap();
+    for i in 0..3 {
+      sqlx::query("INSERT INTO workouts (strava_id, activity_type, started_at) VALUES (?1, 'Run', '2024-12-10T07:00:00Z')")
+        .bind(format!("b{}", i))
+        .execute(&db)
+        .await
+        .unwrap();
+    }
+
+    let info = load_database_info(&db).await.unwrap();
+    assert!(info.path.is_some());
+    assert_eq!(info.workout_count, 3);
+    assert!(info.size_bytes > 0);
+    assert_eq!(load_database_info(&crate::db::test_pool().await).await.unwrap().path, None);
+
+    let backup = backup_database_to(&db, &dest).await.unwrap();
+    assert_eq!(backup.workout_count, 3);
+    assert!(backup.size_bytes > 0);
+
+    // Never overwrite an existing file
+    assert!(backup_database_to(&db, &dest).await.is_err());
+
+    let copy = SqlitePoolOptions::new()
+      .max_connections(1)
+      .connect(&format!("sqlite://{}?mode=ro", dest.display()))
+      .await
+      .unwrap();
+    let (integrity,): (String,) = sqlx::query_as("PRAGMA integrity_check").fetch_one(&copy).await.unwrap();
+    assert_eq!(integrity, "ok");
+    for table in ["workouts", "progression_dimensions", "_sqlx_migrations"] {
+      assert_eq!(count(&copy, table).await, count(&db, table).await, "{}", table);
+    }
+    let copy_info = load_database_info(&copy).await.unwrap();
+    assert!(copy_info.path.is_some());
+    assert_eq!(copy_info.workout_count, backup.workout_count);
+
+    copy.close().await;
+    db.close().await;
+    std::fs::remove_file(&dest).unwrap();
+    std::fs::remove_file(&source).unwrap();
+  }
+}
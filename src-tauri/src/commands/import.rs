@@ -0,0 +1,145 @@
+use crate::activity_file::parse_activity_file;
+use crate::commands::analysis::compute_pending_metrics;
+use crate::commands::strava::{mark_if_duplicate, save_activity_as, save_activity_samples};
+use crate::db::AppState;
+use crate::strava::{downsample_streams, SAMPLE_INTERVAL_SECONDS};
+use serde::Serialize;
+use std::path::Path;
+use std::sync::Arc;
+use tauri::State;
+
+/// ---------------------------------------------------------------------------
+/// Activity File Import
+/// ---------------------------------------------------------------------------
+
+#[derive(Debug, Serialize)]
+pub struct ImportResult {
+  /// workouts.id of the imported (or previously imported) workout
+  pub workout_id: i64,
+  /// False when this file was already imported
+  pub inserted: bool,
+  pub activity_type: String,
+  pub hr_samples: usize,
+  /// The same session was already stored (e.g. synced from Strava), so
+  /// this copy is left out of load
+  pub duplicate_of: Option<i64>,
+}
+
+/// Import a workout recorded outside Strava from a GPX file
+#[tauri::command]
+pub async fn import_activity_file(
+  state: State<'_, Arc<AppState>>,
+  path: String,
+) -> Result<ImportResult, String> {
+  import_activity_file_at(&state.db, Path::new(&path)).await
+}
+
+/// Helper: Parse, store, downsample and compute metrics for one file
+pub(crate) async fn import_activity_file_at(db: &crate::db::DbPool, path: &Path) -> Result<ImportResult, String> {
+  let contents = std::fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+  let parsed = parse_activity_file(path, &contents)?;
+  let source_id = parsed.source_id.as_str();
+
+  let inserted = save_activity_as(db, source_id, &parsed.activity)
+    .await
+    .map_err(|e| format!("Failed to store activity: {}", e))?;
+
+  let mut hr_samples = 0;
+  let mut duplicate_of = None;
+  if inserted {
+    duplicate_of = mark_if_duplicate(db, source_id)
+      .await
+      .map_err(|e| format!("Failed to check for duplicates: {}", e))?;
+
+    let samples = downsample_streams(&parsed.streams, SAMPLE_INTERVAL_SECONDS);
+    hr_samples = samples.hr.len();
+    save_activity_samples(db, source_id, &samples)
+      .await
+      .map_err(|e| format!("Failed to store samples: {}", e))?;
+
+    compute_pending_metrics(db).await?;
+  }
+
+  let workout_id: i64 = sqlx::query_scalar("SELECT id FROM workouts WHERE strava_id = ?1")
+    .bind(source_id)
+    .fetch_one(db)
+    .await
+    .map_err(|e| format!("Failed to load imported workout: {}", e))?;
+
+  println!(
+    "Imported {} as workout {} ({})",
+    path.display(),
+    workout_id,
+    if inserted { "new" } else { "already imported" }
+  );
+
+  Ok(ImportResult {
+    workout_id,
+    inserted,
+    activity_type: parsed.activity.activity_type,
+    hr_samples,
+    duplicate_of,
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn gpx_with_hr(points: usize) -> String {
+    let trackpoints: String = (0..points)
+      .map(|i| {
+        format!(
+          r#"<trkpt lat="{:.6}" lon="8.0"><time>{}</time><extensions><gpxtpx:TrackPointExtension><gpxtpx:hr>{}</gpxtpx:hr></gpxtpx:TrackPointExtension></extensions></trkpt>"#,
+          47.0 + i as f64 * 0.00003,
+          (chrono::DateTime::parse_from_rfc3339("2024-12-10T07:00:00Z").unwrap() + chrono::Duration::seconds(i as i64))
+            .to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+          140 + (i % 10)
+        )
+      })
+      .collect();
+    format!(
+      r#"<?xml version="1.0"?><gpx xmlns:gpxtpx="http://www.garmin.com/xmlschemas/TrackPointExtension/v1"><trk><name>Easy run</name><type>running</type><trkseg>{}</trkseg></trk></gpx>"#,
+      trackpoints
+    )
+  }
+
+  #[tokio::test]
+  async fn test_import_gpx_stores_workout_and_samples() {
+    let db = crate::db::test_pool().await;
+    let path = std::env::temp_dir().join(format!("tempo-import-test-{}.gpx", std::process::id()));
+    std::fs::write(&path, gpx_with_hr(600)).unwrap();
+
+    let result = import_activity_file_at(&db, &path).await.unwrap();
+    assert!(result.inserted);
+    assert_eq!(result.activity_type, "Run");
+    assert_eq!(result.hr_samples, 60);
+    assert_eq!(result.duplicate_of, None);
+
+    let (strava_id, duration, avg_hr, metrics_at): (String, i64, i64, Option<String>) =
+      sqlx::query_as(
+        "SELECT strava_id, duration_seconds, average_heartrate, metrics_computed_at FROM workouts WHERE id = ?1",
+      )
+      .bind(result.workout_id)
+      .fetch_one(&db)
+      .await
+      .unwrap();
+    assert!(strava_id.starts_with("file_"));
+    assert_eq!(duration, 599);
+    assert_eq!(avg_hr, 144);
+    let samples = crate::commands::analysis::load_workout_samples(&db, result.workout_id)
+      .await
+      .unwrap()
+      .unwrap();
+    assert_eq!(samples.hr.len(), 60);
+    assert!(!samples.pace.is_empty());
+    assert!(metrics_at.is_some());
+
+    // Importing the same file again is a no-op
+    let again = import_activity_file_at(&db, &path).await.unwrap();
+    assert!(!again.inserted);
+    assert_eq!(again.workout_id, result.workout_id);
+
+    std::fs::remove_file(&path).unwrap();
+  }
+}
@@ -1,7 +1,13 @@
 pub mod analysis;
+pub mod dashboard;
+pub mod database;
+pub mod import;
+pub mod plan;
 pub mod progression;
+pub mod review;
 pub mod strava;
 pub mod oura;
+pub mod sync;
 
 use crate::db::AppState;
 use crate::models::{Workout, SyncState};
@@ -1,10 +1,11 @@
 use crate::db::AppState;
 use crate::oura::{
-  build_auth_url, exchange_code_for_tokens, refresh_tokens, wait_for_callback,
-  OuraConfig, OuraTokens,
+  build_auth_url, exchange_code_for_tokens, parse_oura_csv, refresh_tokens, wait_for_callback,
+  OuraConfig, OuraContext, OuraCsvDay, OuraDay, OuraFetch, OuraTokens, BASELINE_DAYS, DEFAULT_CALLBACK_TIMEOUT_SECONDS,
 };
 use chrono::Utc;
 use serde::Serialize;
+use std::future::Future;
 use std::sync::Arc;
 use tauri::State;
 
@@ -29,13 +30,18 @@ pub async fn oura_start_auth() -> Result<String, String> {
 
 /// Waits for the OAuth callback, exchanges the code for tokens, and stores them.
 /// This should be called immediately after oura_start_auth.
+/// `timeout_seconds` bounds the wait (defaults to 120s).
 #[tauri::command]
-pub async fn oura_complete_auth(state: State<'_, Arc<AppState>>) -> Result<(), String> {
+pub async fn oura_complete_auth(
+  state: State<'_, Arc<AppState>>,
+  timeout_seconds: Option<u64>,
+) -> Result<(), String> {
   let config = OuraConfig::from_env()
     .map_err(|e| e.to_string())?;
+  let timeout = timeout_seconds.unwrap_or(DEFAULT_CALLBACK_TIMEOUT_SECONDS);
 
   // Wait for callback (blocking - runs in Tauri's async runtime)
-  let callback = tokio::task::spawn_blocking(|| wait_for_callback())
+  let callback = tokio::task::spawn_blocking(move || wait_for_callback(timeout))
     .await
     .map_err(|e| e.to_string())?
     .map_err(|e| e.to_string())?;
@@ -56,7 +62,7 @@ pub async fn oura_complete_auth(state: State<'_, Arc<AppState>>) -> Result<(), S
 /// Check Authentication Status
 /// ---------------------------------------------------------------------------
 
-#[derive(Serialize)]
+#[derive(Debug, Serialize)]
 pub struct OuraAuthStatus {
   pub is_authenticated: bool,
   pub expires_at: Option<String>,
@@ -67,7 +73,12 @@ pub struct OuraAuthStatus {
 pub async fn oura_get_auth_status(
   state: State<'_, Arc<AppState>>,
 ) -> Result<OuraAuthStatus, String> {
-  match load_tokens(&state.db).await.map_err(|e| e.to_string())? {
+  load_auth_status(&state.db).await
+}
+
+/// Helper: Whether Oura tokens are stored, and when they expire
+pub(crate) async fn load_auth_status(db: &crate::db::DbPool) -> Result<OuraAuthStatus, String> {
+  match load_tokens(db).await.map_err(|e| e.to_string())? {
     Some(tokens) => Ok(OuraAuthStatus {
       is_authenticated: true,
       expires_at: Some(tokens.expires_at.to_rfc3339()),
@@ -242,56 +253,267 @@ async fn save_resting_hr_data(
   Ok(())
 }
 
+/// Helper: Get (or create) the merged day for a stored "YYYY-MM-DD" date
+fn oura_day_entry<'a>(
+  days: &'a mut std::collections::BTreeMap<chrono::NaiveDate, OuraDay>,
+  raw_date: &str,
+) -> Option<&'a mut OuraDay> {
+  let date = chrono::NaiveDate::parse_from_str(raw_date, "%Y-%m-%d").ok()?;
+  Some(days.entry(date).or_insert_with(|| OuraDay {
+    date,
+    ..Default::default()
+  }))
+}
+
+/// Load the Oura context for a given day (the workout's date) with its
+/// 7-day baseline. None when there's no Oura data in that window.
+pub(crate) async fn load_oura_context(
+  db: &crate::db::DbPool,
+  date: chrono::NaiveDate,
+  sleep_target_hours: f64,
+) -> Result<Option<OuraContext>, String> {
+  let start = (date - chrono::Duration::days(BASELINE_DAYS)).format("%Y-%m-%d").to_string();
+  let end = date.format("%Y-%m-%d").to_string();
+
+  let sleep: Vec<(String, Option<i64>, Option<i64>, Option<i64>, Option<i64>)> = sqlx::query_as(
+    r#"
+    SELECT date, total_sleep_seconds, deep_sleep_seconds, rem_sleep_seconds, efficiency_pct
+    FROM oura_sleep
+    WHERE date BETWEEN ?1 AND ?2
+    "#,
+  )
+  .bind(&start)
+  .bind(&end)
+  .fetch_all(db)
+  .await
+  .map_err(|e| format!("Failed to load sleep data: {}", e))?;
+
+  let hrv: Vec<(String, f64)> =
+    sqlx::query_as("SELECT date, average_hrv_ms FROM oura_hrv WHERE date BETWEEN ?1 AND ?2")
+      .bind(&start)
+      .bind(&end)
+      .fetch_all(db)
+      .await
+      .map_err(|e| format!("Failed to load HRV data: {}", e))?;
+
+  let resting_hr: Vec<(String, i64)> =
+    sqlx::query_as("SELECT date, resting_hr FROM oura_resting_hr WHERE date BETWEEN ?1 AND ?2")
+      .bind(&start)
+      .bind(&end)
+      .fetch_all(db)
+      .await
+      .map_err(|e| format!("Failed to load resting HR data: {}", e))?;
+
+  let mut days: std::collections::BTreeMap<chrono::NaiveDate, OuraDay> =
+    std::collections::BTreeMap::new();
+  for (raw, total, deep, rem, efficiency) in sleep {
+    if let Some(day) = oura_day_entry(&mut days, &raw) {
+      day.total_sleep_seconds = total;
+      day.deep_sleep_seconds = deep;
+      day.rem_sleep_seconds = rem;
+      day.efficiency_pct = efficiency;
+    }
+  }
+  for (raw, hrv_ms) in hrv {
+    if let Some(day) = oura_day_entry(&mut days, &raw) {
+      day.hrv_ms = Some(hrv_ms);
+    }
+  }
+  for (raw, rhr) in resting_hr {
+    if let Some(day) = oura_day_entry(&mut days, &raw) {
+      day.resting_hr = Some(rhr);
+    }
+  }
+
+  if days.is_empty() {
+    return Ok(None);
+  }
+
+  let days: Vec<OuraDay> = days.into_values().collect();
+  Ok(Some(OuraContext::for_date(date, &days, sleep_target_hours)))
+}
+
+/// Helper: Average resting HR over the most recent BASELINE_DAYS nights
+/// stored (None without Oura data)
+pub(crate) async fn load_resting_hr_baseline(db: &crate::db::DbPool) -> Result<Option<i64>, String> {
+  let average: Option<f64> = sqlx::query_scalar(
+    "SELECT AVG(resting_hr) FROM (SELECT resting_hr FROM oura_resting_hr ORDER BY date DESC LIMIT ?1)",
+  )
+  .bind(BASELINE_DAYS)
+  .fetch_one(db)
+  .await
+  .map_err(|e| format!("Failed to load resting HR baseline: {}", e))?;
+
+  Ok(average.map(|bpm| bpm.round() as i64))
+}
+
 /// ---------------------------------------------------------------------------
 /// Oura Data Sync Command
 /// ---------------------------------------------------------------------------
 
+/// Days fetched on a first sync (today included)
+const INITIAL_SYNC_DAYS: i64 = 7;
+
+/// Most recent days always re-fetched: Oura revises them as sleep and
+/// readiness are finalized
+const RECENT_REFETCH_DAYS: i64 = 2;
+
+/// Days per request window; progress is recorded after each one
+const SYNC_CHUNK_DAYS: i64 = 7;
+
+/// Most days fetched in one sync after a long gap
+const MAX_SYNC_DAYS: i64 = 90;
+
 #[derive(Serialize)]
 pub struct OuraSyncResult {
   pub sleep_records: usize,
   pub hrv_records: usize,
   pub resting_hr_records: usize,
+  /// Last date fully stored (the next sync resumes after it)
+  pub synced_through: Option<String>,
 }
 
 #[tauri::command]
 pub async fn oura_sync_data(
   state: State<'_, Arc<AppState>>,
 ) -> Result<OuraSyncResult, String> {
-  use crate::oura::{fetch_daily_readiness, fetch_daily_sleep, fetch_sleep_periods, OuraConfig};
-  use chrono::Local;
-
-  let config = OuraConfig::from_env().map_err(|e| e.to_string())?;
+  sync_oura(&state.db).await
+}
 
+/// Helper: Sync Oura data since the last fully stored date (shared with sync_all)
+pub(crate) async fn sync_oura(db: &crate::db::DbPool) -> Result<OuraSyncResult, String> {
   // Load tokens from database
-  let mut tokens = load_tokens(&state.db)
+  let mut tokens = load_tokens(db)
     .await?
     .ok_or_else(|| "Not connected to Oura".to_string())?;
 
   // Refresh tokens if needed
   if tokens.needs_refresh() {
+    let config = OuraConfig::from_env().map_err(|e| e.to_string())?;
     tokens = crate::oura::refresh_tokens(&config, &tokens.refresh_token)
       .await
       .map_err(|e| e.to_string())?;
-    save_tokens(&state.db, &tokens).await?;
+    save_tokens(db, &tokens).await?;
+  }
+
+  // Oura dates follow the athlete's configured local day
+  let today = crate::commands::analysis::load_user_settings(db).await?.local_date(&Utc::now());
+  let access_token = tokens.access_token;
+  sync_oura_with(db, today, move |start, end| {
+    let token = access_token.clone();
+    // The three endpoints are independent; fetch them concurrently
+    async move { crate::oura::fetch_all(&token, &start, &end).await }
+  })
+  .await
+}
+
+/// Sync from the day after `last_synced_date` (or the initial window) to
+/// `today`, always including the last RECENT_REFETCH_DAYS. Windows of
+/// SYNC_CHUNK_DAYS are fetched oldest first; after each one whose three
+/// endpoints all succeeded, `last_synced_date` advances to its end. A failed
+/// window keeps what it got and stops, so the next sync resumes there.
+pub(crate) async fn sync_oura_with<F, Fut>(
+  db: &crate::db::DbPool,
+  today: chrono::NaiveDate,
+  fetch: F,
+) -> Result<OuraSyncResult, String>
+where
+  F: Fn(String, String) -> Fut,
+  Fut: Future<Output = OuraFetch>,
+{
+  let last_synced = load_last_synced_date(db).await?;
+  let start_date = oura_sync_start(last_synced, today);
+
+  let mut result = OuraSyncResult {
+    sleep_records: 0,
+    hrv_records: 0,
+    resting_hr_records: 0,
+    synced_through: last_synced.map(|d| d.format("%Y-%m-%d").to_string()),
+  };
+
+  let mut chunk_start = start_date;
+  while chunk_start <= today {
+    let chunk_end = (chunk_start + chrono::Duration::days(SYNC_CHUNK_DAYS - 1)).min(today);
+    let start_str = chunk_start.format("%Y-%m-%d").to_string();
+    let end_str = chunk_end.format("%Y-%m-%d").to_string();
+    println!("Syncing Oura data from {} to {}", start_str, end_str);
+
+    let fetched = fetch(start_str, end_str.clone()).await;
+    let complete = fetched.sleep.is_ok() && fetched.periods.is_ok() && fetched.readiness.is_ok();
+    let saved = save_oura_fetch(db, fetched).await?;
+    result.sleep_records += saved.sleep_records;
+    result.hrv_records += saved.hrv_records;
+    result.resting_hr_records += saved.resting_hr_records;
+
+    if !complete {
+      eprintln!("Oura sync stopped at {}; the next sync resumes there", chunk_start);
+      break;
+    }
+    // Never move backwards: re-fetched recent days are already covered
+    if last_synced.map_or(true, |last| chunk_end > last) {
+      save_last_synced_date(db, chunk_end).await?;
+      result.synced_through = Some(end_str);
+    }
+    chunk_start = chunk_end + chrono::Duration::days(1);
   }
 
-  // Calculate date range (last 7 days)
-  let end_date = Local::now().naive_local().date();
-  let start_date = end_date - chrono::Duration::days(7);
-  let start_str = start_date.format("%Y-%m-%d").to_string();
-  let end_str = end_date.format("%Y-%m-%d").to_string();
+  Ok(result)
+}
+
+/// First date to fetch: the day after the last stored one, pulled back to
+/// cover the recent days Oura may still revise, and capped at MAX_SYNC_DAYS
+fn oura_sync_start(last_synced: Option<chrono::NaiveDate>, today: chrono::NaiveDate) -> chrono::NaiveDate {
+  let recent = today - chrono::Duration::days(RECENT_REFETCH_DAYS - 1);
+  let start = match last_synced {
+    Some(last) => (last + chrono::Duration::days(1)).min(recent),
+    None => today - chrono::Duration::days(INITIAL_SYNC_DAYS - 1),
+  };
+  start.max(today - chrono::Duration::days(MAX_SYNC_DAYS))
+}
+
+async fn load_last_synced_date(db: &crate::db::DbPool) -> Result<Option<chrono::NaiveDate>, String> {
+  let date: Option<Option<String>> =
+    sqlx::query_scalar("SELECT last_synced_date FROM sync_state WHERE source = 'oura'")
+      .fetch_optional(db)
+      .await
+      .map_err(|e| format!("Failed to load Oura sync state: {}", e))?;
+
+  Ok(date.flatten().and_then(|d| chrono::NaiveDate::parse_from_str(&d, "%Y-%m-%d").ok()))
+}
+
+async fn save_last_synced_date(db: &crate::db::DbPool, date: chrono::NaiveDate) -> Result<(), String> {
+  sqlx::query(
+    r#"
+    INSERT INTO sync_state (source, last_synced_date, last_sync_at)
+    VALUES ('oura', ?1, CURRENT_TIMESTAMP)
+    ON CONFLICT(source) DO UPDATE SET
+      last_synced_date = excluded.last_synced_date,
+      last_sync_at = excluded.last_sync_at
+    "#,
+  )
+  .bind(date.format("%Y-%m-%d").to_string())
+  .execute(db)
+  .await
+  .map_err(|e| format!("Failed to save Oura sync state: {}", e))?;
 
-  println!("Syncing Oura data from {} to {}", start_str, end_str);
+  Ok(())
+}
 
+/// Helper: Store whatever each endpoint returned. A failed endpoint is
+/// logged and skipped so the others still save.
+async fn save_oura_fetch(
+  db: &crate::db::DbPool,
+  fetched: OuraFetch,
+) -> Result<OuraSyncResult, String> {
   let mut sleep_count = 0;
   let mut hrv_count = 0;
   let mut resting_hr_count = 0;
 
-  // Fetch daily sleep data
-  match fetch_daily_sleep(&tokens.access_token, &start_str, &end_str).await {
+  // Daily sleep data
+  match fetched.sleep {
     Ok(response) => {
       for sleep_data in response.data {
-        save_sleep_data(&state.db, &sleep_data.day, &sleep_data).await?;
+        save_sleep_data(db, &sleep_data.day, &sleep_data).await?;
         sleep_count += 1;
       }
       println!("Saved {} sleep records", sleep_count);
@@ -301,8 +523,8 @@ pub async fn oura_sync_data(
     }
   }
 
-  // Fetch sleep periods for HRV data
-  match fetch_sleep_periods(&tokens.access_token, &start_str, &end_str).await {
+  // Sleep periods for HRV data
+  match fetched.periods {
     Ok(response) => {
       // Group periods by date and average HRV for each day
       let mut hrv_by_date: std::collections::HashMap<String, Vec<f64>> =
@@ -322,7 +544,7 @@ pub async fn oura_sync_data(
       for (date, hrv_values) in hrv_by_date {
         if !hrv_values.is_empty() {
           let avg_hrv = hrv_values.iter().sum::<f64>() / hrv_values.len() as f64;
-          save_hrv_data(&state.db, &date, avg_hrv).await?;
+          save_hrv_data(db, &date, avg_hrv).await?;
           hrv_count += 1;
         }
       }
@@ -333,12 +555,12 @@ pub async fn oura_sync_data(
     }
   }
 
-  // Fetch daily readiness for resting HR
-  match fetch_daily_readiness(&tokens.access_token, &start_str, &end_str).await {
+  // Daily readiness for resting HR
+  match fetched.readiness {
     Ok(response) => {
       for readiness_data in response.data {
         if let Some(resting_hr) = readiness_data.contributors.resting_heart_rate {
-          save_resting_hr_data(&state.db, &readiness_data.day, resting_hr).await?;
+          save_resting_hr_data(db, &readiness_data.day, resting_hr).await?;
           resting_hr_count += 1;
         }
       }
@@ -353,5 +575,390 @@ pub async fn oura_sync_data(
     sleep_records: sleep_count,
     hrv_records: hrv_count,
     resting_hr_records: resting_hr_count,
+    synced_through: None,
   })
 }
+
+/// ---------------------------------------------------------------------------
+/// Historical CSV Import
+/// ---------------------------------------------------------------------------
+
+#[derive(Debug, Serialize)]
+pub struct OuraImportResult {
+  pub sleep_records: usize,
+  pub hrv_records: usize,
+  pub resting_hr_records: usize,
+  /// "line N: reason" for each row that couldn't be imported
+  pub skipped: Vec<String>,
+}
+
+/// Bulk-import an Oura CSV export (sleep, HRV, resting HR) so baselines
+/// have history without a slow API backfill
+#[tauri::command]
+pub async fn import_oura_csv(
+  state: State<'_, Arc<AppState>>,
+  path: String,
+) -> Result<OuraImportResult, String> {
+  let text = std::fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+  import_oura_csv_text(&state.db, &text).await
+}
+
+/// Helper: Parse and upsert an export in one transaction. Empty cells keep
+/// whatever is already stored for that day.
+pub(crate) async fn import_oura_csv_text(
+  db: &crate::db::DbPool,
+  text: &str,
+) -> Result<OuraImportResult, String> {
+  let import = parse_oura_csv(text)?;
+  let mut tx = db
+    .begin()
+    .await
+    .map_err(|e| format!("Failed to start import: {}", e))?;
+
+  let mut result = OuraImportResult {
+    sleep_records: 0,
+    hrv_records: 0,
+    resting_hr_records: 0,
+    skipped: import.skipped,
+  };
+
+  for day in &import.days {
+    if day.has_sleep() {
+      upsert_csv_sleep(&mut *tx, day).await?;
+      result.sleep_records += 1;
+    }
+    if let Some(hrv_ms) = day.hrv_ms {
+      sqlx::query(
+        "INSERT INTO oura_hrv (date, average_hrv_ms) VALUES (?1, ?2)
+         ON CONFLICT(date) DO UPDATE SET average_hrv_ms = excluded.average_hrv_ms",
+      )
+      .bind(&day.date)
+      .bind(hrv_ms)
+      .execute(&mut *tx)
+      .await
+      .map_err(|e| format!("Failed to save HRV data: {}", e))?;
+      result.hrv_records += 1;
+    }
+    if let Some(resting_hr) = day.resting_hr {
+      sqlx::query(
+        "INSERT INTO oura_resting_hr (date, resting_hr) VALUES (?1, ?2)
+         ON CONFLICT(date) DO UPDATE SET resting_hr = excluded.resting_hr",
+      )
+      .bind(&day.date)
+      .bind(resting_hr)
+      .execute(&mut *tx)
+      .await
+      .map_err(|e| format!("Failed to save resting HR data: {}", e))?;
+      result.resting_hr_records += 1;
+    }
+  }
+
+  tx.commit()
+    .await
+    .map_err(|e| format!("Failed to commit import: {}", e))?;
+
+  println!(
+    "Imported Oura CSV: {} sleep, {} HRV, {} resting HR, {} skipped",
+    result.sleep_records,
+    result.hrv_records,
+    result.resting_hr_records,
+    result.skipped.len()
+  );
+  Ok(result)
+}
+
+/// Helper: Upsert one imported night without blanking stored stages
+async fn upsert_csv_sleep<'e, E>(executor: E, day: &OuraCsvDay) -> Result<(), String>
+where
+  E: sqlx::Executor<'e, Database = sqlx::Sqlite>,
+{
+  sqlx::query(
+    r#"
+    INSERT INTO oura_sleep (
+      date, total_sleep_seconds, deep_sleep_seconds,
+      rem_sleep_seconds, light_sleep_seconds, efficiency_pct
+    )
+    VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+    ON CONFLICT(date) DO UPDATE SET
+      total_sleep_seconds = COALESCE(excluded.total_sleep_seconds, total_sleep_seconds),
+      deep_sleep_seconds = COALESCE(excluded.deep_sleep_seconds, deep_sleep_seconds),
+      rem_sleep_seconds = COALESCE(excluded.rem_sleep_seconds, rem_sleep_seconds),
+      light_sleep_seconds = COALESCE(excluded.light_sleep_seconds, light_sleep_seconds),
+      efficiency_pct = COALESCE(excluded.efficiency_pct, efficiency_pct)
+    "#,
+  )
+  .bind(&day.date)
+  .bind(day.total_sleep_seconds)
+  .bind(day.deep_sleep_seconds)
+  .bind(day.rem_sleep_seconds)
+  .bind(day.light_sleep_seconds)
+  .bind(day.efficiency_pct)
+  .execute(executor)
+  .await
+  .map_err(|e| format!("Failed to save sleep data: {}", e))?;
+
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::analysis::DEFAULT_SLEEP_TARGET_HOURS;
+
+  #[tokio::test]
+  async fn test_load_oura_context_for_workout_date() {
+    let db = crate::db::test_pool().await;
+
+    for (date, rhr) in [
+      ("2024-12-03", 50),
+      ("2024-12-05", 51),
+      ("2024-12-07", 49),
+      ("2024-12-09", 50),
+      ("2024-12-10", 57),
+      ("2024-12-11", 50),
+    ] {
+      save_resting_hr_data(&db, date, rhr).await.unwrap();
+    }
+    save_hrv_data(&db, "2024-12-10", 42.0).await.unwrap();
+
+    let date = chrono::NaiveDate::from_ymd_opt(2024, 12, 10).unwrap();
+    let context = load_oura_context(&db, date, DEFAULT_SLEEP_TARGET_HOURS).await.unwrap().unwrap();
+    assert_eq!(context.resting_hr, Some(57));
+    assert_eq!(context.resting_hr_avg_7d, Some(50));
+    assert!(context.rhr_elevated);
+    assert_eq!(context.hrv_last_night, Some(42.0));
+
+    // No data in the window -> no context at all
+    let empty = chrono::NaiveDate::from_ymd_opt(2025, 6, 1).unwrap();
+    assert!(load_oura_context(&db, empty, DEFAULT_SLEEP_TARGET_HOURS).await.unwrap().is_none());
+  }
+
+  /// Minimal HTTP server answering `count` requests: paths in `failing`
+  /// get a 500, everything else the canned body for its endpoint. Returns
+  /// the base URL and the request paths it saw.
+  fn spawn_mock_oura(
+    count: usize,
+    failing: &'static str,
+  ) -> (String, std::sync::Arc<std::sync::Mutex<Vec<String>>>) {
+    use std::io::{BufRead, BufReader, Write};
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let base = format!("http://{}", listener.local_addr().unwrap());
+    let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let seen_by_server = seen.clone();
+
+    std::thread::spawn(move || {
+      for stream in listener.incoming().take(count) {
+        let mut stream = stream.unwrap();
+        let mut request_line = String::new();
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        reader.read_line(&mut request_line).unwrap();
+        // Drain headers
+        let mut line = String::new();
+        while reader.read_line(&mut line).unwrap() > 2 {
+          line.clear();
+        }
+
+        let path = request_line.split_whitespace().nth(1).unwrap_or("").to_string();
+        let endpoint = path.split('?').next().unwrap_or("").to_string();
+        seen_by_server.lock().unwrap().push(endpoint.clone());
+
+        let body = match endpoint.as_str() {
+          "/daily_sleep" => r#"{"data":[{"day":"2024-12-10","contributors":{"total_sleep":27000,"deep_sleep":5400}}]}"#,
+          "/sleep" => r#"{"data":[{"bedtime_start":"2024-12-10T00:15:00+00:00","bedtime_end":"2024-12-10T06:30:00+00:00","average_hrv":48.0}]}"#,
+          _ => r#"{"data":[{"day":"2024-12-10","contributors":{"resting_heart_rate":52}}]}"#,
+        };
+        let status = if endpoint == failing { "500 Internal Server Error" } else { "200 OK" };
+        let body = if endpoint == failing { "{}" } else { body };
+        write!(
+          stream,
+          "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+          status,
+          body.len(),
+          body
+        )
+        .unwrap();
+      }
+    });
+
+    (base, seen)
+  }
+
+  #[tokio::test]
+  async fn test_sync_fetches_all_endpoints_and_saves_partial_results() {
+    let db = crate::db::test_pool().await;
+    let (base, seen) = spawn_mock_oura(3, "/daily_readiness");
+
+    let fetched = crate::oura::fetch_all_from(&base, "token", "2024-12-03", "2024-12-10").await;
+    assert!(fetched.readiness.is_err());
+
+    let result = save_oura_fetch(&db, fetched).await.unwrap();
+    assert_eq!(result.sleep_records, 1);
+    assert_eq!(result.hrv_records, 1);
+    assert_eq!(result.resting_hr_records, 0);
+
+    let mut endpoints = seen.lock().unwrap().clone();
+    endpoints.sort();
+    assert_eq!(endpoints, vec!["/daily_readiness", "/daily_sleep", "/sleep"]);
+
+    let date = chrono::NaiveDate::from_ymd_opt(2024, 12, 10).unwrap();
+    let context = load_oura_context(&db, date, DEFAULT_SLEEP_TARGET_HOURS).await.unwrap().unwrap();
+    assert_eq!(context.sleep_duration_hours, Some(7.5));
+    assert_eq!(context.hrv_last_night, Some(48.0));
+    assert_eq!(context.resting_hr, None);
+  }
+
+  /// Canned fetch for a window: one night of sleep and resting HR on its
+  /// last day (readiness failing when `fail_readiness`)
+  fn canned_fetch(end: &str, fail_readiness: bool) -> OuraFetch {
+    let sleep = serde_json::json!({"data": [{"day": end, "contributors": {"total_sleep": 27000}}]});
+    let readiness = serde_json::json!({"data": [{"day": end, "contributors": {"resting_heart_rate": 52}}]});
+    OuraFetch {
+      sleep: Ok(serde_json::from_value(sleep).unwrap()),
+      periods: Ok(serde_json::from_value(serde_json::json!({"data": []})).unwrap()),
+      readiness: if fail_readiness {
+        Err(crate::oura::OuraError::Request("timeout".to_string()))
+      } else {
+        Ok(serde_json::from_value(readiness).unwrap())
+      },
+    }
+  }
+
+  #[tokio::test]
+  async fn test_second_sync_fetches_only_new_and_recent_dates() {
+    let db = crate::db::test_pool().await;
+    let requested = std::sync::Mutex::new(Vec::new());
+    let sync = |today: &str, fail_readiness: bool| {
+      let today = chrono::NaiveDate::parse_from_str(today, "%Y-%m-%d").unwrap();
+      let requested = &requested;
+      sync_oura_with(&db, today, move |start: String, end: String| {
+        requested.lock().unwrap().push((start, end.clone()));
+        async move { canned_fetch(&end, fail_readiness) }
+      })
+    };
+    let take = || std::mem::take(&mut *requested.lock().unwrap());
+    let range = |start: &str, end: &str| (start.to_string(), end.to_string());
+
+    // First sync: the initial week
+    let first = sync("2024-12-10", false).await.unwrap();
+    assert_eq!(take(), vec![range("2024-12-04", "2024-12-10")]);
+    assert_eq!(first.synced_through.as_deref(), Some("2024-12-10"));
+
+    // Two days later: only the new days (yesterday included for revisions)
+    sync("2024-12-12", false).await.unwrap();
+    assert_eq!(take(), vec![range("2024-12-11", "2024-12-12")]);
+
+    // Same day again: just the recent days Oura may still revise
+    sync("2024-12-12", false).await.unwrap();
+    assert_eq!(take(), vec![range("2024-12-11", "2024-12-12")]);
+
+    // A failed window keeps its data but doesn't advance; the next sync resumes there
+    let failed = sync("2024-12-20", true).await.unwrap();
+    assert_eq!(take(), vec![range("2024-12-13", "2024-12-19")]);
+    assert_eq!(failed.synced_through.as_deref(), Some("2024-12-12"));
+    assert_eq!(failed.sleep_records, 1);
+
+    let resumed = sync("2024-12-20", false).await.unwrap();
+    assert_eq!(take(), vec![range("2024-12-13", "2024-12-19"), range("2024-12-20", "2024-12-20")]);
+    assert_eq!(resumed.synced_through.as_deref(), Some("2024-12-20"));
+  }
+
+  #[tokio::test]
+  async fn test_sleep_target_setting_changes_debt() {
+    let db = crate::db::test_pool().await;
+    // Seven nights of 7.2 hours before the workout date
+    for day in 3..=9 {
+      sqlx::query("INSERT INTO oura_sleep (date, total_sleep_seconds) VALUES (?1, ?2)")
+        .bind(format!("2024-12-{:02}", day))
+        .bind(7 * 3600 + 12 * 60)
+        .execute(&db)
+        .await
+        .unwrap();
+    }
+    sqlx::query("UPDATE user_settings SET sleep_target_hours = 7.5 WHERE id = 1")
+      .execute(&db)
+      .await
+      .unwrap();
+    let settings = crate::commands::analysis::load_user_settings(&db).await.unwrap();
+    assert_eq!(settings.sleep_target_hours, 7.5);
+
+    let date = chrono::NaiveDate::from_ymd_opt(2024, 12, 10).unwrap();
+    let default_debt = load_oura_context(&db, date, DEFAULT_SLEEP_TARGET_HOURS)
+      .await
+      .unwrap()
+      .unwrap()
+      .sleep_debt_hours
+      .unwrap();
+    let custom_debt = load_oura_context(&db, date, settings.sleep_target_hours)
+      .await
+      .unwrap()
+      .unwrap()
+      .sleep_debt_hours
+      .unwrap();
+    assert!((default_debt - 5.6).abs() < 1e-9);
+    assert!((custom_debt - 2.1).abs() < 1e-9);
+  }
+
+  #[tokio::test]
+  async fn test_import_oura_csv_lands_rows_and_reports_skips() {
+    let db = crate::db::test_pool().await;
+    // A night already synced from the API keeps its stages when the CSV is blank
+    sqlx::query("INSERT INTO oura_sleep (date, total_sleep_seconds, deep_sleep_seconds) VALUES ('2024-03-02', 25000, 5000)")
+      .execute(&db)
+      .await
+      .unwrap();
+
+    let csv = "\
+date,Total Sleep Duration,Deep Sleep Duration,REM Sleep Duration,Sleep Efficiency,Average HRV,Lowest Resting Heart Rate
+2024-03-01,27000,5400,6300,88,52.5,48
+2024-03-02,26400,,,,49,
+not-a-date,27000,5400,6300,88,52,48
+2024-03-04,27000,abc,6300,88,52,48
+2024-03-05,,,,,,
+2024-03-06,,,,,55,50
+";
+    let result = import_oura_csv_text(&db, csv).await.unwrap();
+    assert_eq!((result.sleep_records, result.hrv_records, result.resting_hr_records), (2, 3, 2));
+    assert_eq!(
+      result.skipped,
+      vec![
+        "line 4: invalid date 'not-a-date'".to_string(),
+        "line 5: invalid value 'abc'".to_string(),
+        "line 6: no data".to_string(),
+      ]
+    );
+
+    let sleep: Vec<(String, Option<i64>, Option<i64>, Option<i64>, Option<i64>)> = sqlx::query_as(
+      "SELECT date, total_sleep_seconds, deep_sleep_seconds, rem_sleep_seconds, efficiency_pct FROM oura_sleep ORDER BY date",
+    )
+    .fetch_all(&db)
+    .await
+    .unwrap();
+    assert_eq!(
+      sleep,
+      vec![
+        ("2024-03-01".to_string(), Some(27000), Some(5400), Some(6300), Some(88)),
+        ("2024-03-02".to_string(), Some(26400), Some(5000), None, None),
+      ]
+    );
+
+    let hrv: Vec<(String, f64)> = sqlx::query_as("SELECT date, average_hrv_ms FROM oura_hrv ORDER BY date")
+      .fetch_all(&db)
+      .await
+      .unwrap();
+    assert_eq!(hrv.len(), 3);
+    assert_eq!(hrv[0], ("2024-03-01".to_string(), 52.5));
+    let rhr: Vec<(String, i64)> = sqlx::query_as("SELECT date, resting_hr FROM oura_resting_hr ORDER BY date")
+      .fetch_all(&db)
+      .await
+      .unwrap();
+    assert_eq!(rhr, vec![("2024-03-01".to_string(), 48), ("2024-03-06".to_string(), 50)]);
+
+    // Re-importing the same file is idempotent
+    import_oura_csv_text(&db, csv).await.unwrap();
+    let nights: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM oura_sleep").fetch_one(&db).await.unwrap();
+    assert_eq!(nights, 2);
+
+    assert!(import_oura_csv_text(&db, "day,steps\n2024-03-01,9000\n").await.is_err());
+  }
+}
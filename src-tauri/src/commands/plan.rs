@@ -0,0 +1,576 @@
+use crate::analysis::{
+  compute_plan_adherence as compare_plan_to_actual, GoalCountdown, PhaseSource, PlanAdherence, PlanContext,
+  TrainingContext, TrainingFlags, TrainingPhase, TrainingPhaseContext, UserSettings,
+};
+use crate::commands::analysis::{compute_adherence, get_workout_summaries, load_user_settings};
+use crate::db::AppState;
+use crate::llm::{ClaudeClient, TrainingPlan, CLAUDE_MODEL};
+use crate::progression::{load_all_dimensions, ProgressionSummary};
+use crate::scheduler::record_token_usage;
+use chrono::{Duration, NaiveDate, Utc};
+use serde::Serialize;
+use std::sync::Arc;
+use tauri::State;
+
+/// Longest plan window we'll ask the LLM for
+const MAX_PLAN_WEEKS: i64 = 4;
+
+/// ---------------------------------------------------------------------------
+/// Plan Types
+/// ---------------------------------------------------------------------------
+
+/// A persisted plan with its window
+#[derive(Debug, Clone, Serialize)]
+pub struct StoredTrainingPlan {
+  pub id: i64,
+  pub start_date: String,
+  pub end_date: String,
+  pub weeks: i64,
+  pub plan: TrainingPlan,
+  pub created_at: String,
+}
+
+/// ---------------------------------------------------------------------------
+/// Plan Generation Commands
+/// ---------------------------------------------------------------------------
+
+/// Generate a day-by-day plan for the next `weeks` weeks (starting tomorrow)
+#[tauri::command]
+pub async fn generate_plan(
+  state: State<'_, Arc<AppState>>,
+  weeks: i64,
+) -> Result<StoredTrainingPlan, String> {
+  if !(1..=MAX_PLAN_WEEKS).contains(&weeks) {
+    return Err(format!("Plan length must be 1-{} weeks", MAX_PLAN_WEEKS));
+  }
+
+  let settings = load_user_settings(&state.db).await?;
+  let start_date = settings.local_date(&Utc::now()) + Duration::days(1);
+  check_goal_not_before(&settings, start_date)?;
+
+  let workouts = get_workout_summaries(&state.db)
+    .await
+    .map_err(|e| format!("Failed to get workout summaries: {}", e))?;
+  let training_context = TrainingContext::compute(&workouts, &settings);
+
+  let dimensions = load_all_dimensions(&state.db)
+    .await
+    .map_err(|e| format!("Failed to load progression dimensions: {}", e))?;
+  let phase = load_training_phase(&state.db, &settings, start_date).await?;
+  let flags = TrainingFlags::compute(&workouts, &training_context, &settings, &dimensions)
+    .with_phase(phase.map(|p| p.phase));
+
+  let adherence = compute_adherence(&state.db, &settings)
+    .await
+    .unwrap_or_default();
+  let progression_summary = ProgressionSummary::compute(
+    &dimensions,
+    &training_context,
+    &flags,
+    adherence,
+    settings.progression_overlap_days,
+    settings.experience_level,
+  );
+
+  let context = PlanContext::build(start_date, weeks, training_context, flags, &settings)
+    .with_progression_summary(progression_summary);
+
+  let client = ClaudeClient::from_env().map_err(|e| e.to_string())?;
+  let (plan, usage) = client
+    .generate_plan(&context.to_json(), start_date, weeks)
+    .await
+    .map_err(|e| e.to_string())?;
+  record_token_usage(&state.db, "plan", CLAUDE_MODEL, &usage)
+    .await
+    .map_err(|e| format!("Failed to record token usage: {}", e))?;
+
+  let end_date = start_date + Duration::days(weeks * 7 - 1);
+  let id = save_training_plan(
+    &state.db,
+    &plan,
+    start_date,
+    end_date,
+    weeks,
+    Some((usage.input_tokens, usage.output_tokens)),
+  )
+  .await?;
+
+  println!(
+    "Generated {}-week plan {}: {} tokens in, {} tokens out",
+    weeks, id, usage.input_tokens, usage.output_tokens
+  );
+
+  load_training_plan(&state.db, id)
+    .await?
+    .ok_or_else(|| "Plan not found after saving".to_string())
+}
+
+/// A goal date before the plan's first day would count down negative days
+/// through the whole window
+fn check_goal_not_before(settings: &UserSettings, start_date: NaiveDate) -> Result<(), String> {
+  match GoalCountdown::from_settings(settings, start_date) {
+    Some(goal) if goal.days_remaining < 0 => Err(format!(
+      "Goal date {} is before the plan starts on {}; move or clear the goal first",
+      goal.date, start_date
+    )),
+    _ => Ok(()),
+  }
+}
+
+/// Get the most recently generated plan
+#[tauri::command]
+pub async fn get_latest_plan(
+  state: State<'_, Arc<AppState>>,
+) -> Result<Option<StoredTrainingPlan>, String> {
+  load_latest_training_plan(&state.db).await
+}
+
+/// ---------------------------------------------------------------------------
+/// Plan Adherence
+/// ---------------------------------------------------------------------------
+
+/// Compare a stored plan against the workouts actually recorded in its window
+#[tauri::command]
+pub async fn compute_plan_adherence(
+  state: State<'_, Arc<AppState>>,
+  plan_id: i64,
+) -> Result<PlanAdherence, String> {
+  let stored = load_training_plan(&state.db, plan_id)
+    .await?
+    .ok_or_else(|| format!("Plan {} not found", plan_id))?;
+  let settings = load_user_settings(&state.db).await?;
+
+  plan_adherence_for(&state.db, &stored, None, settings.local_date(&Utc::now())).await
+}
+
+/// Helper: Plan adherence for a stored plan as of the athlete's local
+/// `today`, optionally limited to days on/after `since`
+pub(crate) async fn plan_adherence_for(
+  db: &crate::db::DbPool,
+  stored: &StoredTrainingPlan,
+  since: Option<NaiveDate>,
+  today: NaiveDate,
+) -> Result<PlanAdherence, String> {
+  let actual = load_actual_workouts(db, &stored.start_date, &stored.end_date).await?;
+
+  let days: Vec<_> = stored
+    .plan
+    .days
+    .iter()
+    .filter(|day| match (since, NaiveDate::parse_from_str(&day.date, "%Y-%m-%d")) {
+      (Some(since), Ok(date)) => date >= since,
+      _ => true,
+    })
+    .cloned()
+    .collect();
+
+  Ok(compare_plan_to_actual(&days, &actual, today))
+}
+
+/// ---------------------------------------------------------------------------
+/// Training Phase
+/// ---------------------------------------------------------------------------
+
+/// Plan a phase block starting on `start_date` (YYYY-MM-DD). Without an
+/// `end_date` it runs until the next block starts. Returns today's phase.
+#[tauri::command]
+pub async fn set_training_phase(
+  state: State<'_, Arc<AppState>>,
+  phase: String,
+  start_date: String,
+  end_date: Option<String>,
+) -> Result<Option<TrainingPhaseContext>, String> {
+  let phase = TrainingPhase::parse(&phase)
+    .ok_or_else(|| format!("Invalid phase '{}': expected base, build, peak or recovery", phase))?;
+  let start = parse_date("start_date", &start_date)?;
+  let end = end_date.as_deref().map(|d| parse_date("end_date", d)).transpose()?;
+  save_training_phase(&state.db, phase, start, end).await?;
+
+  let settings = load_user_settings(&state.db).await?;
+  load_training_phase(&state.db, &settings, settings.local_date(&Utc::now())).await
+}
+
+/// Get today's phase: the planned block covering today, else one derived
+/// from goal proximity (None when neither applies)
+#[tauri::command]
+pub async fn get_training_phase(
+  state: State<'_, Arc<AppState>>,
+) -> Result<Option<TrainingPhaseContext>, String> {
+  let settings = load_user_settings(&state.db).await?;
+  load_training_phase(&state.db, &settings, settings.local_date(&Utc::now())).await
+}
+
+fn parse_date(label: &str, value: &str) -> Result<NaiveDate, String> {
+  NaiveDate::parse_from_str(value, "%Y-%m-%d")
+    .map_err(|_| format!("Invalid {} '{}': expected YYYY-MM-DD", label, value))
+}
+
+/// Helper: Phase in effect on `date`. The latest-starting planned block
+/// that covers the date wins; a block without an end date covers every day
+/// until a later block starts. Otherwise fall back to the goal countdown.
+pub(crate) async fn load_training_phase(
+  db: &crate::db::DbPool,
+  settings: &UserSettings,
+  date: NaiveDate,
+) -> Result<Option<TrainingPhaseContext>, String> {
+  let row: Option<(String, String, Option<String>)> = sqlx::query_as(
+    r#"
+    SELECT phase, start_date, end_date
+    FROM training_phases p
+    WHERE start_date <= ?1
+      AND (end_date >= ?1 OR (end_date IS NULL AND NOT EXISTS (
+        SELECT 1 FROM training_phases later
+        WHERE later.start_date > p.start_date AND later.start_date <= ?1
+      )))
+    ORDER BY start_date DESC, id DESC
+    LIMIT 1
+    "#,
+  )
+  .bind(date.format("%Y-%m-%d").to_string())
+  .fetch_optional(db)
+  .await
+  .map_err(|e| format!("Failed to fetch training phase: {}", e))?;
+
+  if let Some((phase, start_date, end_date)) = row {
+    if let Some(phase) = TrainingPhase::parse(&phase) {
+      return Ok(Some(TrainingPhaseContext {
+        phase,
+        source: PhaseSource::Planned,
+        start_date: Some(start_date),
+        end_date,
+      }));
+    }
+  }
+
+  Ok(TrainingPhase::from_goal_proximity(settings, date).map(|phase| TrainingPhaseContext {
+    phase,
+    source: PhaseSource::GoalProximity,
+    start_date: None,
+    end_date: None,
+  }))
+}
+
+/// Helper: Store a planned phase block
+async fn save_training_phase(
+  db: &crate::db::DbPool,
+  phase: TrainingPhase,
+  start_date: NaiveDate,
+  end_date: Option<NaiveDate>,
+) -> Result<(), String> {
+  if end_date.is_some_and(|end| end < start_date) {
+    return Err("end_date must be on or after start_date".to_string());
+  }
+
+  sqlx::query("INSERT INTO training_phases (phase, start_date, end_date) VALUES (?1, ?2, ?3)")
+    .bind(phase.as_str())
+    .bind(start_date.format("%Y-%m-%d").to_string())
+    .bind(end_date.map(|d| d.format("%Y-%m-%d").to_string()))
+    .execute(db)
+    .await
+    .map_err(|e| format!("Failed to store training phase: {}", e))?;
+
+  Ok(())
+}
+
+/// ---------------------------------------------------------------------------
+/// Database Helpers
+/// ---------------------------------------------------------------------------
+
+/// Workouts (date, activity_type) recorded between two ISO dates inclusive
+pub(crate) async fn load_actual_workouts(
+  db: &crate::db::DbPool,
+  start_date: &str,
+  end_date: &str,
+) -> Result<Vec<(NaiveDate, String)>, String> {
+  let rows: Vec<(String, String)> = sqlx::query_as(
+    r#"
+    SELECT date(started_at), activity_type
+    FROM workouts
+    WHERE date(started_at) BETWEEN ?1 AND ?2 AND duplicate_of IS NULL
+    ORDER BY started_at
+    "#,
+  )
+  .bind(start_date)
+  .bind(end_date)
+  .fetch_all(db)
+  .await
+  .map_err(|e| format!("Failed to fetch workouts for plan: {}", e))?;
+
+  Ok(
+    rows
+      .into_iter()
+      .filter_map(|(date, activity_type)| {
+        NaiveDate::parse_from_str(&date, "%Y-%m-%d")
+          .ok()
+          .map(|d| (d, activity_type))
+      })
+      .collect(),
+  )
+}
+
+/// The most recent plan whose window contains `date`
+pub(crate) async fn load_active_training_plan(
+  db: &crate::db::DbPool,
+  date: NaiveDate,
+) -> Result<Option<StoredTrainingPlan>, String> {
+  let row: Option<PlanRow> = sqlx::query_as(
+    r#"
+    SELECT id, start_date, end_date, weeks, plan_json, created_at
+    FROM training_plans
+    WHERE start_date <= ?1 AND end_date >= ?1
+    ORDER BY created_at DESC, id DESC
+    LIMIT 1
+    "#,
+  )
+  .bind(date.format("%Y-%m-%d").to_string())
+  .fetch_optional(db)
+  .await
+  .map_err(|e| format!("Failed to fetch plan: {}", e))?;
+
+  row.map(plan_from_row).transpose()
+}
+
+type PlanRow = (i64, String, String, i64, String, String);
+
+async fn save_training_plan(
+  db: &crate::db::DbPool,
+  plan: &TrainingPlan,
+  start_date: NaiveDate,
+  end_date: NaiveDate,
+  weeks: i64,
+  tokens: Option<(u32, u32)>,
+) -> Result<i64, String> {
+  let plan_json =
+    serde_json::to_string(plan).map_err(|e| format!("Failed to serialize plan: {}", e))?;
+
+  let result = sqlx::query(
+    r#"
+    INSERT INTO training_plans (
+      start_date, end_date, weeks, plan_json, model_version, input_tokens, output_tokens
+    )
+    VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+    "#,
+  )
+  .bind(start_date.format("%Y-%m-%d").to_string())
+  .bind(end_date.format("%Y-%m-%d").to_string())
+  .bind(weeks)
+  .bind(&plan_json)
+  .bind(CLAUDE_MODEL)
+  .bind(tokens.map(|(input, _)| input as i64))
+  .bind(tokens.map(|(_, output)| output as i64))
+  .execute(db)
+  .await
+  .map_err(|e| format!("Failed to store plan: {}", e))?;
+
+  Ok(result.last_insert_rowid())
+}
+
+async fn load_training_plan(
+  db: &crate::db::DbPool,
+  id: i64,
+) -> Result<Option<StoredTrainingPlan>, String> {
+  let row: Option<PlanRow> = sqlx::query_as(
+    r#"
+    SELECT id, start_date, end_date, weeks, plan_json, created_at
+    FROM training_plans
+    WHERE id = ?1
+    "#,
+  )
+  .bind(id)
+  .fetch_optional(db)
+  .await
+  .map_err(|e| format!("Failed to fetch plan: {}", e))?;
+
+  row.map(plan_from_row).transpose()
+}
+
+async fn load_latest_training_plan(
+  db: &crate::db::DbPool,
+) -> Result<Option<StoredTrainingPlan>, String> {
+  let row: Option<PlanRow> = sqlx::query_as(
+    r#"
+    SELECT id, start_date, end_date, weeks, plan_json, created_at
+    FROM training_plans
+    ORDER BY created_at DESC, id DESC
+    LIMIT 1
+    "#,
+  )
+  .fetch_optional(db)
+  .await
+  .map_err(|e| format!("Failed to fetch plan: {}", e))?;
+
+  row.map(plan_from_row).transpose()
+}
+
+fn plan_from_row(row: PlanRow) -> Result<StoredTrainingPlan, String> {
+  let (id, start_date, end_date, weeks, plan_json, created_at) = row;
+  let plan: TrainingPlan =
+    serde_json::from_str(&plan_json).map_err(|e| format!("Failed to parse stored plan: {}", e))?;
+
+  Ok(StoredTrainingPlan {
+    id,
+    start_date,
+    end_date,
+    weeks,
+    plan,
+    created_at,
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::llm::PlannedDay;
+
+  fn sample_plan() -> TrainingPlan {
+    TrainingPlan {
+      summary: Some("Rebuild after the spike".to_string()),
+      days: vec![
+        PlannedDay {
+          date: "2024-12-16".to_string(),
+          activity_type: "ride".to_string(),
+          duration_min: 45,
+          intensity: "Z2".to_string(),
+          purpose: Some("aerobic_development".to_string()),
+          intervals: None,
+        },
+        PlannedDay {
+          date: "2024-12-17".to_string(),
+          activity_type: "rest".to_string(),
+          duration_min: 0,
+          intensity: "rest".to_string(),
+          purpose: None,
+          intervals: None,
+        },
+      ],
+    }
+  }
+
+  #[tokio::test]
+  async fn test_plan_persistence_round_trip() {
+    let db = crate::db::test_pool().await;
+    let start = NaiveDate::from_ymd_opt(2024, 12, 16).unwrap();
+    let end = start + Duration::days(13);
+
+    let id = save_training_plan(&db, &sample_plan(), start, end, 2, Some((1200, 900)))
+      .await
+      .unwrap();
+
+    let stored = load_training_plan(&db, id).await.unwrap().unwrap();
+    assert_eq!(stored.start_date, "2024-12-16");
+    assert_eq!(stored.end_date, "2024-12-29");
+    assert_eq!(stored.weeks, 2);
+    assert_eq!(stored.plan.days.len(), 2);
+    assert_eq!(stored.plan.days[0].activity_type, "ride");
+    assert_eq!(stored.plan.summary.as_deref(), Some("Rebuild after the spike"));
+
+    let latest = load_latest_training_plan(&db).await.unwrap().unwrap();
+    assert_eq!(latest.id, id);
+  }
+
+  #[tokio::test]
+  async fn test_no_plan_yet() {
+    let db = crate::db::test_pool().await;
+    assert!(load_latest_training_plan(&db).await.unwrap().is_none());
+  }
+
+  #[tokio::test]
+  async fn test_plan_adherence_against_recorded_workouts() {
+    let db = crate::db::test_pool().await;
+    let start = NaiveDate::from_ymd_opt(2024, 12, 16).unwrap();
+    let id = save_training_plan(&db, &sample_plan(), start, start + Duration::days(13), 2, None)
+      .await
+      .unwrap();
+
+    sqlx::query(
+      "INSERT INTO workouts (strava_id, activity_type, started_at) VALUES ('1', 'Ride', '2024-12-16T17:30:00Z')",
+    )
+    .execute(&db)
+    .await
+    .unwrap();
+
+    let stored = load_training_plan(&db, id).await.unwrap().unwrap();
+    let adherence = plan_adherence_for(&db, &stored, None, Utc::now().date_naive()).await.unwrap();
+
+    assert_eq!(adherence.completed, 1);
+    assert_eq!(adherence.planned_sessions, 1);
+    assert_eq!(adherence.days[0].actual_types, vec!["Ride".to_string()]);
+
+    let active = load_active_training_plan(&db, NaiveDate::from_ymd_opt(2024, 12, 20).unwrap())
+      .await
+      .unwrap();
+    assert_eq!(active.map(|p| p.id), Some(id));
+    let outside = load_active_training_plan(&db, NaiveDate::from_ymd_opt(2025, 1, 20).unwrap())
+      .await
+      .unwrap();
+    assert!(outside.is_none());
+  }
+
+  #[tokio::test]
+  async fn test_training_phase_planned_then_derived() {
+    let db = crate::db::test_pool().await;
+    let date = |d: &str| NaiveDate::parse_from_str(d, "%Y-%m-%d").unwrap();
+
+    // No plan, no goal: no phase
+    let settings = UserSettings::default();
+    assert_eq!(load_training_phase(&db, &settings, date("2025-03-01")).await.unwrap(), None);
+
+    // Goal 6 weeks out derives Build
+    let settings = UserSettings { goal_date: Some("2025-04-12".to_string()), ..Default::default() };
+    let derived = load_training_phase(&db, &settings, date("2025-03-01")).await.unwrap().unwrap();
+    assert_eq!(derived.phase, TrainingPhase::Build);
+    assert_eq!(derived.source, PhaseSource::GoalProximity);
+
+    // A planned recovery block overrides the countdown inside its window
+    save_training_phase(&db, TrainingPhase::Recovery, date("2025-02-24"), Some(date("2025-03-02")))
+      .await
+      .unwrap();
+    let planned = load_training_phase(&db, &settings, date("2025-03-01")).await.unwrap().unwrap();
+    assert_eq!(planned.phase, TrainingPhase::Recovery);
+    assert_eq!(planned.source, PhaseSource::Planned);
+    assert_eq!(planned.end_date.as_deref(), Some("2025-03-02"));
+
+    let after = load_training_phase(&db, &settings, date("2025-03-03")).await.unwrap().unwrap();
+    assert_eq!(after.source, PhaseSource::GoalProximity);
+
+    assert!(save_training_phase(&db, TrainingPhase::Base, date("2025-03-10"), Some(date("2025-03-01")))
+      .await
+      .is_err());
+  }
+
+  #[tokio::test]
+  async fn test_open_ended_phase_runs_until_the_next_block() {
+    let db = crate::db::test_pool().await;
+    let date = |d: &str| NaiveDate::parse_from_str(d, "%Y-%m-%d").unwrap();
+    let settings = UserSettings::default();
+
+    save_training_phase(&db, TrainingPhase::Base, date("2025-01-06"), None).await.unwrap();
+    let base = load_training_phase(&db, &settings, date("2025-06-01")).await.unwrap().unwrap();
+    assert_eq!(base.phase, TrainingPhase::Base);
+    assert_eq!(base.end_date, None);
+
+    // A later block ends the open-ended one for good, even once it's over
+    save_training_phase(&db, TrainingPhase::Build, date("2025-02-03"), Some(date("2025-03-02")))
+      .await
+      .unwrap();
+    let before = load_training_phase(&db, &settings, date("2025-02-02")).await.unwrap().unwrap();
+    assert_eq!(before.phase, TrainingPhase::Base);
+    let during = load_training_phase(&db, &settings, date("2025-02-10")).await.unwrap().unwrap();
+    assert_eq!(during.phase, TrainingPhase::Build);
+    assert_eq!(load_training_phase(&db, &settings, date("2025-03-03")).await.unwrap(), None);
+  }
+
+  #[test]
+  fn test_plan_window_must_not_start_after_goal() {
+    let date = |d: &str| NaiveDate::parse_from_str(d, "%Y-%m-%d").unwrap();
+    let settings = UserSettings { goal_date: Some("2025-04-12".to_string()), ..Default::default() };
+
+    assert!(check_goal_not_before(&settings, date("2025-03-01")).is_ok());
+    // Race day itself can still be planned
+    assert!(check_goal_not_before(&settings, date("2025-04-12")).is_ok());
+    let err = check_goal_not_before(&settings, date("2025-04-13")).unwrap_err();
+    assert!(err.contains("2025-04-12"), "{}", err);
+
+    assert!(check_goal_not_before(&UserSettings::default(), date("2025-04-13")).is_ok());
+  }
+}
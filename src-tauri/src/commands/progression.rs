@@ -3,10 +3,18 @@
 use std::sync::Arc;
 use tauri::State;
 
+use crate::analysis::{TrainingContext, TrainingFlags};
+use crate::commands::analysis::{compute_adherence, get_workout_summaries, load_user_settings};
 use crate::db::AppState;
 use crate::progression::{
-    apply_progression, apply_regression, load_all_dimensions, load_dimension,
-    record_ceiling_touch, update_ceiling, ProgressionDimension,
+    apply_progression, apply_regression, create_dimension as create_progression_dimension,
+    delete_dimension as delete_progression_dimension,
+    get_dimension_timeline as load_dimension_timeline, load_all_dimensions, load_dimension, load_maintenance_due,
+    mark_key_session as link_key_session, record_ceiling_touch,
+    reset_dimensions as reset_progression_dimensions, set_current_value, set_prerequisite,
+    undo_last_change as undo_last_dimension_change,
+    update_ceiling,
+    DecisionTrace, MaintenanceDue, ProgressionDimension, TimelinePoint,
 };
 
 /// Get all progression dimensions
@@ -26,6 +34,60 @@ pub async fn get_progression_dimension(
     load_dimension(&state.db, &name).await
 }
 
+/// At-ceiling dimensions whose maintenance cadence has lapsed, with days
+/// overdue and the session that touches the ceiling (no LLM call)
+#[tauri::command]
+pub async fn get_maintenance_due(
+    state: State<'_, Arc<AppState>>,
+) -> Result<Vec<MaintenanceDue>, String> {
+    load_maintenance_due(&state.db).await
+}
+
+/// Get a dimension's value history as a plottable series (oldest first)
+#[tauri::command]
+pub async fn get_dimension_timeline(
+    state: State<'_, Arc<AppState>>,
+    dimension_name: String,
+) -> Result<Vec<TimelinePoint>, String> {
+    load_dimension_timeline(&state.db, &dimension_name).await
+}
+
+/// Every condition the engine evaluated for a dimension, with the values
+/// it compared, alongside the decision and its one-line reason
+#[tauri::command]
+pub async fn explain_dimension(
+    state: State<'_, Arc<AppState>>,
+    dimension_name: String,
+) -> Result<DecisionTrace, String> {
+    explain_dimension_decision(&state.db, &dimension_name).await
+}
+
+/// Helper: Trace a dimension against today's context, flags and adherence
+pub(crate) async fn explain_dimension_decision(
+    db: &crate::db::DbPool,
+    dimension_name: &str,
+) -> Result<DecisionTrace, String> {
+    let settings = load_user_settings(db).await?;
+    let workouts = get_workout_summaries(db)
+        .await
+        .map_err(|e| format!("Failed to get workout summaries: {}", e))?;
+    let context = TrainingContext::compute(&workouts, &settings);
+    let dimensions = load_all_dimensions(db).await?;
+    let flags = TrainingFlags::compute(&workouts, &context, &settings, &dimensions);
+    let adherence = compute_adherence(db, &settings).await.unwrap_or_default();
+
+    DecisionTrace::compute(
+        &dimensions,
+        dimension_name,
+        &context,
+        &flags,
+        &adherence,
+        settings.progression_overlap_days,
+        settings.experience_level,
+    )
+    .ok_or_else(|| format!("Dimension not found: {}", dimension_name))
+}
+
 /// Apply a progression to a dimension (advance to next value)
 #[tauri::command]
 pub async fn progress_dimension(
@@ -54,6 +116,17 @@ pub async fn touch_ceiling(
     record_ceiling_touch(&state.db, &dimension_name).await
 }
 
+/// Link a workout to a dimension's key session so adherence counts it
+/// as deliberately completed
+#[tauri::command]
+pub async fn mark_key_session(
+    state: State<'_, Arc<AppState>>,
+    workout_id: i64,
+    dimension_name: String,
+) -> Result<(), String> {
+    link_key_session(&state.db, workout_id, &dimension_name).await
+}
+
 /// Update the ceiling for a dimension
 #[tauri::command]
 pub async fn set_dimension_ceiling(
@@ -63,3 +136,69 @@ pub async fn set_dimension_ceiling(
 ) -> Result<(), String> {
     update_ceiling(&state.db, &dimension_name, &new_ceiling).await
 }
+
+/// Set a dimension's current value directly (validated against its step config)
+#[tauri::command]
+pub async fn set_dimension_current(
+    state: State<'_, Arc<AppState>>,
+    dimension_name: String,
+    value: String,
+) -> Result<ProgressionDimension, String> {
+    set_current_value(&state.db, &dimension_name, &value).await
+}
+
+/// Make a dimension wait on another reaching a value (None clears it)
+#[tauri::command]
+pub async fn set_dimension_prerequisite(
+    state: State<'_, Arc<AppState>>,
+    dimension_name: String,
+    prerequisite: Option<String>,
+    prerequisite_min: Option<String>,
+) -> Result<ProgressionDimension, String> {
+    set_prerequisite(&state.db, &dimension_name, prerequisite.as_deref(), prerequisite_min.as_deref()).await
+}
+
+/// Undo the most recent change to a dimension (restores the exact prior value)
+#[tauri::command]
+pub async fn undo_last_change(
+    state: State<'_, Arc<AppState>>,
+    dimension_name: String,
+) -> Result<ProgressionDimension, String> {
+    undo_last_dimension_change(&state.db, &dimension_name).await
+}
+
+/// Create a new progression dimension
+#[tauri::command]
+pub async fn create_dimension(
+    state: State<'_, Arc<AppState>>,
+    name: String,
+    current: String,
+    ceiling: String,
+    step_config_json: String,
+    criteria_json: Option<String>,
+) -> Result<ProgressionDimension, String> {
+    create_progression_dimension(
+        &state.db,
+        &name,
+        &current,
+        &ceiling,
+        &step_config_json,
+        criteria_json.as_deref(),
+    )
+    .await
+}
+
+/// Delete a progression dimension
+#[tauri::command]
+pub async fn delete_dimension(state: State<'_, Arc<AppState>>, name: String) -> Result<(), String> {
+    delete_progression_dimension(&state.db, &name).await
+}
+
+/// Replace all dimensions with a named template set ("hybrid", "cycling")
+#[tauri::command]
+pub async fn reset_dimensions(
+    state: State<'_, Arc<AppState>>,
+    template: String,
+) -> Result<Vec<ProgressionDimension>, String> {
+    reset_progression_dimensions(&state.db, &template).await
+}
@@ -0,0 +1,323 @@
+use crate::analysis::{compute_plan_adherence as compare_plan_to_actual, WeekStart, WeeklyReviewContext};
+use crate::commands::analysis::{get_workout_summaries_between, load_user_settings};
+use crate::commands::plan::{load_active_training_plan, load_actual_workouts};
+use crate::db::AppState;
+use crate::llm::{ClaudeClient, WeeklyReview, CLAUDE_MODEL};
+use crate::progression::{load_all_dimensions, load_history_between};
+use crate::scheduler::record_token_usage;
+use chrono::{Duration, NaiveDate, Utc};
+use serde::Serialize;
+use std::sync::Arc;
+use tauri::State;
+
+/// History loaded before the week so CTL at its start covers a full 42 days
+const REVIEW_HISTORY_DAYS: i64 = 43;
+
+/// ---------------------------------------------------------------------------
+/// Weekly Review Types
+/// ---------------------------------------------------------------------------
+
+/// A persisted weekly review with its window
+#[derive(Debug, Clone, Serialize)]
+pub struct StoredWeeklyReview {
+  pub week_start: String,
+  pub week_end: String,
+  pub review: WeeklyReview,
+  pub created_at: String,
+}
+
+/// ---------------------------------------------------------------------------
+/// Weekly Review Commands
+/// ---------------------------------------------------------------------------
+
+/// Review a week as a whole. `week_start` (YYYY-MM-DD) defaults to the start
+/// of the last complete week (per `week_start_day`); regenerating replaces the stored review.
+#[tauri::command]
+pub async fn generate_weekly_review(
+  state: State<'_, Arc<AppState>>,
+  week_start: Option<String>,
+) -> Result<StoredWeeklyReview, String> {
+  let settings = load_user_settings(&state.db).await?;
+  let today = settings.local_date(&Utc::now());
+  let week_start = resolve_week_start(week_start.as_deref(), today, settings.week_start_day)?;
+  if week_start > today {
+    return Err(format!("Week starting {} hasn't started yet", week_start));
+  }
+
+  let context = build_weekly_review_context(&state.db, week_start).await?;
+
+  let client = ClaudeClient::from_env().map_err(|e| e.to_string())?;
+  let (review, usage) = client
+    .generate_weekly_review(&context.to_json())
+    .await
+    .map_err(|e| e.to_string())?;
+  record_token_usage(&state.db, "weekly_review", CLAUDE_MODEL, &usage)
+    .await
+    .map_err(|e| format!("Failed to record token usage: {}", e))?;
+
+  save_weekly_review(
+    &state.db,
+    week_start,
+    &review,
+    Some((usage.input_tokens, usage.output_tokens)),
+  )
+  .await?;
+
+  println!(
+    "Generated weekly review for {}: {} tokens in, {} tokens out",
+    week_start, usage.input_tokens, usage.output_tokens
+  );
+
+  load_weekly_review(&state.db, week_start)
+    .await?
+    .ok_or_else(|| "Weekly review not found after saving".to_string())
+}
+
+/// Get the stored review for a week (defaults to the last complete week)
+#[tauri::command]
+pub async fn get_weekly_review(
+  state: State<'_, Arc<AppState>>,
+  week_start: Option<String>,
+) -> Result<Option<StoredWeeklyReview>, String> {
+  let settings = load_user_settings(&state.db).await?;
+  let week_start =
+    resolve_week_start(week_start.as_deref(), settings.local_date(&Utc::now()), settings.week_start_day)?;
+  load_weekly_review(&state.db, week_start).await
+}
+
+/// First day of the last complete week before `today` (Monday-Sunday for a
+/// rolling week)
+fn last_full_week_start(today: NaiveDate, week_start_day: WeekStart) -> NaiveDate {
+  week_start_day.calendar_week_start(today) - Duration::days(7)
+}
+
+fn resolve_week_start(value: Option<&str>, today: NaiveDate, week_start_day: WeekStart) -> Result<NaiveDate, String> {
+  match value {
+    Some(value) => NaiveDate::parse_from_str(value, "%Y-%m-%d")
+      .map_err(|_| format!("Invalid week_start '{}': expected YYYY-MM-DD", value)),
+    None => Ok(last_full_week_start(today, week_start_day)),
+  }
+}
+
+/// Helper: Assemble the week-level context for the 7 local days starting at
+/// `week_start`: load as of the week's end, progression changes recorded
+/// during it, and plan vs actual when a plan covered it
+pub(crate) async fn build_weekly_review_context(
+  db: &crate::db::DbPool,
+  week_start: NaiveDate,
+) -> Result<WeeklyReviewContext, String> {
+  let settings = load_user_settings(db).await?;
+  let week_end = week_start + Duration::days(6);
+
+  // Local noon +/- 12h brackets the local calendar days
+  let starts_at = settings.local_noon(week_start) - Duration::hours(12);
+  let ends_at = settings.local_noon(week_end) + Duration::hours(12);
+
+  let workouts = get_workout_summaries_between(db, starts_at - Duration::days(REVIEW_HISTORY_DAYS), ends_at)
+    .await
+    .map_err(|e| format!("Failed to get workout summaries: {}", e))?;
+  let dimensions = load_all_dimensions(db)
+    .await
+    .map_err(|e| format!("Failed to load progression dimensions: {}", e))?;
+
+  let mut context = WeeklyReviewContext::build(week_start, &workouts, &settings, &dimensions)
+    .with_progression_changes(load_history_between(db, starts_at, ends_at).await?);
+
+  let plan = match load_active_training_plan(db, week_start).await? {
+    Some(plan) => Some(plan),
+    None => load_active_training_plan(db, week_end).await?,
+  };
+  if let Some(plan) = plan {
+    let days: Vec<_> = plan
+      .plan
+      .days
+      .iter()
+      .filter(|day| match NaiveDate::parse_from_str(&day.date, "%Y-%m-%d") {
+        Ok(date) => date >= week_start && date <= week_end,
+        Err(_) => false,
+      })
+      .cloned()
+      .collect();
+
+    if !days.is_empty() {
+      let actual = load_actual_workouts(
+        db,
+        &week_start.format("%Y-%m-%d").to_string(),
+        &week_end.format("%Y-%m-%d").to_string(),
+      )
+      .await?;
+      let today = settings.local_date(&Utc::now());
+      context = context.with_plan_adherence(compare_plan_to_actual(&days, &actual, today));
+    }
+  }
+
+  Ok(context)
+}
+
+/// ---------------------------------------------------------------------------
+/// Database Helpers
+/// ---------------------------------------------------------------------------
+
+async fn save_weekly_review(
+  db: &crate::db::DbPool,
+  week_start: NaiveDate,
+  review: &WeeklyReview,
+  tokens: Option<(u32, u32)>,
+) -> Result<(), String> {
+  let review_json =
+    serde_json::to_string(review).map_err(|e| format!("Failed to serialize weekly review: {}", e))?;
+
+  sqlx::query(
+    r#"
+    INSERT INTO weekly_reviews (
+      week_start, week_end, review_json, model_version, input_tokens, output_tokens
+    )
+    VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+    ON CONFLICT(week_start) DO UPDATE SET
+      week_end = excluded.week_end,
+      review_json = excluded.review_json,
+      model_version = excluded.model_version,
+      input_tokens = excluded.input_tokens,
+      output_tokens = excluded.output_tokens,
+      created_at = CURRENT_TIMESTAMP
+    "#,
+  )
+  .bind(week_start.format("%Y-%m-%d").to_string())
+  .bind((week_start + Duration::days(6)).format("%Y-%m-%d").to_string())
+  .bind(&review_json)
+  .bind(CLAUDE_MODEL)
+  .bind(tokens.map(|(input, _)| input as i64))
+  .bind(tokens.map(|(_, output)| output as i64))
+  .execute(db)
+  .await
+  .map_err(|e| format!("Failed to store weekly review: {}", e))?;
+
+  Ok(())
+}
+
+async fn load_weekly_review(
+  db: &crate::db::DbPool,
+  week_start: NaiveDate,
+) -> Result<Option<StoredWeeklyReview>, String> {
+  let row: Option<(String, String, String, String)> = sqlx::query_as(
+    r#"
+    SELECT week_start, week_end, review_json, created_at
+    FROM weekly_reviews
+    WHERE week_start = ?1
+    "#,
+  )
+  .bind(week_start.format("%Y-%m-%d").to_string())
+  .fetch_optional(db)
+  .await
+  .map_err(|e| format!("Failed to fetch weekly review: {}", e))?;
+
+  row
+    .map(|(week_start, week_end, review_json, created_at)| {
+      let review = serde_json::from_str(&review_json)
+        .map_err(|e| format!("Failed to parse stored weekly review: {}", e))?;
+      Ok(StoredWeeklyReview {
+        week_start,
+        week_end,
+        review,
+        created_at,
+      })
+    })
+    .transpose()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  async fn insert_workout(db: &crate::db::DbPool, strava_id: &str, activity_type: &str, started_at: &str, rtss: Option<f64>) {
+    sqlx::query(
+      "INSERT INTO workouts (strava_id, activity_type, started_at, duration_seconds, rtss) VALUES (?1, ?2, ?3, 3600, ?4)",
+    )
+    .bind(strava_id)
+    .bind(activity_type)
+    .bind(started_at)
+    .bind(rtss)
+    .execute(db)
+    .await
+    .unwrap();
+  }
+
+  #[test]
+  fn test_last_full_week_start() {
+    let date = |d: &str| NaiveDate::parse_from_str(d, "%Y-%m-%d").unwrap();
+    // Wednesday and Sunday both review the previous Monday-Sunday week
+    assert_eq!(last_full_week_start(date("2024-12-25"), WeekStart::Monday), date("2024-12-16"));
+    assert_eq!(last_full_week_start(date("2024-12-29"), WeekStart::Monday), date("2024-12-16"));
+    assert_eq!(last_full_week_start(date("2024-12-23"), WeekStart::Monday), date("2024-12-16"));
+    assert_eq!(last_full_week_start(date("2024-12-25"), WeekStart::Rolling), date("2024-12-16"));
+
+    // Sunday weeks: Wednesday reviews the Sunday-Saturday week before
+    assert_eq!(last_full_week_start(date("2024-12-25"), WeekStart::Sunday), date("2024-12-15"));
+    assert_eq!(last_full_week_start(date("2024-12-22"), WeekStart::Sunday), date("2024-12-15"));
+  }
+
+  #[tokio::test]
+  async fn test_weekly_review_context_covers_only_the_week() {
+    let db = crate::db::test_pool().await;
+    sqlx::query("UPDATE user_settings SET utc_offset_minutes = 0")
+      .execute(&db)
+      .await
+      .unwrap();
+
+    // Before, during and after the week of Mon 2024-12-16
+    insert_workout(&db, "1", "Run", "2024-12-10T07:00:00Z", Some(50.0)).await;
+    insert_workout(&db, "2", "Ride", "2024-12-16T17:30:00Z", Some(40.0)).await;
+    insert_workout(&db, "3", "Run", "2024-12-18T07:00:00Z", Some(60.0)).await;
+    insert_workout(&db, "4", "WeightTraining", "2024-12-18T18:00:00Z", None).await;
+    insert_workout(&db, "5", "Run", "2024-12-23T07:00:00Z", Some(70.0)).await;
+
+    for created_at in ["2024-12-18 08:00:00", "2024-12-24 08:00:00"] {
+      sqlx::query(
+        "INSERT INTO progression_history (dimension_name, previous_value, new_value, change_type, created_at)
+         VALUES ('long_run', '75', '80', 'progress', ?1)",
+      )
+      .bind(created_at)
+      .execute(&db)
+      .await
+      .unwrap();
+    }
+
+    let plan_json = r#"{"days": [
+      {"date": "2024-12-16", "activity_type": "ride", "duration_min": 45, "intensity": "Z2"},
+      {"date": "2024-12-17", "activity_type": "run", "duration_min": 35, "intensity": "Z2"},
+      {"date": "2024-12-23", "activity_type": "run", "duration_min": 35, "intensity": "Z2"}
+    ]}"#;
+    sqlx::query(
+      "INSERT INTO training_plans (start_date, end_date, weeks, plan_json) VALUES ('2024-12-16', '2024-12-29', 2, ?1)",
+    )
+    .bind(plan_json)
+    .execute(&db)
+    .await
+    .unwrap();
+
+    let week_start = NaiveDate::from_ymd_opt(2024, 12, 16).unwrap();
+    let context = build_weekly_review_context(&db, week_start).await.unwrap();
+
+    assert_eq!(context.week_end, "2024-12-22");
+    assert_eq!(context.summary.sessions, 3);
+    assert_eq!(context.summary.training_days, 2);
+    assert_eq!(context.summary.strength_sessions, 1);
+    let dates: Vec<&str> = context.sessions.iter().map(|s| s.date.as_str()).collect();
+    assert_eq!(dates, vec!["2024-12-16", "2024-12-18", "2024-12-18"]);
+
+    // ATL at the start holds the Dec 10 run; at the end only the week's sessions
+    assert_eq!(context.pmc.atl_start, Some(50.0));
+    assert_eq!(context.pmc.atl_end, Some(100.0));
+    assert!(context.pmc.ctl_delta.unwrap() > 0.0);
+
+    // Only the change recorded inside the week
+    assert_eq!(context.progression_changes.len(), 1);
+    assert_eq!(context.progression_changes[0].new_value, "80");
+
+    // Plan days outside the week are left out
+    let adherence = context.plan_adherence.unwrap();
+    assert_eq!(adherence.days.len(), 2);
+    assert_eq!(adherence.completed, 1);
+    assert_eq!(adherence.missed, 1);
+  }
+}
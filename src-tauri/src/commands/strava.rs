@@ -1,14 +1,29 @@
+use crate::commands::analysis::compute_pending_metrics;
 use crate::db::AppState;
 use crate::strava::{
-  build_auth_url, downsample_streams, exchange_code_for_tokens, fetch_activities,
-  fetch_activity_streams, refresh_tokens, wait_for_callback, StravaActivity, StravaConfig,
-  StravaError, StravaTokens,
+  build_auth_url, downsample_streams, exchange_code_for_tokens, fetch_activities, fetch_activity,
+  fetch_activity_streams, fetch_gear, gear_kind, refresh_tokens, wait_for_callback,
+  StravaActivity, StravaConfig, StravaError, StravaGear, StravaStream, StravaTokens, WorkoutSamples,
+  DEFAULT_CALLBACK_TIMEOUT_SECONDS, SAMPLE_INTERVAL_SECONDS,
 };
 use chrono::Utc;
+use futures_util::stream::{self, StreamExt};
 use serde::Serialize;
+use std::future::Future;
 use std::sync::Arc;
 use tauri::State;
 
+/// Simultaneous stream requests during sync. Kept low so a 50-activity
+/// first sync stays well inside Strava's 100 requests / 15 min limit.
+const STREAM_FETCH_CONCURRENCY: usize = 4;
+
+/// Most earlier activities still waiting on streams to retry per sync
+const PENDING_STREAM_LIMIT: i64 = 50;
+
+/// Overlapping activities whose duration and distance agree within this
+/// fraction are the same session recorded twice
+const DUPLICATE_TOLERANCE: f64 = 0.10;
+
 /// ---------------------------------------------------------------------------
 /// Start OAuth Flow
 /// ---------------------------------------------------------------------------
@@ -28,12 +43,17 @@ pub async fn strava_start_auth() -> Result<String, StravaError> {
 
 /// Waits for the OAuth callback, exchanges the code for tokens, and stores them.
 /// This should be called immediately after strava_start_auth.
+/// `timeout_seconds` bounds the wait (defaults to 120s).
 #[tauri::command]
-pub async fn strava_complete_auth(state: State<'_, Arc<AppState>>) -> Result<(), StravaError> {
+pub async fn strava_complete_auth(
+  state: State<'_, Arc<AppState>>,
+  timeout_seconds: Option<u64>,
+) -> Result<(), StravaError> {
   let config = StravaConfig::from_env()?;
+  let timeout = timeout_seconds.unwrap_or(DEFAULT_CALLBACK_TIMEOUT_SECONDS);
 
   // Wait for callback (blocking - runs in Tauri's async runtime)
-  let callback = tokio::task::spawn_blocking(|| wait_for_callback(120))
+  let callback = tokio::task::spawn_blocking(move || wait_for_callback(timeout))
     .await
     .map_err(|e| StravaError::Server(e.to_string()))??;
 
@@ -51,7 +71,7 @@ pub async fn strava_complete_auth(state: State<'_, Arc<AppState>>) -> Result<(),
 /// Check Authentication Status
 /// ---------------------------------------------------------------------------
 
-#[derive(Serialize)]
+#[derive(Debug, Serialize)]
 pub struct StravaAuthStatus {
   pub is_authenticated: bool,
   pub expires_at: Option<String>,
@@ -62,7 +82,12 @@ pub struct StravaAuthStatus {
 pub async fn strava_get_auth_status(
   state: State<'_, Arc<AppState>>,
 ) -> Result<StravaAuthStatus, StravaError> {
-  match load_tokens(&state.db).await? {
+  load_auth_status(&state.db).await
+}
+
+/// Helper: Whether Strava tokens are stored, and when they expire
+pub(crate) async fn load_auth_status(db: &crate::db::DbPool) -> Result<StravaAuthStatus, StravaError> {
+  match load_tokens(db).await? {
     Some(tokens) => Ok(StravaAuthStatus {
       is_authenticated: true,
       expires_at: Some(tokens.expires_at.to_rfc3339()),
@@ -184,6 +209,10 @@ async fn load_tokens(db: &crate::db::DbPool) -> Result<Option<StravaTokens>, Str
 pub struct SyncResult {
   pub new_activities: usize,
   pub total_fetched: usize,
+  /// Activities with neither moving nor elapsed time (not stored)
+  pub skipped_no_duration: usize,
+  /// New activities recorded twice (stored, but left out of load)
+  pub duplicates: usize,
 }
 
 /// Sync recent activities from Strava and store them in the database
@@ -191,55 +220,98 @@ pub struct SyncResult {
 pub async fn strava_sync_activities(
   state: State<'_, Arc<AppState>>,
 ) -> Result<SyncResult, StravaError> {
+  sync_strava(&state.db).await
+}
+
+/// Helper: Sync against the Strava API (shared with sync_all)
+pub(crate) async fn sync_strava(db: &crate::db::DbPool) -> Result<SyncResult, StravaError> {
   // Get valid access token (auto-refreshes if needed)
-  let access_token = get_valid_access_token(&state.db).await?;
+  let access_token = get_valid_access_token(db).await?;
+
+  let list_token = access_token.clone();
+  let stream_token = access_token.clone();
+  let result = sync_activities_with(
+    db,
+    // After our last known activity, or all if first sync
+    move |after| async move { fetch_activities(&list_token, after, 50).await },
+    move |id| {
+      let token = stream_token.clone();
+      async move { fetch_activity_streams(&token, id).await }
+    },
+  )
+  .await?;
+
+  // Gear names are cosmetic: a failed lookup is retried on the next sync
+  let gear_token = access_token;
+  if let Err(e) = sync_gear_names(db, move |id| {
+    let token = gear_token.clone();
+    async move { fetch_gear(&token, &id).await }
+  })
+  .await
+  {
+    eprintln!("Failed to look up Strava gear names: {}", e);
+  }
+
+  Ok(result)
+}
 
-  // Get the timestamp of the most recent workout we have
+/// Sync using the given activity-list and stream fetchers (the command
+/// passes the Strava API; sync_all tests pass canned data)
+pub(crate) async fn sync_activities_with<L, LFut, F, Fut>(
+  db: &crate::db::DbPool,
+  list_activities: L,
+  fetch_streams: F,
+) -> Result<SyncResult, StravaError>
+where
+  L: FnOnce(Option<i64>) -> LFut,
+  LFut: Future<Output = Result<Vec<StravaActivity>, StravaError>>,
+  F: Fn(i64) -> Fut,
+  Fut: Future<Output = Result<Vec<StravaStream>, StravaError>>,
+{
+  // Get the timestamp of the most recent Strava workout we have (a newer
+  // imported file mustn't hide Strava activities recorded before it)
   let last_activity_timestamp: Option<i64> = sqlx::query_scalar(
-    "SELECT CAST(strftime('%s', MAX(started_at)) AS INTEGER) FROM workouts",
+    "SELECT CAST(strftime('%s', MAX(started_at)) AS INTEGER) FROM workouts WHERE strava_id NOT GLOB 'file_*'",
   )
-  .fetch_one(&state.db)
+  .fetch_one(db)
   .await
   .map_err(|e| StravaError::Database(e.to_string()))?;
 
-  // Fetch activities from Strava (after our last known activity, or all if first sync)
-  let activities = fetch_activities(&access_token, last_activity_timestamp, 50).await?;
+  let activities = list_activities(last_activity_timestamp).await?;
   let total_fetched = activities.len();
 
-  // Store each activity in the database
-  let mut new_count = 0;
+  // Store every activity first, remembering which ones are new
+  let mut new_ids = Vec::new();
+  let mut skipped_no_duration = 0;
+  let mut duplicates = 0;
   for activity in &activities {
-    let inserted = save_activity(&state.db, activity).await?;
+    // A zero-duration workout would contribute nothing and break every metric
+    if activity.duration_seconds().is_none() {
+      eprintln!(
+        "Skipping Strava activity {} ({}): no moving or elapsed time",
+        activity.id, activity.name
+      );
+      skipped_no_duration += 1;
+      continue;
+    }
+    let inserted = save_activity(db, activity).await?;
     if inserted {
-      new_count += 1;
-
-      // Fetch and store streams for new activities (10-second intervals)
-      println!("Fetching streams for activity {}", activity.id);
-      match fetch_activity_streams(&access_token, activity.id).await {
-        Ok(streams) => {
-          if !streams.is_empty() {
-            let samples = downsample_streams(&streams, 10);
-            if !samples.is_empty() {
-              save_activity_samples(&state.db, activity.id, &samples).await?;
-              println!(
-                "  Stored {} HR samples, {} watts samples, {} pace samples",
-                samples.hr.len(),
-                samples.watts.len(),
-                samples.pace.len()
-              );
-            }
-          }
-        }
-        Err(e) => {
-          // Don't fail the whole sync if streams fail for one activity
-          eprintln!("Warning: Failed to fetch streams for activity {}: {}", activity.id, e);
-        }
+      if let Some(original) = mark_if_duplicate(db, &activity.id.to_string()).await? {
+        println!("Strava activity {} duplicates workout {}; excluded from load", activity.id, original);
+        duplicates += 1;
       }
+      new_ids.push(activity.id);
     }
   }
+  let new_count = new_ids.len();
+
+  // Then fetch streams concurrently: the new activities, plus earlier ones
+  // never fetched (deferred by a rate limit or a failed request)
+  let stream_ids = with_pending_stream_ids(db, new_ids).await?;
+  sync_activity_streams(db, stream_ids, STREAM_FETCH_CONCURRENCY, fetch_streams).await?;
 
   // Update last sync time
-  update_sync_time(&state.db).await?;
+  update_sync_time(db).await?;
 
   println!(
     "Strava sync complete: {} new activities (fetched {})",
@@ -249,13 +321,230 @@ pub async fn strava_sync_activities(
   Ok(SyncResult {
     new_activities: new_count,
     total_fetched,
+    skipped_no_duration,
+    duplicates,
   })
 }
 
+/// `new_ids` followed by older activities whose streams were never fetched.
+/// Activities already attempted (even with no streams) aren't retried here.
+async fn with_pending_stream_ids(db: &crate::db::DbPool, mut ids: Vec<i64>) -> Result<Vec<i64>, StravaError> {
+  let pending: Vec<String> = sqlx::query_scalar(
+    r#"
+    SELECT strava_id FROM workouts
+    WHERE samples_fetched_at IS NULL AND strava_id IS NOT NULL
+    ORDER BY started_at DESC
+    LIMIT ?1
+    "#,
+  )
+  .bind(PENDING_STREAM_LIMIT)
+  .fetch_all(db)
+  .await
+  .map_err(|e| StravaError::Database(e.to_string()))?;
+
+  for id in pending.iter().filter_map(|id| id.parse::<i64>().ok()) {
+    if !ids.contains(&id) {
+      ids.push(id);
+    }
+  }
+  Ok(ids)
+}
+
+/// Fetch streams for the given activities with at most `concurrency` requests
+/// in flight, storing 10-second samples as each one completes. An activity
+/// with no streams (manual entry, 404) is still stamped as fetched so later
+/// syncs don't ask again. Returns the number of activities whose samples were stored.
+async fn sync_activity_streams<F, Fut>(
+  db: &crate::db::DbPool,
+  activity_ids: Vec<i64>,
+  concurrency: usize,
+  fetch: F,
+) -> Result<usize, StravaError>
+where
+  F: Fn(i64) -> Fut,
+  Fut: Future<Output = Result<Vec<StravaStream>, StravaError>>,
+{
+  let mut results = stream::iter(activity_ids)
+    .map(|id| {
+      let request = fetch(id);
+      async move { (id, request.await) }
+    })
+    .buffer_unordered(concurrency.max(1));
+
+  let mut stored = 0;
+  while let Some((activity_id, result)) = results.next().await {
+    match result {
+      Ok(streams) => {
+        let samples = downsample_streams(&streams, SAMPLE_INTERVAL_SECONDS);
+        if samples.is_empty() {
+          mark_samples_fetched(db, activity_id).await?;
+          continue;
+        }
+        save_activity_samples(db, &activity_id.to_string(), &samples).await?;
+        stored += 1;
+        println!(
+          "  Stored streams for activity {}: {} HR samples, {} watts samples, {} pace samples",
+          activity_id,
+          samples.hr.len(),
+          samples.watts.len(),
+          samples.pace.len()
+        );
+      }
+      Err(StravaError::RateLimited) => {
+        // Stop issuing requests; remaining activities get streams on a later sync
+        eprintln!("Warning: Strava rate limit hit, deferring remaining stream fetches");
+        break;
+      }
+      Err(e) => {
+        // Don't fail the whole sync if streams fail for one activity
+        eprintln!("Warning: Failed to fetch streams for activity {}: {}", activity_id, e);
+      }
+    }
+  }
+
+  Ok(stored)
+}
+
+/// ---------------------------------------------------------------------------
+/// Stream Backfill
+/// ---------------------------------------------------------------------------
+
+#[derive(Serialize)]
+pub struct StreamBackfillResult {
+  /// Activities fetched before without any streams
+  pub attempted: usize,
+  /// How many of them now have samples
+  pub stored: usize,
+}
+
+/// Retry streams for activities that came back empty on an earlier sync
+/// (regular syncs skip them once stamped)
+#[tauri::command]
+pub async fn strava_backfill_streams(
+  state: State<'_, Arc<AppState>>,
+) -> Result<StreamBackfillResult, StravaError> {
+  let access_token = get_valid_access_token(&state.db).await?;
+  backfill_streams_with(&state.db, move |id| {
+    let token = access_token.clone();
+    async move { fetch_activity_streams(&token, id).await }
+  })
+  .await
+}
+
+/// Helper: Re-fetch streams for stamped activities that have no samples
+pub(crate) async fn backfill_streams_with<F, Fut>(
+  db: &crate::db::DbPool,
+  fetch_streams: F,
+) -> Result<StreamBackfillResult, StravaError>
+where
+  F: Fn(i64) -> Fut,
+  Fut: Future<Output = Result<Vec<StravaStream>, StravaError>>,
+{
+  let ids: Vec<String> = sqlx::query_scalar(
+    r#"
+    SELECT strava_id FROM workouts
+    WHERE samples_fetched_at IS NOT NULL AND samples_json IS NULL AND samples_blob IS NULL
+      AND strava_id IS NOT NULL
+    ORDER BY started_at DESC
+    "#,
+  )
+  .fetch_all(db)
+  .await
+  .map_err(|e| StravaError::Database(e.to_string()))?;
+  let ids: Vec<i64> = ids.iter().filter_map(|id| id.parse().ok()).collect();
+
+  let attempted = ids.len();
+  let stored = sync_activity_streams(db, ids, STREAM_FETCH_CONCURRENCY, fetch_streams).await?;
+  Ok(StreamBackfillResult { attempted, stored })
+}
+
+/// ---------------------------------------------------------------------------
+/// Single Activity Fetch
+/// ---------------------------------------------------------------------------
+
+/// Fetch one activity by Strava id (e.g. one the regular sync missed, or
+/// one edited on Strava since), store it with its streams and compute its
+/// metrics. Returns the workout's local id.
+#[tauri::command]
+pub async fn strava_fetch_activity(
+  state: State<'_, Arc<AppState>>,
+  activity_id: i64,
+) -> Result<i64, StravaError> {
+  let access_token = get_valid_access_token(&state.db).await?;
+
+  let activity_token = access_token.clone();
+  fetch_activity_with(
+    &state.db,
+    activity_id,
+    move |id| async move { fetch_activity(&activity_token, id).await },
+    move |id| {
+      let token = access_token.clone();
+      async move { fetch_activity_streams(&token, id).await }
+    },
+  )
+  .await
+}
+
+/// Helper: Store one activity from the given fetchers. An activity already
+/// stored is overwritten with the fresh summary and its metrics recomputed.
+pub(crate) async fn fetch_activity_with<A, AFut, F, Fut>(
+  db: &crate::db::DbPool,
+  activity_id: i64,
+  fetch: A,
+  fetch_streams: F,
+) -> Result<i64, StravaError>
+where
+  A: FnOnce(i64) -> AFut,
+  AFut: Future<Output = Result<StravaActivity, StravaError>>,
+  F: Fn(i64) -> Fut,
+  Fut: Future<Output = Result<Vec<StravaStream>, StravaError>>,
+{
+  let activity = fetch(activity_id).await?;
+  if activity.duration_seconds().is_none() {
+    return Err(StravaError::InvalidActivity(format!(
+      "Activity {} has no moving or elapsed time",
+      activity.id
+    )));
+  }
+
+  let source_id = activity.id.to_string();
+  if save_activity(db, &activity).await? {
+    if let Some(original) = mark_if_duplicate(db, &source_id).await? {
+      println!("Strava activity {} duplicates workout {}; excluded from load", activity.id, original);
+    }
+  } else {
+    update_activity(db, &activity).await?;
+  }
+
+  sync_activity_streams(db, vec![activity.id], 1, fetch_streams).await?;
+
+  compute_pending_metrics(db).await.map_err(StravaError::Database)?;
+
+  let workout_id: i64 = sqlx::query_scalar("SELECT id FROM workouts WHERE strava_id = ?1")
+    .bind(&source_id)
+    .fetch_one(db)
+    .await
+    .map_err(|e| StravaError::Database(e.to_string()))?;
+
+  println!("Fetched Strava activity {} as workout {}", activity.id, workout_id);
+
+  Ok(workout_id)
+}
+
 /// Save a single activity to the database (returns true if inserted, false if already exists)
 async fn save_activity(
   db: &crate::db::DbPool,
   activity: &StravaActivity,
+) -> Result<bool, StravaError> {
+  save_activity_as(db, &activity.id.to_string(), activity).await
+}
+
+/// Save an activity under `source_id` (the Strava id, or e.g. "file_..." for
+/// imported files). Returns true if inserted, false if already stored.
+pub(crate) async fn save_activity_as(
+  db: &crate::db::DbPool,
+  source_id: &str,
+  activity: &StravaActivity,
 ) -> Result<bool, StravaError> {
   let raw_json = serde_json::to_string(activity).unwrap_or_default();
 
@@ -264,16 +553,17 @@ async fn save_activity(
     INSERT INTO workouts (
       strava_id, activity_type, started_at, duration_seconds,
       distance_meters, elevation_gain_meters, average_heartrate,
-      max_heartrate, average_watts, suffer_score, raw_json
+      max_heartrate, average_watts, suffer_score, raw_json, is_indoor,
+      gear_id, device_watts, name, description
     )
-    VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)
+    VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16)
     ON CONFLICT(strava_id) DO NOTHING
     "#,
   )
-  .bind(activity.id.to_string())
+  .bind(source_id)
   .bind(&activity.activity_type)
   .bind(&activity.start_date)
-  .bind(activity.moving_time)
+  .bind(activity.duration_seconds())
   .bind(activity.distance)
   .bind(activity.total_elevation_gain)
   .bind(activity.average_heartrate.map(|hr| hr as i64))
@@ -281,6 +571,11 @@ async fn save_activity(
   .bind(activity.average_watts)
   .bind(activity.suffer_score)
   .bind(&raw_json)
+  .bind(activity.is_indoor())
+  .bind(&activity.gear_id)
+  .bind(activity.device_watts)
+  .bind(&activity.name)
+  .bind(&activity.description)
   .execute(db)
   .await
   .map_err(|e| StravaError::Database(e.to_string()))?;
@@ -288,6 +583,87 @@ async fn save_activity(
   Ok(result.rows_affected() > 0)
 }
 
+/// Overwrite a stored activity's summary fields and clear its metrics so
+/// they are recomputed
+async fn update_activity(db: &crate::db::DbPool, activity: &StravaActivity) -> Result<(), StravaError> {
+  let raw_json = serde_json::to_string(activity).unwrap_or_default();
+
+  sqlx::query(
+    r#"
+    UPDATE workouts
+    SET activity_type = ?2, started_at = ?3, duration_seconds = ?4,
+        distance_meters = ?5, elevation_gain_meters = ?6, average_heartrate = ?7,
+        max_heartrate = ?8, average_watts = ?9, suffer_score = ?10, raw_json = ?11,
+        is_indoor = ?12, gear_id = ?13, device_watts = ?14, name = ?15, description = ?16,
+        metrics_computed_at = NULL
+    WHERE strava_id = ?1
+    "#,
+  )
+  .bind(activity.id.to_string())
+  .bind(&activity.activity_type)
+  .bind(&activity.start_date)
+  .bind(activity.duration_seconds())
+  .bind(activity.distance)
+  .bind(activity.total_elevation_gain)
+  .bind(activity.average_heartrate.map(|hr| hr as i64))
+  .bind(activity.max_heartrate.map(|hr| hr as i64))
+  .bind(activity.average_watts)
+  .bind(activity.suffer_score)
+  .bind(&raw_json)
+  .bind(activity.is_indoor())
+  .bind(&activity.gear_id)
+  .bind(activity.device_watts)
+  .bind(&activity.name)
+  .bind(&activity.description)
+  .execute(db)
+  .await
+  .map_err(|e| StravaError::Database(e.to_string()))?;
+
+  Ok(())
+}
+
+/// Point a newly saved activity at an earlier workout it duplicates: the
+/// time ranges overlap and duration and distance agree within
+/// DUPLICATE_TOLERANCE (both devices log the same session). Returns the
+/// original's id.
+pub(crate) async fn mark_if_duplicate(db: &crate::db::DbPool, source_id: &str) -> Result<Option<i64>, StravaError> {
+  let original: Option<i64> = sqlx::query_scalar(
+    r#"
+    SELECT o.id
+    FROM workouts w
+    JOIN workouts o ON o.id != w.id AND o.duplicate_of IS NULL
+    WHERE w.strava_id = ?1
+      AND o.duration_seconds > 0
+      AND w.duration_seconds > 0
+      AND julianday(o.started_at) < julianday(w.started_at) + w.duration_seconds / 86400.0
+      AND julianday(w.started_at) < julianday(o.started_at) + o.duration_seconds / 86400.0
+      AND ABS(o.duration_seconds - w.duration_seconds) <= ?2 * MAX(o.duration_seconds, w.duration_seconds)
+      AND (
+        (COALESCE(o.distance_meters, 0) = 0 AND COALESCE(w.distance_meters, 0) = 0)
+        OR ABS(o.distance_meters - w.distance_meters) <= ?2 * MAX(o.distance_meters, w.distance_meters)
+      )
+    ORDER BY o.id
+    LIMIT 1
+    "#,
+  )
+  .bind(source_id)
+  .bind(DUPLICATE_TOLERANCE)
+  .fetch_optional(db)
+  .await
+  .map_err(|e| StravaError::Database(e.to_string()))?;
+
+  if let Some(original) = original {
+    sqlx::query("UPDATE workouts SET duplicate_of = ?1 WHERE strava_id = ?2")
+      .bind(original)
+      .bind(source_id)
+      .execute(db)
+      .await
+      .map_err(|e| StravaError::Database(e.to_string()))?;
+  }
+
+  Ok(original)
+}
+
 /// Update the last sync time for Strava
 async fn update_sync_time(db: &crate::db::DbPool) -> Result<(), StravaError> {
   sqlx::query(
@@ -300,27 +676,700 @@ async fn update_sync_time(db: &crate::db::DbPool) -> Result<(), StravaError> {
   Ok(())
 }
 
-/// Save downsampled stream data for an activity
-async fn save_activity_samples(
+/// Save downsampled stream data for an activity, gzip-compressed. A new HR
+/// stream clears metrics_computed_at so rTSS is recomputed from the stream.
+pub(crate) async fn save_activity_samples(
   db: &crate::db::DbPool,
-  strava_id: i64,
-  samples: &crate::strava::WorkoutSamples,
+  source_id: &str,
+  samples: &WorkoutSamples,
 ) -> Result<(), StravaError> {
-  let samples_json = samples.to_json();
+  let samples_blob = samples.to_compressed();
 
   sqlx::query(
     r#"
     UPDATE workouts
-    SET samples_json = ?1, samples_fetched_at = ?2
+    SET samples_blob = ?1, samples_compressed = TRUE, samples_json = NULL, samples_fetched_at = ?2,
+        metrics_computed_at = CASE WHEN ?4 THEN NULL ELSE metrics_computed_at END
     WHERE strava_id = ?3
     "#,
   )
-  .bind(&samples_json)
+  .bind(&samples_blob)
   .bind(Utc::now())
-  .bind(strava_id.to_string())
+  .bind(source_id)
+  .bind(!samples.hr.is_empty())
   .execute(db)
   .await
   .map_err(|e| StravaError::Database(e.to_string()))?;
 
   Ok(())
 }
+
+/// ---------------------------------------------------------------------------
+/// Sample Compaction
+/// ---------------------------------------------------------------------------
+
+/// Compress samples stored as plain JSON (before compression on write) and
+/// reclaim the freed space. Returns the number of workouts compacted.
+#[tauri::command]
+pub async fn compact_workout_samples(state: State<'_, Arc<AppState>>) -> Result<usize, StravaError> {
+  compact_stored_samples(&state.db).await
+}
+
+/// Helper: Move every legacy samples_json into a compressed samples_blob.
+/// Unparseable JSON is left where it is.
+pub(crate) async fn compact_stored_samples(db: &crate::db::DbPool) -> Result<usize, StravaError> {
+  let rows: Vec<(i64, String)> =
+    sqlx::query_as("SELECT id, samples_json FROM workouts WHERE samples_json IS NOT NULL AND samples_blob IS NULL")
+      .fetch_all(db)
+      .await
+      .map_err(|e| StravaError::Database(e.to_string()))?;
+
+  let mut compacted = 0;
+  for (id, json) in rows {
+    let Some(samples) = WorkoutSamples::from_stored(Some(&json), None, false) else {
+      eprintln!("Warning: Skipping unreadable samples for workout {}", id);
+      continue;
+    };
+    sqlx::query(
+      "UPDATE workouts SET samples_blob = ?1, samples_compressed = TRUE, samples_json = NULL WHERE id = ?2",
+    )
+    .bind(samples.to_compressed())
+    .bind(id)
+    .execute(db)
+    .await
+    .map_err(|e| StravaError::Database(e.to_string()))?;
+    compacted += 1;
+  }
+
+  if compacted > 0 {
+    sqlx::query("VACUUM")
+      .execute(db)
+      .await
+      .map_err(|e| StravaError::Database(e.to_string()))?;
+    println!("Compressed stored samples for {} workouts", compacted);
+  }
+
+  Ok(compacted)
+}
+
+/// Record that streams were requested but there were none to store
+async fn mark_samples_fetched(db: &crate::db::DbPool, strava_id: i64) -> Result<(), StravaError> {
+  sqlx::query("UPDATE workouts SET samples_fetched_at = ?1 WHERE strava_id = ?2")
+    .bind(Utc::now())
+    .bind(strava_id.to_string())
+    .execute(db)
+    .await
+    .map_err(|e| StravaError::Database(e.to_string()))?;
+
+  Ok(())
+}
+
+/// ---------------------------------------------------------------------------
+/// Gear Mileage
+/// ---------------------------------------------------------------------------
+
+#[derive(Debug, Serialize)]
+pub struct GearMileage {
+  pub gear_id: String,
+  /// Strava gear name (None until looked up)
+  pub name: Option<String>,
+  /// "shoe" or "bike"
+  pub kind: String,
+  /// Synced distance, or Strava's total when that's higher
+  pub km: f64,
+  pub activities: i64,
+  /// Shoes past the replacement threshold (bikes are never flagged)
+  pub needs_replacement: bool,
+}
+
+/// Look up gear seen on workouts that is not in the gear table yet, or that
+/// has been used since it was last fetched (its Strava total has moved on),
+/// one request per gear id. Stops at the first failed lookup.
+pub(crate) async fn sync_gear_names<F, Fut>(db: &crate::db::DbPool, fetch: F) -> Result<usize, StravaError>
+where
+  F: Fn(String) -> Fut,
+  Fut: Future<Output = Result<StravaGear, StravaError>>,
+{
+  let unknown: Vec<String> = sqlx::query_scalar(
+    "SELECT w.gear_id FROM workouts w
+     LEFT JOIN gear g ON g.id = w.gear_id
+     WHERE w.gear_id IS NOT NULL
+     GROUP BY w.gear_id
+     HAVING MAX(g.id) IS NULL
+         OR MAX(g.distance_meters) IS NULL
+         OR datetime(MAX(w.started_at)) > datetime(MAX(g.fetched_at))",
+  )
+  .fetch_all(db)
+  .await
+  .map_err(|e| StravaError::Database(e.to_string()))?;
+
+  for gear_id in &unknown {
+    let gear = fetch(gear_id.clone()).await?;
+    sqlx::query(
+      "INSERT INTO gear (id, name, kind, distance_meters) VALUES (?1, ?2, ?3, ?4)
+       ON CONFLICT(id) DO UPDATE SET name = excluded.name, kind = excluded.kind,
+         distance_meters = excluded.distance_meters, fetched_at = CURRENT_TIMESTAMP",
+    )
+    .bind(gear_id)
+    .bind(&gear.name)
+    .bind(gear_kind(gear_id))
+    .bind(gear.distance)
+    .execute(db)
+    .await
+    .map_err(|e| StravaError::Database(e.to_string()))?;
+  }
+  Ok(unknown.len())
+}
+
+/// Distance per shoe/bike, flagging shoes past the replacement threshold
+#[tauri::command]
+pub async fn get_gear_mileage(state: State<'_, Arc<AppState>>) -> Result<Vec<GearMileage>, StravaError> {
+  load_gear_mileage(&state.db).await
+}
+
+/// Helper: Sum distance per gear id (second-device duplicates excluded),
+/// highest mileage first. Workouts synced before gear_id existed have none,
+/// so Strava's own total for the gear is used when it is higher.
+pub(crate) async fn load_gear_mileage(db: &crate::db::DbPool) -> Result<Vec<GearMileage>, StravaError> {
+  let settings = crate::commands::analysis::load_user_settings(db)
+    .await
+    .map_err(StravaError::Database)?;
+
+  let rows: Vec<(String, Option<String>, Option<String>, f64, i64)> = sqlx::query_as(
+    r#"
+    SELECT w.gear_id, g.name, g.kind,
+           MAX(COALESCE(SUM(w.distance_meters), 0.0), COALESCE(MAX(g.distance_meters), 0.0)) / 1000.0 AS km,
+           COUNT(*) AS activities
+    FROM workouts w
+    LEFT JOIN gear g ON g.id = w.gear_id
+    WHERE w.gear_id IS NOT NULL AND w.duplicate_of IS NULL
+    GROUP BY w.gear_id
+    ORDER BY km DESC
+    "#,
+  )
+  .fetch_all(db)
+  .await
+  .map_err(|e| StravaError::Database(e.to_string()))?;
+
+  Ok(
+    rows
+      .into_iter()
+      .map(|(gear_id, name, kind, km, activities)| {
+        // Gear not looked up yet: the id prefix still tells shoes from bikes
+        let kind = kind.unwrap_or_else(|| gear_kind(&gear_id).to_string());
+        let needs_replacement = kind == "shoe" && km >= settings.shoe_replacement_km;
+        GearMileage { gear_id, name, kind, km, activities, needs_replacement }
+      })
+      .collect(),
+  )
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::sync::atomic::{AtomicUsize, Ordering};
+
+  fn mock_streams(activity_id: i64) -> Vec<StravaStream> {
+    let make_stream = |stream_type: &str, data: Vec<serde_json::Value>| StravaStream {
+      stream_type: stream_type.to_string(),
+      data,
+      series_type: None,
+      original_size: None,
+      resolution: None,
+    };
+
+    vec![
+      make_stream("time", (0..60).map(|t| serde_json::json!(t)).collect()),
+      make_stream("heartrate", (0..60).map(|_| serde_json::json!(140 + activity_id)).collect()),
+    ]
+  }
+
+  async fn stored_samples(db: &crate::db::DbPool, strava_id: i64) -> Option<WorkoutSamples> {
+    let id: i64 = sqlx::query_scalar("SELECT id FROM workouts WHERE strava_id = ?1")
+      .bind(strava_id.to_string())
+      .fetch_one(db)
+      .await
+      .unwrap();
+    crate::commands::analysis::load_workout_samples(db, id).await.unwrap()
+  }
+
+  async fn insert_workout(db: &crate::db::DbPool, strava_id: i64) {
+    sqlx::query(
+      "INSERT INTO workouts (strava_id, activity_type, started_at) VALUES (?1, 'Run', '2024-12-01T07:00:00Z')",
+    )
+    .bind(strava_id.to_string())
+    .execute(db)
+    .await
+    .unwrap();
+  }
+
+  fn activity(id: i64, moving_time: i64, elapsed_time: i64) -> StravaActivity {
+    StravaActivity {
+      id,
+      name: "Virtual run".to_string(),
+      activity_type: "Run".to_string(),
+      start_date: "2024-12-01T07:00:00Z".parse().unwrap(),
+      elapsed_time,
+      moving_time,
+      distance: Some(10_000.0),
+      total_elevation_gain: None,
+      average_heartrate: Some(150.0),
+      max_heartrate: None,
+      average_watts: None,
+      device_watts: None,
+      suffer_score: None,
+      sport_type: None,
+      trainer: false,
+      gear_id: None,
+      description: None,
+    }
+  }
+
+  #[tokio::test]
+  async fn test_zero_moving_time_falls_back_to_elapsed_time() {
+    let db = crate::db::test_pool().await;
+    assert!(save_activity(&db, &activity(42, 0, 3600)).await.unwrap());
+
+    let duration: Option<i64> =
+      sqlx::query_scalar("SELECT duration_seconds FROM workouts WHERE strava_id = '42'")
+        .fetch_one(&db)
+        .await
+        .unwrap();
+    assert_eq!(duration, Some(3600));
+
+    let settings = crate::analysis::UserSettings { max_hr: Some(190), ..Default::default() };
+    let metrics =
+      crate::analysis::WorkoutMetrics::compute("Run", duration, Some(10_000.0), Some(150), None, &[], &settings);
+    assert_eq!(metrics.pace_min_per_km, Some(6.0));
+    assert!(metrics.rtss.unwrap() > 0.0);
+  }
+
+  #[tokio::test]
+  async fn test_indoor_flag_synced_and_suppresses_speed() {
+    let db = crate::db::test_pool().await;
+    let virtual_ride = StravaActivity {
+      activity_type: "Ride".to_string(),
+      sport_type: Some("VirtualRide".to_string()),
+      average_watts: Some(200.0),
+      ..activity(7, 3600, 3600)
+    };
+    let trainer_ride = StravaActivity { activity_type: "Ride".to_string(), trainer: true, ..activity(8, 3600, 3600) };
+    let road_ride = StravaActivity { activity_type: "Ride".to_string(), ..activity(9, 3600, 3600) };
+    for a in [&virtual_ride, &trainer_ride, &road_ride] {
+      assert!(save_activity(&db, a).await.unwrap());
+    }
+
+    crate::commands::analysis::compute_pending_metrics(&db).await.unwrap();
+
+    let rows: Vec<(String, bool, Option<f64>)> =
+      sqlx::query_as("SELECT strava_id, is_indoor, speed_kmh FROM workouts ORDER BY strava_id")
+        .fetch_all(&db)
+        .await
+        .unwrap();
+    assert_eq!(
+      rows,
+      vec![
+        ("7".to_string(), true, None),
+        ("8".to_string(), true, None),
+        ("9".to_string(), false, Some(10.0)),
+      ]
+    );
+  }
+
+  #[test]
+  fn test_activity_duration_prefers_moving_time() {
+    assert_eq!(activity(1, 3000, 3600).duration_seconds(), Some(3000));
+    assert_eq!(activity(1, 0, 3600).duration_seconds(), Some(3600));
+    assert_eq!(activity(1, 0, 0).duration_seconds(), None);
+  }
+
+  #[tokio::test]
+  async fn test_concurrent_stream_sync_saves_every_activity() {
+    let db = crate::db::test_pool().await;
+    let ids: Vec<i64> = (1..=10).collect();
+    for id in &ids {
+      insert_workout(&db, *id).await;
+    }
+
+    let in_flight = Arc::new(AtomicUsize::new(0));
+    let max_in_flight = Arc::new(AtomicUsize::new(0));
+    let fetched = Arc::new(AtomicUsize::new(0));
+
+    let stored = sync_activity_streams(&db, ids.clone(), 4, |id| {
+      let in_flight = in_flight.clone();
+      let max_in_flight = max_in_flight.clone();
+      let fetched = fetched.clone();
+      async move {
+        let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+        max_in_flight.fetch_max(now, Ordering::SeqCst);
+
+        // Earlier IDs take longer, so completions arrive out of order
+        for _ in 0..(20 - id) {
+          tokio::task::yield_now().await;
+        }
+
+        in_flight.fetch_sub(1, Ordering::SeqCst);
+        fetched.fetch_add(1, Ordering::SeqCst);
+        Ok(mock_streams(id))
+      }
+    })
+    .await
+    .unwrap();
+
+    assert_eq!(stored, ids.len());
+    assert_eq!(fetched.load(Ordering::SeqCst), ids.len());
+    assert!(max_in_flight.load(Ordering::SeqCst) <= 4);
+
+    for id in ids {
+      let samples = stored_samples(&db, id).await.expect("samples should be stored");
+      assert!(samples.hr.iter().all(|&hr| hr == 140 + id));
+    }
+  }
+
+  #[tokio::test]
+  async fn test_stream_sync_skips_failed_activity() {
+    let db = crate::db::test_pool().await;
+    for id in 1..=3 {
+      insert_workout(&db, id).await;
+    }
+
+    let stored = sync_activity_streams(&db, vec![1, 2, 3], 4, |id| async move {
+      if id == 2 {
+        Err(StravaError::OAuth("boom".into()))
+      } else {
+        Ok(mock_streams(id))
+      }
+    })
+    .await
+    .unwrap();
+
+    assert_eq!(stored, 2);
+  }
+
+  #[tokio::test]
+  async fn test_fetch_single_activity_lands_fully_populated() {
+    let db = crate::db::test_pool().await;
+    sqlx::query("UPDATE user_settings SET max_hr = 190").execute(&db).await.unwrap();
+
+    let workout_id = fetch_activity_with(
+      &db,
+      42,
+      |id| async move { Ok(activity(id, 3600, 3600)) },
+      |id| async move { Ok(mock_streams(id)) },
+    )
+    .await
+    .unwrap();
+
+    let row: (i64, Option<f64>, Option<f64>, Option<String>) = sqlx::query_as(
+      "SELECT id, pace_min_per_km, rtss, metrics_computed_at FROM workouts WHERE strava_id = '42'",
+    )
+    .fetch_one(&db)
+    .await
+    .unwrap();
+    assert_eq!(row.0, workout_id);
+    assert!(stored_samples(&db, 42).await.expect("samples should be stored").hr.contains(&182));
+    assert_eq!(row.1, Some(6.0));
+    assert!(row.2.unwrap() > 0.0);
+    assert!(row.3.is_some());
+
+    // Fetching it again after an edit on Strava updates the same workout
+    let refetched = fetch_activity_with(
+      &db,
+      42,
+      |id| async move { Ok(StravaActivity { distance: Some(12_000.0), ..activity(id, 3600, 3600) }) },
+      |id| async move { Ok(mock_streams(id)) },
+    )
+    .await
+    .unwrap();
+    assert_eq!(refetched, workout_id);
+
+    let pace: Option<f64> = sqlx::query_scalar("SELECT pace_min_per_km FROM workouts WHERE strava_id = '42'")
+      .fetch_one(&db)
+      .await
+      .unwrap();
+    assert_eq!(pace, Some(5.0));
+
+    // No moving or elapsed time: rejected as unusable, not an auth failure
+    let result = fetch_activity_with(
+      &db,
+      43,
+      |id| async move { Ok(activity(id, 0, 0)) },
+      |id| async move { Ok(mock_streams(id)) },
+    )
+    .await;
+    assert!(matches!(result, Err(StravaError::InvalidActivity(_))));
+    let stored: bool = sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM workouts WHERE strava_id = '43')")
+      .fetch_one(&db)
+      .await
+      .unwrap();
+    assert!(!stored);
+  }
+
+  #[tokio::test]
+  async fn test_streamless_activity_stamped_and_not_refetched() {
+    let db = crate::db::test_pool().await;
+    let fetched = Arc::new(AtomicUsize::new(0));
+    let no_streams = |fetched: Arc<AtomicUsize>| {
+      move |_id: i64| {
+        let fetched = fetched.clone();
+        async move {
+          fetched.fetch_add(1, Ordering::SeqCst);
+          Ok(vec![])
+        }
+      }
+    };
+
+    let result = sync_activities_with(
+      &db,
+      |_| async { Ok(vec![activity(11, 3600, 3600)]) },
+      no_streams(fetched.clone()),
+    )
+    .await
+    .unwrap();
+    assert_eq!(result.new_activities, 1);
+    assert_eq!(fetched.load(Ordering::SeqCst), 1);
+
+    let fetched_at: Option<String> =
+      sqlx::query_scalar("SELECT samples_fetched_at FROM workouts WHERE strava_id = '11'")
+        .fetch_one(&db)
+        .await
+        .unwrap();
+    assert!(fetched_at.is_some());
+    assert!(stored_samples(&db, 11).await.is_none());
+
+    // Next sync: already attempted, so no new request
+    sync_activities_with(
+      &db,
+      |_| async { Ok(vec![activity(11, 3600, 3600)]) },
+      no_streams(fetched.clone()),
+    )
+    .await
+    .unwrap();
+    assert_eq!(fetched.load(Ordering::SeqCst), 1);
+
+    // An explicit backfill still retries it
+    let backfill = backfill_streams_with(&db, |id| async move { Ok(mock_streams(id)) })
+      .await
+      .unwrap();
+    assert_eq!((backfill.attempted, backfill.stored), (1, 1));
+  }
+
+  #[tokio::test]
+  async fn test_failed_stream_fetch_retried_on_next_sync() {
+    let db = crate::db::test_pool().await;
+    sync_activities_with(
+      &db,
+      |_| async { Ok(vec![activity(21, 3600, 3600)]) },
+      |_| async { Err(StravaError::RateLimited) },
+    )
+    .await
+    .unwrap();
+
+    let retried = Arc::new(AtomicUsize::new(0));
+    let counter = retried.clone();
+    sync_activities_with(&db, |_| async { Ok(vec![]) }, move |id| {
+      let counter = counter.clone();
+      async move {
+        counter.fetch_add(1, Ordering::SeqCst);
+        Ok(mock_streams(id))
+      }
+    })
+    .await
+    .unwrap();
+    assert_eq!(retried.load(Ordering::SeqCst), 1);
+
+    assert!(stored_samples(&db, 21).await.is_some());
+  }
+
+  #[tokio::test]
+  async fn test_legacy_samples_compacted_and_still_load() {
+    let db = crate::db::test_pool().await;
+    let samples = downsample_streams(&mock_streams(5), SAMPLE_INTERVAL_SECONDS);
+    sqlx::query(
+      "INSERT INTO workouts (strava_id, activity_type, started_at, samples_json) VALUES ('51', 'Run', '2023-01-01T07:00:00Z', ?1)",
+    )
+    .bind(samples.to_json())
+    .execute(&db)
+    .await
+    .unwrap();
+
+    assert_eq!(compact_stored_samples(&db).await.unwrap(), 1);
+    let (json, compressed): (Option<String>, bool) =
+      sqlx::query_as("SELECT samples_json, samples_compressed FROM workouts WHERE strava_id = '51'")
+        .fetch_one(&db)
+        .await
+        .unwrap();
+    assert!(json.is_none() && compressed);
+    assert_eq!(stored_samples(&db, 51).await, Some(samples));
+
+    // Nothing left to do the second time
+    assert_eq!(compact_stored_samples(&db).await.unwrap(), 0);
+  }
+
+  #[tokio::test]
+  async fn test_second_device_recording_not_counted_in_ctl() {
+    let db = crate::db::test_pool().await;
+    let start = Utc::now() - chrono::Duration::days(2);
+    let recording = |id: i64, offset_secs: i64, moving_time: i64, distance: f64| StravaActivity {
+      activity_type: "Ride".to_string(),
+      start_date: start + chrono::Duration::seconds(offset_secs),
+      distance: Some(distance),
+      ..activity(id, moving_time, moving_time)
+    };
+
+    // Watch and head unit: same ride, a few seconds and meters apart. A later
+    // ride the same day doesn't overlap and counts on its own.
+    let result = sync_activities_with(
+      &db,
+      |_| async move {
+        Ok(vec![
+          recording(31, 0, 3600, 30_000.0),
+          recording(32, 20, 3570, 29_800.0),
+          recording(33, 4 * 3600, 3600, 30_000.0),
+        ])
+      },
+      |_| async { Ok(vec![]) },
+    )
+    .await
+    .unwrap();
+    assert_eq!(result.new_activities, 3);
+    assert_eq!(result.duplicates, 1);
+
+    let duplicate_of: Vec<(String, Option<i64>)> =
+      sqlx::query_as("SELECT strava_id, duplicate_of FROM workouts ORDER BY strava_id")
+        .fetch_all(&db)
+        .await
+        .unwrap();
+    let original: i64 = sqlx::query_scalar("SELECT id FROM workouts WHERE strava_id = '31'")
+      .fetch_one(&db)
+      .await
+      .unwrap();
+    assert_eq!(
+      duplicate_of,
+      vec![("31".to_string(), None), ("32".to_string(), Some(original)), ("33".to_string(), None)]
+    );
+
+    sqlx::query("UPDATE workouts SET rtss = 60").execute(&db).await.unwrap();
+    let summaries = crate::commands::analysis::get_workout_summaries(&db).await.unwrap();
+    assert_eq!(summaries.len(), 2);
+
+    // CTL is that of the two distinct rides alone
+    let settings = crate::analysis::UserSettings::default();
+    let ctl = crate::analysis::TrainingContext::compute(&summaries, &settings).ctl.unwrap();
+    assert!((ctl - 120.0 / 42.0).abs() < 1e-9, "{}", ctl);
+  }
+
+  #[tokio::test]
+  async fn test_gear_mileage_sums_distance_and_flags_worn_shoes() {
+    let db = crate::db::test_pool().await;
+    let start = Utc::now() - chrono::Duration::days(30);
+    let run = |id: i64, day: i64, km: f64, gear: &str| StravaActivity {
+      start_date: start + chrono::Duration::days(day),
+      distance: Some(km * 1000.0),
+      gear_id: Some(gear.to_string()),
+      ..activity(id, 3600, 3600)
+    };
+
+    // Two shoes: one at 450 km over three runs, one at 100 km; plus a bike
+    sync_activities_with(
+      &db,
+      |_| async move {
+        Ok(vec![
+          run(41, 0, 150.0, "g1"),
+          run(42, 1, 150.0, "g1"),
+          run(43, 2, 150.0, "g1"),
+          run(44, 3, 100.0, "g2"),
+          StravaActivity { activity_type: "Ride".to_string(), ..run(45, 4, 900.0, "b1") },
+        ])
+      },
+      |_| async { Ok(vec![]) },
+    )
+    .await
+    .unwrap();
+
+    let looked_up = sync_gear_names(&db, |id| async move {
+      Ok(StravaGear { name: format!("Gear {}", id), id, distance: Some(0.0) })
+    })
+    .await
+    .unwrap();
+    assert_eq!(looked_up, 3);
+
+    sqlx::query("UPDATE user_settings SET shoe_replacement_km = 400").execute(&db).await.unwrap();
+    let mileage = load_gear_mileage(&db).await.unwrap();
+    let find = |id: &str| mileage.iter().find(|g| g.gear_id == id).unwrap();
+
+    let worn = find("g1");
+    assert!((worn.km - 450.0).abs() < 1e-9, "{}", worn.km);
+    assert_eq!(worn.activities, 3);
+    assert_eq!(worn.name.as_deref(), Some("Gear g1"));
+    assert!(worn.needs_replacement);
+    assert!(!find("g2").needs_replacement);
+
+    // Bikes aren't shoes, however far they've gone
+    assert_eq!(find("b1").kind, "bike");
+    assert!(!find("b1").needs_replacement);
+
+    // Gear fetched since its last use isn't fetched again
+    let again = sync_gear_names(&db, |_| async { Err(StravaError::RateLimited) }).await.unwrap();
+    assert_eq!(again, 0);
+  }
+
+  #[tokio::test]
+  async fn test_gear_mileage_uses_strava_total_for_workouts_synced_without_gear() {
+    let db = crate::db::test_pool().await;
+    // 600 km of this shoe was run before gear_id was synced; only one
+    // 10 km run carries it
+    sync_activities_with(
+      &db,
+      |_| async move { Ok(vec![StravaActivity { gear_id: Some("g1".to_string()), ..activity(41, 3600, 3600) }]) },
+      |_| async { Ok(vec![]) },
+    )
+    .await
+    .unwrap();
+    sqlx::query(
+      "INSERT INTO workouts (strava_id, activity_type, started_at, distance_meters)
+       VALUES ('40', 'Run', '2024-06-01T07:00:00Z', 590000.0)",
+    )
+    .execute(&db)
+    .await
+    .unwrap();
+
+    let looked_up = sync_gear_names(&db, |id| async move {
+      Ok(StravaGear { name: "Daily trainer".to_string(), id, distance: Some(600_000.0) })
+    })
+    .await
+    .unwrap();
+    assert_eq!(looked_up, 1);
+
+    sqlx::query("UPDATE user_settings SET shoe_replacement_km = 500").execute(&db).await.unwrap();
+    let mileage = load_gear_mileage(&db).await.unwrap();
+    assert_eq!(mileage.len(), 1);
+    assert!((mileage[0].km - 600.0).abs() < 1e-9, "{}", mileage[0].km);
+    assert_eq!(mileage[0].activities, 1);
+    assert!(mileage[0].needs_replacement);
+
+    // A run on the shoe after it was fetched refreshes Strava's total
+    sqlx::query("UPDATE gear SET fetched_at = '2024-12-02 00:00:00'").execute(&db).await.unwrap();
+    sync_activities_with(
+      &db,
+      |_| async move {
+        Ok(vec![StravaActivity {
+          start_date: "2024-12-05T07:00:00Z".parse().unwrap(),
+          gear_id: Some("g1".to_string()),
+          ..activity(42, 3600, 3600)
+        }])
+      },
+      |_| async { Ok(vec![]) },
+    )
+    .await
+    .unwrap();
+    let refreshed = sync_gear_names(&db, |id| async move {
+      Ok(StravaGear { name: "Daily trainer".to_string(), id, distance: Some(610_000.0) })
+    })
+    .await
+    .unwrap();
+    assert_eq!(refreshed, 1);
+    let mileage = load_gear_mileage(&db).await.unwrap();
+    assert!((mileage[0].km - 610.0).abs() < 1e-9, "{}", mileage[0].km);
+  }
+}
@@ -0,0 +1,141 @@
+use crate::commands::analysis::{compute_pending_metrics, ComputeResult};
+use crate::commands::oura::{sync_oura, OuraSyncResult};
+use crate::commands::strava::{sync_strava, SyncResult};
+use crate::db::AppState;
+use crate::strava::StravaError;
+use serde::Serialize;
+use std::future::Future;
+use std::sync::Arc;
+use tauri::State;
+
+/// ---------------------------------------------------------------------------
+/// Sync All Sources ("refresh")
+/// ---------------------------------------------------------------------------
+
+/// Combined report for one refresh. A source that failed has no result and
+/// an entry in `errors`; the other sources still ran.
+#[derive(Serialize)]
+pub struct SyncAllResult {
+  pub strava: Option<SyncResult>,
+  pub oura: Option<OuraSyncResult>,
+  pub metrics: Option<ComputeResult>,
+  /// One "source: message" entry per failed step
+  pub errors: Vec<String>,
+}
+
+/// Sync Strava, compute metrics for the new workouts, then sync Oura
+#[tauri::command]
+pub async fn sync_all(state: State<'_, Arc<AppState>>) -> Result<SyncAllResult, String> {
+  let db = &state.db;
+  Ok(run_sync_all(db, sync_strava(db), sync_oura(db)).await)
+}
+
+/// Helper: Run each step in order, collecting failures instead of stopping.
+/// Metrics run even if Strava fails so earlier-synced streams still count.
+pub(crate) async fn run_sync_all<S, O>(db: &crate::db::DbPool, strava: S, oura: O) -> SyncAllResult
+where
+  S: Future<Output = Result<SyncResult, StravaError>>,
+  O: Future<Output = Result<OuraSyncResult, String>>,
+{
+  let mut errors = Vec::new();
+
+  let strava = strava
+    .await
+    .map_err(|e| errors.push(format!("strava: {}", e)))
+    .ok();
+
+  let metrics = compute_pending_metrics(db)
+    .await
+    .map_err(|e| errors.push(format!("metrics: {}", e)))
+    .ok();
+
+  let oura = oura
+    .await
+    .map_err(|e| errors.push(format!("oura: {}", e)))
+    .ok();
+
+  SyncAllResult { strava, oura, metrics, errors }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::commands::strava::sync_activities_with;
+  use crate::strava::{StravaActivity, StravaStream};
+
+  fn mock_activity(id: i64, activity_type: &str, start_date: &str) -> StravaActivity {
+    serde_json::from_value(serde_json::json!({
+      "id": id,
+      "name": format!("Activity {}", id),
+      "type": activity_type,
+      "start_date": start_date,
+      "elapsed_time": 2700,
+      "moving_time": 2700,
+      "distance": 7500.0,
+      "average_heartrate": 145.0,
+    }))
+    .unwrap()
+  }
+
+  fn mock_streams() -> Vec<StravaStream> {
+    let stream = |stream_type: &str, data: Vec<serde_json::Value>| StravaStream {
+      stream_type: stream_type.to_string(),
+      data,
+      series_type: None,
+      original_size: None,
+      resolution: None,
+    };
+    vec![
+      stream("time", (0..2700).map(|t| serde_json::json!(t)).collect()),
+      stream("heartrate", (0..2700).map(|_| serde_json::json!(145)).collect()),
+    ]
+  }
+
+  #[tokio::test]
+  async fn test_sync_all_happy_path_reports_oura_failure() {
+    let db = crate::db::test_pool().await;
+
+    let strava = sync_activities_with(
+      &db,
+      |after| async move {
+        assert_eq!(after, None); // first sync fetches everything
+        Ok(vec![
+          mock_activity(101, "Run", "2024-12-09T07:00:00Z"),
+          mock_activity(102, "Ride", "2024-12-10T18:00:00Z"),
+        ])
+      },
+      |_| async { Ok(mock_streams()) },
+    );
+    // No Oura tokens stored: reported, not fatal
+    let result = run_sync_all(&db, strava, sync_oura(&db)).await;
+
+    let strava = result.strava.unwrap();
+    assert_eq!(strava.new_activities, 2);
+    assert_eq!(strava.total_fetched, 2);
+    let metrics = result.metrics.unwrap();
+    assert_eq!((metrics.total, metrics.computed), (2, 2));
+    assert!(result.oura.is_none());
+    assert_eq!(result.errors, vec!["oura: Not connected to Oura".to_string()]);
+
+    // New workouts came back with metrics and samples
+    let pending: i64 =
+      sqlx::query_scalar("SELECT COUNT(*) FROM workouts WHERE metrics_computed_at IS NULL OR samples_blob IS NULL")
+        .fetch_one(&db)
+        .await
+        .unwrap();
+    assert_eq!(pending, 0);
+
+    // A failed Strava sync still leaves metrics and Oura to run
+    let result = run_sync_all(
+      &db,
+      async { Err(StravaError::NotAuthenticated) },
+      async { Ok(OuraSyncResult { sleep_records: 1, hrv_records: 0, resting_hr_records: 0, synced_through: None }) },
+    )
+    .await;
+    assert!(result.strava.is_none());
+    assert_eq!(result.metrics.unwrap().total, 0);
+    assert_eq!(result.oura.unwrap().sleep_records, 1);
+    assert_eq!(result.errors.len(), 1);
+    assert!(result.errors[0].starts_with("strava: "));
+  }
+}
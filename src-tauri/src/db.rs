@@ -5,9 +5,67 @@ use tauri::Manager;
 
 pub type DbPool = SqlitePool;
 
+/// Attempts at opening the database before giving up (another instance or
+/// a sync tool can hold the file briefly)
+const DB_INIT_ATTEMPTS: u32 = 3;
+const DB_INIT_RETRY_DELAY: std::time::Duration = std::time::Duration::from_millis(500);
+
 /// Application state holding the database connection pool
 pub struct AppState {
   pub db: DbPool,
+  /// Held for the length of one workout analysis so a manual analyze and
+  /// the auto-analyze scheduler never run at the same time
+  pub analysis_lock: futures_util::lock::Mutex<()>,
+  /// Why the database couldn't be opened (None when it's ready)
+  pub db_error: Option<String>,
+}
+
+impl AppState {
+  pub fn new(db: DbPool) -> Self {
+    Self {
+      db,
+      analysis_lock: futures_util::lock::Mutex::new(()),
+      db_error: None,
+    }
+  }
+
+  /// Degraded state after a failed init. Commands are rejected up front via
+  /// ensure_available; the closed pool is only a backstop so the app still
+  /// has managed state (without it each command fails on State extraction).
+  pub async fn unavailable(error: String) -> Self {
+    let db = SqlitePoolOptions::new()
+      .connect_lazy("sqlite::memory:")
+      .expect("in-memory database URL is valid");
+    db.close().await;
+    Self {
+      db,
+      analysis_lock: futures_util::lock::Mutex::new(()),
+      db_error: Some(error),
+    }
+  }
+
+  /// The init error, as commands should report it, while the database is
+  /// unavailable
+  pub fn ensure_available(&self) -> Result<(), String> {
+    match &self.db_error {
+      Some(error) => Err(format!("database unavailable: {}", error)),
+      None => Ok(()),
+    }
+  }
+
+  pub fn startup_status(&self) -> StartupStatus {
+    StartupStatus {
+      database_ready: self.db_error.is_none(),
+      error: self.db_error.clone(),
+    }
+  }
+}
+
+/// Startup outcome the frontend checks before loading data
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct StartupStatus {
+  pub database_ready: bool,
+  pub error: Option<String>,
 }
 
 /// Get the path to the database file
@@ -24,23 +82,90 @@ fn get_db_path<R: tauri::Runtime>(app: &tauri::AppHandle<R>) -> Result<PathBuf,
   Ok(data_dir.join("trainer-log.db"))
 }
 
-/// Initialize the database connection pool and run migrations
+/// Initialize the database connection pool and run migrations, retrying
+/// a couple of times before reporting the failure
 pub async fn initialize_db<R: tauri::Runtime>(app: &tauri::AppHandle<R>) -> Result<DbPool, Box<dyn std::error::Error>> {
   let db_path = get_db_path(app)?;
   let db_url = format!("sqlite://{}?mode=rwc", db_path.display());
 
   println!("Initializing database at: {}", db_path.display());
 
+  let mut attempt = 1;
+  loop {
+    match open_db(&db_url).await {
+      Ok(pool) => {
+        println!("Database initialized successfully");
+        return Ok(pool);
+      }
+      Err(e) if attempt < DB_INIT_ATTEMPTS => {
+        eprintln!("Database init attempt {} failed: {}", attempt, e);
+        attempt += 1;
+        tokio::time::sleep(DB_INIT_RETRY_DELAY).await;
+      }
+      Err(e) => return Err(e),
+    }
+  }
+}
+
+/// Connect to `db_url` and run migrations
+async fn open_db(db_url: &str) -> Result<DbPool, Box<dyn std::error::Error>> {
   // Create connection pool
   let pool = SqlitePoolOptions::new()
     .max_connections(5)
-    .connect(&db_url)
+    .connect(db_url)
     .await?;
 
   // Run migrations
   sqlx::migrate!("./migrations").run(&pool).await?;
 
-  println!("Database initialized successfully");
-
   Ok(pool)
 }
+
+/// Fresh in-memory database with all migrations applied (tests only)
+#[cfg(test)]
+pub async fn test_pool() -> DbPool {
+  // A single connection keeps every query on the same in-memory database
+  let pool = SqlitePoolOptions::new()
+    .max_connections(1)
+    .connect("sqlite::memory:")
+    .await
+    .expect("Failed to open in-memory database");
+
+  sqlx::migrate!("./migrations")
+    .run(&pool)
+    .await
+    .expect("Failed to run migrations");
+
+  pool
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[tokio::test]
+  async fn test_failed_init_leaves_commands_failing_cleanly() {
+    // A database file in a directory that doesn't exist can't be created
+    let missing = std::env::temp_dir().join("tempo-missing-dir").join("nested").join("trainer-log.db");
+    let err = open_db(&format!("sqlite://{}?mode=rwc", missing.display()))
+      .await
+      .unwrap_err();
+
+    let state = AppState::unavailable(err.to_string()).await;
+    let status = state.startup_status();
+    assert!(!status.database_ready);
+    assert_eq!(status.error, Some(err.to_string()));
+
+    // Commands are rejected with the init error up front
+    assert_eq!(state.ensure_available(), Err(format!("database unavailable: {}", err)));
+    assert!(AppState::new(test_pool().await).ensure_available().is_ok());
+
+    // Anything that still reaches the closed pool errors instead of panicking
+    let settings = crate::commands::analysis::load_user_settings(&state.db).await;
+    assert!(settings.unwrap_err().contains("closed pool"));
+    let info = crate::commands::database::load_database_info(&state.db).await;
+    assert!(info.is_err());
+
+    assert!(AppState::new(test_pool().await).startup_status().database_ready);
+  }
+}
@@ -1,9 +1,11 @@
+mod activity_file;
 mod analysis;
 mod db;
 mod llm;
 mod models;
 mod commands;
 mod progression;
+mod scheduler;
 mod strava;
 mod oura;
 
@@ -24,18 +26,23 @@ pub fn run() {
       tauri::async_runtime::block_on(async move {
         match db::initialize_db(&app_handle).await {
           Ok(pool) => {
-            let state = Arc::new(AppState { db: pool });
-            app_handle.manage(state);
+            let state = Arc::new(AppState::new(pool));
+            app_handle.manage(state.clone());
+            scheduler::spawn(state);
             println!("Database ready");
           }
           Err(e) => {
+            // Keep the app usable enough to show the error: commands fail
+            // with "database unavailable" and get_startup_status reports why
             eprintln!("Failed to initialize database: {}", e);
+            let state = AppState::unavailable(format!("Failed to initialize database: {}", e)).await;
+            app_handle.manage(Arc::new(state));
           }
         }
       });
       Ok(())
     })
-    .invoke_handler(tauri::generate_handler![
+    .invoke_handler(with_database_check(tauri::generate_handler![
       commands::get_workouts,
       commands::get_sync_state,
       // Strava commands
@@ -45,6 +52,10 @@ pub fn run() {
       commands::strava::strava_refresh_tokens,
       commands::strava::strava_disconnect,
       commands::strava::strava_sync_activities,
+      commands::strava::strava_backfill_streams,
+      commands::strava::strava_fetch_activity,
+      commands::strava::compact_workout_samples,
+      commands::strava::get_gear_mileage,
       // Oura commands
       commands::oura::oura_start_auth,
       commands::oura::oura_complete_auth,
@@ -52,22 +63,87 @@ pub fn run() {
       commands::oura::oura_refresh_auth,
       commands::oura::oura_disconnect,
       commands::oura::oura_sync_data,
+      commands::oura::import_oura_csv,
+      commands::sync::sync_all,
+      commands::database::get_startup_status,
+      commands::database::get_database_info,
+      commands::database::backup_database,
       commands::analysis::get_user_settings,
       commands::analysis::update_user_settings,
+      commands::analysis::update_flag_thresholds,
+      commands::analysis::update_recent_workout_window,
+      commands::analysis::update_polarization_target,
+      commands::analysis::update_load_weights,
+      commands::analysis::update_tsb_bands,
+      commands::analysis::set_auto_analyze,
+      commands::analysis::diff_analyses,
+      commands::analysis::export_workout_bundle,
+      commands::import::import_activity_file,
+      commands::analysis::get_sport_settings,
+      commands::analysis::update_sport_settings,
+      commands::analysis::get_fitness_trend,
+      commands::analysis::refresh_fitness_trend,
+      commands::analysis::get_activity_calendar,
+      commands::analysis::get_ef_trend,
+      commands::analysis::get_normalized_weekly_load,
       commands::analysis::compute_workout_metrics,
+      commands::analysis::reclassify_zones,
+      commands::analysis::set_workout_subjective,
+      commands::analysis::set_daily_log,
+      commands::analysis::get_daily_log,
       commands::analysis::get_workouts_with_metrics,
       commands::analysis::get_training_context,
+      commands::dashboard::get_dashboard,
       commands::analysis::analyze_workout,
       commands::analysis::get_workout_analysis,
       commands::analysis::get_latest_analysis,
+      commands::analysis::submit_analysis_feedback,
+      commands::analysis::get_analysis_feedback,
+      commands::analysis::get_seasonal_comparison,
+      commands::analysis::get_threshold_test_suggestions,
+      commands::plan::generate_plan,
+      commands::plan::get_latest_plan,
+      commands::plan::compute_plan_adherence,
+      commands::plan::set_training_phase,
+      commands::plan::get_training_phase,
+      commands::review::generate_weekly_review,
+      commands::review::get_weekly_review,
       // Progression commands
       commands::progression::get_progression_dimensions,
       commands::progression::get_progression_dimension,
+      commands::progression::get_maintenance_due,
       commands::progression::progress_dimension,
       commands::progression::regress_dimension,
       commands::progression::touch_ceiling,
       commands::progression::set_dimension_ceiling,
-    ])
+      commands::progression::set_dimension_current,
+      commands::progression::set_dimension_prerequisite,
+      commands::progression::undo_last_change,
+      commands::progression::get_dimension_timeline,
+      commands::progression::explain_dimension,
+      commands::progression::mark_key_session,
+      commands::progression::create_dimension,
+      commands::progression::delete_dimension,
+      commands::progression::reset_dimensions,
+    ]))
     .run(tauri::generate_context!())
     .expect("error while running tauri application");
 }
+
+/// Reject every command except get_startup_status with the init error while
+/// the database is unavailable, before it reaches the closed pool
+fn with_database_check<R: tauri::Runtime>(
+  handler: impl Fn(tauri::ipc::Invoke<R>) -> bool + Send + Sync + 'static,
+) -> impl Fn(tauri::ipc::Invoke<R>) -> bool + Send + Sync + 'static {
+  move |invoke| {
+    if invoke.message.command() != "get_startup_status" {
+      if let Some(state) = invoke.message.webview().try_state::<Arc<AppState>>() {
+        if let Err(e) = state.ensure_available() {
+          invoke.resolver.reject(e);
+          return true;
+        }
+      }
+    }
+    handler(invoke)
+  }
+}
@@ -3,8 +3,12 @@
 //! This module handles communication with the Claude API for generating
 //! training insights and recommendations.
 
+use crate::analysis::CoachTone;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::io::Write;
+use std::path::PathBuf;
 use thiserror::Error;
 
 /// ---------------------------------------------------------------------------
@@ -12,8 +16,13 @@ use thiserror::Error;
 /// ---------------------------------------------------------------------------
 
 const CLAUDE_API_URL: &str = "https://api.anthropic.com/v1/messages";
-const CLAUDE_MODEL: &str = "claude-sonnet-4-20250514";
+pub const CLAUDE_MODEL: &str = "claude-sonnet-4-20250514";
+/// Cheaper model for routine sessions (see analysis::select_model)
+pub const CLAUDE_MODEL_FAST: &str = "claude-3-5-haiku-20241022";
 const API_VERSION: &str = "2023-06-01";
+/// When set to a file path, every request/response is appended there as a
+/// JSON line for debugging prompt regressions (the API key is redacted)
+const DEBUG_LOG_ENV: &str = "TEMPO_LLM_DEBUG_LOG";
 
 /// ---------------------------------------------------------------------------
 /// Error Types
@@ -175,6 +184,13 @@ pub struct PerformanceCard {
   pub today_value: String,
   pub delta: String,
   pub insight: String,
+  /// "improved" | "declined" | "stable" (set by Rust, not the LLM)
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub direction: Option<String>,
+  /// For a decline: whether it's no more than current fatigue explains
+  /// (set by Rust, not the LLM)
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub within_fatigue_expectation: Option<bool>,
 }
 
 /// Card 2: HR and efficiency assessment
@@ -209,6 +225,40 @@ pub struct TomorrowCard {
   pub goal: String,
   pub rationale: String,
   pub confidence: String,
+  /// Structured interval set (absent for steady-state sessions)
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub intervals: Option<Vec<IntervalPrescription>>,
+}
+
+/// A single interval block, e.g. 6×3min Z4 off 2min
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IntervalPrescription {
+  pub reps: u32,
+  pub work_sec: u32,
+  pub work_intensity: String,
+  pub recovery_sec: u32,
+}
+
+impl IntervalPrescription {
+  /// Render as compact text (e.g. "6×3min Z4 off 2min")
+  pub fn describe(&self) -> String {
+    format!(
+      "{}×{} {} off {}",
+      self.reps,
+      format_interval_duration(self.work_sec),
+      self.work_intensity,
+      format_interval_duration(self.recovery_sec)
+    )
+  }
+}
+
+/// Format seconds as whole minutes when possible, otherwise seconds
+fn format_interval_duration(secs: u32) -> String {
+  if secs >= 60 && secs.is_multiple_of(60) {
+    format!("{}min", secs / 60)
+  } else {
+    format!("{}s", secs)
+  }
 }
 
 /// Card 5: Eyes on (actionable flags)
@@ -236,11 +286,21 @@ impl From<WorkoutAnalysisV4> for WorkoutAnalysis {
       v4.hr_efficiency.hr_assessment
     );
 
+    let intervals = v4.tomorrow.intervals
+      .as_ref()
+      .filter(|sets| !sets.is_empty())
+      .map(|sets| {
+        let described: Vec<String> = sets.iter().map(|i| i.describe()).collect();
+        format!(" Intervals: {}.", described.join(", "))
+      })
+      .unwrap_or_default();
+
     let tomorrow = format!(
-      "{} for {} min at {} intensity. {}",
+      "{} for {} min at {} intensity.{} {}",
       v4.tomorrow.activity_type,
       v4.tomorrow.duration_min,
       v4.tomorrow.intensity,
+      intervals,
       v4.tomorrow.rationale
     );
 
@@ -259,6 +319,201 @@ impl From<WorkoutAnalysisV4> for WorkoutAnalysis {
   }
 }
 
+/// A value that differs between two analyses
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Change {
+  pub from: String,
+  pub to: String,
+}
+
+/// A changed field of tomorrow's prescription
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FieldChange {
+  pub field: String,
+  pub from: String,
+  pub to: String,
+}
+
+/// How the coach's read moved from analysis A to analysis B
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AnalysisDiff {
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub tsb_band: Option<Change>,
+  /// B's TSB minus A's
+  pub tsb_delta: f64,
+  /// Flags raised in B but not A
+  pub flags_added: Vec<String>,
+  /// Flags raised in A that B no longer has
+  pub flags_cleared: Vec<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub progression_state: Option<Change>,
+  pub tomorrow: Vec<FieldChange>,
+}
+
+impl AnalysisDiff {
+  pub fn between(a: &WorkoutAnalysisV4, b: &WorkoutAnalysisV4) -> Self {
+    let change = |from: &str, to: &str| {
+      (from != to).then(|| Change { from: from.to_string(), to: to.to_string() })
+    };
+
+    let flags_a = a.active_flags();
+    let flags_b = b.active_flags();
+
+    Self {
+      tsb_band: change(&a.training_status.tsb_band, &b.training_status.tsb_band),
+      tsb_delta: b.training_status.tsb_value - a.training_status.tsb_value,
+      flags_added: flags_b.iter().filter(|f| !flags_a.contains(f)).cloned().collect(),
+      flags_cleared: flags_a.iter().filter(|f| !flags_b.contains(f)).cloned().collect(),
+      progression_state: change(&a.training_status.progression_state, &b.training_status.progression_state),
+      tomorrow: a
+        .tomorrow
+        .diff_fields()
+        .into_iter()
+        .zip(b.tomorrow.diff_fields())
+        .filter(|((_, from), (_, to))| from != to)
+        .map(|((field, from), (_, to))| FieldChange { field: field.to_string(), from, to })
+        .collect(),
+    }
+  }
+}
+
+impl WorkoutAnalysisV4 {
+  /// Flags the coach raised: top_flags plus eyes-on priorities, deduplicated
+  pub fn active_flags(&self) -> Vec<String> {
+    let mut flags = self.training_status.top_flags.clone();
+    if let Some(eyes) = &self.eyes_on {
+      flags.extend(eyes.priorities.iter().map(|p| p.flag.clone()));
+    }
+    let mut seen = std::collections::HashSet::new();
+    flags.retain(|flag| seen.insert(flag.clone()));
+    flags
+  }
+}
+
+impl TomorrowCard {
+  /// The prescription fields compared by AnalysisDiff (rationale excluded:
+  /// it always reads differently)
+  fn diff_fields(&self) -> Vec<(&'static str, String)> {
+    let intervals = self
+      .intervals
+      .as_ref()
+      .map(|sets| sets.iter().map(|i| i.describe()).collect::<Vec<_>>().join(", "))
+      .unwrap_or_default();
+    vec![
+      ("activity_type", self.activity_type.clone()),
+      ("duration_min", self.duration_min.to_string()),
+      ("intensity", self.intensity.clone()),
+      ("goal", self.goal.clone()),
+      ("confidence", self.confidence.clone()),
+      ("intervals", intervals),
+    ]
+  }
+}
+
+/// ---------------------------------------------------------------------------
+/// Multi-Week Training Plan (from Claude)
+/// ---------------------------------------------------------------------------
+
+/// Day-by-day plan covering the requested window
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrainingPlan {
+  pub days: Vec<PlannedDay>,
+  /// Overall intent of the block (e.g. "Absorb volume spike, then rebuild long run")
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub summary: Option<String>,
+}
+
+/// A single planned day
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlannedDay {
+  /// ISO date (YYYY-MM-DD)
+  pub date: String,
+  /// "run" | "run_long" | "ride" | "rest" | ...
+  pub activity_type: String,
+  /// 0 for rest days
+  pub duration_min: i32,
+  pub intensity: String,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub purpose: Option<String>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub intervals: Option<Vec<IntervalPrescription>>,
+}
+
+impl TrainingPlan {
+  /// Parse a plan for `start..=end` from an LLM response, ordering days by
+  /// date. Days outside the window or planned twice are rejected.
+  pub fn parse(text: &str, start: chrono::NaiveDate, end: chrono::NaiveDate) -> Result<Self, LlmError> {
+    let json_str = extract_json(text)?;
+    let mut plan: TrainingPlan = serde_json::from_str(&json_str)
+      .map_err(|e| LlmError::Parse(format!("{}: {}", e, json_str)))?;
+
+    let mut seen = std::collections::HashSet::new();
+    for day in &plan.days {
+      let date = chrono::NaiveDate::parse_from_str(&day.date, "%Y-%m-%d")
+        .map_err(|_| LlmError::Parse(format!("Invalid plan date: {}", day.date)))?;
+      if date < start || date > end {
+        return Err(LlmError::Parse(format!(
+          "Plan date {} is outside the plan window {} to {}",
+          day.date, start, end
+        )));
+      }
+      if !seen.insert(date) {
+        return Err(LlmError::Parse(format!("Plan date {} appears more than once", day.date)));
+      }
+    }
+    plan.days.sort_by(|a, b| a.date.cmp(&b.date));
+
+    Ok(plan)
+  }
+}
+
+/// Week-level review of training, distinct from the per-workout cards
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WeeklyReview {
+  /// One-line verdict on the week
+  pub headline: String,
+  /// Volume and load vs the previous weeks (PMC movement)
+  pub volume: String,
+  /// Intensity distribution vs the polarization target
+  pub intensity: String,
+  /// Plan vs actual; null when no plan covered the week
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub plan_comparison: Option<String>,
+  /// Progression changes (or holds) during the week
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub progression: Option<String>,
+  #[serde(default)]
+  pub notable_sessions: Vec<NotableSession>,
+  /// Two or three priorities for the coming week
+  #[serde(default)]
+  pub next_week_focus: Vec<String>,
+}
+
+/// A session the review calls out
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotableSession {
+  /// ISO date (YYYY-MM-DD)
+  pub date: String,
+  pub activity_type: String,
+  pub note: String,
+}
+
+impl WeeklyReview {
+  /// Parse a weekly review from an LLM response
+  pub fn parse(text: &str) -> Result<Self, LlmError> {
+    let json_str = extract_json(text)?;
+    let review: WeeklyReview = serde_json::from_str(&json_str)
+      .map_err(|e| LlmError::Parse(format!("{}: {}", e, json_str)))?;
+
+    for session in &review.notable_sessions {
+      chrono::NaiveDate::parse_from_str(&session.date, "%Y-%m-%d")
+        .map_err(|_| LlmError::Parse(format!("Invalid session date: {}", session.date)))?;
+    }
+
+    Ok(review)
+  }
+}
+
 /// Legacy V2 format (for backward compatibility)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WorkoutAnalysisV2 {
@@ -364,28 +619,70 @@ impl From<WorkoutAnalysisV2> for WorkoutAnalysis {
 pub struct ClaudeClient {
   client: Client,
   api_key: String,
+  /// Opt-in request/response log (see DEBUG_LOG_ENV)
+  debug_log: Option<PathBuf>,
 }
 
 impl ClaudeClient {
   /// Create a new Claude client, loading API key from environment
   pub fn from_env() -> Result<Self, LlmError> {
     let api_key = std::env::var("ANTHROPIC_API_KEY").map_err(|_| LlmError::MissingApiKey)?;
+    let debug_log = std::env::var(DEBUG_LOG_ENV)
+      .ok()
+      .filter(|path| !path.trim().is_empty())
+      .map(PathBuf::from);
 
     Ok(Self {
       client: Client::new(),
       api_key,
+      debug_log,
     })
   }
 
+  /// Append one exchange to the debug log, if enabled. Failures are printed,
+  /// never returned: logging must not break an analysis.
+  fn log_exchange(
+    &self,
+    model: &str,
+    system_prompt: &str,
+    user_message: &str,
+    status: u16,
+    response: &str,
+  ) {
+    let Some(path) = &self.debug_log else {
+      return;
+    };
+
+    let entry = serde_json::json!({
+      "timestamp": chrono::Utc::now().to_rfc3339(),
+      "model": model,
+      "status": status,
+      "system": system_prompt,
+      "user": user_message,
+      "response": response,
+    });
+    let line = redact(&entry.to_string(), &self.api_key);
+
+    let result = std::fs::OpenOptions::new()
+      .create(true)
+      .append(true)
+      .open(path)
+      .and_then(|mut file| writeln!(file, "{}", line));
+    if let Err(e) = result {
+      eprintln!("Failed to write LLM debug log {}: {}", path.display(), e);
+    }
+  }
+
   /// Call Claude with a system prompt and user message
   pub async fn complete(
     &self,
+    model: &str,
     system_prompt: &str,
     user_message: &str,
     max_tokens: u32,
   ) -> Result<(String, Usage), LlmError> {
     let request = ClaudeRequest {
-      model: CLAUDE_MODEL.to_string(),
+      model: model.to_string(),
       max_tokens,
       system: system_prompt.to_string(),
       messages: vec![ClaudeMessage {
@@ -410,6 +707,7 @@ impl ClaudeClient {
       .text()
       .await
       .map_err(|e| LlmError::Request(e.to_string()))?;
+    self.log_exchange(model, system_prompt, user_message, status.as_u16(), &body);
 
     if !status.is_success() {
       // Try to parse error response
@@ -433,13 +731,15 @@ impl ClaudeClient {
     Ok((text, claude_response.usage))
   }
 
-  /// Analyze a workout and return V4 format (for frontend)
+  /// Analyze a workout with the given model and return V4 format (for frontend)
   pub async fn analyze_workout_v4_or_fallback(
     &self,
+    model: &str,
     context_json: &str,
+    tone: CoachTone,
   ) -> Result<(WorkoutAnalysisV4, Usage), LlmError> {
     // Try V4 first (multi-card), fall back to converting V3/V2/legacy to V4 structure
-    match self.analyze_workout_v4(context_json).await {
+    match self.analyze_workout_v4(model, context_json, tone).await {
       Ok((v4, usage)) => {
         println!("LLM returned V4 format");
         Ok((v4, usage))
@@ -453,6 +753,56 @@ impl ClaudeClient {
     }
   }
 
+  /// Generate a day-by-day plan for the `weeks` weeks from `start_date`
+  /// described in the plan context
+  pub async fn generate_plan(
+    &self,
+    context_json: &str,
+    start_date: chrono::NaiveDate,
+    weeks: i64,
+  ) -> Result<(TrainingPlan, Usage), LlmError> {
+    let system_prompt = include_str!("prompts/plan_system.txt");
+
+    let user_message = format!(
+      r#"Build a {}-week day-by-day training plan.
+
+PLAN CONTEXT:
+{}
+
+Respond with valid JSON matching the PLAN OUTPUT STRUCTURE."#,
+      weeks, context_json
+    );
+
+    let (response_text, usage) = self.complete(CLAUDE_MODEL, system_prompt, &user_message, 4000).await?;
+    let end_date = start_date + chrono::Duration::days(weeks * 7 - 1);
+    let plan = TrainingPlan::parse(&response_text, start_date, end_date)?;
+
+    Ok((plan, usage))
+  }
+
+  /// Review a completed week from the weekly review context
+  pub async fn generate_weekly_review(
+    &self,
+    context_json: &str,
+  ) -> Result<(WeeklyReview, Usage), LlmError> {
+    let system_prompt = include_str!("prompts/weekly_review_system.txt");
+
+    let user_message = format!(
+      r#"Review this training week.
+
+WEEK CONTEXT:
+{}
+
+Respond with valid JSON matching the REVIEW OUTPUT STRUCTURE."#,
+      context_json
+    );
+
+    let (response_text, usage) = self.complete(CLAUDE_MODEL, system_prompt, &user_message, 2000).await?;
+    let review = WeeklyReview::parse(&response_text)?;
+
+    Ok((review, usage))
+  }
+
   /// Analyze a workout with structured JSON output (returns legacy format for DB storage)
   #[allow(dead_code)]
   pub async fn analyze_workout(
@@ -460,7 +810,7 @@ impl ClaudeClient {
     context_json: &str,
   ) -> Result<(WorkoutAnalysis, Usage), LlmError> {
     // Try V4 first (multi-card), fall back to V3, V2, then legacy
-    match self.analyze_workout_v4(context_json).await {
+    match self.analyze_workout_v4(CLAUDE_MODEL, context_json, CoachTone::default()).await {
       Ok((v4, usage)) => {
         println!("LLM returned V4 format");
         Ok((v4.into(), usage))
@@ -487,29 +837,18 @@ impl ClaudeClient {
   /// Analyze a workout with V4 format (multi-card system)
   async fn analyze_workout_v4(
     &self,
+    model: &str,
     context_json: &str,
+    tone: CoachTone,
   ) -> Result<(WorkoutAnalysisV4, Usage), LlmError> {
-    let system_prompt = include_str!("prompts/coach_system_v4.txt");
-
-    let user_message = format!(
-      r#"Analyze this workout and provide card-based coaching feedback.
-
-TRAINING CONTEXT:
-{}
-
-Respond with valid JSON matching the V4 OUTPUT STRUCTURE."#,
-      context_json
-    );
-
-    let (response_text, usage) = self.complete(system_prompt, &user_message, 2500).await?;
-
-    let json_str = extract_json(&response_text)?;
-
-    let analysis: WorkoutAnalysisV4 =
-      serde_json::from_str(&json_str)
-        .map_err(|e| LlmError::Parse(format!("{}: {}", e, json_str)))?;
-
-    Ok((analysis, usage))
+    analyze_workout_v4_with(
+      |system_prompt, user_message| async move {
+        self.complete(model, &system_prompt, &user_message, 2500).await
+      },
+      context_json,
+      tone,
+    )
+    .await
   }
 
   /// Analyze a workout with V3 format (trend-focused with structured prescription)
@@ -530,7 +869,7 @@ Respond with valid JSON matching the OUTPUT STRUCTURE specified in your instruct
       context_json
     );
 
-    let (response_text, usage) = self.complete(system_prompt, &user_message, 2000).await?;
+    let (response_text, usage) = self.complete(CLAUDE_MODEL, system_prompt, &user_message, 2000).await?;
 
     // Parse the JSON response
     let json_str = extract_json(&response_text)?;
@@ -559,7 +898,7 @@ Respond with valid JSON matching the OUTPUT FORMAT specified in your instruction
       context_json
     );
 
-    let (response_text, usage) = self.complete(system_prompt, &user_message, 1500).await?;
+    let (response_text, usage) = self.complete(CLAUDE_MODEL, system_prompt, &user_message, 1500).await?;
 
     // Parse the JSON response
     let json_str = extract_json(&response_text)?;
@@ -596,7 +935,7 @@ Be direct and specific. Reference the actual numbers provided."#,
       context_json
     );
 
-    let (response_text, usage) = self.complete(system_prompt, &user_message, 1024).await?;
+    let (response_text, usage) = self.complete(CLAUDE_MODEL, system_prompt, &user_message, 1024).await?;
 
     let json_str = extract_json(&response_text)?;
 
@@ -607,42 +946,154 @@ Be direct and specific. Reference the actual numbers provided."#,
   }
 }
 
+/// V4 analysis against any completion (system prompt, user message), so the
+/// prompt can be checked without calling the API
+async fn analyze_workout_v4_with<C, Fut>(
+  complete: C,
+  context_json: &str,
+  tone: CoachTone,
+) -> Result<(WorkoutAnalysisV4, Usage), LlmError>
+where
+  C: FnOnce(String, String) -> Fut,
+  Fut: Future<Output = Result<(String, Usage), LlmError>>,
+{
+  let system_prompt = coach_system_prompt(tone);
+
+  let user_message = format!(
+    r#"Analyze this workout and provide card-based coaching feedback.
+
+TRAINING CONTEXT:
+{}
+
+Respond with valid JSON matching the V4 OUTPUT STRUCTURE."#,
+    context_json
+  );
+
+  let (response_text, usage) = complete(system_prompt, user_message).await?;
+
+  let json_str = extract_json(&response_text)?;
+
+  let analysis: WorkoutAnalysisV4 =
+    serde_json::from_str(&json_str)
+      .map_err(|e| LlmError::Parse(format!("{}: {}", e, json_str)))?;
+
+  Ok((analysis, usage))
+}
+
+/// The V4 coach system prompt with the tone's voice filled in; only the
+/// voice line differs between tones
+pub fn coach_system_prompt(tone: CoachTone) -> String {
+  include_str!("prompts/coach_system_v4.txt").replace("{{COACH_TONE}}", tone.voice())
+}
+
+/// Replace every occurrence of the secret (an empty secret redacts nothing)
+fn redact(text: &str, secret: &str) -> String {
+  if secret.is_empty() {
+    text.to_string()
+  } else {
+    text.replace(secret, "[REDACTED]")
+  }
+}
+
 /// Extract JSON from Claude's response (handles markdown code blocks)
+///
+/// Tries, in order: the whole response, every fenced code block, then every
+/// balanced `{...}` span. The first candidate that parses as a JSON object
+/// wins, so reasoning in an earlier fence or a stray `{` in prose is skipped.
 fn extract_json(text: &str) -> Result<String, LlmError> {
   // Try direct parse first
-  if text.trim().starts_with('{') {
-    return Ok(text.trim().to_string());
+  let trimmed = text.trim();
+  if is_json_object(trimmed) {
+    return Ok(trimmed.to_string());
   }
 
-  // Look for JSON in code blocks
-  if let Some(start) = text.find("```json") {
-    let start = start + 7;
-    if let Some(end) = text[start..].find("```") {
-      return Ok(text[start..start + end].trim().to_string());
-    }
+  // Look for JSON in code blocks (```json or plain ```)
+  if let Some(block) = fenced_blocks(text).into_iter().find(|b| is_json_object(b)) {
+    return Ok(block.to_string());
   }
 
-  // Look for plain code blocks
-  if let Some(start) = text.find("```") {
-    let start = start + 3;
-    // Skip language identifier if present
-    let content_start = text[start..]
-      .find('\n')
-      .map(|i| start + i + 1)
-      .unwrap_or(start);
-    if let Some(end) = text[content_start..].find("```") {
-      return Ok(text[content_start..content_start + end].trim().to_string());
-    }
+  // Brace-match each `{` and keep the first span that validates
+  if let Some(span) = balanced_object_spans(text).into_iter().find(|s| is_json_object(s)) {
+    return Ok(span.to_string());
   }
 
-  // Last resort: find first { to last }
+  // Last resort: hand back something for the caller's parse error to report
+  if trimmed.starts_with('{') {
+    return Ok(trimmed.to_string());
+  }
   if let (Some(start), Some(end)) = (text.find('{'), text.rfind('}')) {
-    return Ok(text[start..=end].to_string());
+    if start < end {
+      return Ok(text[start..=end].to_string());
+    }
   }
 
   Err(LlmError::Parse("Could not extract JSON from response".to_string()))
 }
 
+fn is_json_object(candidate: &str) -> bool {
+  matches!(
+    serde_json::from_str::<serde_json::Value>(candidate),
+    Ok(serde_json::Value::Object(_))
+  )
+}
+
+/// Contents of every closed ``` fence, with any language identifier removed
+fn fenced_blocks(text: &str) -> Vec<&str> {
+  text
+    .split("```")
+    .skip(1)
+    .step_by(2)
+    // An unclosed trailing fence yields no block
+    .take(text.matches("```").count() / 2)
+    .map(|block| {
+      let first_line_end = block.find('\n').unwrap_or(block.len());
+      let first_line = block[..first_line_end].trim();
+      let is_language_tag = !first_line.is_empty()
+        && first_line.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+      if is_language_tag {
+        block[first_line_end..].trim()
+      } else {
+        block.trim()
+      }
+    })
+    .collect()
+}
+
+/// Every `{...}` span whose braces balance, skipping braces inside strings
+fn balanced_object_spans(text: &str) -> Vec<&str> {
+  let mut spans = Vec::new();
+  for (start, _) in text.match_indices('{') {
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
+    for (offset, c) in text[start..].char_indices() {
+      if in_string {
+        if escaped {
+          escaped = false;
+        } else if c == '\\' {
+          escaped = true;
+        } else if c == '"' {
+          in_string = false;
+        }
+        continue;
+      }
+      match c {
+        '"' => in_string = true,
+        '{' => depth += 1,
+        '}' => {
+          depth -= 1;
+          if depth == 0 {
+            spans.push(&text[start..=start + offset]);
+            break;
+          }
+        }
+        _ => {}
+      }
+    }
+  }
+  spans
+}
+
 /// ---------------------------------------------------------------------------
 /// Tests
 /// ---------------------------------------------------------------------------
@@ -651,6 +1102,53 @@ fn extract_json(text: &str) -> Result<String, LlmError> {
 mod tests {
   use super::*;
 
+  #[test]
+  fn test_coach_tone_templated_into_system_prompt() {
+    let prompt = coach_system_prompt(CoachTone::Direct);
+    assert!(prompt.contains(CoachTone::Direct.voice()));
+    assert!(!prompt.contains(CoachTone::Conversational.voice()));
+    assert!(!prompt.contains("{{COACH_TONE}}"));
+
+    // Everything but the voice line is shared between tones
+    let supportive = coach_system_prompt(CoachTone::Supportive);
+    assert_eq!(
+      prompt.replace(CoachTone::Direct.voice(), ""),
+      supportive.replace(CoachTone::Supportive.voice(), "")
+    );
+  }
+
+  #[tokio::test]
+  async fn test_analyze_workout_v4_sends_the_tone_in_the_system_prompt() {
+    let response = r#"{
+      "performance": {"metric_name": "pace", "comparison_date": "2025-12-09", "comparison_value": "7:20/km",
+        "today_value": "7:10/km", "delta": "-10 sec/km", "insight": "Pace improving."},
+      "hr_efficiency": {"avg_hr": 140, "hr_zone": "Z2", "hr_pct_max": 74, "hr_assessment": "Solid Z2."},
+      "training_status": {"tsb_value": 2.0, "tsb_band": "slightly_fatigued", "tsb_assessment": "Fresh enough",
+        "top_flags": [], "adherence_note": "5/6 sessions", "progression_state": "Building long run"},
+      "tomorrow": {"activity_type": "ride", "duration_min": 60, "duration_label": "LONG", "intensity": "Z2",
+        "goal": "aerobic_development", "rationale": "TSB positive", "confidence": "high"}
+    }"#;
+    let sent = std::sync::Mutex::new(None);
+
+    let (analysis, usage) = analyze_workout_v4_with(
+      |system_prompt, user_message| {
+        *sent.lock().unwrap() = Some((system_prompt, user_message));
+        async { Ok((response.to_string(), Usage { input_tokens: 900, output_tokens: 300 })) }
+      },
+      r#"{"workout": "context"}"#,
+      CoachTone::Analytical,
+    )
+    .await
+    .unwrap();
+
+    let (system_prompt, user_message) = sent.into_inner().unwrap().unwrap();
+    assert!(system_prompt.contains(CoachTone::Analytical.voice()));
+    assert!(!system_prompt.contains(CoachTone::Conversational.voice()));
+    assert!(user_message.contains(r#"{"workout": "context"}"#));
+    assert_eq!(analysis.hr_efficiency.avg_hr, 140);
+    assert_eq!(usage.output_tokens, 300);
+  }
+
   #[test]
   fn test_extract_json_direct() {
     let input = r#"{"summary": "test", "risk_flags": []}"#;
@@ -678,6 +1176,74 @@ Hope that helps!"#;
     assert!(result.contains("summary"));
   }
 
+  #[test]
+  fn test_extract_json_skips_invalid_first_block() {
+    let input = r#"Let me reason about this first:
+
+```
+pace improved, HR {lower} than last week
+```
+
+```json
+{"summary": "Second block", "risk_flags": []}
+```"#;
+    let result = extract_json(input).unwrap();
+    let value: serde_json::Value = serde_json::from_str(&result).unwrap();
+    assert_eq!(value["summary"], "Second block");
+  }
+
+  #[test]
+  fn test_extract_json_ignores_prose_brace() {
+    let input = r#"Zones used {Z2, Z3} were as expected. Result:
+{"summary": "Real answer", "risk_flags": ["hr {drift}"]}"#;
+    let result = extract_json(input).unwrap();
+    let value: serde_json::Value = serde_json::from_str(&result).unwrap();
+    assert_eq!(value["summary"], "Real answer");
+  }
+
+  fn v4_from_json(tsb_value: f64, tsb_band: &str, flags: &[&str], intensity: &str) -> WorkoutAnalysisV4 {
+    serde_json::from_value(serde_json::json!({
+      "performance": {
+        "metric_name": "pace", "comparison_date": "2025-12-09", "comparison_value": "7:20/km",
+        "today_value": "7:18/km", "delta": "-2 sec/km", "insight": "Steady"
+      },
+      "hr_efficiency": { "avg_hr": 138, "hr_zone": "Z2", "hr_pct_max": 73, "hr_assessment": "Z2 throughout" },
+      "training_status": {
+        "tsb_value": tsb_value, "tsb_band": tsb_band, "tsb_assessment": "...",
+        "top_flags": flags, "adherence_note": "5/6", "progression_state": "Aerobic base on hold"
+      },
+      "tomorrow": {
+        "activity_type": "Run", "duration_min": 45, "duration_label": "MEDIUM", "intensity": intensity,
+        "goal": "aerobic_base", "rationale": "...", "confidence": "high"
+      }
+    }))
+    .unwrap()
+  }
+
+  #[test]
+  fn test_diff_reports_band_change_and_cleared_flag() {
+    let before = v4_from_json(-18.0, "moderate_fatigue", &["volume_spike", "long_run_gap"], "Z2");
+    let after = v4_from_json(4.0, "fresh", &["long_run_gap"], "Z3");
+
+    let diff = AnalysisDiff::between(&before, &after);
+    assert_eq!(
+      diff.tsb_band,
+      Some(Change { from: "moderate_fatigue".to_string(), to: "fresh".to_string() })
+    );
+    assert_eq!(diff.tsb_delta, 22.0);
+    assert_eq!(diff.flags_cleared, vec!["volume_spike".to_string()]);
+    assert!(diff.flags_added.is_empty());
+    assert!(diff.progression_state.is_none());
+    assert_eq!(
+      diff.tomorrow,
+      vec![FieldChange { field: "intensity".to_string(), from: "Z2".to_string(), to: "Z3".to_string() }]
+    );
+
+    let same = AnalysisDiff::between(&after, &after);
+    assert!(same.tsb_band.is_none() && same.progression_state.is_none());
+    assert!(same.flags_added.is_empty() && same.flags_cleared.is_empty() && same.tomorrow.is_empty());
+  }
+
   #[test]
   fn test_v4_to_legacy_conversion() {
     let v4 = WorkoutAnalysisV4 {
@@ -688,6 +1254,8 @@ Hope that helps!"#;
         today_value: "7:22/km".to_string(),
         delta: "+2 sec/km".to_string(),
         insight: "Pace holding steady around 7:20/km across last 3 runs.".to_string(),
+        direction: None,
+        within_fatigue_expectation: None,
       },
       hr_efficiency: HrEfficiencyCard {
         avg_hr: 136,
@@ -712,6 +1280,7 @@ Hope that helps!"#;
         goal: "load_management".to_string(),
         rationale: "TSB -12 + volume spike = keep it short and easy".to_string(),
         confidence: "high".to_string(),
+        intervals: None,
       },
       eyes_on: Some(EyesOnCard {
         priorities: vec![
@@ -743,4 +1312,191 @@ Hope that helps!"#;
     assert!(legacy.risk_flags[0].contains("long_run_gap"));
     assert!(legacy.risk_flags[0].contains("Hit Saturday's long session"));
   }
+
+  #[test]
+  fn test_v4_parse_with_intervals() {
+    let input = r#"{
+      "performance": {
+        "metric_name": "pace",
+        "comparison_date": "2025-12-09",
+        "comparison_value": "7:20/km",
+        "today_value": "7:10/km",
+        "delta": "-10 sec/km",
+        "insight": "Pace improving."
+      },
+      "hr_efficiency": {
+        "avg_hr": 140,
+        "hr_zone": "Z2",
+        "hr_pct_max": 74,
+        "hr_assessment": "Solid Z2."
+      },
+      "training_status": {
+        "tsb_value": 2.0,
+        "tsb_band": "slightly_fatigued",
+        "tsb_assessment": "Fresh enough for quality",
+        "top_flags": [],
+        "adherence_note": "5/6 sessions",
+        "progression_state": "Building long run"
+      },
+      "tomorrow": {
+        "activity_type": "ride",
+        "duration_min": 60,
+        "duration_label": "LONG",
+        "intensity": "Z4",
+        "goal": "aerobic_development",
+        "rationale": "TSB positive, no flags",
+        "confidence": "high",
+        "intervals": [
+          {"reps": 6, "work_sec": 180, "work_intensity": "Z4", "recovery_sec": 120}
+        ]
+      },
+      "eyes_on": null
+    }"#;
+
+    let v4: WorkoutAnalysisV4 = serde_json::from_str(input).unwrap();
+    let intervals = v4.tomorrow.intervals.clone().unwrap();
+    assert_eq!(intervals.len(), 1);
+    assert_eq!(intervals[0].reps, 6);
+    assert_eq!(intervals[0].work_sec, 180);
+    assert_eq!(intervals[0].recovery_sec, 120);
+
+    let legacy: WorkoutAnalysis = v4.into();
+    assert!(legacy.tomorrow_recommendation.contains("6×3min Z4 off 2min"));
+  }
+
+  #[test]
+  fn test_v4_parse_without_intervals() {
+    let input = r#"{
+      "activity_type": "run",
+      "duration_min": 40,
+      "duration_label": "SHORT",
+      "intensity": "Z2",
+      "goal": "load_management",
+      "rationale": "Keep it easy",
+      "confidence": "medium"
+    }"#;
+
+    let card: TomorrowCard = serde_json::from_str(input).unwrap();
+    assert!(card.intervals.is_none());
+  }
+
+  fn date(year: i32, month: u32, day: u32) -> chrono::NaiveDate {
+    chrono::NaiveDate::from_ymd_opt(year, month, day).unwrap()
+  }
+
+  #[test]
+  fn test_training_plan_parse() {
+    let response = r#"Here's your plan:
+```json
+{
+  "summary": "Absorb load, then rebuild the long run",
+  "days": [
+    {"date": "2024-12-17", "activity_type": "run", "duration_min": 35, "intensity": "Z2"},
+    {"date": "2024-12-16", "activity_type": "ride", "duration_min": 45, "intensity": "Z2", "purpose": "aerobic_development"},
+    {"date": "2024-12-18", "activity_type": "ride", "duration_min": 50, "intensity": "Z4",
+     "intervals": [{"reps": 4, "work_sec": 240, "work_intensity": "Z4", "recovery_sec": 120}]},
+    {"date": "2024-12-22", "activity_type": "rest", "duration_min": 0, "intensity": "rest"}
+  ]
+}
+```"#;
+
+    let plan = TrainingPlan::parse(response, date(2024, 12, 16), date(2024, 12, 22)).unwrap();
+    assert_eq!(plan.days.len(), 4);
+    // Sorted by date
+    assert_eq!(plan.days[0].date, "2024-12-16");
+    assert_eq!(plan.days[0].purpose.as_deref(), Some("aerobic_development"));
+    assert!(plan.days[1].intervals.is_none());
+    assert_eq!(plan.days[2].intervals.as_ref().unwrap()[0].describe(), "4×4min Z4 off 2min");
+    assert_eq!(plan.days[3].duration_min, 0);
+    assert_eq!(plan.summary.as_deref(), Some("Absorb load, then rebuild the long run"));
+  }
+
+  #[test]
+  fn test_training_plan_rejects_bad_date() {
+    let response = r#"{"days": [{"date": "next tuesday", "activity_type": "run", "duration_min": 30, "intensity": "Z2"}]}"#;
+    assert!(TrainingPlan::parse(response, date(2024, 12, 16), date(2024, 12, 22)).is_err());
+  }
+
+  #[test]
+  fn test_training_plan_rejects_days_outside_window() {
+    let response = r#"{"days": [
+      {"date": "2024-12-16", "activity_type": "run", "duration_min": 30, "intensity": "Z2"},
+      {"date": "2024-12-23", "activity_type": "run", "duration_min": 30, "intensity": "Z2"}
+    ]}"#;
+    assert!(TrainingPlan::parse(response, date(2024, 12, 16), date(2024, 12, 22)).is_err());
+    // Wide enough window: accepted
+    assert!(TrainingPlan::parse(response, date(2024, 12, 16), date(2024, 12, 29)).is_ok());
+
+    let before_start = r#"{"days": [{"date": "2024-12-15", "activity_type": "rest", "duration_min": 0, "intensity": "rest"}]}"#;
+    assert!(TrainingPlan::parse(before_start, date(2024, 12, 16), date(2024, 12, 22)).is_err());
+  }
+
+  #[test]
+  fn test_training_plan_rejects_duplicate_dates() {
+    let response = r#"{"days": [
+      {"date": "2024-12-17", "activity_type": "run", "duration_min": 30, "intensity": "Z2"},
+      {"date": "2024-12-17", "activity_type": "ride", "duration_min": 45, "intensity": "Z2"}
+    ]}"#;
+    let err = TrainingPlan::parse(response, date(2024, 12, 16), date(2024, 12, 22)).unwrap_err();
+    assert!(err.to_string().contains("more than once"));
+  }
+
+  #[test]
+  fn test_weekly_review_parse() {
+    let response = r#"```json
+{
+  "headline": "Solid aerobic week, long run back on track",
+  "volume": "6.2h, up 8% - CTL +2.1, TSB settled at -6",
+  "intensity": "82/6/12 - a touch of grey zone on Thursday",
+  "plan_comparison": null,
+  "progression": "long_run advanced 75 -> 80 min",
+  "notable_sessions": [
+    {"date": "2024-12-14", "activity_type": "run", "note": "80 min long run, HR drift under 5%"}
+  ],
+  "next_week_focus": ["Hold long run at 80 min", "Keep Thursday ride in Z2"]
+}
+```"#;
+
+    let review = WeeklyReview::parse(response).unwrap();
+    assert_eq!(review.headline, "Solid aerobic week, long run back on track");
+    assert!(review.plan_comparison.is_none());
+    assert_eq!(review.progression.as_deref(), Some("long_run advanced 75 -> 80 min"));
+    assert_eq!(review.notable_sessions.len(), 1);
+    assert_eq!(review.notable_sessions[0].date, "2024-12-14");
+    assert_eq!(review.next_week_focus.len(), 2);
+
+    let bad = r#"{"headline": "h", "volume": "v", "intensity": "i",
+      "notable_sessions": [{"date": "Saturday", "activity_type": "run", "note": "n"}]}"#;
+    assert!(WeeklyReview::parse(bad).is_err());
+  }
+
+  #[test]
+  fn test_debug_log_writes_entry_without_api_key() {
+    let path = std::env::temp_dir().join(format!("tempo-llm-debug-{}.jsonl", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let api_key = "sk-ant-test-secret-123";
+    let client = ClaudeClient {
+      client: Client::new(),
+      api_key: api_key.to_string(),
+      debug_log: Some(path.clone()),
+    };
+
+    // A key pasted into the prompt or echoed back is redacted too
+    client.log_exchange(CLAUDE_MODEL, "system prompt", &format!("context {}", api_key), 200, "{\"headline\": \"ok\"}");
+
+    let log = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(log.lines().count(), 1);
+    let entry: serde_json::Value = serde_json::from_str(log.lines().next().unwrap()).unwrap();
+    assert_eq!(entry["system"], "system prompt");
+    assert_eq!(entry["user"], "context [REDACTED]");
+    assert_eq!(entry["status"], 200);
+    assert_eq!(entry["response"], "{\"headline\": \"ok\"}");
+    assert!(!log.contains(api_key));
+
+    // Disabled by default: nothing written
+    let disabled = ClaudeClient { debug_log: None, ..client };
+    disabled.log_exchange(CLAUDE_MODEL, "system prompt", "user", 200, "response");
+    assert!(!path.exists());
+  }
 }
@@ -3,12 +3,14 @@
 //! This module handles Oura OAuth, data sync, and context building.
 //! We use raw sleep/HRV data, NOT proprietary readiness scores.
 
+use crate::strava::extract_query_param;
 use chrono::{DateTime, Duration, Utc};
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use std::env;
 use std::io::{Read, Write};
 use std::net::TcpListener;
+use std::time::Duration as StdDuration;
 
 /// ---------------------------------------------------------------------------
 /// Configuration Constants
@@ -19,6 +21,15 @@ const OURA_TOKEN_URL: &str = "https://api.ouraring.com/oauth/token";
 const OURA_API_BASE: &str = "https://api.ouraring.com/v2/usercollection";
 const REDIRECT_PORT: u16 = 8766;  // Different from Strava (8765)
 const TOKEN_REFRESH_BUFFER_MINUTES: i64 = 5;
+pub const DEFAULT_CALLBACK_TIMEOUT_SECONDS: u64 = 120;
+/// Days of history used for Oura baselines (excluding the day itself)
+pub const BASELINE_DAYS: i64 = 7;
+/// Morning resting HR this far above the 7-day baseline counts as elevated
+pub const RHR_ELEVATED_BPM: i64 = 5;
+/// Deep-sleep share below this fraction of the baseline share counts as low
+pub const LOW_DEEP_SLEEP_RATIO: f64 = 0.75;
+/// Baseline nights with stage data needed before flagging low deep sleep
+pub const MIN_SLEEP_BASELINE_NIGHTS: usize = 3;
 
 /// ---------------------------------------------------------------------------
 /// OAuth Data Structures
@@ -127,7 +138,7 @@ pub struct OuraContext {
   #[serde(skip_serializing_if = "Option::is_none")]
   pub sleep_avg_7d: Option<f64>,
   #[serde(skip_serializing_if = "Option::is_none")]
-  pub sleep_debt_hours: Option<f64>,  // cumulative shortfall vs 8hr target
+  pub sleep_debt_hours: Option<f64>,  // cumulative shortfall vs the athlete's target
 
   // HRV (raw values in milliseconds, not scores)
   #[serde(skip_serializing_if = "Option::is_none")]
@@ -146,6 +157,82 @@ pub struct OuraContext {
   pub resting_hr_avg_7d: Option<i64>,
   #[serde(skip_serializing_if = "Option::is_none")]
   pub resting_hr_trend: Option<String>, // "up", "stable", "down"
+  /// Morning RHR >= RHR_ELEVATED_BPM over the 7-day baseline (explains elevated workout HR)
+  #[serde(default)]
+  pub rhr_elevated: bool,
+
+  // Sleep stages (last night vs baseline)
+  #[serde(skip_serializing_if = "Option::is_none", default)]
+  pub sleep_quality: Option<SleepQuality>,
+}
+
+/// Sleep-stage interpretation of last night against the 7-day baseline
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SleepQuality {
+  /// Deep sleep as % of total sleep
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub deep_pct: Option<f64>,
+  /// REM sleep as % of total sleep
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub rem_pct: Option<f64>,
+  /// Mean deep-sleep % over the baseline nights
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub deep_pct_baseline: Option<f64>,
+  /// Deep share below LOW_DEEP_SLEEP_RATIO x baseline - a recovery red flag
+  pub low_deep_sleep: bool,
+}
+
+/// Share of total sleep spent in a stage, as a percentage
+fn stage_pct(stage_seconds: Option<i64>, total_seconds: Option<i64>) -> Option<f64> {
+  match (stage_seconds, total_seconds) {
+    (Some(stage), Some(total)) if total > 0 => Some(stage as f64 / total as f64 * 100.0),
+    _ => None,
+  }
+}
+
+/// Derive deep/REM percentages for last night and flag deep sleep that is
+/// unusually low for this athlete. None when last night has no stage data.
+pub fn compute_sleep_quality(last_night: &OuraDay, baseline: &[&OuraDay]) -> Option<SleepQuality> {
+  let deep_pct = stage_pct(last_night.deep_sleep_seconds, last_night.total_sleep_seconds);
+  let rem_pct = stage_pct(last_night.rem_sleep_seconds, last_night.total_sleep_seconds);
+  if deep_pct.is_none() && rem_pct.is_none() {
+    return None;
+  }
+
+  let baseline_deep: Vec<f64> = baseline
+    .iter()
+    .filter_map(|d| stage_pct(d.deep_sleep_seconds, d.total_sleep_seconds))
+    .collect();
+  let deep_pct_baseline = if baseline_deep.len() >= MIN_SLEEP_BASELINE_NIGHTS {
+    Some(baseline_deep.iter().sum::<f64>() / baseline_deep.len() as f64)
+  } else {
+    None
+  };
+
+  let low_deep_sleep = matches!(
+    (deep_pct, deep_pct_baseline),
+    (Some(deep), Some(base)) if deep < base * LOW_DEEP_SLEEP_RATIO
+  );
+
+  Some(SleepQuality {
+    deep_pct,
+    rem_pct,
+    deep_pct_baseline,
+    low_deep_sleep,
+  })
+}
+
+/// One day of stored Oura data. `date` is Oura's day, i.e. the morning the
+/// night's sleep ended, so it lines up with that day's workouts.
+#[derive(Debug, Clone, Default)]
+pub struct OuraDay {
+  pub date: chrono::NaiveDate,
+  pub total_sleep_seconds: Option<i64>,
+  pub deep_sleep_seconds: Option<i64>,
+  pub rem_sleep_seconds: Option<i64>,
+  pub efficiency_pct: Option<i64>,
+  pub hrv_ms: Option<f64>,
+  pub resting_hr: Option<i64>,
 }
 
 impl Default for OuraContext {
@@ -164,11 +251,69 @@ impl Default for OuraContext {
       resting_hr: None,
       resting_hr_avg_7d: None,
       resting_hr_trend: None,
+      rhr_elevated: false,
+      sleep_quality: None,
     }
   }
 }
 
 impl OuraContext {
+  /// Build the context for a specific day (the workout's date): that
+  /// morning's sleep/HRV/RHR against the preceding 7-day baseline.
+  /// Sleep debt is measured against `sleep_target_hours`.
+  pub fn for_date(date: chrono::NaiveDate, days: &[OuraDay], sleep_target_hours: f64) -> Self {
+    let today = days.iter().find(|d| d.date == date);
+    let baseline: Vec<&OuraDay> = days
+      .iter()
+      .filter(|d| d.date < date && (date - d.date).num_days() <= BASELINE_DAYS)
+      .collect();
+
+    let hours = |secs: Option<i64>| secs.map(|s| s as f64 / 3600.0);
+    let mean = |values: Vec<f64>| {
+      if values.is_empty() {
+        None
+      } else {
+        Some(values.iter().sum::<f64>() / values.len() as f64)
+      }
+    };
+
+    let sleep_duration_hours = today.and_then(|d| hours(d.total_sleep_seconds));
+    let sleep_avg_7d = mean(baseline.iter().filter_map(|d| hours(d.total_sleep_seconds)).collect());
+    let hrv_last_night = today.and_then(|d| d.hrv_ms);
+    let hrv_avg_7d = mean(baseline.iter().filter_map(|d| d.hrv_ms).collect());
+    let resting_hr = today.and_then(|d| d.resting_hr);
+    let resting_hr_avg_7d = mean(
+      baseline
+        .iter()
+        .filter_map(|d| d.resting_hr.map(|hr| hr as f64))
+        .collect(),
+    )
+    .map(|avg| avg.round() as i64);
+
+    Self {
+      sleep_duration_hours,
+      deep_sleep_hours: today.and_then(|d| hours(d.deep_sleep_seconds)),
+      rem_sleep_hours: today.and_then(|d| hours(d.rem_sleep_seconds)),
+      sleep_efficiency_pct: today.and_then(|d| d.efficiency_pct.map(|e| e as f64)),
+      sleep_avg_7d,
+      sleep_debt_hours: Self::compute_sleep_debt(sleep_avg_7d, sleep_target_hours),
+      hrv_last_night,
+      hrv_avg_7d,
+      hrv_trend_direction: Self::determine_hrv_trend(hrv_last_night, hrv_avg_7d),
+      hrv_declining_days: Self::count_hrv_declining_days(),
+      resting_hr,
+      resting_hr_avg_7d,
+      resting_hr_trend: Self::determine_resting_hr_trend(resting_hr, resting_hr_avg_7d),
+      rhr_elevated: Self::is_rhr_elevated(resting_hr, resting_hr_avg_7d),
+      sleep_quality: today.and_then(|d| compute_sleep_quality(d, &baseline)),
+    }
+  }
+
+  /// Morning RHR at least RHR_ELEVATED_BPM above baseline
+  pub fn is_rhr_elevated(current: Option<i64>, avg: Option<i64>) -> bool {
+    matches!((current, avg), (Some(curr), Some(avg)) if curr - avg >= RHR_ELEVATED_BPM)
+  }
+
   /// Check if any Oura data is present
   #[allow(dead_code)]
   pub fn has_data(&self) -> bool {
@@ -177,12 +322,10 @@ impl OuraContext {
       || self.resting_hr.is_some()
   }
 
-  /// Compute sleep debt (hours below 8hr target over last 7 days)
-  #[allow(dead_code)]
-  pub fn compute_sleep_debt(sleep_avg_7d: Option<f64>) -> Option<f64> {
+  /// Compute sleep debt (hours below the nightly target over last 7 days)
+  pub fn compute_sleep_debt(sleep_avg_7d: Option<f64>, target_hours: f64) -> Option<f64> {
     sleep_avg_7d.and_then(|avg| {
-      let target = 8.0;
-      let debt = (target - avg) * 7.0;
+      let debt = (target_hours - avg) * 7.0;
       if debt > 0.0 {
         Some(debt)
       } else {
@@ -192,7 +335,6 @@ impl OuraContext {
   }
 
   /// Determine HRV trend direction from recent data
-  #[allow(dead_code)]
   pub fn determine_hrv_trend(hrv_current: Option<f64>, hrv_avg: Option<f64>) -> Option<String> {
     match (hrv_current, hrv_avg) {
       (Some(current), Some(avg)) => {
@@ -213,13 +355,11 @@ impl OuraContext {
 
   /// Count consecutive days HRV has declined
   /// TODO: Implement when we have daily HRV history
-  #[allow(dead_code)]
   pub fn count_hrv_declining_days() -> Option<u8> {
     None  // Placeholder
   }
 
   /// Determine resting HR trend
-  #[allow(dead_code)]
   pub fn determine_resting_hr_trend(
     current: Option<i64>,
     avg: Option<i64>,
@@ -332,59 +472,68 @@ pub struct CallbackResult {
   pub code: String,
 }
 
-pub fn wait_for_callback() -> Result<CallbackResult, String> {
+/// Start a temporary HTTP server, wait for callback, extract auth code.
+/// Gives up after `timeout_seconds` so a closed browser can't hang the auth flow.
+pub fn wait_for_callback(timeout_seconds: u64) -> Result<CallbackResult, OuraError> {
   let listener = TcpListener::bind(format!("127.0.0.1:{}", REDIRECT_PORT))
-    .map_err(|e| format!("Failed to bind: {}", e))?;
+    .map_err(|e| OuraError::Server(format!("Failed to bind port {}: {}", REDIRECT_PORT, e)))?;
 
   println!("Listening for OAuth callback on port {}...", REDIRECT_PORT);
 
-  // Accept one connection
-  let mut stream = listener
-    .incoming()
-    .next()
-    .ok_or_else(|| "No connection received".to_string())?
-    .map_err(|e| format!("Connection error: {}", e))?;
+  accept_callback(listener, StdDuration::from_secs(timeout_seconds))
+}
 
-  // Read HTTP request
-  let mut buffer = [0; 1024];
-  let bytes_read = stream
-    .read(&mut buffer)
-    .map_err(|e| format!("Failed to read: {}", e))?;
+/// Poll a non-blocking listener until a callback arrives or the timeout expires
+fn accept_callback(listener: TcpListener, timeout: StdDuration) -> Result<CallbackResult, OuraError> {
+  listener
+    .set_nonblocking(true)
+    .map_err(|e| OuraError::Server(e.to_string()))?;
 
-  let request = String::from_utf8_lossy(&buffer[..bytes_read]);
+  let start = std::time::Instant::now();
 
-  // Extract code from query string
-  let code = request
-    .lines()
-    .next()
-    .and_then(|line| {
-      // Parse "GET /callback?code=XXX HTTP/1.1"
-      let parts: Vec<&str> = line.split_whitespace().collect();
-      if parts.len() >= 2 {
-        let path = parts[1];
-        if let Some(query_start) = path.find('?') {
-          let query = &path[query_start + 1..];
-          for pair in query.split('&') {
-            let kv: Vec<&str> = pair.split('=').collect();
-            if kv.len() == 2 && kv[0] == "code" {
-              return Some(kv[1].to_string());
-            }
-          }
-        }
-      }
-      None
-    })
-    .ok_or_else(|| "No code in callback".to_string())?;
+  loop {
+    if start.elapsed() > timeout {
+      return Err(OuraError::Server("timeout".into()));
+    }
 
-  // Send success response
-  let response = "HTTP/1.1 200 OK\r\n\r\n<html><body><h1>Oura Connected!</h1><p>You can close this window.</p></body></html>";
-  stream
-    .write_all(response.as_bytes())
-    .map_err(|e| format!("Failed to write response: {}", e))?;
+    match listener.accept() {
+      Ok((mut stream, _)) => {
+        // Accepted sockets may inherit non-blocking mode; read in blocking mode
+        stream.set_nonblocking(false).ok();
 
-  println!("Received authorization code");
+        let mut buffer = [0; 2048];
+        let bytes_read = stream.read(&mut buffer).unwrap_or(0);
+        let request = String::from_utf8_lossy(&buffer[..bytes_read]);
+
+        if let Some(code) = extract_code_from_request(&request) {
+          let response = "HTTP/1.1 200 OK\r\n\r\n<html><body><h1>Oura Connected!</h1><p>You can close this window.</p></body></html>";
+          stream.write_all(response.as_bytes()).ok();
+          stream.flush().ok();
+
+          println!("Received authorization code");
+          return Ok(CallbackResult { code });
+        } else if let Some(error) = extract_query_param(&request, "error") {
+          let response = "HTTP/1.1 400 Bad Request\r\n\r\n<html><body><h1>Connection Failed</h1><p>Please try again.</p></body></html>";
+          stream.write_all(response.as_bytes()).ok();
+          stream.flush().ok();
+
+          return Err(OuraError::OAuth(format!("Authorization denied: {}", error)));
+        }
+      }
+      Err(ref e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+        std::thread::sleep(StdDuration::from_millis(100));
+        continue;
+      }
+      Err(e) => {
+        return Err(OuraError::Server(e.to_string()));
+      }
+    }
+  }
+}
 
-  Ok(CallbackResult { code })
+/// Extract the authorization code from "GET /callback?code=XXX HTTP/1.1"
+fn extract_code_from_request(request: &str) -> Option<String> {
+  extract_query_param(request, "code")
 }
 
 /// ---------------------------------------------------------------------------
@@ -447,8 +596,37 @@ pub struct ReadinessContributors {
 /// Oura API Data Fetching
 /// ---------------------------------------------------------------------------
 
+/// Results of one sync's three independent fetches. Each endpoint succeeds
+/// or fails on its own so a single outage doesn't block the others.
+pub struct OuraFetch {
+  pub sleep: Result<DailySleepResponse, OuraError>,
+  pub periods: Result<SleepPeriodsResponse, OuraError>,
+  pub readiness: Result<DailyReadinessResponse, OuraError>,
+}
+
+/// Fetch daily sleep, sleep periods and readiness concurrently
+pub async fn fetch_all(access_token: &str, start_date: &str, end_date: &str) -> OuraFetch {
+  fetch_all_from(OURA_API_BASE, access_token, start_date, end_date).await
+}
+
+/// fetch_all against an explicit API base (tests point this at a local server)
+pub(crate) async fn fetch_all_from(
+  api_base: &str,
+  access_token: &str,
+  start_date: &str,
+  end_date: &str,
+) -> OuraFetch {
+  let (sleep, periods, readiness) = tokio::join!(
+    fetch_daily_sleep(api_base, access_token, start_date, end_date),
+    fetch_sleep_periods(api_base, access_token, start_date, end_date),
+    fetch_daily_readiness(api_base, access_token, start_date, end_date),
+  );
+  OuraFetch { sleep, periods, readiness }
+}
+
 /// Fetch daily sleep data from Oura API for a date range
-pub async fn fetch_daily_sleep(
+async fn fetch_daily_sleep(
+  api_base: &str,
   access_token: &str,
   start_date: &str,  // YYYY-MM-DD
   end_date: &str,    // YYYY-MM-DD
@@ -456,7 +634,7 @@ pub async fn fetch_daily_sleep(
   let client = Client::new();
   let url = format!(
     "{}/daily_sleep?start_date={}&end_date={}",
-    OURA_API_BASE, start_date, end_date
+    api_base, start_date, end_date
   );
 
   let response = client
@@ -478,7 +656,8 @@ pub async fn fetch_daily_sleep(
 }
 
 /// Fetch sleep periods data (contains HRV) from Oura API for a date range
-pub async fn fetch_sleep_periods(
+async fn fetch_sleep_periods(
+  api_base: &str,
   access_token: &str,
   start_date: &str,  // YYYY-MM-DD
   end_date: &str,    // YYYY-MM-DD
@@ -486,7 +665,7 @@ pub async fn fetch_sleep_periods(
   let client = Client::new();
   let url = format!(
     "{}/sleep?start_date={}&end_date={}",
-    OURA_API_BASE, start_date, end_date
+    api_base, start_date, end_date
   );
 
   let response = client
@@ -508,7 +687,8 @@ pub async fn fetch_sleep_periods(
 }
 
 /// Fetch daily readiness data (contains resting HR) from Oura API for a date range
-pub async fn fetch_daily_readiness(
+async fn fetch_daily_readiness(
+  api_base: &str,
   access_token: &str,
   start_date: &str,  // YYYY-MM-DD
   end_date: &str,    // YYYY-MM-DD
@@ -516,7 +696,7 @@ pub async fn fetch_daily_readiness(
   let client = Client::new();
   let url = format!(
     "{}/daily_readiness?start_date={}&end_date={}",
-    OURA_API_BASE, start_date, end_date
+    api_base, start_date, end_date
   );
 
   let response = client
@@ -537,6 +717,156 @@ pub async fn fetch_daily_readiness(
   Ok(response.json().await?)
 }
 
+/// ---------------------------------------------------------------------------
+/// CSV Import (historical Oura export)
+/// ---------------------------------------------------------------------------
+
+/// Header spellings per field, compared lowercase with `_` as a space
+const CSV_DATE_COLUMNS: &[&str] = &["date", "day", "summary date"];
+const CSV_TOTAL_SLEEP_COLUMNS: &[&str] = &["total sleep duration", "total sleep", "total sleep time"];
+const CSV_DEEP_SLEEP_COLUMNS: &[&str] = &["deep sleep duration", "deep sleep"];
+const CSV_REM_SLEEP_COLUMNS: &[&str] = &["rem sleep duration", "rem sleep"];
+const CSV_LIGHT_SLEEP_COLUMNS: &[&str] = &["light sleep duration", "light sleep"];
+const CSV_EFFICIENCY_COLUMNS: &[&str] = &["sleep efficiency", "efficiency"];
+const CSV_HRV_COLUMNS: &[&str] = &["average hrv", "average hrv ms", "hrv"];
+const CSV_RESTING_HR_COLUMNS: &[&str] = &[
+  "lowest resting heart rate", "lowest heart rate", "resting heart rate", "average resting heart rate",
+];
+
+/// One day parsed from an Oura CSV export. Durations are seconds.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OuraCsvDay {
+  pub date: String,
+  pub total_sleep_seconds: Option<i64>,
+  pub deep_sleep_seconds: Option<i64>,
+  pub rem_sleep_seconds: Option<i64>,
+  pub light_sleep_seconds: Option<i64>,
+  pub efficiency_pct: Option<i64>,
+  pub hrv_ms: Option<f64>,
+  pub resting_hr: Option<i64>,
+}
+
+impl OuraCsvDay {
+  pub fn has_sleep(&self) -> bool {
+    self.total_sleep_seconds.is_some()
+      || self.deep_sleep_seconds.is_some()
+      || self.rem_sleep_seconds.is_some()
+      || self.light_sleep_seconds.is_some()
+      || self.efficiency_pct.is_some()
+  }
+}
+
+/// Parsed export: good days plus a "line N: reason" note per skipped row
+#[derive(Debug, Default)]
+pub struct OuraCsvImport {
+  pub days: Vec<OuraCsvDay>,
+  pub skipped: Vec<String>,
+}
+
+/// Parse an Oura sleep/readiness CSV export. Columns are matched by header
+/// name, so missing or reordered columns are fine; only a date column and
+/// at least one known metric are required. Empty cells are left unset.
+pub fn parse_oura_csv(text: &str) -> Result<OuraCsvImport, String> {
+  let mut lines = text
+    .trim_start_matches('\u{feff}')
+    .lines()
+    .enumerate()
+    .filter(|(_, line)| !line.trim().is_empty());
+
+  let (_, header) = lines.next().ok_or_else(|| "CSV file is empty".to_string())?;
+  let headers: Vec<String> = split_csv_line(header)
+    .iter()
+    .map(|h| h.trim().to_lowercase().replace('_', " "))
+    .collect();
+  let column = |names: &[&str]| headers.iter().position(|h| names.contains(&h.as_str()));
+
+  let date_col = column(CSV_DATE_COLUMNS).ok_or_else(|| "CSV has no date column".to_string())?;
+  let total_col = column(CSV_TOTAL_SLEEP_COLUMNS);
+  let deep_col = column(CSV_DEEP_SLEEP_COLUMNS);
+  let rem_col = column(CSV_REM_SLEEP_COLUMNS);
+  let light_col = column(CSV_LIGHT_SLEEP_COLUMNS);
+  let efficiency_col = column(CSV_EFFICIENCY_COLUMNS);
+  let hrv_col = column(CSV_HRV_COLUMNS);
+  let rhr_col = column(CSV_RESTING_HR_COLUMNS);
+  let metric_cols = [total_col, deep_col, rem_col, light_col, efficiency_col, hrv_col, rhr_col];
+  if metric_cols.iter().all(Option::is_none) {
+    return Err("CSV has no recognized sleep, HRV or resting HR columns".to_string());
+  }
+
+  let mut import = OuraCsvImport::default();
+  for (index, line) in lines {
+    let line_no = index + 1;
+    let fields = split_csv_line(line);
+    let cell = |col: Option<usize>| {
+      col.and_then(|c| fields.get(c)).map(|f| f.trim()).filter(|f| !f.is_empty())
+    };
+
+    // Dates may carry a time ("2024-03-01T00:00:00+00:00"); the day is the prefix
+    let raw_date = cell(Some(date_col)).unwrap_or_default();
+    let date = match raw_date.get(..10).and_then(|d| chrono::NaiveDate::parse_from_str(d, "%Y-%m-%d").ok()) {
+      Some(date) => date.format("%Y-%m-%d").to_string(),
+      None => {
+        import.skipped.push(format!("line {}: invalid date '{}'", line_no, raw_date));
+        continue;
+      }
+    };
+
+    let mut bad_value = None;
+    let mut number = |col: Option<usize>| {
+      let raw = cell(col)?;
+      match raw.parse::<f64>() {
+        Ok(value) if value.is_finite() && value >= 0.0 => Some(value),
+        _ => {
+          bad_value.get_or_insert_with(|| raw.to_string());
+          None
+        }
+      }
+    };
+    let day = OuraCsvDay {
+      date,
+      total_sleep_seconds: number(total_col).map(|v| v.round() as i64),
+      deep_sleep_seconds: number(deep_col).map(|v| v.round() as i64),
+      rem_sleep_seconds: number(rem_col).map(|v| v.round() as i64),
+      light_sleep_seconds: number(light_col).map(|v| v.round() as i64),
+      efficiency_pct: number(efficiency_col).map(|v| v.round() as i64),
+      hrv_ms: number(hrv_col).filter(|v| *v > 0.0),
+      resting_hr: number(rhr_col).map(|v| v.round() as i64).filter(|v| *v > 0),
+    };
+
+    if let Some(raw) = bad_value {
+      import.skipped.push(format!("line {}: invalid value '{}'", line_no, raw));
+    } else if !day.has_sleep() && day.hrv_ms.is_none() && day.resting_hr.is_none() {
+      import.skipped.push(format!("line {}: no data", line_no));
+    } else {
+      import.days.push(day);
+    }
+  }
+
+  Ok(import)
+}
+
+/// Split one CSV line, honoring double-quoted fields and "" escapes
+fn split_csv_line(line: &str) -> Vec<String> {
+  let mut fields = Vec::new();
+  let mut field = String::new();
+  let mut in_quotes = false;
+  let mut chars = line.chars().peekable();
+
+  while let Some(c) = chars.next() {
+    match (c, in_quotes) {
+      ('"', true) if chars.peek() == Some(&'"') => {
+        field.push('"');
+        chars.next();
+      }
+      ('"', _) => in_quotes = !in_quotes,
+      (',', false) => fields.push(std::mem::take(&mut field)),
+      _ => field.push(c),
+    }
+  }
+  fields.push(field);
+  fields
+}
+
 /// ---------------------------------------------------------------------------
 /// Tests
 /// ---------------------------------------------------------------------------
@@ -548,20 +878,29 @@ mod tests {
   #[test]
   fn test_compute_sleep_debt_with_deficit() {
     // 6.5hr average vs 8hr target = 1.5hr * 7 days = 10.5hr debt
-    let result = OuraContext::compute_sleep_debt(Some(6.5));
+    let result = OuraContext::compute_sleep_debt(Some(6.5), 8.0);
     assert_eq!(result, Some(10.5));
   }
 
   #[test]
   fn test_compute_sleep_debt_no_deficit() {
     // 8.5hr average = no debt
-    let result = OuraContext::compute_sleep_debt(Some(8.5));
+    let result = OuraContext::compute_sleep_debt(Some(8.5), 8.0);
     assert_eq!(result, None);
   }
 
+  #[test]
+  fn test_sleep_debt_uses_target() {
+    // Same 7.2hr average: 5.6hr debt at 8hr, 2.1hr at 7.5hr, none at 7hr
+    let avg = Some(7.2);
+    assert!((OuraContext::compute_sleep_debt(avg, 8.0).unwrap() - 5.6).abs() < 1e-9);
+    assert!((OuraContext::compute_sleep_debt(avg, 7.5).unwrap() - 2.1).abs() < 1e-9);
+    assert_eq!(OuraContext::compute_sleep_debt(avg, 7.0), None);
+  }
+
   #[test]
   fn test_compute_sleep_debt_none() {
-    let result = OuraContext::compute_sleep_debt(None);
+    let result = OuraContext::compute_sleep_debt(None, 8.0);
     assert_eq!(result, None);
   }
 
@@ -606,4 +945,143 @@ mod tests {
     let result = OuraContext::determine_resting_hr_trend(Some(51), Some(50));
     assert_eq!(result, Some("stable".to_string()));
   }
+
+  #[test]
+  fn test_callback_times_out_without_connection() {
+    // Bind an ephemeral port so the test never collides with a real auth flow
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let start = std::time::Instant::now();
+
+    let result = accept_callback(listener, StdDuration::from_millis(300));
+
+    match result {
+      Err(OuraError::Server(msg)) => assert_eq!(msg, "timeout"),
+      Err(e) => panic!("Expected timeout error, got {}", e),
+      Ok(_) => panic!("Expected timeout error, got a callback"),
+    }
+    assert!(start.elapsed() < StdDuration::from_secs(5));
+  }
+
+  #[test]
+  fn test_extract_code_from_request() {
+    let request = "GET /callback?code=abc123&scope=daily HTTP/1.1\r\nHost: localhost\r\n";
+    assert_eq!(extract_code_from_request(request), Some("abc123".to_string()));
+  }
+
+  #[test]
+  fn test_extract_code_percent_encoded() {
+    let request = "GET /callback?code=x%2By%3D%3D&scope=daily HTTP/1.1\r\n";
+    assert_eq!(extract_code_from_request(request), Some("x+y==".to_string()));
+  }
+
+  fn oura_day(date: chrono::NaiveDate, resting_hr: i64) -> OuraDay {
+    OuraDay {
+      date,
+      total_sleep_seconds: Some(7 * 3600),
+      hrv_ms: Some(55.0),
+      resting_hr: Some(resting_hr),
+      ..Default::default()
+    }
+  }
+
+  #[test]
+  fn test_context_for_date_flags_elevated_morning_rhr() {
+    let workout_date = chrono::NaiveDate::from_ymd_opt(2024, 12, 10).unwrap();
+    let mut days: Vec<OuraDay> = (1..=7)
+      .map(|offset| oura_day(workout_date - Duration::days(offset), 50))
+      .collect();
+    days.push(oura_day(workout_date, 56));
+    // A later morning must not leak into the workout's context
+    days.push(oura_day(workout_date + Duration::days(1), 49));
+
+    let context = OuraContext::for_date(workout_date, &days, 8.0);
+    assert_eq!(context.resting_hr, Some(56));
+    assert_eq!(context.resting_hr_avg_7d, Some(50));
+    assert!(context.rhr_elevated);
+    assert_eq!(context.resting_hr_trend.as_deref(), Some("up"));
+    assert_eq!(context.sleep_duration_hours, Some(7.0));
+
+    // The next morning is back to baseline
+    let next = OuraContext::for_date(workout_date + Duration::days(1), &days, 8.0);
+    assert_eq!(next.resting_hr, Some(49));
+    assert!(!next.rhr_elevated);
+  }
+
+  #[test]
+  fn test_rhr_not_elevated_without_baseline() {
+    let date = chrono::NaiveDate::from_ymd_opt(2024, 12, 10).unwrap();
+    let context = OuraContext::for_date(date, &[oura_day(date, 70)], 8.0);
+    assert_eq!(context.resting_hr, Some(70));
+    assert!(!context.rhr_elevated);
+    assert!(!OuraContext::is_rhr_elevated(Some(54), Some(50)));
+    assert!(OuraContext::is_rhr_elevated(Some(55), Some(50)));
+  }
+
+  fn staged_night(date: chrono::NaiveDate, deep_minutes: i64) -> OuraDay {
+    OuraDay {
+      date,
+      total_sleep_seconds: Some(8 * 3600),
+      deep_sleep_seconds: Some(deep_minutes * 60),
+      rem_sleep_seconds: Some(96 * 60),
+      ..Default::default()
+    }
+  }
+
+  #[test]
+  fn test_low_deep_sleep_flags_poor_recovery() {
+    let date = chrono::NaiveDate::from_ymd_opt(2024, 12, 10).unwrap();
+    // Baseline ~18% deep (86 min of 8h); last night 48 min = 10%
+    let mut days: Vec<OuraDay> = (1..=7)
+      .map(|offset| staged_night(date - Duration::days(offset), 86))
+      .collect();
+    days.push(staged_night(date, 48));
+
+    let quality = OuraContext::for_date(date, &days, 8.0).sleep_quality.unwrap();
+    assert!((quality.deep_pct.unwrap() - 10.0).abs() < 1e-9);
+    assert!((quality.rem_pct.unwrap() - 20.0).abs() < 1e-9);
+    assert!((quality.deep_pct_baseline.unwrap() - 17.92).abs() < 0.01);
+    assert!(quality.low_deep_sleep);
+  }
+
+  #[test]
+  fn test_normal_deep_sleep_not_flagged() {
+    let date = chrono::NaiveDate::from_ymd_opt(2024, 12, 10).unwrap();
+    let baseline_nights: Vec<OuraDay> = (1..=7)
+      .map(|offset| staged_night(date - Duration::days(offset), 86))
+      .collect();
+    let baseline: Vec<&OuraDay> = baseline_nights.iter().collect();
+
+    let quality = compute_sleep_quality(&staged_night(date, 80), &baseline).unwrap();
+    assert!(!quality.low_deep_sleep);
+
+    // Too little baseline to judge
+    let quality = compute_sleep_quality(&staged_night(date, 30), &baseline[..2]).unwrap();
+    assert_eq!(quality.deep_pct_baseline, None);
+    assert!(!quality.low_deep_sleep);
+
+    // No stage data at all
+    assert_eq!(compute_sleep_quality(&OuraDay { date, ..Default::default() }, &baseline), None);
+  }
+
+  #[test]
+  fn test_parse_oura_csv_matches_columns_by_name() {
+    // Reordered snake_case headers, quoted cells and a timestamped day
+    let csv = "\u{feff}summary_date,average_hrv,\"total_sleep_duration\"\n\"2024-03-01T00:00:00+00:00\",\"61.0\",28800\n";
+    let import = parse_oura_csv(csv).unwrap();
+
+    assert!(import.skipped.is_empty());
+    assert_eq!(
+      import.days,
+      vec![OuraCsvDay {
+        date: "2024-03-01".to_string(),
+        total_sleep_seconds: Some(28800),
+        hrv_ms: Some(61.0),
+        ..Default::default()
+      }]
+    );
+
+    assert_eq!(split_csv_line(r#"a,"b, c","say ""hi""""#), vec!["a", "b, c", r#"say "hi""#]);
+    assert!(parse_oura_csv("").is_err());
+    assert!(parse_oura_csv("total_sleep_duration\n28800\n").is_err());
+  }
 }
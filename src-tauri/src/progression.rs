@@ -16,7 +16,7 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::{Row, SqlitePool};
 
-use crate::analysis::{TrainingContext, TrainingFlags};
+use crate::analysis::{TrainingContext, TrainingFlags, TsbBands};
 
 #[cfg(test)]
 use chrono::Duration;
@@ -94,15 +94,101 @@ pub enum StepConfig {
 
 impl StepConfig {
     pub fn from_json(json: &str) -> Result<Self, String> {
-        serde_json::from_str(json).map_err(|e| format!("Failed to parse step config: {}", e))
+        let config: Self = serde_json::from_str(json)
+            .map_err(|e| format!("Failed to parse step config: {}", e))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Check the config itself is usable: positive steps/options, non-empty
+    /// sequence, and a unit for numeric configs
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            StepConfig::Sequence { sequence } => {
+                if sequence.is_empty() {
+                    return Err("Sequence step config has no values".to_string());
+                }
+                if sequence.iter().any(|v| v.trim().is_empty()) {
+                    return Err("Sequence step config contains an empty value".to_string());
+                }
+            }
+            StepConfig::Increment { increment, unit } => {
+                if *increment <= 0 {
+                    return Err(format!("Increment must be positive, got {}", increment));
+                }
+                if unit.trim().is_empty() {
+                    return Err("Increment step config is missing a unit".to_string());
+                }
+            }
+            StepConfig::Regulated { options, unit } => {
+                if options.is_empty() {
+                    return Err("Regulated step config has no options".to_string());
+                }
+                if let Some(bad) = options.iter().find(|o| **o <= 0) {
+                    return Err(format!("Regulated options must be positive, got {}", bad));
+                }
+                if unit.trim().is_empty() {
+                    return Err("Regulated step config is missing a unit".to_string());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Check a stored value (current or ceiling) is valid for this config
+    pub fn validate_value(&self, value: &str) -> Result<(), String> {
+        match self {
+            StepConfig::Sequence { sequence } => {
+                if !sequence.iter().any(|v| v == value) {
+                    return Err(format!("'{}' is not in the sequence", value));
+                }
+            }
+            StepConfig::Increment { .. } | StepConfig::Regulated { .. } => {
+                match value.trim().parse::<i32>() {
+                    Ok(v) if v > 0 => {}
+                    Ok(v) => return Err(format!("Value must be positive, got {}", v)),
+                    Err(_) => return Err(format!("'{}' is not a whole number", value)),
+                }
+            }
+        }
+        Ok(())
     }
 
     pub fn to_json(&self) -> String {
         serde_json::to_string(self).unwrap_or_default()
     }
 
-    /// Get the next value in the progression
-    pub fn next_value(&self, current: &str) -> Option<String> {
+    /// Check a value can be set directly as the current value: in the
+    /// sequence, a whole number of increments away from `current`, or one
+    /// of the regulated options
+    pub fn validate_target(&self, current: &str, value: &str) -> Result<(), String> {
+        self.validate_value(value)?;
+        match self {
+            StepConfig::Sequence { .. } => {}
+            StepConfig::Increment { increment, unit } => {
+                let target: i32 = value.trim().parse().unwrap_or(0);
+                if let Ok(from) = current.trim().parse::<i32>() {
+                    if (target - from) % increment != 0 {
+                        return Err(format!(
+                            "{} is not a whole number of {} {} steps from {}",
+                            target, increment, unit, from
+                        ));
+                    }
+                }
+            }
+            StepConfig::Regulated { options, unit } => {
+                let target: i32 = value.trim().parse().unwrap_or(0);
+                if !options.contains(&target) {
+                    return Err(format!("{} {} is not one of the regulated options {:?}", target, unit, options));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Get the next value in the progression. An increment that would pass
+    /// the ceiling stops exactly at it.
+    pub fn next_value(&self, current: &str, ceiling: &str) -> Option<String> {
         match self {
             StepConfig::Sequence { sequence } => {
                 let idx = sequence.iter().position(|v| v == current)?;
@@ -110,7 +196,8 @@ impl StepConfig {
             }
             StepConfig::Increment { increment, .. } => {
                 let current_val: i32 = current.parse().ok()?;
-                Some((current_val + increment).to_string())
+                let ceiling_val: i32 = ceiling.parse().unwrap_or(i32::MAX);
+                Some((current_val + increment).min(ceiling_val).to_string())
             }
             StepConfig::Regulated { .. } => None, // No progression for regulated
         }
@@ -140,6 +227,17 @@ impl StepConfig {
         }
     }
 
+    /// Plottable position of a value: index for sequences, the number itself
+    /// for increment/regulated values
+    pub fn ordinal(&self, value: &str) -> Option<f64> {
+        match self {
+            StepConfig::Sequence { sequence } => {
+                sequence.iter().position(|v| v == value).map(|i| i as f64)
+            }
+            StepConfig::Increment { .. } | StepConfig::Regulated { .. } => value.parse().ok(),
+        }
+    }
+
     /// Check if value is at or beyond ceiling
     pub fn is_at_ceiling(&self, current: &str, ceiling: &str) -> bool {
         match self {
@@ -160,21 +258,20 @@ impl StepConfig {
         }
     }
 
-    /// Get regulated duration based on TSB
-    pub fn get_regulated_duration(&self, tsb: Option<f64>) -> Option<i32> {
+    /// Get regulated duration based on the TSB band
+    pub fn get_regulated_duration(&self, tsb: Option<f64>, bands: &TsbBands) -> Option<i32> {
         match self {
             StepConfig::Regulated { options, .. } => {
-                let tsb_val = tsb.unwrap_or(0.0);
                 if options.len() >= 2 {
-                    if tsb_val >= 0.0 {
-                        // Fresh: longest duration
-                        options.last().copied()
-                    } else if tsb_val >= -10.0 {
-                        // Moderate fatigue: shorter duration
-                        options.first().copied()
-                    } else {
-                        // High fatigue: recovery spin (30-40 min or first option)
-                        Some(options.first().copied().unwrap_or(30).min(40))
+                    match bands.band(tsb) {
+                        // Slightly fatigued: shorter duration
+                        "slightly_fatigued" => options.first().copied(),
+                        // Moderate or high fatigue: recovery spin (30-40 min or first option)
+                        "moderate_fatigue" | "high_fatigue" => {
+                            Some(options.first().copied().unwrap_or(30).min(40))
+                        }
+                        // Fresh (or no TSB yet): longest duration
+                        _ => options.last().copied(),
                     }
                 } else {
                     options.first().copied()
@@ -185,6 +282,125 @@ impl StepConfig {
     }
 }
 
+/// ---------------------------------------------------------------------------
+/// Progression Criteria: Gating rules for a progressive dimension
+/// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProgressionCriteria {
+    /// Minimum days between value changes
+    pub min_days_between_changes: i64,
+    /// TSB must be above this to progress
+    pub min_tsb: f64,
+    /// Block progression while intensity is heavy
+    pub require_stable_intensity: bool,
+}
+
+impl Default for ProgressionCriteria {
+    fn default() -> Self {
+        Self {
+            min_days_between_changes: 7,
+            min_tsb: -10.0,
+            require_stable_intensity: false,
+        }
+    }
+}
+
+impl ProgressionCriteria {
+    /// Built-in criteria for dimensions without stored criteria
+    pub fn defaults_for(name: &str) -> Self {
+        match name {
+            "run_interval" => Self {
+                min_tsb: -15.0,
+                require_stable_intensity: true,
+                ..Self::default()
+            },
+            "long_run" => Self {
+                min_tsb: -15.0,
+                ..Self::default()
+            },
+            _ => Self::default(),
+        }
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let criteria: Self = serde_json::from_str(json)
+            .map_err(|e| format!("Failed to parse criteria: {}", e))?;
+        if criteria.min_days_between_changes < 0 {
+            return Err(format!(
+                "min_days_between_changes must not be negative, got {}",
+                criteria.min_days_between_changes
+            ));
+        }
+        Ok(criteria)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+}
+
+/// Beginners wait at least this many days between changes
+pub const BEGINNER_MIN_DAYS_BETWEEN_CHANGES: i64 = 10;
+/// Advanced athletes wait this fraction of the usual days between changes
+pub const ADVANCED_DAYS_FACTOR: f64 = 0.75;
+/// TSB margin by experience: beginners must be this much fresher than the
+/// criteria ask, advanced athletes may be this much more fatigued
+pub const EXPERIENCE_TSB_MARGIN: f64 = 5.0;
+
+/// How aggressively the engine progresses the athlete
+/// (UserSettings.experience_level); scales every dimension's criteria
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExperienceLevel {
+    Beginner,
+    #[default]
+    Intermediate,
+    Advanced,
+}
+
+impl ExperienceLevel {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "beginner" => Some(ExperienceLevel::Beginner),
+            "intermediate" => Some(ExperienceLevel::Intermediate),
+            "advanced" => Some(ExperienceLevel::Advanced),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ExperienceLevel::Beginner => "beginner",
+            ExperienceLevel::Intermediate => "intermediate",
+            ExperienceLevel::Advanced => "advanced",
+        }
+    }
+
+    /// Criteria scaled to the athlete: beginners wait longer and need to be
+    /// fresher, advanced athletes may progress sooner and under more fatigue.
+    /// Intermediate leaves the criteria as configured.
+    pub fn adjust(&self, criteria: ProgressionCriteria) -> ProgressionCriteria {
+        match self {
+            ExperienceLevel::Beginner => ProgressionCriteria {
+                min_days_between_changes: criteria
+                    .min_days_between_changes
+                    .max(BEGINNER_MIN_DAYS_BETWEEN_CHANGES),
+                min_tsb: criteria.min_tsb + EXPERIENCE_TSB_MARGIN,
+                ..criteria
+            },
+            ExperienceLevel::Intermediate => criteria,
+            ExperienceLevel::Advanced => ProgressionCriteria {
+                min_days_between_changes: (criteria.min_days_between_changes as f64 * ADVANCED_DAYS_FACTOR).round()
+                    as i64,
+                min_tsb: criteria.min_tsb - EXPERIENCE_TSB_MARGIN,
+                ..criteria
+            },
+        }
+    }
+}
+
 /// ---------------------------------------------------------------------------
 /// Progression Dimension: Generic dimension from database
 /// ---------------------------------------------------------------------------
@@ -200,6 +416,15 @@ pub struct ProgressionDimension {
     pub last_change_at: Option<DateTime<Utc>>,
     pub last_ceiling_touch_at: Option<DateTime<Utc>>,
     pub maintenance_cadence_days: i32,
+    /// Stored gating rules (None = built-in defaults for the name)
+    #[serde(default)]
+    pub criteria: Option<ProgressionCriteria>,
+    /// Dimension that must be established before this one may progress
+    #[serde(default)]
+    pub prerequisite: Option<String>,
+    /// Value the prerequisite must have reached (None = its ceiling)
+    #[serde(default)]
+    pub prerequisite_min: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -213,6 +438,18 @@ impl ProgressionDimension {
         }
     }
 
+    /// Validate the step config and stored values so a malformed row fails
+    /// loudly instead of leaving the dimension silently stuck
+    pub fn validate(&self) -> Result<(), String> {
+        self.step_config
+            .validate()
+            .and_then(|_| self.step_config.validate_value(&self.current_value))
+            .map_err(|e| format!("Invalid dimension '{}': {}", self.name, e))?;
+        self.step_config
+            .validate_value(&self.ceiling_value)
+            .map_err(|e| format!("Invalid dimension '{}' ceiling: {}", self.name, e))
+    }
+
     /// Check if this dimension is at its ceiling
     pub fn is_at_ceiling(&self) -> bool {
         self.step_config.is_at_ceiling(&self.current_value, &self.ceiling_value)
@@ -223,7 +460,7 @@ impl ProgressionDimension {
         if self.is_at_ceiling() {
             None
         } else {
-            self.step_config.next_value(&self.current_value)
+            self.step_config.next_value(&self.current_value, &self.ceiling_value)
         }
     }
 
@@ -246,6 +483,34 @@ impl ProgressionDimension {
         }
     }
 
+    /// Maintenance reminder for an at-ceiling dimension (None when not due,
+    /// and always None for regulated dimensions)
+    pub fn maintenance_status(&self) -> Option<MaintenanceDue> {
+        if self.dimension_type() == DimensionType::Regulated || !self.maintenance_due() {
+            return None;
+        }
+        let days_since_touch = self.last_ceiling_touch_at.map(|t| (Utc::now() - t).num_days());
+        let recommended_session = match &self.step_config {
+            StepConfig::Increment { unit, .. } | StepConfig::Regulated { unit, .. } => {
+                format!("{} of {} {} (ceiling touch)", self.name.replace('_', " "), self.ceiling_value, unit)
+            }
+            StepConfig::Sequence { .. } => {
+                format!("{} at {} (ceiling touch)", self.name.replace('_', " "), self.ceiling_value)
+            }
+        };
+
+        Some(MaintenanceDue {
+            name: self.name.clone(),
+            ceiling: self.ceiling_value.clone(),
+            cadence_days: self.maintenance_cadence_days,
+            days_since_touch,
+            days_overdue: days_since_touch
+                .map(|days| days - self.maintenance_cadence_days as i64)
+                .unwrap_or(0),
+            recommended_session,
+        })
+    }
+
     /// Check if regression is warranted (at ceiling but haven't touched in 21+ days)
     pub fn should_regress(&self) -> bool {
         if self.status != LifecycleStatus::AtCeiling {
@@ -267,12 +532,50 @@ impl ProgressionDimension {
             .unwrap_or(30)
     }
 
-    /// Get regulated duration for cycling based on TSB
-    pub fn get_regulated_duration(&self, tsb: Option<f64>) -> Option<i32> {
-        self.step_config.get_regulated_duration(tsb)
+    /// Get regulated duration for cycling based on the TSB band
+    pub fn get_regulated_duration(&self, tsb: Option<f64>, bands: &TsbBands) -> Option<i32> {
+        self.step_config.get_regulated_duration(tsb, bands)
+    }
+
+    /// Where this dimension's prerequisite stands (None when it has none).
+    /// A prerequisite missing from `dimensions` counts as not met.
+    pub fn prerequisite_status(&self, dimensions: &[ProgressionDimension]) -> Option<PrerequisiteStatus> {
+        let name = self.prerequisite.as_ref()?;
+        let prereq = dimensions.iter().find(|d| &d.name == name);
+        let required = self
+            .prerequisite_min
+            .clone()
+            .or_else(|| prereq.map(|d| d.ceiling_value.clone()))
+            .unwrap_or_default();
+        let met = prereq.map_or(false, |d| d.step_config.is_at_ceiling(&d.current_value, &required));
+
+        Some(PrerequisiteStatus {
+            name: name.clone(),
+            required,
+            current: prereq.map(|d| d.current_value.clone()),
+            met,
+        })
+    }
+
+    /// Criteria that gate progression (stored, or defaults for the name)
+    pub fn effective_criteria(&self) -> ProgressionCriteria {
+        self.criteria
+            .clone()
+            .unwrap_or_else(|| ProgressionCriteria::defaults_for(&self.name))
     }
 }
 
+/// A dimension's prerequisite checked against its current value
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PrerequisiteStatus {
+    pub name: String,
+    /// Value the prerequisite must have reached
+    pub required: String,
+    /// Prerequisite's current value (None if the dimension doesn't exist)
+    pub current: Option<String>,
+    pub met: bool,
+}
+
 /// ---------------------------------------------------------------------------
 /// Adherence Summary (preserved from original)
 /// ---------------------------------------------------------------------------
@@ -346,6 +649,31 @@ impl Default for AdherenceSummary {
     }
 }
 
+/// An at-ceiling dimension whose ceiling needs touching before it regresses
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MaintenanceDue {
+    pub name: String,
+    pub ceiling: String,
+    pub cadence_days: i32,
+    /// Days since the ceiling was last touched (None if never)
+    pub days_since_touch: Option<i64>,
+    /// Days past the cadence (0 = due today, or never touched)
+    pub days_overdue: i64,
+    /// The session that counts as a ceiling touch
+    pub recommended_session: String,
+}
+
+/// Every dimension with maintenance due, most overdue first
+pub async fn load_maintenance_due(pool: &SqlitePool) -> Result<Vec<MaintenanceDue>, String> {
+    let mut due: Vec<MaintenanceDue> = load_all_dimensions(pool)
+        .await?
+        .iter()
+        .filter_map(ProgressionDimension::maintenance_status)
+        .collect();
+    due.sort_by(|a, b| b.days_overdue.cmp(&a.days_overdue));
+    Ok(due)
+}
+
 /// ---------------------------------------------------------------------------
 /// Engine Decision: What Rust allows
 /// ---------------------------------------------------------------------------
@@ -403,17 +731,11 @@ impl ProgressionSummary {
         context: &TrainingContext,
         flags: &TrainingFlags,
         adherence: AdherenceSummary,
+        overlap_days: i64,
+        experience: ExperienceLevel,
     ) -> Self {
         // Find most recent progression (for overlap rule)
-        let most_recent = dimensions
-            .iter()
-            .filter_map(|d| d.last_change_at.map(|dt| (d.name.clone(), dt)))
-            .max_by_key(|(_, dt)| *dt);
-
-        let last_progression_dimension = most_recent.as_ref().map(|(name, _)| name.clone());
-        let days_since_any_progression = most_recent
-            .map(|(_, dt)| (Utc::now() - dt).num_days())
-            .unwrap_or(30);
+        let (last_progression_dimension, days_since_any_progression) = last_progression(dimensions);
 
         // Build status for each dimension
         let dimension_statuses: Vec<DimensionStatus> = dimensions
@@ -421,11 +743,14 @@ impl ProgressionSummary {
             .map(|dim| {
                 Self::build_dimension_status(
                     dim,
+                    dimensions,
                     context,
                     flags,
                     &adherence,
                     &last_progression_dimension,
                     days_since_any_progression,
+                    overlap_days,
+                    experience,
                 )
             })
             .collect();
@@ -438,71 +763,262 @@ impl ProgressionSummary {
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn build_dimension_status(
         dim: &ProgressionDimension,
+        dimensions: &[ProgressionDimension],
         context: &TrainingContext,
         flags: &TrainingFlags,
         adherence: &AdherenceSummary,
         last_prog_dim: &Option<String>,
         days_since_any: i64,
+        overlap_days: i64,
+        experience: ExperienceLevel,
     ) -> DimensionStatus {
         let dim_type = dim.dimension_type();
+        let trace = DecisionTrace::evaluate(
+            dim,
+            dimensions,
+            context,
+            flags,
+            adherence,
+            last_prog_dim,
+            days_since_any,
+            overlap_days,
+            experience,
+        );
 
         // For regulated dimensions (cycling), just report current state
         if dim_type == DimensionType::Regulated {
-            let regulated_duration = dim.get_regulated_duration(context.tsb);
-            let tsb_desc = match context.tsb {
-                Some(t) if t >= 0.0 => "fresh",
-                Some(t) if t >= -10.0 => "moderate fatigue",
-                Some(_) => "fatigued",
-                None => "unknown fatigue",
-            };
-
             return DimensionStatus {
                 name: dim.name.clone(),
                 dimension_type: dim_type,
                 current: dim.current_value.clone(),
                 ceiling: dim.ceiling_value.clone(),
                 status: LifecycleStatus::AtCeiling, // Regulated = always at "ceiling"
-                engine_decision: EngineDecision::Regulated,
-                reason: format!(
-                    "Duration regulated by TSB ({}): {} min recommended",
-                    tsb_desc,
-                    regulated_duration.unwrap_or(45)
-                ),
+                engine_decision: trace.engine_decision,
+                reason: trace.reason,
                 next_value: None,
                 days_since_change: dim.days_since_change(),
                 maintenance_due: false,
-                regulated_duration,
+                regulated_duration: dim.get_regulated_duration(context.tsb, &flags.tsb_bands),
+            };
+        }
+
+        DimensionStatus {
+            name: dim.name.clone(),
+            dimension_type: dim_type,
+            current: dim.current_value.clone(),
+            ceiling: dim.ceiling_value.clone(),
+            status: dim.status,
+            engine_decision: trace.engine_decision,
+            reason: trace.reason,
+            next_value: dim.next_value(),
+            days_since_change: dim.days_since_change(),
+            maintenance_due: dim.maintenance_due(),
+            regulated_duration: None,
+        }
+    }
+
+    /// Get status for a specific dimension by name
+    #[allow(dead_code)]
+    pub fn get_dimension(&self, name: &str) -> Option<&DimensionStatus> {
+        self.dimensions.iter().find(|d| d.name == name)
+    }
+}
+
+/// Most recently changed dimension and days since that change (30 if none)
+fn last_progression(dimensions: &[ProgressionDimension]) -> (Option<String>, i64) {
+    let most_recent = dimensions
+        .iter()
+        .filter_map(|d| d.last_change_at.map(|dt| (d.name.clone(), dt)))
+        .max_by_key(|(_, dt)| *dt);
+
+    let days_since = most_recent
+        .as_ref()
+        .map(|(_, dt)| (Utc::now() - *dt).num_days())
+        .unwrap_or(30);
+    (most_recent.map(|(name, _)| name), days_since)
+}
+
+/// ---------------------------------------------------------------------------
+/// Decision Trace: every rule behind an engine decision
+/// ---------------------------------------------------------------------------
+
+/// One condition the engine evaluated, with the values it compared.
+/// `passed` means the condition doesn't stand in the way of progressing.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TraceCheck {
+    pub name: String,
+    pub passed: bool,
+    pub actual: String,
+    pub required: String,
+}
+
+impl TraceCheck {
+    fn new(name: &str, passed: bool, actual: impl Into<String>, required: impl Into<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            passed,
+            actual: actual.into(),
+            required: required.into(),
+        }
+    }
+}
+
+/// Full trace for one dimension: the decision, its one-line reason, and
+/// every condition evaluated (not just the one that decided it)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DecisionTrace {
+    pub dimension: String,
+    pub engine_decision: EngineDecision,
+    pub reason: String,
+    pub checks: Vec<TraceCheck>,
+}
+
+impl DecisionTrace {
+    /// Trace a dimension by name against the current context
+    pub fn compute(
+        dimensions: &[ProgressionDimension],
+        name: &str,
+        context: &TrainingContext,
+        flags: &TrainingFlags,
+        adherence: &AdherenceSummary,
+        overlap_days: i64,
+        experience: ExperienceLevel,
+    ) -> Option<Self> {
+        let dim = dimensions.iter().find(|d| d.name == name)?;
+        let (last_prog_dim, days_since_any) = last_progression(dimensions);
+        Some(Self::evaluate(
+            dim,
+            dimensions,
+            context,
+            flags,
+            adherence,
+            &last_prog_dim,
+            days_since_any,
+            overlap_days,
+            experience,
+        ))
+    }
+
+    /// Evaluate every rule, then pick the decision by precedence:
+    /// regression, ceiling, adherence, key session, overlap, prerequisite,
+    /// criteria, week
+    #[allow(clippy::too_many_arguments)]
+    fn evaluate(
+        dim: &ProgressionDimension,
+        dimensions: &[ProgressionDimension],
+        context: &TrainingContext,
+        flags: &TrainingFlags,
+        adherence: &AdherenceSummary,
+        last_prog_dim: &Option<String>,
+        days_since_any: i64,
+        overlap_days: i64,
+        experience: ExperienceLevel,
+    ) -> Self {
+        let tsb = context.tsb.map_or("unknown".to_string(), |t| format!("{:.1}", t));
+
+        if dim.dimension_type() == DimensionType::Regulated {
+            let tsb_desc = flags.tsb_bands.band(context.tsb).replace('_', " ");
+            let duration = dim.get_regulated_duration(context.tsb, &flags.tsb_bands).unwrap_or(45);
+            return Self {
+                dimension: dim.name.clone(),
+                engine_decision: EngineDecision::Regulated,
+                reason: format!("Duration regulated by TSB ({}): {} min recommended", tsb_desc, duration),
+                checks: vec![TraceCheck::new("tsb_band", true, tsb, format!("{} min for this band", duration))],
             };
         }
 
-        // Progressive dimension logic
         let is_at_ceiling = dim.is_at_ceiling();
         let maintenance_due = dim.maintenance_due();
         let should_regress = dim.should_regress();
+        let low_adherence_regress = adherence.should_consider_regression() && dim.prev_value().is_some();
+        let missed_key_session = !adherence.key_adherence_good && is_key_session_dimension(&dim.name);
 
-        // Check dimension-specific criteria
-        let (criteria_met, criteria_reason) =
-            Self::check_criteria(&dim.name, dim, context, flags);
-
-        // Apply overlap rule: if another dimension progressed in last 7 days, hold
+        // Overlap rule: if another dimension progressed within the overlap
+        // window (UserSettings.progression_overlap_days), hold
         let overlap_blocked = last_prog_dim.as_ref().map_or(false, |last| {
-            last != &dim.name && days_since_any < 7
+            last != &dim.name && days_since_any < overlap_days
         });
 
+        let prerequisite = dim.prerequisite_status(dimensions);
+        let prerequisite_blocked = prerequisite.as_ref().map_or(false, |p| !p.met);
+
+        let days_since_touch = dim.last_ceiling_touch_at.map(|d| (Utc::now() - d).num_days());
+        let adherence_pct = (adherence.adherence_pct * 100.0) as i32;
+
+        let mut checks = vec![
+            TraceCheck::new(
+                "ceiling_touch",
+                !should_regress,
+                days_since_touch.map_or("never touched".to_string(), |d| format!("{} days since ceiling touch", d)),
+                "< 21 days at ceiling",
+            ),
+            TraceCheck::new(
+                "low_adherence_streak",
+                !low_adherence_regress,
+                format!("{} consecutive low-adherence weeks", adherence.consecutive_low_adherence_weeks),
+                "< 2 weeks",
+            ),
+            TraceCheck::new(
+                "below_ceiling",
+                !is_at_ceiling,
+                dim.current_value.clone(),
+                format!("below {}", dim.ceiling_value),
+            ),
+            TraceCheck::new(
+                "maintenance",
+                !maintenance_due,
+                if maintenance_due { "due" } else { "not due" },
+                format!("ceiling touched every {} days", dim.maintenance_cadence_days),
+            ),
+            TraceCheck::new("adherence", !adherence.is_unstable(), format!("{}%", adherence_pct), ">= 70%"),
+            TraceCheck::new(
+                "key_session",
+                !missed_key_session,
+                format!("{}/{} key sessions", adherence.key_completed, adherence.key_expected),
+                if is_key_session_dimension(&dim.name) { "all key sessions" } else { "not a key-session dimension" },
+            ),
+            TraceCheck::new(
+                "overlap",
+                !overlap_blocked,
+                match last_prog_dim {
+                    Some(last) => format!("{} progressed {} days ago", last, days_since_any),
+                    None => "no recent progression".to_string(),
+                },
+                format!(">= {} days since another dimension progressed", overlap_days),
+            ),
+        ];
+        if let Some(p) = &prerequisite {
+            checks.push(TraceCheck::new(
+                "prerequisite",
+                p.met,
+                format!("{} at {}", p.name, p.current.as_deref().unwrap_or("missing")),
+                format!("{} at {} or beyond", p.name, p.required),
+            ));
+        }
+
+        let (criteria_checks, criteria_reason) = Self::check_criteria(dim, context, flags, experience);
+        let criteria_met = criteria_checks.iter().all(|c| c.passed);
+        checks.extend(criteria_checks);
+        checks.push(TraceCheck::new(
+            "week_stable",
+            adherence.week_stable,
+            format!("{}% adherence, {}/{} key sessions", adherence_pct, adherence.key_completed, adherence.key_expected),
+            ">= 75% with key sessions done",
+        ));
+
         // Determine engine decision
         let (engine_decision, reason) = if should_regress {
             (
                 EngineDecision::Regress,
                 format!(
                     "Haven't touched ceiling in {} days, stepping back",
-                    dim.last_ceiling_touch_at
-                        .map(|d| (Utc::now() - d).num_days())
-                        .unwrap_or(0)
+                    days_since_touch.unwrap_or(0)
                 ),
             )
-        } else if adherence.should_consider_regression() && dim.prev_value().is_some() {
+        } else if low_adherence_regress {
             (
                 EngineDecision::Regress,
                 format!(
@@ -528,12 +1044,9 @@ impl ProgressionSummary {
         } else if adherence.is_unstable() {
             (
                 EngineDecision::HoldDueToUnstableWeek,
-                format!(
-                    "Week had {}% adherence (need 70%)",
-                    (adherence.adherence_pct * 100.0) as i32
-                ),
+                format!("Week had {}% adherence (need 70%)", adherence_pct),
             )
-        } else if !adherence.key_adherence_good && is_key_session_dimension(&dim.name) {
+        } else if missed_key_session {
             (
                 EngineDecision::HoldDueToMissedKeySession,
                 "Key session missed this week".to_string(),
@@ -542,10 +1055,15 @@ impl ProgressionSummary {
             (
                 EngineDecision::HoldForNow,
                 format!(
-                    "Another dimension progressed {} days ago (need 7)",
-                    days_since_any
+                    "Another dimension progressed {} days ago (need {})",
+                    days_since_any, overlap_days
                 ),
             )
+        } else if let Some(p) = prerequisite.as_ref().filter(|_| prerequisite_blocked) {
+            (
+                EngineDecision::Hold,
+                format!("prerequisite {} not at {}", p.name, p.required),
+            )
         } else if !criteria_met {
             (EngineDecision::Hold, criteria_reason)
         } else if !adherence.week_stable {
@@ -557,52 +1075,65 @@ impl ProgressionSummary {
             (EngineDecision::ProgressAllowed, "All criteria met".to_string())
         };
 
-        DimensionStatus {
-            name: dim.name.clone(),
-            dimension_type: dim_type,
-            current: dim.current_value.clone(),
-            ceiling: dim.ceiling_value.clone(),
-            status: dim.status,
+        Self {
+            dimension: dim.name.clone(),
             engine_decision,
             reason,
-            next_value: dim.next_value(),
-            days_since_change: dim.days_since_change(),
-            maintenance_due,
-            regulated_duration: None,
+            checks,
         }
     }
 
-    /// Check dimension-specific criteria
+    /// Check dimension-specific criteria, scaled for the athlete's
+    /// experience: one trace entry per criterion, plus the combined reason
+    /// used when they aren't all met
     fn check_criteria(
-        name: &str,
         dim: &ProgressionDimension,
         context: &TrainingContext,
         flags: &TrainingFlags,
-    ) -> (bool, String) {
+        experience: ExperienceLevel,
+    ) -> (Vec<TraceCheck>, String) {
+        let criteria = experience.adjust(dim.effective_criteria());
         let days_since_change = dim.days_since_change();
-        let min_days = 7;
+        let min_days = criteria.min_days_between_changes;
 
         let volume_stable = !flags.volume_spike && !flags.volume_drop;
 
-        // Fatigue thresholds vary by dimension
-        let (fatigue_low, fatigue_threshold) = match name {
-            "run_interval" => (context.tsb.map_or(true, |t| t > -15.0), -15.0),
-            "long_run" => (context.tsb.map_or(true, |t| t > -15.0), -15.0),
-            _ => (context.tsb.map_or(true, |t| t > -10.0), -10.0),
-        };
+        let fatigue_threshold = criteria.min_tsb;
+        let fatigue_low = context.tsb.map_or(true, |t| t > fatigue_threshold);
 
-        // HR stability matters more for run intervals
-        let hr_stability = if name == "run_interval" {
-            !flags.intensity_heavy
+        let hr_stability = !criteria.require_stable_intensity || !flags.intensity_heavy;
+
+        let volume_state = if flags.volume_spike {
+            "volume spike"
+        } else if flags.volume_drop {
+            "volume drop"
         } else {
-            true
+            "stable"
         };
-
-        let criteria_met =
-            days_since_change >= min_days && volume_stable && fatigue_low && hr_stability;
-
-        if criteria_met {
-            (true, "All criteria met".to_string())
+        let checks = vec![
+            TraceCheck::new(
+                "days_since_change",
+                days_since_change >= min_days,
+                format!("{} days", days_since_change),
+                format!(">= {} days", min_days),
+            ),
+            TraceCheck::new("volume_stability", volume_stable, volume_state, "no volume spike or drop"),
+            TraceCheck::new(
+                "fatigue",
+                fatigue_low,
+                context.tsb.map_or("unknown".to_string(), |t| format!("TSB {:.1}", t)),
+                format!("TSB > {:.1}", fatigue_threshold),
+            ),
+            TraceCheck::new(
+                "intensity_stability",
+                hr_stability,
+                if flags.intensity_heavy { "intensity heavy" } else { "stable" },
+                if criteria.require_stable_intensity { "not intensity heavy" } else { "not required" },
+            ),
+        ];
+
+        if checks.iter().all(|c| c.passed) {
+            (checks, "All criteria met".to_string())
         } else {
             let mut reasons = Vec::new();
             if days_since_change < min_days {
@@ -624,15 +1155,9 @@ impl ProgressionSummary {
             if !hr_stability {
                 reasons.push("HR/intensity unstable".to_string());
             }
-            (false, reasons.join(", "))
+            (checks, reasons.join(", "))
         }
     }
-
-    /// Get status for a specific dimension by name
-    #[allow(dead_code)]
-    pub fn get_dimension(&self, name: &str) -> Option<&DimensionStatus> {
-        self.dimensions.iter().find(|d| d.name == name)
-    }
 }
 
 /// Check if a dimension is a "key session" for adherence purposes
@@ -651,7 +1176,8 @@ pub async fn load_all_dimensions(pool: &SqlitePool) -> Result<Vec<ProgressionDim
         SELECT
             id, name, current_value, ceiling_value, step_config_json,
             status, last_change_at, last_ceiling_touch_at,
-            maintenance_cadence_days, created_at, updated_at
+            maintenance_cadence_days, criteria_json, prerequisite, prerequisite_min,
+            created_at, updated_at
         FROM progression_dimensions
         ORDER BY id
         "#,
@@ -662,19 +1188,26 @@ pub async fn load_all_dimensions(pool: &SqlitePool) -> Result<Vec<ProgressionDim
 
     let mut dimensions = Vec::new();
     for row in rows {
+        let name: String = row.get("name");
         let step_config_json: String = row.get("step_config_json");
-        let step_config = StepConfig::from_json(&step_config_json)?;
+        let step_config = StepConfig::from_json(&step_config_json)
+            .map_err(|e| format!("Invalid dimension '{}': {}", name, e))?;
         let status_str: String = row.get("status");
         let status: LifecycleStatus = status_str.parse().unwrap_or_default();
+        let criteria = row
+            .get::<Option<String>, _>("criteria_json")
+            .map(|json| ProgressionCriteria::from_json(&json))
+            .transpose()
+            .map_err(|e| format!("Invalid dimension '{}': {}", name, e))?;
 
         let last_change_at: Option<String> = row.get("last_change_at");
         let last_ceiling_touch_at: Option<String> = row.get("last_ceiling_touch_at");
         let created_at: Option<String> = row.get("created_at");
         let updated_at: Option<String> = row.get("updated_at");
 
-        dimensions.push(ProgressionDimension {
+        let dimension = ProgressionDimension {
             id: row.get("id"),
-            name: row.get("name"),
+            name,
             current_value: row.get("current_value"),
             ceiling_value: row.get("ceiling_value"),
             step_config,
@@ -692,6 +1225,9 @@ pub async fn load_all_dimensions(pool: &SqlitePool) -> Result<Vec<ProgressionDim
             maintenance_cadence_days: row
                 .try_get::<i32, _>("maintenance_cadence_days")
                 .unwrap_or(14),
+            criteria,
+            prerequisite: row.get("prerequisite"),
+            prerequisite_min: row.get("prerequisite_min"),
             created_at: created_at
                 .and_then(|s| DateTime::parse_from_rfc3339(&s).ok())
                 .map(|dt| dt.with_timezone(&Utc))
@@ -700,7 +1236,9 @@ pub async fn load_all_dimensions(pool: &SqlitePool) -> Result<Vec<ProgressionDim
                 .and_then(|s| DateTime::parse_from_rfc3339(&s).ok())
                 .map(|dt| dt.with_timezone(&Utc))
                 .unwrap_or_else(Utc::now),
-        });
+        };
+        dimension.validate()?;
+        dimensions.push(dimension);
     }
 
     Ok(dimensions)
@@ -719,7 +1257,10 @@ pub async fn load_dimension(
 }
 
 /// Save a dimension back to database
-pub async fn save_dimension(pool: &SqlitePool, dim: &ProgressionDimension) -> Result<(), String> {
+pub async fn save_dimension<'e, E>(executor: E, dim: &ProgressionDimension) -> Result<(), String>
+where
+    E: sqlx::Executor<'e, Database = sqlx::Sqlite>,
+{
     let step_config_json = dim.step_config.to_json();
     let status_str = dim.status.to_string();
     let last_change_str = dim.last_change_at.map(|d| d.to_rfc3339());
@@ -749,28 +1290,35 @@ pub async fn save_dimension(pool: &SqlitePool, dim: &ProgressionDimension) -> Re
     .bind(dim.maintenance_cadence_days)
     .bind(&updated_at)
     .bind(&dim.name)
-    .execute(pool)
+    .execute(executor)
     .await
     .map_err(|e| format!("Failed to save dimension: {}", e))?;
 
     Ok(())
 }
 
-/// Log a progression change to history
-pub async fn log_progression(
-    pool: &SqlitePool,
+/// Log a progression change to history, with the dimension's status
+/// before the change
+#[allow(clippy::too_many_arguments)]
+pub async fn log_progression<'e, E>(
+    executor: E,
     dimension_name: &str,
     previous_value: &str,
     new_value: &str,
     change_type: &str,
     trigger_workout_id: Option<i64>,
     context_json: Option<&str>,
-) -> Result<(), String> {
+    previous_status: LifecycleStatus,
+) -> Result<(), String>
+where
+    E: sqlx::Executor<'e, Database = sqlx::Sqlite>,
+{
     sqlx::query(
         r#"
         INSERT INTO progression_history
-            (dimension_name, previous_value, new_value, change_type, trigger_workout_id, context_snapshot_json)
-        VALUES (?, ?, ?, ?, ?, ?)
+            (dimension_name, previous_value, new_value, change_type, trigger_workout_id, context_snapshot_json,
+             previous_status)
+        VALUES (?, ?, ?, ?, ?, ?, ?)
         "#,
     )
     .bind(dimension_name)
@@ -779,48 +1327,263 @@ pub async fn log_progression(
     .bind(change_type)
     .bind(trigger_workout_id)
     .bind(context_json)
-    .execute(pool)
+    .bind(previous_status.to_string())
+    .execute(executor)
     .await
     .map_err(|e| format!("Failed to log progression: {}", e))?;
 
     Ok(())
 }
 
-/// ---------------------------------------------------------------------------
-/// Progression Actions
-/// ---------------------------------------------------------------------------
-
-/// Apply a progression to a dimension
-pub async fn apply_progression(
+/// Log a change to a dimension's current value, remembering the prior
+/// last_change_at and status so an undo can restore them exactly
+#[allow(clippy::too_many_arguments)]
+async fn log_value_change(
     pool: &SqlitePool,
     dimension_name: &str,
+    previous_value: &str,
+    new_value: &str,
+    change_type: &str,
     trigger_workout_id: Option<i64>,
-) -> Result<String, String> {
-    let mut dim = load_dimension(pool, dimension_name).await?;
+    previous_last_change_at: Option<DateTime<Utc>>,
+    previous_status: LifecycleStatus,
+) -> Result<(), String> {
+    sqlx::query(
+        r#"
+        INSERT INTO progression_history
+            (dimension_name, previous_value, new_value, change_type, trigger_workout_id, previous_last_change_at,
+             previous_status)
+        VALUES (?, ?, ?, ?, ?, ?, ?)
+        "#,
+    )
+    .bind(dimension_name)
+    .bind(previous_value)
+    .bind(new_value)
+    .bind(change_type)
+    .bind(trigger_workout_id)
+    .bind(previous_last_change_at.map(|d| d.to_rfc3339()))
+    .bind(previous_status.to_string())
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to log progression: {}", e))?;
 
-    let next_val = dim
-        .next_value()
-        .ok_or_else(|| format!("No next value available for {}", dimension_name))?;
+    Ok(())
+}
 
-    let prev_val = dim.current_value.clone();
-    dim.current_value = next_val.clone();
-    dim.last_change_at = Some(Utc::now());
+/// One row of progression_history
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProgressionHistoryEntry {
+    pub id: i64,
+    pub dimension_name: String,
+    pub previous_value: String,
+    pub new_value: String,
+    pub change_type: String,
+    pub previous_last_change_at: Option<DateTime<Utc>>,
+    /// Status before the change (None on rows logged before it was tracked)
+    pub previous_status: Option<LifecycleStatus>,
+    pub created_at: Option<DateTime<Utc>>,
+}
 
-    // Update status if we've reached ceiling
-    if dim.is_at_ceiling() {
-        dim.status = LifecycleStatus::AtCeiling;
-        dim.last_ceiling_touch_at = Some(Utc::now());
-    }
+/// Parse an RFC 3339 or SQLite CURRENT_TIMESTAMP ("YYYY-MM-DD HH:MM:SS") value
+fn parse_history_timestamp(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|dt| dt.with_timezone(&Utc))
+        .ok()
+        .or_else(|| {
+            chrono::NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S")
+                .ok()
+                .map(|dt| dt.and_utc())
+        })
+}
 
-    save_dimension(pool, &dim).await?;
-    log_progression(
-        pool,
-        dimension_name,
-        &prev_val,
+/// Load a dimension's history, oldest first
+pub async fn load_dimension_history(
+    pool: &SqlitePool,
+    dimension_name: &str,
+) -> Result<Vec<ProgressionHistoryEntry>, String> {
+    let rows = sqlx::query(
+        r#"
+        SELECT id, dimension_name, previous_value, new_value, change_type,
+               previous_last_change_at, previous_status, created_at
+        FROM progression_history
+        WHERE dimension_name = ?
+        ORDER BY id
+        "#,
+    )
+    .bind(dimension_name)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to load progression history: {}", e))?;
+
+    Ok(rows.iter().map(history_entry_from_row).collect())
+}
+
+/// Load history rows for every dimension created in [start, end), oldest first
+pub async fn load_history_between(
+    pool: &SqlitePool,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Result<Vec<ProgressionHistoryEntry>, String> {
+    let rows = sqlx::query(
+        r#"
+        SELECT id, dimension_name, previous_value, new_value, change_type,
+               previous_last_change_at, previous_status, created_at
+        FROM progression_history
+        WHERE julianday(created_at) >= julianday(?1)
+          AND julianday(created_at) < julianday(?2)
+        ORDER BY id
+        "#,
+    )
+    .bind(start)
+    .bind(end)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to load progression history: {}", e))?;
+
+    Ok(rows.iter().map(history_entry_from_row).collect())
+}
+
+fn history_entry_from_row(row: &sqlx::sqlite::SqliteRow) -> ProgressionHistoryEntry {
+    let previous_last_change_at: Option<String> = row.get("previous_last_change_at");
+    let previous_status: Option<String> = row.get("previous_status");
+    let created_at: Option<String> = row.get("created_at");
+    ProgressionHistoryEntry {
+        id: row.get("id"),
+        dimension_name: row.get("dimension_name"),
+        previous_value: row.get("previous_value"),
+        new_value: row.get("new_value"),
+        change_type: row.get("change_type"),
+        previous_last_change_at: previous_last_change_at
+            .as_deref()
+            .and_then(parse_history_timestamp),
+        previous_status: previous_status.and_then(|s| s.parse().ok()),
+        created_at: created_at.as_deref().and_then(parse_history_timestamp),
+    }
+}
+
+/// Work out which change an undo should revert, treating history as a stack:
+/// each "undo" cancels the latest change not already undone. Ceiling touches
+/// don't change a value and are skipped. Returns (target, change before it).
+fn find_undo_target(
+    history: &[ProgressionHistoryEntry],
+) -> Option<(&ProgressionHistoryEntry, Option<&ProgressionHistoryEntry>)> {
+    let mut stack: Vec<&ProgressionHistoryEntry> = Vec::new();
+    for entry in history {
+        match entry.change_type.as_str() {
+            "undo" => {
+                stack.pop();
+            }
+            "ceiling_touch" => {}
+            _ => stack.push(entry),
+        }
+    }
+    let target = stack.pop()?;
+    // Latest remaining change to the current value (ceiling updates don't count)
+    let before = stack.iter().rev().find(|e| e.change_type != "ceiling_update").copied();
+    Some((target, before))
+}
+
+/// One point on a dimension's progress chart
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimelinePoint {
+    pub date: Option<DateTime<Utc>>,
+    pub value: String,
+    /// Plottable position (sequence index or numeric value)
+    pub ordinal: Option<f64>,
+    /// "initial" for the starting value, otherwise the history change_type
+    pub change_type: String,
+}
+
+/// Replay history into the series of current values. Ceiling touches and
+/// ceiling updates don't move the value and are left out; an undo plots the
+/// restored value unless it undid a ceiling update.
+pub fn build_dimension_timeline(
+    dim: &ProgressionDimension,
+    history: &[ProgressionHistoryEntry],
+) -> Vec<TimelinePoint> {
+    let point = |entry: &ProgressionHistoryEntry, value: &str, change_type: &str| TimelinePoint {
+        date: entry.created_at,
+        value: value.to_string(),
+        ordinal: dim.step_config.ordinal(value),
+        change_type: change_type.to_string(),
+    };
+
+    let mut points = Vec::new();
+    let mut stack: Vec<&ProgressionHistoryEntry> = Vec::new();
+    for entry in history {
+        match entry.change_type.as_str() {
+            "ceiling_touch" => continue,
+            "ceiling_update" => {
+                stack.push(entry);
+                continue;
+            }
+            "undo" => {
+                let undid_ceiling = stack
+                    .pop()
+                    .is_some_and(|undone| undone.change_type == "ceiling_update");
+                if undid_ceiling {
+                    continue;
+                }
+            }
+            _ => stack.push(entry),
+        }
+        if points.is_empty() {
+            points.push(point(entry, &entry.previous_value, "initial"));
+        }
+        points.push(point(entry, &entry.new_value, &entry.change_type));
+    }
+
+    points
+}
+
+/// Load a dimension's value history as a plottable series, oldest first
+pub async fn get_dimension_timeline(
+    pool: &SqlitePool,
+    dimension_name: &str,
+) -> Result<Vec<TimelinePoint>, String> {
+    let dim = load_dimension(pool, dimension_name).await?;
+    let history = load_dimension_history(pool, dimension_name).await?;
+    Ok(build_dimension_timeline(&dim, &history))
+}
+
+/// ---------------------------------------------------------------------------
+/// Progression Actions
+/// ---------------------------------------------------------------------------
+
+/// Apply a progression to a dimension
+pub async fn apply_progression(
+    pool: &SqlitePool,
+    dimension_name: &str,
+    trigger_workout_id: Option<i64>,
+) -> Result<String, String> {
+    let mut dim = load_dimension(pool, dimension_name).await?;
+
+    let next_val = dim
+        .next_value()
+        .ok_or_else(|| format!("No next value available for {}", dimension_name))?;
+
+    let prev_val = dim.current_value.clone();
+    let prev_change_at = dim.last_change_at;
+    let prev_status = dim.status;
+    dim.current_value = next_val.clone();
+    dim.last_change_at = Some(Utc::now());
+
+    // Update status if we've reached ceiling
+    if dim.is_at_ceiling() {
+        dim.status = LifecycleStatus::AtCeiling;
+        dim.last_ceiling_touch_at = Some(Utc::now());
+    }
+
+    save_dimension(pool, &dim).await?;
+    log_value_change(
+        pool,
+        dimension_name,
+        &prev_val,
         &next_val,
         "progress",
         trigger_workout_id,
-        None,
+        prev_change_at,
+        prev_status,
     )
     .await?;
 
@@ -846,6 +1609,7 @@ pub async fn record_ceiling_touch(pool: &SqlitePool, dimension_name: &str) -> Re
         "ceiling_touch",
         None,
         None,
+        dim.status,
     )
     .await?;
 
@@ -861,25 +1625,85 @@ pub async fn apply_regression(pool: &SqlitePool, dimension_name: &str) -> Result
         .ok_or_else(|| format!("No previous value available for {}", dimension_name))?;
 
     let old_val = dim.current_value.clone();
+    let prev_change_at = dim.last_change_at;
+    let prev_status = dim.status;
     dim.current_value = prev_val.clone();
     dim.last_change_at = Some(Utc::now());
     dim.status = LifecycleStatus::Building; // Back to building
 
     save_dimension(pool, &dim).await?;
-    log_progression(
+    log_value_change(
         pool,
         dimension_name,
         &old_val,
         &prev_val,
         "regress",
         None,
-        None,
+        prev_change_at,
+        prev_status,
     )
     .await?;
 
     Ok(prev_val)
 }
 
+/// Set a dimension's current value directly (e.g. dropping several steps
+/// after a layoff). The value must be legal for the step config and not
+/// beyond the ceiling. Logged as "manual" so it can be undone.
+pub async fn set_current_value(
+    pool: &SqlitePool,
+    dimension_name: &str,
+    value: &str,
+) -> Result<ProgressionDimension, String> {
+    let mut dim = load_dimension(pool, dimension_name).await?;
+    let value = value.trim();
+
+    if dim.current_value == value {
+        return Err(format!("{} is already at {}", dimension_name, value));
+    }
+    dim.step_config
+        .validate_target(&dim.current_value, value)
+        .map_err(|e| format!("Invalid value for {}: {}", dimension_name, e))?;
+    if let (Some(target), Some(ceiling)) = (
+        dim.step_config.ordinal(value),
+        dim.step_config.ordinal(&dim.ceiling_value),
+    ) {
+        if dim.dimension_type() == DimensionType::Progressive && target > ceiling {
+            return Err(format!(
+                "Invalid value for {}: {} is beyond the ceiling {}",
+                dimension_name, value, dim.ceiling_value
+            ));
+        }
+    }
+
+    let old_val = std::mem::replace(&mut dim.current_value, value.to_string());
+    let prev_change_at = dim.last_change_at;
+    let prev_status = dim.status;
+    dim.last_change_at = Some(Utc::now());
+
+    if dim.is_at_ceiling() {
+        dim.status = LifecycleStatus::AtCeiling;
+        dim.last_ceiling_touch_at = Some(Utc::now());
+    } else {
+        dim.status = LifecycleStatus::Building;
+    }
+
+    save_dimension(pool, &dim).await?;
+    log_value_change(
+        pool,
+        dimension_name,
+        &old_val,
+        value,
+        "manual",
+        None,
+        prev_change_at,
+        prev_status,
+    )
+    .await?;
+
+    Ok(dim)
+}
+
 /// Update ceiling for a dimension
 pub async fn update_ceiling(
     pool: &SqlitePool,
@@ -889,7 +1713,9 @@ pub async fn update_ceiling(
     let mut dim = load_dimension(pool, dimension_name).await?;
 
     let old_ceiling = dim.ceiling_value.clone();
+    let prev_status = dim.status;
     dim.ceiling_value = new_ceiling.to_string();
+    dim.validate()?;
 
     // Re-evaluate status
     if dim.is_at_ceiling() {
@@ -907,12 +1733,419 @@ pub async fn update_ceiling(
         "ceiling_update",
         None,
         None,
+        prev_status,
     )
     .await?;
 
     Ok(())
 }
 
+/// Set (or clear, with `prerequisite` None) the dimension that must reach
+/// `min` before this one may progress. `min` defaults to the prerequisite's
+/// ceiling and must be a legal value for its step config.
+pub async fn set_prerequisite(
+    pool: &SqlitePool,
+    dimension_name: &str,
+    prerequisite: Option<&str>,
+    min: Option<&str>,
+) -> Result<ProgressionDimension, String> {
+    let dimensions = load_all_dimensions(pool).await?;
+    let dim = dimensions
+        .iter()
+        .find(|d| d.name == dimension_name)
+        .ok_or_else(|| format!("Dimension not found: {}", dimension_name))?;
+
+    let prerequisite = prerequisite.map(str::trim).filter(|p| !p.is_empty());
+    let min = min.map(str::trim).filter(|m| !m.is_empty());
+    if let Some(name) = prerequisite {
+        if name == dim.name {
+            return Err(format!("{} cannot be its own prerequisite", name));
+        }
+        let prereq = dimensions
+            .iter()
+            .find(|d| d.name == name)
+            .ok_or_else(|| format!("Dimension not found: {}", name))?;
+        if let Some(min) = min {
+            prereq
+                .step_config
+                .validate_value(min)
+                .map_err(|e| format!("Invalid prerequisite value for {}: {}", name, e))?;
+        }
+
+        // Walk the chain so a dimension can't end up waiting on itself
+        let mut next = prereq.prerequisite.as_deref();
+        let mut hops = 0;
+        while let Some(link) = next {
+            if link == dim.name || hops > dimensions.len() {
+                return Err(format!("Prerequisite {} would create a cycle with {}", name, dim.name));
+            }
+            next = dimensions.iter().find(|d| d.name == link).and_then(|d| d.prerequisite.as_deref());
+            hops += 1;
+        }
+    } else if min.is_some() {
+        return Err("A prerequisite value needs a prerequisite dimension".to_string());
+    }
+
+    sqlx::query(
+        "UPDATE progression_dimensions SET prerequisite = ?, prerequisite_min = ?, updated_at = ? WHERE name = ?",
+    )
+    .bind(prerequisite)
+    .bind(min)
+    .bind(Utc::now().to_rfc3339())
+    .bind(dimension_name)
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to set prerequisite: {}", e))?;
+
+    load_dimension(pool, dimension_name).await
+}
+
+/// Undo the most recent change to a dimension (progress, regress, manual or
+/// ceiling update), restoring the exact prior value, last_change_at and
+/// status. Logged as "undo" - distinct from a training-driven regression.
+pub async fn undo_last_change(
+    pool: &SqlitePool,
+    dimension_name: &str,
+) -> Result<ProgressionDimension, String> {
+    let mut dim = load_dimension(pool, dimension_name).await?;
+    let history = load_dimension_history(pool, dimension_name).await?;
+
+    let (target, before) = find_undo_target(&history)
+        .ok_or_else(|| format!("No change to undo for {}", dimension_name))?;
+
+    let undone_value = if target.change_type == "ceiling_update" {
+        std::mem::replace(&mut dim.ceiling_value, target.previous_value.clone())
+    } else {
+        // Older rows predate previous_last_change_at; fall back to when the
+        // change before this one happened
+        dim.last_change_at = target
+            .previous_last_change_at
+            .or_else(|| before.and_then(|e| e.created_at));
+        std::mem::replace(&mut dim.current_value, target.previous_value.clone())
+    };
+    dim.validate()?;
+
+    // Rows from before statuses were logged: re-derive, keeping a regression
+    let status_before_undo = dim.status;
+    dim.status = match target.previous_status {
+        Some(status) => status,
+        None if dim.status == LifecycleStatus::Regressing => LifecycleStatus::Regressing,
+        None if dim.is_at_ceiling() => LifecycleStatus::AtCeiling,
+        None => LifecycleStatus::Building,
+    };
+
+    // The dimension and its history change together or not at all
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    save_dimension(&mut *tx, &dim).await?;
+    log_progression(
+        &mut *tx,
+        dimension_name,
+        &undone_value,
+        &target.previous_value,
+        "undo",
+        None,
+        None,
+        status_before_undo,
+    )
+    .await?;
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to undo change: {}", e))?;
+
+    Ok(dim)
+}
+
+/// ---------------------------------------------------------------------------
+/// Dimension Management
+/// ---------------------------------------------------------------------------
+
+/// Template dimension: (name, current, ceiling, step_config_json, maintenance_cadence_days)
+type TemplateDimension = (&'static str, &'static str, &'static str, &'static str, i32);
+
+/// Hybrid fitness template (the original seed: run intervals, long run, Z2 rides)
+const HYBRID_TEMPLATE: &[TemplateDimension] = &[
+    (
+        "run_interval",
+        "4:1",
+        "continuous_45",
+        r#"{"type": "sequence", "sequence": ["4:1", "5:1", "6:1", "8:1", "10:1", "continuous_20", "continuous_30", "continuous_45"]}"#,
+        7,
+    ),
+    (
+        "long_run",
+        "30",
+        "90",
+        r#"{"type": "increment", "increment": 5, "unit": "min"}"#,
+        14,
+    ),
+    (
+        "z2_ride",
+        "45",
+        "60",
+        r#"{"type": "regulated", "options": [45, 60], "unit": "min"}"#,
+        10,
+    ),
+];
+
+/// Cycling template: VO2 intervals and long rides, endurance rides regulated by TSB
+const CYCLING_TEMPLATE: &[TemplateDimension] = &[
+    (
+        "vo2_interval",
+        "4x3",
+        "5x5",
+        r#"{"type": "sequence", "sequence": ["4x3", "5x3", "6x3", "4x4", "5x4", "4x5", "5x5"]}"#,
+        10,
+    ),
+    (
+        "long_ride",
+        "90",
+        "240",
+        r#"{"type": "increment", "increment": 15, "unit": "min"}"#,
+        14,
+    ),
+    (
+        "z2_ride",
+        "60",
+        "90",
+        r#"{"type": "regulated", "options": [60, 90], "unit": "min"}"#,
+        10,
+    ),
+];
+
+/// Names accepted by `reset_dimensions`
+pub const DIMENSION_TEMPLATES: &[&str] = &["hybrid", "cycling"];
+
+fn template_dimensions(template: &str) -> Option<&'static [TemplateDimension]> {
+    match template {
+        "hybrid" => Some(HYBRID_TEMPLATE),
+        "cycling" => Some(CYCLING_TEMPLATE),
+        _ => None,
+    }
+}
+
+/// Build and validate a new (unsaved) dimension from raw config
+fn build_new_dimension(
+    name: &str,
+    current: &str,
+    ceiling: &str,
+    step_config_json: &str,
+    criteria_json: Option<&str>,
+) -> Result<ProgressionDimension, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Dimension name must not be empty".to_string());
+    }
+
+    let step_config = StepConfig::from_json(step_config_json)
+        .map_err(|e| format!("Invalid dimension '{}': {}", name, e))?;
+    let criteria = criteria_json
+        .map(ProgressionCriteria::from_json)
+        .transpose()
+        .map_err(|e| format!("Invalid dimension '{}': {}", name, e))?;
+
+    let now = Utc::now();
+    let mut dim = ProgressionDimension {
+        id: 0,
+        name: name.to_string(),
+        current_value: current.trim().to_string(),
+        ceiling_value: ceiling.trim().to_string(),
+        step_config,
+        status: LifecycleStatus::Building,
+        last_change_at: None,
+        last_ceiling_touch_at: None,
+        maintenance_cadence_days: 14,
+        criteria,
+        prerequisite: None,
+        prerequisite_min: None,
+        created_at: now,
+        updated_at: now,
+    };
+    dim.validate()?;
+
+    // Regulated dimensions always report at ceiling
+    if dim.is_at_ceiling() {
+        dim.status = LifecycleStatus::AtCeiling;
+    }
+
+    Ok(dim)
+}
+
+/// Insert a new dimension row
+async fn insert_dimension<'e, E>(executor: E, dim: &ProgressionDimension) -> Result<(), String>
+where
+    E: sqlx::Executor<'e, Database = sqlx::Sqlite>,
+{
+    sqlx::query(
+        r#"
+        INSERT INTO progression_dimensions
+            (name, current_value, ceiling_value, step_config_json, status,
+             maintenance_cadence_days, criteria_json, prerequisite, prerequisite_min)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+        "#,
+    )
+    .bind(&dim.name)
+    .bind(&dim.current_value)
+    .bind(&dim.ceiling_value)
+    .bind(dim.step_config.to_json())
+    .bind(dim.status.to_string())
+    .bind(dim.maintenance_cadence_days)
+    .bind(dim.criteria.as_ref().map(|c| c.to_json()))
+    .bind(&dim.prerequisite)
+    .bind(&dim.prerequisite_min)
+    .execute(executor)
+    .await
+    .map_err(|e| format!("Failed to create dimension '{}': {}", dim.name, e))?;
+
+    Ok(())
+}
+
+/// Create a new progression dimension
+pub async fn create_dimension(
+    pool: &SqlitePool,
+    name: &str,
+    current: &str,
+    ceiling: &str,
+    step_config_json: &str,
+    criteria_json: Option<&str>,
+) -> Result<ProgressionDimension, String> {
+    let dim = build_new_dimension(name, current, ceiling, step_config_json, criteria_json)?;
+
+    if load_all_dimensions(pool).await?.iter().any(|d| d.name == dim.name) {
+        return Err(format!("Dimension already exists: {}", dim.name));
+    }
+
+    insert_dimension(pool, &dim).await?;
+    load_dimension(pool, &dim.name).await
+}
+
+/// Delete a progression dimension (history is kept as an audit log)
+pub async fn delete_dimension(pool: &SqlitePool, name: &str) -> Result<(), String> {
+    let result = sqlx::query("DELETE FROM progression_dimensions WHERE name = ?")
+        .bind(name)
+        .execute(pool)
+        .await
+        .map_err(|e| format!("Failed to delete dimension: {}", e))?;
+
+    if result.rows_affected() == 0 {
+        return Err(format!("Dimension not found: {}", name));
+    }
+
+    Ok(())
+}
+
+/// Replace all dimensions with a named template set
+pub async fn reset_dimensions(
+    pool: &SqlitePool,
+    template: &str,
+) -> Result<Vec<ProgressionDimension>, String> {
+    let entries = template_dimensions(template).ok_or_else(|| {
+        format!(
+            "Unknown dimension template '{}' (expected one of: {})",
+            template,
+            DIMENSION_TEMPLATES.join(", ")
+        )
+    })?;
+
+    let mut dims = Vec::with_capacity(entries.len());
+    for (name, current, ceiling, step_config_json, cadence) in entries {
+        let mut dim = build_new_dimension(name, current, ceiling, step_config_json, None)?;
+        dim.maintenance_cadence_days = *cadence;
+        dims.push(dim);
+    }
+
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    sqlx::query("DELETE FROM progression_dimensions")
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to clear dimensions: {}", e))?;
+
+    for dim in &dims {
+        insert_dimension(&mut *tx, dim).await?;
+    }
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to reset dimensions: {}", e))?;
+
+    load_all_dimensions(pool).await
+}
+
+/// ---------------------------------------------------------------------------
+/// Key Sessions
+/// ---------------------------------------------------------------------------
+
+/// Link a workout to the dimension whose key session it completed.
+/// Marking the same pair twice is a no-op.
+pub async fn mark_key_session(
+    pool: &SqlitePool,
+    workout_id: i64,
+    dimension_name: &str,
+) -> Result<(), String> {
+    load_dimension(pool, dimension_name).await?;
+
+    let exists: Option<(i64,)> = sqlx::query_as("SELECT id FROM workouts WHERE id = ?")
+        .bind(workout_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| format!("Failed to look up workout: {}", e))?;
+    if exists.is_none() {
+        return Err(format!("Workout not found: {}", workout_id));
+    }
+
+    sqlx::query("INSERT OR IGNORE INTO key_sessions (workout_id, dimension_name) VALUES (?, ?)")
+        .bind(workout_id)
+        .bind(dimension_name)
+        .execute(pool)
+        .await
+        .map_err(|e| format!("Failed to mark key session: {}", e))?;
+
+    Ok(())
+}
+
+/// Whether the athlete linked this workout as a key session
+pub async fn is_key_session(pool: &SqlitePool, workout_id: i64) -> Result<bool, String> {
+    let (linked,): (bool,) = sqlx::query_as("SELECT EXISTS(SELECT 1 FROM key_sessions WHERE workout_id = ?)")
+        .bind(workout_id)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| format!("Failed to look up key session: {}", e))?;
+    Ok(linked)
+}
+
+/// Workouts in the last 7 days explicitly linked as key sessions.
+/// None until the athlete has linked any session, so callers can fall back
+/// to a heuristic.
+pub async fn count_recent_key_sessions(pool: &SqlitePool) -> Result<Option<i64>, String> {
+    let (any_links,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM key_sessions")
+        .fetch_one(pool)
+        .await
+        .map_err(|e| format!("Failed to count key sessions: {}", e))?;
+    if any_links == 0 {
+        return Ok(None);
+    }
+
+    let (recent,): (i64,) = sqlx::query_as(
+        r#"
+        SELECT COUNT(DISTINCT ks.workout_id)
+        FROM key_sessions ks
+        JOIN workouts w ON w.id = ks.workout_id
+        WHERE w.started_at >= datetime('now', '-7 days')
+        "#,
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(|e| format!("Failed to count recent key sessions: {}", e))?;
+
+    Ok(Some(recent))
+}
+
 /// ---------------------------------------------------------------------------
 /// Tests
 /// ---------------------------------------------------------------------------
@@ -943,6 +2176,9 @@ mod tests {
             last_change_at: Some(Utc::now() - Duration::days(10)),
             last_ceiling_touch_at: None,
             maintenance_cadence_days: 7,
+            criteria: None,
+            prerequisite: None,
+            prerequisite_min: None,
             created_at: Utc::now(),
             updated_at: Utc::now(),
         }
@@ -962,6 +2198,9 @@ mod tests {
             last_change_at: Some(Utc::now() - Duration::days(10)),
             last_ceiling_touch_at: None,
             maintenance_cadence_days: 14,
+            criteria: None,
+            prerequisite: None,
+            prerequisite_min: None,
             created_at: Utc::now(),
             updated_at: Utc::now(),
         }
@@ -981,11 +2220,176 @@ mod tests {
             last_change_at: None,
             last_ceiling_touch_at: None,
             maintenance_cadence_days: 10,
+            criteria: None,
+            prerequisite: None,
+            prerequisite_min: None,
             created_at: Utc::now(),
             updated_at: Utc::now(),
         }
     }
 
+    #[test]
+    fn test_decision_trace_lists_every_condition_for_held_dimension() {
+        let held = make_sequence_dimension("5:1", "continuous_45");
+        let mut recent = make_increment_dimension(40, 90);
+        recent.last_change_at = Some(Utc::now() - Duration::days(3));
+        let dimensions = vec![held, recent];
+
+        let context = TrainingContext::compute(&[], &crate::analysis::UserSettings::default());
+        let flags = TrainingFlags { volume_spike: true, ..Default::default() };
+        let adherence = AdherenceSummary::default();
+
+        let trace = DecisionTrace::compute(
+            &dimensions, "run_interval", &context, &flags, &adherence, 7, ExperienceLevel::default(),
+        ).unwrap();
+        assert_eq!(trace.engine_decision, EngineDecision::HoldForNow);
+        assert_eq!(trace.reason, "Another dimension progressed 3 days ago (need 7)");
+
+        let names: Vec<&str> = trace.checks.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "ceiling_touch",
+                "low_adherence_streak",
+                "below_ceiling",
+                "maintenance",
+                "adherence",
+                "key_session",
+                "overlap",
+                "days_since_change",
+                "volume_stability",
+                "fatigue",
+                "intensity_stability",
+                "week_stable",
+            ]
+        );
+
+        // The overlap rule decided it, but the volume spike is recorded too
+        let failed: Vec<&TraceCheck> = trace.checks.iter().filter(|c| !c.passed).collect();
+        assert_eq!(failed.len(), 2);
+        assert_eq!(failed[0].name, "overlap");
+        assert_eq!(failed[0].actual, "long_run progressed 3 days ago");
+        assert_eq!(failed[1].name, "volume_stability");
+        assert_eq!(failed[1].actual, "volume spike");
+        let days = trace.checks.iter().find(|c| c.name == "days_since_change").unwrap();
+        assert_eq!((days.actual.as_str(), days.required.as_str()), ("10 days", ">= 7 days"));
+
+        // Summary status reports the same decision
+        let summary = ProgressionSummary::compute(
+            &dimensions, &context, &flags, adherence.clone(), 7, ExperienceLevel::default(),
+        );
+        let status = summary.get_dimension("run_interval").unwrap();
+        assert_eq!(status.engine_decision, trace.engine_decision);
+        assert_eq!(status.reason, trace.reason);
+
+        assert!(DecisionTrace::compute(
+            &dimensions, "missing", &context, &flags, &adherence, 7, ExperienceLevel::default(),
+        ).is_none());
+    }
+
+    #[test]
+    fn test_shorter_overlap_window_permits_progression() {
+        let candidate = make_sequence_dimension("5:1", "continuous_45");
+        let mut recent = make_increment_dimension(40, 90);
+        recent.last_change_at = Some(Utc::now() - Duration::days(4));
+        let dimensions = vec![candidate, recent];
+
+        let context = TrainingContext::compute(&[], &crate::analysis::UserSettings::default());
+        let flags = TrainingFlags::default();
+        let adherence = AdherenceSummary::default();
+
+        let default_window = DecisionTrace::compute(
+            &dimensions, "run_interval", &context, &flags, &adherence, 7, ExperienceLevel::default(),
+        ).unwrap();
+        assert_eq!(default_window.engine_decision, EngineDecision::HoldForNow);
+        assert_eq!(default_window.reason, "Another dimension progressed 4 days ago (need 7)");
+
+        let short_window = DecisionTrace::compute(
+            &dimensions, "run_interval", &context, &flags, &adherence, 3, ExperienceLevel::default(),
+        ).unwrap();
+        let overlap = short_window.checks.iter().find(|c| c.name == "overlap").unwrap();
+        assert!(overlap.passed);
+        assert_eq!(overlap.required, ">= 3 days since another dimension progressed");
+        assert_ne!(short_window.engine_decision, EngineDecision::HoldForNow);
+    }
+
+    #[test]
+    fn test_experience_level_scales_criteria() {
+        // 8 days since the last change, TSB -12
+        let mut long_run = make_increment_dimension(40, 90);
+        long_run.last_change_at = Some(Utc::now() - Duration::days(8));
+        let dimensions = vec![long_run];
+
+        let mut context = TrainingContext::compute(&[], &crate::analysis::UserSettings::default());
+        context.tsb = Some(-12.0);
+        let flags = TrainingFlags::default();
+        let adherence = AdherenceSummary::default();
+        let decide = |experience| {
+            DecisionTrace::compute(&dimensions, "long_run", &context, &flags, &adherence, 7, experience).unwrap()
+        };
+
+        let advanced = decide(ExperienceLevel::Advanced);
+        assert_eq!(advanced.engine_decision, EngineDecision::ProgressAllowed);
+        assert_eq!(decide(ExperienceLevel::Intermediate).engine_decision, EngineDecision::ProgressAllowed);
+
+        let beginner = decide(ExperienceLevel::Beginner);
+        assert_eq!(beginner.engine_decision, EngineDecision::Hold);
+        assert_eq!(beginner.reason, "8 days since last change (need 10), TSB too low (-12.0, need > -10.0)");
+        let days = beginner.checks.iter().find(|c| c.name == "days_since_change").unwrap();
+        assert_eq!(days.required, ">= 10 days");
+
+        // Advanced waits 3/4 of the usual days and tolerates more fatigue
+        let adjusted = ExperienceLevel::Advanced.adjust(ProgressionCriteria::defaults_for("long_run"));
+        assert_eq!((adjusted.min_days_between_changes, adjusted.min_tsb), (5, -20.0));
+        assert_eq!(ExperienceLevel::parse("beginner"), Some(ExperienceLevel::Beginner));
+        assert_eq!(ExperienceLevel::parse("expert"), None);
+    }
+
+    #[test]
+    fn test_prerequisite_blocks_until_met() {
+        let mut long_run = make_increment_dimension(40, 90);
+        long_run.prerequisite = Some("run_interval".to_string());
+        long_run.prerequisite_min = Some("continuous_20".to_string());
+        let mut dimensions = vec![make_sequence_dimension("8:1", "continuous_45"), long_run];
+
+        let context = TrainingContext::compute(&[], &crate::analysis::UserSettings::default());
+        let flags = TrainingFlags::default();
+        let adherence = AdherenceSummary::default();
+
+        let summary = ProgressionSummary::compute(
+            &dimensions, &context, &flags, adherence.clone(), 7, ExperienceLevel::default(),
+        );
+        let status = summary.get_dimension("long_run").unwrap();
+        assert_eq!(status.engine_decision, EngineDecision::Hold);
+        assert_eq!(status.reason, "prerequisite run_interval not at continuous_20");
+        let trace = DecisionTrace::compute(
+            &dimensions, "long_run", &context, &flags, &adherence, 7, ExperienceLevel::default(),
+        ).unwrap();
+        let check = trace.checks.iter().find(|c| c.name == "prerequisite").unwrap();
+        assert!(!check.passed);
+        assert_eq!(check.actual, "run_interval at 8:1");
+        // The prerequisite itself is unaffected
+        let run_interval = summary.get_dimension("run_interval").unwrap();
+        assert_eq!(run_interval.engine_decision, EngineDecision::ProgressAllowed);
+
+        // Reaching the threshold (or going past it) releases the hold
+        for value in ["continuous_20", "continuous_30"] {
+            dimensions[0].current_value = value.to_string();
+            let summary = ProgressionSummary::compute(
+                &dimensions, &context, &flags, adherence.clone(), 7, ExperienceLevel::default(),
+            );
+            let status = summary.get_dimension("long_run").unwrap();
+            assert_eq!(status.engine_decision, EngineDecision::ProgressAllowed, "at {}", value);
+        }
+
+        // A prerequisite that no longer exists keeps holding
+        dimensions.remove(0);
+        let summary = ProgressionSummary::compute(
+            &dimensions, &context, &flags, adherence, 7, ExperienceLevel::default(),
+        );
+        assert_eq!(summary.get_dimension("long_run").unwrap().engine_decision, EngineDecision::Hold);
+    }
+
     #[test]
     fn test_sequence_progression() {
         let dim = make_sequence_dimension("4:1", "continuous_45");
@@ -1007,6 +2411,15 @@ mod tests {
         assert!(!dim.is_at_ceiling());
     }
 
+    #[test]
+    fn test_increment_clamps_final_step_to_ceiling() {
+        let dim = make_increment_dimension(88, 90);
+        assert_eq!(dim.next_value(), Some("90".to_string()));
+
+        let landed = make_increment_dimension(90, 90);
+        assert!(landed.is_at_ceiling());
+    }
+
     #[test]
     fn test_increment_at_ceiling() {
         let dim = make_increment_dimension(90, 90);
@@ -1025,14 +2438,41 @@ mod tests {
     fn test_regulated_tsb_duration() {
         let dim = make_regulated_dimension();
 
-        // Fresh (TSB >= 0): longest duration
-        assert_eq!(dim.get_regulated_duration(Some(5.0)), Some(60));
+        let bands = TsbBands::default();
+
+        // Fresh (TSB > 5): longest duration
+        assert_eq!(dim.get_regulated_duration(Some(10.0), &bands), Some(60));
+
+        // Slightly fatigued (TSB -10 to 5): shorter
+        assert_eq!(dim.get_regulated_duration(Some(-5.0), &bands), Some(45));
 
-        // Moderate fatigue (TSB -10 to 0): shorter
-        assert_eq!(dim.get_regulated_duration(Some(-5.0)), Some(45));
+        // Moderate or high fatigue (TSB <= -10): recovery
+        assert_eq!(dim.get_regulated_duration(Some(-15.0), &bands), Some(40));
 
-        // High fatigue (TSB < -10): recovery
-        assert_eq!(dim.get_regulated_duration(Some(-15.0)), Some(40));
+        // No TSB yet: longest duration
+        assert_eq!(dim.get_regulated_duration(None, &bands), Some(60));
+    }
+
+    #[test]
+    fn test_regulated_duration_follows_custom_tsb_bands() {
+        let dim = make_regulated_dimension();
+        // An athlete who routinely trains deep: -15 is only slightly fatigued
+        let bands = TsbBands {
+            tsb_fresh_above: 0.0,
+            tsb_moderate_below: -25.0,
+            tsb_high_below: -35.0,
+        };
+        assert_eq!(dim.get_regulated_duration(Some(2.0), &bands), Some(60));
+        assert_eq!(dim.get_regulated_duration(Some(-15.0), &bands), Some(45));
+        assert_eq!(dim.get_regulated_duration(Some(-30.0), &bands), Some(40));
+
+        let flags = TrainingFlags { tsb_bands: bands, ..Default::default() };
+        let mut context = TrainingContext::compute(&[], &crate::analysis::UserSettings::default());
+        context.tsb = Some(-15.0);
+        let trace = DecisionTrace::compute(
+            &[dim], "z2_ride", &context, &flags, &AdherenceSummary::default(), 7, ExperienceLevel::default(),
+        ).unwrap();
+        assert_eq!(trace.reason, "Duration regulated by TSB (slightly fatigued): 45 min recommended");
     }
 
     #[test]
@@ -1082,4 +2522,433 @@ mod tests {
             _ => panic!("Wrong type"),
         }
     }
+
+    #[test]
+    fn test_seeded_dimensions_validate() {
+        assert!(make_sequence_dimension("4:1", "continuous_45").validate().is_ok());
+        assert!(make_increment_dimension(30, 90).validate().is_ok());
+        assert!(make_regulated_dimension().validate().is_ok());
+    }
+
+    #[test]
+    fn test_from_json_rejects_empty_sequence() {
+        let err = StepConfig::from_json(r#"{"type": "sequence", "sequence": []}"#).unwrap_err();
+        assert!(err.contains("no values"), "{}", err);
+    }
+
+    #[test]
+    fn test_from_json_rejects_non_positive_increment() {
+        let err = StepConfig::from_json(r#"{"type": "increment", "increment": 0, "unit": "min"}"#)
+            .unwrap_err();
+        assert!(err.contains("positive"), "{}", err);
+
+        let err = StepConfig::from_json(r#"{"type": "increment", "increment": -5, "unit": "min"}"#)
+            .unwrap_err();
+        assert!(err.contains("-5"), "{}", err);
+    }
+
+    #[test]
+    fn test_from_json_rejects_missing_unit() {
+        let err = StepConfig::from_json(r#"{"type": "increment", "increment": 5, "unit": ""}"#)
+            .unwrap_err();
+        assert!(err.contains("unit"), "{}", err);
+    }
+
+    #[test]
+    fn test_from_json_rejects_bad_regulated_options() {
+        assert!(StepConfig::from_json(r#"{"type": "regulated", "options": [], "unit": "min"}"#).is_err());
+        let err = StepConfig::from_json(r#"{"type": "regulated", "options": [45, -10], "unit": "min"}"#)
+            .unwrap_err();
+        assert!(err.contains("-10"), "{}", err);
+    }
+
+    #[test]
+    fn test_validate_rejects_non_numeric_increment_value() {
+        let mut dim = make_increment_dimension(30, 90);
+        dim.current_value = "thirty".to_string();
+        let err = dim.validate().unwrap_err();
+        assert!(err.contains("long_run"), "{}", err);
+        assert!(err.contains("not a whole number"), "{}", err);
+    }
+
+    #[test]
+    fn test_validate_rejects_negative_increment_value() {
+        let dim = make_increment_dimension(-5, 90);
+        assert!(dim.validate().is_err());
+    }
+
+    #[test]
+    fn test_validate_rejects_bad_ceiling() {
+        let mut dim = make_increment_dimension(30, 90);
+        dim.ceiling_value = "".to_string();
+        let err = dim.validate().unwrap_err();
+        assert!(err.contains("ceiling"), "{}", err);
+    }
+
+    #[test]
+    fn test_validate_rejects_value_outside_sequence() {
+        let dim = make_sequence_dimension("7:1", "continuous_45");
+        let err = dim.validate().unwrap_err();
+        assert!(err.contains("not in the sequence"), "{}", err);
+    }
+
+    #[test]
+    fn test_default_criteria_match_builtin_rules() {
+        let run = ProgressionCriteria::defaults_for("run_interval");
+        assert_eq!(run.min_tsb, -15.0);
+        assert!(run.require_stable_intensity);
+        assert_eq!(ProgressionCriteria::defaults_for("long_run").min_tsb, -15.0);
+        assert_eq!(ProgressionCriteria::defaults_for("vo2_interval"), ProgressionCriteria::default());
+    }
+
+    #[test]
+    fn test_criteria_from_json_fills_defaults() {
+        let criteria = ProgressionCriteria::from_json(r#"{"min_tsb": -5.0}"#).unwrap();
+        assert_eq!(criteria.min_tsb, -5.0);
+        assert_eq!(criteria.min_days_between_changes, 7);
+        assert!(ProgressionCriteria::from_json(r#"{"min_days_between_changes": -1}"#).is_err());
+    }
+
+    const VO2_STEPS: &str =
+        r#"{"type": "sequence", "sequence": ["4x3", "5x3", "6x3", "4x4", "5x4"]}"#;
+
+    #[tokio::test]
+    async fn test_create_and_progress_vo2_interval() {
+        let pool = crate::db::test_pool().await;
+
+        let dim = create_dimension(
+            &pool,
+            "vo2_interval",
+            "4x3",
+            "5x4",
+            VO2_STEPS,
+            Some(r#"{"min_days_between_changes": 10, "min_tsb": -5.0}"#),
+        )
+        .await
+        .unwrap();
+        assert_eq!(dim.status, LifecycleStatus::Building);
+        assert_eq!(dim.effective_criteria().min_days_between_changes, 10);
+
+        assert_eq!(apply_progression(&pool, "vo2_interval", None).await.unwrap(), "5x3");
+        assert_eq!(apply_progression(&pool, "vo2_interval", None).await.unwrap(), "6x3");
+
+        let dim = load_dimension(&pool, "vo2_interval").await.unwrap();
+        assert_eq!(dim.current_value, "6x3");
+        assert!(dim.last_change_at.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_create_progress_to_ceiling() {
+        let pool = crate::db::test_pool().await;
+        create_dimension(&pool, "vo2_interval", "4x4", "5x4", VO2_STEPS, None)
+            .await
+            .unwrap();
+
+        assert_eq!(apply_progression(&pool, "vo2_interval", None).await.unwrap(), "5x4");
+        let dim = load_dimension(&pool, "vo2_interval").await.unwrap();
+        assert_eq!(dim.status, LifecycleStatus::AtCeiling);
+        assert!(apply_progression(&pool, "vo2_interval", None).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_maintenance_due_lists_only_lapsed_ceilings() {
+        let pool = crate::db::test_pool().await;
+        // Both sit at their ceilings; only run_interval's touch has lapsed
+        for (name, days_ago) in [("run_interval", 10), ("long_run", 0)] {
+            sqlx::query(
+                "UPDATE progression_dimensions
+                 SET current_value = ceiling_value, status = 'at_ceiling', last_ceiling_touch_at = ?1
+                 WHERE name = ?2",
+            )
+            .bind((Utc::now() - Duration::days(days_ago)).to_rfc3339())
+            .bind(name)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+
+        let due = load_maintenance_due(&pool).await.unwrap();
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].name, "run_interval");
+        assert_eq!(due[0].days_since_touch, Some(10));
+        // Cadence is 7 days
+        assert_eq!(due[0].days_overdue, 3);
+        assert_eq!(due[0].recommended_session, "run interval at continuous_45 (ceiling touch)");
+    }
+
+    #[tokio::test]
+    async fn test_set_current_value_jumps_back_several_steps() {
+        let pool = crate::db::test_pool().await;
+        // Seeded run_interval starts at 4:1
+        set_current_value(&pool, "run_interval", "continuous_30").await.unwrap();
+
+        let dim = set_current_value(&pool, "run_interval", "5:1").await.unwrap();
+        assert_eq!(dim.current_value, "5:1");
+        assert_eq!(dim.status, LifecycleStatus::Building);
+        let history = load_dimension_history(&pool, "run_interval").await.unwrap();
+        let last = history.last().unwrap();
+        assert_eq!(last.change_type, "manual");
+        assert_eq!((last.previous_value.as_str(), last.new_value.as_str()), ("continuous_30", "5:1"));
+
+        // Straight to the ceiling
+        let dim = set_current_value(&pool, "run_interval", "continuous_45").await.unwrap();
+        assert_eq!(dim.status, LifecycleStatus::AtCeiling);
+
+        // Undo restores the manual change
+        let dim = undo_last_change(&pool, "run_interval").await.unwrap();
+        assert_eq!(dim.current_value, "5:1");
+    }
+
+    #[tokio::test]
+    async fn test_set_prerequisite_round_trips_and_rejects_cycles() {
+        let pool = crate::db::test_pool().await;
+        let dim = set_prerequisite(&pool, "long_run", Some("run_interval"), Some("continuous_20"))
+            .await
+            .unwrap();
+        assert_eq!(dim.prerequisite.as_deref(), Some("run_interval"));
+        assert_eq!(dim.prerequisite_min.as_deref(), Some("continuous_20"));
+
+        assert!(set_prerequisite(&pool, "long_run", Some("long_run"), None).await.is_err());
+        assert!(set_prerequisite(&pool, "run_interval", Some("long_run"), None).await.is_err());
+        assert!(set_prerequisite(&pool, "long_run", Some("run_interval"), Some("12:1")).await.is_err());
+        assert!(set_prerequisite(&pool, "long_run", Some("missing"), None).await.is_err());
+        assert!(set_prerequisite(&pool, "long_run", None, Some("continuous_20")).await.is_err());
+
+        let dim = set_prerequisite(&pool, "long_run", None, None).await.unwrap();
+        assert_eq!(dim.prerequisite, None);
+        assert_eq!(dim.prerequisite_min, None);
+    }
+
+    #[tokio::test]
+    async fn test_set_current_value_rejects_illegal_values() {
+        let pool = crate::db::test_pool().await;
+        let vo2_steps = r#"{"type": "sequence", "sequence": ["4x3", "5x3", "6x3", "4x4", "5x4", "4x5"]}"#;
+        create_dimension(&pool, "vo2_interval", "5x3", "5x4", vo2_steps, None).await.unwrap();
+        // Seeded: long_run 30 (+5 min, ceiling 90), z2_ride 45 (options 45/60)
+
+        // Sequences: a listed value, not beyond the ceiling
+        assert!(set_current_value(&pool, "vo2_interval", "7x3").await.is_err());
+        assert!(set_current_value(&pool, "vo2_interval", "4x5").await.is_err());
+        assert_eq!(set_current_value(&pool, "vo2_interval", "4x3").await.unwrap().current_value, "4x3");
+
+        // Increments: whole steps from the current value only
+        assert!(set_current_value(&pool, "long_run", "47").await.is_err());
+        assert!(set_current_value(&pool, "long_run", "0").await.is_err());
+        assert!(set_current_value(&pool, "long_run", "95").await.is_err());
+        assert_eq!(set_current_value(&pool, "long_run", "60").await.unwrap().current_value, "60");
+
+        // Regulated: one of the options
+        assert!(set_current_value(&pool, "z2_ride", "50").await.is_err());
+        assert_eq!(set_current_value(&pool, "z2_ride", "60").await.unwrap().current_value, "60");
+
+        // Setting the same value is not a change
+        assert!(set_current_value(&pool, "z2_ride", "60").await.is_err());
+        assert_eq!(load_dimension(&pool, "long_run").await.unwrap().current_value, "60");
+    }
+
+    #[tokio::test]
+    async fn test_create_dimension_rejects_invalid_config() {
+        let pool = crate::db::test_pool().await;
+
+        let err = create_dimension(
+            &pool,
+            "vo2_interval",
+            "4x3",
+            "5x4",
+            r#"{"type": "sequence", "sequence": []}"#,
+            None,
+        )
+        .await
+        .unwrap_err();
+        assert!(err.contains("vo2_interval"), "{}", err);
+
+        let err = create_dimension(&pool, "vo2_interval", "3x3", "5x4", VO2_STEPS, None)
+            .await
+            .unwrap_err();
+        assert!(err.contains("not in the sequence"), "{}", err);
+
+        assert!(load_dimension(&pool, "vo2_interval").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_create_dimension_rejects_duplicate_name() {
+        let pool = crate::db::test_pool().await;
+        let err = create_dimension(
+            &pool,
+            "long_run",
+            "30",
+            "90",
+            r#"{"type": "increment", "increment": 5, "unit": "min"}"#,
+            None,
+        )
+        .await
+        .unwrap_err();
+        assert!(err.contains("already exists"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_delete_dimension() {
+        let pool = crate::db::test_pool().await;
+        delete_dimension(&pool, "z2_ride").await.unwrap();
+
+        let names: Vec<String> = load_all_dimensions(&pool)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|d| d.name)
+            .collect();
+        assert!(!names.contains(&"z2_ride".to_string()));
+        assert!(delete_dimension(&pool, "z2_ride").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_reset_dimensions_seeds_template() {
+        let pool = crate::db::test_pool().await;
+
+        let dims = reset_dimensions(&pool, "cycling").await.unwrap();
+        let names: Vec<&str> = dims.iter().map(|d| d.name.as_str()).collect();
+        assert_eq!(names, vec!["vo2_interval", "long_ride", "z2_ride"]);
+        assert_eq!(dims[2].status, LifecycleStatus::AtCeiling);
+
+        let dims = reset_dimensions(&pool, "hybrid").await.unwrap();
+        let names: Vec<&str> = dims.iter().map(|d| d.name.as_str()).collect();
+        assert_eq!(names, vec!["run_interval", "long_run", "z2_ride"]);
+    }
+
+    #[tokio::test]
+    async fn test_reset_dimensions_unknown_template_keeps_existing() {
+        let pool = crate::db::test_pool().await;
+        let err = reset_dimensions(&pool, "ultra").await.unwrap_err();
+        assert!(err.contains("hybrid"), "{}", err);
+        assert_eq!(load_all_dimensions(&pool).await.unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_timeline_ordinals_follow_sequence_through_regression() {
+        let pool = crate::db::test_pool().await;
+        apply_progression(&pool, "run_interval", None).await.unwrap();
+        apply_progression(&pool, "run_interval", None).await.unwrap();
+        apply_regression(&pool, "run_interval").await.unwrap();
+        apply_progression(&pool, "run_interval", None).await.unwrap();
+        apply_progression(&pool, "run_interval", None).await.unwrap();
+
+        let timeline = get_dimension_timeline(&pool, "run_interval").await.unwrap();
+        let ordinals: Vec<f64> = timeline.iter().map(|p| p.ordinal.unwrap()).collect();
+        assert_eq!(ordinals, vec![0.0, 1.0, 2.0, 1.0, 2.0, 3.0]);
+        assert_eq!(timeline[0].change_type, "initial");
+        assert_eq!(timeline[0].value, "4:1");
+        assert_eq!(timeline[3].change_type, "regress");
+
+        // Each progress steps up from the point before it, the regression dips
+        for pair in timeline.windows(2) {
+            let delta = pair[1].ordinal.unwrap() - pair[0].ordinal.unwrap();
+            match pair[1].change_type.as_str() {
+                "progress" => assert_eq!(delta, 1.0),
+                "regress" => assert_eq!(delta, -1.0),
+                other => panic!("unexpected change type {}", other),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_timeline_skips_ceiling_changes_and_plots_undo() {
+        let pool = crate::db::test_pool().await;
+        apply_progression(&pool, "long_run", None).await.unwrap();
+        update_ceiling(&pool, "long_run", "120").await.unwrap();
+        undo_last_change(&pool, "long_run").await.unwrap();
+        undo_last_change(&pool, "long_run").await.unwrap();
+
+        let timeline = get_dimension_timeline(&pool, "long_run").await.unwrap();
+        let ordinals: Vec<f64> = timeline.iter().map(|p| p.ordinal.unwrap()).collect();
+        assert_eq!(ordinals, vec![30.0, 35.0, 30.0]);
+        assert_eq!(timeline[2].change_type, "undo");
+    }
+
+    #[tokio::test]
+    async fn test_undo_restores_value_status_and_last_change() {
+        let pool = crate::db::test_pool().await;
+        create_dimension(&pool, "vo2_interval", "4x4", "5x4", VO2_STEPS, None)
+            .await
+            .unwrap();
+        let before = load_dimension(&pool, "vo2_interval").await.unwrap();
+        assert_eq!(before.status, LifecycleStatus::Building);
+        assert!(before.last_change_at.is_none());
+
+        apply_progression(&pool, "vo2_interval", None).await.unwrap();
+        let progressed = load_dimension(&pool, "vo2_interval").await.unwrap();
+        assert_eq!(progressed.current_value, "5x4");
+        assert_eq!(progressed.status, LifecycleStatus::AtCeiling);
+
+        let undone = undo_last_change(&pool, "vo2_interval").await.unwrap();
+        assert_eq!(undone.current_value, "4x4");
+        assert_eq!(undone.status, LifecycleStatus::Building);
+        assert!(undone.last_change_at.is_none());
+
+        let history = load_dimension_history(&pool, "vo2_interval").await.unwrap();
+        let last = history.last().unwrap();
+        assert_eq!(last.change_type, "undo");
+        assert_eq!(last.previous_value, "5x4");
+        assert_eq!(last.new_value, "4x4");
+
+        // Nothing left to undo
+        assert!(undo_last_change(&pool, "vo2_interval").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_undo_restores_regressing_status() {
+        let pool = crate::db::test_pool().await;
+        sqlx::query("UPDATE progression_dimensions SET status = 'regressing' WHERE name = 'long_run'")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        set_current_value(&pool, "long_run", "40").await.unwrap();
+        assert_eq!(load_dimension(&pool, "long_run").await.unwrap().status, LifecycleStatus::Building);
+
+        let undone = undo_last_change(&pool, "long_run").await.unwrap();
+        assert_eq!(undone.current_value, "30");
+        assert_eq!(undone.status, LifecycleStatus::Regressing);
+        assert_eq!(load_dimension(&pool, "long_run").await.unwrap().status, LifecycleStatus::Regressing);
+
+        let history = load_dimension_history(&pool, "long_run").await.unwrap();
+        let manual = &history[history.len() - 2];
+        let undo = history.last().unwrap();
+        assert_eq!(manual.change_type, "manual");
+        assert_eq!(manual.previous_status, Some(LifecycleStatus::Regressing));
+        assert_eq!(undo.change_type, "undo");
+        assert_eq!(undo.previous_status, Some(LifecycleStatus::Building));
+    }
+
+    #[tokio::test]
+    async fn test_undo_restores_hand_edited_value_not_step() {
+        let pool = crate::db::test_pool().await;
+        // Hand-edited long run of 37 min (not on the 5-min grid)
+        sqlx::query("UPDATE progression_dimensions SET current_value = '37' WHERE name = 'long_run'")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        assert_eq!(apply_progression(&pool, "long_run", None).await.unwrap(), "42");
+        let first_change = load_dimension(&pool, "long_run").await.unwrap().last_change_at;
+        assert_eq!(apply_progression(&pool, "long_run", None).await.unwrap(), "47");
+
+        // Undo twice walks back through both changes
+        let undone = undo_last_change(&pool, "long_run").await.unwrap();
+        assert_eq!(undone.current_value, "42");
+        assert_eq!(
+            undone.last_change_at.map(|d| d.timestamp()),
+            first_change.map(|d| d.timestamp())
+        );
+        let undone = undo_last_change(&pool, "long_run").await.unwrap();
+        assert_eq!(undone.current_value, "37");
+    }
+
+    #[tokio::test]
+    async fn test_undo_ceiling_update() {
+        let pool = crate::db::test_pool().await;
+        update_ceiling(&pool, "long_run", "120").await.unwrap();
+        let undone = undo_last_change(&pool, "long_run").await.unwrap();
+        assert_eq!(undone.ceiling_value, "90");
+        assert_eq!(undone.current_value, "30");
+    }
 }
@@ -0,0 +1,378 @@
+//! Background auto-analysis of newly synced workouts
+//!
+//! Every few minutes, when auto-analyze is on, analyzes recent workouts that
+//! have metrics but no analysis yet, stopping once the day's token budget
+//! is spent. Shares the analysis lock with the manual analyze command.
+
+use crate::analysis::UserSettings;
+use crate::commands::analysis::{load_user_settings, run_workout_analysis, AnalysisError, AnalysisErrorKind};
+use crate::db::{AppState, DbPool};
+use crate::llm::Usage;
+use chrono::{DateTime, Utc};
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// How often the scheduler looks for un-analyzed workouts
+const CHECK_INTERVAL: Duration = Duration::from_secs(300);
+
+/// Only workouts from the last few days qualify, so turning auto-analyze on
+/// doesn't work through years of history
+const LOOKBACK_DAYS: i64 = 3;
+
+/// Failed analyses after which a workout is no longer retried automatically
+const MAX_AUTO_ANALYZE_ATTEMPTS: i64 = 3;
+
+/// What one scheduler pass did
+#[derive(Debug, Default, PartialEq)]
+pub struct AutoAnalyzeReport {
+  pub analyzed: Vec<i64>,
+  /// Workouts whose analysis failed this pass (retried on later passes)
+  pub failed: Vec<i64>,
+  /// Tokens spent by this pass
+  pub tokens_used: i64,
+  /// Workouts were left because the daily cap was reached
+  pub budget_exhausted: bool,
+}
+
+/// Start the scheduler on its own thread for the life of the app
+pub fn spawn(state: Arc<AppState>) {
+  std::thread::spawn(move || loop {
+    std::thread::sleep(CHECK_INTERVAL);
+    match tauri::async_runtime::block_on(run_scheduled_pass(&state)) {
+      Ok(report) if !report.analyzed.is_empty() || !report.failed.is_empty() || report.budget_exhausted => println!(
+        "Auto-analyze: {} workouts, {} failed, {} tokens{}",
+        report.analyzed.len(),
+        report.failed.len(),
+        report.tokens_used,
+        if report.budget_exhausted { " (daily token cap reached)" } else { "" }
+      ),
+      Ok(_) => {}
+      Err(e) => eprintln!("Auto-analyze failed: {}", e),
+    }
+  });
+}
+
+/// One pass against the real analysis path
+async fn run_scheduled_pass(state: &AppState) -> Result<AutoAnalyzeReport, String> {
+  let db = &state.db;
+  run_auto_analyze_pass(db, Utc::now(), |workout_id| async move {
+    // Wait out a manual analysis rather than run alongside it
+    let _guard = state.analysis_lock.lock().await;
+    if has_analysis(db, workout_id).await? {
+      return Ok(0);
+    }
+    let result = run_workout_analysis(db, workout_id).await?;
+    Ok(result.input_tokens as i64 + result.output_tokens as i64)
+  })
+  .await
+}
+
+/// Analyze pending workouts oldest first with `analyze` (which returns the
+/// tokens it spent) until none are left or today's budget is used up.
+/// A failed analysis is logged and recorded, and the pass moves on; the
+/// workout is retried on later passes until it has failed
+/// `MAX_AUTO_ANALYZE_ATTEMPTS` times. Configuration and database errors
+/// still end the pass, since they'd fail every workout alike.
+pub(crate) async fn run_auto_analyze_pass<F, Fut>(
+  db: &DbPool,
+  now: DateTime<Utc>,
+  analyze: F,
+) -> Result<AutoAnalyzeReport, String>
+where
+  F: Fn(i64) -> Fut,
+  Fut: Future<Output = Result<i64, AnalysisError>>,
+{
+  let settings = load_user_settings(db).await?;
+  let auto_analyze = &settings.auto_analyze;
+  let mut report = AutoAnalyzeReport::default();
+  if !auto_analyze.enabled {
+    return Ok(report);
+  }
+
+  let mut used = tokens_used_since(db, local_day_start(&settings, now))
+    .await
+    .map_err(|e| format!("Failed to read token usage: {}", e))?;
+
+  let pending = pending_workouts(db, now - chrono::Duration::days(LOOKBACK_DAYS))
+    .await
+    .map_err(|e| format!("Failed to find workouts to analyze: {}", e))?;
+
+  for workout_id in pending {
+    if used >= auto_analyze.daily_token_cap {
+      report.budget_exhausted = true;
+      break;
+    }
+    match analyze(workout_id).await {
+      Ok(tokens) => {
+        used += tokens;
+        report.tokens_used += tokens;
+        report.analyzed.push(workout_id);
+      }
+      // No API key or no database: nothing later in the pass can succeed either
+      Err(e) if matches!(e.kind, AnalysisErrorKind::Config | AnalysisErrorKind::Database) => {
+        return Err(format!("workout {}: {}", workout_id, e.message));
+      }
+      Err(e) => {
+        eprintln!("Auto-analyze: workout {} failed: {}", workout_id, e.message);
+        record_failure(db, workout_id, &e.message)
+          .await
+          .map_err(|e| format!("Failed to record analysis failure: {}", e))?;
+        report.failed.push(workout_id);
+      }
+    }
+  }
+
+  Ok(report)
+}
+
+/// Start of the athlete's local day, in UTC
+fn local_day_start(settings: &UserSettings, now: DateTime<Utc>) -> DateTime<Utc> {
+  let local = settings.to_local(&now);
+  local
+    .date_naive()
+    .and_hms_opt(0, 0, 0)
+    .and_then(|midnight| midnight.and_local_timezone(*local.offset()).single())
+    .map(|midnight| midnight.with_timezone(&Utc))
+    .unwrap_or(now)
+}
+
+/// Append one LLM call's spend to the token ledger. `source` is
+/// "analysis", "weekly_review" or "plan".
+pub(crate) async fn record_token_usage(
+  db: &DbPool,
+  source: &str,
+  model_version: &str,
+  usage: &Usage,
+) -> Result<(), sqlx::Error> {
+  sqlx::query(
+    "INSERT INTO llm_token_usage (source, model_version, input_tokens, output_tokens) VALUES (?1, ?2, ?3, ?4)",
+  )
+  .bind(source)
+  .bind(model_version)
+  .bind(usage.input_tokens as i64)
+  .bind(usage.output_tokens as i64)
+  .execute(db)
+  .await?;
+  Ok(())
+}
+
+/// Input + output tokens of every LLM call since `since` (re-analyses and
+/// weekly reviews included)
+async fn tokens_used_since(db: &DbPool, since: DateTime<Utc>) -> Result<i64, sqlx::Error> {
+  sqlx::query_scalar(
+    r#"
+    SELECT COALESCE(SUM(input_tokens + output_tokens), 0)
+    FROM llm_token_usage
+    WHERE julianday(created_at) >= julianday(?1)
+    "#,
+  )
+  .bind(since)
+  .fetch_one(db)
+  .await
+}
+
+/// Workouts since `since` with computed metrics and no analysis, oldest
+/// first, skipping those that have failed too often
+async fn pending_workouts(db: &DbPool, since: DateTime<Utc>) -> Result<Vec<i64>, sqlx::Error> {
+  sqlx::query_scalar(
+    r#"
+    SELECT w.id
+    FROM workouts w
+    LEFT JOIN workout_analysis a ON a.workout_id = w.id
+    LEFT JOIN auto_analyze_failures f ON f.workout_id = w.id
+    WHERE a.id IS NULL
+      AND w.metrics_computed_at IS NOT NULL
+      AND w.duplicate_of IS NULL
+      AND julianday(w.started_at) >= julianday(?1)
+      AND COALESCE(f.attempts, 0) < ?2
+    ORDER BY w.started_at
+    "#,
+  )
+  .bind(since)
+  .bind(MAX_AUTO_ANALYZE_ATTEMPTS)
+  .fetch_all(db)
+  .await
+}
+
+/// Count a failed auto-analysis of `workout_id`
+async fn record_failure(db: &DbPool, workout_id: i64, error: &str) -> Result<(), sqlx::Error> {
+  sqlx::query(
+    r#"
+    INSERT INTO auto_analyze_failures (workout_id, last_error) VALUES (?1, ?2)
+    ON CONFLICT(workout_id) DO UPDATE SET
+      attempts = attempts + 1,
+      last_error = excluded.last_error,
+      last_attempt_at = CURRENT_TIMESTAMP
+    "#,
+  )
+  .bind(workout_id)
+  .bind(error)
+  .execute(db)
+  .await?;
+  Ok(())
+}
+
+async fn has_analysis(db: &DbPool, workout_id: i64) -> Result<bool, AnalysisError> {
+  let exists: bool = sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM workout_analysis WHERE workout_id = ?1)")
+    .bind(workout_id)
+    .fetch_one(db)
+    .await?;
+  Ok(exists)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::analysis::AutoAnalyzeSettings;
+  use crate::commands::analysis::save_auto_analyze;
+  use std::sync::atomic::{AtomicUsize, Ordering};
+
+  async fn insert_computed_workout(db: &DbPool, strava_id: &str, started_at: DateTime<Utc>) -> i64 {
+    sqlx::query(
+      "INSERT INTO workouts (strava_id, activity_type, started_at, duration_seconds, metrics_computed_at)
+       VALUES (?1, 'Run', ?2, 2700, CURRENT_TIMESTAMP)",
+    )
+    .bind(strava_id)
+    .bind(started_at)
+    .execute(db)
+    .await
+    .unwrap()
+    .last_insert_rowid()
+  }
+
+  async fn store_analysis(db: &DbPool, workout_id: i64, tokens: i64) {
+    sqlx::query(
+      "INSERT INTO workout_analysis (workout_id, summary, tomorrow_recommendation, model_version, input_tokens, output_tokens)
+       VALUES (?1, 'ok', 'rest', 'test', ?2, 0)",
+    )
+    .bind(workout_id)
+    .bind(tokens)
+    .execute(db)
+    .await
+    .unwrap();
+    record_token_usage(db, "analysis", "test", &Usage { input_tokens: tokens as u32, output_tokens: 0 })
+      .await
+      .unwrap();
+  }
+
+  #[tokio::test]
+  async fn test_auto_analyze_stops_at_daily_token_cap() {
+    let db = crate::db::test_pool().await;
+    let now = Utc::now();
+    let already = insert_computed_workout(&db, "a0", now - chrono::Duration::hours(5)).await;
+    store_analysis(&db, already, 3000).await;
+    let mut pending = Vec::new();
+    for i in 1..=3 {
+      pending.push(insert_computed_workout(&db, &format!("a{}", i), now - chrono::Duration::hours(4 - i)).await);
+    }
+
+    let calls = AtomicUsize::new(0);
+    let fake_analyze = |workout_id: i64| {
+      calls.fetch_add(1, Ordering::SeqCst);
+      let db = &db;
+      async move {
+        store_analysis(db, workout_id, 4000).await;
+        Ok(4000)
+      }
+    };
+
+    // Disabled: nothing runs
+    let report = run_auto_analyze_pass(&db, now, &fake_analyze).await.unwrap();
+    assert_eq!(report, AutoAnalyzeReport::default());
+    assert_eq!(calls.load(Ordering::SeqCst), 0);
+
+    // 3000 already spent today; each analysis costs 4000 against a 10000 cap
+    save_auto_analyze(&db, &AutoAnalyzeSettings { enabled: true, daily_token_cap: 10_000 })
+      .await
+      .unwrap();
+    let report = run_auto_analyze_pass(&db, now, &fake_analyze).await.unwrap();
+    assert_eq!(report.analyzed, pending[..2].to_vec());
+    assert_eq!(report.tokens_used, 8000);
+    assert!(report.budget_exhausted);
+
+    // Budget spent: the last workout waits for tomorrow
+    let report = run_auto_analyze_pass(&db, now, &fake_analyze).await.unwrap();
+    assert!(report.analyzed.is_empty());
+    assert!(report.budget_exhausted);
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+    assert!(save_auto_analyze(&db, &AutoAnalyzeSettings { enabled: true, daily_token_cap: -1 }).await.is_err());
+  }
+
+  #[tokio::test]
+  async fn test_failed_analysis_does_not_block_later_workouts() {
+    let db = crate::db::test_pool().await;
+    let now = Utc::now();
+    let broken = insert_computed_workout(&db, "f1", now - chrono::Duration::hours(3)).await;
+    let next = insert_computed_workout(&db, "f2", now - chrono::Duration::hours(2)).await;
+    save_auto_analyze(&db, &AutoAnalyzeSettings { enabled: true, daily_token_cap: 100_000 })
+      .await
+      .unwrap();
+
+    let calls = AtomicUsize::new(0);
+    let fake_analyze = |workout_id: i64| {
+      calls.fetch_add(1, Ordering::SeqCst);
+      let db = &db;
+      async move {
+        if workout_id == broken {
+          return Err(AnalysisError::new(AnalysisErrorKind::Parse, "bad JSON"));
+        }
+        store_analysis(db, workout_id, 4000).await;
+        Ok(4000)
+      }
+    };
+
+    // The first workout fails; the second is still analyzed
+    let report = run_auto_analyze_pass(&db, now, &fake_analyze).await.unwrap();
+    assert_eq!(report.analyzed, vec![next]);
+    assert_eq!(report.failed, vec![broken]);
+    assert_eq!(report.tokens_used, 4000);
+
+    // Retried on later passes, then left alone once it has failed too often
+    for _ in 1..MAX_AUTO_ANALYZE_ATTEMPTS {
+      let report = run_auto_analyze_pass(&db, now, &fake_analyze).await.unwrap();
+      assert_eq!(report.failed, vec![broken]);
+    }
+    let report = run_auto_analyze_pass(&db, now, &fake_analyze).await.unwrap();
+    assert_eq!(report, AutoAnalyzeReport::default());
+    assert_eq!(calls.load(Ordering::SeqCst), 1 + MAX_AUTO_ANALYZE_ATTEMPTS as usize);
+
+    let (attempts, last_error): (i64, String) =
+      sqlx::query_as("SELECT attempts, last_error FROM auto_analyze_failures WHERE workout_id = ?1")
+        .bind(broken)
+        .fetch_one(&db)
+        .await
+        .unwrap();
+    assert_eq!(attempts, MAX_AUTO_ANALYZE_ATTEMPTS);
+    assert_eq!(last_error, "bad JSON");
+  }
+
+  #[tokio::test]
+  async fn test_token_cap_counts_reanalyses_and_reviews() {
+    let db = crate::db::test_pool().await;
+    let now = Utc::now();
+    let analyzed = insert_computed_workout(&db, "r0", now - chrono::Duration::hours(3)).await;
+    let pending = insert_computed_workout(&db, "r1", now - chrono::Duration::hours(2)).await;
+
+    // Analyzed twice today: the stored row only keeps the second count, the ledger both
+    store_analysis(&db, analyzed, 4000).await;
+    sqlx::query("DELETE FROM workout_analysis WHERE workout_id = ?1")
+      .bind(analyzed)
+      .execute(&db)
+      .await
+      .unwrap();
+    store_analysis(&db, analyzed, 4000).await;
+    let review = Usage { input_tokens: 1500, output_tokens: 500 };
+    record_token_usage(&db, "weekly_review", "test", &review).await.unwrap();
+    assert_eq!(tokens_used_since(&db, now - chrono::Duration::hours(12)).await.unwrap(), 10_000);
+
+    save_auto_analyze(&db, &AutoAnalyzeSettings { enabled: true, daily_token_cap: 10_000 })
+      .await
+      .unwrap();
+    let report = run_auto_analyze_pass(&db, now, |_| async { Ok(4000) }).await.unwrap();
+    assert!(report.analyzed.is_empty());
+    assert!(report.budget_exhausted);
+    assert!(!has_analysis(&db, pending).await.unwrap());
+  }
+}
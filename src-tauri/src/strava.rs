@@ -1,4 +1,7 @@
 use chrono::{DateTime, Duration, Utc};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use std::env;
@@ -16,6 +19,19 @@ const STRAVA_TOKEN_URL: &str = "https://www.strava.com/oauth/token";
 const STRAVA_API_BASE: &str = "https://www.strava.com/api/v3";
 const REDIRECT_PORT: u16 = 8765;
 const TOKEN_REFRESH_BUFFER_MINUTES: i64 = 5;
+pub const DEFAULT_CALLBACK_TIMEOUT_SECONDS: u64 = 120;
+/// Bucket size for stored workout samples
+pub const SAMPLE_INTERVAL_SECONDS: i64 = 10;
+/// Velocity at or below this (m/s) counts as stopped
+const PAUSE_VELOCITY_MPS: f64 = 0.3;
+/// A stopped stretch must last this long to be treated as a pause
+const MIN_PAUSE_SECONDS: i64 = 10;
+/// Streams with less of the session covered by samples are unreliable
+const MIN_STREAM_COVERAGE_PCT: f64 = 80.0;
+/// Streams with fewer raw samples than this are unreliable
+const MIN_STREAM_SAMPLES: usize = 60;
+/// HR with more than 30% of moving samples zero/missing had a dropped strap
+pub const MIN_HR_COVERAGE_PCT: f64 = 70.0;
 
 /// ---------------------------------------------------------------------------
 /// OAuth Data Structures
@@ -106,6 +122,12 @@ pub enum StravaError {
 
   #[error("Not authenticated with Strava")]
   NotAuthenticated,
+
+  #[error("Strava rate limit exceeded")]
+  RateLimited,
+
+  #[error("Unusable activity: {0}")]
+  InvalidActivity(String),
 }
 
 impl Serialize for StravaError {
@@ -268,28 +290,23 @@ fn extract_code_from_request(request: &str) -> Option<String> {
     return None;
   }
 
-  let url_part = first_line.split_whitespace().nth(1)?;
-
-  for param in url_part.split('?').nth(1)?.split('&') {
-    let mut kv = param.split('=');
-    if kv.next() == Some("code") {
-      return kv.next().map(String::from);
-    }
-  }
-  None
+  extract_query_param(request, "code")
 }
 
 fn extract_error_from_request(request: &str) -> Option<String> {
+  extract_query_param(request, "error")
+}
+
+/// Pull a percent-decoded query parameter out of the request line
+/// (e.g. "GET /callback?code=XXX HTTP/1.1"). Values may contain '='.
+pub(crate) fn extract_query_param(request: &str, key: &str) -> Option<String> {
   let first_line = request.lines().next()?;
   let url_part = first_line.split_whitespace().nth(1)?;
+  let (_, query) = url_part.split_once('?')?;
 
-  for param in url_part.split('?').nth(1)?.split('&') {
-    let mut kv = param.split('=');
-    if kv.next() == Some("error") {
-      return kv.next().map(|s| s.replace("%20", " "));
-    }
-  }
-  None
+  url::form_urlencoded::parse(query.as_bytes())
+    .find(|(k, _)| k == key)
+    .map(|(_, v)| v.into_owned())
 }
 
 fn build_success_response() -> String {
@@ -355,8 +372,40 @@ pub struct StravaActivity {
   pub max_heartrate: Option<f64>,
   #[serde(default)]
   pub average_watts: Option<f64>,
+  /// True when watts came from a power meter, false for Strava's estimate
+  /// (None when the source doesn't say)
+  #[serde(default)]
+  pub device_watts: Option<bool>,
   #[serde(default)]
   pub suffer_score: Option<f64>,
+  /// Newer, more specific type (e.g. "VirtualRide", "GravelRide")
+  #[serde(default)]
+  pub sport_type: Option<String>,
+  /// Recorded on an indoor trainer
+  #[serde(default)]
+  pub trainer: bool,
+  /// Strava gear (shoe "g..." or bike "b...") the activity was logged with
+  #[serde(default)]
+  pub gear_id: Option<String>,
+  /// The athlete's own write-up. Only the single-activity endpoint returns
+  /// it; the activity list leaves it out.
+  #[serde(default)]
+  pub description: Option<String>,
+}
+
+impl StravaActivity {
+  /// Indoor trainer or virtual (Zwift-style) session
+  pub fn is_indoor(&self) -> bool {
+    self.trainer
+      || crate::analysis::is_indoor_activity(&self.activity_type)
+      || self.sport_type.as_deref().is_some_and(crate::analysis::is_indoor_activity)
+  }
+
+  /// Workout duration: moving time, or elapsed time when Strava reports no
+  /// moving time (manual entries, some virtual workouts). None if both are zero.
+  pub fn duration_seconds(&self) -> Option<i64> {
+    [self.moving_time, self.elapsed_time].into_iter().find(|&t| t > 0)
+  }
 }
 
 /// ---------------------------------------------------------------------------
@@ -385,7 +434,7 @@ pub struct StravaStreamKeyed {
 }
 
 /// Downsampled workout samples for charts
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct WorkoutSamples {
   #[serde(skip_serializing_if = "Vec::is_empty", default)]
   pub hr: Vec<i64>,
@@ -393,6 +442,18 @@ pub struct WorkoutSamples {
   pub watts: Vec<i64>,
   #[serde(skip_serializing_if = "Vec::is_empty", default)]
   pub pace: Vec<f64>,  // min/km
+  /// Share of elapsed time spent moving (None without a velocity stream).
+  /// Paused stretches are excluded from the buckets above.
+  #[serde(skip_serializing_if = "Option::is_none", default)]
+  pub moving_fraction: Option<f64>,
+  /// How trustworthy the raw streams were (None for samples stored before
+  /// quality was tracked)
+  #[serde(skip_serializing_if = "Option::is_none", default)]
+  pub stream_quality: Option<StreamQuality>,
+  /// Share of moving samples with a non-zero HR (None without an HR stream,
+  /// or for samples stored before coverage was tracked)
+  #[serde(skip_serializing_if = "Option::is_none", default)]
+  pub hr_coverage_pct: Option<f64>,
 }
 
 impl WorkoutSamples {
@@ -400,9 +461,75 @@ impl WorkoutSamples {
     self.hr.is_empty() && self.watts.is_empty() && self.pace.is_empty()
   }
 
+  /// Whether stream-derived metrics (decoupling, test detection, stream
+  /// rTSS) can be trusted. Legacy samples without a quality record pass.
+  pub fn is_reliable(&self) -> bool {
+    self.stream_quality.as_ref().is_none_or(StreamQuality::is_reliable)
+  }
+
+  /// Whether HR-derived metrics (zones, decoupling, LTHR tests) can be
+  /// trusted. False when the strap dropped out for much of the session.
+  pub fn has_reliable_hr(&self) -> bool {
+    self.hr_coverage_pct.is_none_or(|pct| pct >= MIN_HR_COVERAGE_PCT)
+  }
+
+  /// HR stream to integrate rTSS over; empty when the stream or the strap
+  /// can't be trusted, so load falls back to the duration estimate
+  pub fn into_load_hr(self) -> Vec<i64> {
+    if self.is_reliable() && self.has_reliable_hr() {
+      self.hr
+    } else {
+      Vec::new()
+    }
+  }
+
   pub fn to_json(&self) -> String {
     serde_json::to_string(self).unwrap_or_default()
   }
+
+  /// Gzip-compressed JSON, as stored in workouts.samples_blob
+  pub fn to_compressed(&self) -> Vec<u8> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    // Writing into a Vec can't fail
+    let _ = encoder.write_all(self.to_json().as_bytes());
+    encoder.finish().unwrap_or_default()
+  }
+
+  /// Decode a workout's stored samples: samples_blob (gzip when
+  /// `compressed`, plain JSON otherwise), else the legacy samples_json
+  /// column. None when neither holds valid samples.
+  pub fn from_stored(json: Option<&str>, blob: Option<&[u8]>, compressed: bool) -> Option<Self> {
+    match blob {
+      Some(bytes) if compressed => {
+        let mut decoded = String::new();
+        GzDecoder::new(bytes).read_to_string(&mut decoded).ok()?;
+        serde_json::from_str(&decoded).ok()
+      }
+      Some(bytes) => serde_json::from_slice(bytes).ok(),
+      None => serde_json::from_str(json?).ok(),
+    }
+  }
+}
+
+/// Resolution and gap coverage of the raw streams behind a set of samples
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StreamQuality {
+  /// Strava's stream resolution ("low", "medium", "high"), if reported
+  #[serde(skip_serializing_if = "Option::is_none", default)]
+  pub resolution: Option<String>,
+  /// Share of sample buckets across the session that contain raw data
+  pub coverage_pct: f64,
+  /// Raw samples in the time stream
+  pub sample_count: usize,
+}
+
+impl StreamQuality {
+  /// Good enough for metrics that depend on the shape of the stream
+  pub fn is_reliable(&self) -> bool {
+    self.resolution.as_deref() != Some("low")
+      && self.coverage_pct >= MIN_STREAM_COVERAGE_PCT
+      && self.sample_count >= MIN_STREAM_SAMPLES
+  }
 }
 
 /// Fetch activity streams (heartrate, watts, velocity) from Strava
@@ -429,6 +556,10 @@ pub async fn fetch_activity_streams(
     return Err(StravaError::NotAuthenticated);
   }
 
+  if response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS {
+    return Err(StravaError::RateLimited);
+  }
+
   // 404 means no streams available for this activity (manual entry, etc.)
   if response.status() == reqwest::StatusCode::NOT_FOUND {
     return Ok(vec![]);
@@ -520,18 +651,18 @@ pub fn downsample_streams(streams: &[StravaStream], interval_seconds: i64) -> Wo
     .unwrap_or_default();
 
   if time_data.is_empty() {
-    return WorkoutSamples {
-      hr: vec![],
-      watts: vec![],
-      pace: vec![],
-    };
+    return WorkoutSamples::default();
   }
 
+  // Auto-pauses and stops drag down pace and HR zones; drop them from buckets
+  let paused = detect_paused(&time_data, &velocity_data);
+
   // Downsample by taking average of each interval bucket
   let mut samples = WorkoutSamples {
-    hr: vec![],
-    watts: vec![],
-    pace: vec![],
+    moving_fraction: moving_fraction(&time_data, &velocity_data, &paused),
+    stream_quality: Some(stream_quality(streams, &time_data, interval_seconds)),
+    hr_coverage_pct: hr_coverage_pct(&hr_data, &paused),
+    ..Default::default()
   };
 
   let max_time = *time_data.last().unwrap_or(&0);
@@ -544,15 +675,17 @@ pub fn downsample_streams(streams: &[StravaStream], interval_seconds: i64) -> Wo
     let indices: Vec<usize> = time_data
       .iter()
       .enumerate()
-      .filter(|(_, &t)| t >= bucket_start && t < bucket_end)
+      .filter(|(i, &t)| t >= bucket_start && t < bucket_end && !paused[*i])
       .map(|(i, _)| i)
       .collect();
 
     if !indices.is_empty() {
-      // Average HR for bucket
+      // Average HR for bucket, skipping dropouts (0 if the whole bucket dropped)
       if !hr_data.is_empty() {
-        let sum: i64 = indices.iter().filter_map(|&i| hr_data.get(i)).sum();
-        samples.hr.push(sum / indices.len() as i64);
+        let beats: Vec<i64> =
+          indices.iter().filter_map(|&i| hr_data.get(i)).copied().filter(|&hr| hr > 0).collect();
+        let avg = if beats.is_empty() { 0 } else { beats.iter().sum::<i64>() / beats.len() as i64 };
+        samples.hr.push(avg);
       }
 
       // Average watts for bucket
@@ -578,6 +711,135 @@ pub fn downsample_streams(streams: &[StravaStream], interval_seconds: i64) -> Wo
   samples
 }
 
+/// Mark samples inside zero-velocity stretches lasting at least
+/// MIN_PAUSE_SECONDS. Brief dips (a tight corner, a stumble) stay moving.
+fn detect_paused(time_data: &[i64], velocity_data: &[f64]) -> Vec<bool> {
+  let mut paused = vec![false; time_data.len()];
+  let is_stopped = |i: usize| velocity_data.get(i).is_some_and(|&v| v <= PAUSE_VELOCITY_MPS);
+
+  let mut i = 0;
+  while i < time_data.len() {
+    if !is_stopped(i) {
+      i += 1;
+      continue;
+    }
+    let start = i;
+    while i < time_data.len() && is_stopped(i) {
+      i += 1;
+    }
+    // Stretch lasts until the next moving sample (or the last stopped one)
+    let end_time = time_data.get(i).unwrap_or(&time_data[i - 1]);
+    if end_time - time_data[start] >= MIN_PAUSE_SECONDS {
+      paused[start..i].iter_mut().for_each(|p| *p = true);
+    }
+  }
+
+  paused
+}
+
+/// Rate the raw streams: coverage is the share of buckets between the first
+/// and last timestamp that hold at least one sample, so recording gaps show up
+fn stream_quality(streams: &[StravaStream], time_data: &[i64], interval_seconds: i64) -> StreamQuality {
+  let resolution = streams
+    .iter()
+    .find(|s| s.stream_type == "time")
+    .and_then(|s| s.resolution.clone());
+
+  let (first, last) = (time_data[0], time_data[time_data.len() - 1]);
+  let total_buckets = (last - first) / interval_seconds + 1;
+  let mut filled: Vec<i64> = time_data.iter().map(|t| (t - first) / interval_seconds).collect();
+  filled.dedup();
+
+  StreamQuality {
+    resolution,
+    coverage_pct: filled.len() as f64 / total_buckets as f64 * 100.0,
+    sample_count: time_data.len(),
+  }
+}
+
+/// Percent of moving samples with a non-zero HR reading
+fn hr_coverage_pct(hr_data: &[i64], paused: &[bool]) -> Option<f64> {
+  if hr_data.is_empty() {
+    return None;
+  }
+  let moving: Vec<usize> = (0..paused.len()).filter(|&i| !paused[i]).collect();
+  if moving.is_empty() {
+    return None;
+  }
+  let covered = moving.iter().filter(|&&i| hr_data.get(i).is_some_and(|&hr| hr > 0)).count();
+  Some(covered as f64 / moving.len() as f64 * 100.0)
+}
+
+/// Fraction of elapsed time not spent in detected pauses
+fn moving_fraction(time_data: &[i64], velocity_data: &[f64], paused: &[bool]) -> Option<f64> {
+  if velocity_data.is_empty() {
+    return None;
+  }
+  let elapsed = time_data.last()? - time_data.first()?;
+  if elapsed <= 0 {
+    return None;
+  }
+
+  // Each paused sample owns the time until the next sample
+  let paused_seconds: i64 = time_data
+    .windows(2)
+    .zip(paused)
+    .filter(|(_, &p)| p)
+    .map(|(w, _)| w[1] - w[0])
+    .sum();
+
+  Some(1.0 - paused_seconds as f64 / elapsed as f64)
+}
+
+/// A shoe or bike from Strava's gear endpoint
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct StravaGear {
+  pub id: String,
+  #[serde(default)]
+  pub name: String,
+  /// Strava's total distance on this gear, in meters
+  #[serde(default)]
+  pub distance: Option<f64>,
+}
+
+/// "bike" or "shoe": Strava prefixes bike ids with "b" and shoe ids with "g"
+pub fn gear_kind(gear_id: &str) -> &'static str {
+  if gear_id.starts_with('b') {
+    "bike"
+  } else {
+    "shoe"
+  }
+}
+
+/// Fetch one piece of gear (for its display name and total distance)
+pub async fn fetch_gear(access_token: &str, gear_id: &str) -> Result<StravaGear, StravaError> {
+  let client = Client::new();
+
+  let url = format!("{}/gear/{}", STRAVA_API_BASE, gear_id);
+
+  let response = client
+    .get(&url)
+    .header("Authorization", format!("Bearer {}", access_token))
+    .send()
+    .await?;
+
+  if response.status() == reqwest::StatusCode::UNAUTHORIZED {
+    return Err(StravaError::NotAuthenticated);
+  }
+
+  if response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS {
+    return Err(StravaError::RateLimited);
+  }
+
+  if !response.status().is_success() {
+    let error_text = response.text().await.unwrap_or_default();
+    return Err(StravaError::OAuth(format!("Failed to fetch gear: {}", error_text)));
+  }
+
+  let gear: StravaGear = response.json().await?;
+  Ok(gear)
+}
+
 /// Fetch recent activities from Strava
 pub async fn fetch_activities(
   access_token: &str,
@@ -623,3 +885,201 @@ pub async fn fetch_activities(
 
   Ok(activities)
 }
+
+/// Fetch a single activity by its Strava id
+pub async fn fetch_activity(access_token: &str, activity_id: i64) -> Result<StravaActivity, StravaError> {
+  let client = Client::new();
+
+  let url = format!("{}/activities/{}", STRAVA_API_BASE, activity_id);
+
+  let response = client
+    .get(&url)
+    .header("Authorization", format!("Bearer {}", access_token))
+    .send()
+    .await?;
+
+  if response.status() == reqwest::StatusCode::UNAUTHORIZED {
+    return Err(StravaError::NotAuthenticated);
+  }
+
+  if response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS {
+    return Err(StravaError::RateLimited);
+  }
+
+  if !response.status().is_success() {
+    let error_text = response.text().await.unwrap_or_default();
+    return Err(StravaError::OAuth(format!(
+      "Failed to fetch activity {}: {}",
+      activity_id, error_text
+    )));
+  }
+
+  let activity: StravaActivity = response.json().await?;
+  Ok(activity)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_extract_code_plain() {
+    let request = "GET /callback?state=&code=abc123&scope=read HTTP/1.1\r\nHost: localhost\r\n";
+    assert_eq!(extract_code_from_request(request), Some("abc123".to_string()));
+  }
+
+  #[test]
+  fn test_extract_code_percent_encoded_with_padding() {
+    let request = "GET /callback?code=a%2Fb%2Bc%3D%3D&scope=read HTTP/1.1\r\n";
+    assert_eq!(extract_code_from_request(request), Some("a/b+c==".to_string()));
+  }
+
+  #[test]
+  fn test_extract_code_raw_equals_in_value() {
+    // Only the first '=' separates key from value
+    let request = "GET /callback?code=YWJj== HTTP/1.1\r\n";
+    assert_eq!(extract_code_from_request(request), Some("YWJj==".to_string()));
+  }
+
+  #[test]
+  fn test_extract_code_ignores_other_paths() {
+    let request = "GET /favicon.ico HTTP/1.1\r\n";
+    assert_eq!(extract_code_from_request(request), None);
+  }
+
+  #[test]
+  fn test_extract_error_decodes_spaces_and_symbols() {
+    let request =
+      "GET /callback?error=access_denied%3A%20user%20said%20%22no%22%20%26%20left+early HTTP/1.1\r\n";
+    assert_eq!(
+      extract_error_from_request(request),
+      Some("access_denied: user said \"no\" & left early".to_string())
+    );
+  }
+
+  fn stream(stream_type: &str, data: Vec<serde_json::Value>) -> StravaStream {
+    StravaStream {
+      stream_type: stream_type.to_string(),
+      data,
+      series_type: None,
+      original_size: None,
+      resolution: None,
+    }
+  }
+
+  #[test]
+  fn test_compressed_samples_round_trip() {
+    // An hour at 10-second resolution
+    let samples = WorkoutSamples {
+      hr: (0..360).map(|i| 130 + i % 25).collect(),
+      watts: (0..360).map(|i| 200 + i % 40).collect(),
+      pace: (0..360).map(|i| 5.0 + (i % 12) as f64 * 0.05).collect(),
+      moving_fraction: Some(0.97),
+      stream_quality: None,
+      hr_coverage_pct: Some(99.5),
+    };
+
+    let blob = samples.to_compressed();
+    assert!(blob.len() < samples.to_json().len() / 2);
+    assert_eq!(WorkoutSamples::from_stored(None, Some(&blob), true), Some(samples.clone()));
+
+    // Rows written before compression still load from samples_json
+    let json = samples.to_json();
+    assert_eq!(WorkoutSamples::from_stored(Some(&json), None, false), Some(samples));
+    assert_eq!(WorkoutSamples::from_stored(None, Some(b"not gzip"), true), None);
+    assert_eq!(WorkoutSamples::from_stored(None, None, false), None);
+  }
+
+  #[test]
+  fn test_downsample_excludes_pause_from_pace() {
+    // 5 min at 4:00/km, a 2-minute stop at a light, 5 more min at 4:00/km
+    let mps = 1000.0 / 240.0;
+    let paused = |t: &i64| (300..420).contains(t);
+    let time: Vec<serde_json::Value> = (0..720i64).map(|t| serde_json::json!(t)).collect();
+    let velocity: Vec<serde_json::Value> = (0..720i64)
+      .map(|t| serde_json::json!(if paused(&t) { 0.0 } else { mps }))
+      .collect();
+    let hr: Vec<serde_json::Value> = (0..720i64)
+      .map(|t| serde_json::json!(if paused(&t) { 100 } else { 160 }))
+      .collect();
+
+    let samples = downsample_streams(
+      &[stream("time", time), stream("velocity_smooth", velocity), stream("heartrate", hr)],
+      SAMPLE_INTERVAL_SECONDS,
+    );
+
+    // Only moving buckets remain, all at the moving pace and HR
+    assert_eq!(samples.pace.len(), 60);
+    assert!(samples.pace.iter().all(|&p| (p - 4.0).abs() < 0.01));
+    assert!(samples.hr.iter().all(|&h| h == 160));
+
+    let fraction = samples.moving_fraction.unwrap();
+    assert!((fraction - 600.0 / 719.0).abs() < 0.01);
+  }
+
+  #[test]
+  fn test_brief_zero_velocity_is_not_a_pause() {
+    let time: Vec<i64> = (0..60).collect();
+    let velocity: Vec<f64> = (0..60).map(|t| if (20..25).contains(&t) { 0.0 } else { 3.0 }).collect();
+    assert!(detect_paused(&time, &velocity).iter().all(|&p| !p));
+    assert_eq!(moving_fraction(&time, &velocity, &detect_paused(&time, &velocity)), Some(1.0));
+  }
+
+  #[test]
+  fn test_moving_fraction_none_without_velocity() {
+    let samples = downsample_streams(
+      &[stream("time", (0..60i64).map(|t| serde_json::json!(t)).collect())],
+      SAMPLE_INTERVAL_SECONDS,
+    );
+    assert_eq!(samples.moving_fraction, None);
+  }
+
+  #[test]
+  fn test_stream_quality_flags_sparse_stream() {
+    // Hour-long ride recorded once a minute: most buckets are empty
+    let sparse = downsample_streams(
+      &[stream("time", (0..60i64).map(|m| serde_json::json!(m * 60)).collect())],
+      SAMPLE_INTERVAL_SECONDS,
+    );
+    let quality = sparse.stream_quality.clone().unwrap();
+    assert_eq!(quality.sample_count, 60);
+    assert!(quality.coverage_pct < 20.0);
+    assert!(!sparse.is_reliable());
+
+    // Every second, but Strava flagged it low resolution
+    let mut time = stream("time", (0..600i64).map(|t| serde_json::json!(t)).collect());
+    time.resolution = Some("low".to_string());
+    let low_res = downsample_streams(&[time], SAMPLE_INTERVAL_SECONDS);
+    assert_eq!(low_res.stream_quality.as_ref().unwrap().coverage_pct, 100.0);
+    assert!(!low_res.is_reliable());
+
+    let dense = downsample_streams(
+      &[stream("time", (0..600i64).map(|t| serde_json::json!(t)).collect())],
+      SAMPLE_INTERVAL_SECONDS,
+    );
+    assert!(dense.is_reliable());
+
+    // Samples stored before quality tracking stay usable
+    assert!(WorkoutSamples::default().is_reliable());
+  }
+
+  #[test]
+  fn test_hr_dropout_lowers_coverage() {
+    // Strap dropped out for the second half of a 20-minute run
+    let time: Vec<serde_json::Value> = (0..1200i64).map(|t| serde_json::json!(t)).collect();
+    let hr: Vec<serde_json::Value> = (0..1200i64)
+      .map(|t| serde_json::json!(if t < 600 { 150 } else { 0 }))
+      .collect();
+    let samples = downsample_streams(&[stream("time", time.clone()), stream("heartrate", hr)], SAMPLE_INTERVAL_SECONDS);
+
+    assert!((samples.hr_coverage_pct.unwrap() - 50.0).abs() < 1e-9);
+    assert!(!samples.has_reliable_hr());
+    // Dropped buckets read 0 rather than dragging averages down
+    assert!(samples.hr.iter().all(|&h| h == 150 || h == 0));
+
+    let steady: Vec<serde_json::Value> = (0..1200i64).map(|_| serde_json::json!(150)).collect();
+    let samples = downsample_streams(&[stream("time", time), stream("heartrate", steady)], SAMPLE_INTERVAL_SECONDS);
+    assert_eq!(samples.hr_coverage_pct, Some(100.0));
+    assert!(samples.has_reliable_hr());
+  }
+}
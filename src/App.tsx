@@ -20,12 +20,15 @@ interface OuraAuthStatus {
 interface SyncResult {
   new_activities: number;
   total_fetched: number;
+  skipped_no_duration: number;
+  duplicates: number;
 }
 
 interface OuraSyncResult {
   sleep_records: number;
   hrv_records: number;
   resting_hr_records: number;
+  synced_through: string | null;
 }
 
 interface ComputeResult {
@@ -38,6 +41,67 @@ interface UserSettings {
   lthr: number | null;
   ftp: number | null;
   training_days_per_week: number;
+  goal_name: string | null;
+  goal_date: string | null;
+  lthr_pct_of_max: number;
+  run_lthr_pct_of_max: number | null;
+  ride_lthr_pct_of_max: number | null;
+  flag_thresholds: {
+    volume_spike_ratio: number;
+    volume_drop_ratio: number;
+    volume_drop_min_chronic: number;
+  };
+  recent_window: {
+    same_type_count: number;
+    all_type_count: number;
+    window_days: number | null;
+  };
+  include_other_load: boolean;
+  sleep_target_hours: number;
+  sport_settings: SportSettings[];
+  timezone: string | null;
+  utc_offset_minutes: number | null;
+  polarization_target: ZoneSplit;
+  auto_analyze: { enabled: boolean; daily_token_cap: number };
+  progression_overlap_days: number;
+  css_pace_sec_per_100m: number | null;
+  load_weights: LoadWeights;
+  shoe_replacement_km: number;
+  tsb_bands: TsbBands;
+  long_session_window_days: number;
+  zone_model: "pct_max" | "hr_reserve";
+  resting_hr: number | null;
+  context_char_budget: number;
+  units: "metric" | "imperial";
+  experience_level: "beginner" | "intermediate" | "advanced";
+  week_start_day: "rolling" | "monday" | "tuesday" | "wednesday" | "thursday" | "friday" | "saturday" | "sunday";
+  coach_tone: "conversational" | "direct" | "supportive" | "analytical";
+}
+
+interface LoadWeights {
+  run: number;
+  ride: number;
+  swim: number;
+  other: number;
+}
+
+interface TsbBands {
+  tsb_fresh_above: number;
+  tsb_moderate_below: number;
+  tsb_high_below: number;
+}
+
+interface ZoneSplit {
+  low_pct: number;
+  moderate_pct: number;
+  high_pct: number;
+}
+
+interface SportSettings {
+  sport: "run" | "ride" | "swim";
+  max_hr: number | null;
+  lthr: number | null;
+  ftp: number | null;
 }
 
 interface WorkoutWithMetrics {
@@ -58,15 +122,37 @@ interface WorkoutWithMetrics {
   efficiency: number | null;
   cardiac_cost: number | null;
   hr_zone: string | null;
+  display: UnitMetrics;
+}
+
+interface UnitMetrics {
+  units: "metric" | "imperial";
+  distance: number | null;
+  distance_unit: string;
+  pace: number | null;
+  pace_unit: string;
+  speed: number | null;
+  speed_unit: string;
 }
 
 interface WeeklyVolume {
   total_hrs: number;
   run_hrs: number;
   ride_hrs: number;
+  swim_hrs: number;
+  strength_hrs: number;
+  hike_hrs: number;
+  row_hrs: number;
   other_hrs: number;
 }
 
+interface PolarizationGap {
+  actual: ZoneSplit;
+  target: ZoneSplit;
+  gap: ZoneSplit;
+  summary: string;
+}
+
 interface IntensityDistribution {
   z1_pct: number;
   z2_pct: number;
@@ -86,10 +172,25 @@ interface TrainingContext {
   tsb: number | null;
   weekly_volume: WeeklyVolume;
   week_over_week_delta_pct: number | null;
+  projected_week_over_week_delta_pct: number | null;
+  week_elapsed_fraction: number | null;
   intensity_distribution: IntensityDistribution;
+  polarization_gap: PolarizationGap | null;
   longest_session: LongestSession;
   consistency_pct: number | null;
   workouts_this_week: number;
+  strength_sessions: number;
+  monotony: number | null;
+  strain: number | null;
+  load_confidence: LoadConfidence;
+}
+
+interface LoadConfidence {
+  atl_measured: number;
+  atl_estimated: number;
+  ctl_measured: number;
+  ctl_estimated: number;
+  measured_pct: number | null;
 }
 
 // Legacy format (still stored in DB) - not currently used in frontend
@@ -101,13 +202,21 @@ interface TrainingContext {
 //   risk_flags: string[];
 //   goal_notes: string | null;
 //   created_at: string | null;
+//   is_stale: boolean;
 // }
 
 interface AnalysisResult {
   workout_id: number;
   analysis: WorkoutAnalysisV4;  // V4 multi-card format
+  model: string;
   input_tokens: number;
   output_tokens: number;
+  context_chars: number;
+}
+
+/** Hours outside run and ride (swim, strength, hike, row, other) */
+function crossTrainingHrs(v: WeeklyVolume): number {
+  return v.swim_hrs + v.strength_hrs + v.hike_hrs + v.row_hrs + v.other_hrs;
 }
 
 function App() {
@@ -204,8 +313,9 @@ function App() {
       });
       setLatestAnalysis(result.analysis);
     } catch (e: unknown) {
-      const errorObj = e as { message?: string };
-      setError(`Analysis failed: ${errorObj.message || e}`);
+      const errorObj = e as { kind?: string; message?: string };
+      const hint = errorObj.kind === "llm" ? " (try again in a moment)" : "";
+      setError(`Analysis failed: ${errorObj.message || e}${hint}`);
     } finally {
       setIsAnalyzing(false);
     }
@@ -341,17 +451,16 @@ function App() {
     return `${minutes}m`;
   }
 
-  function formatDistance(meters: number | null): string {
-    if (!meters) return "-";
-    const km = meters / 1000;
-    return `${km.toFixed(1)} km`;
+  function formatDistance(display: UnitMetrics): string {
+    if (!display.distance) return "-";
+    return `${display.distance.toFixed(1)} ${display.distance_unit}`;
   }
 
-  function formatPace(paceMinPerKm: number | null): string {
-    if (!paceMinPerKm) return "-";
-    const mins = Math.floor(paceMinPerKm);
-    const secs = Math.round((paceMinPerKm - mins) * 60);
-    return `${mins}:${secs.toString().padStart(2, "0")}/km`;
+  function formatPace(display: UnitMetrics): string {
+    if (!display.pace) return "-";
+    const mins = Math.floor(display.pace);
+    const secs = Math.round((display.pace - mins) * 60);
+    return `${mins}:${secs.toString().padStart(2, "0")}/${display.pace_unit.replace("min/", "")}`;
   }
 
   function formatRtss(rtss: number | null): string {
@@ -362,6 +471,7 @@ function App() {
   // Check if any workouts need metrics computed
   const needsCompute = workouts.some(w => w.rtss === null && w.average_heartrate !== null);
   const hasSettings = settings?.max_hr !== null;
+  const weekDelta = trainingContext?.projected_week_over_week_delta_pct ?? trainingContext?.week_over_week_delta_pct ?? null;
 
   return (
     <main className="container">
@@ -511,7 +621,7 @@ function App() {
             <div className="volume-bar">
               <div className="volume-segment run" style={{ flex: trainingContext.weekly_volume.run_hrs }} />
               <div className="volume-segment ride" style={{ flex: trainingContext.weekly_volume.ride_hrs }} />
-              <div className="volume-segment other" style={{ flex: trainingContext.weekly_volume.other_hrs }} />
+              <div className="volume-segment other" style={{ flex: crossTrainingHrs(trainingContext.weekly_volume) }} />
             </div>
             <div className="volume-legend">
               <span>{trainingContext.weekly_volume.total_hrs.toFixed(1)}h total</span>
@@ -521,9 +631,12 @@ function App() {
               {trainingContext.weekly_volume.ride_hrs > 0 && (
                 <span className="ride-label">{trainingContext.weekly_volume.ride_hrs.toFixed(1)}h ride</span>
               )}
-              {trainingContext.week_over_week_delta_pct !== null && (
-                <span className={trainingContext.week_over_week_delta_pct >= 0 ? "delta-up" : "delta-down"}>
-                  {trainingContext.week_over_week_delta_pct >= 0 ? "+" : ""}{trainingContext.week_over_week_delta_pct.toFixed(0)}% vs last week
+              {crossTrainingHrs(trainingContext.weekly_volume) > 0 && (
+                <span>{crossTrainingHrs(trainingContext.weekly_volume).toFixed(1)}h cross-training</span>
+              )}
+              {weekDelta !== null && (
+                <span className={weekDelta >= 0 ? "delta-up" : "delta-down"}>
+                  {weekDelta >= 0 ? "+" : ""}{weekDelta.toFixed(0)}% vs last week{trainingContext.week_elapsed_fraction !== null && trainingContext.week_elapsed_fraction < 1 ? " (projected)" : ""}
                 </span>
               )}
             </div>
@@ -597,9 +710,9 @@ function App() {
                 </div>
                 <div className="workout-stats">
                   <span>{formatDuration(workout.duration_seconds)}</span>
-                  <span>{formatDistance(workout.distance_meters)}</span>
-                  {workout.activity_type === "Run" && workout.pace_min_per_km && (
-                    <span className="pace">{formatPace(workout.pace_min_per_km)}</span>
+                  <span>{formatDistance(workout.display)}</span>
+                  {workout.activity_type === "Run" && workout.display.pace && (
+                    <span className="pace">{formatPace(workout.display)}</span>
                   )}
                   {workout.activity_type === "Ride" && workout.average_watts && (
                     <span className="power">{workout.average_watts.toFixed(0)}W</span>
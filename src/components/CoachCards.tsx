@@ -106,6 +106,16 @@ export function CoachCards({ analysis }: CoachCardsProps) {
                 <span className="detail-label">Goal:</span>
                 <span>{analysis.tomorrow.goal.replace('_', ' ')}</span>
               </div>
+              {analysis.tomorrow.intervals && analysis.tomorrow.intervals.length > 0 && (
+                <div className="detail-row">
+                  <span className="detail-label">Intervals:</span>
+                  <span>
+                    {analysis.tomorrow.intervals
+                      .map((i) => `${i.reps}×${formatSeconds(i.work_sec)} ${i.work_intensity} off ${formatSeconds(i.recovery_sec)}`)
+                      .join(', ')}
+                  </span>
+                </div>
+              )}
             </div>
           </div>
           <p className="tomorrow-rationale">{analysis.tomorrow.rationale}</p>
@@ -167,3 +177,7 @@ function getTsbEmoji(tsb: number): string {
   if (tsb > -20) return "🟠";
   return "🔴";
 }
+
+function formatSeconds(secs: number): string {
+  return secs >= 60 && secs % 60 === 0 ? `${secs / 60}min` : `${secs}s`;
+}
@@ -15,6 +15,7 @@ export interface PerformanceCard {
   today_value: string;
   delta: string;
   insight: string;
+  direction?: "improved" | "declined" | "stable";
 }
 
 export interface HrEfficiencyCard {
@@ -42,6 +43,14 @@ export interface TomorrowCard {
   goal: string;
   rationale: string;
   confidence: string;
+  intervals?: IntervalPrescription[];
+}
+
+export interface IntervalPrescription {
+  reps: number;
+  work_sec: number;
+  work_intensity: string;
+  recovery_sec: number;
 }
 
 export interface EyesOnCard {
@@ -55,3 +64,12 @@ export interface FlagPriority {
   action: string;
   why_it_matters: string;
 }
+
+export interface AnalysisDiff {
+  tsb_band?: { from: string; to: string };
+  tsb_delta: number;
+  flags_added: string[];
+  flags_cleared: string[];
+  progression_state?: { from: string; to: string };
+  tomorrow: { field: string; from: string; to: string }[];
+}